@@ -14,22 +14,141 @@ fi
 
 # Setup directory for runtime data
 RUSTFIG_RUNTIME_DIR="${XDG_RUNTIME_DIR:-/tmp}/rustfig-$USER"
-mkdir -p "$RUSTFIG_RUNTIME_DIR"
+mkdir -p -m 700 "$RUSTFIG_RUNTIME_DIR"
+[ -O "$RUSTFIG_RUNTIME_DIR" ] && chmod 700 "$RUSTFIG_RUNTIME_DIR"
 
 # Initialize RustFig context
 __rustfig_init_context() {
   rustfig update-context --shell=bash --dir="$PWD" --term="$TERM" >/dev/null 2>&1
 }
 
+# Dump aliases, functions, and builtins so RustFig can expand and suggest them
+rustfig-refresh-shell-symbols() {
+  alias > "$RUSTFIG_RUNTIME_DIR/aliases.bash" 2>/dev/null
+  declare -F > "$RUSTFIG_RUNTIME_DIR/functions.bash" 2>/dev/null
+  compgen -b > "$RUSTFIG_RUNTIME_DIR/builtins.bash" 2>/dev/null
+}
+
+# OSC 133 prompt marks: A and B around the prompt, C before a command's output and D
+# after it, so the terminal and RustFig know where the command line is. Set
+# RUSTFIG_SEMANTIC_PROMPT=0 if the terminal's own shell integration emits them.
+__rustfig_mark_prompt() {
+  # Runs last in PROMPT_COMMAND, after prompt frameworks like starship set PS1
+  if [ "${RUSTFIG_SEMANTIC_PROMPT:-1}" != 0 ] && [[ "$PS1" != *'133;B'* ]]; then
+    PS1="\[\e]133;A\a\]$PS1\[\e]133;B\a\]"
+  fi
+  __RUSTFIG_AT_PROMPT=1
+}
+
+# Milliseconds into __RUSTFIG_CLOCK_MS; whole seconds before bash 5's EPOCHREALTIME
+__rustfig_clock() {
+  if [ -n "$EPOCHREALTIME" ]; then
+    __RUSTFIG_CLOCK_MS=$(( ${EPOCHREALTIME/[.,]/} / 1000 ))
+  else
+    __RUSTFIG_CLOCK_MS=$(( SECONDS * 1000 ))
+  fi
+}
+
 # Called before command execution
 __rustfig_preexec() {
-  # Save the command to history
-  local cmd="$1"
-  rustfig record-command "$cmd" >/dev/null 2>&1
+  # The DEBUG trap fires for every simple command; only the first after the prompt
+  # starts the output and is the command to record (an empty line runs PROMPT_COMMAND)
+  if [ -n "$__RUSTFIG_AT_PROMPT" ]; then
+    __RUSTFIG_AT_PROMPT=
+    [ "${RUSTFIG_SEMANTIC_PROMPT:-1}" != 0 ] && printf '\e]133;C\a'
+    [[ "$1" == __rustfig_* ]] && return
+    
+    # Kept until the prompt returns, when the exit status and duration are known
+    __RUSTFIG_CMD="$1"
+    __RUSTFIG_CMD_DIR="$PWD"
+    __rustfig_clock
+    __RUSTFIG_CMD_START=$__RUSTFIG_CLOCK_MS
+  fi
+}
+
+# Record the finished command with its exit status and wall-clock duration
+__rustfig_record_finished() {
+  [ -n "$__RUSTFIG_CMD" ] || return 0
+  __rustfig_clock
+  (rustfig record-command "$__RUSTFIG_CMD" --dir="$__RUSTFIG_CMD_DIR" --shell=bash \
+    --exit-code="$1" --duration-ms=$(( __RUSTFIG_CLOCK_MS - __RUSTFIG_CMD_START )) >/dev/null 2>&1 &)
+  __RUSTFIG_CMD=
+}
+
+# Track visited directories for frecency-based jumping
+__rustfig_track_dir() {
+  if [ "$PWD" != "$__RUSTFIG_LAST_DIR" ]; then
+    __RUSTFIG_LAST_DIR="$PWD"
+    (rustfig record-dir "$PWD" >/dev/null 2>&1 &)
+  fi
+}
+
+# Jump to the best frecent match, zoxide-style
+if ! type z >/dev/null 2>&1; then
+  z() {
+    local dir
+    dir=$(rustfig z "$@") && cd "$dir"
+  }
+fi
+
+# Name the package that provides a missing command; the next prompt offers its install
+# command as ghost text. A handler the system already has (Ubuntu's command-not-found)
+# still runs when no package is known. Set RUSTFIG_COMMAND_NOT_FOUND=0 to keep it alone.
+if [ "${RUSTFIG_COMMAND_NOT_FOUND:-1}" != 0 ]; then
+  if declare -F command_not_found_handle >/dev/null; then
+    eval "__rustfig_previous_not_found() $(declare -f command_not_found_handle | tail -n +2)"
+  fi
+  command_not_found_handle() {
+    local packages
+    if packages=$(rustfig whichpkg "$1" --cached </dev/null 2>/dev/null); then
+      printf 'bash: %s: command not found\n%s\n' "$1" "$packages" >&2
+      return 127
+    fi
+    if declare -F __rustfig_previous_not_found >/dev/null; then
+      __rustfig_previous_not_found "$@"
+      return $?
+    fi
+    printf 'bash: %s: command not found\n' "$1" >&2
+    return 127
+  }
+fi
+
+# Readline can't be filled in before the prompt, so a command queued by
+# 'rustfig chat' (/insert) goes into history, one Up arrow away
+__rustfig_insert_queued() {
+  local queued="$RUSTFIG_RUNTIME_DIR/insert.$$"
+  # Only run what came from a directory nobody else can write to
+  [[ -O "$RUSTFIG_RUNTIME_DIR" && $(ls -ld "$RUSTFIG_RUNTIME_DIR") == drwx------* ]] || return 0
+  if [ -s "$queued" ]; then
+    history -s -- "$(<"$queued")"
+    echo "rustfig: press Up for the command from chat"
+  fi
+  rm -f -- "$queued"
+}
+
+# Prints "offline" (or $RUSTFIG_OFFLINE_LABEL) while RustFig isn't using the network;
+# put $(rustfig_offline_indicator) in a single-quoted PS1
+rustfig_offline_indicator() {
+  if [ -e "${XDG_CONFIG_HOME:-$HOME/.config}/rustfig/offline" ] || [ -e "$RUSTFIG_RUNTIME_DIR/no-network" ]; then
+    printf '%s' "${RUSTFIG_OFFLINE_LABEL:-offline}"
+  fi
 }
 
 # Called after command completion
 __rustfig_postcmd() {
+  local status=$?
+  [ "${RUSTFIG_SEMANTIC_PROMPT:-1}" != 0 ] && printf '\e]133;D;%s\a' "$status"
+  
+  __rustfig_record_finished "$status"
+  __rustfig_track_dir
+  __rustfig_insert_queued
+  
+  # Capture shell symbols once per session, after the rc files have defined them
+  if [ -z "$RUSTFIG_SYMBOLS_DUMPED" ]; then
+    RUSTFIG_SYMBOLS_DUMPED=1
+    rustfig-refresh-shell-symbols
+  fi
+  
   # Update context after directory changes
   __rustfig_init_context
 }
@@ -70,6 +189,28 @@ rustfig-explain-command() {
   rustfig explain "$current_line"
 }
 
+# Full-screen fuzzy history search replacing Ctrl+R
+__rustfig_history_search() {
+  local selected
+  selected=$(rustfig history search --query="$READLINE_LINE" --shell=bash </dev/tty)
+  if [ -n "$selected" ]; then
+    READLINE_LINE="$selected"
+    READLINE_POINT=${#selected}
+  fi
+}
+
+# Replace a '# description' line with a command generated for it
+__rustfig_generate_command() {
+  local line="${READLINE_LINE#"${READLINE_LINE%%[![:space:]]*}"}"
+  [[ "$line" == '#'* ]] || return 0
+  local generated
+  generated=$(rustfig generate "$READLINE_LINE" --shell=bash </dev/tty)
+  if [ -n "$generated" ]; then
+    READLINE_LINE="$generated"
+    READLINE_POINT=${#generated}
+  fi
+}
+
 # Uninstall RustFig (removes integration block)
 rustfig-uninstall() {
   rustfig service stop
@@ -82,9 +223,12 @@ __rustfig_init_context
 
 # Set bash-specific hooks if available
 if [ -n "$PROMPT_COMMAND" ]; then
-  PROMPT_COMMAND="__rustfig_postcmd;$PROMPT_COMMAND"
+  PROMPT_COMMAND="__rustfig_postcmd;$PROMPT_COMMAND;__rustfig_mark_prompt"
 else
-  PROMPT_COMMAND="__rustfig_postcmd"
+  PROMPT_COMMAND="__rustfig_postcmd;__rustfig_mark_prompt"
 fi
 
 trap '__rustfig_preexec "$BASH_COMMAND"' DEBUG
+
+bind -x "\"${RUSTFIG_HISTORY_KEY:-\\C-r}\": __rustfig_history_search"
+bind -x "\"${RUSTFIG_GENERATE_KEY:-\\e\\C-m}\": __rustfig_generate_command"
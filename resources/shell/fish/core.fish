@@ -13,31 +13,77 @@ if not status is-interactive
 end
 
 # Setup directory for runtime data
-set -l RUSTFIG_RUNTIME_DIR $XDG_RUNTIME_DIR
+set -g RUSTFIG_RUNTIME_DIR $XDG_RUNTIME_DIR
 if not set -q XDG_RUNTIME_DIR
   set RUSTFIG_RUNTIME_DIR /tmp
 end
 set RUSTFIG_RUNTIME_DIR $RUSTFIG_RUNTIME_DIR/rustfig-$USER
-mkdir -p $RUSTFIG_RUNTIME_DIR
+mkdir -p -m 700 $RUSTFIG_RUNTIME_DIR
+test -O $RUSTFIG_RUNTIME_DIR; and chmod 700 $RUSTFIG_RUNTIME_DIR
 
 # Initialize RustFig context
 function rustfig-update-context
   rustfig update-context --shell=fish --dir="$PWD" --term="$TERM" >/dev/null 2>&1
 end
 
+# Dump aliases, functions, and builtins so RustFig can expand and suggest them
+function rustfig-refresh-shell-symbols
+  alias > $RUSTFIG_RUNTIME_DIR/aliases.fish 2>/dev/null
+  functions --names | string split ', ' > $RUSTFIG_RUNTIME_DIR/functions.fish 2>/dev/null
+  builtin --names > $RUSTFIG_RUNTIME_DIR/builtins.fish 2>/dev/null
+end
+
 # Called before command execution
 function __rustfig_preexec --on-event fish_preexec
-  # Save the command to history
-  set -l cmd $argv[1]
-  rustfig record-command "$cmd" >/dev/null 2>&1
+  # Where it ran; a cd has moved on by the time it's recorded
+  set -g __rustfig_cmd_dir $PWD
 end
 
 # Called after command completion
 function __rustfig_postexec --on-event fish_postexec
+  # Record the command with its exit status and wall-clock duration
+  set -l exit_code $status
+  if test -n "$argv[1]"
+    rustfig record-command "$argv[1]" --dir="$__rustfig_cmd_dir" --shell=fish --exit-code=$exit_code --duration-ms=$CMD_DURATION >/dev/null 2>&1 &
+    disown 2>/dev/null
+  end
+  
+  # Capture shell symbols once per session, after config.fish has defined them
+  if not set -q RUSTFIG_SYMBOLS_DUMPED
+    set -g RUSTFIG_SYMBOLS_DUMPED 1
+    rustfig-refresh-shell-symbols
+  end
+  
   # Update context after directory changes
   rustfig-update-context
 end
 
+# OSC 133 prompt marks: A and B around the prompt, C before a command's output and D
+# after it, so the terminal and RustFig know where the command line is. fish 4 emits
+# them itself; set RUSTFIG_SEMANTIC_PROMPT=0 if the terminal's shell integration does.
+if test "$RUSTFIG_SEMANTIC_PROMPT" != 0; and string match -qr '^[0-3]\.' $FISH_VERSION
+  function __rustfig_mark_prompt --on-event fish_prompt
+    printf '\e]133;A\a'
+    # Wrap whichever fish_prompt config.fish and the theme left, at the first prompt
+    if not set -q __rustfig_prompt_wrapped
+      set -g __rustfig_prompt_wrapped 1
+      functions -c fish_prompt __rustfig_original_prompt
+      function fish_prompt
+        __rustfig_original_prompt
+        printf '\e]133;B\a'
+      end
+    end
+  end
+  
+  function __rustfig_mark_executed --on-event fish_preexec
+    printf '\e]133;C\a'
+  end
+  
+  function __rustfig_mark_finished --on-event fish_postexec
+    printf '\e]133;D;%s\a' $status
+  end
+end
+
 # Custom tab completion with RustFig predictions
 function __rustfig_predict
   set -l cmdline (commandline)
@@ -74,6 +120,87 @@ function rustfig-explain-command
   rustfig explain "$cmdline"
 end
 
+# Name the package that provides a missing command; the next prompt offers its install
+# command as ghost text. fish's own handler still runs when no package is known. Set
+# RUSTFIG_COMMAND_NOT_FOUND=0 to keep it alone.
+if test "$RUSTFIG_COMMAND_NOT_FOUND" != 0
+  if functions -q fish_command_not_found
+    functions -c fish_command_not_found __rustfig_previous_not_found
+  end
+  function fish_command_not_found
+    set -l packages
+    if set packages (rustfig whichpkg $argv[1] --cached </dev/null 2>/dev/null | string collect)
+      printf 'fish: Unknown command: %s\n%s\n' $argv[1] $packages >&2
+      return 127
+    end
+    if functions -q __rustfig_previous_not_found
+      __rustfig_previous_not_found $argv
+    else
+      printf 'fish: Unknown command: %s\n' $argv[1] >&2
+    end
+  end
+end
+
+# Put a command queued by 'rustfig chat' (/insert) on the next command line
+function __rustfig_insert_queued --on-event fish_prompt
+  set -l queued $RUSTFIG_RUNTIME_DIR/insert.$fish_pid
+  # Only run what came from a directory nobody else can write to
+  test -O $RUSTFIG_RUNTIME_DIR; and string match -q -- 'drwx------*' (ls -ld $RUSTFIG_RUNTIME_DIR); or return
+  if test -s $queued
+    commandline -r -- (string collect < $queued)
+  end
+  rm -f -- $queued
+end
+
+# Prints "offline" (or $RUSTFIG_OFFLINE_LABEL) while RustFig isn't using the network;
+# call it from fish_prompt or fish_right_prompt
+function rustfig_offline_indicator
+  set -l config_dir $HOME/.config
+  if set -q XDG_CONFIG_HOME
+    set config_dir $XDG_CONFIG_HOME
+  end
+  if test -e $config_dir/rustfig/offline; or test -e $RUSTFIG_RUNTIME_DIR/no-network
+    set -q RUSTFIG_OFFLINE_LABEL; and echo -n $RUSTFIG_OFFLINE_LABEL; or echo -n offline
+  end
+end
+
+# Track visited directories for frecency-based jumping
+function __rustfig_track_dir --on-variable PWD
+  rustfig record-dir "$PWD" >/dev/null 2>&1 &
+  disown 2>/dev/null
+end
+
+# Jump to the best frecent match, zoxide-style
+if not functions -q z
+  function z
+    set -l dir (rustfig z $argv); and cd $dir
+  end
+end
+
+# Full-screen fuzzy history search replacing Ctrl+R
+function __rustfig_history_search
+  set -l selected (rustfig history search --query=(commandline) --shell=fish </dev/tty | string collect)
+  if test -n "$selected"
+    commandline -r -- $selected
+  end
+  commandline -f repaint
+end
+
+# Replace a '# description' line with a command generated for it; elsewhere the key
+# keeps its usual meaning (a newline)
+function __rustfig_generate_command
+  set -l cmdline (commandline | string collect)
+  if not string match -qr '^\s*#' -- $cmdline
+    commandline -i \n
+    return
+  end
+  set -l generated (rustfig generate "$cmdline" --shell=fish </dev/tty | string collect)
+  if test -n "$generated"
+    commandline -r -- $generated
+  end
+  commandline -f repaint
+end
+
 # Uninstall RustFig (removes integration block)
 function rustfig-uninstall
   rustfig service stop
@@ -83,6 +210,16 @@ end
 
 # Setup key bindings
 bind \t '__rustfig_predict'
+if set -q RUSTFIG_HISTORY_KEY
+  bind $RUSTFIG_HISTORY_KEY __rustfig_history_search
+else
+  bind \cr __rustfig_history_search
+end
+if set -q RUSTFIG_GENERATE_KEY
+  bind $RUSTFIG_GENERATE_KEY __rustfig_generate_command
+else
+  bind \e\r __rustfig_generate_command
+end
 
 # Run the initial context setup
 rustfig-update-context
@@ -1,15 +1,24 @@
 // Re-export from the ai module
+pub mod assist;
 pub mod client;
 pub mod cache;
+pub mod conversation;
 pub mod ollama;
+pub mod retry;
+pub mod scheduler;
+pub mod tokens;
 
 use std::error::Error;
 use std::time::Duration;
 use async_trait::async_trait;
 
+use self::conversation::{flatten_messages, ConversationMessage};
+use crate::utils::offline;
+
 pub use self::client::AiClient;
 pub use self::cache::AiCache;
 pub use self::ollama::OllamaClient;
+pub use self::retry::RetryingProvider;
 
 /// Common trait for AI providers
 #[async_trait]
@@ -17,6 +26,12 @@ pub trait AiProvider: Send + Sync {
     /// Query the AI with a prompt
     async fn query(&self, prompt: &str) -> Result<String, Box<dyn Error>>;
     
+    /// Answer the last of `messages`, a chat history with "system", "user" and
+    /// "assistant" roles; providers without a chat API get it as one prompt
+    async fn chat(&self, messages: &[ConversationMessage]) -> Result<String, Box<dyn Error>> {
+        self.query(&flatten_messages(messages)).await
+    }
+    
     /// Check if the provider is available
     async fn is_available(&self) -> bool;
     
@@ -30,6 +45,10 @@ impl AiProvider for AiClient {
         self.query(prompt).await
     }
     
+    async fn chat(&self, messages: &[ConversationMessage]) -> Result<String, Box<dyn Error>> {
+        self.chat(messages).await
+    }
+    
     async fn is_available(&self) -> bool {
         true // Simple API client is always considered available
     }
@@ -45,6 +64,10 @@ impl AiProvider for OllamaClient {
         self.query(prompt).await
     }
     
+    async fn chat(&self, messages: &[ConversationMessage]) -> Result<String, Box<dyn Error>> {
+        self.chat(messages).await
+    }
+    
     async fn is_available(&self) -> bool {
         self.is_available().await
     }
@@ -58,28 +81,33 @@ impl AiProvider for OllamaClient {
 pub struct AiProviderFactory;
 
 impl AiProviderFactory {
-    /// Create an AI provider based on configuration
+    /// Create an AI provider based on configuration, with retries and rate limiting
     pub async fn create_provider(
         config: &crate::config::Config
     ) -> Option<Box<dyn AiProvider>> {
-        // Try Ollama first if enabled
+        scheduler::configure(&config.ai);
+        
+        // Try Ollama first if enabled; offline, providers on this machine still work
         if let Some(ollama_config) = &config.ollama {
-            if ollama_config.enabled {
-                if let Ok(client) = OllamaClient::new(ollama_config) {
+            if ollama_config.enabled && offline::allows_url(&ollama_config.api_url) {
+                // Box<dyn Error> isn't Send, so the error can't be held across the await
+                let client = OllamaClient::new(ollama_config).ok();
+                if let Some(client) = client {
                     if client.is_available().await {
-                        return Some(Box::new(client));
+                        return Some(Box::new(RetryingProvider::new(Box::new(client), &config.ai)));
                     }
                 }
             }
         }
         
         // Fall back to API if enabled
-        if config.ai.enabled {
+        if config.ai.enabled && offline::allows_url(&config.ai.api_endpoint) {
             if let Ok(client) = AiClient::new(
                 config.ai.api_endpoint.clone(),
                 config.ai.api_key.clone()
             ) {
-                return Some(Box::new(client));
+                let client = client.with_model(config.ai.model.clone());
+                return Some(Box::new(RetryingProvider::new(Box::new(client), &config.ai)));
             }
         }
         
@@ -3,6 +3,8 @@ use std::time::{Duration, Instant};
 use std::sync::Arc;
 use parking_lot::RwLock;
 
+use crate::utils::memory::{self, MemoryConsumer};
+
 pub struct AiCache {
     cache: Arc<RwLock<HashMap<String, CacheEntry>>>,
     max_entries: usize,
@@ -36,6 +38,13 @@ impl AiCache {
     }
     
     pub fn set(&self, key: &str, value: String) {
+        self.insert(key, value);
+        
+        // With the lock released, since staying in budget may shrink this cache
+        memory::enforce();
+    }
+    
+    fn insert(&self, key: &str, value: String) {
         let mut cache = self.cache.write();
         
         // Clean up expired entries if cache is full
@@ -69,3 +78,35 @@ impl AiCache {
         cache.clear();
     }
 }
+
+impl MemoryConsumer for AiCache {
+    fn memory_bytes(&self) -> usize {
+        self.cache.read().iter().map(|(key, entry)| entry_bytes(key, entry)).sum()
+    }
+    
+    /// Evict the oldest responses first
+    fn shrink_to(&self, target: usize) {
+        let mut cache = self.cache.write();
+        let mut total: usize = cache.iter().map(|(key, entry)| entry_bytes(key, entry)).sum();
+        if total <= target {
+            return;
+        }
+        
+        let mut oldest: Vec<(String, Instant, usize)> = cache.iter()
+            .map(|(key, entry)| (key.clone(), entry.timestamp, entry_bytes(key, entry)))
+            .collect();
+        oldest.sort_by_key(|(_, timestamp, _)| *timestamp);
+        
+        for (key, _, bytes) in oldest {
+            if total <= target {
+                break;
+            }
+            cache.remove(&key);
+            total -= bytes;
+        }
+    }
+}
+
+fn entry_bytes(key: &str, entry: &CacheEntry) -> usize {
+    std::mem::size_of::<CacheEntry>() + key.len() + entry.value.len()
+}
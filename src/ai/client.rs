@@ -2,6 +2,9 @@ use std::error::Error;
 use reqwest::{Client, header};
 use serde::{Deserialize, Serialize};
 
+use crate::ai::conversation::ConversationMessage;
+use crate::ai::retry::ProviderError;
+
 #[derive(Serialize)]
 struct ApiRequest {
     prompt: String,
@@ -19,10 +22,41 @@ struct Choice {
     text: String,
 }
 
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<&'a str>,
+    messages: Vec<ChatMessage<'a>>,
+    temperature: f32,
+}
+
+#[derive(Serialize)]
+struct ChatMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatReply,
+}
+
+#[derive(Deserialize)]
+struct ChatReply {
+    #[serde(default)]
+    content: String,
+}
+
 pub struct AiClient {
     client: Client,
     api_endpoint: String,
     api_key: Option<String>,
+    model: Option<String>,
 }
 
 impl AiClient {
@@ -35,10 +69,17 @@ impl AiClient {
             client,
             api_endpoint,
             api_key,
+            model: None,
         })
     }
     
-    pub async fn query(&self, prompt: &str) -> Result<String, Box<dyn Error>> {
+    /// Name the model in chat requests, for endpoints that serve more than one
+    pub fn with_model(mut self, model: Option<String>) -> Self {
+        self.model = model;
+        self
+    }
+    
+    fn headers(&self) -> Result<header::HeaderMap, Box<dyn Error>> {
         let mut headers = header::HeaderMap::new();
         
         if let Some(api_key) = &self.api_key {
@@ -47,6 +88,26 @@ impl AiClient {
                 header::HeaderValue::from_str(&format!("Bearer {}", api_key))?,
             );
         }
+        Ok(headers)
+    }
+    
+    /// The chat completions URL next to the configured endpoint, which may be the API
+    /// base or its completions URL
+    fn chat_url(&self) -> String {
+        let (path, query) = match self.api_endpoint.split_once('?') {
+            Some((path, query)) => (path, format!("?{}", query)),
+            None => (self.api_endpoint.as_str(), String::new()),
+        };
+        let path = path.trim_end_matches('/');
+        if path.ends_with("/chat/completions") {
+            return format!("{}{}", path, query);
+        }
+        let base = path.strip_suffix("/completions").unwrap_or(path);
+        format!("{}/chat/completions{}", base, query)
+    }
+    
+    pub async fn query(&self, prompt: &str) -> Result<String, Box<dyn Error>> {
+        let headers = self.headers()?;
         
         let request = ApiRequest {
             prompt: prompt.to_string(),
@@ -62,7 +123,7 @@ impl AiClient {
             .await?;
         
         if !response.status().is_success() {
-            return Err(format!("API error: {}", response.status()).into());
+            return Err(ProviderError::from_response("API", &response).into());
         }
         
         let api_response: ApiResponse = response.json().await?;
@@ -73,4 +134,34 @@ impl AiClient {
         
         Ok(api_response.choices[0].text.clone())
     }
+    
+    /// Answer the last of `messages` through the chat completions API, roles and all
+    pub async fn chat(&self, messages: &[ConversationMessage]) -> Result<String, Box<dyn Error>> {
+        let request = ChatRequest {
+            model: self.model.as_deref(),
+            messages: messages
+                .iter()
+                .map(|message| ChatMessage { role: &message.role, content: &message.content })
+                .collect(),
+            temperature: 0.7,
+        };
+        
+        let headers = self.headers()?;
+        let response = self.client
+            .post(self.chat_url())
+            .headers(headers)
+            .json(&request)
+            .send()
+            .await?;
+        
+        if !response.status().is_success() {
+            return Err(ProviderError::from_response("API", &response).into());
+        }
+        
+        let chat_response: ChatResponse = response.json().await?;
+        match chat_response.choices.into_iter().next() {
+            Some(choice) => Ok(choice.message.content),
+            None => Err("No response from AI".into()),
+        }
+    }
 }
@@ -3,13 +3,43 @@ use std::error::Error;
 use std::fs::{self, File};
 use std::io::{self, BufRead, Write};
 use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
 
+use crate::ai::tokens::{estimate_tokens, MESSAGE_OVERHEAD};
+use crate::ai::ollama::same_model;
+use crate::ai::scheduler::{scheduler, Priority};
 use crate::ai::AiProvider;
+use crate::config::Config;
+use crate::suggestion::fuzzy::fast_fuzzy_score;
 
-/// Maximum number of messages to store in conversation history
-const MAX_HISTORY_MESSAGES: usize = 20;
+/// Maximum number of messages kept on disk per conversation; prompts carry only what
+/// fits the model's history budget
+const MAX_STORED_MESSAGES: usize = 500;
+
+/// Context window assumed for models not in ai_models.yaml
+const DEFAULT_CONTEXT_WINDOW: usize = 4096;
+
+/// Response tokens assumed for models that don't set `max_tokens`
+const DEFAULT_MAX_TOKENS: usize = 1024;
+
+/// History tokens set aside for the summary when summarizing
+const SUMMARY_TOKENS: usize = 256;
+
+/// Characters of context shown either side of a search match
+const SNIPPET_CONTEXT: usize = 40;
+
+/// Every word of a query must match a word of the line at least this well
+const MIN_FUZZY_SCORE: f32 = 0.4;
+
+/// Languages of fenced blocks taken to hold shell commands; unlabeled blocks count too
+const SHELL_LANGUAGES: &[&str] = &["sh", "bash", "zsh", "fish", "shell", "console", "shell-session"];
+
+/// Longest automatic title, in characters
+const MAX_TITLE_CHARS: usize = 48;
 
 /// Represents a message in a conversation
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,6 +67,14 @@ pub struct Conversation {
     storage_path: PathBuf,
     /// AI provider to use for this conversation
     provider_name: String,
+    /// Set from the first exchange, or by the user
+    title: Option<String>,
+    /// Summary of the messages before `summarized`, which prompts carry instead of them
+    summary: Option<String>,
+    summarized: usize,
+    /// Messages dropped from the front since the conversation was loaded, so positions
+    /// taken earlier can be shifted to match
+    dropped: usize,
 }
 
 impl Conversation {
@@ -58,6 +96,10 @@ impl Conversation {
             last_used: now,
             storage_path,
             provider_name: provider_name.to_string(),
+            title: None,
+            summary: None,
+            summarized: 0,
+            dropped: 0,
         }
     }
     
@@ -69,11 +111,15 @@ impl Conversation {
         
         Ok(Self {
             id: data.id,
-            messages: VecDeque::from(data.messages),
             created_at: data.created_at,
             last_used: data.last_used,
             storage_path: path.to_path_buf(),
             provider_name: data.provider_name,
+            title: data.title,
+            summarized: data.summarized.min(data.messages.len()),
+            summary: data.summary,
+            messages: VecDeque::from(data.messages),
+            dropped: 0,
         })
     }
     
@@ -85,6 +131,9 @@ impl Conversation {
             created_at: self.created_at,
             last_used: self.last_used,
             provider_name: self.provider_name.clone(),
+            title: self.title.clone(),
+            summary: self.summary.clone(),
+            summarized: self.summarized,
         };
         
         let json = serde_json::to_string_pretty(&data)?;
@@ -119,9 +168,11 @@ impl Conversation {
         
         self.last_used = now;
         
-        // Maintain maximum history size
-        while self.messages.len() > MAX_HISTORY_MESSAGES {
+        // Maintain maximum stored history size
+        while self.messages.len() > MAX_STORED_MESSAGES {
             self.messages.pop_front();
+            self.summarized = self.summarized.saturating_sub(1);
+            self.dropped += 1;
         }
     }
     
@@ -140,21 +191,355 @@ impl Conversation {
         &self.provider_name
     }
     
-    /// Build prompt with conversation history for the AI
-    pub fn build_prompt(&self) -> String {
-        let mut prompt = String::new();
-        
-        for message in &self.messages {
-            match message.role.as_str() {
-                "user" => prompt.push_str(&format!("User: {}\n", message.content)),
-                "assistant" => prompt.push_str(&format!("Assistant: {}\n", message.content)),
-                _ => {}
+    /// Title to show for the conversation
+    pub fn title(&self) -> &str {
+        self.title.as_deref().unwrap_or("Untitled")
+    }
+    
+    /// When the conversation was last used
+    pub fn last_used(&self) -> u64 {
+        self.last_used
+    }
+    
+    /// Title the conversation after its first question, once it has been answered
+    fn title_from_first_exchange(&mut self) {
+        if self.title.is_some() || !self.messages.iter().any(|m| m.role == "assistant") {
+            return;
+        }
+        self.title = self.messages.iter().find(|m| m.role == "user").and_then(|m| title_from(&m.content));
+    }
+    
+    /// Index of the oldest message a prompt of `budget` history tokens can carry; always
+    /// the latest message at least, and never one the summary already covers
+    fn history_start(&self, budget: usize) -> usize {
+        let mut remaining = budget.saturating_sub(self.summary.as_deref().map_or(0, estimate_tokens));
+        let mut start = self.messages.len();
+        
+        for (index, message) in self.messages.iter().enumerate().rev() {
+            let cost = estimate_tokens(&message.content) + MESSAGE_OVERHEAD;
+            if cost > remaining && start < self.messages.len() {
+                break;
             }
+            remaining = remaining.saturating_sub(cost);
+            start = index;
         }
         
-        prompt.push_str("Assistant:");
+        if self.summary.is_some() {
+            start.max(self.summarized)
+        } else {
+            start
+        }
+    }
+    
+    /// Prompt asking for a summary of the earlier summary and the messages up to `end`
+    fn summary_prompt(&self, end: usize) -> String {
+        let mut prompt = String::from(
+            "Summarize this conversation between a user and a terminal assistant in at most 150 words. \
+             Keep commands, file names, versions and decisions; drop pleasantries.\n\n",
+        );
+        if let Some(summary) = &self.summary {
+            prompt.push_str(&format!("Earlier summary: {}\n", summary));
+        }
+        for message in self.messages.iter().take(end).skip(self.summarized) {
+            let who = if message.role == "user" { "User" } else { "Assistant" };
+            prompt.push_str(&format!("{}: {}\n", who, message.content));
+        }
+        prompt.push_str("Summary:");
         prompt
     }
+    
+    /// As much recent history as fits in `budget` tokens, after a system message with
+    /// the summary of what came before it
+    pub fn build_messages(&self, budget: usize) -> Vec<ConversationMessage> {
+        let start = self.history_start(budget);
+        let mut messages = Vec::with_capacity(self.messages.len() - start + 1);
+        
+        if let Some(summary) = self.summary.as_deref().filter(|_| start >= self.summarized) {
+            messages.push(ConversationMessage {
+                role: "system".to_string(),
+                content: format!("Summary of the earlier conversation: {}", summary),
+                timestamp: self.messages.get(start).map_or(self.last_used, |message| message.timestamp),
+            });
+        }
+        messages.extend(self.messages.iter().skip(start).cloned());
+        messages
+    }
+    
+    /// Build prompt with as much recent history as fits in `budget` tokens
+    pub fn build_prompt(&self, budget: usize) -> String {
+        flatten_messages(&self.build_messages(budget))
+    }
+}
+
+/// Messages as one "User:/Assistant:" prompt, for providers without a chat format
+pub fn flatten_messages(messages: &[ConversationMessage]) -> String {
+    let mut prompt = String::new();
+    for message in messages {
+        match message.role.as_str() {
+            "system" => prompt.push_str(&format!("{}\n", message.content)),
+            "user" => prompt.push_str(&format!("User: {}\n", message.content)),
+            "assistant" => prompt.push_str(&format!("Assistant: {}\n", message.content)),
+            _ => {}
+        }
+    }
+    prompt.push_str("Assistant:");
+    prompt
+}
+
+/// How much history a prompt may carry, from the model's entry in ai_models.yaml
+#[derive(Debug, Clone, Copy)]
+pub struct HistoryBudget {
+    /// Estimated tokens of history per prompt
+    pub tokens: usize,
+    /// Summarize messages that no longer fit instead of dropping them
+    pub summarize: bool,
+}
+
+impl Default for HistoryBudget {
+    fn default() -> Self {
+        Self { tokens: DEFAULT_CONTEXT_WINDOW - DEFAULT_MAX_TOKENS, summarize: false }
+    }
+}
+
+impl HistoryBudget {
+    /// Budget for `model`, or the configured API or Ollama model when not given
+    pub fn for_model(config: &Config, model: Option<&str>) -> Self {
+        let ollama_model = config.ollama.as_ref().filter(|ollama| ollama.enabled).map(|ollama| ollama.model.as_str());
+        let Some(name) = model.or(config.ai.model.as_deref()).or(ollama_model) else {
+            return Self::default();
+        };
+        
+        let definition = config
+            .ai_models
+            .iter()
+            .flat_map(|models| models.models.values())
+            .flat_map(|provider| provider.available.iter())
+            .find(|(key, definition)| {
+                same_model(key, name) || definition.model_name.as_deref().map_or(false, |n| same_model(n, name))
+            })
+            .map(|(_, definition)| definition);
+        let Some(definition) = definition else {
+            return Self::default();
+        };
+        
+        let context_window = definition.context_window.map_or(DEFAULT_CONTEXT_WINDOW, |t| t as usize);
+        let max_tokens = definition.max_tokens.map_or(DEFAULT_MAX_TOKENS, |t| t as usize);
+        let room = context_window.saturating_sub(max_tokens);
+        Self {
+            tokens: definition.history_tokens.map_or(room, |t| (t as usize).min(room)),
+            summarize: definition.summarize_history.unwrap_or(false),
+        }
+    }
+}
+
+/// The command in each fenced shell block of a response, without `$ ` prompts; in
+/// `console` blocks only the prompted lines are commands, the rest is output
+pub fn extract_commands(response: &str) -> Vec<String> {
+    let mut commands = Vec::new();
+    // Lines of the open block, and its language
+    let mut block: Option<(String, Vec<&str>)> = None;
+    
+    for line in response.lines() {
+        let Some(fence) = line.trim().strip_prefix("```") else {
+            if let Some((_, lines)) = block.as_mut() {
+                lines.push(line);
+            }
+            continue;
+        };
+        
+        let Some((language, lines)) = block.take() else {
+            block = Some((fence.trim().to_lowercase(), Vec::new()));
+            continue;
+        };
+        if !language.is_empty() && !SHELL_LANGUAGES.contains(&language.as_str()) {
+            continue;
+        }
+        
+        let session = matches!(language.as_str(), "console" | "shell-session");
+        let command: Vec<&str> = lines
+            .iter()
+            .map(|line| line.trim_end())
+            .filter_map(|line| match line.trim_start().strip_prefix("$ ") {
+                Some(prompted) => Some(prompted),
+                None if session => None,
+                None => Some(line),
+            })
+            .filter(|line| !line.trim().is_empty())
+            .collect();
+        if !command.is_empty() {
+            commands.push(command.join("\n"));
+        }
+    }
+    
+    commands
+}
+
+/// List the commands found in a response and how to use them
+fn print_commands(commands: &[String]) {
+    if commands.is_empty() {
+        return;
+    }
+    for (index, command) in commands.iter().enumerate() {
+        println!("  [{}] {}", index + 1, command.replace('\n', "\n      "));
+    }
+    if commands.len() == 1 {
+        println!("  '/insert' puts it on your command line, '/run' runs it");
+    } else {
+        println!("  '/insert N' puts one on your command line, '/run N' runs it");
+    }
+}
+
+/// The command `/insert` or `/run` refers to, by number or the only one there is
+fn pick_command<'a>(commands: &'a [String], choice: &str) -> Result<&'a str, String> {
+    let choice = choice.trim();
+    if commands.is_empty() {
+        return Err("The last response had no commands".to_string());
+    }
+    if choice.is_empty() {
+        return match commands {
+            [command] => Ok(command),
+            _ => Err(format!("Which one? Give a number from 1 to {}", commands.len())),
+        };
+    }
+    choice
+        .parse::<usize>()
+        .ok()
+        .and_then(|number| commands.get(number.checked_sub(1)?))
+        .map(String::as_str)
+        .ok_or_else(|| format!("No command {}; give a number from 1 to {}", choice, commands.len()))
+}
+
+/// A short title from a message: its first non-empty line, cut at a word boundary
+fn title_from(message: &str) -> Option<String> {
+    let line = message.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+    if line.chars().count() <= MAX_TITLE_CHARS {
+        return Some(line);
+    }
+
+    let cut: String = line.chars().take(MAX_TITLE_CHARS - 1).collect();
+    let cut = match cut.rfind(' ') {
+        Some(space) if space > MAX_TITLE_CHARS / 2 => &cut[..space],
+        _ => cut.as_str(),
+    };
+    Some(format!("{}…", cut.trim_end_matches(|c: char| !c.is_alphanumeric())))
+}
+
+/// How long ago `timestamp` was, like "5 minutes ago"
+pub fn format_age(timestamp: u64) -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let seconds = now.saturating_sub(timestamp);
+    let (count, unit) = match seconds {
+        0..=59 => return "just now".to_string(),
+        60..=3599 => (seconds / 60, "minute"),
+        3600..=86399 => (seconds / 3600, "hour"),
+        86400..=604799 => (seconds / 86400, "day"),
+        604800..=2591999 => (seconds / 604800, "week"),
+        2592000..=31535999 => (seconds / 2592000, "month"),
+        _ => (seconds / 31536000, "year"),
+    };
+    format!("{} {}{} ago", count, unit, if count == 1 { "" } else { "s" })
+}
+
+/// A conversation as listed, without its messages
+#[derive(Debug, Clone)]
+pub struct ConversationSummary {
+    pub id: String,
+    pub title: String,
+    pub last_used: u64,
+    pub message_count: usize,
+}
+
+/// A stored message that matched a search
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub conversation_id: String,
+    pub role: String,
+    pub timestamp: u64,
+    /// The matching part of the message, on one line
+    pub snippet: String,
+    /// 1.0 for the query found as typed, lower for fuzzy matches
+    pub score: f32,
+}
+
+/// Print hits as `ID  Role: snippet`, one per line
+pub fn print_search_hits(hits: &[SearchHit]) {
+    if hits.is_empty() {
+        println!("No matching messages");
+    }
+    for hit in hits {
+        let who = if hit.role == "user" { "You" } else { "AI" };
+        println!("{}  {}: {}", hit.conversation_id, who, hit.snippet);
+    }
+}
+
+/// How well a message matches: the query itself, case-insensitively, or else every word
+/// of it fuzzily matching a word on one line
+fn match_message(query: &str, content: &str) -> Option<(f32, String)> {
+    if let Some((start, end)) = find_ignore_case(content, query) {
+        return Some((1.0, snippet(content, start, end)));
+    }
+
+    let terms: Vec<&str> = query.split_whitespace().collect();
+    content
+        .lines()
+        .filter_map(|line| {
+            let words: Vec<&str> = line.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).collect();
+            let mut total = 0.0;
+            for term in &terms {
+                let best = words
+                    .iter()
+                    .filter_map(|word| fast_fuzzy_score(term, word))
+                    .fold(0.0f32, f32::max);
+                if best < MIN_FUZZY_SCORE {
+                    return None;
+                }
+                total += best;
+            }
+            Some((0.5 * total / terms.len() as f32, snippet(line, 0, line.len())))
+        })
+        .max_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal))
+}
+
+/// Byte range of the first case-insensitive occurrence of `needle` in `haystack`
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<(usize, usize)> {
+    let needle: Vec<char> = needle.chars().flat_map(char::to_lowercase).collect();
+    if needle.is_empty() {
+        return None;
+    }
+    haystack.char_indices().find_map(|(start, _)| {
+        let mut matched = 0;
+        for (offset, c) in haystack[start..].char_indices() {
+            for lower in c.to_lowercase() {
+                if needle.get(matched) != Some(&lower) {
+                    return None;
+                }
+                matched += 1;
+            }
+            if matched == needle.len() {
+                return Some((start, start + offset + c.len_utf8()));
+            }
+        }
+        None
+    })
+}
+
+/// `text[start..end]` with some context either side, on one line
+fn snippet(text: &str, start: usize, end: usize) -> String {
+    let before: String = text[..start].chars().rev().take(SNIPPET_CONTEXT).collect::<Vec<_>>().into_iter().rev().collect();
+    let after: String = text[end..].chars().take(SNIPPET_CONTEXT).collect();
+
+    let mut snippet = String::new();
+    if before.len() < start {
+        snippet.push('…');
+    }
+    snippet.push_str(&before);
+    snippet.push_str(&text[start..end]);
+    snippet.push_str(&after);
+    if after.len() < text.len() - end {
+        snippet.push('…');
+    }
+    snippet.split_whitespace().collect::<Vec<_>>().join(" ")
 }
 
 /// Serializable conversation data for storage
@@ -165,6 +550,12 @@ struct ConversationData {
     created_at: u64,
     last_used: u64,
     provider_name: String,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    summary: Option<String>,
+    #[serde(default)]
+    summarized: usize,
 }
 
 /// Manages conversation sessions
@@ -172,6 +563,19 @@ pub struct ConversationManager {
     conversations: Vec<Conversation>,
     active_conversation_id: Option<String>,
     storage_dir: PathBuf,
+    history_budget: HistoryBudget,
+    /// A history summary being made in the background
+    pending_summary: Option<PendingSummary>,
+}
+
+/// A summary of a conversation's history up to message `end`, made after a reply so the
+/// next request doesn't wait for it
+struct PendingSummary {
+    conversation_id: String,
+    end: usize,
+    /// The conversation's `dropped` when `end` was taken
+    dropped: usize,
+    task: JoinHandle<Option<String>>,
 }
 
 impl ConversationManager {
@@ -183,6 +587,8 @@ impl ConversationManager {
             conversations: Vec::new(),
             active_conversation_id: None,
             storage_dir: storage_dir.to_path_buf(),
+            history_budget: HistoryBudget::default(),
+            pending_summary: None,
         };
         
         // Load existing conversations
@@ -191,6 +597,11 @@ impl ConversationManager {
         Ok(manager)
     }
     
+    /// How much history prompts carry
+    pub fn set_history_budget(&mut self, budget: HistoryBudget) {
+        self.history_budget = budget;
+    }
+    
     /// Load existing conversations from the storage directory
     fn load_conversations(&mut self) -> Result<(), Box<dyn Error>> {
         for entry in fs::read_dir(&self.storage_dir)? {
@@ -240,26 +651,18 @@ impl ConversationManager {
             self.new_conversation(provider_name)?;
         }
         
-        let active_id = self.active_conversation_id.as_ref().unwrap().clone();
-        
-        // Find the active conversation
-        for conversation in &mut self.conversations {
-            if conversation.id() == active_id {
-                return Ok(conversation);
-            }
-        }
-        
         // If not found (should not happen), create a new one
-        self.new_conversation(provider_name)?;
         let active_id = self.active_conversation_id.as_ref().unwrap().clone();
-        
-        for conversation in &mut self.conversations {
-            if conversation.id() == active_id {
-                return Ok(conversation);
-            }
+        if !self.conversations.iter().any(|conversation| conversation.id() == active_id) {
+            self.new_conversation(provider_name)?;
         }
         
-        Err("Could not find or create conversation".into())
+        // Find the active conversation
+        let active_id = self.active_conversation_id.as_ref().unwrap().clone();
+        self.conversations
+            .iter_mut()
+            .find(|conversation| conversation.id() == active_id)
+            .ok_or_else(|| "Could not find or create conversation".into())
     }
     
     /// Set the active conversation by ID
@@ -275,12 +678,68 @@ impl ConversationManager {
         }
     }
     
-    /// List all available conversations
-    pub fn list_conversations(&self) -> Vec<(String, u64)> {
-        self.conversations
+    /// List all available conversations, most recently used first
+    pub fn list_conversations(&self) -> Vec<ConversationSummary> {
+        let mut summaries: Vec<ConversationSummary> = self
+            .conversations
+            .iter()
+            .map(|c| ConversationSummary {
+                id: c.id().to_string(),
+                title: c.title().to_string(),
+                last_used: c.last_used,
+                message_count: c.messages.len(),
+            })
+            .collect();
+        summaries.sort_by(|a, b| b.last_used.cmp(&a.last_used));
+        summaries
+    }
+    
+    /// Give a conversation a title of the user's choosing
+    pub fn rename_conversation(&mut self, id: &str, title: &str) -> Result<(), Box<dyn Error>> {
+        let title = title.trim();
+        if title.is_empty() {
+            return Err("Title cannot be empty".into());
+        }
+        
+        let conversation = self
+            .conversations
+            .iter_mut()
+            .find(|c| c.id() == id)
+            .ok_or_else(|| format!("Conversation with ID {} not found", id))?;
+        conversation.title = Some(title.to_string());
+        conversation.save()
+    }
+    
+    /// Messages across all stored conversations matching `query`, best first, then newest
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let mut hits: Vec<SearchHit> = self
+            .conversations
             .iter()
-            .map(|c| (c.id().to_string(), c.last_used))
-            .collect()
+            .flat_map(|conversation| {
+                conversation.messages.iter().filter_map(move |message| {
+                    let (score, snippet) = match_message(query, &message.content)?;
+                    Some(SearchHit {
+                        conversation_id: conversation.id().to_string(),
+                        role: message.role.clone(),
+                        timestamp: message.timestamp,
+                        snippet,
+                        score,
+                    })
+                })
+            })
+            .collect();
+        hits.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| b.timestamp.cmp(&a.timestamp))
+        });
+        hits
     }
     
     /// Save the active conversation
@@ -300,7 +759,9 @@ impl ConversationManager {
     /// Send a message to the active conversation and get response
     pub async fn send_message(&mut self, 
                              message: &str, 
-                             ai_provider: &dyn AiProvider) -> Result<String, Box<dyn Error>> {
+                             ai_provider: &Arc<dyn AiProvider>) -> Result<String, Box<dyn Error>> {
+        self.apply_finished_summary().await;
+        
         // Get active conversation
         let conversation = match self.active_conversation_id {
             Some(ref id) => {
@@ -317,21 +778,55 @@ impl ConversationManager {
         // Add user message
         conversation.add_user_message(message);
         
-        // Build prompt with conversation history
-        let prompt = conversation.build_prompt();
-        
-        // Query AI provider
-        let response = ai_provider.query(&prompt).await?;
+        // Send the history in the provider's own chat format
+        let budget = self.history_budget;
+        let messages = conversation.build_messages(budget.tokens);
+        let response = scheduler().run(Priority::Interactive, ai_provider.chat(&messages)).await?;
         
         // Add assistant response
         conversation.add_assistant_message(&response);
+        conversation.title_from_first_exchange();
         
         // Save conversation
         conversation.save()?;
         
+        // Fold what no longer fits into the summary while the reply is read; the next
+        // request uses it if it's ready, and never waits for it
+        if budget.summarize && self.pending_summary.is_none() {
+            let end = conversation.history_start(budget.tokens.saturating_sub(SUMMARY_TOKENS));
+            if end > conversation.summarized {
+                let summary_prompt = conversation.summary_prompt(end);
+                let provider = Arc::clone(ai_provider);
+                self.pending_summary = Some(PendingSummary {
+                    conversation_id: conversation.id().to_string(),
+                    end,
+                    dropped: conversation.dropped,
+                    task: tokio::spawn(async move {
+                        scheduler().run(Priority::Background, provider.query(&summary_prompt)).await.ok()
+                    }),
+                });
+            }
+        }
+        
         Ok(response)
     }
     
+    /// Take in the background summary if it has finished, keeping the old one if it failed
+    async fn apply_finished_summary(&mut self) {
+        if !self.pending_summary.as_ref().map_or(false, |pending| pending.task.is_finished()) {
+            return;
+        }
+        let Some(pending) = self.pending_summary.take() else { return };
+        
+        // Finished, so this doesn't wait
+        let Ok(Some(summary)) = pending.task.await else { return };
+        if let Some(conversation) = self.conversations.iter_mut().find(|c| c.id() == pending.conversation_id) {
+            conversation.summary = Some(summary.trim().to_string());
+            conversation.summarized = pending.end.saturating_sub(conversation.dropped - pending.dropped);
+            let _ = conversation.save();
+        }
+    }
+    
     /// Delete a conversation by ID
     pub fn delete_conversation(&mut self, id: &str) -> Result<(), Box<dyn Error>> {
         let pos = self.conversations.iter().position(|c| c.id() == id);
@@ -361,8 +856,9 @@ impl ConversationManager {
     
     /// Run an interactive chat session in the terminal
     pub async fn run_interactive_session(&mut self, 
-                                        ai_provider: &dyn AiProvider) -> Result<(), Box<dyn Error>> {
-        println!("RustFig AI Chat (type 'exit' to quit, 'clear' to start new conversation)");
+                                        ai_provider: &Arc<dyn AiProvider>) -> Result<(), Box<dyn Error>> {
+        println!("RustFig AI Chat (type 'exit' to quit, 'clear' to start new conversation, '/search TEXT' to search past chats)");
+        println!("Commands in answers can be put on your command line with '/insert' or run with '/run'");
         
         // Create a new conversation if none exists
         if self.active_conversation_id.is_none() {
@@ -370,11 +866,9 @@ impl ConversationManager {
         }
         
         // Get active conversation
-        let conversation_id = self.active_conversation_id.clone().unwrap();
-        println!("Chat ID: {}", conversation_id);
-        
         // Print existing conversation
         let conversation = self.get_active_conversation(ai_provider.name())?;
+        println!("{} ({})", conversation.title(), conversation.id());
         for msg in conversation.get_messages() {
             let prefix = match msg.role.as_str() {
                 "user" => "You: ",
@@ -388,6 +882,9 @@ impl ConversationManager {
         let mut reader = stdin.lock();
         let mut input = String::new();
         
+        // Commands in the last response, for /insert and /run
+        let mut commands: Vec<String> = Vec::new();
+        
         loop {
             // Print prompt
             print!("> ");
@@ -415,10 +912,64 @@ impl ConversationManager {
                 continue;
             }
             
+            // Chat commands are matched on the whole first word; anything else, like a
+            // question starting with a path, goes to the model
+            let (word, argument) = input.split_once(char::is_whitespace).unwrap_or((input, ""));
+            
+            // Search every saved conversation, not just this one
+            if word == "/search" {
+                print_search_hits(&self.search(argument));
+                continue;
+            }
+            
+            // Put a command from the last response on the shell's command line, which
+            // means leaving the chat so the shell can show it
+            if word == "/insert" {
+                match pick_command(&commands, argument) {
+                    Ok(command) => {
+                        println!("$ {}", command);
+                        crate::shell::queue_for_command_line(command)?;
+                        println!("It will be on your command line when the prompt comes back");
+                        break;
+                    },
+                    Err(message) => println!("{}", message),
+                }
+                continue;
+            }
+            
+            // Run a command from the last response, once confirmed
+            if word == "/run" {
+                let command = match pick_command(&commands, argument) {
+                    Ok(command) => command.to_string(),
+                    Err(message) => {
+                        println!("{}", message);
+                        continue;
+                    }
+                };
+                
+                println!("$ {}", command);
+                print!("Run this command? [y/N] ");
+                io::stdout().flush()?;
+                let mut answer = String::new();
+                reader.read_line(&mut answer)?;
+                if !matches!(answer.trim(), "y" | "Y" | "yes") {
+                    println!("Not run");
+                    continue;
+                }
+                
+                let status = Command::new("sh").arg("-c").arg(&command).status()?;
+                if !status.success() {
+                    println!("Exited with {}", status);
+                }
+                continue;
+            }
+            
             // Send message and get response
             match self.send_message(input, ai_provider).await {
                 Ok(response) => {
                     println!("AI: {}", response);
+                    commands = extract_commands(&response);
+                    print_commands(&commands);
                 },
                 Err(e) => {
                     println!("Error: {}", e);
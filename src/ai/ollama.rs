@@ -3,6 +3,8 @@ use std::time::Duration;
 use serde::{Deserialize, Serialize};
 use reqwest::{Client, header};
 use tokio::time::timeout;
+use crate::ai::conversation::ConversationMessage;
+use crate::ai::retry::ProviderError;
 use crate::config::OllamaConfig;
 
 /// Ollama API integration for local LLM inference
@@ -34,6 +36,84 @@ struct OllamaResponse {
     done: bool,
 }
 
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMessage<'a>>,
+    stream: bool,
+    options: ChatOptions,
+}
+
+#[derive(Serialize)]
+struct ChatMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+/// Chat answers aren't capped like completions; the model's own limit applies
+#[derive(Serialize)]
+struct ChatOptions {
+    temperature: f32,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    message: ChatReply,
+}
+
+#[derive(Deserialize)]
+struct ChatReply {
+    #[serde(default)]
+    content: String,
+}
+
+/// A model already pulled into Ollama, as listed by `/api/tags`
+#[derive(Debug, Clone, Deserialize)]
+pub struct InstalledModel {
+    pub name: String,
+    /// Size on disk in bytes
+    #[serde(default)]
+    pub size: u64,
+}
+
+#[derive(Deserialize)]
+struct TagsResponse {
+    #[serde(default)]
+    models: Vec<InstalledModel>,
+}
+
+#[derive(Serialize)]
+struct PullRequest<'a> {
+    name: &'a str,
+    stream: bool,
+}
+
+#[derive(Deserialize)]
+struct PullResponse {
+    status: String,
+}
+
+/// Pulling downloads gigabytes, so it gets far longer than a query
+const PULL_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// Bytes of weights per parameter for the 4-bit quantizations Ollama pulls by default
+const BYTES_PER_PARAM: f64 = 0.6;
+
+/// Working memory (context cache, runtime) on top of the weights
+const RAM_OVERHEAD: f64 = 1.2;
+
+/// Share of the machine's memory a model may use and still leave room for everything else
+const MAX_RAM_SHARE: f64 = 0.6;
+
+/// Models to fall back to on smaller machines, largest first, with their parameter counts (billions)
+const FALLBACK_MODELS: &[(&str, f64)] = &[
+    ("llama3:8b", 8.0),
+    ("mistral:7b-instruct", 7.2),
+    ("codellama:7b-instruct", 7.0),
+    ("phi3:mini", 3.8),
+    ("qwen2.5-coder:1.5b", 1.5),
+];
+
 impl OllamaClient {
     pub fn new(config: &OllamaConfig) -> Result<Self, Box<dyn Error>> {
         let client = Client::builder()
@@ -56,6 +136,37 @@ impl OllamaClient {
         }
     }
     
+    /// Models already pulled
+    pub async fn list_models(&self) -> Result<Vec<InstalledModel>, Box<dyn Error>> {
+        let response = self.client.get(&format!("{}/api/tags", self.base_url)).send().await?;
+        if !response.status().is_success() {
+            return Err(format!("Ollama API error: {}", response.status()).into());
+        }
+        
+        let tags: TagsResponse = response.json().await?;
+        Ok(tags.models)
+    }
+    
+    /// Download a model, waiting until Ollama has it
+    pub async fn pull_model(&self, name: &str) -> Result<(), Box<dyn Error>> {
+        let response = self.client
+            .post(&format!("{}/api/pull", self.base_url))
+            .timeout(PULL_TIMEOUT)
+            .json(&PullRequest { name, stream: false })
+            .send()
+            .await?;
+        
+        if !response.status().is_success() {
+            return Err(format!("Ollama API error: {}", response.status()).into());
+        }
+        
+        let pulled: PullResponse = response.json().await?;
+        if pulled.status != "success" {
+            return Err(format!("Pulling {} ended with: {}", name, pulled.status).into());
+        }
+        Ok(())
+    }
+    
     /// Query Ollama model for command prediction or explanation
     pub async fn query(&self, prompt: &str) -> Result<String, Box<dyn Error>> {
         // Create the request
@@ -79,13 +190,41 @@ impl OllamaClient {
         ).await??;
         
         if !response.status().is_success() {
-            return Err(format!("Ollama API error: {}", response.status()).into());
+            return Err(ProviderError::from_response("Ollama API", &response).into());
         }
         
         let ollama_response: OllamaResponse = response.json().await?;
         Ok(ollama_response.response)
     }
     
+    /// Answer the last of `messages` through `/api/chat`, roles and all
+    pub async fn chat(&self, messages: &[ConversationMessage]) -> Result<String, Box<dyn Error>> {
+        let request = ChatRequest {
+            model: &self.model,
+            messages: messages
+                .iter()
+                .map(|message| ChatMessage { role: &message.role, content: &message.content })
+                .collect(),
+            stream: false,
+            options: ChatOptions { temperature: 0.1 },
+        };
+        
+        let response = timeout(
+            self.timeout_duration,
+            self.client
+                .post(&format!("{}/api/chat", self.base_url))
+                .json(&request)
+                .send()
+        ).await??;
+        
+        if !response.status().is_success() {
+            return Err(ProviderError::from_response("Ollama API", &response).into());
+        }
+        
+        let chat_response: ChatResponse = response.json().await?;
+        Ok(chat_response.message.content)
+    }
+    
     /// Generate command suggestions based on user input and context
     pub async fn suggest_command(
         &self, 
@@ -138,3 +277,56 @@ impl OllamaClient {
         self.query(&prompt).await
     }
 }
+
+/// Whether two model names refer to the same model (`llama3` is `llama3:latest`)
+pub fn same_model(a: &str, b: &str) -> bool {
+    let normalize = |name: &str| if name.contains(':') { name.to_string() } else { format!("{}:latest", name) };
+    normalize(a) == normalize(b)
+}
+
+/// Rough RAM needed to run a model: its weights plus working memory
+///
+/// Uses the size on disk when the model is pulled, otherwise the parameter count
+/// in its tag (`7b`, `1.5b`).
+pub fn estimate_ram_bytes(name: &str, size_on_disk: Option<u64>) -> Option<u64> {
+    let weights = match size_on_disk.filter(|size| *size > 0) {
+        Some(size) => size as f64,
+        None => parameter_count(name)? * BYTES_PER_PARAM,
+    };
+    Some((weights * RAM_OVERHEAD) as u64)
+}
+
+/// Whether a model needing `needed` bytes leaves enough of `total` for the rest of the system
+pub fn fits_in_memory(needed: u64, total: u64) -> bool {
+    needed as f64 <= total as f64 * MAX_RAM_SHARE
+}
+
+/// The largest fallback model that's smaller than `current` and fits in `total` bytes,
+/// preferring ones already pulled
+pub fn smaller_model(current: &str, installed: &[InstalledModel], total: u64) -> Option<&'static str> {
+    let current_params = parameter_count(current).unwrap_or(f64::MAX);
+    let candidates: Vec<&'static str> = FALLBACK_MODELS
+        .iter()
+        .filter(|(name, billions)| billions * 1e9 < current_params && !same_model(name, current))
+        .filter(|(name, _)| estimate_ram_bytes(name, None).map_or(false, |needed| fits_in_memory(needed, total)))
+        .map(|(name, _)| *name)
+        .collect();
+
+    candidates
+        .iter()
+        .find(|name| installed.iter().any(|model| same_model(&model.name, name)))
+        .or_else(|| candidates.first())
+        .copied()
+}
+
+/// Parameters from a known model or a tag like `codellama:7b-instruct`
+fn parameter_count(name: &str) -> Option<f64> {
+    if let Some((_, billions)) = FALLBACK_MODELS.iter().find(|(model, _)| same_model(model, name)) {
+        return Some(billions * 1e9);
+    }
+
+    let tag = name.split_once(':')?.1;
+    tag.split(|c| c == '-' || c == '_')
+        .find_map(|part| part.strip_suffix('b')?.parse::<f64>().ok())
+        .map(|billions| billions * 1e9)
+}
@@ -0,0 +1,249 @@
+//! Retries and rate limiting around any `AiProvider`
+//!
+//! Providers fail in passing all the time: a model still loading, a gateway timing out,
+//! a 429 while a burst drains. `RetryingProvider` retries those a bounded number of
+//! times with jittered exponential backoff, waits out a `Retry-After` when the provider
+//! sends one, and spaces requests with a token bucket shared by every provider of the
+//! same name in the process. Errors that won't go away on their own (a bad key, a
+//! missing model) are returned at once.
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::ai::conversation::ConversationMessage;
+use crate::ai::AiProvider;
+use crate::config::AiConfig;
+
+/// Delay before the first retry; it doubles with each one after
+const BASE_DELAY: Duration = Duration::from_millis(250);
+
+/// Longest a request waits, for a backoff, a `Retry-After` or the rate limiter, before
+/// it gives up; a prompt shouldn't hang for longer
+const MAX_DELAY: Duration = Duration::from_secs(8);
+
+/// Requests that can go out back to back before the rate limiter spaces them
+const BURST: f64 = 5.0;
+
+/// Statuses worth another try: timeouts, rate limiting, and an overloaded or
+/// restarting server
+const TRANSIENT_STATUSES: &[u16] = &[408, 425, 429, 500, 502, 503, 504];
+
+/// Rate limiters by provider name, so every instance of a provider shares one
+static LIMITERS: Lazy<Mutex<HashMap<String, Arc<RateLimiter>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// A provider answered with an HTTP error status
+#[derive(Debug)]
+pub struct ProviderError {
+    pub status: u16,
+    /// How long the provider asked us to wait, from `Retry-After`
+    pub retry_after: Option<Duration>,
+    message: String,
+}
+
+impl ProviderError {
+    /// The error for an unsuccessful `response`, labelled with the provider (`"API"`,
+    /// `"Ollama API"`)
+    pub fn from_response(label: &str, response: &reqwest::Response) -> Self {
+        // Only the delay-seconds form; an HTTP date is rare from these APIs
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<f64>().ok())
+            .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+            .map(Duration::from_secs_f64);
+
+        Self {
+            status: response.status().as_u16(),
+            retry_after,
+            message: format!("{} error: {}", label, response.status()),
+        }
+    }
+}
+
+impl fmt::Display for ProviderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for ProviderError {}
+
+/// Token bucket: `BURST` requests at once, refilled at the configured rate
+pub struct RateLimiter {
+    per_second: f64,
+    state: Mutex<Bucket>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    /// Nothing goes out before this, after a `Retry-After`
+    held_until: Option<Instant>,
+}
+
+impl RateLimiter {
+    /// A limiter for `requests_per_minute`, 0 for no limit
+    pub fn new(requests_per_minute: u32) -> Self {
+        Self {
+            per_second: requests_per_minute as f64 / 60.0,
+            state: Mutex::new(Bucket { tokens: BURST, updated: Instant::now(), held_until: None }),
+        }
+    }
+
+    /// The limiter shared by providers called `name`
+    pub fn shared(name: &str, requests_per_minute: u32) -> Arc<Self> {
+        LIMITERS
+            .lock()
+            .entry(name.to_string())
+            .or_insert_with(|| Arc::new(Self::new(requests_per_minute)))
+            .clone()
+    }
+
+    /// Take a slot, or say how long until one is free
+    fn try_acquire(&self) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut bucket = self.state.lock();
+
+        if let Some(until) = bucket.held_until {
+            if until > now {
+                return Err(until - now);
+            }
+            bucket.held_until = None;
+        }
+        if self.per_second <= 0.0 {
+            return Ok(());
+        }
+
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.per_second).min(BURST);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.per_second))
+    }
+
+    /// Wait for a slot, unless that would take longer than `MAX_DELAY`
+    pub async fn acquire(&self) -> Result<(), Box<dyn Error>> {
+        let started = Instant::now();
+        loop {
+            let wait = match self.try_acquire() {
+                Ok(()) => return Ok(()),
+                Err(wait) => wait,
+            };
+            if started.elapsed() + wait > MAX_DELAY {
+                return Err(format!("Rate limited; try again in {}s", wait.as_secs().max(1)).into());
+            }
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Send nothing for `delay`, as a `Retry-After` asks
+    pub fn hold_off(&self, delay: Duration) {
+        let until = Instant::now() + delay;
+        let mut bucket = self.state.lock();
+        if bucket.held_until.map_or(true, |held| held < until) {
+            bucket.held_until = Some(until);
+        }
+    }
+}
+
+/// Wraps a provider with retries and the shared rate limiter
+pub struct RetryingProvider {
+    inner: Box<dyn AiProvider>,
+    max_retries: u32,
+    limiter: Arc<RateLimiter>,
+}
+
+impl RetryingProvider {
+    pub fn new(inner: Box<dyn AiProvider>, config: &AiConfig) -> Self {
+        let limiter = RateLimiter::shared(inner.name(), config.requests_per_minute.unwrap_or(60));
+        Self { inner, max_retries: config.max_retries.unwrap_or(2), limiter }
+    }
+
+    /// How long to wait before retrying after `error` on attempt `attempt` (from 1), or
+    /// None if it isn't worth retrying
+    fn retry_delay(&self, error: &(dyn Error + 'static), attempt: u32) -> Option<Duration> {
+        if attempt > self.max_retries {
+            return None;
+        }
+
+        if let Some(error) = error.downcast_ref::<ProviderError>() {
+            if !TRANSIENT_STATUSES.contains(&error.status) {
+                return None;
+            }
+            if let Some(retry_after) = error.retry_after {
+                self.limiter.hold_off(retry_after);
+                return (retry_after <= MAX_DELAY).then_some(retry_after);
+            }
+        } else if let Some(error) = error.downcast_ref::<reqwest::Error>() {
+            if !(error.is_timeout() || error.is_connect()) {
+                return None;
+            }
+        } else if error.downcast_ref::<tokio::time::error::Elapsed>().is_none() {
+            return None;
+        }
+
+        Some(backoff(attempt))
+    }
+
+    /// Send `request` until it succeeds, fails for good, or runs out of retries
+    async fn with_retries<F, R>(&self, mut request: F) -> Result<String, Box<dyn Error>>
+    where
+        F: FnMut() -> R,
+        R: Future<Output = Result<String, Box<dyn Error>>>,
+    {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            self.limiter.acquire().await?;
+
+            let delay = match request().await {
+                Ok(response) => return Ok(response),
+                Err(error) => match self.retry_delay(error.as_ref(), attempt) {
+                    Some(delay) => delay,
+                    None => return Err(error),
+                },
+            };
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+/// Exponential backoff for retry `attempt`, with half of it random so clients that
+/// failed together don't retry together
+fn backoff(attempt: u32) -> Duration {
+    let delay = BASE_DELAY.saturating_mul(1 << (attempt - 1).min(16)).min(MAX_DELAY);
+    let random = RandomState::new().build_hasher().finish();
+    delay / 2 + delay.mul_f64((random % 1000) as f64 / 2000.0)
+}
+
+#[async_trait]
+impl AiProvider for RetryingProvider {
+    async fn query(&self, prompt: &str) -> Result<String, Box<dyn Error>> {
+        self.with_retries(|| self.inner.query(prompt)).await
+    }
+
+    async fn chat(&self, messages: &[ConversationMessage]) -> Result<String, Box<dyn Error>> {
+        self.with_retries(|| self.inner.chat(messages)).await
+    }
+
+    async fn is_available(&self) -> bool {
+        self.inner.is_available().await
+    }
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+}
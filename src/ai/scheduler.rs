@@ -0,0 +1,121 @@
+//! Priorities for AI requests
+//!
+//! Requests someone is waiting on at the prompt (completions, a command from a
+//! description, a chat answer) are `Interactive`; work nobody is watching (explanations
+//! prepared ahead, history summaries, cache warmers) is `Background`. Each class has its
+//! own concurrency cap, and a background request doesn't start while an interactive one
+//! is queued or running, so a burst of background work can't hold up a keystroke. A
+//! background request already sent isn't cancelled; it just finishes.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use once_cell::sync::OnceCell;
+use tokio::sync::{Notify, Semaphore, SemaphorePermit};
+
+use crate::config::AiConfig;
+
+static SCHEDULER: OnceCell<AiScheduler> = OnceCell::new();
+
+/// How soon a request needs its answer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Priority {
+    /// Someone is waiting on it
+    Interactive,
+    /// It can wait for the interactive requests
+    Background,
+}
+
+pub struct AiScheduler {
+    interactive: Semaphore,
+    background: Semaphore,
+    /// Interactive requests queued or running
+    interactive_pending: AtomicUsize,
+    /// Signalled when the last pending interactive request finishes
+    interactive_idle: Notify,
+}
+
+/// Held while a request runs; frees its slot when dropped
+pub struct SchedulerPermit<'a> {
+    _permit: SemaphorePermit<'a>,
+}
+
+/// Counts an interactive request as pending until dropped, even if it's cancelled
+/// while queued
+struct PendingInteractive<'a>(&'a AiScheduler);
+
+impl Drop for PendingInteractive<'_> {
+    fn drop(&mut self) {
+        if self.0.interactive_pending.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.0.interactive_idle.notify_waiters();
+        }
+    }
+}
+
+/// Set the concurrency caps from the config; the first call wins
+pub fn configure(config: &AiConfig) {
+    let _ = SCHEDULER.set(AiScheduler::new(
+        config.interactive_concurrency.unwrap_or(2),
+        config.background_concurrency.unwrap_or(1),
+    ));
+}
+
+/// The process's scheduler, with the default caps if it wasn't configured
+pub fn scheduler() -> &'static AiScheduler {
+    SCHEDULER.get_or_init(|| AiScheduler::new(2, 1))
+}
+
+impl AiScheduler {
+    pub fn new(interactive: usize, background: usize) -> Self {
+        Self {
+            interactive: Semaphore::new(interactive.max(1)),
+            background: Semaphore::new(background.max(1)),
+            interactive_pending: AtomicUsize::new(0),
+            interactive_idle: Notify::new(),
+        }
+    }
+
+    /// Run `request` once a slot for its priority is free
+    pub async fn run<F: Future>(&self, priority: Priority, request: F) -> F::Output {
+        match priority {
+            Priority::Interactive => {
+                self.interactive_pending.fetch_add(1, Ordering::AcqRel);
+                let _pending = PendingInteractive(self);
+                let _permit = self.acquire(&self.interactive).await;
+                request.await
+            }
+            Priority::Background => {
+                let _permit = self.acquire_background().await;
+                request.await
+            }
+        }
+    }
+
+    async fn acquire<'a>(&'a self, semaphore: &'a Semaphore) -> SchedulerPermit<'a> {
+        // The semaphores are never closed
+        let permit = semaphore.acquire().await.expect("scheduler semaphore closed");
+        SchedulerPermit { _permit: permit }
+    }
+
+    /// A background slot, taken only while no interactive request is pending
+    async fn acquire_background(&self) -> SchedulerPermit<'_> {
+        loop {
+            self.interactive_idle_wait().await;
+            let permit = self.acquire(&self.background).await;
+            // An interactive request may have arrived while this one queued
+            if self.interactive_pending.load(Ordering::Acquire) == 0 {
+                return permit;
+            }
+        }
+    }
+
+    async fn interactive_idle_wait(&self) {
+        loop {
+            // Created before the check, so a notification between the two isn't lost
+            let idle = self.interactive_idle.notified();
+            if self.interactive_pending.load(Ordering::Acquire) == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+}
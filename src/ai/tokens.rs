@@ -0,0 +1,49 @@
+//! Token counts without the model's tokenizer
+//!
+//! Prompts are sized before they're sent, for models whose tokenizers differ and aren't
+//! available offline, so counts are estimated the way BPE vocabularies tend to split
+//! text: common words are a token per few letters, numbers a token per few digits, and
+//! punctuation and non-Latin characters mostly a token each. The estimate errs high for
+//! English prose and is close for code and shell commands.
+
+/// Letters a token covers in a run of them
+const LETTERS_PER_TOKEN: usize = 4;
+
+/// Digits a token covers in a run of them
+const DIGITS_PER_TOKEN: usize = 3;
+
+/// Tokens a chat message costs beyond its content, for the role label and separators
+pub const MESSAGE_OVERHEAD: usize = 4;
+
+/// Estimated tokens in `text`
+pub fn estimate_tokens(text: &str) -> usize {
+    let mut tokens = 0;
+    let mut letters: usize = 0;
+    let mut digits: usize = 0;
+
+    for c in text.chars() {
+        if c.is_ascii_alphabetic() {
+            letters += 1;
+            tokens += digits.div_ceil(DIGITS_PER_TOKEN);
+            digits = 0;
+            continue;
+        }
+        if c.is_ascii_digit() {
+            digits += 1;
+            tokens += letters.div_ceil(LETTERS_PER_TOKEN);
+            letters = 0;
+            continue;
+        }
+
+        tokens += letters.div_ceil(LETTERS_PER_TOKEN) + digits.div_ceil(DIGITS_PER_TOKEN);
+        letters = 0;
+        digits = 0;
+
+        // A space usually belongs to the word after it
+        if c != ' ' {
+            tokens += 1;
+        }
+    }
+
+    tokens + letters.div_ceil(LETTERS_PER_TOKEN) + digits.div_ceil(DIGITS_PER_TOKEN)
+}
@@ -0,0 +1,141 @@
+// The `rustfig` subcommands: argument parsing and dispatch to the `cmd_*` handlers
+pub mod args;
+pub mod commands;
+
+pub use self::args::{Cli, CliCommand, ServiceAction};
+
+use std::error::Error;
+use std::future::Future;
+
+use self::args::*;
+use self::commands::*;
+
+/// Run a subcommand
+///
+/// `service run` is handled by `main` before this, since it may fork.
+pub fn run(command: CliCommand) -> Result<(), Box<dyn Error>> {
+    match command {
+        CliCommand::Version => {
+            println!("rustfig {}", env!("CARGO_PKG_VERSION"));
+            Ok(())
+        }
+        CliCommand::Setup { minimal, verbose } => cmd_setup(minimal, verbose),
+        CliCommand::Init { shell } => {
+            print!("{}", cmd_init(&shell)?);
+            Ok(())
+        }
+        CliCommand::Install { shell, force } => cmd_install(shell.as_deref(), force),
+        CliCommand::Uninstall { shell } => cmd_uninstall(shell.as_deref()),
+        CliCommand::Doctor { fix, verbose } => block_on(cmd_doctor(fix, verbose)),
+        CliCommand::Service { action } => match action {
+            ServiceAction::Start { verbose } => cmd_service_start(verbose),
+            ServiceAction::Run { daemon } => cmd_service_run(daemon),
+            ServiceAction::Stop { force } => cmd_service_stop(force),
+            ServiceAction::Restart => {
+                cmd_service_stop(false)?;
+                cmd_service_start(false)
+            }
+            ServiceAction::Ping => cmd_service_ping(),
+            ServiceAction::Info => cmd_service_info(),
+            ServiceAction::Status { verbose } => cmd_service_status(verbose),
+            ServiceAction::Signal { signal } => cmd_service_signal(&signal),
+            ServiceAction::Logs { follow, since, lines } => cmd_service_logs(follow, since.as_deref(), lines),
+        },
+        CliCommand::Ssh { args } => cmd_ssh(&args),
+        CliCommand::Perf { action } => match action {
+            PerfAction::Show { json, prometheus } => {
+                cmd_perf_show(if json { "json" } else if prometheus { "prometheus" } else { "text" })
+            }
+            PerfAction::Memory { json } => cmd_perf_memory(if json { "json" } else { "text" }),
+        },
+        CliCommand::Bench { iterations, json } => block_on(cmd_bench(iterations, if json { "json" } else { "text" })),
+        CliCommand::Config { action } => match action {
+            ConfigAction::Get { setting, format } => cmd_config_get(&setting, &format),
+            ConfigAction::Set { setting, value, append } => cmd_config_set(&setting, &value, append),
+            ConfigAction::List { format, show_origin } => cmd_config_list(&format, show_origin),
+            ConfigAction::Edit { editor } => cmd_config_edit(editor.as_deref()),
+            ConfigAction::Validate { path } => cmd_config_validate(path.as_deref()),
+            ConfigAction::Schema { output } => cmd_config_schema(output.as_deref()),
+        },
+        CliCommand::Profile { action } => match action {
+            ProfileAction::List => cmd_profile_list(),
+            ProfileAction::Switch { name } => cmd_profile_switch(&name),
+        },
+        CliCommand::Project { action } => match action {
+            ProjectAction::Trust { revoke } => cmd_project_trust(revoke),
+        },
+        CliCommand::Snippets => cmd_snippets(),
+        CliCommand::Run { workflow, dry_run, yes, params } => {
+            // Flags after the first parameter land in `params`
+            let dry_run = dry_run || params.iter().any(|param| param == "--dry-run");
+            let yes = yes || params.iter().any(|param| param == "--yes" || param == "-y");
+            let params: Vec<String> = params
+                .into_iter()
+                .filter(|param| !matches!(param.as_str(), "--dry-run" | "--yes" | "-y"))
+                .collect();
+            cmd_run(&workflow, &params, dry_run, yes)
+        }
+        CliCommand::RecordCommand { command, dir, shell, exit_code, duration_ms } => {
+            cmd_record_command(&command, dir.as_deref(), shell.as_deref(), exit_code, duration_ms)
+        }
+        CliCommand::History { limit, search, action } => match action {
+            Some(HistoryAction::Search { query, shell }) => cmd_history_search(&query, shell.as_deref()),
+            None => cmd_history(limit, search.as_deref()),
+        },
+        CliCommand::Ghost { line } => cmd_ghost(&line),
+        CliCommand::Predict { line, pos, format } => block_on(cmd_predict(&line, pos, &format)),
+        CliCommand::RecordDir { dir } => cmd_record_dir(&dir),
+        CliCommand::Whichpkg { command, refresh, cached } => cmd_whichpkg(&command, refresh, cached),
+        CliCommand::Z { terms, list } => cmd_z(&terms, list),
+        CliCommand::Bookmark { action } => match action {
+            BookmarkAction::Add { command, description, tags } => cmd_bookmark_add(&command, description.as_deref(), &tags),
+            BookmarkAction::List { tag, format } => cmd_bookmark_list(tag.as_deref(), &format),
+            BookmarkAction::Remove { target } => cmd_bookmark_remove(&target),
+        },
+        CliCommand::Stats { reset, format } => cmd_stats(reset, &format),
+        CliCommand::Feedback { kind } => block_on(cmd_feedback(kind.as_deref())),
+        CliCommand::CrashReport { action } => match action {
+            CrashReportAction::List => cmd_crash_report_list(),
+            CrashReportAction::Submit { path, yes } => block_on(cmd_crash_report_submit(path.as_deref(), yes)),
+        },
+        CliCommand::Telemetry { action } => match action {
+            TelemetryAction::Status => cmd_telemetry_status(),
+            TelemetryAction::Enable => cmd_telemetry_set(true),
+            TelemetryAction::Disable => cmd_telemetry_set(false),
+            TelemetryAction::Purge => cmd_telemetry_purge(),
+        },
+        CliCommand::Offline { state } => cmd_offline(state.as_deref()),
+        CliCommand::PromptWidget { format, starship } => cmd_prompt_widget(&format, starship),
+        CliCommand::Spec { action } => match action {
+            SpecAction::Convert { paths, output, force } => cmd_spec_convert(&paths, output.as_deref(), force),
+            SpecAction::Lint { file } => cmd_spec_lint(&file),
+            SpecAction::Test { file, input } => cmd_spec_test(&file, &input),
+        },
+        CliCommand::GenerateSpec { command, deep, output, force } => cmd_generate_spec(&command, output.as_deref(), deep, force),
+        CliCommand::Ask { question, model, markdown } => block_on(cmd_ask(&question, model.as_deref(), markdown)),
+        CliCommand::Generate { description, shell } => block_on(cmd_generate(&description, shell.as_deref())),
+        CliCommand::Chat { model, conversation, list, action } => match action {
+            Some(ChatAction::List) => cmd_chat_list(),
+            None if list => cmd_chat_list(),
+            Some(ChatAction::Pick { model }) => block_on(cmd_chat_pick(model.as_deref())),
+            Some(ChatAction::Continue { id, model }) => block_on(cmd_chat(model.as_deref(), Some(&id))),
+            Some(ChatAction::Search { query, limit }) => cmd_chat_search(&query, limit),
+            None => block_on(cmd_chat(model.as_deref(), conversation.as_deref())),
+        },
+    }
+}
+
+/// Run an async command to completion on a runtime of its own
+///
+/// Two workers are quicker to start than one per core, and still run what a command
+/// leaves in the background (like chat history summaries) while it blocks on input.
+fn block_on<F>(command: F) -> Result<(), Box<dyn Error>>
+where
+    F: Future<Output = Result<(), Box<dyn Error>>>,
+{
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(2)
+        .enable_all()
+        .build()?
+        .block_on(command)
+}
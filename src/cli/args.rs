@@ -0,0 +1,490 @@
+//! Command-line arguments, one subcommand per group in cli_command_structure.MD.
+
+use clap::{Parser, Subcommand};
+
+/// Ultra-fast, context-aware terminal assistant
+#[derive(Debug, Parser)]
+#[command(name = "rustfig", version, about)]
+pub struct Cli {
+    /// Print how long each startup phase takes, then exit
+    #[arg(long)]
+    pub profile_startup: bool,
+
+    /// Without a subcommand, RustFig runs in the terminal
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CliCommand {
+    /// Display version information
+    Version,
+
+    /// Run initial setup
+    Setup {
+        #[arg(long)]
+        minimal: bool,
+        #[arg(long, short)]
+        verbose: bool,
+    },
+
+    /// Print the shell integration code
+    Init {
+        #[arg(value_parser = ["bash", "zsh", "fish"])]
+        shell: String,
+    },
+
+    /// Install the integration into the shell's configuration
+    Install {
+        #[arg(long, value_parser = ["bash", "zsh", "fish"])]
+        shell: Option<String>,
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Remove the integration from the shell's configuration
+    Uninstall {
+        #[arg(long, value_parser = ["bash", "zsh", "fish"])]
+        shell: Option<String>,
+    },
+
+    /// Diagnose the installation, AI models and conflicting shell plugins
+    Doctor {
+        #[arg(long)]
+        fix: bool,
+        #[arg(long, short)]
+        verbose: bool,
+    },
+
+    /// Manage the background prediction service
+    Service {
+        #[command(subcommand)]
+        action: ServiceAction,
+    },
+
+    /// Connect with ssh, forwarding a predict-only service socket
+    Ssh {
+        /// The destination and any ssh arguments, passed to ssh unchanged
+        #[arg(required = true, trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+
+    /// Show the service's latencies or memory use
+    Perf {
+        #[command(subcommand)]
+        action: PerfAction,
+    },
+
+    /// Replay a fixed corpus through both engines and report latency percentiles
+    Bench {
+        #[arg(long, default_value_t = 100)]
+        iterations: usize,
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Read and change the configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// List or switch configuration profiles
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+
+    /// Trust the current project's .rustfig.yaml
+    Project {
+        #[command(subcommand)]
+        action: ProjectAction,
+    },
+
+    /// Browse, search, add, edit and delete snippets
+    Snippets,
+
+    /// Run a workflow, prompting for parameters not given as `--NAME VALUE`
+    Run {
+        workflow: String,
+        #[arg(long)]
+        dry_run: bool,
+        #[arg(long, short)]
+        yes: bool,
+        /// Workflow parameters
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        params: Vec<String>,
+    },
+
+    /// Record a finished command (used by the shell hooks)
+    RecordCommand {
+        command: String,
+        #[arg(long)]
+        dir: Option<String>,
+        #[arg(long, value_parser = ["bash", "zsh", "fish"])]
+        shell: Option<String>,
+        #[arg(long, allow_hyphen_values = true)]
+        exit_code: Option<i32>,
+        #[arg(long)]
+        duration_ms: Option<u64>,
+    },
+
+    /// Show command history, or search it full-screen
+    History {
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+        #[arg(long)]
+        search: Option<String>,
+        #[command(subcommand)]
+        action: Option<HistoryAction>,
+    },
+
+    /// Print what the best prediction adds to the end of LINE (used by the zsh integration)
+    Ghost {
+        #[arg(long, allow_hyphen_values = true)]
+        line: String,
+    },
+
+    /// Predict a command line (used by the Tab bindings)
+    Predict {
+        #[arg(long, allow_hyphen_values = true)]
+        line: String,
+        /// Byte offset of the cursor in LINE; the end when not given
+        #[arg(long)]
+        pos: Option<usize>,
+        #[arg(long, default_value = "text", value_parser = ["completion", "text"])]
+        format: String,
+    },
+
+    /// Record a directory visit (used by the shell cd hooks)
+    RecordDir { dir: String },
+
+    /// List packages that provide a missing command
+    Whichpkg {
+        command: String,
+        #[arg(long, conflicts_with = "cached")]
+        refresh: bool,
+        #[arg(long)]
+        cached: bool,
+    },
+
+    /// Print the best-matching frecent directory
+    Z {
+        #[arg(required_unless_present = "list")]
+        terms: Vec<String>,
+        #[arg(long)]
+        list: bool,
+    },
+
+    /// Manage bookmarked commands
+    Bookmark {
+        #[command(subcommand)]
+        action: BookmarkAction,
+    },
+
+    /// Show top commands, acceptance rates and the slowest suggestion sources
+    Stats {
+        #[arg(long)]
+        reset: bool,
+        #[arg(long, default_value = "text", value_parser = ["yaml", "json", "text"])]
+        format: String,
+    },
+
+    /// Submit feedback or a bug report
+    Feedback {
+        #[arg(long = "type", value_parser = ["bug", "suggestion", "feedback"])]
+        kind: Option<String>,
+    },
+
+    /// List crash reports, or send one
+    CrashReport {
+        #[command(subcommand)]
+        action: CrashReportAction,
+    },
+
+    /// Show, turn on or off, or purge usage telemetry
+    Telemetry {
+        #[command(subcommand)]
+        action: TelemetryAction,
+    },
+
+    /// Show or switch offline mode
+    Offline {
+        #[arg(value_parser = ["on", "off"])]
+        state: Option<String>,
+    },
+
+    /// Print one glyph for RustFig's state, for the prompt
+    PromptWidget {
+        #[arg(long, default_value = "glyph", value_parser = ["glyph", "text"])]
+        format: String,
+        #[arg(long)]
+        starship: bool,
+    },
+
+    /// Convert, check and try out completion specs
+    Spec {
+        #[command(subcommand)]
+        action: SpecAction,
+    },
+
+    /// Write a skeleton spec for a command from its --help output and man page
+    GenerateSpec {
+        command: String,
+        #[arg(long)]
+        deep: bool,
+        #[arg(long, allow_hyphen_values = true)]
+        output: Option<String>,
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Ask a question
+    Ask {
+        question: String,
+        #[arg(long)]
+        model: Option<String>,
+        #[arg(long)]
+        markdown: bool,
+    },
+
+    /// Generate a command from a description
+    Generate {
+        description: String,
+        #[arg(long, value_parser = ["bash", "zsh", "fish"])]
+        shell: Option<String>,
+    },
+
+    /// Start or continue an interactive chat session
+    Chat {
+        #[arg(long)]
+        model: Option<String>,
+        #[arg(long)]
+        conversation: Option<String>,
+        /// List saved conversations, like `chat list`
+        #[arg(long)]
+        list: bool,
+        #[command(subcommand)]
+        action: Option<ChatAction>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ServiceAction {
+    /// Start the service in the background
+    Start {
+        #[arg(long, short)]
+        verbose: bool,
+    },
+    /// Run the service in the foreground, or detached with --daemon
+    Run {
+        #[arg(long)]
+        daemon: bool,
+    },
+    /// Stop the service
+    Stop {
+        #[arg(long)]
+        force: bool,
+    },
+    /// Stop the service, then start it again
+    Restart,
+    /// Check that the service answers
+    Ping,
+    /// Show the service's pid, uptime, attached shells and supervised tasks
+    Info,
+    /// Check whether the service is running
+    Status {
+        #[arg(long, short)]
+        verbose: bool,
+    },
+    /// Send a control request to the service
+    #[command(hide = true)]
+    Signal {
+        #[arg(value_parser = ["stop"])]
+        signal: String,
+    },
+    /// View the service logs
+    Logs {
+        #[arg(long, short)]
+        follow: bool,
+        /// Like 30s, 15m, 1h or 2d
+        #[arg(long)]
+        since: Option<String>,
+        #[arg(long, default_value_t = 50)]
+        lines: usize,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PerfAction {
+    /// Per-component operation counts and latencies
+    Show {
+        #[arg(long, conflicts_with = "prometheus")]
+        json: bool,
+        #[arg(long)]
+        prometheus: bool,
+    },
+    /// Memory held by caches and learning data against the budget
+    Memory {
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigAction {
+    /// Print a configuration value
+    Get {
+        setting: String,
+        #[arg(long, default_value = "text", value_parser = ["yaml", "json", "text"])]
+        format: String,
+    },
+    /// Set a configuration value
+    Set {
+        setting: String,
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+        /// Add VALUE to a list setting
+        #[arg(long)]
+        append: bool,
+    },
+    /// List every configuration option
+    List {
+        #[arg(long, default_value = "text", value_parser = ["yaml", "json", "text"])]
+        format: String,
+        #[arg(long)]
+        show_origin: bool,
+    },
+    /// Edit config.yaml, validating it before it's replaced
+    Edit {
+        #[arg(long)]
+        editor: Option<String>,
+    },
+    /// Validate the configuration files
+    Validate {
+        #[arg(long)]
+        path: Option<String>,
+    },
+    /// Print or write the JSON Schema of config.yaml
+    Schema {
+        #[arg(long)]
+        output: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ProfileAction {
+    /// List profiles, marking the active one
+    List,
+    /// Switch profile (`default` for plain config.yaml)
+    Switch { name: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ProjectAction {
+    /// Trust the current project's .rustfig.yaml
+    Trust {
+        #[arg(long)]
+        revoke: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum HistoryAction {
+    /// Full-screen fuzzy history search
+    Search {
+        #[arg(long, default_value = "")]
+        query: String,
+        #[arg(long, value_parser = ["bash", "zsh", "fish"])]
+        shell: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum BookmarkAction {
+    /// Bookmark a command
+    Add {
+        command: String,
+        #[arg(long)]
+        description: Option<String>,
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+    },
+    /// List bookmarks
+    List {
+        #[arg(long)]
+        tag: Option<String>,
+        #[arg(long, default_value = "text", value_parser = ["yaml", "json", "text"])]
+        format: String,
+    },
+    /// Remove a bookmark by number or exact command
+    Remove { target: String },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CrashReportAction {
+    /// List the reports not sent yet
+    List,
+    /// Show a report (the newest by default) and send it once confirmed
+    Submit {
+        path: Option<String>,
+        #[arg(long, short)]
+        yes: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TelemetryAction {
+    /// Show whether telemetry is on and the data stored locally
+    Status,
+    /// Turn usage uploads on
+    Enable,
+    /// Turn usage uploads off
+    Disable,
+    /// Delete stored usage data, the anonymous user id and saved feedback
+    Purge,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SpecAction {
+    /// Convert Fig completion specs into native specs
+    Convert {
+        #[arg(required = true)]
+        paths: Vec<String>,
+        #[arg(long, allow_hyphen_values = true)]
+        output: Option<String>,
+        #[arg(long)]
+        force: bool,
+    },
+    /// Check a spec file
+    Lint { file: String },
+    /// Show the suggestions a spec file gives for an input
+    Test {
+        file: String,
+        #[arg(long, allow_hyphen_values = true)]
+        input: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ChatAction {
+    /// List saved conversations
+    List,
+    /// Pick a conversation to continue from an interactive list
+    Pick {
+        #[arg(long)]
+        model: Option<String>,
+    },
+    /// Continue a saved conversation
+    Continue {
+        id: String,
+        #[arg(long)]
+        model: Option<String>,
+    },
+    /// Search messages across saved conversations
+    Search {
+        query: String,
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+}
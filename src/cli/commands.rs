@@ -1,4 +1,6 @@
+use std::collections::HashMap;
 use std::error::Error;
+use std::io::{self, BufRead, IsTerminal, Write};
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
 use std::fs;
@@ -25,6 +27,9 @@ pub fn cmd_setup(minimal: bool, verbose: bool) -> Result<(), Box<dyn Error>> {
     // Install shell integration
     cmd_install(Some(shell.as_str()), false)?;
     
+    // Telemetry stays off unless the user says otherwise, and is asked only once
+    ask_telemetry_consent()?;
+    
     if !minimal {
         // Start service
         cmd_service_start(verbose)?;
@@ -37,29 +42,11 @@ pub fn cmd_setup(minimal: bool, verbose: bool) -> Result<(), Box<dyn Error>> {
 }
 
 /// Generate shell integration code
-pub fn cmd_init(shell: &str, minimal: bool) -> Result<String, Box<dyn Error>> {
+pub fn cmd_init(shell: &str) -> Result<String, Box<dyn Error>> {
     let integration_code = match shell {
-        "bash" => {
-            if minimal {
-                include_str!("../../resources/shell/bash/minimal.sh").to_string()
-            } else {
-                include_str!("../../resources/shell/bash/full.sh").to_string()
-            }
-        },
-        "zsh" => {
-            if minimal {
-                include_str!("../../resources/shell/zsh/minimal.zsh").to_string()
-            } else {
-                include_str!("../../resources/shell/zsh/full.zsh").to_string()
-            }
-        },
-        "fish" => {
-            if minimal {
-                include_str!("../../resources/shell/fish/minimal.fish").to_string()
-            } else {
-                include_str!("../../resources/shell/fish/full.fish").to_string()
-            }
-        },
+        "bash" => include_str!("../../resources/shell/bash/core.sh").to_string(),
+        "zsh" => include_str!("../../resources/shell/zsh/core.zsh").to_string(),
+        "fish" => include_str!("../../resources/shell/fish/core.fish").to_string(),
         _ => return Err(format!("Unsupported shell: {}", shell).into()),
     };
     
@@ -76,7 +63,7 @@ pub fn cmd_install(shell_override: Option<&str>, force: bool) -> Result<(), Box<
     };
     
     // Generate integration code
-    let integration_code = cmd_init(&shell, false)?;
+    let integration_code = cmd_init(&shell)?;
     
     // Determine the appropriate RC file
     let rc_file = match shell.as_str() {
@@ -208,7 +195,7 @@ pub fn cmd_uninstall(shell_override: Option<&str>) -> Result<(), Box<dyn Error>>
 }
 
 /// Run system checks
-pub fn cmd_doctor(fix: bool, verbose: bool) -> Result<(), Box<dyn Error>> {
+pub async fn cmd_doctor(fix: bool, verbose: bool) -> Result<(), Box<dyn Error>> {
     println!("Running RustFig diagnostics...");
     
     let mut issues_found = false;
@@ -239,7 +226,18 @@ pub fn cmd_doctor(fix: bool, verbose: bool) -> Result<(), Box<dyn Error>> {
                 println!("  [✗] Configuration validation failed: {}", e);
                 issues_found = true;
                 
-                if fix {
+                // Upgrading an old config version keeps the user's customizations
+                let migrated = fix && fs::read_to_string(&config_file)
+                    .ok()
+                    .and_then(|content| serde_yaml::from_str::<serde_yaml::Value>(&content).ok())
+                    .map_or(false, |mut value| {
+                        config::migration::migrate_file(&config_file, &mut value).is_ok()
+                            && config::validator::validate_config(&config_file).is_ok()
+                    });
+                
+                if migrated {
+                    println!("    Upgraded configuration to version {}", config::migration::CURRENT_VERSION);
+                } else if fix {
                     println!("    Creating backup and generating new configuration...");
                     let backup_path = config_file.with_extension("yaml.bak");
                     fs::copy(&config_file, &backup_path)?;
@@ -292,6 +290,31 @@ pub fn cmd_doctor(fix: bool, verbose: bool) -> Result<(), Box<dyn Error>> {
         }
     }
     
+    // Plugins that take the same keys or draw their own ghost text break rustfig silently
+    if let Some(home_dir) = dirs::home_dir() {
+        let startup_files = crate::shell::conflicts::startup_files(&shell, &home_dir);
+        let conflicts = crate::shell::conflicts::detect_conflicts(&startup_files);
+        if conflicts.is_empty() {
+            println!("  [✓] No conflicting shell plugins found");
+        }
+        
+        let mut ghost_text_conflict = false;
+        for (conflict, file) in &conflicts {
+            println!("  [!] {} is loaded in {}", conflict.name, file.display());
+            println!("    Problem: {}", conflict.problem);
+            println!("    Fix: {}", conflict.remediation);
+            ghost_text_conflict |= conflict.ghost_text;
+            issues_found = true;
+        }
+        
+        let ghost_text_enabled = config::loader::load_config()
+            .map_or(true, |config| config.general.enable_ghost_text.unwrap_or(true));
+        if fix && ghost_text_conflict && ghost_text_enabled {
+            println!("    Disabling RustFig ghost text to avoid drawing it twice...");
+            cmd_config_set("general.enable_ghost_text", "false", false)?;
+        }
+    }
+    
     // Check service
     println!("\nChecking RustFig service:");
     match cmd_service_status_internal() {
@@ -314,6 +337,13 @@ pub fn cmd_doctor(fix: bool, verbose: bool) -> Result<(), Box<dyn Error>> {
         }
     }
     
+    // Network access, which AI providers and uploads need
+    println!("\nChecking network:");
+    match crate::utils::offline::status() {
+        crate::utils::offline::Status::Online => println!("  [✓] Online"),
+        status => println!("  [!] RustFig is {}; only AI providers on this machine are used", status.description()),
+    }
+    
     // Check for AI capabilities
     println!("\nChecking AI capabilities:");
     let config = config::loader::load_config()?;
@@ -333,17 +363,52 @@ pub fn cmd_doctor(fix: bool, verbose: bool) -> Result<(), Box<dyn Error>> {
         issues_found = true;
     }
     
+    // Check that the local model is pulled and fits this machine
+    if let Some(ollama) = config.ollama.as_ref().filter(|ollama| ollama.enabled) {
+        println!("\nChecking Ollama model:");
+        match check_ollama_model(ollama, fix).await {
+            Ok(ollama_issues) => issues_found |= ollama_issues,
+            Err(e) => {
+                println!("  [✗] Could not query Ollama at {}: {}", ollama.api_url, e);
+                if verbose {
+                    println!("    Start it with 'ollama serve' or set ollama.enabled to false");
+                }
+                issues_found = true;
+            }
+        }
+    }
+    
     // Check if running in SSH session
     if is_ssh_session() {
         println!("\nRunning in SSH session:");
         if config.ssh.as_ref().map_or(false, |s| s.enable_optimizations) {
             println!("  [✓] SSH optimizations enabled");
+            if verbose {
+                let capabilities = crate::terminal::Capabilities::from_env(&config);
+                let mode = crate::terminal::RenderMode::detect(&config, &capabilities);
+                println!("    Dropdown limited to {} rows, redrawn at most every {}ms",
+                    mode.dropdown_max_height, mode.min_frame_interval.as_millis());
+            }
+            
+            #[cfg(unix)]
+            {
+                match crate::service::remote::RemotePredictor::detect(&config) {
+                    Some(remote) => println!("  [✓] Predicting with the forwarded service at {}", remote.socket().display()),
+                    None if config.ssh.as_ref().and_then(|s| s.enable_command_caching).unwrap_or(true) => {
+                        println!("  [!] No forwarded service found; predictions run on this host");
+                        if verbose {
+                            println!("    Connect with 'rustfig ssh <host>' to use the service on your own machine");
+                        }
+                    }
+                    None => {}
+                }
+            }
         } else {
             println!("  [✗] SSH optimizations disabled");
             
             if fix {
                 println!("    Enabling SSH optimizations...");
-                cmd_config_set("ssh.enable_optimizations", "true")?;
+                cmd_config_set("ssh.enable_optimizations", "true", false)?;
             }
         }
     }
@@ -354,6 +419,16 @@ pub fn cmd_doctor(fix: bool, verbose: bool) -> Result<(), Box<dyn Error>> {
     println!("  Architecture: {}", std::env::consts::ARCH);
     println!("  RustFig version: {}", env!("CARGO_PKG_VERSION"));
     
+    // Without raw mode the terminal can't be queried, so this is what the environment says
+    let capabilities = crate::terminal::Capabilities::from_env(&config);
+    println!("  Terminal: {} ({} profile, truecolor {})",
+        capabilities.terminal.name(),
+        format!("{:?}", capabilities.profile).to_lowercase(),
+        if capabilities.truecolor { "yes" } else { "no" });
+    if crate::terminal::RenderMode::detect(&config, &capabilities).accessible {
+        println!("  Accessible mode: plain numbered suggestions, no ghost text");
+    }
+    
     if verbose {
         // Additional checks for verbose mode
         println!("\nAdditional information:");
@@ -401,6 +476,67 @@ pub fn cmd_doctor(fix: bool, verbose: bool) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Doctor: report whether the configured Ollama model is pulled and fits in memory,
+/// pulling it or switching to a smaller model with `fix`
+async fn check_ollama_model(ollama: &config::schema::OllamaConfig, fix: bool) -> Result<bool, Box<dyn Error>> {
+    use crate::ai::ollama::{self, OllamaClient};
+    use crate::utils::system::{format_bytes, total_memory_bytes};
+    
+    let client = OllamaClient::new(ollama)?;
+    let installed = client.list_models().await?;
+    let pulled = installed.iter().find(|model| ollama::same_model(&model.name, &ollama.model));
+    let mut issues = false;
+    
+    match pulled {
+        Some(_) => println!("  [✓] Model '{}' is pulled", ollama.model),
+        None => {
+            println!("  [✗] Model '{}' is not pulled", ollama.model);
+            issues = true;
+        }
+    }
+    
+    let needed = ollama::estimate_ram_bytes(&ollama.model, pulled.map(|model| model.size));
+    let total = total_memory_bytes();
+    let fits = match (needed, total) {
+        (Some(needed), Some(total)) => {
+            let fits = ollama::fits_in_memory(needed, total);
+            let mark = if fits { "✓" } else { "✗" };
+            println!("  [{}] Needs about {} of RAM ({} installed)", mark, format_bytes(needed), format_bytes(total));
+            fits
+        }
+        _ => {
+            println!("  [?] Could not estimate whether '{}' fits in memory", ollama.model);
+            true
+        }
+    };
+    
+    if !fits {
+        issues = true;
+        let smaller = total.and_then(|total| ollama::smaller_model(&ollama.model, &installed, total));
+        match smaller {
+            Some(smaller) if fix => {
+                if !installed.iter().any(|model| ollama::same_model(&model.name, smaller)) {
+                    println!("    Pulling {} (this can take a while)...", smaller);
+                    client.pull_model(smaller).await?;
+                }
+                cmd_config_set("ollama.model", smaller, false)?;
+            }
+            Some(smaller) => println!("    Switch to a smaller model: rustfig config set ollama.model {}", smaller),
+            None => println!("    No smaller model fits; consider: rustfig config set ollama.enabled false"),
+        }
+    } else if pulled.is_none() {
+        if fix {
+            println!("    Pulling {} (this can take a while)...", ollama.model);
+            client.pull_model(&ollama.model).await?;
+            println!("    Pulled {}", ollama.model);
+        } else {
+            println!("    Pull it with: ollama pull {}", ollama.model);
+        }
+    }
+    
+    Ok(issues)
+}
+
 /// Service: Start
 pub fn cmd_service_start(verbose: bool) -> Result<(), Box<dyn Error>> {
     // Check if already running
@@ -507,15 +643,121 @@ pub fn cmd_service_stop(force: bool) -> Result<(), Box<dyn Error>> {
 
 /// Service: Status (internal implementation)
 fn cmd_service_status_internal() -> Result<bool, Box<dyn Error>> {
-    // Check if the service is running
-    let executable = std::env::current_exe()?;
-    let mut command = Command::new(executable);
-    command.arg("service");
-    command.arg("ping");
+    // Check if the service answers on its socket
+    let config = config::loader::load_config()?;
+    Ok(crate::service::client::is_running(&crate::service::socket_path(&config)))
+}
+
+/// Service: run in the foreground, or detached from the terminal with `daemon`
+///
+/// This is what `service start` spawns; it serves until `service stop`.
+pub fn cmd_service_run(daemon: bool) -> Result<(), Box<dyn Error>> {
+    let config = config::loader::load_config()?;
     
-    let output = command.output()?;
+    #[cfg(unix)]
+    {
+        crate::service::daemon::run(&config, daemon)
+    }
     
-    Ok(output.status.success())
+    #[cfg(not(unix))]
+    {
+        let _ = (config, daemon);
+        Err("The RustFig service needs Unix domain sockets, which this platform lacks".into())
+    }
+}
+
+/// Service: succeed only if the service answers
+pub fn cmd_service_ping() -> Result<(), Box<dyn Error>> {
+    use crate::service::protocol::{Request, Response};
+    
+    let config = config::loader::load_config()?;
+    match crate::service::client::request(&crate::service::socket_path(&config), &Request::Ping)? {
+        Response::Pong { version } => {
+            println!("pong (rustfig {})", version);
+            Ok(())
+        }
+        other => Err(format!("Unexpected response: {:?}", other).into()),
+    }
+}
+
+/// Service: print process and session details
+pub fn cmd_service_info() -> Result<(), Box<dyn Error>> {
+    use crate::service::protocol::{Request, Response};
+    
+    let config = config::loader::load_config()?;
+    let info = match crate::service::client::request(&crate::service::socket_path(&config), &Request::Info)? {
+        Response::Info(info) => info,
+        other => return Err(format!("Unexpected response: {:?}", other).into()),
+    };
+    
+    println!("  PID: {}", info.pid);
+    println!("  Version: {} (protocol {})", info.version, info.protocol);
+    println!("  Uptime: {}s", info.uptime_secs);
+    println!("  Socket: {}", info.socket.display());
+    println!("  Sessions: {}", info.sessions.len());
+    for session in &info.sessions {
+        let tty = session.tty.as_deref().unwrap_or("-");
+        println!("    #{} {} (pid {}, tty {}, {}s)", session.id, session.shell, session.pid, tty, session.attached_secs);
+    }
+    println!("  Tasks:");
+    for task in &info.tasks {
+        let state = if task.running { "running" } else { "finished" };
+        println!("    {} ({}, {} restarts)", task.name, state, task.restarts);
+        if let Some(failure) = &task.last_failure {
+            println!("      last failure: {}", failure);
+        }
+    }
+    
+    Ok(())
+}
+
+/// Service: send a control request (`stop`)
+pub fn cmd_service_signal(signal: &str) -> Result<(), Box<dyn Error>> {
+    use crate::service::protocol::{Request, Response};
+    
+    let request = match signal {
+        "stop" => Request::Stop,
+        _ => return Err(format!("Unknown service signal: {}", signal).into()),
+    };
+    
+    let config = config::loader::load_config()?;
+    match crate::service::client::request(&crate::service::socket_path(&config), &request)? {
+        Response::Stopping => Ok(()),
+        Response::Error { message } => Err(message.into()),
+        other => Err(format!("Unexpected response: {:?}", other).into()),
+    }
+}
+
+/// Service: connect with `ssh`, forwarding the service socket so rustfig on the remote
+/// host predicts with this machine's history and AI
+///
+/// `args` are passed to ssh unchanged. The service is started first if it isn't running.
+pub fn cmd_ssh(args: &[String]) -> Result<(), Box<dyn Error>> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        
+        let config = config::loader::load_config()?;
+        if !cmd_service_status_internal()? {
+            cmd_service_start(false)?;
+        }
+        
+        let forward = format!(
+            "{}:{}",
+            crate::service::remote::forward_path().display(),
+            crate::service::remote_socket_path(&config).display()
+        );
+        
+        // Replace this process, so ssh owns the terminal and its exit status is ours
+        let error = Command::new("ssh").arg("-R").arg(forward).args(args).exec();
+        Err(format!("Could not run ssh: {}", error).into())
+    }
+    
+    #[cfg(not(unix))]
+    {
+        let _ = args;
+        Err("Forwarding the RustFig service needs Unix domain sockets, which this platform lacks".into())
+    }
 }
 
 /// Service: Status
@@ -544,9 +786,318 @@ pub fn cmd_service_status(verbose: bool) -> Result<(), Box<dyn Error>> {
         println!("RustFig service: NOT RUNNING");
     }
     
+    // A process that stopped beating is wedged rather than just busy
+    let config = config::loader::load_config()?;
+    if let Some(heartbeat) = crate::service::heartbeat::read_heartbeat(&crate::service::heartbeat::heartbeat_path(&config)) {
+        if heartbeat.is_stale() {
+            println!("Warning: last heartbeat from pid {} was {}s ago; try 'rustfig service restart'", heartbeat.pid, heartbeat.age().as_secs());
+        } else if verbose {
+            println!("Last heartbeat: {}s ago", heartbeat.age().as_secs());
+        }
+    }
+    
+    Ok(())
+}
+
+/// Service: print the service log, oldest first, optionally following new lines
+///
+/// `since` takes durations like `30m` or `1h`; without it the last `lines` lines are shown.
+pub fn cmd_service_logs(follow: bool, since: Option<&str>, lines: usize) -> Result<(), Box<dyn Error>> {
+    use crate::service::log;
+    
+    let path = log::log_path()?;
+    let cutoff = match since {
+        Some(since) => {
+            let age = log::parse_since(since).ok_or_else(|| format!("Invalid --since '{}': use e.g. 30s, 15m, 1h or 2d", since))?;
+            Some(log::unix_now().saturating_sub(age.as_secs()))
+        }
+        None => None,
+    };
+    
+    let files = log::log_files(&path);
+    if files.is_empty() && !follow {
+        println!("No service logs yet ({})", path.display());
+        return Ok(());
+    }
+    
+    let mut entries: Vec<String> = Vec::new();
+    for file in &files {
+        let content = fs::read_to_string(file)?;
+        entries.extend(content.lines().map(str::to_string));
+    }
+    
+    if let Some(cutoff) = cutoff {
+        // Continuation lines have no timestamp; keep them with the line they belong to
+        let mut keep = false;
+        entries.retain(|line| {
+            if let Some(timestamp) = log::line_timestamp(line) {
+                keep = timestamp >= cutoff;
+            }
+            keep
+        });
+    } else if entries.len() > lines {
+        entries.drain(..entries.len() - lines);
+    }
+    
+    for line in &entries {
+        println!("{}", line);
+    }
+    
+    if follow {
+        follow_log(&path)?;
+    }
+    
+    Ok(())
+}
+
+/// Print lines appended to the log until interrupted, reopening it after a rotation
+fn follow_log(path: &std::path::Path) -> Result<(), Box<dyn Error>> {
+    use std::io::{Read, Seek, SeekFrom};
+    
+    let mut offset = fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+    let mut pending = String::new();
+    
+    loop {
+        std::thread::sleep(std::time::Duration::from_millis(250));
+        
+        let len = match fs::metadata(path) {
+            Ok(meta) => meta.len(),
+            Err(_) => continue,
+        };
+        if len < offset {
+            // Rotated: the file at this path is a new log
+            offset = 0;
+        }
+        if len == offset {
+            continue;
+        }
+        
+        let mut file = fs::File::open(path)?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut chunk = Vec::new();
+        file.read_to_end(&mut chunk)?;
+        offset += chunk.len() as u64;
+        
+        // Only print complete lines; hold a partial one until the rest is written
+        pending.push_str(&String::from_utf8_lossy(&chunk));
+        while let Some(end) = pending.find('\n') {
+            println!("{}", &pending[..end]);
+            pending.drain(..=end);
+        }
+        io::stdout().flush()?;
+    }
+}
+
+/// Perf: print the service's per-component operation counts and latencies
+///
+/// `format` is `text`, `json`, or `prometheus` (the text exposition format).
+pub fn cmd_perf_show(format: &str) -> Result<(), Box<dyn Error>> {
+    use crate::service::protocol::{Request, Response};
+    
+    let config = config::loader::load_config()?;
+    let components = match crate::service::client::request(&crate::service::socket_path(&config), &Request::Metrics)? {
+        Response::Metrics { components } => components,
+        Response::Error { message } => return Err(message.into()),
+        other => return Err(format!("Unexpected response: {:?}", other).into()),
+    };
+    
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&components)?),
+        "prometheus" => print!("{}", prometheus_metrics(&components)),
+        "text" => {
+            if components.iter().all(|component| component.operations.is_empty()) {
+                println!("No operations recorded yet.");
+            }
+            for component in components.iter().filter(|component| !component.operations.is_empty()) {
+                println!("{} ({} operations)", component.component, component.operation_count);
+                println!("  {:<28} {:>8} {:>10} {:>10} {:>10}", "operation", "calls", "avg", "min", "max");
+                for op in &component.operations {
+                    println!(
+                        "  {:<28} {:>8} {:>10} {:>10} {:>10}",
+                        op.operation, op.calls, format_micros(op.avg_us), format_micros(op.min_us), format_micros(op.max_us)
+                    );
+                }
+            }
+        }
+        _ => return Err(format!("Unsupported format: {}", format).into()),
+    }
+    
+    Ok(())
+}
+
+/// Perf: print the service's accounted memory by tier against `performance.max_memory_mb`
+pub fn cmd_perf_memory(format: &str) -> Result<(), Box<dyn Error>> {
+    use crate::service::protocol::{Request, Response};
+    use crate::utils::system::format_bytes;
+    
+    let config = config::loader::load_config()?;
+    let report = match crate::service::client::request(&crate::service::socket_path(&config), &Request::Memory)? {
+        Response::Memory(report) => report,
+        Response::Error { message } => return Err(message.into()),
+        other => return Err(format!("Unexpected response: {:?}", other).into()),
+    };
+    
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+        "text" => {
+            // Listed in the order memory is reclaimed when over budget
+            for (tier, bytes) in &report.tiers {
+                println!("  {:<20} {:>10}", tier.label(), format_bytes(*bytes as u64));
+            }
+            println!("  {:<20} {:>10}", "accounted", format_bytes(report.accounted_bytes() as u64));
+            if report.budget_bytes == 0 {
+                println!("  {:<20} {:>10}", "budget", "unlimited");
+            } else {
+                println!("  {:<20} {:>10}", "budget", format_bytes(report.budget_bytes as u64));
+            }
+            println!("  {:<20} {:>10}", "evicted so far", format_bytes(report.evicted_bytes as u64));
+            if let Some(rss) = report.rss_bytes {
+                println!("  {:<20} {:>10}", "process RSS", format_bytes(rss));
+            }
+        }
+        _ => return Err(format!("Unsupported format: {}", format).into()),
+    }
+    
     Ok(())
 }
 
+/// Bench: replay a fixed corpus through both engines and report latency percentiles per source
+///
+/// Each input runs `iterations` times after one unmeasured warm-up pass; the prediction
+/// cache is cleared before every call so repeats measure real work.
+pub async fn cmd_bench(iterations: usize, format: &str) -> Result<(), Box<dyn Error>> {
+    use crate::prediction::PredictionEngine;
+    use crate::suggestion::SuggestionEngine;
+    use crate::suggestion::fuzzy;
+    use crate::utils::bench::{LatencyStats, CORPUS, FUZZY_CANDIDATES, FUZZY_PATTERNS};
+    
+    #[derive(serde::Serialize)]
+    struct BenchResult {
+        engine: &'static str,
+        source: &'static str,
+        #[serde(flatten)]
+        stats: LatencyStats,
+    }
+    
+    let config = config::loader::load_config()?;
+    let suggestion_engine = SuggestionEngine::new(&config);
+    let prediction_engine = PredictionEngine::new(&config);
+    let iterations = iterations.max(1);
+    
+    let mut results = Vec::new();
+    for (source, inputs) in CORPUS {
+        let mut suggestion_samples = Vec::new();
+        let mut prediction_samples = Vec::new();
+        
+        for pass in 0..=iterations {
+            for input in inputs.iter() {
+                let start = Instant::now();
+                suggestion_engine.get_suggestions(input, input.len(), 10).await;
+                let suggestion_time = start.elapsed();
+                
+                prediction_engine.clear_cache();
+                let start = Instant::now();
+                prediction_engine.predict(input, input.len(), 5).await;
+                let prediction_time = start.elapsed();
+                
+                // Pass 0 warms up lazily loaded specs, history and the like
+                if pass > 0 {
+                    suggestion_samples.push(suggestion_time);
+                    prediction_samples.push(prediction_time);
+                }
+            }
+        }
+        
+        for (engine, samples) in [("suggestion", suggestion_samples), ("prediction", prediction_samples)] {
+            if let Some(stats) = LatencyStats::from_samples(samples) {
+                results.push(BenchResult { engine, source, stats });
+            }
+        }
+    }
+    
+    // The fuzzy matcher on its own, with the SIMD kernels this CPU runs and without
+    let scorers: [(&'static str, fn(&str, &str) -> Option<f32>); 2] =
+        [(crate::utils::simd::backend(), fuzzy::fast_fuzzy_score), ("scalar", fuzzy::scalar_fuzzy_score)];
+    for (source, score) in scorers {
+        let mut samples = Vec::new();
+        for pass in 0..=iterations {
+            let start = Instant::now();
+            for pattern in FUZZY_PATTERNS {
+                for candidate in FUZZY_CANDIDATES {
+                    std::hint::black_box(score(pattern, candidate));
+                }
+            }
+            if pass > 0 {
+                samples.push(start.elapsed());
+            }
+        }
+        if let Some(stats) = LatencyStats::from_samples(samples) {
+            results.push(BenchResult { engine: "fuzzy", source, stats });
+        }
+    }
+    
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&results)?),
+        "text" => {
+            println!("{:<12} {:<10} {:>8} {:>10} {:>10} {:>10} {:>10}", "engine", "source", "samples", "p50", "p95", "p99", "max");
+            for result in &results {
+                let stats = &result.stats;
+                println!(
+                    "{:<12} {:<10} {:>8} {:>10} {:>10} {:>10} {:>10}",
+                    result.engine, result.source, stats.samples,
+                    format_micros(stats.p50_us), format_micros(stats.p95_us), format_micros(stats.p99_us), format_micros(stats.max_us)
+                );
+            }
+        }
+        _ => return Err(format!("Unsupported format: {}", format).into()),
+    }
+    
+    Ok(())
+}
+
+/// Microseconds as `850µs` or `4.20ms`
+fn format_micros(us: u64) -> String {
+    if us < 1000 {
+        format!("{}µs", us)
+    } else {
+        format!("{:.2}ms", us as f64 / 1000.0)
+    }
+}
+
+/// Metrics in the Prometheus text exposition format
+fn prometheus_metrics(components: &[crate::utils::perf_metrics::ComponentSnapshot]) -> String {
+    let families: [(&str, &str, &str, fn(&crate::utils::perf_metrics::OperationSnapshot) -> String); 5] = [
+        ("rustfig_operation_calls_total", "counter", "Operations performed", |op| op.calls.to_string()),
+        ("rustfig_operation_duration_seconds_sum", "counter", "Total time spent in operations", |op| seconds(op.total_us)),
+        ("rustfig_operation_duration_avg_seconds", "gauge", "Average operation latency", |op| seconds(op.avg_us)),
+        ("rustfig_operation_duration_min_seconds", "gauge", "Fastest operation", |op| seconds(op.min_us)),
+        ("rustfig_operation_duration_max_seconds", "gauge", "Slowest operation", |op| seconds(op.max_us)),
+    ];
+    
+    fn seconds(us: u64) -> String {
+        format!("{}", us as f64 / 1_000_000.0)
+    }
+    
+    let mut out = String::new();
+    for (name, kind, help, value) in families {
+        out.push_str(&format!("# HELP {} {}\n# TYPE {} {}\n", name, help, name, kind));
+        for component in components {
+            for op in &component.operations {
+                out.push_str(&format!(
+                    "{}{{component=\"{}\",operation=\"{}\"}} {}\n",
+                    name, prometheus_label(&component.component), prometheus_label(&op.operation), value(op)
+                ));
+            }
+        }
+    }
+    out
+}
+
+/// Escape a Prometheus label value
+fn prometheus_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
 /// Config: get a specific value
 pub fn cmd_config_get(key: &str, format: &str) -> Result<(), Box<dyn Error>> {
     let config = config::loader::load_config()?;
@@ -589,7 +1140,8 @@ pub fn cmd_config_get(key: &str, format: &str) -> Result<(), Box<dyn Error>> {
                 let yaml = serde_yaml::to_string(&current_value)?;
                 println!("{}", yaml);
             } else {
-                println!("{}", current_value);
+                // Scalars print bare, as YAML writes them
+                println!("{}", serde_yaml::to_string(&current_value)?.trim_end());
             }
         }
     }
@@ -597,96 +1149,1437 @@ pub fn cmd_config_get(key: &str, format: &str) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-/// Config: set a specific value
-pub fn cmd_config_set(key: &str, value: &str) -> Result<(), Box<dyn Error>> {
+/// Config: set a specific value, or add one to a list with `append`
+///
+/// The key and value are checked against the config schema before anything is written.
+pub fn cmd_config_set(key: &str, value: &str, append: bool) -> Result<(), Box<dyn Error>> {
     let config_dir = config::loader::get_config_dir()?;
-    let config_file = config_dir.join("config.yaml");
+    let config_file = config_dir.join(config::loader::CONFIG_FILE);
     
     // Load the existing config as YAML Value
-    let yaml_str = fs::read_to_string(&config_file)?;
-    let mut yaml_value: serde_yaml::Value = serde_yaml::from_str(&yaml_str)?;
+    let mut yaml_value: serde_yaml::Value = match fs::read_to_string(&config_file) {
+        Ok(yaml_str) => serde_yaml::from_str(&yaml_str)?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => serde_yaml::Value::Null,
+        Err(e) => return Err(e.into()),
+    };
+    if yaml_value.is_null() {
+        yaml_value = serde_yaml::Value::Mapping(serde_yaml::Mapping::new());
+    }
+    config::migration::migrate(&mut yaml_value)?;
     
     // Parse the key path (e.g., "ui.theme")
     let parts: Vec<&str> = key.split('.').collect();
     
-    // Convert the value string to YAML Value
-    let new_value: serde_yaml::Value = match value {
-        "true" => serde_yaml::Value::Bool(true),
-        "false" => serde_yaml::Value::Bool(false),
-        "null" => serde_yaml::Value::Null,
-        _ => {
-            // Try to parse as integer
-            if let Ok(int_val) = value.parse::<i64>() {
-                serde_yaml::Value::Number(serde_yaml::Number::from(int_val))
-            }
-            // Try to parse as float
-            else if let Ok(float_val) = value.parse::<f64>() {
-                serde_yaml::to_value(float_val)?
-            }
-            // Default to string
-            else {
-                serde_yaml::Value::String(value.to_string())
-            }
-        }
+    // `--append` extends the effective list, so the defaults aren't lost when the file takes it over
+    let mut effective = serde_yaml::to_value(config::Config::default())?;
+    config::loader::merge_values(&mut effective, yaml_value.clone());
+    let current = parts.iter().try_fold(&effective, |current, part| current.get(*part));
+    let items = match current {
+        _ if !append => Vec::new(),
+        Some(serde_yaml::Value::Sequence(items)) => items.clone(),
+        None | Some(serde_yaml::Value::Null) => Vec::new(),
+        Some(_) => return Err(format!("{} is not a list, so it can't be appended to", key).into()),
     };
     
-    // Navigate and update the configuration structure
-    let mut current_value = &mut yaml_value;
-    
-    for (i, part) in parts.iter().enumerate() {
-        if i == parts.len() - 1 {
-            // Last part, set the value
-            current_value[part] = new_value.clone();
+    // Values are read as YAML (`true`, `20`, `[a, b]`); if that doesn't fit, try the literal string
+    let literal = serde_yaml::Value::String(value.to_string());
+    let parsed = serde_yaml::from_str(value).unwrap_or_else(|_| literal.clone());
+    let candidates = if parsed == literal { vec![parsed] } else { vec![parsed, literal] };
+    
+    let mut problems = Vec::new();
+    for candidate in candidates {
+        let new_value = if append {
+            let mut items = items.clone();
+            items.push(candidate);
+            serde_yaml::Value::Sequence(items)
         } else {
-            // Create the path if it doesn't exist
-            if !current_value.get(part).is_some() {
-                current_value[part] = serde_yaml::Value::Mapping(serde_yaml::Mapping::new());
+            candidate
+        };
+        
+        let mut updated = yaml_value.clone();
+        set_config_path(&mut updated, &parts, new_value.clone());
+        
+        let mut merged = serde_yaml::to_value(config::Config::default())?;
+        config::loader::merge_values(&mut merged, updated.clone());
+        let issues: Vec<String> = config::validator::validate_value(&merged)?
+            .into_iter()
+            .filter(|issue| concerns_key(&issue.path, key))
+            .map(|issue| issue.to_string())
+            .collect();
+        
+        if issues.is_empty() {
+            // Write the updated config back
+            let yaml_str = serde_yaml::to_string(&updated)?;
+            fs::write(&config_file, yaml_str)?;
+            
+            if append {
+                println!("Configuration updated: appended {} to {}", value, key);
+            } else {
+                println!("Configuration updated: {} = {}", key, value);
             }
-            current_value = &mut current_value[part];
+            return Ok(());
+        }
+        
+        // The first reading's complaint is the one that explains the problem
+        if problems.is_empty() {
+            problems = issues;
         }
     }
-    
-    // Write the updated config back
-    let yaml_str = serde_yaml::to_string(&yaml_value)?;
-    fs::write(&config_file, yaml_str)?;
-    
-    println!("Configuration updated: {} = {}", key, value);
-    
-    Ok(())
+    Err(format!("Not setting {}: {}", key, problems.join("; ")).into())
 }
 
-/// Ask an AI question
-pub async fn cmd_ask(question: &str, model: Option<&str>, markdown: bool) -> Result<(), Box<dyn Error>> {
-    let config = config::loader::load_config()?;
-    
-    // Create AI provider
-    let ai_provider = match AiProviderFactory::create_provider(&config).await {
-        Some(provider) => provider,
-        None => return Err("No AI provider configured. Check your configuration.".into()),
-    };
-    
-    println!("Asking AI: {}", question);
-    println!();
-    
-    // Measure response time
-    let start = Instant::now();
-    
-    // Query AI
-    let response = ai_provider.query(question).await?;
-    
-    let duration = start.elapsed();
-    
-    // Output result
-    if markdown {
-        println!("{}", response);
-    } else {
-        // Simple markdown stripping for terminal output
-        let response = response.replace("```", "");
-        println!("{}", response);
+/// Set a dotted key path, replacing anything in the way that isn't a mapping
+fn set_config_path(root: &mut serde_yaml::Value, parts: &[&str], value: serde_yaml::Value) {
+    let mut current = root;
+    for part in parts {
+        if !current.is_mapping() {
+            *current = serde_yaml::Value::Mapping(serde_yaml::Mapping::new());
+        }
+        current = match current.as_mapping_mut() {
+            Some(mapping) => mapping.entry(serde_yaml::Value::from(*part)).or_insert(serde_yaml::Value::Null),
+            None => return,
+        };
     }
-    
-    println!("\nResponse time: {:.2?}", duration);
-    
+    *current = value;
+}
+
+/// Whether a validation issue at `path` is about `key`: the key itself, inside it, or a parent of it
+fn concerns_key(path: &str, key: &str) -> bool {
+    let nested = |outer: &str, inner: &str| {
+        inner.strip_prefix(outer).map_or(false, |rest| rest.is_empty() || rest.starts_with('.') || rest.starts_with('['))
+    };
+    nested(key, path) || nested(path, key)
+}
+
+/// Config: print the merged configuration, optionally with where each value comes from
+pub fn cmd_config_list(format: &str, show_origin: bool) -> Result<(), Box<dyn Error>> {
+    use config::loader::{self, LayerSource};
+
+    let config_dir = loader::get_config_dir()?;
+    let layers = loader::config_layers(&config_dir)?;
+
+    let mut merged = serde_yaml::Value::Null;
+    for layer in &layers {
+        loader::merge_values(&mut merged, layer.value.clone());
+    }
+
+    if !show_origin {
+        match format {
+            "yaml" => println!("{}", serde_yaml::to_string(&merged)?),
+            "json" => println!("{}", serde_json::to_string_pretty(&merged)?),
+            "text" | _ => {
+                for (key, value) in flatten_config(&merged) {
+                    println!("{}={}", key, value);
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    // Like `git config --list --show-origin`: one `origin<TAB>key=value` line per setting
+    for (key, value) in flatten_config(&merged) {
+        let path: Vec<&str> = key.split('.').collect();
+        let origin = match loader::value_origin(&layers, &path) {
+            Some(layer) => match (layer.source, &layer.path) {
+                (LayerSource::Env, _) => format!("env:{}", loader::env_var_name(&path)),
+                (LayerSource::Profile, Some(path)) => format!("profile:{}", path.display()),
+                (LayerSource::Project, Some(path)) => format!("project:{}", path.display()),
+                (_, Some(path)) => format!("file:{}", path.display()),
+                (_, None) => "default".to_string(),
+            },
+            None => "default".to_string(),
+        };
+        println!("{}\t{}={}", origin, key, value);
+    }
+
+    Ok(())
+}
+
+/// Dotted `key=value` pairs for every leaf of a config value, in file order
+fn flatten_config(value: &serde_yaml::Value) -> Vec<(String, String)> {
+    fn walk(value: &serde_yaml::Value, prefix: &str, out: &mut Vec<(String, String)>) {
+        match value {
+            serde_yaml::Value::Mapping(mapping) if !mapping.is_empty() => {
+                for (key, inner) in mapping {
+                    let key = match key {
+                        serde_yaml::Value::String(key) => key.clone(),
+                        other => serde_json::to_string(other).unwrap_or_default(),
+                    };
+                    let path = if prefix.is_empty() { key } else { format!("{}.{}", prefix, key) };
+                    walk(inner, &path, out);
+                }
+            }
+            serde_yaml::Value::String(text) => out.push((prefix.to_string(), text.clone())),
+            // Lists, empty maps and scalars print inline
+            other => out.push((prefix.to_string(), serde_json::to_string(other).unwrap_or_default())),
+        }
+    }
+
+    let mut out = Vec::new();
+    walk(value, "", &mut out);
+    out
+}
+
+/// Config: edit config.yaml in an editor, only applying the result once it validates
+pub fn cmd_config_edit(editor: Option<&str>) -> Result<(), Box<dyn Error>> {
+    use config::validator::{self, Severity};
+
+    let config_dir = config::loader::get_config_dir()?;
+    let config_file = config_dir.join(config::loader::CONFIG_FILE);
+    if !config_file.exists() {
+        config::init::generate_default_config(&config_file)?;
+    }
+
+    // `--editor`, then $VISUAL, then $EDITOR; values like "code --wait" carry their own arguments
+    let editor = editor
+        .map(|editor| editor.to_string())
+        .or_else(|| std::env::var("VISUAL").ok())
+        .or_else(|| std::env::var("EDITOR").ok())
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    let mut editor_args = editor.split_whitespace();
+    let program = editor_args.next().unwrap_or("vi");
+    let editor_args: Vec<&str> = editor_args.collect();
+
+    // Edit a draft so a broken config never becomes the live one
+    let original = fs::read_to_string(&config_file)?;
+    let draft = config_dir.join("config.edit.yaml");
+    fs::write(&draft, &original)?;
+
+    let stdin = io::stdin();
+    loop {
+        let status = Command::new(program).args(&editor_args).arg(&draft).status();
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                let _ = fs::remove_file(&draft);
+                return Err(format!("Editor '{}' exited with {}; configuration unchanged", editor, status).into());
+            }
+            Err(e) => {
+                let _ = fs::remove_file(&draft);
+                return Err(format!("Could not start editor '{}': {}", editor, e).into());
+            }
+        }
+
+        if fs::read_to_string(&draft)? == original {
+            fs::remove_file(&draft)?;
+            println!("No changes");
+            return Ok(());
+        }
+
+        let issues = match validator::check_config_file(&draft) {
+            Ok(issues) => issues,
+            // Unparseable YAML is reported the same way as a schema error
+            Err(e) => vec![validator::ValidationIssue {
+                severity: Severity::Error,
+                path: String::new(),
+                message: e.to_string(),
+            }],
+        };
+
+        for issue in issues.iter().filter(|issue| issue.severity == Severity::Warning) {
+            println!("  [!] {}", issue);
+        }
+        let errors: Vec<_> = issues.iter().filter(|issue| issue.severity == Severity::Error).collect();
+        if errors.is_empty() {
+            break;
+        }
+
+        println!("The edited configuration is invalid:");
+        for issue in &errors {
+            println!("  [✗] {}", issue);
+        }
+        print!("Edit again, or revert to the previous configuration? [E/r] ");
+        io::stdout().flush()?;
+
+        let mut answer = String::new();
+        stdin.lock().read_line(&mut answer)?;
+        if matches!(answer.trim(), "r" | "R" | "revert") {
+            fs::remove_file(&draft)?;
+            println!("Reverted; {} is unchanged", config_file.display());
+            return Ok(());
+        }
+    }
+
+    fs::rename(&draft, &config_file)?;
+    println!("Configuration updated: {}", config_file.display());
+
+    Ok(())
+}
+
+/// Config: validate one file, or every config file and the active profile
+pub fn cmd_config_validate(path: Option<&str>) -> Result<(), Box<dyn Error>> {
+    use config::validator::{self, Severity};
+
+    let issues = match path {
+        Some(path) => {
+            let path = PathBuf::from(path);
+            validator::check_config_file(&path)?
+                .into_iter()
+                .map(|issue| (path.display().to_string(), issue))
+                .collect()
+        }
+        None => {
+            let config_dir = config::loader::get_config_dir()?;
+            validator::check_layers(&config::loader::config_layers(&config_dir)?)?
+        }
+    };
+
+    let mut errors = 0;
+    for (origin, issue) in &issues {
+        match issue.severity {
+            Severity::Error => {
+                errors += 1;
+                println!("  [✗] {}: {}", origin, issue);
+            }
+            Severity::Warning => println!("  [!] {}: {}", origin, issue),
+        }
+    }
+
+    if errors > 0 {
+        return Err(format!("Configuration is invalid ({} error{})", errors, if errors == 1 { "" } else { "s" }).into());
+    }
+    println!("Configuration is valid");
+
+    Ok(())
+}
+
+/// Config: print the JSON Schema of the config files, or write it to `output`
+pub fn cmd_config_schema(output: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let schema = serde_json::to_string_pretty(&config::validator::config_schema()?)?;
+
+    match output {
+        Some(output) => {
+            fs::write(output, format!("{}\n", schema))?;
+            println!("Wrote schema to {}", output);
+            println!("For YAML autocompletion, add this line to the top of config.yaml:");
+            println!("  # yaml-language-server: $schema={}", output);
+        }
+        None => println!("{}", schema),
+    }
+
+    Ok(())
+}
+
+/// Profile: list the available profiles, marking the active one
+pub fn cmd_profile_list() -> Result<(), Box<dyn Error>> {
+    use config::profiles::{self, ProfileSource};
+    
+    let config_dir = config::loader::get_config_dir()?;
+    let active = profiles::active_profile(&config_dir);
+    let active_name = active.as_ref().map(|(name, _)| name.as_str());
+    
+    println!("{} {}", if active_name.is_none() { "*" } else { " " }, profiles::DEFAULT_PROFILE);
+    for name in profiles::list_profiles(&config_dir) {
+        let marker = if active_name == Some(name.as_str()) { "*" } else { " " };
+        println!("{} {}", marker, name);
+    }
+    
+    if let Some((name, ProfileSource::Env)) = &active {
+        println!("\nProfile '{}' is set by {}", name, profiles::PROFILE_ENV_VAR);
+    }
+    println!("\nProfiles are overlays in {}", config_dir.join(profiles::PROFILES_DIR).display());
+    
+    Ok(())
+}
+
+/// Profile: make a profile active (`default` goes back to config.yaml alone)
+pub fn cmd_profile_switch(name: &str) -> Result<(), Box<dyn Error>> {
+    use config::profiles::{self, ProfileSource};
+    
+    let config_dir = config::loader::get_config_dir()?;
+    
+    if name != profiles::DEFAULT_PROFILE {
+        profiles::validate_profile_name(name)?;
+        let path = profiles::profile_path(&config_dir, name);
+        if !path.exists() {
+            return Err(format!("No profile named '{}' (create {})", name, path.display()).into());
+        }
+        
+        // Refuse to switch to a profile that doesn't produce a valid configuration
+        let mut merged = serde_yaml::to_value(config::Config::default())?;
+        config::loader::merge_file(&mut merged, &config_dir.join(config::loader::CONFIG_FILE))?;
+        config::loader::merge_file(&mut merged, &path)?;
+        serde_yaml::from_value::<config::Config>(merged)
+            .map_err(|e| format!("Profile '{}' is invalid: {}", name, e))?;
+    }
+    
+    profiles::set_active_profile(&config_dir, Some(name))?;
+    println!("Switched to profile: {}", name);
+    
+    if let Some((overriding, ProfileSource::Env)) = profiles::active_profile(&config_dir) {
+        println!("Note: {}={} overrides this in the current environment", profiles::PROFILE_ENV_VAR, overriding);
+    }
+    
+    Ok(())
+}
+
+/// Project: trust the current project's `.rustfig.yaml`, or revoke that trust
+pub fn cmd_project_trust(revoke: bool) -> Result<(), Box<dyn Error>> {
+    use config::project;
+    
+    let cwd = std::env::current_dir()?;
+    let path = project::find_project_config(&cwd)
+        .ok_or_else(|| format!("No {} in {} or its parents", project::PROJECT_FILE, cwd.display()))?;
+    let content = fs::read_to_string(&path)?;
+    
+    let config_dir = config::loader::get_config_dir()?;
+    project::set_trust(&config_dir, &path, &content, !revoke)?;
+    
+    if revoke {
+        println!("No longer applying {}", path.display());
+    } else {
+        for line in content.lines() {
+            println!("  | {}", line);
+        }
+        println!("Trusted {}; it applies until its contents change", path.display());
+    }
+    
+    Ok(())
+}
+
+/// Browse and curate the snippet library interactively
+pub fn cmd_snippets() -> Result<(), Box<dyn Error>> {
+    let config = config::loader::load_config()?;
+    let snippets_dir = match config.suggestions.snippets_dir {
+        Some(dir) => dir,
+        None => config::loader::get_config_dir()?.join(crate::suggestion::snippets::SNIPPETS_DIR),
+    };
+    fs::create_dir_all(&snippets_dir)?;
+    
+    crate::terminal::SnippetManager::new(snippets_dir).run()
+}
+
+/// Run a workflow, prompting for any parameters not given as `--name value`
+pub fn cmd_run(workflow_name: &str, args: &[String], dry_run: bool, yes: bool) -> Result<(), Box<dyn Error>> {
+    use crate::suggestion::workflows::load_workflows;
+    
+    let cwd = std::env::current_dir()?;
+    let workflows = load_workflows(&cwd);
+    let workflow = match workflows.iter().find(|workflow| workflow.name == workflow_name) {
+        Some(workflow) => workflow,
+        None => {
+            let names: Vec<&str> = workflows.iter().map(|workflow| workflow.name.as_str()).collect();
+            return Err(format!("Unknown workflow '{}'. Available: {}", workflow_name, names.join(", ")).into());
+        }
+    };
+    
+    // Collect `--name value` and `--name=value` from the command line
+    let mut given: HashMap<String, String> = HashMap::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let flag = match arg.strip_prefix("--") {
+            Some(flag) => flag,
+            None => return Err(format!("Unexpected argument '{}'", arg).into()),
+        };
+        
+        let (name, value) = match flag.split_once('=') {
+            Some((name, value)) => (name.to_string(), value.to_string()),
+            None => {
+                let value = iter.next().ok_or_else(|| format!("Missing value for --{}", flag))?;
+                (flag.to_string(), value.clone())
+            }
+        };
+        given.insert(name, value);
+    }
+    
+    if let Some(unknown) = given.keys().find(|name| !workflow.parameters.iter().any(|p| &p.name == *name)) {
+        return Err(format!("Workflow '{}' has no parameter '{}'", workflow.name, unknown).into());
+    }
+    
+    println!("Workflow: {}", workflow.name);
+    if let Some(description) = &workflow.description {
+        println!("  {}", description);
+    }
+    
+    // Validate given values and prompt for the rest
+    let stdin = io::stdin();
+    let mut values = HashMap::new();
+    for parameter in &workflow.parameters {
+        let value = match given.get(&parameter.name) {
+            Some(value) => parameter.validate(value)?,
+            None => loop {
+                let mut prompt = parameter.name.clone();
+                if let Some(description) = &parameter.description {
+                    prompt.push_str(&format!(" ({})", description));
+                }
+                if !parameter.choices.is_empty() {
+                    prompt.push_str(&format!(" [{}]", parameter.choices.join("/")));
+                }
+                if let Some(default) = &parameter.default {
+                    prompt.push_str(&format!(" <{}>", default));
+                }
+                print!("{}: ", prompt);
+                io::stdout().flush()?;
+                
+                let mut line = String::new();
+                if stdin.lock().read_line(&mut line)? == 0 {
+                    return Err("Aborted".into());
+                }
+                let line = line.trim();
+                let input = if line.is_empty() { parameter.default.as_deref().unwrap_or("") } else { line };
+                
+                match parameter.validate(input) {
+                    Ok(value) => break value,
+                    Err(message) => println!("  {}", message),
+                }
+            },
+        };
+        values.insert(parameter.name.clone(), value);
+    }
+    
+    for (index, step) in workflow.steps.iter().enumerate() {
+        let command = workflow.render_step(step, &values);
+        println!("\n[{}/{}] {}", index + 1, workflow.steps.len(), step.name);
+        println!("  $ {}", command);
+        
+        if dry_run {
+            continue;
+        }
+        
+        if step.confirm && !yes {
+            print!("Run this step? [y/N] ");
+            io::stdout().flush()?;
+            
+            let mut answer = String::new();
+            stdin.lock().read_line(&mut answer)?;
+            if !matches!(answer.trim(), "y" | "Y" | "yes") {
+                println!("Stopped before '{}'", step.name);
+                return Ok(());
+            }
+        }
+        
+        let status = Command::new("sh").arg("-c").arg(&command).status()?;
+        if !status.success() {
+            if step.continue_on_error {
+                println!("  Step failed ({}), continuing", status);
+            } else {
+                return Err(format!("Step '{}' failed ({})", step.name, status).into());
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Record a finished command with its exit status and duration (called from the shell
+/// hooks); the running service records it and takes it into account for predictions,
+/// otherwise it's written to the history database directly
+pub fn cmd_record_command(
+    command: &str,
+    dir: Option<&str>,
+    shell: Option<&str>,
+    exit_code: Option<i32>,
+    duration_ms: Option<u64>,
+) -> Result<(), Box<dyn Error>> {
+    use crate::service::protocol::{Request, Response};
+    use crate::shell::history::{HistoryDb, HistoryEntry};
+    
+    let config = config::loader::load_config()?;
+    
+    let mut entry = HistoryEntry::new(command);
+    entry.cwd = dir.map(PathBuf::from);
+    entry.shell = shell.map(|s| s.to_string());
+    entry.exit_code = exit_code;
+    entry.duration_ms = duration_ms;
+    // Reported when it finished; the timestamp is when it started
+    entry.timestamp = entry.timestamp.saturating_sub(duration_ms.unwrap_or(0) / 1000);
+    
+    let request = Request::RecordCommand { entry: entry.clone() };
+    match crate::service::client::request(&crate::service::socket_path(&config), &request) {
+        Ok(Response::Recorded) => Ok(()),
+        _ => HistoryDb::new(&config.general.user_data_dir).record(&entry),
+    }
+}
+
+/// Print recorded and native history, newest first, optionally only commands containing
+/// `search`
+pub fn cmd_history(limit: usize, search: Option<&str>) -> Result<(), Box<dyn Error>> {
+    use crate::shell::history::HistoryDb;
+    
+    let config = config::loader::load_config()?;
+    let shell = detect_current_shell()?;
+    let search = search.map(|search| search.to_lowercase());
+    
+    let entries = HistoryDb::from_config(&config).load_unified(&shell);
+    let matching = entries
+        .iter()
+        .filter(|entry| search.as_ref().map_or(true, |search| entry.command.to_lowercase().contains(search)))
+        .take(limit);
+    for entry in matching {
+        println!("{}", entry.command);
+    }
+    
+    Ok(())
+}
+
+/// Interactive fuzzy history search; prints the chosen command(s) for the shell widget
+pub fn cmd_history_search(query: &str, shell_override: Option<&str>) -> Result<(), Box<dyn Error>> {
+    use crate::shell::history::HistoryDb;
+    
+    let config = config::loader::load_config()?;
+    let shell = match shell_override {
+        Some(shell) => shell.to_string(),
+        None => detect_current_shell()?,
+    };
+    
+    let entries = HistoryDb::from_config(&config).load_unified(&shell);
+    let cwd = std::env::current_dir().ok();
+    
+    if let Some(chosen) = crate::terminal::HistorySearch::new(entries, query, cwd).run()? {
+        print!("{}", chosen);
+    }
+    
+    Ok(())
+}
+
+/// Print what the best prediction adds to the end of a command line, or nothing; the zsh
+/// integration shows it as ghost text through ZLE's POSTDISPLAY
+///
+/// Only the running service is asked: this runs as the user types, and a fresh engine
+/// would load history and learning data first. Without the service there's no ghost text.
+pub fn cmd_ghost(line: &str) -> Result<(), Box<dyn Error>> {
+    use crate::service::protocol::{Request, Response};
+    
+    let config = config::loader::load_config()?;
+    if !config.general.enable_ghost_text.unwrap_or(true) || line.trim().is_empty() {
+        return Ok(());
+    }
+    
+    let request = Request::Predict { input: line.to_string(), cursor: line.len(), limit: 1 };
+    if let Ok(Response::Predictions { predictions }) = crate::service::client::request(&crate::service::socket_path(&config), &request) {
+        if let Some(prediction) = predictions.into_iter().next() {
+            print!("{}", prediction.into_prediction().get_ghost_text(line));
+        }
+    }
+    Ok(())
+}
+
+/// Predict a command line with the cursor at byte `pos` (the end by default)
+///
+/// `completion` prints the whole line with the best prediction applied, for the Tab
+/// bindings to put on the command line, or nothing; `text` lists the predictions.
+pub async fn cmd_predict(line: &str, pos: Option<usize>, format: &str) -> Result<(), Box<dyn Error>> {
+    use crate::prediction::{Prediction, PredictionEngine};
+    use crate::service::protocol::{Request, Response};
+    
+    let config = config::loader::load_config()?;
+    let mut cursor = pos.unwrap_or(line.len()).min(line.len());
+    while !line.is_char_boundary(cursor) {
+        cursor -= 1;
+    }
+    let limit = if format == "completion" { 1 } else { 10 };
+    
+    // Tab was pressed, so a cold engine is worth waiting for when the service is down
+    let request = Request::Predict { input: line.to_string(), cursor, limit };
+    let predictions: Vec<Prediction> = match crate::service::client::request(&crate::service::socket_path(&config), &request) {
+        Ok(Response::Predictions { predictions }) => predictions.into_iter().map(|prediction| prediction.into_prediction()).collect(),
+        _ => PredictionEngine::new(&config).predict(line, cursor, limit).await,
+    };
+    
+    let (before, after) = line.split_at(cursor);
+    match format {
+        "completion" => {
+            if let Some(prediction) = predictions.first() {
+                print!("{}{}{}", before, prediction.get_ghost_text(before), after);
+            }
+        }
+        "text" => {
+            for prediction in &predictions {
+                println!("{:.2}  {}", prediction.confidence.value(), prediction.text);
+            }
+        }
+        other => return Err(format!("Unknown format '{}'; use 'completion' or 'text'", other).into()),
+    }
+    Ok(())
+}
+
+/// Record a visit to a directory (called from the shell's cd hooks)
+pub fn cmd_record_dir(dir: &str) -> Result<(), Box<dyn Error>> {
+    let config = config::loader::load_config()?;
+    crate::suggestion::frecency::FrecencyDb::new(&config.general.user_data_dir).visit(&PathBuf::from(dir))
+}
+
+/// Print the packages that provide a missing command and how to install them; fails when
+/// none is known
+///
+/// With `cached` (as the shells' command-not-found hooks run it) only a remembered answer
+/// is printed, so a typo never waits on a package index; a miss is looked up by a
+/// background `rustfig whichpkg` for next time.
+pub fn cmd_whichpkg(command: &str, refresh: bool, cached: bool) -> Result<(), Box<dyn Error>> {
+    use crate::shell::PackageFinder;
+    
+    if refresh && cached {
+        return Err("--refresh and --cached can't be combined".into());
+    }
+    
+    let config = config::loader::load_config()?;
+    let finder = PackageFinder::from_config(&config);
+    let matches = if cached {
+        match finder.cached(command) {
+            Some(matches) => matches,
+            None => {
+                if !claim_background_lookup(command) {
+                    return Err(format!("'{}' is being looked up, or was recently and isn't known", command).into());
+                }
+                let mut lookup = Command::new(std::env::current_exe()?);
+                lookup.arg("whichpkg").arg(command);
+                lookup.stdin(Stdio::null());
+                lookup.stdout(Stdio::null());
+                lookup.stderr(Stdio::null());
+                // Its own process group, so Ctrl+C at the next prompt doesn't cut it short
+                #[cfg(unix)]
+                {
+                    use std::os::unix::process::CommandExt;
+                    lookup.process_group(0);
+                }
+                lookup.spawn()?;
+                return Err(format!("'{}' hasn't been looked up yet; looking it up in the background", command).into());
+            }
+        }
+    } else {
+        finder.find(command, refresh)?
+    };
+    if matches.is_empty() {
+        return Err(format!("No known package provides '{}'", command).into());
+    }
+    
+    println!("'{}' isn't installed; it's provided by:", command);
+    for package in &matches {
+        println!("  {:<24} {}", package.package, package.install_command());
+    }
+    Ok(())
+}
+
+/// Whether a background lookup of `command` should start: not while one is running or
+/// within `WHICHPKG_RETRY` of the last, as recorded by a `whichpkg.<command>` marker in
+/// the private runtime directory
+#[cfg(unix)]
+fn claim_background_lookup(command: &str) -> bool {
+    const WHICHPKG_RETRY: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+    // Names with a slash are paths, which no package index lists
+    if command.is_empty() || command.contains('/') {
+        return false;
+    }
+    let Ok(dir) = crate::shell::private_runtime_dir() else { return false };
+    let marker = dir.join(format!("whichpkg.{}", command));
+    if let Ok(modified) = fs::metadata(&marker).and_then(|metadata| metadata.modified()) {
+        if modified.elapsed().map_or(true, |age| age < WHICHPKG_RETRY) {
+            return false;
+        }
+        let _ = fs::remove_file(&marker);
+    }
+    // Of two shells missing the same command at once, only the one that creates the
+    // marker looks it up
+    fs::OpenOptions::new().write(true).create_new(true).open(&marker).is_ok()
+}
+
+#[cfg(not(unix))]
+fn claim_background_lookup(_command: &str) -> bool {
+    true
+}
+
+/// Print the best frecent directory matching the query, or all matches with `--list`
+pub fn cmd_z(terms: &[String], list: bool) -> Result<(), Box<dyn Error>> {
+    let config = config::loader::load_config()?;
+    let terms: Vec<&str> = terms.iter().map(|term| term.as_str()).collect();
+    let matches = crate::suggestion::frecency::FrecencyDb::new(&config.general.user_data_dir).query(&terms);
+    
+    if list {
+        for (entry, score) in &matches {
+            println!("{:>8.1}  {}", score, entry.path.display());
+        }
+        return Ok(());
+    }
+    
+    match matches.first() {
+        Some((entry, _)) => {
+            println!("{}", entry.path.display());
+            Ok(())
+        },
+        None => Err(format!("No visited directory matches '{}'", terms.join(" ")).into()),
+    }
+}
+
+/// Bookmark a command
+pub fn cmd_bookmark_add(command: &str, description: Option<&str>, tags: &[String]) -> Result<(), Box<dyn Error>> {
+    use crate::suggestion::bookmarks::{Bookmark, BookmarkStore};
+    
+    let config = config::loader::load_config()?;
+    let store = BookmarkStore::new(&config.general.user_data_dir);
+    
+    let tags = tags.iter()
+        .flat_map(|tag| tag.split(','))
+        .map(|tag| tag.trim().trim_start_matches('#').to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    
+    store.add(Bookmark::new(command, description.map(|d| d.to_string()), tags))?;
+    println!("Bookmarked: {}", command.trim());
+    
+    Ok(())
+}
+
+/// List bookmarks, optionally only those tagged `tag`
+pub fn cmd_bookmark_list(tag: Option<&str>, format: &str) -> Result<(), Box<dyn Error>> {
+    use crate::suggestion::bookmarks::BookmarkStore;
+    
+    let config = config::loader::load_config()?;
+    let bookmarks = BookmarkStore::new(&config.general.user_data_dir).load();
+    
+    // Keep the 1-based numbers stable so `bookmark remove N` matches the full list
+    let tag = tag.map(|tag| tag.trim_start_matches('#'));
+    let listed: Vec<(usize, &_)> = bookmarks.iter()
+        .enumerate()
+        .filter(|(_, bookmark)| tag.map_or(true, |tag| bookmark.tags.iter().any(|t| t == tag)))
+        .collect();
+    
+    match format {
+        "json" => {
+            let selected: Vec<_> = listed.iter().map(|(_, bookmark)| *bookmark).collect();
+            println!("{}", serde_json::to_string_pretty(&selected)?);
+        },
+        "yaml" => {
+            let selected: Vec<_> = listed.iter().map(|(_, bookmark)| *bookmark).collect();
+            println!("{}", serde_yaml::to_string(&selected)?);
+        },
+        _ => {
+            if listed.is_empty() {
+                println!("No bookmarks. Add one with: rustfig bookmark add \"COMMAND\"");
+            }
+            for (index, bookmark) in listed {
+                println!("{:>3}. {}", index + 1, bookmark.command);
+                if let Some(description) = &bookmark.description {
+                    println!("     {}", description);
+                }
+                if !bookmark.tags.is_empty() {
+                    let tags: Vec<String> = bookmark.tags.iter().map(|t| format!("#{}", t)).collect();
+                    println!("     {}", tags.join(" "));
+                }
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+/// Remove a bookmark by number (as shown by `bookmark list`) or exact command
+pub fn cmd_bookmark_remove(target: &str) -> Result<(), Box<dyn Error>> {
+    use crate::suggestion::bookmarks::BookmarkStore;
+    
+    let config = config::loader::load_config()?;
+    let removed = BookmarkStore::new(&config.general.user_data_dir).remove(target)?;
+    println!("Removed bookmark: {}", removed.command);
+    
+    Ok(())
+}
+
+/// Stats: what rustfig has done for the user, from local data only
+///
+/// Top commands come from the recorded history; acceptance counts and source latencies
+/// from the counters the prompt keeps in `stats.json`. `reset` clears the counters.
+pub fn cmd_stats(reset: bool, format: &str) -> Result<(), Box<dyn Error>> {
+    use crate::shell::history::HistoryDb;
+    use crate::utils::usage_stats::UsageStats;
+    
+    #[derive(serde::Serialize)]
+    struct SourceReport<'a> {
+        source: &'a str,
+        calls: u64,
+        avg_us: u64,
+        max_us: u64,
+    }
+    
+    #[derive(serde::Serialize)]
+    struct StatsReport<'a> {
+        since: u64,
+        top_commands: Vec<(&'a str, usize)>,
+        ghost_shown: u64,
+        ghost_accepted: u64,
+        ghost_acceptance_rate: Option<f64>,
+        suggestions_accepted: u64,
+        keystrokes_saved: u64,
+        slowest_sources: Vec<SourceReport<'a>>,
+    }
+    
+    let config = config::loader::load_config()?;
+    let user_data_dir = &config.general.user_data_dir;
+    
+    if reset {
+        UsageStats::reset(user_data_dir)?;
+        println!("Usage statistics cleared");
+        return Ok(());
+    }
+    
+    let stats = UsageStats::load(user_data_dir);
+    let history = HistoryDb::from_config(&config).load();
+    
+    // By program, which says more than exact lines and keeps arguments off the screen
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for entry in &history {
+        if let Some(program) = entry.command.split_whitespace().next() {
+            *counts.entry(program).or_default() += 1;
+        }
+    }
+    let mut top_commands: Vec<(&str, usize)> = counts.into_iter().collect();
+    top_commands.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    top_commands.truncate(10);
+    
+    let report = StatsReport {
+        since: stats.since,
+        top_commands,
+        ghost_shown: stats.ghost_shown,
+        ghost_accepted: stats.ghost_accepted,
+        ghost_acceptance_rate: stats.ghost_acceptance_rate(),
+        suggestions_accepted: stats.suggestions_accepted,
+        keystrokes_saved: stats.keystrokes_saved(),
+        slowest_sources: stats
+            .slowest_sources()
+            .into_iter()
+            .map(|(source, timing)| SourceReport { source, calls: timing.calls, avg_us: timing.avg_us(), max_us: timing.max_us })
+            .collect(),
+    };
+    
+    match format {
+        "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+        "yaml" => println!("{}", serde_yaml::to_string(&report)?),
+        "text" => {
+            println!("Top commands ({} recorded):", history.len());
+            if report.top_commands.is_empty() {
+                println!("  None recorded yet");
+            }
+            for (program, count) in &report.top_commands {
+                println!("  {:<20} {:>6}", program, count);
+            }
+            
+            println!("\nSuggestions:");
+            match report.ghost_acceptance_rate {
+                Some(rate) => println!(
+                    "  Ghost text accepted {} of {} times ({:.0}%)",
+                    report.ghost_accepted, report.ghost_shown, rate * 100.0
+                ),
+                None => println!("  No ghost text shown yet"),
+            }
+            println!("  Dropdown suggestions accepted: {}", report.suggestions_accepted);
+            println!("  Keystrokes saved (estimated): {}", report.keystrokes_saved);
+            
+            if !report.slowest_sources.is_empty() {
+                println!("\nSlowest suggestion sources:");
+                println!("  {:<14} {:>8} {:>10} {:>10}", "source", "calls", "avg", "max");
+                for source in report.slowest_sources.iter().take(5) {
+                    println!(
+                        "  {:<14} {:>8} {:>10} {:>10}",
+                        source.source, source.calls, format_micros(source.avg_us), format_micros(source.max_us)
+                    );
+                }
+            }
+        }
+        _ => return Err(format!("Unsupported format: {}", format).into()),
+    }
+    
+    Ok(())
+}
+
+/// Feedback: a short form, saved locally and uploaded only with telemetry enabled
+///
+/// `kind` (`bug`, `suggestion` or `feedback`) picks the category instead of asking.
+pub async fn cmd_feedback(kind: Option<&str>) -> Result<(), Box<dyn Error>> {
+    use crate::telementary::FeedbackCategory;
+    
+    let config = config::loader::load_config()?;
+    crate::telementary::init(&config);
+    let collector = crate::telementary::create_feedback_collector(&config);
+    
+    let stdin = io::stdin();
+    let ask = |prompt: &str| -> Result<String, Box<dyn Error>> {
+        print!("{}", prompt);
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        stdin.lock().read_line(&mut answer)?;
+        Ok(answer.trim().to_string())
+    };
+    
+    let category = match kind {
+        Some("bug") => FeedbackCategory::BugReport,
+        Some("suggestion") => FeedbackCategory::FeatureRequest,
+        Some("feedback") => FeedbackCategory::General,
+        Some(other) => return Err(format!("Unknown feedback type: {} (expected bug, suggestion or feedback)", other).into()),
+        None => {
+            println!("What is this about?");
+            for (index, category) in FeedbackCategory::ALL.iter().enumerate() {
+                println!("  {}. {}", index + 1, category.label());
+            }
+            loop {
+                let answer = ask(&format!("Choose 1-{} [1]: ", FeedbackCategory::ALL.len()))?;
+                if answer.is_empty() {
+                    break FeedbackCategory::General;
+                }
+                match answer.parse::<usize>().ok().and_then(|n| FeedbackCategory::ALL.get(n.wrapping_sub(1))) {
+                    Some(category) => break *category,
+                    None => println!("  Enter a number from the list"),
+                }
+            }
+        }
+    };
+    
+    let rating = loop {
+        let answer = ask("Rating from 1 (poor) to 5 (great), Enter to skip: ")?;
+        if answer.is_empty() {
+            break None;
+        }
+        match answer.parse::<u8>() {
+            Ok(rating @ 1..=5) => break Some(rating),
+            _ => println!("  Enter a number from 1 to 5"),
+        }
+    };
+    
+    println!("Describe it; finish with an empty line:");
+    let mut lines = Vec::new();
+    loop {
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        lines.push(line.trim_end().to_string());
+    }
+    let content = lines.join("\n");
+    if content.is_empty() {
+        println!("Nothing to send; cancelled");
+        return Ok(());
+    }
+    
+    let email = Some(ask("Email, if you'd like a reply (optional): ")?).filter(|email| !email.is_empty());
+    if email.as_deref().map_or(false, |email| !email.contains('@')) {
+        return Err("That doesn't look like an email address; nothing was saved".into());
+    }
+    
+    let uploads = crate::telementary::is_telemetry_enabled();
+    let destination = if uploads { "saved locally and sent" } else { "saved locally only (telemetry is off)" };
+    if !matches!(ask(&format!("This will be {}. Submit? [Y/n] ", destination))?.as_str(), "" | "y" | "Y" | "yes") {
+        println!("Cancelled");
+        return Ok(());
+    }
+    
+    let feedback = collector.create_feedback(category, content, rating, email, category == FeedbackCategory::BugReport);
+    match collector.submit_feedback(feedback).await {
+        Ok(()) if uploads => println!("Thanks! Your feedback was sent."),
+        Ok(()) => println!("Thanks! Saved in {}", crate::telementary::feedback_dir(&config.telemetry.unwrap_or_default()).display()),
+        // It's written to disk before the upload is tried
+        Err(e) if uploads => println!("Saved locally, but sending failed: {}", e),
+        Err(e) => return Err(e),
+    }
+    
+    Ok(())
+}
+
+/// Crash report: list the reports not sent yet
+pub fn cmd_crash_report_list() -> Result<(), Box<dyn Error>> {
+    let reports = crate::utils::crash::unsent_reports();
+    if reports.is_empty() {
+        println!("No crash reports");
+    }
+    for report in &reports {
+        println!("{}", report.display());
+    }
+    Ok(())
+}
+
+/// Crash report: show a report (the newest unless `path` is given) and send it once confirmed
+pub async fn cmd_crash_report_submit(path: Option<&str>, yes: bool) -> Result<(), Box<dyn Error>> {
+    use crate::telementary::FeedbackCategory;
+    
+    let report = match path {
+        Some(path) => PathBuf::from(path),
+        None => match crate::utils::crash::unsent_reports().into_iter().next() {
+            Some(report) => report,
+            None => {
+                println!("No crash reports to send");
+                return Ok(());
+            }
+        },
+    };
+    let content = fs::read_to_string(&report)?;
+    
+    // Show exactly what leaves the machine
+    println!("{}", content);
+    if !yes {
+        print!("Send this report? [y/N] ");
+        io::stdout().flush()?;
+        let mut answer = String::new();
+        io::stdin().lock().read_line(&mut answer)?;
+        if !matches!(answer.trim(), "y" | "Y" | "yes") {
+            println!("Not sent");
+            return Ok(());
+        }
+    }
+    
+    let config = config::loader::load_config()?;
+    let collector = crate::telementary::create_feedback_collector(&config);
+    let feedback = collector.create_feedback(FeedbackCategory::BugReport, content, None, None, true);
+    collector.submit_explicitly(feedback).await?;
+    crate::utils::crash::mark_sent(&report)?;
+    
+    println!("Crash report sent. Thank you!");
+    Ok(())
+}
+
+/// Setup's one-time question about telemetry; skipped when not run interactively
+fn ask_telemetry_consent() -> Result<(), Box<dyn Error>> {
+    let config = config::loader::load_config()?;
+    let telemetry = config.telemetry.unwrap_or_default();
+    if telemetry.consent_asked.unwrap_or(false) || !io::stdin().is_terminal() {
+        return Ok(());
+    }
+    
+    println!();
+    println!("RustFig can send anonymous usage statistics to {}", telemetry.upload_url);
+    println!("to help improve it. Nothing is sent unless you agree, and you can change your");
+    println!("mind with 'rustfig telemetry enable|disable' at any time.");
+    print!("Enable telemetry? [y/N] ");
+    io::stdout().flush()?;
+    
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    let enable = matches!(answer.trim(), "y" | "Y" | "yes");
+    
+    cmd_config_set("telemetry.enabled", if enable { "true" } else { "false" }, false)?;
+    cmd_config_set("telemetry.consent_asked", "true", false)?;
+    Ok(())
+}
+
+/// Telemetry: whether it's on, and what it has stored on this machine
+pub fn cmd_telemetry_status() -> Result<(), Box<dyn Error>> {
+    use crate::utils::system::format_bytes;
+    
+    let config = config::loader::load_config()?;
+    let telemetry = config.telemetry.unwrap_or_default();
+    
+    if telemetry.enabled {
+        println!("Telemetry is enabled; usage data is uploaded to {}", telemetry.upload_url);
+    } else {
+        println!("Telemetry is disabled; nothing is uploaded");
+    }
+    let detail = telemetry.command_detail.unwrap_or(config::schema::CommandDetail::Flags);
+    println!("Command lines are reduced to: {}", format!("{:?}", detail).to_lowercase());
+    
+    let files = crate::telementary::stored_files(&telemetry);
+    if files.is_empty() {
+        println!("No telemetry or feedback data is stored");
+    } else {
+        println!("Stored on this machine:");
+        for file in &files {
+            let size = fs::metadata(file).map(|metadata| metadata.len()).unwrap_or(0);
+            println!("  {} ({})", file.display(), format_bytes(size));
+        }
+        println!("Run 'rustfig telemetry purge' to delete it");
+    }
+    
+    Ok(())
+}
+
+/// Telemetry: turn uploads on or off
+pub fn cmd_telemetry_set(enabled: bool) -> Result<(), Box<dyn Error>> {
+    cmd_config_set("telemetry.enabled", if enabled { "true" } else { "false" }, false)?;
+    cmd_config_set("telemetry.consent_asked", "true", false)?;
+    
+    if !enabled {
+        println!("Data already stored stays until 'rustfig telemetry purge'");
+    }
+    Ok(())
+}
+
+/// Telemetry: delete stored usage data, the anonymous id, and saved feedback
+pub fn cmd_telemetry_purge() -> Result<(), Box<dyn Error>> {
+    let config = config::loader::load_config()?;
+    let removed = crate::telementary::purge(&config.telemetry.unwrap_or_default())?;
+    
+    match removed {
+        0 => println!("No telemetry data to delete"),
+        1 => println!("Deleted 1 file"),
+        n => println!("Deleted {} files", n),
+    }
+    Ok(())
+}
+
+/// Offline mode: `on`, `off`, or no state to show the current one
+pub fn cmd_offline(state: Option<&str>) -> Result<(), Box<dyn Error>> {
+    use crate::utils::offline;
+    
+    match state {
+        Some("on") => {
+            offline::set_forced(true)?;
+            println!("Offline mode is on: no AI providers off this machine, telemetry uploads or remote completion");
+        }
+        Some("off") => {
+            offline::set_forced(false)?;
+            match offline::status() {
+                offline::Status::NoNetwork => println!("Offline mode is off, but no network was detected"),
+                _ => println!("Offline mode is off"),
+            }
+        }
+        Some(other) => return Err(format!("Unknown state '{}'; use 'on' or 'off'", other).into()),
+        None => println!("RustFig is {}", offline::status().description()),
+    }
+    Ok(())
+}
+
+/// Print a glyph for RustFig's state (AI ready, no AI, offline, service down, SSH) for
+/// the prompt; `--starship` prints a starship module that shows it instead
+pub fn cmd_prompt_widget(format: &str, starship: bool) -> Result<(), Box<dyn Error>> {
+    use crate::service::widget::{WidgetState, STARSHIP_MODULE};
+    
+    if starship {
+        print!("{}", STARSHIP_MODULE);
+        return Ok(());
+    }
+    
+    let config = config::loader::load_config()?;
+    let state = WidgetState::detect(&config);
+    match format {
+        "glyph" => print!("{}", state.glyph()),
+        "text" => print!("{}", state.name()),
+        other => return Err(format!("Unknown format '{}'; use 'glyph' or 'text'", other).into()),
+    }
+    Ok(())
+}
+
+/// Convert Fig completion specs (.js, .ts or .json files, or directories of them) into
+/// native specs in the user's spec directory; `output` names another directory, or `-`
+/// for stdout
+pub fn cmd_spec_convert(inputs: &[String], output: Option<&str>, force: bool) -> Result<(), Box<dyn Error>> {
+    use crate::suggestion::fig_spec;
+    use crate::suggestion::spec_files::SPECS_DIR;
+    
+    let mut files = Vec::new();
+    for input in inputs {
+        let path = PathBuf::from(input);
+        if path.is_dir() {
+            let mut found: Vec<PathBuf> = fs::read_dir(&path)?
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|file| matches!(file.extension().and_then(|e| e.to_str()), Some("js" | "mjs" | "ts" | "json")))
+                .collect();
+            found.sort();
+            files.extend(found);
+        } else {
+            files.push(path);
+        }
+    }
+    if files.is_empty() {
+        return Err("No spec files given".into());
+    }
+    
+    let to_stdout = output == Some("-");
+    if to_stdout && files.len() > 1 {
+        return Err("Only one spec can be converted to stdout".into());
+    }
+    let out_dir = match output {
+        Some(dir) if !to_stdout => PathBuf::from(dir),
+        _ => config::loader::get_config_dir()?.join(SPECS_DIR),
+    };
+    
+    let mut failed = 0;
+    for file in &files {
+        let conversion = match fs::read_to_string(file).map_err(Box::<dyn Error>::from).and_then(|source| fig_spec::convert(&source)) {
+            Ok(conversion) => conversion,
+            Err(e) => {
+                eprintln!("{}: {}", file.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+        if to_stdout {
+            print!("{}", serde_yaml::to_string(&conversion.spec)?);
+        } else {
+            let target = match write_spec_file(&conversion.spec, "", &out_dir, force) {
+                Ok(target) => target,
+                Err(e) => {
+                    eprintln!("{}: {}", file.display(), e);
+                    failed += 1;
+                    continue;
+                }
+            };
+            println!(
+                "{}: {} subcommands, {} options -> {}",
+                conversion.spec.name,
+                conversion.spec.subcommands.len(),
+                conversion.spec.options.len(),
+                target.display()
+            );
+        }
+        
+        let dropped = conversion.dropped;
+        if !dropped.is_empty() {
+            eprintln!(
+                "  left out: {} script generators, {} specs loaded at runtime, {} names defined elsewhere",
+                dropped.generators, dropped.loaded_specs, dropped.unresolved
+            );
+        }
+    }
+    
+    if failed > 0 {
+        return Err(format!("{} of {} specs were not converted", failed, files.len()).into());
+    }
+    Ok(())
+}
+
+/// Write a spec as `<name>.yaml` in `dir`, after a `header` comment; an existing file
+/// is only replaced with `force`
+fn write_spec_file(spec: &crate::suggestion::CommandSpec, header: &str, dir: &std::path::Path, force: bool) -> Result<PathBuf, Box<dyn Error>> {
+    let name: String = spec.name.chars().map(|c| if c == '/' || c == '\\' { '_' } else { c }).collect();
+    let target = dir.join(format!("{}.yaml", name));
+    if target.exists() && !force {
+        return Err(format!("{} exists; use --force to replace it", target.display()).into());
+    }
+    
+    fs::create_dir_all(dir)?;
+    let draft = target.with_extension("yaml.tmp");
+    fs::write(&draft, format!("{}{}", header, serde_yaml::to_string(spec)?))?;
+    fs::rename(&draft, &target)?;
+    Ok(target)
+}
+
+/// Generate a skeleton spec for a command from its `--help` output and man page, into
+/// the user's spec directory for hand-tuning; `deep` also reads each subcommand's help
+pub fn cmd_generate_spec(command: &str, output: Option<&str>, deep: bool, force: bool) -> Result<(), Box<dyn Error>> {
+    use crate::suggestion::spec_generator;
+    use crate::suggestion::spec_files::SPECS_DIR;
+    
+    let generated = spec_generator::generate(command, deep)?;
+    let spec = &generated.spec;
+    let header = format!(
+        "# Generated by `rustfig generate-spec` from {}.\n# Review it: descriptions are the first line only, and values are left for you to fill in.\n",
+        generated.sources.join(" and ")
+    );
+    
+    if output == Some("-") {
+        print!("{}{}", header, serde_yaml::to_string(spec)?);
+        return Ok(());
+    }
+    let dir = match output {
+        Some(dir) => PathBuf::from(dir),
+        None => config::loader::get_config_dir()?.join(SPECS_DIR),
+    };
+    let target = write_spec_file(spec, &header, &dir, force)?;
+    
+    println!(
+        "Wrote {} ({} subcommands, {} options, from {})",
+        target.display(),
+        spec.subcommands.len(),
+        spec.options.len(),
+        generated.sources.join(" and ")
+    );
+    println!("Check it with 'rustfig spec lint {}'", target.display());
+    Ok(())
+}
+
+/// Check a spec file for mistakes the loader would silently ignore
+pub fn cmd_spec_lint(file: &str) -> Result<(), Box<dyn Error>> {
+    use crate::suggestion::spec_lint::{self, Severity};
+    
+    let problems = spec_lint::lint_file(std::path::Path::new(file)).map_err(|e| format!("{}: {}", file, e))?;
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    
+    let errors = problems.iter().filter(|problem| problem.severity == Severity::Error).count();
+    if errors > 0 {
+        return Err(format!("{}: {} errors, {} warnings", file, errors, problems.len() - errors).into());
+    }
+    if problems.is_empty() {
+        println!("{}: no problems found", file);
+    }
+    Ok(())
+}
+
+/// Show the suggestions a spec file gives for a command line, as if the cursor were at
+/// its end
+pub fn cmd_spec_test(file: &str, input: &str) -> Result<(), Box<dyn Error>> {
+    use crate::shell::parser::CommandParser;
+    use crate::suggestion::spec_lint;
+    
+    let spec = spec_lint::load(std::path::Path::new(file)).map_err(|e| format!("{}: {}", file, e))?;
+    let parsed = CommandParser::new().parse(input, input.len())?;
+    if parsed.command != spec.name {
+        return Err(format!("The input runs '{}', but {} is a spec for '{}'", parsed.command, file, spec.name).into());
+    }
+    
+    let resolved = spec.resolve(&parsed.args);
+    println!("Completing '{}' in '{}'", parsed.current_arg, resolved.name);
+    
+    let suggestions = spec.suggestions_for(&parsed.args, parsed.current_arg);
+    if suggestions.is_empty() {
+        println!("No suggestions");
+    }
+    for suggestion in &suggestions {
+        match suggestion.description.as_deref().filter(|d| !d.is_empty()) {
+            Some(description) => println!("  {:<30} {:?}  {}", suggestion.display, suggestion.kind, description),
+            None => println!("  {:<30} {:?}", suggestion.display, suggestion.kind),
+        }
+    }
+    Ok(())
+}
+
+/// Ask an AI question
+pub async fn cmd_ask(question: &str, model: Option<&str>, markdown: bool) -> Result<(), Box<dyn Error>> {
+    let config = config::loader::load_config()?;
+    
+    // Create AI provider
+    let ai_provider = match AiProviderFactory::create_provider(&config).await {
+        Some(provider) => provider,
+        None => return Err("No AI provider configured. Check your configuration.".into()),
+    };
+    
+    println!("Asking AI: {}", question);
+    println!();
+    
+    // Measure response time
+    let start = Instant::now();
+    
+    // Query AI
+    let response = ai_provider.query(question).await?;
+    
+    let duration = start.elapsed();
+    
+    // Output result
+    if markdown {
+        println!("{}", response);
+    } else {
+        // Simple markdown stripping for terminal output
+        let response = response.replace("```", "");
+        println!("{}", response);
+    }
+    
+    println!("\nResponse time: {:.2?}", duration);
+    
+    Ok(())
+}
+
+/// Generate a command from a description, printing only the command so the shell
+/// integration can put it on the command line; a leading `#` is ignored
+pub async fn cmd_generate(description: &str, shell: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let config = config::loader::load_config()?;
+    let description = crate::ai::assist::comment_description(description).unwrap_or(description.trim());
+    if description.is_empty() {
+        return Err("Describe the command to generate".into());
+    }
+    
+    let ai_provider = match AiProviderFactory::create_provider(&config).await {
+        Some(provider) => provider,
+        None => return Err("No AI provider configured. Check your configuration.".into()),
+    };
+    
+    let shell = match shell {
+        Some(shell) => shell.to_string(),
+        None => detect_current_shell()?,
+    };
+    let directory = std::env::current_dir()?.display().to_string();
+    let command =
+        crate::ai::assist::command_from_description(ai_provider.as_ref(), &config, description, &shell, &directory)
+            .await?;
+    println!("{}", command);
+    
     Ok(())
 }
 
@@ -705,6 +2598,7 @@ pub async fn cmd_chat(model: Option<&str>, conversation_id: Option<&str>) -> Res
     let conversation_dir = config_dir.join("conversations");
     
     let mut conversation_manager = crate::ai::conversation::ConversationManager::new(&conversation_dir)?;
+    conversation_manager.set_history_budget(crate::ai::conversation::HistoryBudget::for_model(&config, model));
     
     // Handle conversation ID if provided
     if let Some(id) = conversation_id {
@@ -712,7 +2606,55 @@ pub async fn cmd_chat(model: Option<&str>, conversation_id: Option<&str>) -> Res
     }
     
     // Run interactive session
-    conversation_manager.run_interactive_session(ai_provider.as_ref()).await?;
+    conversation_manager.run_interactive_session(&std::sync::Arc::from(ai_provider)).await?;
+    
+    Ok(())
+}
+
+/// Chat: list saved conversations by title, most recent first
+pub fn cmd_chat_list() -> Result<(), Box<dyn Error>> {
+    let conversation_dir = config::loader::get_config_dir()?.join("conversations");
+    let manager = crate::ai::conversation::ConversationManager::new(&conversation_dir)?;
+    
+    let conversations = manager.list_conversations();
+    if conversations.is_empty() {
+        println!("No saved conversations");
+        return Ok(());
+    }
+    
+    for conversation in conversations {
+        println!(
+            "{:<50} {:>16}  {:>3} messages  {}",
+            conversation.title,
+            crate::ai::conversation::format_age(conversation.last_used),
+            conversation.message_count,
+            conversation.id,
+        );
+    }
+    
+    Ok(())
+}
+
+/// Chat: pick a saved conversation to continue, renaming or deleting on the way
+pub async fn cmd_chat_pick(model: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let conversation_dir = config::loader::get_config_dir()?.join("conversations");
+    let mut manager = crate::ai::conversation::ConversationManager::new(&conversation_dir)?;
+    
+    let picked = crate::terminal::ConversationPicker::new(&mut manager).run()?;
+    match picked {
+        Some(id) => cmd_chat(model, Some(&id)).await,
+        None => Ok(()),
+    }
+}
+
+/// Chat: search the messages of every saved conversation
+pub fn cmd_chat_search(query: &str, limit: usize) -> Result<(), Box<dyn Error>> {
+    let conversation_dir = config::loader::get_config_dir()?.join("conversations");
+    let manager = crate::ai::conversation::ConversationManager::new(&conversation_dir)?;
+    
+    let mut hits = manager.search(query);
+    hits.truncate(limit);
+    crate::ai::conversation::print_search_hits(&hits);
     
     Ok(())
 }
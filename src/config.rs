@@ -1,6 +1,12 @@
 // Re-export from the config module
 pub mod loader;
+pub mod init;
 pub mod schema;
+pub mod keybindings;
+pub mod profiles;
+pub mod project;
+pub mod migration;
+pub mod validator;
 
 pub use self::schema::Config;
 pub use self::loader::load_config;
@@ -11,5 +17,7 @@ pub use self::schema::{
     UiConfig,
     SuggestionConfig,
     AiConfig,
+    OllamaConfig,
+    TelemetryConfig,
     ShellConfig,
 };
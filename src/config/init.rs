@@ -1,8 +1,11 @@
 use std::error::Error;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 use std::io::Write;
 
+use super::loader::get_config_dir;
+use super::profiles::{profile_path, PROFILES_DIR};
+
 /// Initialize configuration files for RustFig
 pub fn initialize_config_files() -> Result<(), Box<dyn Error>> {
     // Determine user config directory
@@ -48,33 +51,25 @@ pub fn initialize_config_files() -> Result<(), Box<dyn Error>> {
     fs::create_dir_all(&snippets_dir)?;
     create_file_if_not_exists(
         &snippets_dir.join("examples.yaml"),
-        "# Example snippets\nfind_large_files: \"find . -type f -size +100M -exec ls -lh {} \\;\"\nbackup_dir: \"tar -czvf backup_$(date +%Y%m%d).tar.gz\"\n"
+        "# Example snippets\n# ${1:default} marks a tab stop; write \\$ for a literal dollar sign\nfind_large_files: \"find ${1:.} -type f -size +${2:100M} -exec ls -lh {} \\\\;\"\nbackup_dir:\n  command: \"tar -czvf ${1:backup}_$(date +%Y%m%d).tar.gz ${2:.}\"\n  description: Archive a directory with today's date\n  tags: [backup, tar]\n"
     )?;
     
-    Ok(())
-}
-
-/// Get user configuration directory
-fn get_config_dir() -> Result<PathBuf, Box<dyn Error>> {
-    // Check XDG_CONFIG_HOME first
-    if let Ok(xdg_config_home) = std::env::var("XDG_CONFIG_HOME") {
-        let dir = PathBuf::from(xdg_config_home).join("rustfig");
-        return Ok(dir);
-    }
-    
-    // Then try ~/.config/rustfig
-    if let Some(home_dir) = dirs::home_dir() {
-        let dir = home_dir.join(".config").join("rustfig");
-        return Ok(dir);
-    }
+    // Create profiles directory and an example fully-local profile
+    fs::create_dir_all(config_dir.join(PROFILES_DIR))?;
+    create_file_if_not_exists(
+        &profile_path(&config_dir, "local"),
+        "# Fully-local profile: rustfig profile switch local\n# Profiles only list the keys they change; everything else comes from config.yaml\nai:\n  enabled: false\nsuggestions:\n  enable_ai: false\ntelemetry:\n  enabled: false\n"
+    )?;
     
-    // If all else fails, use ~/.rustfig
-    if let Some(home_dir) = dirs::home_dir() {
-        let dir = home_dir.join(".rustfig");
-        return Ok(dir);
-    }
+    // Create workflows directory and example workflow
+    let workflows_dir = config_dir.join("workflows");
+    fs::create_dir_all(&workflows_dir)?;
+    create_file_if_not_exists(
+        &workflows_dir.join("example.yaml"),
+        "# Example workflow: rustfig run release-tag --version 1.2.0\nname: release-tag\ndescription: Tag and push a release\nparameters:\n  - name: version\n    type: string\n    description: Version to release\n  - name: remote\n    type: choice\n    choices: [origin, upstream]\n    default: origin\nsteps:\n  - name: Tag\n    run: git tag -a v{{version}} -m \"Release {{version}}\"\n  - name: Push\n    run: git push {{remote}} v{{version}}\n    confirm: true\n"
+    )?;
     
-    Err("Could not determine configuration directory".into())
+    Ok(())
 }
 
 /// Create the directory structure for configuration
@@ -86,6 +81,8 @@ fn create_directory_structure(config_dir: &Path) -> Result<(), Box<dyn Error>> {
     fs::create_dir_all(config_dir.join("themes"))?;
     fs::create_dir_all(config_dir.join("plugins"))?;
     fs::create_dir_all(config_dir.join("snippets"))?;
+    fs::create_dir_all(config_dir.join("workflows"))?;
+    fs::create_dir_all(config_dir.join(PROFILES_DIR))?;
     fs::create_dir_all(config_dir.join("cache"))?;
     fs::create_dir_all(config_dir.join("logs"))?;
     fs::create_dir_all(config_dir.join("data"))?;
@@ -112,7 +109,7 @@ pub fn generate_default_config(output_path: &Path) -> Result<(), Box<dyn Error>>
     
     // Write default configuration
     let mut file = fs::File::create(output_path)?;
-    file.write_all(include_str!("../../resources/config.yaml").as_bytes())?;
+    file.write_all(include_str!("../../resources/config/config.yaml").as_bytes())?;
     
     Ok(())
 }
@@ -126,7 +123,7 @@ pub fn generate_default_keybindings(output_path: &Path) -> Result<(), Box<dyn Er
     
     // Write default keybindings
     let mut file = fs::File::create(output_path)?;
-    file.write_all(include_str!("../../resources/keybindings.yaml").as_bytes())?;
+    file.write_all(include_str!("../../resources/config/keybindings.yaml").as_bytes())?;
     
     Ok(())
 }
@@ -140,7 +137,7 @@ pub fn generate_default_appearance(output_path: &Path) -> Result<(), Box<dyn Err
     
     // Write default appearance
     let mut file = fs::File::create(output_path)?;
-    file.write_all(include_str!("../../resources/appearance.yaml").as_bytes())?;
+    file.write_all(include_str!("../../resources/config/appearance.yaml").as_bytes())?;
     
     Ok(())
 }
@@ -154,7 +151,7 @@ pub fn generate_default_ai_models(output_path: &Path) -> Result<(), Box<dyn Erro
     
     // Write default AI models
     let mut file = fs::File::create(output_path)?;
-    file.write_all(include_str!("../../resources/ai_models.yaml").as_bytes())?;
+    file.write_all(include_str!("../../resources/config/ai_models.yaml").as_bytes())?;
     
     Ok(())
 }
@@ -0,0 +1,65 @@
+//! Keyboard shortcuts, as read from keybindings.yaml.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The bundled keybindings.yaml, used when the user has none
+const DEFAULT_KEYBINDINGS: &str = include_str!("../../resources/config/keybindings.yaml");
+
+/// Something a key can be bound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum KeyAction {
+    AcceptGhost,
+    ShowSuggestions,
+    CompleteSuggestion,
+    NextSuggestion,
+    PrevSuggestion,
+    NextPage,
+    PrevPage,
+    ExplainCommand,
+    #[serde(rename = "AskAI")]
+    AskAi,
+    StartChat,
+    Cancel,
+    ToggleGhost,
+    ToggleDropdown,
+    OpenSettings,
+    BookmarkCommand,
+    TogglePathDetails,
+    ShowHistory,
+}
+
+/// A key with its modifiers, written like `Ctrl+Shift+a`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct KeyCombination(pub String);
+
+impl fmt::Display for KeyCombination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Actions and the keys that trigger them, plus keys bound to shell commands
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Keybindings {
+    #[serde(flatten)]
+    pub actions: HashMap<KeyAction, Vec<KeyCombination>>,
+
+    /// Keys that run a shell command
+    #[serde(rename = "CustomCommands", default)]
+    pub custom_commands: HashMap<KeyCombination, String>,
+}
+
+impl Keybindings {
+    /// The bindings shipped in keybindings.yaml
+    pub fn default_bindings() -> Self {
+        serde_yaml::from_str(DEFAULT_KEYBINDINGS).unwrap_or_default()
+    }
+
+    /// Keys bound to `action`
+    pub fn keys_for(&self, action: KeyAction) -> &[KeyCombination] {
+        self.actions.get(&action).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
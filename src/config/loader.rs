@@ -0,0 +1,320 @@
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use serde_yaml::{Mapping, Value};
+
+use super::migration;
+use super::profiles;
+use super::project;
+use super::schema::Config;
+use super::validator::{self, Severity};
+
+/// Main configuration file inside the config directory
+pub const CONFIG_FILE: &str = "config.yaml";
+
+/// Keybindings file, mounted at `keybindings`
+pub const KEYBINDINGS_FILE: &str = "keybindings.yaml";
+
+/// Themes and styling file, mounted at `appearance`
+pub const APPEARANCE_FILE: &str = "appearance.yaml";
+
+/// AI model catalog, mounted at `ai_models`
+pub const AI_MODELS_FILE: &str = "ai_models.yaml";
+
+/// Environment variables overriding single keys: `RUSTFIG_UI__THEME=nord` sets `ui.theme`
+pub const ENV_PREFIX: &str = "RUSTFIG_";
+
+/// Separates key path segments in override variable names
+const ENV_SEPARATOR: &str = "__";
+
+/// Where a layer of configuration comes from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LayerSource {
+    /// Built-in defaults
+    Default,
+    /// A file in the config directory
+    File,
+    /// The active profile's overlay
+    Profile,
+    /// A trusted `.rustfig.yaml` in the working directory or above
+    Project,
+    /// `RUSTFIG_SECTION__KEY` environment variables
+    Env,
+}
+
+/// One source of configuration values, already shaped like the full config
+#[derive(Debug, Clone)]
+pub struct ConfigLayer {
+    pub source: LayerSource,
+    /// File the layer was read from (`None` for defaults)
+    pub path: Option<PathBuf>,
+    pub value: Value,
+}
+
+impl ConfigLayer {
+    /// Human-readable origin, e.g. for error messages
+    pub fn origin(&self) -> String {
+        match (&self.path, self.source) {
+            (Some(path), _) => path.display().to_string(),
+            (None, LayerSource::Env) => format!("environment ({}*)", ENV_PREFIX),
+            (None, _) => "built-in defaults".to_string(),
+        }
+    }
+}
+
+/// Get user configuration directory
+pub fn get_config_dir() -> Result<PathBuf, Box<dyn Error>> {
+    // Check XDG_CONFIG_HOME first
+    if let Ok(xdg_config_home) = std::env::var("XDG_CONFIG_HOME") {
+        let dir = PathBuf::from(xdg_config_home).join("rustfig");
+        return Ok(dir);
+    }
+
+    // Then try ~/.config/rustfig
+    if let Some(home_dir) = dirs::home_dir() {
+        let dir = home_dir.join(".config").join("rustfig");
+        return Ok(dir);
+    }
+
+    Err("Could not determine configuration directory".into())
+}
+
+/// Load the effective configuration
+///
+/// Layers are applied in order, later ones winning key by key:
+///
+/// 1. built-in defaults
+/// 2. `keybindings.yaml`, `appearance.yaml`, `ai_models.yaml` for their own sections
+/// 3. `config.yaml`, which wins where it overlaps them (e.g. `ui.theme` over `active_theme`)
+/// 4. the active profile
+/// 5. the project's `.rustfig.yaml`, once trusted (see [`project`])
+/// 6. `RUSTFIG_SECTION__KEY` environment variables
+///
+/// Files only need the keys they change. A file that fails to parse, or whose values
+/// don't fit the schema, is reported by name. `config.yaml` and profiles written for an
+/// older config version are upgraded first (see [`migration`]).
+pub fn load_config() -> Result<Config, Box<dyn Error>> {
+    let config_dir = get_config_dir()?;
+    load_config_from(&config_dir)
+}
+
+/// Load the effective configuration from a specific config directory
+pub fn load_config_from(config_dir: &Path) -> Result<Config, Box<dyn Error>> {
+    let layers = config_layers(config_dir)?;
+    let mut config = build_config(&layers)?;
+
+    config.general.user_data_dir = expand_home(&config.general.user_data_dir);
+    if let Some(dir) = &mut config.suggestions.snippets_dir {
+        *dir = expand_home(dir);
+    }
+    if let Some(dirs) = &mut config.suggestions.spec_dirs {
+        dirs.iter_mut().for_each(|dir| *dir = expand_home(dir));
+    }
+    resolve_theme(&mut config);
+
+    Ok(config)
+}
+
+/// Every layer of configuration for a config directory, in precedence order
+pub fn config_layers(config_dir: &Path) -> Result<Vec<ConfigLayer>, Box<dyn Error>> {
+    let mut layers = vec![ConfigLayer {
+        source: LayerSource::Default,
+        path: None,
+        value: serde_yaml::to_value(Config::default())?,
+    }];
+
+    let mut push_file = |file: &str, mount: fn(Value) -> Value| -> Result<(), Box<dyn Error>> {
+        let path = config_dir.join(file);
+        if let Some(value) = read_yaml(&path)? {
+            layers.push(ConfigLayer { source: LayerSource::File, path: Some(path), value: mount(value) });
+        }
+        Ok(())
+    };
+
+    push_file(KEYBINDINGS_FILE, |value| mounted("keybindings", value))?;
+    push_file(APPEARANCE_FILE, mount_appearance)?;
+    push_file(AI_MODELS_FILE, |value| mounted("ai_models", value))?;
+
+    let path = config_dir.join(CONFIG_FILE);
+    if let Some(mut value) = read_yaml(&path)? {
+        migration::migrate_file(&path, &mut value)?;
+        layers.push(ConfigLayer { source: LayerSource::File, path: Some(path), value });
+    }
+
+    if let Some((name, _)) = profiles::active_profile(config_dir) {
+        let path = profiles::profile_path(config_dir, &name);
+        match read_yaml(&path)? {
+            Some(mut value) => {
+                migration::migrate_file(&path, &mut value)?;
+                layers.push(ConfigLayer { source: LayerSource::Profile, path: Some(path), value });
+            }
+            None if path.exists() => {}
+            None => return Err(format!("Active profile '{}' not found (expected {})", name, path.display()).into()),
+        }
+    }
+
+    if let Ok(cwd) = std::env::current_dir() {
+        if let Some((path, mut value)) = project::load_trusted(config_dir, &cwd)? {
+            // Project files belong to the repository, so they're upgraded in memory only
+            migration::migrate(&mut value).map_err(|e| format!("{}: {}", path.display(), e))?;
+            layers.push(ConfigLayer { source: LayerSource::Project, path: Some(path), value });
+        }
+    }
+
+    if let Some(value) = env_overrides(std::env::vars()) {
+        layers.push(ConfigLayer { source: LayerSource::Env, path: None, value });
+    }
+
+    Ok(layers)
+}
+
+/// The layer a key's effective value comes from: the last one that sets it
+pub fn value_origin<'a>(layers: &'a [ConfigLayer], path: &[&str]) -> Option<&'a ConfigLayer> {
+    layers.iter().rev().find(|layer| {
+        let mut value = &layer.value;
+        for key in path {
+            match value.get(*key) {
+                Some(inner) => value = inner,
+                None => return false,
+            }
+        }
+        true
+    })
+}
+
+/// Environment variable that overrides a key path, e.g. `RUSTFIG_UI__THEME` for `ui.theme`
+pub fn env_var_name(path: &[&str]) -> String {
+    format!("{}{}", ENV_PREFIX, path.join(ENV_SEPARATOR).to_ascii_uppercase())
+}
+
+/// Collect `RUSTFIG_SECTION__KEY=value` variables into one overlay
+///
+/// Names without a `__` (like `RUSTFIG_PROFILE`) aren't overrides. Values are read as
+/// YAML so `true` and `20` keep their types.
+fn env_overrides(vars: impl Iterator<Item = (String, String)>) -> Option<Value> {
+    let mut overlay = Value::Null;
+
+    for (name, raw) in vars {
+        let key = match name.strip_prefix(ENV_PREFIX) {
+            Some(key) if key.contains(ENV_SEPARATOR) => key.to_ascii_lowercase(),
+            _ => continue,
+        };
+        let path: Vec<&str> = key.split(ENV_SEPARATOR).collect();
+        if path.iter().any(|segment| segment.is_empty()) {
+            continue;
+        }
+
+        let value = serde_yaml::from_str(&raw).unwrap_or_else(|_| Value::String(raw.clone()));
+        merge_values(&mut overlay, path.iter().rev().fold(value, |inner, key| mounted(key, inner)));
+    }
+
+    if overlay.is_null() { None } else { Some(overlay) }
+}
+
+/// Merge layers and deserialize, blaming the first layer that makes the schema fail
+pub fn build_config(layers: &[ConfigLayer]) -> Result<Config, Box<dyn Error>> {
+    let mut merged = Value::Null;
+    for layer in layers {
+        merge_values(&mut merged, layer.value.clone());
+    }
+
+    match serde_yaml::from_value(merged) {
+        Ok(config) => Ok(config),
+        Err(e) => {
+            // Replay the layers to find which file introduced the bad value
+            let mut partial = Value::Null;
+            for layer in layers {
+                merge_values(&mut partial, layer.value.clone());
+                if let Err(layer_error) = serde_yaml::from_value::<Config>(partial.clone()) {
+                    // Prefer the validator's key path ("ui.dropdown_width: expected integer, got string")
+                    let detail = validator::validate_value(&partial)
+                        .ok()
+                        .and_then(|issues| issues.into_iter().find(|issue| issue.severity == Severity::Error))
+                        .map_or_else(|| layer_error.to_string(), |issue| issue.to_string());
+                    return Err(format!("{}: {}", layer.origin(), detail).into());
+                }
+            }
+            Err(e.into())
+        }
+    }
+}
+
+/// Read a YAML file; `None` if it doesn't exist or is empty, an error naming it if it's broken
+fn read_yaml(path: &Path) -> Result<Option<Value>, Box<dyn Error>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("{}: {}", path.display(), e).into()),
+    };
+
+    let value: Value = serde_yaml::from_str(&content)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    // An empty file parses as null and changes nothing
+    Ok(if value.is_null() { None } else { Some(value) })
+}
+
+/// Merge a YAML file over `base`; a missing file is skipped, a broken one is an error naming it
+pub fn merge_file(base: &mut Value, path: &Path) -> Result<(), Box<dyn Error>> {
+    if let Some(overlay) = read_yaml(path)? {
+        merge_values(base, overlay);
+    }
+    Ok(())
+}
+
+/// Deep-merge `overlay` into `base`: mappings merge key by key, anything else replaces
+pub fn merge_values(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Mapping(base), Value::Mapping(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Wrap a file's contents under a top-level key
+fn mounted(key: &str, value: Value) -> Value {
+    let mut mapping = Mapping::new();
+    mapping.insert(Value::from(key), value);
+    Value::Mapping(mapping)
+}
+
+/// appearance.yaml lives at `appearance`, and its `active_theme` also selects `ui.theme`
+fn mount_appearance(value: Value) -> Value {
+    let mut mapping = Mapping::new();
+
+    if let Some(theme @ Value::String(_)) = value.get("active_theme").cloned() {
+        mapping.insert(Value::from("ui"), mounted("theme", theme));
+    }
+    mapping.insert(Value::from("appearance"), value);
+
+    Value::Mapping(mapping)
+}
+
+/// Fill in the selected theme's palette unless `ui.colors` is set explicitly
+fn resolve_theme(config: &mut Config) {
+    if config.ui.colors.is_some() {
+        return;
+    }
+
+    let theme = config.appearance.as_ref().and_then(|appearance| appearance.themes.get(&config.ui.theme));
+    if let Some(theme) = theme {
+        config.ui.colors = Some(theme.colors.palette.clone());
+    }
+}
+
+/// Expand a leading `~` so paths written as `~/.rustfig` work
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
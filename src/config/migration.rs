@@ -0,0 +1,130 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use serde_yaml::Value;
+
+/// Version of the config file format this build writes and expects
+pub const CURRENT_VERSION: u64 = 2;
+
+/// One step of the upgrade pipeline
+struct Migration {
+    /// Version this step upgrades from
+    from: u64,
+    description: &'static str,
+    apply: fn(&mut Value),
+}
+
+/// Every upgrade step, oldest first
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 1,
+        description: "ui.dropdown_sort and ui.dropdown_position values are now snake_case",
+        apply: snake_case_dropdown_enums,
+    },
+];
+
+/// Version a parsed config file declares; files from before versioning are version 1
+pub fn file_version(value: &Value) -> u64 {
+    value.get("version").and_then(Value::as_u64).unwrap_or(1)
+}
+
+/// Upgrade a parsed config file to the current version in place
+///
+/// Returns the steps that changed something; the version is only stamped when one did.
+pub fn migrate(value: &mut Value) -> Result<Vec<&'static str>, Box<dyn Error>> {
+    let version = file_version(value);
+    if version > CURRENT_VERSION {
+        return Err(format!(
+            "config version {} is newer than this version of rustfig supports ({})",
+            version, CURRENT_VERSION
+        ).into());
+    }
+
+    let mut applied = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|migration| migration.from >= version) {
+        let before = value.clone();
+        (migration.apply)(value);
+        if *value != before {
+            applied.push(migration.description);
+        }
+    }
+
+    if !applied.is_empty() {
+        if let Value::Mapping(mapping) = value {
+            mapping.insert(Value::from("version"), Value::from(CURRENT_VERSION));
+        }
+    }
+
+    Ok(applied)
+}
+
+/// Migrate a config file that was just read, rewriting it with a backup of the original
+///
+/// Rewriting is best effort: if the file can't be written the migrated values are still
+/// used for this run.
+pub fn migrate_file(path: &Path, value: &mut Value) -> Result<(), Box<dyn Error>> {
+    let version = file_version(value);
+    let applied = migrate(value).map_err(|e| format!("{}: {}", path.display(), e))?;
+    if applied.is_empty() {
+        return Ok(());
+    }
+
+    let backup = path.with_extension(format!("yaml.v{}.bak", version));
+    let written = fs::copy(path, &backup)
+        .map_err(|e| e.to_string())
+        .and_then(|_| serde_yaml::to_string(value).map_err(|e| e.to_string()))
+        .and_then(|yaml| fs::write(path, yaml).map_err(|e| e.to_string()));
+
+    match written {
+        Ok(()) => {
+            eprintln!("Upgraded {} to config version {} (backup at {}):", path.display(), CURRENT_VERSION, backup.display());
+            for description in applied {
+                eprintln!("  - {}", description);
+            }
+        }
+        Err(e) => eprintln!("Could not rewrite {} after upgrading it in memory: {}", path.display(), e),
+    }
+
+    Ok(())
+}
+
+/// Version 1 wrote the dropdown enums as `Relevance`, `MostUsed`, `{Custom: [x, y]}`
+fn snake_case_dropdown_enums(value: &mut Value) {
+    let ui = match value.get_mut("ui") {
+        Some(ui) => ui,
+        None => return,
+    };
+
+    for key in ["dropdown_sort", "dropdown_position"] {
+        match ui.get_mut(key) {
+            Some(Value::String(variant)) => *variant = snake_case(variant),
+            Some(Value::Mapping(tagged)) => {
+                let renamed = std::mem::take(tagged)
+                    .into_iter()
+                    .map(|(variant, inner)| match variant {
+                        Value::String(name) => (Value::String(snake_case(&name)), inner),
+                        other => (other, inner),
+                    })
+                    .collect();
+                *tagged = renamed;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `MostUsed` -> `most_used`; already snake_case input is unchanged
+fn snake_case(name: &str) -> String {
+    let mut result = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                result.push('_');
+            }
+            result.push(c.to_ascii_lowercase());
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
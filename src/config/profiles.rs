@@ -0,0 +1,97 @@
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Directory inside the config directory holding one overlay file per profile
+pub const PROFILES_DIR: &str = "profiles";
+
+/// Environment variable that overrides the active profile (e.g. for one shell)
+pub const PROFILE_ENV_VAR: &str = "RUSTFIG_PROFILE";
+
+/// File recording the profile chosen with `rustfig profile switch`
+const ACTIVE_PROFILE_FILE: &str = "active_profile";
+
+/// Name that means "no profile, just config.yaml"
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Overlay file for a profile
+pub fn profile_path(config_dir: &Path, name: &str) -> PathBuf {
+    config_dir.join(PROFILES_DIR).join(format!("{}.yaml", name))
+}
+
+/// Profile names are file stems, so keep them to something that can't escape the directory
+pub fn validate_profile_name(name: &str) -> Result<(), Box<dyn Error>> {
+    let valid = !name.is_empty()
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid profile name '{}': use letters, digits, '-' and '_'", name).into())
+    }
+}
+
+/// Where the active profile came from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProfileSource {
+    /// `RUSTFIG_PROFILE`
+    Env,
+    /// `rustfig profile switch`
+    Switched,
+}
+
+/// The active profile, if any: `RUSTFIG_PROFILE` wins over the switched profile
+pub fn active_profile(config_dir: &Path) -> Option<(String, ProfileSource)> {
+    if let Ok(name) = env::var(PROFILE_ENV_VAR) {
+        let name = name.trim();
+        // An explicit empty or `default` value turns profiles off for this environment
+        if name.is_empty() || name == DEFAULT_PROFILE {
+            return None;
+        }
+        return Some((name.to_string(), ProfileSource::Env));
+    }
+
+    fs::read_to_string(config_dir.join(ACTIVE_PROFILE_FILE))
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty() && name != DEFAULT_PROFILE)
+        .map(|name| (name, ProfileSource::Switched))
+}
+
+/// Make `name` the active profile; `None` (or `default`) goes back to plain config.yaml
+pub fn set_active_profile(config_dir: &Path, name: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let path = config_dir.join(ACTIVE_PROFILE_FILE);
+
+    match name.filter(|name| *name != DEFAULT_PROFILE) {
+        Some(name) => {
+            validate_profile_name(name)?;
+            fs::create_dir_all(config_dir)?;
+            fs::write(path, format!("{}\n", name))?;
+        }
+        None => {
+            if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Names of the profiles defined in the profiles directory, sorted
+pub fn list_profiles(config_dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(config_dir.join(PROFILES_DIR))
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("yaml"))
+                .filter_map(|path| path.file_stem().and_then(|s| s.to_str()).map(|s| s.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    names.sort();
+    names
+}
@@ -0,0 +1,134 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use sha2::{Digest, Sha256};
+
+/// Project-local overrides, found by walking up from the working directory
+pub const PROJECT_FILE: &str = ".rustfig.yaml";
+
+/// Decisions about project files, kept in the config directory
+const TRUST_FILE: &str = "trusted_projects.json";
+
+/// Keys holding paths, which a project file writes relative to its own directory
+const PROJECT_PATH_KEYS: &[(&str, &str)] = &[
+    ("suggestions", "snippets_dir"),
+    ("suggestions", "spec_dirs"),
+];
+
+/// Project files already reported as untrusted, so reloads don't repeat the note
+static NOTED_UNTRUSTED: Lazy<Mutex<HashSet<PathBuf>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// The user's answer for one version of a project file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TrustDecision {
+    /// SHA-256 of the file contents the decision was made for
+    sha256: String,
+    trusted: bool,
+}
+
+/// Nearest project file at or above `cwd`
+pub fn find_project_config(cwd: &Path) -> Option<PathBuf> {
+    cwd.ancestors()
+        .map(|dir| dir.join(PROJECT_FILE))
+        .find(|path| path.is_file())
+}
+
+/// Load the project file for `cwd` if the user trusts it
+///
+/// A project file can change any setting (including commands rustfig runs), so it
+/// only applies once `rustfig project trust` has recorded its exact contents. Loading
+/// config never prompts: it runs inside the shell hooks and the daemon, where stdin
+/// belongs to someone else. An undecided file is skipped with a note, once per process.
+pub fn load_trusted(config_dir: &Path, cwd: &Path) -> Result<Option<(PathBuf, Value)>, Box<dyn Error>> {
+    let path = match find_project_config(cwd) {
+        Some(path) => path,
+        None => return Ok(None),
+    };
+    let content = fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    let trusted = match decision(config_dir, &path, &content) {
+        Some(trusted) => trusted,
+        None => {
+            if NOTED_UNTRUSTED.lock().insert(path.clone()) {
+                eprintln!("Ignoring untrusted {} (run `rustfig project trust` to apply it)", path.display());
+            }
+            false
+        }
+    };
+    if !trusted {
+        return Ok(None);
+    }
+
+    let mut value: Value = serde_yaml::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+    if value.is_null() {
+        return Ok(None);
+    }
+    if let Some(project_dir) = path.parent() {
+        rebase_paths(&mut value, project_dir);
+    }
+
+    Ok(Some((path, value)))
+}
+
+/// Trust (or stop trusting) the current contents of a project file
+pub fn set_trust(config_dir: &Path, path: &Path, content: &str, trusted: bool) -> Result<(), Box<dyn Error>> {
+    let mut decisions = read_decisions(config_dir);
+    decisions.insert(trust_key(path), TrustDecision { sha256: digest(content), trusted });
+
+    fs::create_dir_all(config_dir)?;
+
+    // Write to a temporary file first so a crash never leaves a torn trust list behind
+    let trust_path = config_dir.join(TRUST_FILE);
+    let tmp_path = trust_path.with_extension("json.tmp");
+    fs::write(&tmp_path, serde_json::to_string_pretty(&decisions)?)?;
+    fs::rename(tmp_path, trust_path)?;
+
+    Ok(())
+}
+
+/// The recorded answer for this exact content, if any
+fn decision(config_dir: &Path, path: &Path, content: &str) -> Option<bool> {
+    read_decisions(config_dir)
+        .remove(&trust_key(path))
+        .filter(|decision| decision.sha256 == digest(content))
+        .map(|decision| decision.trusted)
+}
+
+fn read_decisions(config_dir: &Path) -> HashMap<String, TrustDecision> {
+    fs::read_to_string(config_dir.join(TRUST_FILE))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn trust_key(path: &Path) -> String {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf()).display().to_string()
+}
+
+fn digest(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
+}
+
+/// Make relative paths in a project file relative to the project, not the shell's cwd
+fn rebase_paths(value: &mut Value, project_dir: &Path) {
+    let rebase = |path: &mut Value| {
+        if let Value::String(text) = path {
+            if Path::new(text.as_str()).is_relative() && !text.starts_with('~') {
+                *text = project_dir.join(&*text).display().to_string();
+            }
+        }
+    };
+
+    for (section, key) in PROJECT_PATH_KEYS {
+        match value.get_mut(*section).and_then(|section| section.get_mut(*key)) {
+            Some(Value::Sequence(paths)) => paths.iter_mut().for_each(rebase),
+            Some(path) => rebase(path),
+            None => {}
+        }
+    }
+}
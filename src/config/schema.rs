@@ -1,12 +1,17 @@
 use serde::{Deserialize, Serialize};
+use schemars::JsonSchema;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::time::Duration;
 
-use super::keybindings::{Keybindings, KeyAction, KeyCombination};
+use super::keybindings::Keybindings;
+use super::migration::CURRENT_VERSION;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct Config {
+    /// Config file format version (see `config::migration`)
+    pub version: Option<u64>,
+    
     /// General configuration
     pub general: GeneralConfig,
     
@@ -29,8 +34,15 @@ pub struct Config {
     pub shells: HashMap<String, ShellConfig>,
     
     /// Keybindings configuration
+    #[schemars(with = "Option<HashMap<String, Vec<String>>>")]
     pub keybindings: Option<Keybindings>,
     
+    /// Themes and UI styling, loaded from appearance.yaml
+    pub appearance: Option<AppearanceConfig>,
+    
+    /// AI model catalog and prompts, loaded from ai_models.yaml
+    pub ai_models: Option<AiModelsConfig>,
+    
     /// Plugin configuration
     pub plugins: Option<PluginConfig>,
     
@@ -42,11 +54,15 @@ pub struct Config {
     
     /// Performance tuning
     pub performance: Option<PerformanceConfig>,
+    
+    /// Terminal capability detection and per-terminal overrides
+    pub terminal: Option<TerminalConfig>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            version: Some(CURRENT_VERSION),
             general: GeneralConfig::default(),
             ui: UiConfig::default(),
             suggestions: SuggestionConfig::default(),
@@ -55,15 +71,18 @@ impl Default for Config {
             ollama: Some(OllamaConfig::default()),
             shells: HashMap::new(),
             keybindings: Some(Keybindings::default_bindings()),
+            appearance: None,
+            ai_models: None,
             plugins: Some(PluginConfig::default()),
             ssh: Some(SshConfig::default()),
             telemetry: Some(TelemetryConfig::default()),
             performance: Some(PerformanceConfig::default()),
+            terminal: Some(TerminalConfig::default()),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct GeneralConfig {
     /// Debug mode
     pub debug: bool,
@@ -115,7 +134,7 @@ impl Default for GeneralConfig {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct UiConfig {
     /// Dropdown width
     pub dropdown_width: u16,
@@ -155,9 +174,21 @@ pub struct UiConfig {
     
     /// Dropdown position (default/top/bottom)
     pub dropdown_position: Option<DropdownPosition>,
+    
+    /// Inside tmux, show the suggestion list as a tmux menu instead of drawing over the pane
+    pub tmux_popup: Option<bool>,
+    
+    /// Plain output for screen readers: no ghost text, suggestions as numbered lines.
+    /// Unset means on when `NO_COLOR` is set or `TERM=dumb`
+    pub accessible: Option<bool>,
+    
+    /// Before accepting a suggestion that makes the line delete or recursively change
+    /// files, show what it would affect and wait for a second Tab (default false)
+    pub preview_destructive: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(rename_all = "snake_case")]
 pub enum DropdownSortMode {
     /// Sort by relevance score
     Relevance,
@@ -169,7 +200,8 @@ pub enum DropdownSortMode {
     Recent,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+#[serde(rename_all = "snake_case")]
 pub enum DropdownPosition {
     /// Default position (below cursor)
     Default,
@@ -181,7 +213,7 @@ pub enum DropdownPosition {
     Custom(u16, u16),
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct ColorConfig {
     /// Primary UI color
     pub primary: Option<String>,
@@ -209,7 +241,7 @@ pub struct ColorConfig {
     pub syntax: Option<SyntaxColors>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct SyntaxColors {
     /// Command color
     pub command: Option<String>,
@@ -241,11 +273,14 @@ impl Default for UiConfig {
             dropdown_sort: Some(DropdownSortMode::Relevance),
             dropdown_delay_ms: Some(100),
             dropdown_position: Some(DropdownPosition::Default),
+            tmux_popup: Some(false),
+            accessible: None,
+            preview_destructive: Some(false),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct SuggestionConfig {
     /// Maximum number of suggestions to show
     pub max_suggestions: usize,
@@ -280,6 +315,15 @@ pub struct SuggestionConfig {
     /// Enable snippet suggestions
     pub enable_snippets: Option<bool>,
     
+    /// Snippet library directory (defaults to `snippets` in the config directory)
+    pub snippets_dir: Option<PathBuf>,
+    
+    /// Extra directories of command spec files, searched after the user's `specs` directory
+    pub spec_dirs: Option<Vec<PathBuf>>,
+    
+    /// Enable bookmarked command suggestions
+    pub enable_bookmarks: Option<bool>,
+    
     /// Enable variable expansion suggestions
     pub enable_variables: Option<bool>,
     
@@ -291,9 +335,30 @@ pub struct SuggestionConfig {
     
     /// Minimum prefix length for suggestions
     pub min_prefix_length: Option<usize>,
+    
+    /// Generate flag suggestions by scraping `--help` output of commands without specs
+    pub enable_help_scraping: Option<bool>,
+    
+    /// Timeout for a single `--help` invocation in milliseconds
+    pub help_scrape_timeout_ms: Option<u64>,
+    
+    /// Query cluster APIs (e.g. Kubernetes) to complete live resource names
+    pub enable_cluster_queries: Option<bool>,
+    
+    /// Timeout for a single cluster query in milliseconds
+    pub cluster_query_timeout_ms: Option<u64>,
+    
+    /// Show git status on path suggestions in a repository and rank changed files first
+    pub git_badges: Option<bool>,
+    
+    /// Also show how long ago unchanged entries were last committed
+    pub git_commit_age: Option<bool>,
+    
+    /// Start with type, size and age columns shown on path suggestions
+    pub path_details: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct ScoringConfig {
     /// Recency weight (0.0-1.0)
     pub recency_weight: f32,
@@ -301,6 +366,19 @@ pub struct ScoringConfig {
     pub frequency_weight: f32,
     /// Context weight (0.0-1.0)
     pub context_weight: f32,
+    /// Show each suggestion's score breakdown in its description
+    pub debug: Option<bool>,
+}
+
+impl Default for ScoringConfig {
+    fn default() -> Self {
+        Self {
+            recency_weight: 0.7,
+            frequency_weight: 0.8,
+            context_weight: 0.9,
+            debug: Some(false),
+        }
+    }
 }
 
 impl Default for SuggestionConfig {
@@ -319,21 +397,27 @@ impl Default for SuggestionConfig {
             cache_lifetime_secs: 60,
             fuzzy_matching: Some(true),
             max_history_items: Some(1000),
-            scoring: Some(ScoringConfig {
-                recency_weight: 0.7,
-                frequency_weight: 0.8,
-                context_weight: 0.9,
-            }),
+            scoring: Some(ScoringConfig::default()),
             enable_snippets: Some(true),
+            snippets_dir: None,
+            spec_dirs: Some(Vec::new()),
+            enable_bookmarks: Some(true),
             enable_variables: Some(true),
             enable_file_content: Some(false),
             complete_while_typing: Some(true),
             min_prefix_length: Some(1),
+            enable_help_scraping: Some(false),
+            help_scrape_timeout_ms: Some(500),
+            enable_cluster_queries: Some(false),
+            cluster_query_timeout_ms: Some(800),
+            git_badges: Some(true),
+            git_commit_age: Some(false),
+            path_details: Some(false),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct PredictionConfig {
     /// Enable prediction system
     pub enable: bool,
@@ -365,14 +449,24 @@ pub struct PredictionConfig {
     /// Maximum latency for predictions to be considered (ms)
     pub max_prediction_latency_ms: Option<u64>,
     
-    /// Enable context-based ranking of predictions
+    /// Rank predictions accepted in the same directory, project type or git branch higher
     pub enable_context_ranking: Option<bool>,
     
+    /// Re-rank predictions by similarity to the input and recent commands (default false)
+    pub semantic_ranking: Option<bool>,
+    
+    /// Longest re-ranking may take before it's skipped, in microseconds (default 500)
+    pub semantic_ranking_budget_us: Option<u64>,
+    
+    /// Keys closer together than this (ms) count as rapid typing, and predictions wait
+    /// for a pause (0 = predict on every key)
+    pub debounce_threshold_ms: Option<u64>,
+    
     /// Sources configuration
     pub sources: Option<SourcesConfig>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct SourcesConfig {
     /// Enable history-based predictions
     pub history: bool,
@@ -402,6 +496,9 @@ impl Default for PredictionConfig {
             cache_ttl_seconds: 300,
             max_prediction_latency_ms: Some(5),
             enable_context_ranking: Some(true),
+            semantic_ranking: Some(false),
+            semantic_ranking_budget_us: Some(500),
+            debounce_threshold_ms: Some(100),
             sources: Some(SourcesConfig {
                 history: true,
                 directory_context: true,
@@ -414,7 +511,7 @@ impl Default for PredictionConfig {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct AiConfig {
     /// Enable AI integration
     pub enabled: bool,
@@ -442,6 +539,21 @@ pub struct AiConfig {
     
     /// Max tokens for API responses
     pub max_tokens: Option<u32>,
+    
+    /// After a command fails, offer to explain its error (default true)
+    pub explain_errors: Option<bool>,
+    
+    /// Times a request is retried after a timeout, 429 or 5xx (default 2)
+    pub max_retries: Option<u32>,
+    
+    /// Requests per minute to a provider, 0 for no limit (default 60)
+    pub requests_per_minute: Option<u32>,
+    
+    /// Requests someone is waiting on that can run at once (default 2)
+    pub interactive_concurrency: Option<usize>,
+    
+    /// Background requests that can run at once; they wait for interactive ones (default 1)
+    pub background_concurrency: Option<usize>,
 }
 
 impl Default for AiConfig {
@@ -456,12 +568,17 @@ impl Default for AiConfig {
             model: Some("gpt-3.5-turbo".to_string()),
             temperature: Some(0.2),
             max_tokens: Some(100),
+            explain_errors: Some(true),
+            max_retries: Some(2),
+            requests_per_minute: Some(60),
+            interactive_concurrency: Some(2),
+            background_concurrency: Some(1),
         }
     }
 }
 
 /// Ollama local model configuration
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct OllamaConfig {
     /// Enable Ollama integration
     pub enabled: bool,
@@ -485,7 +602,7 @@ pub struct OllamaConfig {
     pub parameters: Option<OllamaParameters>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct OllamaParameters {
     /// Temperature (0.0-1.0)
     pub temperature: f32,
@@ -516,7 +633,7 @@ impl Default for OllamaConfig {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct ShellConfig {
     /// Shell command to execute for shell-specific operations
     pub command: String,
@@ -534,7 +651,7 @@ pub struct ShellConfig {
     pub load_aliases: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct PluginConfig {
     /// Enable the plugin system
     pub enabled: bool,
@@ -546,6 +663,7 @@ pub struct PluginConfig {
     pub enabled_plugins: Vec<String>,
     
     /// Plugin-specific configurations
+    #[schemars(with = "HashMap<String, serde_json::Value>")]
     pub plugin_configs: HashMap<String, serde_yaml::Value>,
 }
 
@@ -563,15 +681,15 @@ impl Default for PluginConfig {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct SshConfig {
     /// Enable SSH-specific optimizations
     pub enable_optimizations: bool,
     
-    /// Maximum bandwidth usage (KB/s)
+    /// Maximum bandwidth usage (KB/s), enforced by capping the redraw rate
     pub max_bandwidth_kb: Option<u32>,
     
-    /// Enable command caching for SSH sessions
+    /// Predict through the service forwarded by `rustfig ssh`, caching its answers
     pub enable_command_caching: Option<bool>,
     
     /// Disable expensive features in SSH sessions
@@ -579,6 +697,15 @@ pub struct SshConfig {
     
     /// Reduce animation in SSH sessions
     pub reduce_animations: Option<bool>,
+    
+    /// Complete remote paths for scp/rsync by running `ls` over ssh
+    pub enable_remote_completion: Option<bool>,
+    
+    /// Timeout for a single remote listing in milliseconds
+    pub remote_completion_timeout_ms: Option<u64>,
+    
+    /// How long remote listings are cached in seconds
+    pub remote_cache_secs: Option<u64>,
 }
 
 impl Default for SshConfig {
@@ -589,11 +716,59 @@ impl Default for SshConfig {
             enable_command_caching: Some(true),
             disable_expensive_features: Some(true),
             reduce_animations: Some(true),
+            enable_remote_completion: Some(false),
+            remote_completion_timeout_ms: Some(1500),
+            remote_cache_secs: Some(30),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct TerminalConfig {
+    /// Query the terminal at startup for synchronized output and cell size
+    pub probe: Option<bool>,
+    
+    /// How long to wait for the terminal's replies in milliseconds
+    pub probe_timeout_ms: Option<u64>,
+    
+    /// Corrections for specific terminals, keyed by apple_terminal, windows_terminal,
+    /// alacritty, wezterm, kitty, iterm2, vscode, console or unknown
+    #[serde(default)]
+    pub overrides: HashMap<String, TerminalOverride>,
+}
+
+impl Default for TerminalConfig {
+    fn default() -> Self {
+        Self {
+            probe: Some(true),
+            probe_timeout_ms: Some(100),
+            overrides: HashMap::new(),
+        }
+    }
+}
+
+/// Capabilities to force for one terminal; unset ones are detected
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Default)]
+pub struct TerminalOverride {
+    pub truecolor: Option<bool>,
+    pub synchronized_output: Option<bool>,
+    /// Understands OSC sequences such as hyperlinks and prompt marks
+    pub osc: Option<bool>,
+    pub profile: Option<RenderProfile>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RenderProfile {
+    /// 24-bit color and every visual
+    Full,
+    /// The 256-color palette
+    Basic,
+    /// No icons or animations, for consoles and unusual terminals
+    Minimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct TelemetryConfig {
     /// Enable telemetry
     pub enabled: bool,
@@ -606,6 +781,29 @@ pub struct TelemetryConfig {
     
     /// Feedback submission URL
     pub feedback_url: String,
+    
+    /// Setup has asked whether to enable telemetry, so it doesn't ask again
+    pub consent_asked: Option<bool>,
+    
+    /// How much of a command line usage events keep
+    pub command_detail: Option<CommandDetail>,
+    
+    /// Regexes whose matches are replaced with `<redacted>` in whatever events keep
+    pub redact_patterns: Option<Vec<String>>,
+}
+
+/// How much of each command line usage telemetry keeps
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CommandDetail {
+    /// Nothing; events only say that a command ran
+    None,
+    /// The program names
+    Program,
+    /// The program names and the names of their flags, without values
+    Flags,
+    /// The whole line, as typed
+    Full,
 }
 
 impl Default for TelemetryConfig {
@@ -615,11 +813,14 @@ impl Default for TelemetryConfig {
             data_dir: None,
             upload_url: "https://api.rustfig.dev/telemetry".to_string(),
             feedback_url: "https://api.rustfig.dev/feedback".to_string(),
+            consent_asked: None,
+            command_detail: Some(CommandDetail::Flags),
+            redact_patterns: None,
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct PerformanceConfig {
     /// Number of worker threads (0 = auto-detect)
     pub worker_threads: usize,
@@ -637,15 +838,15 @@ pub struct PerformanceConfig {
     pub optimizations: Option<OptimizationConfig>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
 pub struct OptimizationConfig {
-    /// Use memory mapped files
+    /// Map shell history files instead of reading them
     pub mmap_files: bool,
     
     /// Buffer size for file operations
     pub file_buffer_size: usize,
     
-    /// Compress cached data
+    /// zstd-compress persisted caches and learning data
     pub compress_cache: bool,
 }
 
@@ -664,3 +865,126 @@ impl Default for PerformanceConfig {
         }
     }
 }
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Default)]
+pub struct AppearanceConfig {
+    /// Theme selected in appearance.yaml (`ui.theme` in config.yaml takes precedence)
+    pub active_theme: Option<String>,
+    
+    /// Theme definitions by name
+    #[serde(default)]
+    pub themes: HashMap<String, ThemeDefinition>,
+    
+    /// Dropdown styling
+    #[schemars(with = "Option<serde_json::Value>")]
+    pub dropdown: Option<serde_yaml::Value>,
+    
+    /// Ghost text styling
+    #[schemars(with = "Option<serde_json::Value>")]
+    pub ghost_text: Option<serde_yaml::Value>,
+    
+    /// Font hints
+    #[schemars(with = "Option<serde_json::Value>")]
+    pub fonts: Option<serde_yaml::Value>,
+    
+    /// Terminal compatibility settings
+    #[schemars(with = "Option<serde_json::Value>")]
+    pub terminal: Option<serde_yaml::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct ThemeDefinition {
+    /// Display name
+    pub name: Option<String>,
+    
+    /// Theme author
+    pub author: Option<String>,
+    
+    /// Theme palette
+    pub colors: ThemeColors,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct ThemeColors {
+    /// UI and syntax colors
+    #[serde(flatten)]
+    pub palette: ColorConfig,
+    
+    /// Ghost text color
+    pub ghost: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone, Default)]
+pub struct AiModelsConfig {
+    /// Provider to prefer (openai, anthropic, ollama, other)
+    pub preferred_provider: Option<String>,
+    
+    /// Model definitions grouped by provider
+    #[serde(default)]
+    pub models: HashMap<String, ProviderModels>,
+    
+    /// Which model handles which task
+    #[serde(default)]
+    pub task_models: HashMap<String, TaskModel>,
+    
+    /// Prompt templates by task
+    #[serde(default)]
+    pub prompts: HashMap<String, String>,
+    
+    /// System prompts by model
+    #[serde(default)]
+    pub system_prompts: HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct ProviderModels {
+    /// Provider API settings (base_url, api_key, ...)
+    #[serde(default)]
+    #[schemars(with = "HashMap<String, serde_json::Value>")]
+    pub api: HashMap<String, serde_yaml::Value>,
+    
+    /// Models offered by the provider
+    #[serde(default)]
+    pub available: HashMap<String, ModelDefinition>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct ModelDefinition {
+    /// Name the provider knows the model by, if different from the key
+    pub model_name: Option<String>,
+    
+    /// Human-readable name
+    pub display_name: Option<String>,
+    
+    /// Short description
+    pub description: Option<String>,
+    
+    /// Context window in tokens
+    pub context_window: Option<u32>,
+    
+    /// Max tokens per response
+    pub max_tokens: Option<u32>,
+    
+    /// Sampling temperature
+    pub temperature: Option<f32>,
+    
+    /// Tokens of chat history sent with each message (default: what the context window
+    /// leaves after `max_tokens`)
+    pub history_tokens: Option<u32>,
+    
+    /// Summarize chat history that no longer fits instead of dropping it (default false)
+    pub summarize_history: Option<bool>,
+    
+    /// Tasks the model is suited for
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, JsonSchema, Clone)]
+pub struct TaskModel {
+    /// Model used for the task
+    pub default: String,
+    
+    /// Model used when the default is unavailable
+    pub fallback: Option<String>,
+}
@@ -0,0 +1,339 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use serde_json::{Map, Value as JsonValue};
+use serde_yaml::Value;
+
+use super::loader::{self, ConfigLayer, LayerSource};
+use super::migration;
+use super::schema::Config;
+
+/// How serious a validation finding is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    /// The value doesn't fit the schema; the config won't load
+    Error,
+    /// The value is ignored (e.g. a misspelled key)
+    Warning,
+}
+
+/// One problem found in a config file, located by its key path
+#[derive(Debug, Clone)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    /// Dotted key path, e.g. `ui.dropdown_width` or `plugins.enabled_plugins[2]`
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+/// JSON Schema for the config files
+///
+/// Every file is an overlay merged over the defaults, so no key is required.
+pub fn config_schema() -> Result<JsonValue, Box<dyn Error>> {
+    let mut schema = generated_schema()?;
+    strip_required(&mut schema);
+    Ok(schema)
+}
+
+/// The schema as derived from `config::schema`
+fn generated_schema() -> Result<JsonValue, Box<dyn Error>> {
+    Ok(serde_json::to_value(schemars::schema_for!(Config))?)
+}
+
+/// Validate one config-shaped file (config.yaml or a profile) on top of the defaults
+///
+/// Errors list every offending key path; warnings alone don't fail validation.
+pub fn validate_config(path: &Path) -> Result<(), Box<dyn Error>> {
+    let errors: Vec<String> = check_config_file(path)?
+        .into_iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .map(|issue| issue.to_string())
+        .collect();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; ").into())
+    }
+}
+
+/// Every issue in a config-shaped file, without rewriting it
+pub fn check_config_file(path: &Path) -> Result<Vec<ValidationIssue>, Box<dyn Error>> {
+    let content = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut value: Value = serde_yaml::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?;
+    if value.is_null() {
+        return Ok(Vec::new());
+    }
+
+    // Check what the file means today, not how an older version spelled it
+    migration::migrate(&mut value).map_err(|e| format!("{}: {}", path.display(), e))?;
+
+    let mut merged = serde_yaml::to_value(Config::default())?;
+    loader::merge_values(&mut merged, value);
+    validate_value(&merged)
+}
+
+/// Every issue in each file layer, each checked on top of the defaults alone
+pub fn check_layers(layers: &[ConfigLayer]) -> Result<Vec<(String, ValidationIssue)>, Box<dyn Error>> {
+    let defaults = serde_yaml::to_value(Config::default())?;
+    let mut found = Vec::new();
+
+    for layer in layers.iter().filter(|layer| layer.source != LayerSource::Default) {
+        let mut merged = defaults.clone();
+        loader::merge_values(&mut merged, layer.value.clone());
+        for issue in validate_value(&merged)? {
+            found.push((layer.origin(), issue));
+        }
+    }
+
+    Ok(found)
+}
+
+/// Check a full config value against the schema
+pub fn validate_value(value: &Value) -> Result<Vec<ValidationIssue>, Box<dyn Error>> {
+    let schema = generated_schema()?;
+    let instance = serde_json::to_value(value)?;
+    let definitions = schema.get("definitions").and_then(JsonValue::as_object).cloned().unwrap_or_default();
+
+    let mut issues = Vec::new();
+    check(&definitions, &schema, &instance, "", &mut issues);
+    Ok(issues)
+}
+
+/// Walk `value` alongside `schema`, recording what doesn't fit
+fn check(definitions: &Map<String, JsonValue>, schema: &JsonValue, value: &JsonValue, path: &str, issues: &mut Vec<ValidationIssue>) {
+    let schema = resolve(definitions, schema);
+    let schema = match schema.as_object() {
+        Some(schema) => schema,
+        // `true` accepts anything (free-form values)
+        None => return,
+    };
+
+    if let Some(variants) = schema.get("anyOf").or_else(|| schema.get("oneOf")).and_then(JsonValue::as_array) {
+        check_variants(definitions, variants, value, path, issues);
+        return;
+    }
+    if let Some(all) = schema.get("allOf").and_then(JsonValue::as_array) {
+        for part in all {
+            check(definitions, part, value, path, issues);
+        }
+    }
+
+    if let Some(expected) = schema.get("type") {
+        if !type_matches(expected, value) {
+            issues.push(error(path, format!("expected {}, got {}", describe_type(expected), type_name(value))));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(JsonValue::as_array) {
+        if !allowed.contains(value) {
+            issues.push(error(path, format!("expected one of {}, got {}", list_values(allowed), value)));
+            return;
+        }
+    }
+
+    match value {
+        JsonValue::Number(number) => check_range(schema, number.as_f64().unwrap_or_default(), path, issues),
+        JsonValue::Array(items) => check_items(definitions, schema, items, path, issues),
+        JsonValue::Object(fields) => check_fields(definitions, schema, fields, path, issues),
+        _ => {}
+    }
+}
+
+/// `Option<T>` and data-carrying enums: the value has to fit one variant
+fn check_variants(definitions: &Map<String, JsonValue>, variants: &[JsonValue], value: &JsonValue, path: &str, issues: &mut Vec<ValidationIssue>) {
+    let mut attempts = Vec::new();
+    for variant in variants {
+        let mut found = Vec::new();
+        check(definitions, variant, value, path, &mut found);
+        if found.iter().all(|issue| issue.severity == Severity::Warning) {
+            issues.extend(found);
+            return;
+        }
+        attempts.push((resolve(definitions, variant), found));
+    }
+
+    // If only one variant takes this kind of value, its complaint is the useful one
+    let same_type: Vec<&Vec<ValidationIssue>> = attempts
+        .iter()
+        .filter(|(variant, _)| variant.get("type").map_or(true, |expected| type_matches(expected, value)))
+        .map(|(_, found)| found)
+        .collect();
+    if let [found] = same_type.as_slice() {
+        issues.extend(found.iter().cloned());
+        return;
+    }
+
+    let expected: Vec<String> = attempts
+        .iter()
+        .filter(|(variant, _)| variant.get("type") != Some(&JsonValue::from("null")))
+        .map(|(variant, _)| match variant.get("enum").and_then(JsonValue::as_array) {
+            Some(allowed) => list_values(allowed),
+            None => variant.get("type").map_or_else(|| "a value".to_string(), describe_type),
+        })
+        .collect();
+    // Several variants take this type (e.g. documented enum values), so show the value itself
+    let got = if same_type.is_empty() { type_name(value).to_string() } else { value.to_string() };
+    issues.push(error(path, format!("expected {}, got {}", expected.join(" or "), got)));
+}
+
+/// Bounds from `minimum`/`maximum` and from fixed-width integer formats (`uint16`, ...)
+fn check_range(schema: &Map<String, JsonValue>, number: f64, path: &str, issues: &mut Vec<ValidationIssue>) {
+    let format_max = match schema.get("format").and_then(JsonValue::as_str) {
+        Some("uint8") => Some(u8::MAX as f64),
+        Some("uint16") => Some(u16::MAX as f64),
+        Some("uint32") => Some(u32::MAX as f64),
+        _ => None,
+    };
+    let minimum = schema.get("minimum").and_then(JsonValue::as_f64);
+    let maximum = schema.get("maximum").and_then(JsonValue::as_f64).or(format_max);
+
+    if let Some(minimum) = minimum.filter(|minimum| number < *minimum) {
+        issues.push(error(path, format!("expected at least {}, got {}", minimum, number)));
+    } else if let Some(maximum) = maximum.filter(|maximum| number > *maximum) {
+        issues.push(error(path, format!("expected at most {}, got {}", maximum, number)));
+    }
+}
+
+fn check_items(definitions: &Map<String, JsonValue>, schema: &Map<String, JsonValue>, items: &[JsonValue], path: &str, issues: &mut Vec<ValidationIssue>) {
+    let min = schema.get("minItems").and_then(JsonValue::as_u64);
+    let max = schema.get("maxItems").and_then(JsonValue::as_u64);
+    let len = items.len() as u64;
+    if min.map_or(false, |min| len < min) || max.map_or(false, |max| len > max) {
+        let expected = match (min, max) {
+            (Some(min), Some(max)) if min == max => format!("{}", min),
+            (Some(min), Some(max)) => format!("{} to {}", min, max),
+            (Some(min), None) => format!("at least {}", min),
+            (None, _) => format!("at most {}", max.unwrap_or_default()),
+        };
+        issues.push(error(path, format!("expected {} items, got {}", expected, len)));
+        return;
+    }
+
+    for (i, item) in items.iter().enumerate() {
+        let item_schema = match schema.get("items") {
+            // Tuples list one schema per position
+            Some(JsonValue::Array(positions)) => positions.get(i),
+            other => other,
+        };
+        if let Some(item_schema) = item_schema {
+            check(definitions, item_schema, item, &format!("{}[{}]", path, i), issues);
+        }
+    }
+}
+
+fn check_fields(definitions: &Map<String, JsonValue>, schema: &Map<String, JsonValue>, fields: &Map<String, JsonValue>, path: &str, issues: &mut Vec<ValidationIssue>) {
+    let properties = schema.get("properties").and_then(JsonValue::as_object);
+    let additional = schema.get("additionalProperties");
+
+    for (key, field) in fields {
+        let field_path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+
+        match (properties.and_then(|properties| properties.get(key)), additional) {
+            (Some(field_schema), _) => check(definitions, field_schema, field, &field_path, issues),
+            (None, Some(JsonValue::Bool(false))) => issues.push(error(&field_path, "unknown key".to_string())),
+            // Maps (e.g. `shells`, `themes`) describe every value with one schema
+            (None, Some(value_schema @ JsonValue::Object(_))) => check(definitions, value_schema, field, &field_path, issues),
+            // serde ignores unknown keys, which is how typos go unnoticed
+            (None, _) if properties.is_some() => issues.push(ValidationIssue {
+                severity: Severity::Warning,
+                path: field_path,
+                message: "unknown key (ignored)".to_string(),
+            }),
+            (None, _) => {}
+        }
+    }
+
+    if let Some(required) = schema.get("required").and_then(JsonValue::as_array) {
+        for key in required.iter().filter_map(JsonValue::as_str) {
+            if !fields.contains_key(key) {
+                let field_path = if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+                issues.push(error(&field_path, "missing required key".to_string()));
+            }
+        }
+    }
+}
+
+/// Follow a `$ref` into the schema's definitions
+fn resolve<'a>(definitions: &'a Map<String, JsonValue>, schema: &'a JsonValue) -> &'a JsonValue {
+    match schema.get("$ref").and_then(JsonValue::as_str) {
+        Some(reference) => reference
+            .strip_prefix("#/definitions/")
+            .and_then(|name| definitions.get(name))
+            .map_or(schema, |target| resolve(definitions, target)),
+        None => schema,
+    }
+}
+
+/// Whether `value` has one of the JSON types in a schema's `type` (a name or a list of names)
+fn type_matches(expected: &JsonValue, value: &JsonValue) -> bool {
+    let actual = type_name(value);
+    let fits = |name: &str| name == actual || (name == "number" && actual == "integer");
+
+    match expected {
+        JsonValue::String(name) => fits(name.as_str()),
+        JsonValue::Array(names) => names.iter().filter_map(JsonValue::as_str).any(fits),
+        _ => true,
+    }
+}
+
+/// `"integer"`, or `integer or null` for a type list
+fn describe_type(expected: &JsonValue) -> String {
+    match expected {
+        JsonValue::Array(names) => names.iter().filter_map(JsonValue::as_str).collect::<Vec<_>>().join(" or "),
+        other => other.as_str().unwrap_or("a value").to_string(),
+    }
+}
+
+/// JSON Schema type name of a value
+fn type_name(value: &JsonValue) -> &'static str {
+    match value {
+        JsonValue::Null => "null",
+        JsonValue::Bool(_) => "boolean",
+        JsonValue::Number(number) if number.is_i64() || number.is_u64() => "integer",
+        JsonValue::Number(_) => "number",
+        JsonValue::String(_) => "string",
+        JsonValue::Array(_) => "array",
+        JsonValue::Object(_) => "object",
+    }
+}
+
+fn list_values(values: &[JsonValue]) -> String {
+    values.iter().map(|value| value.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+fn error(path: &str, message: String) -> ValidationIssue {
+    ValidationIssue {
+        severity: Severity::Error,
+        path: path.to_string(),
+        message,
+    }
+}
+
+/// Drop the `required` lists of struct schemas
+fn strip_required(schema: &mut JsonValue) {
+    match schema {
+        JsonValue::Object(fields) => {
+            if fields.contains_key("properties") && fields.get("required").map_or(false, JsonValue::is_array) {
+                fields.remove("required");
+            }
+            for value in fields.values_mut() {
+                strip_required(value);
+            }
+        }
+        JsonValue::Array(items) => items.iter_mut().for_each(strip_required),
+        _ => {}
+    }
+}
@@ -1,6 +1,9 @@
 use std::error::Error;
 use std::process;
 
+use clap::Parser;
+
+mod cli;
 mod config;
 mod terminal;
 mod shell;
@@ -9,20 +12,58 @@ mod ai;
 mod plugin;
 mod utils;
 mod prediction;
+mod service;
+mod telementary;
 
-#[tokio::main(flavor = "multi_thread", worker_threads = 4)]
-async fn main() -> Result<(), Box<dyn Error>> {
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = cli::Cli::parse();
+    
+    let result = match args.command {
+        // It may fork to detach, which is only sound while this is the only thread, so it
+        // runs before any runtime is built and builds its own
+        Some(cli::CliCommand::Service { action: cli::ServiceAction::Run { daemon } }) => {
+            cli::commands::cmd_service_run(daemon)
+        }
+        Some(command) => cli::run(command),
+        None => start(args.profile_startup),
+    };
+    
     // Initialize application with error handling
-    if let Err(e) = run().await {
+    if let Err(e) = result {
         eprintln!("Application error: {}", e);
         process::exit(1);
     }
     Ok(())
 }
 
-async fn run() -> Result<(), Box<dyn Error>> {
-    // Load configuration
+/// Load the configuration, then build the runtime it sizes and run on it
+fn start(profiling: bool) -> Result<(), Box<dyn Error>> {
+    let mut profile = profiling.then(utils::startup::StartupProfile::new);
+    
     let config = config::loader::load_config()?;
+    if let Some(profile) = profile.as_mut() {
+        profile.phase("load config");
+    }
+    
+    let runtime = utils::concurrent::build_runtime(&config)?;
+    
+    match profile {
+        Some(mut profile) => {
+            profile.phase("async runtime");
+            runtime.block_on(profile_startup(config, profile))
+        }
+        None => runtime.block_on(run(config)),
+    }
+}
+
+async fn run(config: config::Config) -> Result<(), Box<dyn Error>> {
+    // Keep caches within performance.max_memory_mb
+    if let Some(performance) = &config.performance {
+        utils::memory::set_budget_mb(performance.max_memory_mb);
+    }
+    
+    // A panic puts the terminal back and leaves a crash report
+    utils::crash::install(&config, utils::crash::CrashSource::Terminal);
     
     // Initialize terminal
     let mut term = terminal::Terminal::new()?;
@@ -38,3 +79,29 @@ async fn run() -> Result<(), Box<dyn Error>> {
     
     Ok(())
 }
+
+/// Time each startup phase, including the work deferred until after the prompt, and print
+/// the timings instead of starting the terminal
+async fn profile_startup(config: config::Config, mut profile: utils::startup::StartupProfile) -> Result<(), Box<dyn Error>> {
+    let suggestion_engine = suggestion::engine::SuggestionEngine::new(&config);
+    profile.phase("suggestion engine");
+    
+    let prediction_engine = prediction::PredictionEngine::new(&config);
+    profile.phase("prediction engine");
+    
+    let _shell_integration = shell::detect_and_initialize()?;
+    profile.phase("shell integration");
+    
+    // What the background warm-up and first keystrokes pay for
+    prediction_engine.warm_up();
+    profile.deferred_phase("learning data and aliases");
+    
+    suggestion_engine.get_suggestions("git ", 4, 10).await;
+    profile.deferred_phase("first suggestion");
+    
+    prediction_engine.predict("git ", 4, 5).await;
+    profile.deferred_phase("first prediction");
+    
+    profile.print();
+    Ok(())
+}
@@ -5,10 +5,12 @@ pub mod models;
 pub mod ranking;
 pub mod learning;
 pub mod cache;
+pub mod semantic;
+pub mod remediation;
 
 pub use self::engine::PredictionEngine;
 pub use self::models::{Prediction, PredictionSource, PredictionType, Confidence};
-pub use self::context_analyzer::ContextAnalyzer;
+pub use self::context_analyzer::{BranchState, ContextAnalyzer};
 pub use self::ranking::PredictionRanker;
-pub use self::learning::UserLearningSystem;
+pub use self::learning::{LearningContext, UserLearningSystem};
 pub use self::cache::PredictionCache;
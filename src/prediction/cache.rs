@@ -2,8 +2,9 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use parking_lot::RwLock;
-use hashbrown::hash_map::Entry;
+use std::collections::hash_map::Entry;
 use super::models::Prediction;
+use crate::utils::memory::{self, MemoryConsumer};
 
 /// Ultra-fast prediction cache for sub-millisecond response times
 pub struct PredictionCache {
@@ -16,6 +17,8 @@ pub struct PredictionCache {
 struct CacheEntry {
     predictions: Vec<Prediction>,
     timestamp: Instant,
+    /// Approximate size of the key and predictions
+    bytes: usize,
 }
 
 impl PredictionCache {
@@ -46,17 +49,25 @@ impl PredictionCache {
             return;
         }
         
-        let mut cache = self.cache.write();
+        let bytes = key.len() + predictions.iter().map(Prediction::memory_bytes).sum::<usize>();
         
-        // If cache is full, remove oldest entries
-        if cache.len() >= self.max_entries {
-            self.cleanup_cache(&mut cache);
+        {
+            let mut cache = self.cache.write();
+            
+            // If cache is full, remove oldest entries
+            if cache.len() >= self.max_entries {
+                self.cleanup_cache(&mut cache);
+            }
+            
+            cache.insert(key, CacheEntry {
+                predictions,
+                timestamp: Instant::now(),
+                bytes,
+            });
         }
         
-        cache.insert(key, CacheEntry {
-            predictions,
-            timestamp: Instant::now(),
-        });
+        // With the lock released, since staying in budget may shrink this cache
+        memory::enforce();
     }
     
     /// Check if cache contains an entry that's not expired
@@ -80,6 +91,7 @@ impl PredictionCache {
             // Find and update the prediction
             for pred in &mut cache_entry.predictions {
                 if pred.text == old_prediction.text {
+                    cache_entry.bytes = cache_entry.bytes + new_prediction.memory_bytes() - pred.memory_bytes();
                     *pred = new_prediction;
                     return true;
                 }
@@ -97,13 +109,13 @@ impl PredictionCache {
         
         // If still too large, remove oldest entries
         if cache.len() >= self.max_entries {
-            let mut entries: Vec<_> = cache.iter().collect();
-            entries.sort_by_key(|(_, entry)| entry.timestamp);
+            let mut entries: Vec<_> = cache.iter().map(|(key, entry)| (key.clone(), entry.timestamp)).collect();
+            entries.sort_by_key(|(_, timestamp)| *timestamp);
             
             // Remove oldest third of entries
             let to_remove = self.max_entries / 3;
             for (key, _) in entries.iter().take(to_remove) {
-                cache.remove(*key);
+                cache.remove(key);
             }
         }
     }
@@ -114,3 +126,31 @@ impl PredictionCache {
         cache.clear();
     }
 }
+
+impl MemoryConsumer for PredictionCache {
+    fn memory_bytes(&self) -> usize {
+        self.cache.read().values().map(|entry| entry.bytes).sum()
+    }
+    
+    /// Evict the oldest entries first
+    fn shrink_to(&self, target: usize) {
+        let mut cache = self.cache.write();
+        let mut total: usize = cache.values().map(|entry| entry.bytes).sum();
+        if total <= target {
+            return;
+        }
+        
+        let mut oldest: Vec<(String, Instant, usize)> = cache.iter()
+            .map(|(key, entry)| (key.clone(), entry.timestamp, entry.bytes))
+            .collect();
+        oldest.sort_by_key(|(_, timestamp, _)| *timestamp);
+        
+        for (key, _, bytes) in oldest {
+            if total <= target {
+                break;
+            }
+            cache.remove(&key);
+            total -= bytes;
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use std::path::{Path, PathBuf};
+use super::learning::LearningContext;
+use crate::shell::parser::ParsedCommand;
+use crate::suggestion::context::{Context, ProjectType};
+#[cfg(feature = "git-integration")]
+use crate::suggestion::completers::git::{FileStatus, GitRepository};
+
+/// Analyzes current terminal context for more accurate predictions
+pub struct ContextAnalyzer;
+
+impl ContextAnalyzer {
+    pub fn new() -> Self {
+        Self
+    }
+    
+    /// Analyze the current context to enable smarter predictions
+    pub async fn analyze(&self, input: &str, parsed: &ParsedCommand<'_>) -> Context {
+        // Get current directory
+        let current_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        
+        // Determine if we're in a git repository
+        let in_git_repo = self.is_git_repository(&current_dir);
+        
+        // Determine if we're in a docker context
+        let in_docker_context = self.is_docker_context(&current_dir);
+        
+        // Detect project type
+        let project_type = self.detect_project_type(&current_dir);
+        
+        // Create context
+        Context {
+            current_dir,
+            in_git_repo,
+            in_docker_context,
+            current_command: parsed.command.to_string(),
+            project_type,
+        }
+    }
+    
+    /// Check if current directory is inside a git repository
+    fn is_git_repository(&self, dir: &PathBuf) -> bool {
+        #[cfg(feature = "git-integration")]
+        {
+            GitRepository::discover(dir).is_some()
+        }
+        
+        #[cfg(not(feature = "git-integration"))]
+        {
+            dir.ancestors().any(|ancestor| ancestor.join(".git").exists())
+        }
+    }
+    
+    /// Check if current directory is a docker context
+    fn is_docker_context(&self, dir: &PathBuf) -> bool {
+        dir.join("Dockerfile").exists() || dir.join("docker-compose.yml").exists()
+    }
+    
+    /// Detect project type based on files in directory
+    fn detect_project_type(&self, dir: &PathBuf) -> ProjectType {
+        // Check for Rust project
+        if dir.join("Cargo.toml").exists() {
+            return ProjectType::Rust;
+        }
+        
+        // Check for Node project
+        if dir.join("package.json").exists() {
+            return ProjectType::Node;
+        }
+        
+        // Check for Python project
+        if dir.join("requirements.txt").exists() || dir.join("setup.py").exists() {
+            return ProjectType::Python;
+        }
+        
+        // Check for Go project
+        if dir.join("go.mod").exists() {
+            return ProjectType::Go;
+        }
+        
+        let any_of = |files: &[&str]| files.iter().any(|file| dir.join(file).exists());
+        
+        // Check for Java project (Maven or Gradle)
+        if any_of(&["pom.xml", "build.gradle", "build.gradle.kts", "settings.gradle", "settings.gradle.kts"]) {
+            return ProjectType::Java;
+        }
+        
+        // Check for Ruby project
+        if dir.join("Gemfile").exists() {
+            return ProjectType::Ruby;
+        }
+        
+        // Check for PHP project
+        if dir.join("composer.json").exists() {
+            return ProjectType::Php;
+        }
+        
+        // Check for Elixir project
+        if dir.join("mix.exs").exists() {
+            return ProjectType::Elixir;
+        }
+        
+        // Check for Haskell project (Stack or Cabal)
+        if any_of(&["stack.yaml", "cabal.project"]) {
+            return ProjectType::Haskell;
+        }
+        
+        // Check for C/C++ project built with CMake
+        if dir.join("CMakeLists.txt").exists() {
+            return ProjectType::C;
+        }
+        
+        // The rest have build files named after the project, so go by extension
+        let extensions: Vec<String> = std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter_map(|entry| entry.path().extension().map(|e| e.to_string_lossy().to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let has = |wanted: &[&str]| extensions.iter().any(|extension| wanted.contains(&extension.as_str()));
+        
+        // Check for .NET solution or project
+        if has(&["sln", "csproj", "fsproj", "vbproj"]) {
+            return ProjectType::DotNet;
+        }
+        
+        // Check for Terraform configuration
+        if has(&["tf"]) {
+            return ProjectType::Terraform;
+        }
+        
+        // A Cabal package without a cabal.project
+        if has(&["cabal"]) {
+            return ProjectType::Haskell;
+        }
+        
+        ProjectType::Unknown
+    }
+    
+    /// Where `dir` is, as accepted predictions are learned: the directory, its project
+    /// type and the checked-out branch
+    pub fn learning_context(&self, dir: &Path) -> LearningContext {
+        let project_type = match self.detect_project_type(&dir.to_path_buf()) {
+            ProjectType::Unknown => None,
+            project_type => Some(format!("{:?}", project_type)),
+        };
+        
+        LearningContext {
+            dir: dir.to_path_buf(),
+            project_type,
+            branch: self.current_branch(dir),
+        }
+    }
+    
+    /// Short name of the checked-out branch, `None` outside a repository or when detached
+    fn current_branch(&self, dir: &Path) -> Option<String> {
+        #[cfg(feature = "git-integration")]
+        {
+            GitRepository::discover(dir)?.current_branch()
+        }
+        
+        #[cfg(not(feature = "git-integration"))]
+        {
+            // HEAD names the branch directly; worktrees (a `.git` file) are left out
+            let git_dir = dir.ancestors().map(|ancestor| ancestor.join(".git")).find(|git| git.is_dir())?;
+            let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+            head.trim().strip_prefix("ref: refs/heads/").map(str::to_string)
+        }
+    }
+    
+    /// Get local git branches (async to avoid blocking)
+    pub async fn get_git_branches(&self, dir: &PathBuf) -> Vec<String> {
+        #[cfg(feature = "git-integration")]
+        {
+            let dir_clone = dir.clone();
+            let branches = tokio::task::spawn_blocking(move || {
+                GitRepository::discover(&dir_clone)
+                    .map(|repo| repo.local_branches())
+                    .unwrap_or_default()
+            }).await;
+            
+            branches.unwrap_or_default()
+        }
+        
+        #[cfg(not(feature = "git-integration"))]
+        {
+            let _ = dir;
+            Vec::new()
+        }
+    }
+    
+    /// Get the checked-out branch with its upstream tracking state
+    pub async fn get_branch_state(&self, dir: &PathBuf) -> Option<BranchState> {
+        #[cfg(feature = "git-integration")]
+        {
+            let dir_clone = dir.clone();
+            let state = tokio::task::spawn_blocking(move || {
+                let repo = GitRepository::discover(&dir_clone)?;
+                let branch = repo.current_branch()?;
+                let upstream = repo.upstream(&branch);
+                let (ahead, behind) = upstream.as_ref()
+                    .and_then(|upstream| repo.ahead_behind(&branch, upstream))
+                    .unwrap_or((0, 0));
+                
+                // Prefer `origin`, otherwise whichever remote exists
+                let remotes = repo.remotes();
+                let default_remote = remotes.iter()
+                    .find(|remote| *remote == "origin")
+                    .or_else(|| remotes.first())
+                    .cloned();
+                
+                let dirty = repo.status().iter().any(|e| e.status != FileStatus::Untracked);
+                
+                Some(BranchState {
+                    branch,
+                    upstream,
+                    default_remote,
+                    ahead,
+                    behind,
+                    dirty,
+                })
+            }).await;
+            
+            state.unwrap_or(None)
+        }
+        
+        #[cfg(not(feature = "git-integration"))]
+        {
+            let _ = dir;
+            None
+        }
+    }
+    
+    /// Get information about current git status
+    pub async fn get_git_status(&self, dir: &PathBuf) -> Option<GitStatus> {
+        #[cfg(feature = "git-integration")]
+        {
+            let dir_clone = dir.clone();
+            let status = tokio::task::spawn_blocking(move || {
+                let entries = GitRepository::discover(&dir_clone)?.status();
+                
+                Some(GitStatus {
+                    has_modified: entries.iter().any(|e| e.status != FileStatus::Untracked),
+                    has_untracked: entries.iter().any(|e| e.status == FileStatus::Untracked),
+                })
+            }).await;
+            
+            status.unwrap_or(None)
+        }
+        
+        #[cfg(not(feature = "git-integration"))]
+        {
+            let _ = dir;
+            None
+        }
+    }
+}
+
+/// Git repository status information
+#[derive(Debug, Clone)]
+pub struct GitStatus {
+    pub has_modified: bool,
+    pub has_untracked: bool,
+}
+
+/// Checked-out branch and how it relates to its upstream
+#[derive(Debug, Clone)]
+pub struct BranchState {
+    pub branch: String,
+    /// Upstream as `remote/branch`, `None` if the branch doesn't track anything
+    pub upstream: Option<String>,
+    /// Remote to publish to when there is no upstream yet
+    pub default_remote: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    /// Tracked files have uncommitted changes
+    pub dirty: bool,
+}
@@ -1,19 +1,33 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use once_cell::sync::OnceCell;
 use super::{
     models::{Prediction, PredictionType, PredictionSource, Confidence},
-    context_analyzer::ContextAnalyzer,
+    context_analyzer::{BranchState, ContextAnalyzer},
     ranking::PredictionRanker,
-    learning::UserLearningSystem,
+    learning::{LearningContext, UserLearningSystem},
     cache::PredictionCache,
+    remediation::{Failure, Remediations},
+    semantic,
 };
 use crate::{
     config::Config,
     suggestion::context::Context,
+    suggestion::completers::cargo::CargoProject,
+    suggestion::completers::node::PackageJson,
+    suggestion::completers::task_runners::TaskRunner,
     shell::parser::{CommandParser, ParsedCommand},
+    shell::aliases::AliasTable,
+    shell::history::HistoryDb,
+    terminal::INTERRUPTED,
+    utils::cache_file,
+    utils::memory::{self, MemoryTier},
     utils::perf_metrics::PerformanceMetrics,
+    utils::string_pools::intern,
 };
 
 /// Core prediction engine responsible for generating high-quality, low-latency predictions
@@ -21,53 +35,237 @@ pub struct PredictionEngine {
     config: Arc<Config>,
     context_analyzer: ContextAnalyzer,
     prediction_ranker: PredictionRanker,
-    user_learning: UserLearningSystem,
-    prediction_cache: PredictionCache,
+    /// Loaded on first use (or by `warm_up`), so reading it from disk doesn't delay the prompt
+    user_learning: OnceCell<Arc<UserLearningSystem>>,
+    prediction_cache: Arc<PredictionCache>,
     command_parser: CommandParser,
+    /// Loaded on first use (or by `warm_up`): listing aliases runs the user's shell
+    aliases: OnceCell<RwLock<AliasTable>>,
+    /// The last fully generated set, narrowed as characters are typed onto its input
+    last_predictions: Mutex<Option<LastPredictions>>,
+    /// Commands run lately, newest first, as context for semantic re-ranking
+    recent_commands: Mutex<VecDeque<String>>,
+    /// Loaded on first use, like the learning data
+    remediations: OnceCell<Remediations>,
+    /// The last command, if it failed; the next line's predictions lead with its fixes
+    last_failure: Mutex<Option<Failure>>,
     performance_metrics: PerformanceMetrics,
 }
 
+/// Recent commands kept as context for semantic re-ranking
+const RECENT_COMMANDS: usize = 5;
+
+/// Predictions generated for `input` with the cursor at the end of the line
+struct LastPredictions {
+    input: String,
+    predictions: Vec<Prediction>,
+}
+
 impl PredictionEngine {
     pub fn new(config: &Config) -> Self {
+        let prediction_cache = Arc::new(PredictionCache::new(1000, Duration::from_secs(300)));
+        memory::register(MemoryTier::PredictionCache, &prediction_cache);
+        
         Self {
             config: Arc::new(config.clone()),
             context_analyzer: ContextAnalyzer::new(),
             prediction_ranker: PredictionRanker::new(),
-            user_learning: UserLearningSystem::new(&config.general.user_data_dir),
-            prediction_cache: PredictionCache::new(1000, Duration::from_secs(300)),
+            user_learning: OnceCell::new(),
+            prediction_cache,
             command_parser: CommandParser::new(),
+            aliases: OnceCell::new(),
+            last_predictions: Mutex::new(None),
+            recent_commands: Mutex::new(VecDeque::new()),
+            remediations: OnceCell::new(),
+            last_failure: Mutex::new(None),
             performance_metrics: PerformanceMetrics::new("prediction_engine"),
         }
     }
     
+    /// Load everything deferred at construction; call from a background task once the
+    /// prompt is up so the first prediction doesn't pay for it
+    pub fn warm_up(&self) {
+        self.learning();
+        self.aliases();
+        
+        if self.config.prediction.semantic_ranking.unwrap_or(false) {
+            let history = HistoryDb::from_config(&self.config).load();
+            let mut recent = self.recent_commands.lock();
+            for entry in history.into_iter().rev().take(RECENT_COMMANDS) {
+                recent.push_back(entry.command);
+            }
+        }
+    }
+    
+    /// A command was run; it's context for the predictions that follow
+    pub fn note_command(&self, command: &str) {
+        let command = command.trim();
+        if command.is_empty() {
+            return;
+        }
+        {
+            let mut recent = self.recent_commands.lock();
+            recent.push_front(command.to_string());
+            recent.truncate(RECENT_COMMANDS);
+        }
+        
+        // Whatever was run after a failure is a candidate fix for the next time it fails
+        if let Some(failure) = self.last_failure.lock().take() {
+            self.remediations().learn(&failure, command);
+            self.clear_cache();
+        }
+    }
+    
+    /// The command noted last finished with `status`, having printed `output` (empty
+    /// when it wasn't seen); after a failure the next line is predicted to fix it
+    pub fn note_result(&self, command: &str, status: Option<i32>, output: &[u8]) {
+        let status = match status {
+            Some(status) if status != 0 && !INTERRUPTED.contains(&status) => status,
+            _ => return,
+        };
+        if command.trim().is_empty() {
+            return;
+        }
+        *self.last_failure.lock() = Some(Failure::new(command, status, output));
+        self.clear_cache();
+    }
+    
+    fn remediations(&self) -> &Remediations {
+        self.remediations.get_or_init(|| {
+            Remediations::new(&self.config.general.user_data_dir, cache_file::compression_enabled(&self.config))
+        })
+    }
+    
+    fn learning(&self) -> &UserLearningSystem {
+        self.user_learning.get_or_init(|| {
+            let learning = Arc::new(UserLearningSystem::new(
+                &self.config.general.user_data_dir,
+                cache_file::compression_enabled(&self.config),
+            ));
+            memory::register(MemoryTier::Learning, &learning);
+            learning
+        })
+    }
+    
+    fn aliases(&self) -> &RwLock<AliasTable> {
+        self.aliases.get_or_init(|| RwLock::new(AliasTable::load_for_current_shell(&self.config)))
+    }
+    
     /// Generate predictions for the current input with ultra-low latency
-    pub async fn predict(&self, input: &str, limit: usize) -> Vec<Prediction> {
+    ///
+    /// `cursor_pos` is the byte offset of the cursor; predictions complete the word under
+    /// it and keep the rest of the line.
+    pub async fn predict(&self, input: &str, cursor_pos: usize, limit: usize) -> Vec<Prediction> {
         let _timing = self.performance_metrics.measure_operation("predict");
         
-        // Fast path: Check cache first
-        if let Some(predictions) = self.prediction_cache.get(input) {
+        // Fast path: Check cache first (the same line predicts differently mid-line)
+        let cache_key = if cursor_pos == input.len() {
+            input.to_string()
+        } else {
+            format!("{}\u{0}{}", input, cursor_pos)
+        };
+        if let Some(predictions) = self.prediction_cache.get(&cache_key) {
             return predictions;
         }
         
+        // Typing onto the same word only narrows what was predicted a keystroke ago
+        let at_end = cursor_pos == input.len();
+        if at_end {
+            if let Some(predictions) = self.narrow_last_predictions(input, limit) {
+                self.prediction_cache.set(cache_key, predictions.clone());
+                return predictions;
+            }
+        }
+        
+        // Predict only for the simple command under the cursor, so `ls | grep foo && git `
+        // is completed as `git `
+        let line = match self.command_parser.parse(input, cursor_pos) {
+            Ok(parsed) => parsed,
+            Err(_) => return Vec::new(),
+        };
+        
+        // Half a quote or a heredoc body isn't something to predict from
+        if line.is_incomplete() {
+            return Vec::new();
+        }
+        
+        // Continued lines are predicted as the single logical line they form
+        let (prefix, command_line) = (line.prefix(), line.command_line());
+        let joined = if line.continued { command_line.replace("\\\n", "") } else { command_line.to_string() };
+        
+        // Expand a leading alias so `gco ma` is predicted as `git checkout ma`
+        let expansion = self.aliases().read().expand(&joined);
+        let effective_input = expansion.as_ref().map_or(joined.as_str(), |e| e.line.as_str());
+        
         // Parse command and current context
-        let cursor_pos = input.len(); // Assume cursor at end
-        let parsed = match self.command_parser.parse(input, cursor_pos) {
+        let parsed = match self.command_parser.parse(effective_input, effective_input.len()) {
             Ok(parsed) => parsed,
             Err(_) => return Vec::new(),
         };
         
         // Analyze context (filesystem, git, project type, etc.)
-        let context = self.context_analyzer.analyze(input, &parsed).await;
+        let context = self.context_analyzer.analyze(effective_input, &parsed).await;
         
         // Generate predictions concurrently from multiple sources
-        let predictions = self.generate_predictions(input, &parsed, &context, limit).await;
+        let mut predictions = self.generate_predictions(effective_input, &parsed, &context, limit).await;
+        
+        // Rewrite predictions back in terms of the alias the user actually typed
+        if let Some(expansion) = &expansion {
+            for prediction in predictions.iter_mut() {
+                prediction.text = intern(&expansion.restore(&prediction.text));
+                prediction.display_text = intern(&expansion.restore(&prediction.display_text));
+            }
+        }
+        
+        // Put the line back the way it's shown: earlier commands in front, continuations kept,
+        // and whatever follows the word under the cursor left alone
+        let suffix = &input[line.current_word_end..];
+        if !prefix.is_empty() || line.continued || !suffix.is_empty() {
+            for prediction in predictions.iter_mut() {
+                let rest = prediction.text.strip_prefix(joined.as_str()).unwrap_or(&prediction.text);
+                let typed = if rest.len() < prediction.text.len() { command_line } else { "" };
+                prediction.text = intern(&format!("{}{}{}{}", prefix, typed, rest, suffix));
+            }
+        }
         
         // Cache results
-        self.prediction_cache.set(input.to_string(), predictions.clone());
+        self.prediction_cache.set(cache_key, predictions.clone());
+        if at_end {
+            *self.last_predictions.lock() = Some(LastPredictions {
+                input: input.to_string(),
+                predictions: predictions.clone(),
+            });
+        }
         
         predictions
     }
     
+    /// The previous set filtered down to `input`, when `input` only appends characters to
+    /// the word it was generated for; `None` means the full pipeline has to run
+    fn narrow_last_predictions(&self, input: &str, limit: usize) -> Option<Vec<Prediction>> {
+        let _timing = self.performance_metrics.measure_operation("predict_incremental");
+        
+        let mut last = self.last_predictions.lock();
+        let previous = last.as_mut()?;
+        let appended = input.strip_prefix(previous.input.as_str())?;
+        
+        // A space or operator starts a new word, which the previous set knows nothing about
+        if appended.is_empty() || appended.contains(|c: char| c.is_whitespace() || "|&;<>()".contains(c)) {
+            return None;
+        }
+        
+        previous.predictions.retain(|prediction| {
+            prediction.text.len() > input.len() && prediction.text.starts_with(input)
+        });
+        if previous.predictions.is_empty() {
+            *last = None;
+            return None;
+        }
+        
+        previous.input = input.to_string();
+        Some(previous.predictions.iter().take(limit).cloned().collect())
+    }
+    
     /// Generate predictions from multiple sources concurrently
     async fn generate_predictions(
         &self,
@@ -79,8 +277,10 @@ impl PredictionEngine {
         let (tx, mut rx) = mpsc::channel(8);
         
         // Clone what we need for async blocks
-        let input_owned = input.to_string();
-        let context_clone = context.clone();
+        let input_owned: Arc<str> = Arc::from(input);
+        let context_clone = Arc::new(context.clone());
+        let (input1, input2, input3) = (input_owned.clone(), input_owned.clone(), input_owned.clone());
+        let (context2, context3) = (context_clone.clone(), context_clone.clone());
         let tx1 = tx.clone();
         let tx2 = tx.clone();
         let tx3 = tx.clone();
@@ -88,26 +288,34 @@ impl PredictionEngine {
         
         // 1. Generate history-based predictions (common commands)
         tokio::spawn(async move {
-            let predictions = Self::predict_from_history(&input_owned);
+            let predictions = Self::predict_from_history(&input1);
             let _ = tx1.send(predictions).await;
         });
         
         // 2. Generate directory context predictions (files, paths)
         tokio::spawn(async move {
-            let predictions = Self::predict_from_directory_context(&input_owned, &context_clone);
+            let predictions = Self::predict_from_directory_context(&input2, &context2);
             let _ = tx2.send(predictions).await;
         });
         
         // 3. Generate project-specific predictions
         tokio::spawn(async move {
-            let predictions = Self::predict_from_project_context(&input_owned, &context_clone);
+            let predictions = Self::predict_from_project_context(&input3, &context3);
             let _ = tx3.send(predictions).await;
         });
         
         // 4. Generate git-aware predictions if in a git repo
         if context.in_git_repo {
             tokio::spawn(async move {
-                let predictions = Self::predict_from_git_context(&input_owned, &context_clone);
+                let mut predictions = Self::predict_from_git_context(&input_owned, &context_clone);
+                
+                let branch_state = ContextAnalyzer::new()
+                    .get_branch_state(&context_clone.current_dir)
+                    .await;
+                if let Some(state) = branch_state {
+                    predictions.extend(Self::predict_from_branch_state(&input_owned, &state));
+                }
+                
                 let _ = tx4.send(predictions).await;
             });
         }
@@ -121,11 +329,24 @@ impl PredictionEngine {
             all_predictions.append(&mut predictions);
         }
         
-        // Apply user learning to adjust scores
-        self.user_learning.adjust_scores(&mut all_predictions, input);
+        // Offer the user's own aliases while the command name is being typed
+        all_predictions.extend(self.predict_from_aliases(input));
+        
+        // After a failure, lead with what fixes it
+        all_predictions.extend(self.predict_remediations(input));
+        
+        // Apply user learning to adjust scores, favouring what was accepted right here
+        let learning_context = self.learning_context(&context.current_dir);
+        self.learning().adjust_scores(&mut all_predictions, input, learning_context.as_ref());
         
         // Rank and limit predictions
         self.prediction_ranker.rank(&mut all_predictions);
+        if self.config.prediction.semantic_ranking.unwrap_or(false) {
+            let recent: Vec<String> = self.recent_commands.lock().iter().cloned().collect();
+            let budget = Duration::from_micros(self.config.prediction.semantic_ranking_budget_us.unwrap_or(500));
+            let query = semantic::query(input, &recent);
+            self.prediction_ranker.rerank_semantic(&mut all_predictions, &query, budget);
+        }
         all_predictions.truncate(limit);
         
         all_predictions
@@ -204,15 +425,24 @@ impl PredictionEngine {
                     ));
                 },
                 crate::suggestion::context::ProjectType::Node => {
-                    predictions.push(Prediction::new(
-                        "npm run dev".to_string(), 
-                        PredictionType::FullCommand,
-                        PredictionSource::DirectoryContext,
-                        Confidence(0.85)
-                    ));
+                    // Use the project's own package manager and the scripts it actually defines
+                    let package = PackageJson::load(&context.current_dir);
+                    let manager = package.as_ref().map_or("npm", |p| p.package_manager());
+                    
+                    if let Some(package) = &package {
+                        let script = ["dev", "start"].iter().find(|s| package.has_script(s));
+                        if let Some(script) = script {
+                            predictions.push(Prediction::new(
+                                format!("{} run {}", manager, script),
+                                PredictionType::FullCommand,
+                                PredictionSource::DirectoryContext,
+                                Confidence(0.85)
+                            ));
+                        }
+                    }
                     
                     predictions.push(Prediction::new(
-                        "npm install".to_string(), 
+                        format!("{} install", manager),
                         PredictionType::FullCommand,
                         PredictionSource::DirectoryContext,
                         Confidence(0.8)
@@ -227,30 +457,123 @@ impl PredictionEngine {
         predictions
     }
     
+    /// Commands a project of this type is usually built, tested or set up with, picking
+    /// the tool (wrapper scripts, Stack or Cabal) from what's in the directory
+    fn project_commands(context: &Context) -> Vec<(String, f32)> {
+        use crate::suggestion::context::ProjectType;
+        
+        let dir = &context.current_dir;
+        let commands = |list: &[(&str, f32)]| -> Vec<(String, f32)> {
+            list.iter().map(|&(command, confidence)| (command.to_string(), confidence)).collect()
+        };
+        match context.project_type {
+            ProjectType::Rust => commands(&[("cargo test", 0.7)]),
+            ProjectType::Python => commands(&[("python -m venv .venv", 0.7)]),
+            ProjectType::Java if dir.join("pom.xml").exists() => {
+                let mvn = if dir.join("mvnw").exists() { "./mvnw" } else { "mvn" };
+                vec![(format!("{} clean install", mvn), 0.7), (format!("{} test", mvn), 0.65)]
+            }
+            ProjectType::Java => {
+                let gradle = if dir.join("gradlew").exists() { "./gradlew" } else { "gradle" };
+                vec![(format!("{} build", gradle), 0.7), (format!("{} test", gradle), 0.65)]
+            }
+            ProjectType::Ruby if dir.join("spec").is_dir() => commands(&[("bundle exec rspec", 0.7), ("bundle install", 0.65)]),
+            ProjectType::Ruby => commands(&[("bundle install", 0.7), ("bundle exec rake", 0.65)]),
+            ProjectType::Php => commands(&[("composer install", 0.7), ("vendor/bin/phpunit", 0.65)]),
+            // Nothing else works before the first init
+            ProjectType::Terraform if !dir.join(".terraform").is_dir() => commands(&[("terraform init", 0.75)]),
+            ProjectType::Terraform => commands(&[("terraform plan", 0.7), ("terraform validate", 0.65)]),
+            ProjectType::Elixir => commands(&[("mix test", 0.7), ("mix deps.get", 0.65)]),
+            ProjectType::Haskell if dir.join("stack.yaml").exists() => commands(&[("stack build", 0.7), ("stack test", 0.65)]),
+            ProjectType::Haskell => commands(&[("cabal build", 0.7), ("cabal test", 0.65)]),
+            ProjectType::C if dir.join("build").is_dir() => commands(&[("cmake --build build", 0.7), ("ctest --test-dir build", 0.65)]),
+            ProjectType::C => commands(&[("cmake -S . -B build", 0.7)]),
+            ProjectType::DotNet => commands(&[("dotnet build", 0.7), ("dotnet test", 0.65)]),
+            _ => Vec::new(),
+        }
+    }
+    
     /// Predict based on project context
     fn predict_from_project_context(input: &str, context: &Context) -> Vec<Prediction> {
         let mut predictions = Vec::new();
         
-        // Example predictions based on project type
-        if input.is_empty() {
-            match context.project_type {
-                crate::suggestion::context::ProjectType::Rust => {
-                    predictions.push(Prediction::new(
-                        "cargo test".to_string(), 
-                        PredictionType::FullCommand,
-                        PredictionSource::ProjectType,
-                        Confidence(0.7)
-                    ));
-                },
-                crate::suggestion::context::ProjectType::Python => {
-                    predictions.push(Prediction::new(
-                        "python -m venv .venv".to_string(), 
-                        PredictionType::FullCommand,
-                        PredictionSource::ProjectType,
-                        Confidence(0.7)
-                    ));
-                },
-                _ => {}
+        // The project's usual build and test commands, on an empty line or as it's typed
+        for (command, confidence) in Self::project_commands(context) {
+            if input.is_empty() || (command.starts_with(input) && command.len() > input.len()) {
+                predictions.push(Prediction::new(
+                    command,
+                    PredictionType::FullCommand,
+                    PredictionSource::ProjectType,
+                    Confidence(confidence)
+                ));
+            }
+        }
+        
+        // `npm run <script>` and friends for the scripts in package.json
+        if matches!(context.project_type, crate::suggestion::context::ProjectType::Node) {
+            if let Some(package) = PackageJson::load(&context.current_dir) {
+                let manager = package.package_manager();
+                
+                for (script, body) in &package.scripts {
+                    let command = format!("{} run {}", manager, script);
+                    if !input.is_empty() && command.starts_with(input) && command.len() > input.len() {
+                        predictions.push(Prediction::new(
+                            command,
+                            PredictionType::FullCommand,
+                            PredictionSource::ProjectType,
+                            Confidence(0.6)
+                        ).with_explanation(body));
+                    }
+                }
+            }
+        }
+        
+        // Recipes from a justfile, Taskfile.yml, or tasks.py work in any project type
+        if !input.is_empty() {
+            for runner in TaskRunner::ALL {
+                if !runner.command().starts_with(input) && !input.starts_with(runner.command()) {
+                    continue;
+                }
+                
+                if let Some(recipes) = runner.recipes(&context.current_dir) {
+                    for recipe in recipes.iter() {
+                        let command = format!("{} {}", runner.command(), recipe.name);
+                        if command.starts_with(input) && command.len() > input.len() {
+                            predictions.push(Prediction::new(
+                                command,
+                                PredictionType::FullCommand,
+                                PredictionSource::ProjectType,
+                                Confidence(0.6)
+                            ).with_explanation(&recipe.description));
+                        }
+                    }
+                }
+            }
+        }
+        
+        // With several binaries a bare `cargo run` is ambiguous, so offer each target
+        if matches!(context.project_type, crate::suggestion::context::ProjectType::Rust)
+            && input.starts_with("cargo r")
+        {
+            if let Some(project) = CargoProject::load(&context.current_dir) {
+                let bins: Vec<&String> = project.packages_for(&context.current_dir)
+                    .into_iter()
+                    .flat_map(|package| package.bins.iter())
+                    .collect();
+                
+                if bins.len() > 1 {
+                    for bin in bins {
+                        let command = format!("cargo run --bin {}", bin);
+                        if command.starts_with(input) && command.len() > input.len() {
+                            predictions.push(Prediction::new(
+                                command,
+                                PredictionType::FullCommand,
+                                PredictionSource::ProjectType,
+                                Confidence(0.65)
+                            ));
+                        }
+                    }
+                }
             }
         }
         
@@ -287,8 +610,128 @@ impl PredictionEngine {
         predictions
     }
     
-    /// Record that a prediction was accepted
+    /// Predict upstream-aware git commands from the checked-out branch's tracking state
+    fn predict_from_branch_state(input: &str, state: &BranchState) -> Vec<Prediction> {
+        let mut predictions = Vec::new();
+        
+        // Only offer a command when the input is (the start of) it
+        let extends = |command: &str| command.starts_with(input) && command.len() > input.len();
+        
+        // First push of a new branch needs an upstream
+        if state.upstream.is_none() {
+            if let Some(remote) = &state.default_remote {
+                let command = format!("git push --set-upstream {} {}", remote, state.branch);
+                if extends(&command) {
+                    predictions.push(Prediction::new(
+                        command,
+                        PredictionType::FullCommand,
+                        PredictionSource::GitContext,
+                        Confidence(0.85)
+                    ).with_explanation(&format!("'{}' has no upstream branch yet", state.branch)));
+                }
+            }
+        }
+        
+        // Behind upstream: rebase local work on top of it instead of creating a merge commit
+        if state.behind > 0 {
+            let command = "git pull --rebase";
+            if extends(command) {
+                let upstream = state.upstream.as_deref().unwrap_or("upstream");
+                predictions.push(Prediction::new(
+                    command.to_string(),
+                    PredictionType::FullCommand,
+                    PredictionSource::GitContext,
+                    Confidence(0.8)
+                ).with_explanation(&format!("{} commit(s) behind {}", state.behind, upstream)));
+            }
+        }
+        
+        // Uncommitted changes: stashing them is a likely next step. Only offered as a
+        // continuation of what's typed, since ghost text can only extend the line
+        if state.dirty && extends("git stash") {
+            predictions.push(Prediction::new(
+                "git stash".to_string(),
+                PredictionType::FullCommand,
+                PredictionSource::GitContext,
+                Confidence(0.65)
+            ).with_explanation("Working tree has uncommitted changes"));
+        }
+        
+        predictions
+    }
+    
+    /// Fixes for the command that just failed that continue what's typed
+    fn predict_remediations(&self, input: &str) -> Vec<Prediction> {
+        let failure = match self.last_failure.lock().clone() {
+            Some(failure) => failure,
+            None => return Vec::new(),
+        };
+        
+        self.remediations()
+            .fixes(&failure)
+            .into_iter()
+            .filter(|(command, _)| command.starts_with(input) && command.len() > input.len())
+            .map(|(command, confidence)| {
+                let mut prediction = Prediction::new(command, PredictionType::FullCommand, PredictionSource::Remediation, confidence);
+                prediction.explanation = Some(intern(&format!("after `{}` failed ({})", failure.command, failure.status)));
+                prediction
+            })
+            .collect()
+    }
+    
+    /// Predict alias names matching the partially typed command
+    fn predict_from_aliases(&self, input: &str) -> Vec<Prediction> {
+        if input.is_empty() || input.contains(char::is_whitespace) {
+            return Vec::new();
+        }
+        
+        let aliases = self.aliases().read();
+        aliases.suggestions(input)
+            .into_iter()
+            .filter(|s| s.completion != input)
+            .map(|s| {
+                let mut prediction = Prediction::new(
+                    format!("{} ", s.completion),
+                    PredictionType::NextToken,
+                    PredictionSource::Alias,
+                    Confidence(0.6)
+                );
+                prediction.explanation = s.description.as_deref().map(intern);
+                prediction
+            })
+            .collect()
+    }
+    
+    /// Re-read the shell's aliases (called when the integration script reports a change)
+    ///
+    /// Aliases that were never loaded are read fresh on first use anyway.
+    pub fn reload_aliases(&self) -> bool {
+        match self.aliases.get() {
+            Some(aliases) => aliases.write().reload(),
+            None => false,
+        }
+    }
+    
+    /// Forget cached predictions, so the next call for every input is computed afresh
+    pub fn clear_cache(&self) {
+        self.prediction_cache.clear();
+        *self.last_predictions.lock() = None;
+    }
+    
+    /// Record that a prediction was accepted, in the current directory
     pub fn record_prediction_accepted(&self, prediction: &Prediction) {
-        self.user_learning.record_accepted_prediction(prediction);
+        let dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        let learning_context = self.learning_context(&dir);
+        self.learning().record_accepted_prediction(prediction, learning_context.as_ref());
+    }
+    
+    /// Where predictions are made and accepted, unless `prediction.enable_context_ranking`
+    /// is off
+    fn learning_context(&self, dir: &Path) -> Option<LearningContext> {
+        self.config
+            .prediction
+            .enable_context_ranking
+            .unwrap_or(true)
+            .then(|| self.context_analyzer.learning_context(dir))
     }
 }
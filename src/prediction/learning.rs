@@ -1,40 +1,81 @@
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::fs::{self, File};
-use std::io::{Read, Write};
+use std::fs;
 use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
 use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
 use super::models::Prediction;
+use crate::utils::cache_file;
+use crate::utils::memory::MemoryConsumer;
 
 // Constants for learning system
 const MAX_PATTERNS: usize = 10000;
 const SAVE_INTERVAL: usize = 100; // Save after this many new entries
+const DATA_VERSION: u32 = 2;
+
+/// Layout before context patterns were saved; still read so nothing learned is lost
+const DATA_VERSION_V1: u32 = 1;
+
+/// Contexts (directories, projects, branches) remembered; the least used are dropped
+const MAX_CONTEXTS: usize = 2000;
+
+/// Commands remembered per context
+const MAX_CONTEXT_COMMANDS: usize = 32;
+
+/// Acceptances in a context at which its boost is full
+const CONTEXT_SATURATION: usize = 5;
 
 /// System that learns from user behavior to improve predictions
 pub struct UserLearningSystem {
     data_file: PathBuf,
+    compress: bool,
     command_patterns: Arc<RwLock<HashMap<String, PatternData>>>,
     context_patterns: Arc<RwLock<HashMap<String, Vec<ContextPattern>>>>,
     modification_count: Arc<AtomicUsize>,
 }
 
 /// Data about a command pattern
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct PatternData {
     count: usize,
     last_used: u64, // Timestamp
 }
 
 /// Context-based pattern
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct ContextPattern {
     context_key: String,
     command: String,
     count: usize,
 }
 
+/// Where a prediction was accepted
+#[derive(Debug, Clone, Default)]
+pub struct LearningContext {
+    pub dir: PathBuf,
+    /// The project type's name, like `Rust`; `None` outside a known kind of project
+    pub project_type: Option<String>,
+    pub branch: Option<String>,
+}
+
+impl LearningContext {
+    /// The keys patterns are filed under, each with the boost a full match gives; the
+    /// same directory says the most, a shared project type the least
+    fn keys(&self) -> Vec<(String, f32)> {
+        let mut keys = vec![(format!("dir:{}", self.dir.display()), 0.3)];
+        if let Some(branch) = &self.branch {
+            keys.push((format!("branch:{}", branch), 0.2));
+        }
+        if let Some(project_type) = &self.project_type {
+            keys.push((format!("project:{}", project_type), 0.1));
+        }
+        keys
+    }
+}
+
 impl UserLearningSystem {
-    pub fn new(data_dir: &Path) -> Self {
+    /// Load learned patterns from `data_dir`, saving them compressed with `compress`
+    pub fn new(data_dir: &Path, compress: bool) -> Self {
         // Ensure data directory exists
         let data_dir = if data_dir.exists() && data_dir.is_dir() {
             data_dir.to_path_buf()
@@ -49,6 +90,7 @@ impl UserLearningSystem {
         
         let mut system = Self {
             data_file,
+            compress,
             command_patterns: Arc::new(RwLock::new(HashMap::new())),
             context_patterns: Arc::new(RwLock::new(HashMap::new())),
             modification_count: Arc::new(AtomicUsize::new(0)),
@@ -60,9 +102,9 @@ impl UserLearningSystem {
         system
     }
     
-    /// Record a prediction that the user accepted
-    pub fn record_accepted_prediction(&self, prediction: &Prediction) {
-        let command = prediction.text.clone();
+    /// Record a prediction that the user accepted, and where, when `context` is known
+    pub fn record_accepted_prediction(&self, prediction: &Prediction, context: Option<&LearningContext>) {
+        let command = prediction.text.to_string();
         
         // Update command pattern
         {
@@ -79,6 +121,10 @@ impl UserLearningSystem {
                 .unwrap_or(0);
         }
         
+        if let Some(context) = context {
+            self.record_context(&command, context);
+        }
+        
         // Record modification and possibly save
         let count = self.modification_count.fetch_add(1, Ordering::SeqCst);
         if count % SAVE_INTERVAL == 0 {
@@ -86,37 +132,106 @@ impl UserLearningSystem {
         }
     }
     
-    /// Adjust prediction scores based on learned patterns
-    pub fn adjust_scores(&self, predictions: &mut Vec<Prediction>, input: &str) {
+    /// Count `command` under each of `context`'s keys
+    fn record_context(&self, command: &str, context: &LearningContext) {
+        let mut contexts = self.context_patterns.write();
+        for (key, _) in context.keys() {
+            if !contexts.contains_key(&key) && contexts.len() >= MAX_CONTEXTS {
+                // Make room by forgetting the context with the fewest acceptances
+                let least = contexts
+                    .iter()
+                    .min_by_key(|(_, patterns)| patterns.iter().map(|p| p.count).sum::<usize>())
+                    .map(|(key, _)| key.clone());
+                if let Some(least) = least {
+                    contexts.remove(&least);
+                }
+            }
+            
+            let patterns = contexts.entry(key.clone()).or_default();
+            match patterns.iter_mut().find(|p| p.command == command) {
+                Some(pattern) => pattern.count += 1,
+                None => {
+                    if patterns.len() >= MAX_CONTEXT_COMMANDS {
+                        if let Some(rarest) = patterns.iter().enumerate().min_by_key(|(_, p)| p.count).map(|(i, _)| i) {
+                            patterns.swap_remove(rarest);
+                        }
+                    }
+                    patterns.push(ContextPattern { context_key: key, command: command.to_string(), count: 1 });
+                }
+            }
+        }
+    }
+    
+    /// Times `command` was accepted
+    pub fn accepted_count(&self, command: &str) -> usize {
+        self.command_patterns.read().get(command).map_or(0, |pattern| pattern.count)
+    }
+    
+    /// Adjust prediction scores based on learned patterns; with `context`, commands
+    /// accepted in the same directory, branch or kind of project rank higher
+    pub fn adjust_scores(&self, predictions: &mut Vec<Prediction>, input: &str, context: Option<&LearningContext>) {
         let patterns = self.command_patterns.read();
+        let context_boosts = context.map(|context| self.context_boosts(context)).unwrap_or_default();
         
         for prediction in predictions.iter_mut() {
             // Check if this prediction matches a learned pattern
-            if let Some(pattern) = patterns.get(&prediction.text) {
+            if let Some(pattern) = patterns.get(&*prediction.text) {
                 let boost = (pattern.count as f32).min(10.0) / 10.0; // Max boost of 1.0
                 let current = prediction.confidence.0;
                 prediction.confidence.0 = (current + boost).min(1.0);
             }
+            if let Some(boost) = context_boosts.get(&*prediction.text) {
+                prediction.confidence.0 = (prediction.confidence.0 + boost).min(1.0);
+            }
         }
     }
     
+    /// The boost each command learned in `context` gets
+    fn context_boosts(&self, context: &LearningContext) -> HashMap<String, f32> {
+        let contexts = self.context_patterns.read();
+        let mut boosts: HashMap<String, f32> = HashMap::new();
+        for (key, weight) in context.keys() {
+            for pattern in contexts.get(&key).into_iter().flatten() {
+                let saturation = pattern.count.min(CONTEXT_SATURATION) as f32 / CONTEXT_SATURATION as f32;
+                *boosts.entry(pattern.command.clone()).or_insert(0.0) += weight * saturation;
+            }
+        }
+        boosts
+    }
+    
     /// Load learning data from disk
     fn load_data(&mut self) {
         if !self.data_file.exists() {
             return;
         }
         
-        match File::open(&self.data_file) {
-            Ok(mut file) => {
-                let mut buffer = Vec::new();
-                if file.read_to_end(&mut buffer).is_ok() {
-                    if let Ok(data) = bincode::deserialize::<SerializedData>(&buffer) {
-                        *self.command_patterns.write() = data.command_patterns;
-                    }
-                }
-            },
+        let data = cache_file::read::<SerializedData>(&self.data_file, DATA_VERSION).or_else(|_| {
+            cache_file::read::<SerializedDataV1>(&self.data_file, DATA_VERSION_V1).map(|data| SerializedData {
+                command_patterns: data.command_patterns,
+                context_patterns: HashMap::new(),
+            })
+        }).or_else(|error| {
+            // Files from before the header was added are plain bincode of the V1 layout
+            let bytes = fs::read(&self.data_file)?;
+            if cache_file::has_header(&bytes) {
+                return Err(error);
+            }
+            bincode::deserialize::<SerializedDataV1>(&bytes)
+                .map(|data| SerializedData {
+                    command_patterns: data.command_patterns,
+                    context_patterns: HashMap::new(),
+                })
+                .map_err(|_| error)
+        });
+        match data {
+            Ok(data) => {
+                *self.command_patterns.write() = data.command_patterns;
+                *self.context_patterns.write() = data.context_patterns;
+            }
+            // Learned patterns can't be regenerated; keep a damaged file aside instead of
+            // overwriting it on the next save
             Err(_) => {
-                // Failed to open file - start fresh
+                let _ = fs::rename(&self.data_file, self.data_file.with_extension("bin.corrupt"));
             }
         }
     }
@@ -125,20 +240,42 @@ impl UserLearningSystem {
     fn save_data(&self) {
         let data = SerializedData {
             command_patterns: self.command_patterns.read().clone(),
-            version: 1,
+            context_patterns: self.context_patterns.read().clone(),
         };
         
-        if let Ok(serialized) = bincode::serialize(&data) {
-            if let Ok(mut file) = File::create(&self.data_file) {
-                let _ = file.write_all(&serialized);
-            }
-        }
+        let _ = cache_file::write(&self.data_file, DATA_VERSION, &data, self.compress);
+    }
+}
+
+impl MemoryConsumer for UserLearningSystem {
+    fn memory_bytes(&self) -> usize {
+        let patterns: usize = self.command_patterns.read()
+            .keys()
+            .map(|command| command.len() + std::mem::size_of::<PatternData>())
+            .sum();
+        let contexts: usize = self.context_patterns.read()
+            .iter()
+            .flat_map(|(key, patterns)| std::iter::once(key.len()).chain(patterns.iter().map(|p| {
+                std::mem::size_of::<ContextPattern>() + p.context_key.len() + p.command.len()
+            })))
+            .sum();
+        patterns + contexts
     }
+    
+    /// Learned patterns are the user's data, not a cache; they're only reported
+    fn shrink_to(&self, _target: usize) {}
 }
 
 /// Data structure for serialization
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Serialize, Deserialize)]
 struct SerializedData {
     command_patterns: HashMap<String, PatternData>,
-    version: u32,
+    /// Context key to the commands accepted there
+    context_patterns: HashMap<String, Vec<ContextPattern>>,
+}
+
+/// `SerializedData` as version 1 saved it
+#[derive(Deserialize)]
+struct SerializedDataV1 {
+    command_patterns: HashMap<String, PatternData>,
 }
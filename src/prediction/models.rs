@@ -1,5 +1,9 @@
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use hashbrown::HashMap;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::string_pools::intern;
 
 /// Confidence level for a prediction
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
@@ -22,7 +26,8 @@ impl Confidence {
 }
 
 /// Type of prediction
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PredictionType {
     /// Complete command prediction
     FullCommand,
@@ -37,7 +42,8 @@ pub enum PredictionType {
 }
 
 /// Source of the prediction
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PredictionSource {
     /// From command history
     History,
@@ -51,16 +57,20 @@ pub enum PredictionSource {
     CommandPatterns,
     /// From user's personal patterns
     UserPatterns,
+    /// From the user's shell aliases
+    Alias,
+    /// A known or learned fix for the command that just failed
+    Remediation,
 }
 
 /// A command prediction with metadata
 #[derive(Debug, Clone)]
 pub struct Prediction {
-    /// The predicted text
-    pub text: String,
+    /// The predicted text, interned so clones don't allocate
+    pub text: Arc<str>,
     
     /// Display text (may include additional info)
-    pub display_text: String,
+    pub display_text: Arc<str>,
     
     /// Type of prediction
     pub prediction_type: PredictionType,
@@ -75,7 +85,7 @@ pub struct Prediction {
     pub tokens_completed: usize,
     
     /// Optional explanation of what this command does
-    pub explanation: Option<String>,
+    pub explanation: Option<Arc<str>>,
     
     /// User acceptance count (how often user selected this)
     pub usage_count: usize,
@@ -90,8 +100,9 @@ pub struct Prediction {
 impl Prediction {
     /// Create a new prediction
     pub fn new(text: String, prediction_type: PredictionType, source: PredictionSource, confidence: Confidence) -> Self {
+        let text = intern(&text);
         Self {
-            display_text: text.clone(),
+            display_text: Arc::clone(&text),
             text,
             prediction_type,
             source,
@@ -104,6 +115,15 @@ impl Prediction {
         }
     }
     
+    /// Approximate heap and inline size, for memory accounting
+    pub fn memory_bytes(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.text.len()
+            + self.display_text.len()
+            + self.explanation.as_ref().map_or(0, |explanation| explanation.len())
+            + self.metadata.iter().map(|(k, v)| k.len() + v.len()).sum::<usize>()
+    }
+    
     /// Check if prediction is still fresh
     #[inline]
     pub fn is_fresh(&self, max_age: Duration) -> bool {
@@ -114,7 +134,7 @@ impl Prediction {
     #[inline]
     pub fn get_ghost_text(&self, current_input: &str) -> String {
         if current_input.is_empty() {
-            return self.text.clone();
+            return self.text.to_string();
         }
         
         // Only show the part of the prediction that hasn't been typed yet
@@ -133,7 +153,7 @@ impl Prediction {
     
     /// Add explanation to the prediction
     pub fn with_explanation(mut self, explanation: &str) -> Self {
-        self.explanation = Some(explanation.to_string());
+        self.explanation = Some(intern(explanation));
         self
     }
     
@@ -145,7 +165,7 @@ impl Prediction {
     
     /// Set display text
     pub fn with_display_text(mut self, display_text: &str) -> Self {
-        self.display_text = display_text.to_string();
+        self.display_text = intern(display_text);
         self
     }
     
@@ -1,4 +1,11 @@
+use std::time::{Duration, Instant};
+
 use super::models::{Prediction, PredictionSource, PredictionType};
+use super::semantic::Embedding;
+use crate::utils::merge::{better_description, merge_by_key, AGREEMENT_SHARE};
+
+/// Share of the score semantic similarity makes up after re-ranking
+const SEMANTIC_WEIGHT: f32 = 0.25;
 
 /// Ranks predictions by relevance and confidence
 pub struct PredictionRanker;
@@ -20,6 +27,7 @@ impl PredictionRanker {
                 PredictionSource::History => score *= 1.2, // Boost history-based
                 PredictionSource::UserPatterns => score *= 1.3, // Boost user patterns
                 PredictionSource::GitContext => score *= 1.1, // Boost git context
+                PredictionSource::Remediation => score *= 1.3, // Boost fixes for a failure
                 _ => {}
             }
             
@@ -39,35 +47,55 @@ impl PredictionRanker {
             prediction.confidence.0 = score.min(1.0);
         }
         
+        // One prediction per text, scored higher when several sources agree
+        Self::dedup_predictions(predictions);
+        
         // Sort by confidence score (descending)
         predictions.sort_by(|a, b| {
             b.confidence.0.partial_cmp(&a.confidence.0)
                 .unwrap_or(std::cmp::Ordering::Equal)
         });
+    }
+    
+    /// Blend each prediction's similarity to `query` into its score and sort again, unless
+    /// that takes longer than `budget`, in which case the order is left alone; returns
+    /// whether the predictions were re-ranked
+    pub fn rerank_semantic(&self, predictions: &mut [Prediction], query: &Embedding, budget: Duration) -> bool {
+        let start = Instant::now();
+        let mut similarities = Vec::with_capacity(predictions.len());
+        for prediction in predictions.iter() {
+            if start.elapsed() > budget {
+                return false;
+            }
+            similarities.push(query.similarity(&Embedding::of(&prediction.text)));
+        }
         
-        // Remove duplicates, keeping the highest scored one
-        Self::dedup_predictions(predictions);
+        for (prediction, similarity) in predictions.iter_mut().zip(similarities) {
+            prediction.confidence.0 = (1.0 - SEMANTIC_WEIGHT) * prediction.confidence.0 + SEMANTIC_WEIGHT * similarity;
+        }
+        predictions.sort_by(|a, b| {
+            b.confidence.0.partial_cmp(&a.confidence.0)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        true
     }
     
-    /// Remove duplicate predictions keeping the highest scored one
+    /// Merge predictions of the same text: the best-scored one's source and type, the
+    /// longer explanation, and a share of the others' confidence
     fn dedup_predictions(predictions: &mut Vec<Prediction>) {
-        let mut seen = std::collections::HashSet::new();
-        let mut i = 0;
-        
-        while i < predictions.len() {
-            let text = &predictions[i].text;
-            
-            if seen.contains(text) {
-                predictions.remove(i);
-            } else {
-                seen.insert(text.clone());
-                i += 1;
+        let merged = merge_by_key(std::mem::take(predictions), |p| p.text.clone(), |kept, mut other| {
+            if other.confidence.0 > kept.confidence.0 {
+                std::mem::swap(kept, &mut other);
             }
-        }
+            kept.confidence.0 = (kept.confidence.0 + AGREEMENT_SHARE * other.confidence.0).min(1.0);
+            kept.usage_count = kept.usage_count.max(other.usage_count);
+            better_description(&mut kept.explanation, other.explanation);
+        });
+        *predictions = merged;
     }
     
     /// Filter predictions that are appropriate for ghost text display
-    pub fn filter_for_ghost(&self, predictions: &[Prediction]) -> Option<&Prediction> {
+    pub fn filter_for_ghost<'a>(&self, predictions: &'a [Prediction]) -> Option<&'a Prediction> {
         predictions.iter()
             .find(|p| p.confidence.is_high_enough_for_ghost())
     }
@@ -0,0 +1,249 @@
+//! Fixes for the command that just failed
+//!
+//! After a failure, the next line's predictions lead with what usually fixes it: a rule
+//! table of well-known remediations (a rejected `git push` is followed by
+//! `git pull --rebase`, "permission denied" by the same command with `sudo`, a script
+//! that isn't executable by `chmod +x`), and what the user ran after the same failure
+//! before, learned and kept in `remediations.bin`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use parking_lot::RwLock;
+
+use super::models::Confidence;
+use crate::shell::impact;
+use crate::utils::cache_file;
+
+const DATA_FILE: &str = "remediations.bin";
+
+/// Bump when the learned data's layout changes
+const DATA_VERSION: u32 = 1;
+
+/// Save after this many learned follow-ups
+const SAVE_INTERVAL: usize = 10;
+
+/// Failures remembered; the least used are dropped beyond this
+const MAX_FAILURES: usize = 500;
+
+/// Follow-ups remembered per failure
+const MAX_FOLLOWUPS: usize = 8;
+
+/// Most of a failed command's output searched for rules, from its end
+const MAX_OUTPUT: usize = 16 * 1024;
+
+const RULE_CONFIDENCE: f32 = 0.85;
+
+/// A follow-up seen once; each time it's seen again adds `LEARNED_STEP`
+const LEARNED_CONFIDENCE: f32 = 0.6;
+const LEARNED_STEP: f32 = 0.1;
+const MAX_LEARNED_CONFIDENCE: f32 = 0.95;
+
+/// What fixes a failure a rule matches
+enum Fix {
+    /// A fixed command line
+    Run(&'static str),
+    /// The failed command again, with `sudo`
+    Sudo,
+    /// `chmod +x` on the file the failed command ran by its path
+    Executable,
+    /// A command the tool printed itself, on the output line starting with this
+    Printed(&'static str),
+}
+
+/// A well-known remediation
+struct Rule {
+    /// The failed command starts with this; empty for any command
+    command: &'static str,
+    /// One of these appears in the output (case-insensitively); empty to match on the
+    /// status alone
+    output: &'static [&'static str],
+    /// The exit status, when it's what identifies the failure
+    status: Option<i32>,
+    fix: Fix,
+}
+
+const RULES: &[Rule] = &[
+    Rule { command: "git push", output: &["has no upstream branch"], status: None, fix: Fix::Printed("git push --set-upstream") },
+    Rule { command: "git push", output: &["[rejected]", "non-fast-forward", "fetch first"], status: None, fix: Fix::Run("git pull --rebase") },
+    Rule { command: "git pull", output: &["divergent branches", "need to specify how to reconcile"], status: None, fix: Fix::Run("git pull --rebase") },
+    Rule { command: "git pull", output: &["no tracking information"], status: None, fix: Fix::Printed("git branch --set-upstream-to") },
+    Rule { command: "git checkout", output: &["would be overwritten"], status: None, fix: Fix::Run("git stash") },
+    Rule { command: "git switch", output: &["would be overwritten"], status: None, fix: Fix::Run("git stash") },
+    Rule { command: "git pull", output: &["would be overwritten"], status: None, fix: Fix::Run("git stash") },
+    Rule { command: "git rebase", output: &["conflict"], status: None, fix: Fix::Run("git status") },
+    Rule { command: "git merge", output: &["conflict"], status: None, fix: Fix::Run("git status") },
+    Rule { command: "git commit", output: &["no changes added to commit"], status: None, fix: Fix::Run("git add -A") },
+    Rule { command: "npm", output: &["cannot find module", "missing script"], status: None, fix: Fix::Run("npm install") },
+    Rule { command: "cargo", output: &["could not find `cargo.toml`"], status: None, fix: Fix::Run("cargo init") },
+    Rule { command: "pip", output: &["externally-managed-environment"], status: None, fix: Fix::Run("python3 -m venv .venv") },
+    Rule { command: "", output: &["permission denied", "operation not permitted", "are you root", "must be run as root", "eacces"], status: None, fix: Fix::Sudo },
+    // What shells give a file that isn't executable
+    Rule { command: "", output: &[], status: Some(126), fix: Fix::Executable },
+];
+
+/// A command that failed, as the predictions for the next line see it
+#[derive(Debug, Clone)]
+pub struct Failure {
+    pub command: String,
+    pub status: i32,
+    /// The end of what it printed, when the terminal saw it
+    output: String,
+}
+
+impl Failure {
+    pub fn new(command: &str, status: i32, output: &[u8]) -> Self {
+        let output = &output[output.len().saturating_sub(MAX_OUTPUT)..];
+        Self {
+            command: command.trim().to_string(),
+            status,
+            output: String::from_utf8_lossy(output).into_owned(),
+        }
+    }
+
+    /// What identifies the failure for learning: the command and its subcommand (not its
+    /// arguments), and the status
+    fn key(&self) -> String {
+        let mut words = self.command.split_whitespace();
+        let mut key: Vec<&str> = words.next().into_iter().collect();
+        key.extend(words.next().filter(|word| !word.starts_with('-')));
+        format!("{} {}", key.join(" "), self.status)
+    }
+}
+
+/// The rule table and what was learned from the user's own fixes
+pub struct Remediations {
+    data_file: PathBuf,
+    compress: bool,
+    /// Failure key to follow-up commands and how often each was run
+    learned: RwLock<HashMap<String, HashMap<String, usize>>>,
+    modification_count: AtomicUsize,
+}
+
+impl Remediations {
+    /// Load what was learned from `data_dir`, saving it compressed with `compress`
+    pub fn new(data_dir: &Path, compress: bool) -> Self {
+        let data_file = data_dir.join(DATA_FILE);
+        // Learned fixes can't be regenerated; keep a damaged file aside instead of
+        // overwriting it on the next save
+        let learned = match cache_file::read(&data_file, DATA_VERSION) {
+            Ok(learned) => learned,
+            Err(_) if data_file.exists() => {
+                let _ = fs::rename(&data_file, data_file.with_extension("bin.corrupt"));
+                HashMap::new()
+            }
+            Err(_) => HashMap::new(),
+        };
+
+        Self {
+            data_file,
+            compress,
+            learned: RwLock::new(learned),
+            modification_count: AtomicUsize::new(0),
+        }
+    }
+
+    /// Commands that fix `failure`, most likely first
+    pub fn fixes(&self, failure: &Failure) -> Vec<(String, Confidence)> {
+        let mut fixes: Vec<(String, Confidence)> = Vec::new();
+        let mut add = |command: String, confidence: f32| {
+            if command == failure.command {
+                return;
+            }
+            match fixes.iter_mut().find(|(existing, _)| *existing == command) {
+                Some((_, existing)) => existing.0 = existing.0.max(confidence),
+                None => fixes.push((command, Confidence(confidence))),
+            }
+        };
+
+        let output = failure.output.to_lowercase();
+        for rule in RULES {
+            let applies = starts_command(&failure.command, rule.command)
+                && rule.status.map_or(true, |status| status == failure.status)
+                && (rule.output.is_empty() || rule.output.iter().any(|needle| output.contains(needle)));
+            if !applies {
+                continue;
+            }
+            let fix = match rule.fix {
+                Fix::Run(command) => Some(command.to_string()),
+                // A rejected SSH key isn't fixed by root, a file that can't be run isn't
+                // either, and a command that deletes or changes files shouldn't be one
+                // keypress from doing it as root
+                Fix::Sudo
+                    if !failure.command.starts_with("sudo ")
+                        && !output.contains("(publickey)")
+                        && failure.status != 126
+                        && !impact::is_destructive(&failure.command) =>
+                {
+                    Some(format!("sudo {}", failure.command))
+                }
+                Fix::Sudo => None,
+                Fix::Executable => failure
+                    .command
+                    .split_whitespace()
+                    .next()
+                    .filter(|program| program.contains('/'))
+                    .map(|program| format!("chmod +x {}", program)),
+                Fix::Printed(prefix) => failure.output.lines().map(str::trim).find(|line| line.starts_with(prefix)).map(str::to_string),
+            };
+            if let Some(fix) = fix {
+                add(fix, RULE_CONFIDENCE);
+            }
+        }
+
+        if let Some(followups) = self.learned.read().get(&failure.key()) {
+            for (command, count) in followups {
+                let confidence = LEARNED_CONFIDENCE + LEARNED_STEP * (*count as f32 - 1.0);
+                add(command.clone(), confidence.min(MAX_LEARNED_CONFIDENCE));
+            }
+        }
+
+        fixes.sort_by(|a, b| b.1 .0.partial_cmp(&a.1 .0).unwrap_or(std::cmp::Ordering::Equal));
+        fixes
+    }
+
+    /// Remember that `next` was run right after `failure`
+    pub fn learn(&self, failure: &Failure, next: &str) {
+        let next = next.trim();
+        if next.is_empty() || next == failure.command {
+            return;
+        }
+
+        {
+            let mut learned = self.learned.write();
+            if !learned.contains_key(&failure.key()) && learned.len() >= MAX_FAILURES {
+                // Make room by forgetting the failure whose fixes were used least
+                let least = learned
+                    .iter()
+                    .min_by_key(|(_, followups)| followups.values().sum::<usize>())
+                    .map(|(key, _)| key.clone());
+                if let Some(least) = least {
+                    learned.remove(&least);
+                }
+            }
+
+            let followups = learned.entry(failure.key()).or_default();
+            *followups.entry(next.to_string()).or_insert(0) += 1;
+            if followups.len() > MAX_FOLLOWUPS {
+                let rarest = followups.iter().min_by_key(|(_, count)| **count).map(|(command, _)| command.clone());
+                if let Some(rarest) = rarest {
+                    followups.remove(&rarest);
+                }
+            }
+        }
+
+        let count = self.modification_count.fetch_add(1, Ordering::SeqCst);
+        if count % SAVE_INTERVAL == 0 {
+            let _ = cache_file::write(&self.data_file, DATA_VERSION, &*self.learned.read(), self.compress);
+        }
+    }
+}
+
+/// Whether `command` is `prefix` or starts with it as whole words
+fn starts_command(command: &str, prefix: &str) -> bool {
+    match command.strip_prefix(prefix) {
+        Some(rest) => prefix.is_empty() || rest.is_empty() || rest.starts_with(' '),
+        None => false,
+    }
+}
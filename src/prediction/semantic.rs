@@ -0,0 +1,76 @@
+//! Embeddings for semantic re-ranking
+//!
+//! Commands are embedded as hashed character trigrams of their words plus the words
+//! themselves, so `docker compose up` lands near `docker-compose up -d` and `git log
+//! --oneline` near `git log --graph`. It's computed on the spot, in microseconds, and
+//! needs no model; the query mixes the line being typed with the commands run just
+//! before it.
+
+/// Size of an embedding
+const DIMENSIONS: usize = 256;
+
+/// Weight of whole words relative to their trigrams
+const WORD_WEIGHT: f32 = 2.0;
+
+/// Weight of the most recent command in the query; each older one counts half as much
+const RECENT_WEIGHT: f32 = 0.5;
+
+/// A unit vector, or all zeros for text with no words
+#[derive(Debug, Clone)]
+pub struct Embedding([f32; DIMENSIONS]);
+
+impl Embedding {
+    pub fn of(text: &str) -> Self {
+        let mut vector = [0.0; DIMENSIONS];
+        add_features(&mut vector, text, 1.0);
+        Self(normalized(vector))
+    }
+
+    /// Cosine similarity, 0 for unrelated text
+    pub fn similarity(&self, other: &Embedding) -> f32 {
+        self.0.iter().zip(other.0.iter()).map(|(a, b)| a * b).sum::<f32>().max(0.0)
+    }
+}
+
+/// What predictions are compared with: the input, and the recent commands (newest first)
+/// as context
+pub fn query(input: &str, recent: &[String]) -> Embedding {
+    let mut vector = [0.0; DIMENSIONS];
+    add_features(&mut vector, input, 1.0);
+    let mut weight = RECENT_WEIGHT;
+    for command in recent {
+        add_features(&mut vector, command, weight);
+        weight /= 2.0;
+    }
+    Embedding(normalized(vector))
+}
+
+fn add_features(vector: &mut [f32; DIMENSIONS], text: &str, weight: f32) {
+    for word in text.split(|c: char| c.is_whitespace() || c == '-' || c == '/' || c == '=').filter(|w| !w.is_empty()) {
+        let word = word.to_lowercase();
+        add_feature(vector, word.as_bytes(), weight * WORD_WEIGHT);
+
+        let padded: Vec<u8> = [b" ", word.as_bytes(), b" "].concat();
+        for trigram in padded.windows(3) {
+            add_feature(vector, trigram, weight);
+        }
+    }
+}
+
+/// Hash a feature into a dimension, with a sign so collisions tend to cancel
+fn add_feature(vector: &mut [f32; DIMENSIONS], feature: &[u8], weight: f32) {
+    // FNV-1a
+    let hash = feature.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+    vector[(hash % DIMENSIONS as u64) as usize] += sign * weight;
+}
+
+fn normalized(mut vector: [f32; DIMENSIONS]) -> [f32; DIMENSIONS] {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
@@ -0,0 +1,39 @@
+// Background service shared by every shell session
+pub mod protocol;
+pub mod client;
+pub mod heartbeat;
+pub mod log;
+pub mod supervisor;
+pub mod widget;
+#[cfg(unix)]
+pub mod daemon;
+#[cfg(unix)]
+pub mod remote;
+
+use std::path::PathBuf;
+
+use crate::config::Config;
+
+/// Socket the service listens on, inside the user data directory
+pub const SOCKET_FILE: &str = "rustfig.sock";
+
+/// Socket `rustfig ssh` forwards to remote hosts, which only answers predictions
+pub const REMOTE_SOCKET_FILE: &str = "rustfig-remote.sock";
+
+/// File holding the running service's process id, inside the user data directory
+pub const PID_FILE: &str = "rustfig.pid";
+
+/// Where the service for this configuration listens
+pub fn socket_path(config: &Config) -> PathBuf {
+    config.general.user_data_dir.join(SOCKET_FILE)
+}
+
+/// Where the service for this configuration listens for forwarded remote shells
+pub fn remote_socket_path(config: &Config) -> PathBuf {
+    config.general.user_data_dir.join(REMOTE_SOCKET_FILE)
+}
+
+/// Where the service for this configuration records its pid
+pub fn pid_path(config: &Config) -> PathBuf {
+    config.general.user_data_dir.join(PID_FILE)
+}
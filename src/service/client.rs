@@ -0,0 +1,72 @@
+use std::error::Error;
+use std::path::Path;
+#[cfg(unix)]
+use std::time::Duration;
+
+use super::protocol::{Request, Response};
+
+/// How long a client waits on the service before treating it as down
+#[cfg(unix)]
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A connection kept open for several requests, answered in order
+#[cfg(unix)]
+pub struct Connection {
+    reader: std::io::BufReader<std::os::unix::net::UnixStream>,
+}
+
+#[cfg(unix)]
+impl Connection {
+    pub fn connect(socket: &Path, timeout: Duration) -> Result<Self, Box<dyn Error>> {
+        let stream = std::os::unix::net::UnixStream::connect(socket)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+        Ok(Self { reader: std::io::BufReader::new(stream) })
+    }
+
+    /// Send one request and wait for its response
+    ///
+    /// After an error the connection may be out of step with the service; drop it.
+    pub fn request(&mut self, request: &Request) -> Result<Response, Box<dyn Error>> {
+        use std::io::{BufRead, Write};
+
+        let mut line = serde_json::to_string(request)?;
+        line.push('\n');
+        self.reader.get_mut().write_all(line.as_bytes())?;
+
+        let mut response = String::new();
+        self.reader.read_line(&mut response)?;
+        if response.is_empty() {
+            return Err("The service closed the connection without answering".into());
+        }
+
+        Ok(serde_json::from_str(&response)?)
+    }
+}
+
+/// Send one request and wait for the response
+#[cfg(unix)]
+pub fn request(socket: &Path, request: &Request) -> Result<Response, Box<dyn Error>> {
+    Connection::connect(socket, CLIENT_TIMEOUT)?.request(request)
+}
+
+#[cfg(not(unix))]
+pub fn request(_socket: &Path, _request: &Request) -> Result<Response, Box<dyn Error>> {
+    Err("The RustFig service needs Unix domain sockets, which this platform lacks".into())
+}
+
+/// Send one request, treating the service as down after `timeout` instead of the usual wait
+#[cfg(unix)]
+pub fn request_within(socket: &Path, request: &Request, timeout: Duration) -> Result<Response, Box<dyn Error>> {
+    Connection::connect(socket, timeout)?.request(request)
+}
+
+#[cfg(not(unix))]
+pub fn request_within(socket: &Path, request: &Request, _timeout: std::time::Duration) -> Result<Response, Box<dyn Error>> {
+    self::request(socket, request)
+}
+
+/// Whether a service answers on the socket
+pub fn is_running(socket: &Path) -> bool {
+    matches!(request(socket, &Request::Ping), Ok(Response::Pong { .. }))
+}
@@ -0,0 +1,383 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use parking_lot::Mutex;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+
+use crate::ai::AiProviderFactory;
+use crate::config::Config;
+use crate::prediction::PredictionEngine;
+use crate::shell::history::HistoryDb;
+use super::client;
+use super::heartbeat::{self, HEARTBEAT_INTERVAL};
+use super::log;
+use super::protocol::{RemotePrediction, Request, Response, ServiceInfo, ServiceStatus, SessionInfo, PROTOCOL_VERSION};
+use super::supervisor::{Supervisor, TaskResult};
+
+/// How often the AI providers are checked for the prompt widget
+const AI_PROBE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Longest request line read from a connection; a client sending more is disconnected
+/// rather than buffered without bound
+const MAX_REQUEST_BYTES: u64 = 256 * 1024;
+
+/// What a listener's connections may ask for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    /// Local shells and `rustfig` commands
+    Full,
+    /// The socket `rustfig ssh` forwards: anyone on the remote host who can reach it
+    /// only gets predictions, not history recording, sessions or `stop`
+    PredictOnly,
+}
+
+/// A shell attached to the service
+struct Session {
+    shell: String,
+    pid: u32,
+    tty: Option<String>,
+    attached: Instant,
+}
+
+/// State shared by every connection
+struct ServiceState {
+    started: Instant,
+    socket: PathBuf,
+    next_session: AtomicU64,
+    sessions: Mutex<HashMap<u64, Session>>,
+    supervisor: Supervisor,
+    /// Answers `predict` requests, which come from shells on remote hosts
+    predictions: PredictionEngine,
+    /// Where the commands shells report are recorded
+    history: HistoryDb,
+    /// Whether an AI provider was usable when last probed, for the prompt widget
+    ai_ready: AtomicBool,
+    stop: watch::Sender<bool>,
+}
+
+impl ServiceState {
+    fn info(&self) -> ServiceInfo {
+        let mut sessions: Vec<SessionInfo> = self.sessions
+            .lock()
+            .iter()
+            .map(|(id, session)| SessionInfo {
+                id: *id,
+                shell: session.shell.clone(),
+                pid: session.pid,
+                tty: session.tty.clone(),
+                attached_secs: session.attached.elapsed().as_secs(),
+            })
+            .collect();
+        sessions.sort_by_key(|session| session.id);
+
+        ServiceInfo {
+            pid: std::process::id(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            protocol: PROTOCOL_VERSION,
+            uptime_secs: self.started.elapsed().as_secs(),
+            socket: self.socket.clone(),
+            sessions,
+            tasks: self.supervisor.tasks(),
+        }
+    }
+}
+
+/// Run the service until it's told to stop (or gets SIGTERM/SIGINT)
+///
+/// With `daemonize` the process first detaches from its terminal. Call this before any
+/// async runtime exists: a forked child only keeps the thread that forked.
+pub fn run(config: &Config, daemonize: bool) -> Result<(), Box<dyn Error>> {
+    let socket = super::socket_path(config);
+    let remote_socket = super::remote_socket_path(config);
+    let pid_file = super::pid_path(config);
+    let heartbeat_file = heartbeat::heartbeat_path(config);
+    fs::create_dir_all(&config.general.user_data_dir)?;
+
+    if client::is_running(&socket) {
+        return Err(format!("RustFig service is already running ({})", socket.display()).into());
+    }
+
+    if daemonize {
+        detach()?;
+    }
+    fs::write(&pid_file, format!("{}\n", std::process::id()))?;
+
+    if let Some(performance) = &config.performance {
+        crate::utils::memory::set_budget_mb(performance.max_memory_mb);
+    }
+
+    let debug = config.general.debug || config.general.verbose_logging.unwrap_or(false);
+    log::init(&log::log_path()?, debug)?;
+    crate::utils::crash::install(config, crate::utils::crash::CrashSource::Service);
+    log::info(&format!("service {} started (pid {}, socket {})", env!("CARGO_PKG_VERSION"), std::process::id(), socket.display()));
+
+    let runtime = crate::utils::concurrent::build_runtime(config)?;
+    let result = runtime.block_on(serve(config, &socket, &remote_socket, &heartbeat_file));
+    match &result {
+        Ok(()) => log::info("service stopped"),
+        Err(e) => log::error(&format!("service failed: {}", e)),
+    }
+
+    let _ = fs::remove_file(&socket);
+    let _ = fs::remove_file(&remote_socket);
+    let _ = fs::remove_file(&pid_file);
+    let _ = fs::remove_file(&heartbeat_file);
+    result
+}
+
+/// Double fork into a new session, so the service has no controlling terminal and
+/// isn't killed when the shell that started it exits
+fn detach() -> Result<(), Box<dyn Error>> {
+    // SAFETY: `main` runs `service run` before building any runtime, and nothing before
+    // this starts a thread, so the child isn't missing one; the parents exit without
+    // unwinding
+    unsafe {
+        match libc::fork() {
+            -1 => return Err(io::Error::last_os_error().into()),
+            0 => {}
+            _ => libc::_exit(0),
+        }
+        if libc::setsid() == -1 {
+            return Err(io::Error::last_os_error().into());
+        }
+        match libc::fork() {
+            -1 => return Err(io::Error::last_os_error().into()),
+            0 => {}
+            _ => libc::_exit(0),
+        }
+        libc::umask(0o077);
+    }
+
+    std::env::set_current_dir("/")?;
+
+    // Nothing is left to read from or print to
+    let null = fs::OpenOptions::new().read(true).write(true).open("/dev/null")?;
+    for fd in 0..=2 {
+        // SAFETY: dup2 onto the standard descriptors of our own process
+        if unsafe { libc::dup2(null.as_raw_fd(), fd) } == -1 {
+            return Err(io::Error::last_os_error().into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Run the supervised tasks until a stop request or signal
+async fn serve(config: &Config, socket: &Path, remote_socket: &Path, heartbeat_file: &Path) -> Result<(), Box<dyn Error>> {
+    // Bind up front so a service that can't listen fails at startup instead of retrying quietly
+    let initial_listener = Arc::new(Mutex::new(Some(bind(socket)?)));
+    let initial_remote_listener = Arc::new(Mutex::new(Some(bind(remote_socket)?)));
+
+    let (stop, mut stopped) = watch::channel(false);
+    let supervisor = Supervisor::new(stop.subscribe());
+    let state = Arc::new(ServiceState {
+        started: Instant::now(),
+        socket: socket.to_path_buf(),
+        next_session: AtomicU64::new(1),
+        sessions: Mutex::new(HashMap::new()),
+        supervisor: supervisor.clone(),
+        predictions: PredictionEngine::new(config),
+        history: HistoryDb::from_config(config),
+        ai_ready: AtomicBool::new(false),
+        stop,
+    });
+    
+    let warm_state = Arc::clone(&state);
+    tokio::task::spawn_blocking(move || warm_state.predictions.warm_up());
+
+    let listener_state = Arc::clone(&state);
+    let listener_socket = socket.to_path_buf();
+    supervisor.spawn("listener", move || {
+        listen(Arc::clone(&listener_state), listener_socket.clone(), initial_listener.lock().take(), Access::Full)
+    });
+
+    let remote_state = Arc::clone(&state);
+    let remote_socket = remote_socket.to_path_buf();
+    supervisor.spawn("remote-listener", move || {
+        listen(Arc::clone(&remote_state), remote_socket.clone(), initial_remote_listener.lock().take(), Access::PredictOnly)
+    });
+
+    let heartbeat_file = heartbeat_file.to_path_buf();
+    let heartbeat_state = Arc::clone(&state);
+    supervisor.spawn("heartbeat", move || {
+        beat(heartbeat_file.clone(), Arc::clone(&heartbeat_state))
+    });
+
+    let probe_config = config.clone();
+    let probe_state = Arc::clone(&state);
+    supervisor.spawn("ai-probe", move || {
+        probe_ai(probe_config.clone(), Arc::clone(&probe_state))
+    });
+
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    tokio::select! {
+        _ = stopped.changed() => log::info("stop requested"),
+        _ = terminate.recv() => log::info("received SIGTERM"),
+        _ = interrupt.recv() => log::info("received SIGINT"),
+    }
+
+    // Tell the supervised tasks to wind down
+    let _ = state.stop.send(true);
+    Ok(())
+}
+
+/// Listen on a service socket, replacing a file left by a crashed service
+fn bind(socket: &Path) -> io::Result<UnixListener> {
+    if socket.exists() {
+        fs::remove_file(socket)?;
+    }
+    let listener = UnixListener::bind(socket)?;
+    fs::set_permissions(socket, fs::Permissions::from_mode(0o600))?;
+    Ok(listener)
+}
+
+/// Accept connections on `socket` until the service stops; a restart binds it again
+async fn listen(state: Arc<ServiceState>, socket: PathBuf, listener: Option<UnixListener>, access: Access) -> TaskResult {
+    let listener = match listener {
+        Some(listener) => listener,
+        None => bind(&socket)?,
+    };
+    let mut stopped = state.stop.subscribe();
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                // Accept errors (e.g. running out of descriptors) are transient; keep serving
+                match accepted {
+                    Ok((stream, _)) => {
+                        tokio::spawn(handle_connection(stream, Arc::clone(&state), access));
+                    }
+                    Err(e) => {
+                        log::warn(&format!("accept failed: {}", e));
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                    }
+                }
+            }
+            _ = stopped.changed() => return Ok(()),
+        }
+    }
+}
+
+/// Rewrite the heartbeat file until the service stops
+async fn beat(path: PathBuf, state: Arc<ServiceState>) -> TaskResult {
+    let mut stopped = state.stop.subscribe();
+
+    loop {
+        heartbeat::write_heartbeat(&path, state.supervisor.tasks())?;
+        tokio::select! {
+            _ = tokio::time::sleep(HEARTBEAT_INTERVAL) => {}
+            _ = stopped.changed() => return Ok(()),
+        }
+    }
+}
+
+/// Check whether an AI provider is usable until the service stops; Ollama is only
+/// asked every `AI_PROBE_INTERVAL`, not at every prompt
+async fn probe_ai(config: Config, state: Arc<ServiceState>) -> TaskResult {
+    let mut stopped = state.stop.subscribe();
+
+    loop {
+        let ready = AiProviderFactory::create_provider(&config).await.is_some();
+        state.ai_ready.store(ready, Ordering::Relaxed);
+        tokio::select! {
+            _ = tokio::time::sleep(AI_PROBE_INTERVAL) => {}
+            _ = stopped.changed() => return Ok(()),
+        }
+    }
+}
+
+/// Answer requests on one connection; an attached session lasts until it closes
+async fn handle_connection(stream: UnixStream, state: Arc<ServiceState>, access: Access) {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    let mut session = None;
+
+    loop {
+        line.clear();
+        match (&mut reader).take(MAX_REQUEST_BYTES).read_until(b'\n', &mut line).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        if !line.ends_with(b"\n") && line.len() as u64 >= MAX_REQUEST_BYTES {
+            log::warn(&format!("dropped a connection whose request exceeded {} bytes", MAX_REQUEST_BYTES));
+            break;
+        }
+
+        let response = match serde_json::from_slice::<Request>(&line) {
+            Ok(request) if access == Access::PredictOnly && !matches!(request, Request::Predict { .. }) => {
+                Response::Error { message: "Only predictions are served to remote hosts".to_string() }
+            }
+            Ok(request) => respond(request, &state, &mut session).await,
+            Err(e) => Response::Error { message: format!("Invalid request: {}", e) },
+        };
+
+        let mut encoded = match serde_json::to_string(&response) {
+            Ok(encoded) => encoded,
+            Err(_) => break,
+        };
+        encoded.push('\n');
+        if writer.write_all(encoded.as_bytes()).await.is_err() {
+            break;
+        }
+
+        // Stop only once the client has its answer
+        if matches!(response, Response::Stopping) {
+            let _ = state.stop.send(true);
+            break;
+        }
+    }
+
+    if let Some(id) = session {
+        state.sessions.lock().remove(&id);
+        log::debug(&format!("session #{} detached", id));
+    }
+}
+
+async fn respond(request: Request, state: &ServiceState, session: &mut Option<u64>) -> Response {
+    match request {
+        Request::Ping => Response::Pong { version: env!("CARGO_PKG_VERSION").to_string() },
+        Request::Info => Response::Info(state.info()),
+        Request::Stop => Response::Stopping,
+        Request::Metrics => Response::Metrics { components: crate::utils::perf_metrics::snapshot() },
+        Request::Memory => Response::Memory(crate::utils::memory::report()),
+        Request::Attach { shell, pid, tty } => {
+            // Re-attaching on the same connection updates the existing session
+            let id = *session.get_or_insert_with(|| state.next_session.fetch_add(1, Ordering::Relaxed));
+            log::debug(&format!("session #{} attached: {} (pid {})", id, shell, pid));
+            state.sessions.lock().insert(id, Session { shell, pid, tty, attached: Instant::now() });
+            Response::Attached { session: id }
+        }
+        Request::Predict { input, cursor, limit } => {
+            if cursor > input.len() || !input.is_char_boundary(cursor) {
+                return Response::Error { message: format!("Cursor {} is not a character boundary", cursor) };
+            }
+            let predictions = state.predictions.predict(&input, cursor, limit).await;
+            Response::Predictions { predictions: predictions.iter().map(RemotePrediction::from).collect() }
+        }
+        Request::RecordCommand { entry } => {
+            state.predictions.note_command(&entry.command);
+            // The hooks don't see the output, so only status rules and learned fixes apply
+            state.predictions.note_result(&entry.command, entry.exit_code, &[]);
+            match state.history.record(&entry) {
+                Ok(()) => Response::Recorded,
+                Err(e) => Response::Error { message: format!("Couldn't record the command: {}", e) },
+            }
+        }
+        Request::Status => Response::Status(ServiceStatus {
+            ai_ready: state.ai_ready.load(Ordering::Relaxed),
+            offline: crate::utils::offline::is_offline(),
+        }),
+    }
+}
@@ -0,0 +1,69 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use super::protocol::TaskInfo;
+
+/// File the service rewrites while it's healthy, inside the user data directory
+pub const HEARTBEAT_FILE: &str = "rustfig.heartbeat";
+
+/// How often the service writes its heartbeat
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A heartbeat older than this means the service is wedged, even if its process exists
+pub const HEARTBEAT_STALE_AFTER: Duration = Duration::from_secs(30);
+
+/// Snapshot of the service's health
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Heartbeat {
+    pub pid: u32,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub tasks: Vec<TaskInfo>,
+}
+
+impl Heartbeat {
+    /// How long ago the heartbeat was written
+    pub fn age(&self) -> Duration {
+        Duration::from_secs(unix_now().saturating_sub(self.timestamp))
+    }
+
+    /// Whether the service has stopped beating
+    pub fn is_stale(&self) -> bool {
+        self.age() > HEARTBEAT_STALE_AFTER
+    }
+}
+
+/// Where the service for this configuration writes its heartbeat
+pub fn heartbeat_path(config: &Config) -> PathBuf {
+    config.general.user_data_dir.join(HEARTBEAT_FILE)
+}
+
+/// Record that the service is alive, with the state of its tasks
+pub fn write_heartbeat(path: &Path, tasks: Vec<TaskInfo>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let heartbeat = Heartbeat {
+        pid: std::process::id(),
+        timestamp: unix_now(),
+        tasks,
+    };
+
+    // Write to a temporary file first so readers never see a torn heartbeat
+    let tmp_path = path.with_extension("heartbeat.tmp");
+    fs::write(&tmp_path, serde_json::to_string(&heartbeat)?)?;
+    fs::rename(tmp_path, path)?;
+
+    Ok(())
+}
+
+/// The last heartbeat, if the service has written one
+pub fn read_heartbeat(path: &Path) -> Option<Heartbeat> {
+    let json = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+}
@@ -0,0 +1,212 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use parking_lot::Mutex;
+
+/// Directory inside the config directory that holds the service logs
+pub const LOG_DIR: &str = "logs";
+
+/// The log the service is currently writing; rotated copies get `.1`, `.2`, ... appended
+pub const LOG_FILE: &str = "service.log";
+
+/// Size at which the current log is rotated
+const MAX_LOG_BYTES: u64 = 1024 * 1024;
+
+/// Rotated logs kept besides the current one
+const KEEP_ROTATED: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    fn as_str(self) -> &'static str {
+        match self {
+            Level::Debug => "DEBUG",
+            Level::Info => "INFO",
+            Level::Warn => "WARN",
+            Level::Error => "ERROR",
+        }
+    }
+}
+
+/// A size-rotated log file
+struct RotatingLog {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    debug: bool,
+}
+
+impl RotatingLog {
+    fn open(path: &Path, debug: bool) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(Self { path: path.to_path_buf(), file, size, debug })
+    }
+
+    fn write(&mut self, level: Level, message: &str) -> io::Result<()> {
+        if self.size >= MAX_LOG_BYTES {
+            self.rotate()?;
+        }
+        let line = format!("{} {:<5} {}\n", format_timestamp(unix_now()), level.as_str(), message);
+        self.file.write_all(line.as_bytes())?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Shift `service.log.N` to `.N+1`, dropping the oldest, and start a fresh log
+    fn rotate(&mut self) -> io::Result<()> {
+        for index in (1..KEEP_ROTATED).rev() {
+            let from = rotated_path(&self.path, index);
+            if from.exists() {
+                fs::rename(from, rotated_path(&self.path, index + 1))?;
+            }
+        }
+        fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+static LOG: OnceLock<Mutex<RotatingLog>> = OnceLock::new();
+
+/// Where the service writes its log
+pub fn log_path() -> Result<PathBuf, Box<dyn std::error::Error>> {
+    Ok(crate::config::loader::get_config_dir()?.join(LOG_DIR).join(LOG_FILE))
+}
+
+/// Send this process's log lines to `path`; debug lines are only kept with `debug`
+pub fn init(path: &Path, debug: bool) -> io::Result<()> {
+    let log = RotatingLog::open(path, debug)?;
+    // A second init keeps the first log
+    let _ = LOG.set(Mutex::new(log));
+    Ok(())
+}
+
+/// Append a line to the service log, if one was initialized
+///
+/// Logging never fails the caller: a full disk shouldn't take completions down with it.
+pub fn write(level: Level, message: &str) {
+    if let Some(log) = LOG.get() {
+        let mut log = log.lock();
+        if level != Level::Debug || log.debug {
+            let _ = log.write(level, message);
+        }
+    }
+}
+
+pub fn debug(message: &str) {
+    write(Level::Debug, message);
+}
+
+pub fn info(message: &str) {
+    write(Level::Info, message);
+}
+
+pub fn warn(message: &str) {
+    write(Level::Warn, message);
+}
+
+pub fn error(message: &str) {
+    write(Level::Error, message);
+}
+
+/// The current log and its rotated copies, oldest first
+pub fn log_files(path: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = (1..=KEEP_ROTATED)
+        .rev()
+        .map(|index| rotated_path(path, index))
+        .collect();
+    files.push(path.to_path_buf());
+    files.retain(|file| file.is_file());
+    files
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+/// Seconds since the Unix epoch at which a log line was written
+pub fn line_timestamp(line: &str) -> Option<u64> {
+    parse_timestamp(line.split_whitespace().next()?)
+}
+
+/// Parse a duration like `30s`, `15m`, `1h` or `2d`
+pub fn parse_since(since: &str) -> Option<Duration> {
+    let since = since.trim();
+    let split = since.find(|c: char| !c.is_ascii_digit())?;
+    let amount: u64 = since[..split].parse().ok()?;
+    let unit = match &since[split..] {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return None,
+    };
+    Some(Duration::from_secs(amount * unit))
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// `2026-10-18T09:30:00Z` for a Unix timestamp
+pub fn format_timestamp(secs: u64) -> String {
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let time = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, time / 3600, time / 60 % 60, time % 60
+    )
+}
+
+/// Inverse of `format_timestamp`
+fn parse_timestamp(text: &str) -> Option<u64> {
+    let text = text.strip_suffix('Z')?;
+    let (date, time) = text.split_once('T')?;
+
+    let mut date = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+    let mut time = time.splitn(3, ':').map(|part| part.parse::<u64>().ok());
+    let (hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+
+    let days = u64::try_from(days_from_civil(year, month, day)).ok()?;
+    Some(days * 86_400 + hour * 3600 + minute * 60 + second)
+}
+
+// Howard Hinnant's days <-> civil date algorithms, for the proleptic Gregorian calendar
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
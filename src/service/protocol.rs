@@ -0,0 +1,139 @@
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+
+use crate::prediction::models::{Confidence, Prediction, PredictionSource, PredictionType};
+use crate::shell::history::HistoryEntry;
+use crate::utils::memory::MemoryReport;
+use crate::utils::perf_metrics::ComponentSnapshot;
+
+/// Bump when requests or responses change incompatibly
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// A request to the service, sent as one JSON object per line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    /// Liveness check
+    Ping,
+    /// Process and session details
+    Info,
+    /// Shut the service down
+    Stop,
+    /// Operation counts and latencies recorded by the service
+    Metrics,
+    /// Memory accounted against `performance.max_memory_mb`
+    Memory,
+    /// Register this connection as a shell session for as long as it stays open
+    Attach {
+        shell: String,
+        pid: u32,
+        #[serde(default)]
+        tty: Option<String>,
+    },
+    /// Predict for a command line; lets shells on remote hosts use this machine's engine
+    Predict {
+        input: String,
+        /// Byte offset of the cursor in `input`
+        cursor: usize,
+        limit: usize,
+    },
+    /// A command finished in a shell, with its exit status and duration; the service
+    /// writes it to the history database
+    RecordCommand { entry: HistoryEntry },
+    /// What the prompt's status widget shows
+    Status,
+}
+
+/// The service's answer to one request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    Pong { version: String },
+    Info(ServiceInfo),
+    /// Sent before the service shuts down
+    Stopping,
+    Attached { session: u64 },
+    Metrics { components: Vec<ComponentSnapshot> },
+    Memory(MemoryReport),
+    Predictions { predictions: Vec<RemotePrediction> },
+    Recorded,
+    Status(ServiceStatus),
+    Error { message: String },
+}
+
+/// What `info` reports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceInfo {
+    pub pid: u32,
+    pub version: String,
+    pub protocol: u32,
+    pub uptime_secs: u64,
+    pub socket: PathBuf,
+    pub sessions: Vec<SessionInfo>,
+    /// Supervised background tasks
+    #[serde(default)]
+    pub tasks: Vec<TaskInfo>,
+}
+
+/// What `status` reports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceStatus {
+    /// An AI provider was usable when last checked
+    pub ai_ready: bool,
+    /// Offline mode is on, or there's no network
+    pub offline: bool,
+}
+
+/// A supervised background task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskInfo {
+    pub name: String,
+    /// False once the task has finished for good
+    pub running: bool,
+    /// Times it was restarted after a panic or error
+    pub restarts: u32,
+    pub last_failure: Option<String>,
+}
+
+/// A shell attached to the service
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionInfo {
+    pub id: u64,
+    pub shell: String,
+    pub pid: u32,
+    pub tty: Option<String>,
+    pub attached_secs: u64,
+}
+
+/// A prediction as sent to a remote shell
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemotePrediction {
+    pub text: String,
+    pub display_text: String,
+    pub prediction_type: PredictionType,
+    pub source: PredictionSource,
+    pub confidence: f32,
+    pub explanation: Option<String>,
+}
+
+impl From<&Prediction> for RemotePrediction {
+    fn from(prediction: &Prediction) -> Self {
+        Self {
+            text: prediction.text.to_string(),
+            display_text: prediction.display_text.to_string(),
+            prediction_type: prediction.prediction_type.clone(),
+            source: prediction.source.clone(),
+            confidence: prediction.confidence.value(),
+            explanation: prediction.explanation.as_deref().map(str::to_string),
+        }
+    }
+}
+
+impl RemotePrediction {
+    pub fn into_prediction(self) -> Prediction {
+        let mut prediction = Prediction::new(self.text, self.prediction_type, self.source, Confidence(self.confidence));
+        prediction.display_text = self.display_text.into();
+        prediction.explanation = self.explanation.map(Into::into);
+        prediction
+    }
+}
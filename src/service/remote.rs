@@ -0,0 +1,154 @@
+//! Predictions from the service on the machine the user connected from
+//!
+//! `rustfig ssh` forwards the local service's predict-only socket to the remote host
+//! (`ssh -R`) as `/tmp/rustfig-remote-*.sock`. A shell on that host finds the socket and
+//! asks the local service to predict, so AI and history features keep working on thin
+//! servers that couldn't run them.
+//!
+//! Every other request over the socket is refused, so the remote host can't record
+//! history, read service details or stop the service.
+//!
+//! It's governed by `ssh.enable_command_caching`. Answers are cached for a minute, so
+//! retyping a line doesn't cross the network again.
+
+use std::fs;
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use parking_lot::Mutex;
+
+use crate::config::Config;
+use crate::prediction::cache::PredictionCache;
+use crate::prediction::models::Prediction;
+use crate::utils::ssh::is_ssh_session;
+use super::client::Connection;
+use super::protocol::{Request, Response};
+
+/// Points a remote shell at a specific forwarded socket, instead of searching for one
+pub const REMOTE_SOCKET_ENV: &str = "RUSTFIG_REMOTE_SOCKET";
+
+/// Where forwarded sockets are created on the remote host
+const FORWARD_DIR: &str = "/tmp";
+
+const FORWARD_PREFIX: &str = "rustfig-remote-";
+
+/// A round trip over SSH; slower than this and the keystroke goes without a prediction
+const REMOTE_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// After a failed request the local engine is used for this long before trying again
+const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Path to bind on the remote host, unique to this connection
+///
+/// sshd doesn't replace existing sockets unless configured to (`StreamLocalBindUnlink`),
+/// so every connection gets its own name and stale ones are removed by `find_forwarded_socket`.
+pub fn forward_path() -> PathBuf {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |since| since.subsec_nanos());
+    Path::new(FORWARD_DIR).join(format!("{}{}-{:08x}.sock", FORWARD_PREFIX, std::process::id(), nanos))
+}
+
+/// The forwarded socket of the most recent connection that still answers
+pub fn find_forwarded_socket() -> Option<PathBuf> {
+    if let Some(socket) = std::env::var_os(REMOTE_SOCKET_ENV).filter(|socket| !socket.is_empty()) {
+        return Some(PathBuf::from(socket));
+    }
+
+    // sshd creates the socket as the logged-in user
+    // SAFETY: getuid has no preconditions and can't fail
+    let uid = unsafe { libc::getuid() };
+    let mut candidates: Vec<(SystemTime, PathBuf)> = fs::read_dir(FORWARD_DIR)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name.starts_with(FORWARD_PREFIX) && name.ends_with(".sock")
+        })
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            (metadata.uid() == uid).then(|| (metadata.modified().unwrap_or(UNIX_EPOCH), entry.path()))
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.0.cmp(&a.0));
+
+    for (_, path) in candidates {
+        match std::os::unix::net::UnixStream::connect(&path) {
+            Ok(_) => return Some(path),
+            // Nothing listens any more: left behind by a connection that has closed
+            Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => {
+                let _ = fs::remove_file(&path);
+            }
+            Err(_) => {}
+        }
+    }
+    None
+}
+
+/// Asks the service at the other end of the SSH connection for predictions
+pub struct RemotePredictor {
+    socket: PathBuf,
+    connection: Mutex<Option<Connection>>,
+    /// Set after a failure; until then requests aren't attempted
+    retry_at: Mutex<Option<Instant>>,
+    cache: PredictionCache,
+}
+
+impl RemotePredictor {
+    /// A predictor for this SSH session, if `ssh.enable_command_caching` allows it and a
+    /// forwarded socket exists
+    pub fn detect(config: &Config) -> Option<Self> {
+        let ssh = config.ssh.as_ref()?;
+        if !ssh.enable_command_caching.unwrap_or(true) || !is_ssh_session() {
+            return None;
+        }
+
+        let socket = find_forwarded_socket()?;
+        Some(Self {
+            socket,
+            connection: Mutex::new(None),
+            retry_at: Mutex::new(None),
+            cache: PredictionCache::new(500, Duration::from_secs(60)),
+        })
+    }
+
+    pub fn socket(&self) -> &Path {
+        &self.socket
+    }
+
+    /// Predictions from the local machine, or None while it can't be reached
+    pub fn predict(&self, input: &str, cursor: usize, limit: usize) -> Option<Vec<Prediction>> {
+        let cache_key = format!("{}\u{0}{}\u{0}{}", input, cursor, limit);
+        if let Some(predictions) = self.cache.get(&cache_key) {
+            return Some(predictions);
+        }
+
+        if self.retry_at.lock().map_or(false, |at| Instant::now() < at) {
+            return None;
+        }
+
+        let request = Request::Predict { input: input.to_string(), cursor, limit };
+        let predictions = match self.request(&request) {
+            Ok(Response::Predictions { predictions }) => predictions,
+            _ => {
+                // A connection that failed mid-request may be out of step; start over later
+                *self.connection.lock() = None;
+                *self.retry_at.lock() = Some(Instant::now() + RETRY_INTERVAL);
+                return None;
+            }
+        };
+        *self.retry_at.lock() = None;
+
+        let predictions: Vec<Prediction> = predictions.into_iter().map(|prediction| prediction.into_prediction()).collect();
+        self.cache.set(cache_key, predictions.clone());
+        Some(predictions)
+    }
+
+    fn request(&self, request: &Request) -> Result<Response, Box<dyn std::error::Error>> {
+        let mut connection = self.connection.lock();
+        if connection.is_none() {
+            *connection = Some(Connection::connect(&self.socket, REMOTE_TIMEOUT)?);
+        }
+        connection.as_mut().expect("connected above").request(request)
+    }
+}
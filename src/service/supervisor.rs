@@ -0,0 +1,117 @@
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use parking_lot::Mutex;
+use tokio::sync::watch;
+
+use super::log;
+use super::protocol::TaskInfo;
+
+/// Delay before the first restart of a failed task
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Longest delay between restarts of a task that keeps failing
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// A task that ran this long before failing counts as healthy, so its backoff starts over
+const HEALTHY_RUN: Duration = Duration::from_secs(60);
+
+/// What a supervised task returns; an error restarts it like a panic does
+pub type TaskResult = Result<(), Box<dyn Error + Send + Sync>>;
+
+/// Keeps the service's long-running tasks alive
+///
+/// A task that panics or returns an error is restarted with exponential backoff, so one
+/// bad input can't leave every terminal without completions until a manual restart.
+#[derive(Clone)]
+pub struct Supervisor {
+    tasks: Arc<Mutex<BTreeMap<String, TaskInfo>>>,
+    stop: watch::Receiver<bool>,
+}
+
+impl Supervisor {
+    /// Supervise until `stop` turns true
+    pub fn new(stop: watch::Receiver<bool>) -> Self {
+        Self {
+            tasks: Arc::new(Mutex::new(BTreeMap::new())),
+            stop,
+        }
+    }
+
+    /// Run `task` until it finishes cleanly or the service stops, restarting it on failure
+    pub fn spawn<F, Fut>(&self, name: &str, task: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = TaskResult> + Send + 'static,
+    {
+        let name = name.to_string();
+        self.tasks.lock().insert(name.clone(), TaskInfo {
+            name: name.clone(),
+            running: true,
+            restarts: 0,
+            last_failure: None,
+        });
+
+        let tasks = Arc::clone(&self.tasks);
+        let mut stop = self.stop.clone();
+
+        tokio::spawn(async move {
+            let mut backoff = INITIAL_BACKOFF;
+
+            loop {
+                let started = Instant::now();
+
+                // A separate task, so a panic surfaces here as a JoinError instead of unwinding the supervisor
+                let failure = match tokio::spawn(task()).await {
+                    Ok(Ok(())) => None,
+                    Ok(Err(e)) => Some(e.to_string()),
+                    Err(e) if e.is_panic() => Some(format!("panicked: {}", panic_message(e.into_panic()))),
+                    Err(_) => None,
+                };
+
+                let failure = match failure {
+                    Some(failure) if !*stop.borrow() => failure,
+                    _ => {
+                        if let Some(info) = tasks.lock().get_mut(&name) {
+                            info.running = false;
+                        }
+                        return;
+                    }
+                };
+
+                if started.elapsed() >= HEALTHY_RUN {
+                    backoff = INITIAL_BACKOFF;
+                }
+                log::error(&format!("task {} failed: {}; restarting in {:?}", name, failure, backoff));
+                if let Some(info) = tasks.lock().get_mut(&name) {
+                    info.restarts += 1;
+                    info.last_failure = Some(failure);
+                }
+
+                tokio::select! {
+                    _ = tokio::time::sleep(backoff) => {}
+                    _ = stop.changed() => return,
+                }
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        });
+    }
+
+    /// Current state of every supervised task, by name
+    pub fn tasks(&self) -> Vec<TaskInfo> {
+        self.tasks.lock().values().cloned().collect()
+    }
+}
+
+/// The message a panic was raised with, when it's a string
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => payload
+            .downcast_ref::<&str>()
+            .map_or_else(|| "unknown panic".to_string(), |message| message.to_string()),
+    }
+}
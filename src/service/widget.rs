@@ -0,0 +1,98 @@
+//! The prompt's status widget
+//!
+//! `rustfig prompt-widget` prints one glyph for RustFig's state, to put in RPROMPT or a
+//! starship custom module: whether the service answers, whether it's offline, whether
+//! it has an AI provider, and whether this shell predicts with the service forwarded
+//! over SSH. It runs at every prompt, so the service gets a short deadline and is never
+//! started from here.
+
+use std::env;
+use std::time::Duration;
+
+use crate::config::Config;
+use super::client;
+use super::protocol::{Request, Response};
+
+/// How long the widget waits on the service; any longer and the prompt visibly lags
+const WIDGET_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// A starship custom module that shows the widget; add it to `starship.toml`
+pub const STARSHIP_MODULE: &str = r#"[custom.rustfig]
+command = "rustfig prompt-widget"
+when = "command -v rustfig"
+shell = ["sh"]
+format = "[$output]($style) "
+style = "dimmed"
+"#;
+
+/// RustFig's state as the prompt shows it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WidgetState {
+    /// The service answers and has an AI provider
+    AiReady,
+    /// The service answers, without an AI provider
+    Ready,
+    /// Offline mode is on, or there's no network; only local suggestions
+    Offline,
+    /// No service answers
+    Down,
+    /// Predicting with the service forwarded from the machine this SSH session came from
+    Ssh,
+}
+
+impl WidgetState {
+    /// Ask the service for its state; in an SSH session, the forwarded one if there is one
+    pub fn detect(config: &Config) -> Self {
+        let forwarded = forwarded_socket(config);
+        let socket = forwarded.clone().unwrap_or_else(|| super::socket_path(config));
+
+        let status = match client::request_within(&socket, &Request::Status, WIDGET_TIMEOUT) {
+            Ok(Response::Status(status)) => status,
+            // A service from before `status` existed still answers
+            Ok(_) => return if forwarded.is_some() { WidgetState::Ssh } else { WidgetState::Ready },
+            Err(_) => return WidgetState::Down,
+        };
+
+        if status.offline {
+            WidgetState::Offline
+        } else if forwarded.is_some() {
+            WidgetState::Ssh
+        } else if status.ai_ready {
+            WidgetState::AiReady
+        } else {
+            WidgetState::Ready
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WidgetState::AiReady => "ai",
+            WidgetState::Ready => "ready",
+            WidgetState::Offline => "offline",
+            WidgetState::Down => "down",
+            WidgetState::Ssh => "ssh",
+        }
+    }
+
+    /// The glyph shown, unless `RUSTFIG_WIDGET_<NAME>` (e.g. `RUSTFIG_WIDGET_AI`) sets another
+    pub fn glyph(self) -> String {
+        let default = match self {
+            WidgetState::AiReady => "✦",
+            WidgetState::Ready => "●",
+            WidgetState::Offline => "○",
+            WidgetState::Down => "✗",
+            WidgetState::Ssh => "⇄",
+        };
+        env::var(format!("RUSTFIG_WIDGET_{}", self.name().to_uppercase())).unwrap_or_else(|_| default.to_string())
+    }
+}
+
+#[cfg(unix)]
+fn forwarded_socket(config: &Config) -> Option<std::path::PathBuf> {
+    super::remote::RemotePredictor::detect(config).map(|remote| remote.socket().to_path_buf())
+}
+
+#[cfg(not(unix))]
+fn forwarded_socket(_config: &Config) -> Option<std::path::PathBuf> {
+    None
+}
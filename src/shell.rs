@@ -3,14 +3,54 @@ pub mod parser;
 pub mod bash;
 pub mod zsh;
 pub mod fish;
+pub mod aliases;
+pub mod symbols;
+pub mod history;
+pub mod conflicts;
+pub mod packages;
+pub mod impact;
 
 pub use self::parser::CommandParser;
+pub use self::aliases::{AliasTable, AliasExpansion};
+pub use self::symbols::ShellSymbols;
+pub use self::history::{HistoryDb, HistoryEntry};
+pub use self::packages::{PackageFinder, PackageMatch};
 use self::bash::BashIntegration;
 use self::zsh::ZshIntegration;
 use self::fish::FishIntegration;
 
+use std::collections::HashMap;
 use std::env;
 use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+/// A change to the command line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEdit<'a> {
+    /// Insert text at the cursor, like accepted ghost text
+    Insert(&'a str),
+    /// Replace the word under the cursor, which starts at byte `start`, like a flag or
+    /// path from the dropdown
+    ReplaceToken { start: usize, text: &'a str },
+    /// Replace the whole line, like a command from history
+    ReplaceLine(&'a str),
+}
+
+impl LineEdit<'_> {
+    /// The line this edit makes of `line` with the cursor at byte `cursor`
+    pub fn apply_to(self, line: &str, cursor: usize) -> String {
+        let cursor = cursor.min(line.len());
+        match self {
+            LineEdit::Insert(text) => format!("{}{}{}", &line[..cursor], text, &line[cursor..]),
+            LineEdit::ReplaceLine(new_line) => new_line.to_string(),
+            LineEdit::ReplaceToken { start, text } => {
+                let start = start.min(cursor);
+                format!("{}{}{}", &line[..start], text, &line[cursor..])
+            }
+        }
+    }
+}
 
 /// Interface for shell integrations
 pub trait ShellIntegration: Send + Sync {
@@ -23,11 +63,145 @@ pub trait ShellIntegration: Send + Sync {
     /// Get command history
     fn get_history(&self, limit: usize) -> Result<Vec<String>, Box<dyn Error>>;
     
-    /// Apply a completion to the current command line
+    /// Get the cursor's byte offset in the current command line
+    ///
+    /// Integrations that can't tell report the end of the line.
+    fn get_cursor_position(&self) -> Result<usize, Box<dyn Error>> {
+        Ok(self.get_current_command_line()?.len())
+    }
+    
+    /// Insert a completion at the cursor
     fn apply_completion(&self, completion: &str) -> Result<(), Box<dyn Error>>;
     
+    /// Make an edit to the command line
+    ///
+    /// A token replacement that only extends what's typed is an insert, so the cursor
+    /// stays mid-line; any other rewrites the line and leaves the cursor at its end.
+    fn apply_edit(&self, edit: LineEdit) -> Result<(), Box<dyn Error>> {
+        match edit {
+            LineEdit::Insert(text) => self.apply_completion(text),
+            LineEdit::ReplaceLine(line) => self.replace_command_line(line),
+            LineEdit::ReplaceToken { start, text } => {
+                let line = self.get_current_command_line()?;
+                let cursor = self.get_cursor_position()?.min(line.len());
+                let start = start.min(cursor);
+                let typed = &line[start..cursor];
+                match text.strip_prefix(typed) {
+                    Some(rest) => self.apply_completion(rest),
+                    None => self.replace_command_line(&format!("{}{}{}", &line[..start], text, &line[cursor..])),
+                }
+            }
+        }
+    }
+    
     /// Get shell name
     fn get_shell_name(&self) -> &str;
+    
+    /// What the shell wrote to the terminal since the last call
+    ///
+    /// Only integrations that relay the shell's output return anything; the OSC 133 prompt
+    /// marks in it tell where the command line is.
+    fn take_terminal_output(&self) -> Result<Vec<u8>, Box<dyn Error>> {
+        Ok(Vec::new())
+    }
+    
+    /// Replace the whole command line, leaving the cursor at its end
+    ///
+    /// Integrations that can't replace the line apply the new one as a completion.
+    fn replace_command_line(&self, line: &str) -> Result<(), Box<dyn Error>> {
+        self.apply_completion(line)
+    }
+    
+    /// Whether the shell's integration script shows ghost text itself, so RustFig
+    /// mustn't draw it over the line
+    fn draws_ghost_text(&self) -> bool {
+        false
+    }
+    
+    /// Select a byte range of the command line so typing replaces it (snippet placeholders)
+    ///
+    /// Integrations that can't select leave the cursor where it is.
+    fn select_range(&self, _start: usize, _end: usize) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+    
+    /// Get the aliases defined in the user's shell, as dumped by the integration script
+    fn get_aliases(&self) -> Result<HashMap<String, String>, Box<dyn Error>> {
+        let Some(path) = aliases::alias_dump_path(self.get_shell_name()) else { return Ok(HashMap::new()) };
+        if !path.exists() {
+            return Ok(HashMap::new());
+        }
+        
+        let content = fs::read_to_string(path)?;
+        Ok(AliasTable::parse(&content))
+    }
+    
+    /// Get user-defined functions and builtins, as dumped by the integration script
+    fn get_shell_symbols(&self) -> ShellSymbols {
+        ShellSymbols::load(self.get_shell_name())
+    }
+}
+
+/// Directory shared with the shell integration scripts for runtime data
+pub fn runtime_dir() -> PathBuf {
+    let base = env::var("XDG_RUNTIME_DIR").unwrap_or_else(|_| "/tmp".to_string());
+    let user = env::var("USER").unwrap_or_default();
+    PathBuf::from(base).join(format!("rustfig-{}", user))
+}
+
+/// `runtime_dir`, created private to this user
+///
+/// Under `/tmp` anyone could create it first, so one owned by someone else is refused and
+/// a loose mode is tightened to 0700 before anything the shell acts on is written there.
+#[cfg(unix)]
+pub fn private_runtime_dir() -> Result<PathBuf, Box<dyn Error>> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+    
+    let dir = runtime_dir();
+    fs::DirBuilder::new().recursive(true).mode(0o700).create(&dir)?;
+    
+    let metadata = fs::symlink_metadata(&dir)?;
+    // SAFETY: getuid has no preconditions and can't fail
+    let uid = unsafe { libc::getuid() };
+    if !metadata.is_dir() || metadata.uid() != uid {
+        return Err(format!("{} isn't a directory you own; not using it", dir.display()).into());
+    }
+    if metadata.mode() & 0o077 != 0 {
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(dir)
+}
+
+/// Where the integration scripts dump aliases, functions and builtins; `None` when the
+/// runtime directory isn't private, since a dump planted there would be trusted
+#[cfg(unix)]
+fn dump_dir() -> Option<PathBuf> {
+    private_runtime_dir().ok()
+}
+
+#[cfg(not(unix))]
+fn dump_dir() -> Option<PathBuf> {
+    Some(runtime_dir())
+}
+
+/// Leave `command` for the shell that started this process to put on its next command
+/// line; the integration scripts look for `insert.<pid>` before each prompt
+#[cfg(unix)]
+pub fn queue_for_command_line(command: &str) -> Result<(), Box<dyn Error>> {
+    let dir = private_runtime_dir()?;
+    let shell_pid = std::os::unix::process::parent_id();
+    
+    let path = dir.join(format!("insert.{}", shell_pid));
+    let tmp_path = dir.join(format!("insert.{}.tmp", shell_pid));
+    fs::write(&tmp_path, command)?;
+    fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+/// The integration scripts that pick up queued commands only exist for Unix shells
+#[cfg(not(unix))]
+pub fn queue_for_command_line(_command: &str) -> Result<(), Box<dyn Error>> {
+    Err("Putting a command on the shell's command line needs a Unix shell".into())
 }
 
 /// Detect the current shell and initialize the appropriate integration
@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use super::dump_dir;
+use crate::config::Config;
+use crate::suggestion::{Suggestion, SuggestionKind};
+
+/// Maximum number of nested alias expansions (guards against alias loops)
+const MAX_EXPANSION_DEPTH: usize = 5;
+
+/// File the integration script dumps the shell's `alias` output into
+pub fn alias_dump_path(shell_name: &str) -> Option<PathBuf> {
+    dump_dir().map(|dir| dir.join(format!("aliases.{}", shell_name)))
+}
+
+/// Result of expanding an alias at the start of a command line
+#[derive(Debug, Clone)]
+pub struct AliasExpansion {
+    /// The alias as typed by the user (e.g. `gco`)
+    pub alias: String,
+    /// What the alias expanded to (e.g. `git checkout`)
+    pub expansion: String,
+    /// The full command line with the alias expanded
+    pub line: String,
+}
+
+impl AliasExpansion {
+    /// Map text produced for the expanded line back onto what the user typed
+    pub fn restore(&self, text: &str) -> String {
+        match text.strip_prefix(&self.expansion) {
+            Some(rest) => format!("{}{}", self.alias, rest),
+            None => text.to_string(),
+        }
+    }
+}
+
+/// Aliases harvested from the user's shell
+pub struct AliasTable {
+    shell_name: String,
+    aliases: HashMap<String, String>,
+    loaded_at: Option<SystemTime>,
+}
+
+impl AliasTable {
+    /// Create an empty table for a shell
+    pub fn new(shell_name: &str) -> Self {
+        Self {
+            shell_name: shell_name.to_string(),
+            aliases: HashMap::new(),
+            loaded_at: None,
+        }
+    }
+
+    /// Load aliases for the current shell, honoring `shells.<name>.load_aliases`
+    pub fn load_for_current_shell(config: &Config) -> Self {
+        let shell_name = std::env::var("SHELL")
+            .ok()
+            .and_then(|s| s.rsplit('/').next().map(|s| s.to_string()))
+            .unwrap_or_else(|| "bash".to_string());
+
+        let mut table = Self::new(&shell_name);
+
+        let enabled = config.shells.get(&shell_name)
+            .and_then(|s| s.load_aliases)
+            .unwrap_or(true);
+
+        if enabled {
+            table.reload();
+        }
+
+        table
+    }
+
+    /// Re-read the alias dump if the shell has written a newer one
+    pub fn reload(&mut self) -> bool {
+        let Some(path) = alias_dump_path(&self.shell_name) else { return false };
+
+        let modified = match fs::metadata(&path).and_then(|m| m.modified()) {
+            Ok(modified) => modified,
+            Err(_) => return false,
+        };
+
+        if self.loaded_at.map_or(false, |loaded| loaded >= modified) {
+            return false;
+        }
+
+        match fs::read_to_string(&path) {
+            Ok(content) => {
+                self.aliases = Self::parse(&content);
+                self.loaded_at = Some(modified);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Replace the table contents with aliases reported directly by the shell
+    pub fn set_aliases(&mut self, aliases: HashMap<String, String>) {
+        self.aliases = aliases;
+        self.loaded_at = Some(SystemTime::now());
+    }
+
+    /// Parse `alias` builtin output from bash (`alias ll='ls -l'`),
+    /// zsh (`ll='ls -l'`), or fish (`alias ll 'ls -l'`)
+    pub fn parse(content: &str) -> HashMap<String, String> {
+        let mut aliases = HashMap::new();
+
+        for line in content.lines() {
+            let line = line.trim();
+            let line = line.strip_prefix("alias ").unwrap_or(line).trim_start();
+
+            // Skip option flags such as `alias -g` in zsh global alias listings
+            if line.is_empty() || line.starts_with('-') {
+                continue;
+            }
+
+            let split_at = match line.find(|c: char| c == '=' || c == ' ') {
+                Some(idx) => idx,
+                None => continue,
+            };
+
+            let name = &line[..split_at];
+            let value = unquote(line[split_at + 1..].trim());
+
+            if !name.is_empty() && !value.is_empty() {
+                aliases.insert(name.to_string(), value);
+            }
+        }
+
+        aliases
+    }
+
+    /// Look up a single alias
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.aliases.get(name).map(|s| s.as_str())
+    }
+
+    /// Number of known aliases
+    pub fn len(&self) -> usize {
+        self.aliases.len()
+    }
+
+    /// Every alias name, in no particular order
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.aliases.keys().map(String::as_str)
+    }
+
+    /// Expand an alias in command position so downstream completion sees the real command
+    pub fn expand(&self, line: &str) -> Option<AliasExpansion> {
+        let trimmed = line.trim_start();
+        let first_end = trimmed.find(char::is_whitespace)?;
+        let alias = &trimmed[..first_end];
+
+        let mut expansion = self.aliases.get(alias)?.clone();
+
+        // Follow chained aliases (e.g. `g=git`, `gco='g checkout'`) without looping forever
+        for _ in 0..MAX_EXPANSION_DEPTH {
+            let head = expansion.split_whitespace().next().unwrap_or("");
+            if head == alias {
+                break;
+            }
+
+            match self.aliases.get(head) {
+                Some(next) if next.split_whitespace().next() != Some(head) => {
+                    expansion = format!("{}{}", next, &expansion[head.len()..]);
+                }
+                _ => break,
+            }
+        }
+
+        Some(AliasExpansion {
+            alias: alias.to_string(),
+            line: format!("{}{}", expansion, &trimmed[first_end..]),
+            expansion,
+        })
+    }
+
+    /// Suggest the user's own aliases for a partially typed command name
+    pub fn suggestions(&self, prefix: &str) -> Vec<Suggestion> {
+        let mut names: Vec<&String> = self.aliases.keys()
+            .filter(|name| name.starts_with(prefix))
+            .collect();
+        names.sort();
+
+        names.into_iter()
+            .map(|name| {
+                Suggestion::new(name.clone(), name.clone(), SuggestionKind::Command)
+                    .with_description(format!("alias for {}", self.aliases[name]))
+            })
+            .collect()
+    }
+}
+
+/// Strip one level of shell quoting from an alias value
+fn unquote(value: &str) -> String {
+    if value.len() >= 2 {
+        if value.starts_with('\'') && value.ends_with('\'') {
+            // bash/zsh escape embedded single quotes as '\''
+            return value[1..value.len() - 1].replace("'\\''", "'");
+        }
+
+        if value.starts_with('"') && value.ends_with('"') {
+            return value[1..value.len() - 1].replace("\\\"", "\"");
+        }
+    }
+
+    value.to_string()
+}
@@ -0,0 +1,81 @@
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::env;
+
+use super::ShellIntegration;
+
+pub struct BashIntegration {
+    history_file: Option<PathBuf>,
+}
+
+impl BashIntegration {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        let history_file = env::var("HISTFILE")
+            .ok()
+            .map(PathBuf::from)
+            .or_else(|| env::var("HOME").ok().map(|home| PathBuf::from(home).join(".bash_history")))
+            .filter(|path| path.exists());
+        
+        Ok(Self {
+            history_file,
+        })
+    }
+    
+    fn read_history_file(&self, limit: usize) -> Result<Vec<String>, Box<dyn Error>> {
+        if let Some(history_file) = &self.history_file {
+            let content = fs::read_to_string(history_file)?;
+            let lines: Vec<String> = content
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                // With HISTTIMEFORMAT set, each command is preceded by a `#<epoch>` line
+                .filter(|line| !(line.starts_with('#') && line[1..].bytes().all(|b| b.is_ascii_digit())))
+                .map(|line| line.to_string())
+                .take(limit)
+                .collect();
+            
+            Ok(lines)
+        } else {
+            Ok(Vec::new())
+        }
+    }
+}
+
+impl ShellIntegration for BashIntegration {
+    fn get_current_command_line(&self) -> Result<String, Box<dyn Error>> {
+        // In a real implementation, this would read READLINE_LINE from the bind -x hook
+        // For now, we'll just simulate
+        
+        // For testing purposes, let's return a dummy command
+        Ok(String::from("echo 'Hello from bash'"))
+    }
+    
+    fn get_current_directory(&self) -> Result<String, Box<dyn Error>> {
+        let output = Command::new("pwd")
+            .output()?;
+        
+        if output.status.success() {
+            let pwd = String::from_utf8(output.stdout)?;
+            Ok(pwd.trim().to_string())
+        } else {
+            env::current_dir()
+                .map(|p| p.to_string_lossy().to_string())
+                .map_err(|e| e.into())
+        }
+    }
+    
+    fn get_history(&self, limit: usize) -> Result<Vec<String>, Box<dyn Error>> {
+        self.read_history_file(limit)
+    }
+    
+    fn apply_completion(&self, completion: &str) -> Result<(), Box<dyn Error>> {
+        // In a real implementation, this would rewrite READLINE_LINE and READLINE_POINT
+        println!("Applied completion in bash: {}", completion);
+        Ok(())
+    }
+    
+    fn get_shell_name(&self) -> &str {
+        "bash"
+    }
+}
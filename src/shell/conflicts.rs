@@ -0,0 +1,99 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A shell plugin known to fight rustfig over keys or the command line
+pub struct ShellConflict {
+    pub name: &'static str,
+    /// Text on a non-comment line of a startup file that means the plugin is loaded
+    markers: &'static [&'static str],
+    /// What goes wrong with both active
+    pub problem: &'static str,
+    /// How to resolve it
+    pub remediation: &'static str,
+    /// Both draw ghost text; `doctor --fix` resolves this by turning rustfig's off
+    pub ghost_text: bool,
+}
+
+/// Plugins that silently break rustfig when loaded alongside it
+pub const KNOWN_CONFLICTS: &[ShellConflict] = &[
+    ShellConflict {
+        name: "zsh-autosuggestions",
+        markers: &["zsh-autosuggestions"],
+        problem: "both draw ghost text after the cursor, so suggestions overlap and Right arrow accepts the wrong one",
+        remediation: "remove zsh-autosuggestions from your plugins, or set general.enable_ghost_text to false to keep its ghost text and rustfig's dropdown",
+        ghost_text: true,
+    },
+    ShellConflict {
+        name: "fzf key bindings",
+        markers: &[
+            "fzf --zsh", "fzf --bash", "fzf --fish", "fzf/shell/key-bindings", "key-bindings.zsh",
+            "key-bindings.bash", ".fzf.zsh", ".fzf.bash", "fzf_key_bindings", "fzf_configure_bindings",
+        ],
+        problem: "fzf binds Ctrl+R, as rustfig's history search does; whichever loads last silently wins",
+        remediation: "set RUSTFIG_HISTORY_KEY to another key before the RustFig integration block, or drop fzf's Ctrl+R binding",
+        ghost_text: false,
+    },
+    ShellConflict {
+        name: "Fig / Amazon Q",
+        markers: &[".fig/shell", "fig init", "amazon-q/shell", "q init"],
+        problem: "it replaces the completion UI and input handling that rustfig hooks into, so neither works reliably",
+        remediation: "remove the Fig / Amazon Q pre and post blocks from your startup files (or uninstall it)",
+        ghost_text: true,
+    },
+    ShellConflict {
+        name: "atuin",
+        markers: &["atuin init"],
+        problem: "atuin binds Ctrl+R and Up arrow, taking over history search from rustfig",
+        remediation: "initialize it with `atuin init <shell> --disable-ctrl-r`, or set RUSTFIG_HISTORY_KEY to another key",
+        ghost_text: false,
+    },
+];
+
+/// Startup files a shell reads, which is where plugins get loaded
+pub fn startup_files(shell: &str, home: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = match shell {
+        "zsh" => vec![home.join(".zshrc"), home.join(".zshenv"), home.join(".zprofile")],
+        "bash" => vec![home.join(".bashrc"), home.join(".bash_profile"), home.join(".profile")],
+        "fish" => vec![home.join(".config/fish/config.fish")],
+        _ => Vec::new(),
+    };
+
+    // fish plugin managers (fisher, fzf.fish) drop their setup into conf.d
+    if shell == "fish" {
+        if let Ok(entries) = fs::read_dir(home.join(".config/fish/conf.d")) {
+            let mut conf_d: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+            conf_d.sort();
+            files.extend(conf_d);
+        }
+    }
+
+    files.retain(|file| file.is_file());
+    files
+}
+
+/// Every known conflict loaded by any of `files`, with the first file that loads it
+pub fn detect_conflicts(files: &[PathBuf]) -> Vec<(&'static ShellConflict, PathBuf)> {
+    let contents: Vec<(&PathBuf, String)> = files
+        .iter()
+        .filter_map(|file| fs::read_to_string(file).ok().map(|content| (file, content)))
+        .collect();
+
+    KNOWN_CONFLICTS
+        .iter()
+        .filter_map(|conflict| {
+            contents
+                .iter()
+                .find(|(_, content)| loads(content, conflict.markers))
+                .map(|(file, _)| (conflict, (*file).clone()))
+        })
+        .collect()
+}
+
+/// Whether any non-comment line mentions one of the markers
+fn loads(content: &str, markers: &[&str]) -> bool {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .any(|line| markers.iter().any(|marker| line.contains(marker)))
+}
@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use memmap2::Mmap;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+
+/// Name of the history database inside the user data directory
+pub const HISTORY_FILE: &str = "history.jsonl";
+
+/// Bytes kept from before the parsed offset to tell an append from a rewrite
+const TAIL_BYTES: usize = 64;
+
+/// Native history parsed so far, per file, so a refresh only parses what was appended
+static NATIVE_HISTORY: Lazy<Mutex<HashMap<PathBuf, ParsedHistory>>> = Lazy::new(Default::default);
+
+type Parser = fn(&str) -> Vec<HistoryEntry>;
+
+/// A command run in the shell, with whatever context the hooks could capture
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub command: String,
+
+    /// Unix timestamp when the command started
+    #[serde(default)]
+    pub timestamp: u64,
+
+    /// Directory the command ran in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+
+    /// Shell that ran the command
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+}
+
+impl HistoryEntry {
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.trim().to_string(),
+            timestamp: now(),
+            cwd: None,
+            exit_code: None,
+            duration_ms: None,
+            shell: None,
+        }
+    }
+}
+
+/// Append-only JSON Lines log of commands recorded by the shell hooks
+///
+/// Shell history files only know the command text; this keeps the cwd, exit
+/// code, and timing the hooks report, and is merged with the shell's own
+/// history so commands from before installation still show up.
+pub struct HistoryDb {
+    path: PathBuf,
+    mmap: bool,
+}
+
+impl HistoryDb {
+    pub fn new(user_data_dir: &Path) -> Self {
+        Self {
+            path: user_data_dir.join(HISTORY_FILE),
+            mmap: false,
+        }
+    }
+
+    /// The user's database, mapping shell history unless `performance.optimizations.mmap_files` is off
+    pub fn from_config(config: &Config) -> Self {
+        let mmap = config
+            .performance
+            .as_ref()
+            .and_then(|performance| performance.optimizations.as_ref())
+            .map_or(true, |optimizations| optimizations.mmap_files);
+
+        Self {
+            mmap,
+            ..Self::new(&config.general.user_data_dir)
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an entry
+    pub fn record(&self, entry: &HistoryEntry) -> Result<(), Box<dyn Error>> {
+        if entry.command.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        // One write per line so concurrent shells don't interleave partial records
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        OpenOptions::new().create(true).append(true).open(&self.path)?.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// Recorded entries, oldest first; corrupt lines are skipped
+    pub fn load(&self) -> Vec<HistoryEntry> {
+        fs::read_to_string(&self.path)
+            .map(|content| {
+                content.lines()
+                    .filter_map(|line| serde_json::from_str(line).ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Recorded entries merged with the shell's native history, newest first, one per command
+    ///
+    /// When a command appears in both, the recorded entry wins since it carries context,
+    /// and the merged entry keeps the latest timestamp.
+    pub fn load_unified(&self, shell: &str) -> Vec<HistoryEntry> {
+        let mut latest: HashMap<String, HistoryEntry> = HashMap::new();
+
+        for entry in read_native_history(shell, self.mmap).into_iter().chain(self.load()) {
+            let merged = match latest.remove(&entry.command) {
+                Some(existing) if entry.cwd.is_none() && existing.cwd.is_some() => HistoryEntry {
+                    timestamp: existing.timestamp.max(entry.timestamp),
+                    ..existing
+                },
+                Some(existing) => HistoryEntry {
+                    timestamp: existing.timestamp.max(entry.timestamp),
+                    ..entry
+                },
+                None => entry,
+            };
+            latest.insert(merged.command.clone(), merged);
+        }
+
+        let mut entries: Vec<HistoryEntry> = latest.into_values().collect();
+        entries.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        entries
+    }
+}
+
+/// Read the shell's own history file, oldest first
+///
+/// Only what was appended since the last call is parsed. With `mmap` the zsh and fish
+/// histories are mapped rather than copied into memory.
+pub fn read_native_history(shell: &str, mmap: bool) -> Vec<HistoryEntry> {
+    let home = match dirs::home_dir() {
+        Some(home) => home,
+        None => return Vec::new(),
+    };
+
+    match shell {
+        "zsh" => {
+            let path = env::var("HISTFILE").map(PathBuf::from).unwrap_or_else(|_| home.join(".zsh_history"));
+            read_incremental(&path, parse_zsh_history, mmap)
+        }
+        "fish" => {
+            let path = dirs::data_dir()
+                .unwrap_or_else(|| home.join(".local/share"))
+                .join("fish")
+                .join("fish_history");
+            read_incremental(&path, parse_fish_history, mmap)
+        }
+        _ => {
+            let path = env::var("HISTFILE").map(PathBuf::from).unwrap_or_else(|_| home.join(".bash_history"));
+            // `history -w` truncates the file in place, which would fault a mapping mid-read
+            read_incremental(&path, parse_bash_history, false)
+        }
+    }
+}
+
+/// A history file parsed up to `offset`
+#[derive(Default)]
+struct ParsedHistory {
+    offset: u64,
+    /// The bytes just before `offset`, as they were when parsed
+    tail: Vec<u8>,
+    entries: Vec<HistoryEntry>,
+}
+
+impl ParsedHistory {
+    /// Parse what was appended since the last update, or the whole file if it was rewritten
+    fn update(&mut self, file: &File, parse: Parser, mmap: bool) -> io::Result<()> {
+        let len = file.metadata()?.len();
+        if len == self.offset {
+            return Ok(());
+        }
+
+        if len > self.offset {
+            let start = self.offset - self.tail.len() as u64;
+            let appended = with_bytes(file, start, mmap, |bytes| match bytes.strip_prefix(self.tail.as_slice()) {
+                Some(appended) => {
+                    self.append(appended, parse);
+                    true
+                }
+                None => false,
+            })?;
+            if appended {
+                return Ok(());
+            }
+        }
+
+        // Truncated or rewritten (zsh trimming to SAVEHIST, fish merging sessions): start over
+        *self = ParsedHistory::default();
+        with_bytes(file, 0, mmap, |bytes| self.append(bytes, parse))
+    }
+
+    /// Parse the complete lines of `bytes`, which start at `offset`
+    ///
+    /// Shells append whole records at once, so a chunk ending at a newline parses on its own.
+    fn append(&mut self, bytes: &[u8], parse: Parser) {
+        // A line still being written is picked up on the next update
+        let end = match memchr::memrchr(b'\n', bytes) {
+            Some(newline) => newline + 1,
+            None => return,
+        };
+
+        // History files may contain invalid UTF-8 (zsh metafies some bytes)
+        self.entries.extend(parse(&String::from_utf8_lossy(&bytes[..end])));
+        self.offset += end as u64;
+        self.tail = bytes[end - end.min(TAIL_BYTES)..end].to_vec();
+    }
+}
+
+fn read_incremental(path: &Path, parse: Parser, mmap: bool) -> Vec<HistoryEntry> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut cache = NATIVE_HISTORY.lock();
+    let parsed = cache.entry(path.to_path_buf()).or_default();
+    if parsed.update(&file, parse, mmap).is_err() {
+        *parsed = ParsedHistory::default();
+    }
+    parsed.entries.clone()
+}
+
+/// Run `f` over the file's bytes from `start` to its end
+fn with_bytes<R>(file: &File, start: u64, mmap: bool, f: impl FnOnce(&[u8]) -> R) -> io::Result<R> {
+    if mmap {
+        // SAFETY: zsh and fish replace their history by renaming a new file over it, so a
+        // mapped file is only ever appended to
+        let map = unsafe { Mmap::map(file)? };
+        return Ok(f(map.get(start as usize..).unwrap_or_default()));
+    }
+
+    let mut file = file;
+    let mut bytes = Vec::new();
+    file.seek(SeekFrom::Start(start))?;
+    file.read_to_end(&mut bytes)?;
+    Ok(f(&bytes))
+}
+
+/// Plain lines, optionally preceded by `#<timestamp>` when HISTTIMEFORMAT is set
+fn parse_bash_history(content: &str) -> Vec<HistoryEntry> {
+    let mut entries = Vec::new();
+    let mut timestamp = 0;
+
+    for line in content.lines() {
+        if let Some(ts) = line.strip_prefix('#').and_then(|ts| ts.parse().ok()) {
+            timestamp = ts;
+        } else if !line.trim().is_empty() {
+            let mut entry = HistoryEntry::new(line);
+            entry.timestamp = timestamp;
+            entries.push(entry);
+        }
+    }
+
+    entries
+}
+
+/// `: <start>:<elapsed>;<command>` in extended format, plain lines otherwise
+fn parse_zsh_history(content: &str) -> Vec<HistoryEntry> {
+    let mut entries: Vec<HistoryEntry> = Vec::new();
+    let mut continuation = false;
+
+    for line in content.lines() {
+        // Multi-line commands end each line but the last with a backslash
+        if continuation {
+            if let Some(last) = entries.last_mut() {
+                last.command.push('\n');
+                last.command.push_str(line.trim_end_matches('\\'));
+            }
+            continuation = line.ends_with('\\');
+            continue;
+        }
+
+        let (timestamp, duration, command) = match line.strip_prefix(": ").and_then(|rest| rest.split_once(';')) {
+            Some((meta, command)) => {
+                let (start, elapsed) = meta.split_once(':').unwrap_or((meta, "0"));
+                (start.trim().parse().unwrap_or(0), elapsed.trim().parse::<u64>().ok(), command)
+            }
+            None => (0, None, line),
+        };
+
+        continuation = command.ends_with('\\');
+        if command.trim().is_empty() {
+            continue;
+        }
+
+        let mut entry = HistoryEntry::new(command.trim_end_matches('\\'));
+        entry.timestamp = timestamp;
+        entry.duration_ms = duration.map(|secs| secs * 1000);
+        entries.push(entry);
+    }
+
+    entries
+}
+
+/// fish's YAML-like `- cmd: ...` / `  when: ...` records
+fn parse_fish_history(content: &str) -> Vec<HistoryEntry> {
+    let mut entries: Vec<HistoryEntry> = Vec::new();
+
+    for line in content.lines() {
+        if let Some(command) = line.strip_prefix("- cmd: ") {
+            // fish escapes newlines and backslashes in history
+            let mut entry = HistoryEntry::new(&command.replace("\\n", "\n").replace("\\\\", "\\"));
+            entry.timestamp = 0;
+            entries.push(entry);
+        } else if let Some(when) = line.trim_start().strip_prefix("when: ") {
+            if let Some(last) = entries.last_mut() {
+                last.timestamp = when.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+
+    entries
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
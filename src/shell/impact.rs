@@ -0,0 +1,391 @@
+//! What a destructive command would touch
+//!
+//! With `ui.preview_destructive` on, accepting a suggestion that makes the line delete,
+//! shred or recursively change files first shows what it would affect: its arguments
+//! with globs expanded the way the shell would, directories counted through. Nothing is
+//! run, except `git clean`, which is asked with `-n` for what it would remove.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use super::parser::{unquote, CommandParser, ParsedCommand};
+use crate::utils::process::run_with_timeout;
+use crate::utils::system::format_bytes;
+
+/// Entries counted under the targets before counting stops
+const MAX_WALK: usize = 20_000;
+
+/// Targets listed by name
+const MAX_LISTED: usize = 8;
+
+/// Deadline for `git clean -n`
+const GIT_CLEAN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Commands that destroy what they're given, and what they do to it
+const DESTRUCTIVE: &[(&str, &str)] = &[
+    ("rm", "delete"),
+    ("rmdir", "delete"),
+    ("unlink", "delete"),
+    ("shred", "overwrite"),
+    ("chmod", "change the mode of"),
+    ("chown", "change the owner of"),
+    ("chgrp", "change the group of"),
+];
+
+/// What a destructive command line would affect
+#[derive(Debug, Clone, Default)]
+pub struct Impact {
+    /// The command line
+    pub command: String,
+    /// What it does to its targets, like "delete"
+    pub action: &'static str,
+    /// Existing paths it's given, globs expanded, as they'd be written
+    pub targets: Vec<PathBuf>,
+    /// Files and directories affected, counting everything under recursed directories
+    pub files: usize,
+    pub dirs: usize,
+    pub bytes: u64,
+    /// Counting stopped at `MAX_WALK` entries
+    pub truncated: bool,
+    /// Globs that match nothing
+    pub unmatched: Vec<String>,
+}
+
+impl Impact {
+    /// The preview panel's text
+    pub fn describe(&self) -> String {
+        let mut text = if self.targets.is_empty() {
+            format!("{} would {} nothing that exists", self.command, self.action)
+        } else {
+            format!(
+                "{} would {} {}{} files in {} directories, {}",
+                self.command,
+                self.action,
+                if self.truncated { "at least " } else { "" },
+                self.files,
+                self.dirs,
+                format_bytes(self.bytes)
+            )
+        };
+        for target in self.targets.iter().take(MAX_LISTED) {
+            text.push_str(&format!("\n  {}", target.display()));
+        }
+        if self.targets.len() > MAX_LISTED {
+            text.push_str(&format!("\n  … and {} more", self.targets.len() - MAX_LISTED));
+        }
+        for pattern in &self.unmatched {
+            text.push_str(&format!("\n  {} matches nothing", pattern));
+        }
+        text
+    }
+}
+
+/// Whether `line`'s command is one that deletes or changes what it's given, without
+/// looking at what that is
+pub fn is_destructive(line: &str) -> bool {
+    let Ok(parsed) = CommandParser::new().parse(line, line.len()) else { return false };
+    match command_words(&parsed).split_first() {
+        Some((&"git", args)) => args.first() == Some(&"clean"),
+        Some((command, _)) => DESTRUCTIVE.iter().any(|(name, _)| name == command),
+        None => false,
+    }
+}
+
+/// What `line` would affect, run in `cwd`, if its command is a destructive one
+pub fn preview(line: &str, cwd: &Path) -> Option<Impact> {
+    let parsed = CommandParser::new().parse(line, line.len()).ok()?;
+    let words = command_words(&parsed);
+    let (&command, args) = words.split_first()?;
+    let mut impact = Impact { command: parsed.command_line().trim().to_string(), ..Impact::default() };
+
+    if command == "git" && args.first() == Some(&"clean") {
+        impact.action = "delete";
+        impact.targets = git_clean_targets(&args[1..], cwd)?;
+        count_targets(&mut impact, cwd, true);
+        return Some(impact);
+    }
+
+    let (_, action) = DESTRUCTIVE.iter().find(|(name, _)| *name == command)?;
+    impact.action = action;
+
+    let (mut options, mut operands) = split_options(args);
+    // `chmod -w file` takes write permission away; `-w` is its mode, not an option
+    if command == "chmod" {
+        if let Some(index) = options.iter().position(|option| is_mode_removal(option)) {
+            operands.insert(0, options.remove(index));
+        }
+    }
+    // `-r` is recursive for rm and cp, but chmod and friends only recurse with `-R`
+    let recursive_flags: &[char] = match command {
+        "rm" | "cp" => &['r', 'R'],
+        "chmod" | "chown" | "chgrp" => &['R'],
+        _ => &[],
+    };
+    let recursive = options.iter().any(|option| {
+        *option == "--recursive" || (!option.starts_with("--") && option.contains(recursive_flags))
+    });
+    let changes_mode = matches!(command, "chmod" | "chown" | "chgrp");
+    if changes_mode {
+        // Only a recursive change reaches further than what's named
+        if !recursive || operands.is_empty() {
+            return None;
+        }
+        operands.remove(0);
+    }
+
+    for operand in operands {
+        let paths = expand(operand, cwd);
+        if paths.is_empty() {
+            impact.unmatched.push(operand.to_string());
+        }
+        impact.targets.extend(paths.into_iter().filter(|path| cwd.join(path).symlink_metadata().is_ok()));
+    }
+    // rm without -r refuses directories, and rmdir only takes empty ones
+    count_targets(&mut impact, cwd, recursive || command == "rmdir");
+    Some(impact)
+}
+
+/// The command and its arguments, from after a leading `sudo` and its options
+fn command_words<'a>(parsed: &ParsedCommand<'a>) -> Vec<&'a str> {
+    let mut words: Vec<&str> = std::iter::once(parsed.command).chain(parsed.args.iter().copied()).filter(|word| !word.is_empty()).collect();
+    if !parsed.current_arg.is_empty() {
+        words.push(parsed.current_arg);
+    }
+    // Under sudo it's the same command
+    if words.first() == Some(&"sudo") {
+        words.remove(0);
+        while words.first().map_or(false, |word| word.starts_with('-')) {
+            words.remove(0);
+        }
+    }
+    words
+}
+
+/// Options and operands; everything after `--` is an operand
+fn split_options<'a>(args: &[&'a str]) -> (Vec<&'a str>, Vec<&'a str>) {
+    let mut options = Vec::new();
+    let mut operands = Vec::new();
+    let mut after_dashes = false;
+    for &arg in args {
+        if after_dashes || !arg.starts_with('-') || arg == "-" {
+            operands.push(arg);
+        } else if arg == "--" {
+            after_dashes = true;
+        } else {
+            options.push(arg);
+        }
+    }
+    (options, operands)
+}
+
+/// A symbolic chmod mode that starts with `-`, like `-w` or `-x,g+r`
+fn is_mode_removal(arg: &str) -> bool {
+    arg.len() > 1 && arg[1..].chars().all(|c| "rwxXstugoa+-=,".contains(c))
+}
+
+/// Add up what the targets hold; directories are only entered with `recursive`
+fn count_targets(impact: &mut Impact, cwd: &Path, recursive: bool) {
+    let mut seen = 0;
+    let mut pending: Vec<PathBuf> = impact.targets.iter().map(|target| cwd.join(target)).collect();
+    while let Some(path) = pending.pop() {
+        seen += 1;
+        if seen > MAX_WALK {
+            impact.truncated = true;
+            return;
+        }
+        // Symlinks are removed, not followed
+        let Ok(metadata) = path.symlink_metadata() else { continue };
+        if !metadata.is_dir() {
+            impact.files += 1;
+            impact.bytes += metadata.len();
+        } else if recursive {
+            impact.dirs += 1;
+            if let Ok(entries) = fs::read_dir(&path) {
+                pending.extend(entries.flatten().map(|entry| entry.path()));
+            }
+        }
+    }
+}
+
+/// What `git clean` with these arguments would remove, by asking it with `-n`; `None`
+/// when it wouldn't run without `-f` anyway
+fn git_clean_targets(args: &[&str], cwd: &Path) -> Option<Vec<PathBuf>> {
+    let mut forced = false;
+    let mut dry_args = vec!["clean".to_string(), "-n".to_string()];
+    for &arg in args {
+        if arg == "--force" || arg == "--interactive" || arg == "--dry-run" {
+            forced |= arg == "--force";
+        } else if arg.starts_with('-') && !arg.starts_with("--") {
+            // Short flags bundle, like `-fdx`
+            forced |= arg.contains('f');
+            let kept: String = arg[1..].chars().filter(|c| !matches!(c, 'f' | 'i' | 'n')).collect();
+            if !kept.is_empty() {
+                dry_args.push(format!("-{}", kept));
+            }
+        } else {
+            dry_args.push(arg.to_string());
+        }
+    }
+    if !forced {
+        return None;
+    }
+
+    let mut git = Command::new("git");
+    git.args(&dry_args).current_dir(cwd);
+    let output = run_with_timeout(&mut git, GIT_CLEAN_TIMEOUT, 256 * 1024)?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|line| line.strip_prefix("Would remove "))
+            .map(PathBuf::from)
+            .collect(),
+    )
+}
+
+/// The paths a word names, globs expanded like the shell does (sorted, hidden files
+/// only for patterns starting with a dot); empty when a glob matches nothing
+fn expand(word: &str, cwd: &Path) -> Vec<PathBuf> {
+    // Quoted, a glob is literal
+    let quoted = word.contains(['\'', '"', '\\']);
+    let value = unquote(word);
+    let value = match value.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => match dirs::home_dir() {
+            Some(home) => format!("{}{}", home.display(), rest),
+            None => value,
+        },
+        _ => value,
+    };
+    if quoted || !value.contains(['*', '?', '[']) {
+        return vec![PathBuf::from(value)];
+    }
+
+    let mut candidates = vec![if value.starts_with('/') { PathBuf::from("/") } else { PathBuf::new() }];
+    for component in value.split('/').filter(|component| !component.is_empty()) {
+        if !component.contains(['*', '?', '[']) {
+            candidates.iter_mut().for_each(|candidate| candidate.push(component));
+            continue;
+        }
+        let mut matched = Vec::new();
+        for candidate in &candidates {
+            let Ok(entries) = fs::read_dir(cwd.join(candidate)) else { continue };
+            for entry in entries.flatten() {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                if (!name.starts_with('.') || component.starts_with('.')) && glob_match(component, &name) {
+                    matched.push(candidate.join(&*name));
+                }
+            }
+        }
+        candidates = matched;
+    }
+    candidates.sort();
+    candidates
+}
+
+/// Whether `name` matches a glob of `*`, `?` and `[...]` classes
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // Where the last `*` was, to retry from one character further on a mismatch
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        let step = match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+                continue;
+            }
+            Some('?') => Some(p + 1),
+            Some('[') => match_class(&pattern, p, name[n]),
+            Some(&c) => (c == name[n]).then_some(p + 1),
+            None => None,
+        };
+        match (step, star) {
+            (Some(next), _) => {
+                p = next;
+                n += 1;
+            }
+            (None, Some((star_p, star_n))) => {
+                p = star_p + 1;
+                n = star_n + 1;
+                star = Some((star_p, star_n + 1));
+            }
+            (None, None) => return false,
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// The pattern index after the class at `start` if it matches `c`; an unclosed `[` is a
+/// literal
+fn match_class(pattern: &[char], start: usize, c: char) -> Option<usize> {
+    let mut i = start + 1;
+    let negated = matches!(pattern.get(i), Some('!' | '^'));
+    if negated {
+        i += 1;
+    }
+    let first = i;
+    let mut matched = false;
+    while let Some(&class_char) = pattern.get(i) {
+        if class_char == ']' && i > first {
+            return (matched != negated).then_some(i + 1);
+        }
+        if pattern.get(i + 1) == Some(&'-') && pattern.get(i + 2).map_or(false, |&end| end != ']') {
+            matched |= (class_char..=pattern[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            matched |= class_char == c;
+            i += 1;
+        }
+    }
+    (c == '[').then_some(start + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory holding `dir/file`, removed when dropped
+    fn tree() -> tempfile::TempDir {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join("dir")).unwrap();
+        fs::write(root.path().join("dir/file"), b"data").unwrap();
+        root
+    }
+
+    #[test]
+    fn chmod_minus_r_is_a_mode_not_recursion() {
+        let root = tree();
+        assert!(preview("chmod -r dir", root.path()).is_none());
+        assert!(preview("chmod u-r dir", root.path()).is_none());
+    }
+
+    #[test]
+    fn chmod_capital_r_recurses() {
+        let root = tree();
+        let impact = preview("chmod -R u-r dir", root.path()).unwrap();
+        assert_eq!(impact.targets, vec![PathBuf::from("dir")]);
+        assert_eq!((impact.files, impact.dirs), (1, 1));
+
+        let impact = preview("chmod -R -w dir", root.path()).unwrap();
+        assert_eq!(impact.targets, vec![PathBuf::from("dir")]);
+    }
+
+    #[test]
+    fn rm_recurses_with_either_case() {
+        let root = tree();
+        for line in ["rm -rf dir", "rm -R dir", "rm --recursive dir"] {
+            let impact = preview(line, root.path()).unwrap();
+            assert_eq!((impact.files, impact.dirs), (1, 1), "{}", line);
+        }
+        let impact = preview("rm -f dir", root.path()).unwrap();
+        assert_eq!((impact.files, impact.dirs), (0, 0));
+    }
+}
@@ -0,0 +1,196 @@
+//! Which package provides a missing command
+//!
+//! `rustfig whichpkg` asks the system's package manager for packages shipping a binary
+//! of a given name: `apt-file` on Debian and Ubuntu, `brew which-formula` on macOS,
+//! `dnf provides` on Fedora. Lookups take seconds, so answers (including "none") are
+//! kept in `packages.cache` in the user data directory. When the shell can't find a
+//! command, its not-found hook only prints what's cached and has a miss looked up in the
+//! background; the prompt reads the cache too, to offer the install command as ghost text.
+
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::utils::cache_file;
+use crate::utils::process::run_with_timeout;
+
+/// Bump when `CachedLookup` changes
+const CACHE_FORMAT_VERSION: u32 = 1;
+
+const CACHE_FILE: &str = "packages.cache";
+
+/// How long an answer is trusted; package indexes change slowly
+const CACHE_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Deadline for one package manager's lookup
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Packages listed per command
+const MAX_MATCHES: usize = 5;
+
+/// A package that provides a command, and the manager that installs it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackageMatch {
+    pub manager: String,
+    pub package: String,
+}
+
+impl PackageMatch {
+    /// The command that installs the package
+    pub fn install_command(&self) -> String {
+        match self.manager.as_str() {
+            "brew" => format!("brew install {}", self.package),
+            "dnf" => format!("sudo dnf install {}", self.package),
+            _ => format!("sudo apt install {}", self.package),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedLookup {
+    /// Seconds since the epoch when the package managers were asked
+    checked: u64,
+    matches: Vec<PackageMatch>,
+}
+
+/// Looks up and remembers the packages that provide commands
+pub struct PackageFinder {
+    path: PathBuf,
+    compress: bool,
+}
+
+impl PackageFinder {
+    pub fn new(user_data_dir: &Path, compress: bool) -> Self {
+        Self {
+            path: user_data_dir.join(CACHE_FILE),
+            compress,
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(&config.general.user_data_dir, cache_file::compression_enabled(config))
+    }
+
+    /// Packages providing `command`, from the cache when it's fresh unless `refresh`
+    pub fn find(&self, command: &str, refresh: bool) -> Result<Vec<PackageMatch>, Box<dyn Error>> {
+        if !refresh {
+            if let Some(matches) = self.cached(command) {
+                return Ok(matches);
+            }
+        }
+
+        let managers = available_managers();
+        if managers.is_empty() {
+            return Err("No supported package index found (apt-file, brew or dnf)".into());
+        }
+        let mut matches: Vec<PackageMatch> = managers.iter().flat_map(|manager| lookup(manager, command)).collect();
+        // A package named like the command is almost always the one wanted
+        matches.sort_by_key(|m| m.package != command);
+        matches.truncate(MAX_MATCHES);
+
+        let mut lookups = self.load();
+        lookups.insert(command.to_string(), CachedLookup { checked: now(), matches: matches.clone() });
+        cache_file::write(&self.path, CACHE_FORMAT_VERSION, &lookups, self.compress)?;
+        Ok(matches)
+    }
+
+    /// The remembered answer for `command`, if it's fresh; never runs a package manager
+    pub fn cached(&self, command: &str) -> Option<Vec<PackageMatch>> {
+        let lookup = self.load().remove(command)?;
+        (now().saturating_sub(lookup.checked) < CACHE_TTL_SECS).then_some(lookup.matches)
+    }
+
+    fn load(&self) -> HashMap<String, CachedLookup> {
+        // A missing, corrupt or outdated cache starts over
+        cache_file::read(&self.path, CACHE_FORMAT_VERSION).unwrap_or_default()
+    }
+}
+
+/// Package managers with a file index on this machine, in the order they're asked
+fn available_managers() -> Vec<&'static str> {
+    [("apt", "apt-file"), ("brew", "brew"), ("dnf", "dnf")]
+        .into_iter()
+        .filter(|(_, binary)| on_path(binary))
+        .map(|(manager, _)| manager)
+        .collect()
+}
+
+fn lookup(manager: &str, command: &str) -> Vec<PackageMatch> {
+    let mut process = match manager {
+        "apt" => {
+            let mut process = Command::new("apt-file");
+            process.args(["search", "--regexp", &format!("/s?bin/{}$", regex_escape(command))]);
+            process
+        }
+        "brew" => {
+            let mut process = Command::new("brew");
+            process.args(["which-formula", command]).env("HOMEBREW_NO_AUTO_UPDATE", "1");
+            process
+        }
+        _ => {
+            // Only the metadata already downloaded; a refresh would take far too long
+            let mut process = Command::new("dnf");
+            process.args(["provides", "--cacheonly", "--quiet", &format!("*/bin/{}", command)]);
+            process
+        }
+    };
+    let Some(output) = run_with_timeout(&mut process, LOOKUP_TIMEOUT, 256 * 1024) else { return Vec::new() };
+    if !output.status.success() {
+        return Vec::new();
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let packages = match manager {
+        "apt" => parse_apt_file(&stdout),
+        "brew" => stdout.lines().map(str::trim).filter(|line| !line.is_empty()).map(str::to_string).collect(),
+        _ => parse_dnf_provides(&stdout),
+    };
+
+    let mut matches: Vec<PackageMatch> = Vec::new();
+    for package in packages {
+        if !matches.iter().any(|m| m.package == package) {
+            matches.push(PackageMatch { manager: manager.to_string(), package });
+        }
+    }
+    matches
+}
+
+/// `htop: /usr/bin/htop` lines
+fn parse_apt_file(output: &str) -> Vec<String> {
+    output.lines().filter_map(|line| line.split_once(": ")).map(|(package, _)| package.to_string()).collect()
+}
+
+/// `htop-3.2.2-1.fc38.x86_64 : Interactive process viewer` lines, each followed by
+/// indented `Repo : ...` details
+fn parse_dnf_provides(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter(|line| !line.starts_with(char::is_whitespace))
+        .filter_map(|line| line.split_once(" : "))
+        .filter_map(|(nevra, _)| {
+            // name-[epoch:]version-release.arch
+            let mut parts = nevra.trim().rsplitn(3, '-');
+            let (_release, _version) = (parts.next()?, parts.next()?);
+            parts.next().map(str::to_string)
+        })
+        .collect()
+}
+
+fn regex_escape(text: &str) -> String {
+    text.chars()
+        .flat_map(|c| (!c.is_ascii_alphanumeric() && c != '_' && c != '-').then_some('\\').into_iter().chain([c]))
+        .collect()
+}
+
+fn on_path(binary: &str) -> bool {
+    env::var_os("PATH").map_or(false, |path| env::split_paths(&path).any(|dir| dir.join(binary).is_file()))
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
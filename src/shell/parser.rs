@@ -0,0 +1,544 @@
+use std::error::Error;
+
+/// Words that can precede a command without being the command themselves
+const RESERVED_PREFIXES: &[&str] = &[
+    "!", "{", "if", "then", "elif", "else", "do", "while", "until", "time", "exec", "command", "builtin", "nohup",
+];
+
+/// What the word under the cursor is, grammatically
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WordPosition {
+    /// The command name (first word of a simple command)
+    Command,
+    /// An argument or flag of the command
+    Argument,
+    /// The target of a redirection (`> file`, `< file`)
+    RedirectTarget,
+}
+
+/// Quote left open at the cursor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuoteKind {
+    Single,
+    Double,
+}
+
+impl QuoteKind {
+    pub fn as_char(self) -> char {
+        match self {
+            QuoteKind::Single => '\'',
+            QuoteKind::Double => '"',
+        }
+    }
+}
+
+/// A redirection in the current simple command
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redirection<'a> {
+    /// The operator, including any file descriptor (`2>`, `>>`, `<<<`)
+    pub operator: &'a str,
+    /// The target word, empty if it hasn't been typed yet
+    pub target: &'a str,
+}
+
+/// The simple command the cursor is in, with everything else on the line stripped away
+///
+/// For `ls | grep foo && git ch` the command is `git`, not `ls`; inside `echo $(git ch`
+/// it's also `git`. Words are raw slices of the input, quotes included; use [`unquote`]
+/// to get their shell value.
+#[derive(Debug, Clone)]
+pub struct ParsedCommand<'a> {
+    /// The whole line as given to the parser
+    pub input: &'a str,
+
+    /// Byte offset of the cursor
+    pub cursor_pos: usize,
+
+    /// Byte offset where the current simple command starts (its first word, or the cursor)
+    pub command_start: usize,
+
+    /// Command name, empty while the first word is still being typed
+    pub command: &'a str,
+
+    /// Completed arguments before the cursor, excluding assignments and redirections
+    pub args: Vec<&'a str>,
+
+    /// Partial word up to the cursor, empty after whitespace
+    pub current_arg: &'a str,
+
+    /// Byte offset where `current_arg` starts
+    pub current_arg_start: usize,
+
+    /// Byte offset where the word under the cursor ends, which is past the cursor when
+    /// editing mid-word
+    pub current_word_end: usize,
+
+    /// What `current_arg` is
+    pub position: WordPosition,
+
+    /// Redirections seen so far in the current command
+    pub redirections: Vec<Redirection<'a>>,
+
+    /// Quote left open at the cursor, if any
+    pub open_quote: Option<QuoteKind>,
+
+    /// How many subshells and substitutions the cursor is nested in
+    pub depth: usize,
+
+    /// The cursor is inside a heredoc body, where nothing is a command
+    pub in_heredoc: bool,
+
+    /// The command spans several lines joined with `\`
+    pub continued: bool,
+}
+
+impl<'a> ParsedCommand<'a> {
+    /// The current simple command up to the cursor (`git ch` in `ls && git ch`)
+    pub fn command_line(&self) -> &'a str {
+        &self.input[self.command_start..self.cursor_pos]
+    }
+
+    /// Everything before the current simple command (`ls && ` in `ls && git ch`)
+    pub fn prefix(&self) -> &'a str {
+        &self.input[..self.command_start]
+    }
+
+    /// The cursor is in the middle of a construct (an unclosed quote or a heredoc body)
+    /// whose text isn't a command line yet
+    pub fn is_incomplete(&self) -> bool {
+        let unclosed = self.open_quote.map_or(false, |quote| !self.input[self.cursor_pos..].contains(quote.as_char()));
+        unclosed || self.in_heredoc
+    }
+}
+
+/// What opened a nesting level
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FrameKind {
+    Root,
+    Subshell,
+    /// `$(...)`, `<(...)`, `>(...)`
+    Substitution,
+    /// `` `...` ``
+    Backtick,
+    SingleQuote,
+    DoubleQuote,
+}
+
+/// Parsing state for one nesting level
+struct Frame {
+    kind: FrameKind,
+    /// Words of the current simple command as byte ranges
+    words: Vec<(usize, usize)>,
+    redirections: Vec<(usize, usize, Option<(usize, usize)>)>,
+    /// Start of the word being read, if any
+    word_start: Option<usize>,
+    /// The next word is a redirection target (index into `redirections`)
+    pending_redirect: Option<usize>,
+    /// Start of the current simple command
+    command_start: usize,
+    /// The pending redirection is a heredoc (`true` for `<<-`, which strips leading tabs)
+    pending_heredoc: Option<bool>,
+    /// Heredoc delimiters (byte range, strip tabs) whose bodies start after the next newline
+    heredocs: Vec<(usize, usize, bool)>,
+}
+
+impl Frame {
+    fn new(kind: FrameKind, command_start: usize) -> Self {
+        Self {
+            kind,
+            words: Vec::new(),
+            redirections: Vec::new(),
+            word_start: None,
+            pending_redirect: None,
+            command_start,
+            pending_heredoc: None,
+            heredocs: Vec::new(),
+        }
+    }
+
+    fn is_quote(&self) -> bool {
+        matches!(self.kind, FrameKind::SingleQuote | FrameKind::DoubleQuote)
+    }
+
+    fn begin_word(&mut self, at: usize) {
+        if self.word_start.is_none() {
+            if self.words.is_empty() && self.pending_redirect.is_none() && self.redirections.is_empty() {
+                self.command_start = at;
+            }
+            self.word_start = Some(at);
+        }
+    }
+
+    fn end_word(&mut self, at: usize) {
+        if let Some(start) = self.word_start.take() {
+            match self.pending_redirect.take() {
+                Some(index) => {
+                    self.redirections[index].2 = Some((start, at));
+                    if let Some(strip_tabs) = self.pending_heredoc.take() {
+                        self.heredocs.push((start, at, strip_tabs));
+                    }
+                }
+                None => self.words.push((start, at)),
+            }
+        }
+    }
+
+    /// A control operator ends the simple command
+    fn end_command(&mut self, at: usize, next: usize) {
+        self.end_word(at);
+        self.words.clear();
+        self.redirections.clear();
+        self.pending_redirect = None;
+        self.pending_heredoc = None;
+        self.command_start = next;
+    }
+}
+
+/// Finds the simple command under the cursor in a full shell command line
+///
+/// Understands pipes, `&&`/`||`/`;`/`&`, redirections, subshells, `$(...)` and backtick
+/// substitution, process substitution, comments, and single/double/backslash quoting,
+/// across continued lines and heredocs.
+pub struct CommandParser;
+
+impl CommandParser {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Parse `input` with the cursor at byte offset `cursor_pos`
+    pub fn parse<'a>(&self, input: &'a str, cursor_pos: usize) -> Result<ParsedCommand<'a>, Box<dyn Error>> {
+        if cursor_pos > input.len() || !input.is_char_boundary(cursor_pos) {
+            return Err(format!("cursor position {} is outside the command line", cursor_pos).into());
+        }
+
+        let bytes = input.as_bytes();
+        let mut stack = vec![Frame::new(FrameKind::Root, 0)];
+        let mut i = 0;
+        let mut in_heredoc = false;
+
+        // Only the text before the cursor decides which command the cursor is in
+        while i < cursor_pos {
+            let c = bytes[i];
+            let next = bytes.get(i + 1).copied();
+            let frame = stack.last_mut().expect("root frame is never popped");
+
+            match frame.kind {
+                FrameKind::SingleQuote => {
+                    if c == b'\'' {
+                        stack.pop();
+                    }
+                    i += 1;
+                    continue;
+                }
+                FrameKind::DoubleQuote => {
+                    match c {
+                        b'\\' => i += 2,
+                        b'"' => {
+                            stack.pop();
+                            i += 1;
+                        }
+                        b'$' if next == Some(b'(') => {
+                            stack.push(Frame::new(FrameKind::Substitution, i + 2));
+                            i += 2;
+                        }
+                        b'`' => {
+                            stack.push(Frame::new(FrameKind::Backtick, i + 1));
+                            i += 1;
+                        }
+                        _ => i += 1,
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+
+            match c {
+                // A line continuation joins the lines without separating words
+                b'\\' if next == Some(b'\n') => i += 2,
+                b'\\' => {
+                    frame.begin_word(i);
+                    i += 2;
+                }
+                b'\'' => {
+                    frame.begin_word(i);
+                    stack.push(Frame::new(FrameKind::SingleQuote, i + 1));
+                    i += 1;
+                }
+                b'"' => {
+                    frame.begin_word(i);
+                    stack.push(Frame::new(FrameKind::DoubleQuote, i + 1));
+                    i += 1;
+                }
+                b'$' | b'<' | b'>' if next == Some(b'(') && (c == b'$' || frame.word_start.is_none()) => {
+                    // The substitution is part of the surrounding word
+                    frame.begin_word(i);
+                    stack.push(Frame::new(FrameKind::Substitution, i + 2));
+                    i += 2;
+                }
+                b'`' => {
+                    if frame.kind == FrameKind::Backtick {
+                        frame.end_word(i);
+                        stack.pop();
+                    } else {
+                        frame.begin_word(i);
+                        stack.push(Frame::new(FrameKind::Backtick, i + 1));
+                    }
+                    i += 1;
+                }
+                b'(' if frame.word_start.is_none() && frame.words.is_empty() => {
+                    stack.push(Frame::new(FrameKind::Subshell, i + 1));
+                    i += 1;
+                }
+                b')' if matches!(frame.kind, FrameKind::Subshell | FrameKind::Substitution) => {
+                    // A substitution's surrounding word carries on after the `)`
+                    frame.end_word(i);
+                    stack.pop();
+                    i += 1;
+                }
+                b' ' | b'\t' => {
+                    frame.end_word(i);
+                    i += 1;
+                }
+                b'#' if frame.word_start.is_none() => {
+                    // Comment to end of line
+                    while i < cursor_pos && bytes[i] != b'\n' {
+                        i += 1;
+                    }
+                }
+                b'\n' => {
+                    frame.end_command(i, i + 1);
+                    i += 1;
+
+                    // Heredoc bodies follow the line that introduced them
+                    let heredocs: Vec<(usize, usize, bool)> = frame.heredocs.drain(..).collect();
+                    for (start, end, strip_tabs) in heredocs {
+                        let delimiter = unquote(&input[start..end]);
+                        match skip_heredoc(input, i, &delimiter, strip_tabs, cursor_pos) {
+                            Some(after) => i = after,
+                            None => {
+                                in_heredoc = true;
+                                break;
+                            }
+                        }
+                    }
+                    if in_heredoc {
+                        break;
+                    }
+                    frame.command_start = i;
+                }
+                b';' => {
+                    let len = if next == Some(b';') { 2 } else { 1 };
+                    frame.end_command(i, i + len);
+                    i += len;
+                }
+                b'|' | b'&' => {
+                    // `&>` and `&>>` redirect both streams
+                    if c == b'&' && next == Some(b'>') {
+                        frame.end_word(i);
+                        let len = if bytes.get(i + 2) == Some(&b'>') { 3 } else { 2 };
+                        let index = frame.redirections.len();
+                        frame.redirections.push((i, i + len, None));
+                        frame.pending_redirect = Some(index);
+                        i += len;
+                        continue;
+                    }
+
+                    // `|`, `||`, `|&`, `&&`, `&`
+                    let len = match (c, next) {
+                        (b'|', Some(b'|')) | (b'|', Some(b'&')) | (b'&', Some(b'&')) => 2,
+                        _ => 1,
+                    };
+                    frame.end_command(i, i + len);
+                    i += len;
+                }
+                b'<' | b'>' => {
+                    // A word made only of digits right before the operator is its file descriptor
+                    let mut start = i;
+                    if let Some(word_start) = frame.word_start {
+                        if input[word_start..i].bytes().all(|b| b.is_ascii_digit()) {
+                            frame.word_start = None;
+                            start = word_start;
+                        } else {
+                            frame.end_word(i);
+                        }
+                    }
+
+                    let mut end = i + 1;
+                    while end < cursor_pos && end - i < 3 && matches!(bytes[end], b'<' | b'>' | b'&' | b'|') {
+                        end += 1;
+                    }
+
+                    // `<<` and `<<-` start heredocs; `<<<` is a here-string
+                    let operator = &input[i..end];
+                    if operator == "<<" && end < cursor_pos && bytes[end] == b'-' {
+                        end += 1;
+                    }
+                    if operator == "<<" {
+                        frame.pending_heredoc = Some(end - i == 3);
+                    }
+
+                    let index = frame.redirections.len();
+                    frame.redirections.push((start, end, None));
+                    frame.pending_redirect = Some(index);
+                    i = end;
+                }
+                _ => {
+                    frame.begin_word(i);
+                    i += 1;
+                }
+            }
+        }
+
+        // An open quote belongs to the word being typed in the frame below it
+        let open_quote = match stack.last().map(|frame| frame.kind) {
+            Some(FrameKind::SingleQuote) => Some(QuoteKind::Single),
+            Some(FrameKind::DoubleQuote) => Some(QuoteKind::Double),
+            _ => None,
+        };
+        while stack.len() > 1 && stack.last().map_or(false, Frame::is_quote) {
+            stack.pop();
+        }
+
+        let depth = stack.iter().filter(|frame| frame.kind != FrameKind::Root).count();
+        let frame = stack.pop().expect("root frame is never popped");
+
+        let current_arg_start = frame.word_start.unwrap_or(cursor_pos);
+        let current_arg = &input[current_arg_start..cursor_pos];
+
+        // Leading assignments and keywords aren't the command
+        let mut words = frame.words.iter()
+            .map(|&(start, end)| &input[start..end])
+            .skip_while(|word| is_assignment(word) || RESERVED_PREFIXES.contains(word));
+        let command = words.next();
+        let args: Vec<&str> = words.collect();
+
+        let position = if frame.pending_redirect.is_some() {
+            WordPosition::RedirectTarget
+        } else if command.is_none() && !is_assignment(current_arg) {
+            WordPosition::Command
+        } else {
+            WordPosition::Argument
+        };
+
+        // Nothing typed yet after an operator: the command starts at the cursor
+        let command_start = if frame.words.is_empty() && frame.word_start.is_none() && frame.redirections.is_empty() {
+            cursor_pos
+        } else {
+            frame.command_start
+        };
+
+        let redirections = frame.redirections.iter()
+            .map(|&(start, end, target)| Redirection {
+                operator: &input[start..end],
+                target: target.map_or("", |(start, end)| &input[start..end]),
+            })
+            .collect();
+
+        Ok(ParsedCommand {
+            input,
+            cursor_pos,
+            command_start,
+            command: command.unwrap_or(""),
+            args,
+            current_arg,
+            current_arg_start,
+            current_word_end: word_end(input, cursor_pos, open_quote),
+            position,
+            redirections,
+            open_quote,
+            depth,
+            in_heredoc,
+            continued: input[command_start..cursor_pos].contains("\\\n"),
+        })
+    }
+}
+
+/// End of the word the cursor is in: the closing quote if one is open, otherwise the next
+/// unescaped whitespace or operator
+fn word_end(input: &str, cursor_pos: usize, open_quote: Option<QuoteKind>) -> usize {
+    let rest = &input[cursor_pos..];
+
+    if let Some(quote) = open_quote {
+        return rest.find(quote.as_char()).map_or(input.len(), |offset| cursor_pos + offset + 1);
+    }
+
+    let mut escaped = false;
+    for (offset, c) in rest.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c.is_whitespace() || "|&;<>()".contains(c) {
+            return cursor_pos + offset;
+        }
+    }
+
+    input.len()
+}
+
+/// Skip a heredoc body starting at `start`; returns the offset after the delimiter line,
+/// or `None` if the cursor is inside the body
+fn skip_heredoc(input: &str, start: usize, delimiter: &str, strip_tabs: bool, cursor_pos: usize) -> Option<usize> {
+    let mut position = start;
+
+    loop {
+        let line_end = input[position..].find('\n').map_or(input.len(), |offset| position + offset);
+        if cursor_pos <= line_end {
+            return None;
+        }
+
+        let line = &input[position..line_end];
+        let line = if strip_tabs { line.trim_start_matches('\t') } else { line };
+        position = line_end + 1;
+
+        if line == delimiter {
+            return Some(position);
+        }
+    }
+}
+
+/// `NAME=value` prefixes set environment variables rather than naming the command
+fn is_assignment(word: &str) -> bool {
+    match word.split_once('=') {
+        Some((name, _)) => {
+            !name.is_empty()
+                && !name.starts_with(|c: char| c.is_ascii_digit())
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        None => false,
+    }
+}
+
+/// The shell value of a word: quotes removed and backslash escapes resolved
+///
+/// Substitutions are left as written. An unclosed quote runs to the end of the word.
+pub fn unquote(word: &str) -> String {
+    let mut value = String::with_capacity(word.len());
+    let mut chars = word.chars().peekable();
+    let mut quote: Option<char> = None;
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some('\''), '\'') => quote = None,
+            (Some('\''), _) => value.push(c),
+            (Some('"'), '"') => quote = None,
+            (Some('"'), '\\') => match chars.peek() {
+                // Inside double quotes only these are escapable
+                Some('$' | '`' | '"' | '\\') => value.extend(chars.next()),
+                Some('\n') => {
+                    chars.next();
+                }
+                _ => value.push('\\'),
+            },
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '\\') => match chars.next() {
+                Some('\n') | None => {}
+                Some(escaped) => value.push(escaped),
+            },
+            _ => value.push(c),
+        }
+    }
+
+    value
+}
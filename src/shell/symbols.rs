@@ -0,0 +1,59 @@
+use std::fs;
+use std::path::PathBuf;
+
+use super::dump_dir;
+
+/// File the integration script dumps user-defined function names into
+pub fn functions_dump_path(shell_name: &str) -> Option<PathBuf> {
+    dump_dir().map(|dir| dir.join(format!("functions.{}", shell_name)))
+}
+
+/// File the integration script dumps builtin names into
+pub fn builtins_dump_path(shell_name: &str) -> Option<PathBuf> {
+    dump_dir().map(|dir| dir.join(format!("builtins.{}", shell_name)))
+}
+
+/// Functions and builtins known to the user's shell (in addition to PATH binaries)
+#[derive(Debug, Clone, Default)]
+pub struct ShellSymbols {
+    /// User-defined shell functions
+    pub functions: Vec<String>,
+    /// Shell builtins (`cd`, `export`, `source`, ...)
+    pub builtins: Vec<String>,
+}
+
+impl ShellSymbols {
+    /// Load the symbol dumps written by the integration script for a shell
+    pub fn load(shell_name: &str) -> Self {
+        Self {
+            functions: read_names(&functions_dump_path(shell_name)),
+            builtins: read_names(&builtins_dump_path(shell_name)),
+        }
+    }
+
+    /// Whether a name resolves to a function or builtin
+    pub fn contains(&self, name: &str) -> bool {
+        self.functions.iter().any(|f| f == name) || self.builtins.iter().any(|b| b == name)
+    }
+}
+
+/// Read a newline-separated list of names, skipping private helpers like `_git` or `__fish_*`
+fn read_names(path: &Option<PathBuf>) -> Vec<String> {
+    let content = match path.as_ref().map(fs::read_to_string) {
+        Some(Ok(content)) => content,
+        _ => return Vec::new(),
+    };
+
+    let mut names: Vec<String> = content
+        .lines()
+        .map(|line| line.trim())
+        // bash `declare -F` prints "declare -f name"
+        .map(|line| line.strip_prefix("declare -f ").unwrap_or(line))
+        .filter(|name| !name.is_empty() && !name.starts_with('_') && !name.contains(char::is_whitespace))
+        .map(|name| name.to_string())
+        .collect();
+
+    names.sort();
+    names.dedup();
+    names
+}
@@ -88,4 +88,9 @@ impl ShellIntegration for ZshIntegration {
     fn get_shell_name(&self) -> &str {
         "zsh"
     }
+    
+    /// The integration script puts ghost text in POSTDISPLAY unless told not to
+    fn draws_ghost_text(&self) -> bool {
+        env::var("RUSTFIG_ZSH_POSTDISPLAY").map_or(true, |value| value != "0")
+    }
 }
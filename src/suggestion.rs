@@ -2,9 +2,48 @@
 pub mod engine;
 pub mod command;
 pub mod path;
+pub mod dir_index;
+pub mod remote;
+pub mod snippets;
+pub mod bookmarks;
+pub mod variables;
+pub mod assignments;
+pub mod frecency;
+pub mod workflows;
+pub mod completers;
+#[cfg(all(unix, feature = "docker-integration"))]
+pub mod docker;
 pub mod context;
+pub mod project;
+pub mod fuzzy;
+pub mod scoring;
+pub mod spec;
+pub mod help_scraper;
+pub mod spec_cache;
+pub mod spec_files;
+pub mod fig_spec;
+pub mod spec_lint;
+pub mod spec_generator;
+pub mod subcommands;
+#[cfg(feature = "git-integration")]
+pub mod git_badges;
 
 pub use self::engine::{Suggestion, SuggestionKind, SuggestionEngine};
-pub use self::context::{Context, ContextDetector, ProjectType};
+pub use self::context::{Context, ProjectType};
 pub use self::command::CommandSuggester;
 pub use self::path::PathSuggester;
+pub use self::dir_index::DirectoryIndex;
+pub use self::remote::RemotePathSuggester;
+pub use self::snippets::{Snippet, SnippetSession, SnippetSuggester};
+pub use self::bookmarks::{Bookmark, BookmarkStore, BookmarkSuggester};
+pub use self::variables::VariableSuggester;
+pub use self::assignments::AssignmentSuggester;
+pub use self::frecency::{FrecencyCompleter, FrecencyDb};
+pub use self::workflows::{Workflow, WorkflowCompleter};
+#[cfg(all(unix, feature = "docker-integration"))]
+pub use self::docker::DockerSuggester;
+pub use self::spec::{CommandSpec, OptionSpec};
+pub use self::help_scraper::HelpScraper;
+pub use self::spec_cache::SpecCache;
+pub use self::spec_files::SpecFileCompleter;
+pub use self::subcommands::SubcommandCorrector;
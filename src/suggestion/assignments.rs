@@ -0,0 +1,185 @@
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::path::Path;
+use once_cell::sync::OnceCell;
+
+use super::fuzzy::fast_fuzzy_score;
+use super::variables::is_secret;
+use super::{Suggestion, SuggestionKind};
+use crate::config::Config;
+
+/// Commands whose arguments can be `NAME=value`
+const ASSIGNING_COMMANDS: &[&str] = &["export", "declare", "typeset", "local", "readonly", "env"];
+
+/// Programs worth offering for variables that name one, by variable
+const PROGRAMS: &[(&[&str], &[&str])] = &[
+    (&["EDITOR", "VISUAL", "GIT_EDITOR", "SUDO_EDITOR"], &["nvim", "vim", "vi", "nano", "emacs", "hx", "micro", "kak", "code --wait", "subl -w"]),
+    (&["PAGER", "MANPAGER", "GIT_PAGER"], &["less", "more", "most", "bat", "delta"]),
+    (&["BROWSER"], &["firefox", "chromium", "google-chrome", "w3m", "lynx"]),
+];
+
+/// Fixed values for variables that take one of a known few
+const VALUES: &[(&[&str], &[&str])] = &[
+    (&["TERM"], &["xterm-256color", "screen-256color", "tmux-256color", "xterm", "dumb"]),
+    (&["LANG", "LC_ALL", "LC_CTYPE", "LC_MESSAGES", "LANGUAGE"], &["C.UTF-8", "en_US.UTF-8", "en_GB.UTF-8", "C", "POSIX"]),
+    (&["COLORTERM"], &["truecolor", "24bit"]),
+    (&["RUST_BACKTRACE"], &["1", "full", "0"]),
+    (&["RUST_LOG"], &["error", "warn", "info", "debug", "trace"]),
+    (&["NODE_ENV"], &["development", "production", "test"]),
+];
+
+/// Directories often added to a search path, relative to the home directory
+const HOME_PATH_DIRS: &[&str] = &[".local/bin", "bin", ".cargo/bin", "go/bin"];
+
+/// Directories often added to a search path
+const SYSTEM_PATH_DIRS: &[&str] = &["/usr/local/bin", "/opt/homebrew/bin", "/usr/local/sbin"];
+
+/// Suggests values for `NAME=` and `export NAME=`: the current value, common programs or
+/// settings for well-known variables, and directories for `PATH`-like lists
+pub struct AssignmentSuggester {
+    enabled: bool,
+    /// Which of `PROGRAMS` are installed, checked on first use
+    installed: OnceCell<HashSet<&'static str>>,
+}
+
+impl AssignmentSuggester {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            enabled: config.suggestions.enable_variables.unwrap_or(true),
+            installed: OnceCell::new(),
+        }
+    }
+
+    /// Whether `current_arg` is the value of an assignment; before the command, or after
+    /// `export` and the like
+    pub fn applies(&self, command: &str, current_arg: &str) -> bool {
+        self.enabled
+            && (command.is_empty() || ASSIGNING_COMMANDS.contains(&command))
+            && split_assignment(current_arg).is_some()
+    }
+
+    pub fn suggest(&self, current_arg: &str, limit: usize) -> Vec<Suggestion> {
+        let Some((name, value)) = split_assignment(current_arg) else { return Vec::new() };
+        let (quote, value) = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => (&value[..1], &value[1..]),
+            _ => ("", value),
+        };
+        // A `$VAR` reference is the variable source's to complete
+        if value.rsplit(':').next().map_or(false, |part| part.contains('$') && !part.contains('/')) {
+            return Vec::new();
+        }
+
+        let mut suggestions = if is_path_list(name) {
+            self.path_entries(name, quote, value)
+        } else {
+            self.values(name, quote, value)
+        };
+
+        suggestions.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        suggestions.truncate(limit);
+        suggestions
+    }
+
+    /// The current value and what's known to suit `name`
+    fn values(&self, name: &str, quote: &str, typed: &str) -> Vec<Suggestion> {
+        let mut candidates: Vec<(String, &str, f32)> = Vec::new();
+        if let Ok(current) = env::var(name) {
+            if !current.is_empty() && !is_secret(name) {
+                candidates.push((current, "current value", 100.0));
+            }
+        }
+        if let Some((_, programs)) = PROGRAMS.iter().find(|(names, _)| names.contains(&name)) {
+            let installed = self.installed();
+            candidates.extend(programs.iter().filter(|program| installed.contains(*program)).map(|program| (program.to_string(), "installed", 80.0)));
+        }
+        if let Some((_, values)) = VALUES.iter().find(|(names, _)| names.contains(&name)) {
+            candidates.extend(values.iter().map(|value| (value.to_string(), "", 70.0)));
+        }
+
+        let mut seen = HashSet::new();
+        candidates
+            .into_iter()
+            .filter(|(value, _, _)| seen.insert(value.clone()))
+            .filter_map(|(value, description, weight)| {
+                let score = fast_fuzzy_score(typed, &value)?;
+                // Values with spaces need quoting to stay one word
+                let quote = if quote.is_empty() && value.contains(' ') { "\"" } else { quote };
+                let completion = format!("{}={}{}{}", name, quote, value, quote);
+                let suggestion = Suggestion::new(value, completion, SuggestionKind::EnvVar).with_score(score * weight);
+                Some(if description.is_empty() { suggestion } else { suggestion.with_description(description.to_string()) })
+            })
+            .collect()
+    }
+
+    /// Directories for the last entry of a `:`-separated list; the variable itself keeps
+    /// what's already there
+    fn path_entries(&self, name: &str, quote: &str, typed: &str) -> Vec<Suggestion> {
+        let (before, entry) = match typed.rfind(':') {
+            Some(colon) => typed.split_at(colon + 1),
+            None => ("", typed),
+        };
+
+        let mut candidates: Vec<(String, &str, f32)> = Vec::new();
+        if env::var_os(name).is_some() && !before.contains(&format!("${}", name)) {
+            candidates.push((format!("${}", name), "keep the current entries", 100.0));
+        }
+        if let Some(home) = dirs::home_dir() {
+            for dir in HOME_PATH_DIRS {
+                if home.join(dir).is_dir() {
+                    candidates.push((format!("$HOME/{}", dir), "exists", 80.0));
+                }
+            }
+        }
+        for dir in SYSTEM_PATH_DIRS.iter().filter(|dir| Path::new(dir).is_dir()) {
+            candidates.push((dir.to_string(), "exists", 70.0));
+        }
+        if let Some(current) = env::var_os(name) {
+            candidates.extend(env::split_paths(&current).map(|dir| (dir.to_string_lossy().into_owned(), "already listed", 60.0)));
+        }
+
+        let listed: HashSet<&str> = before.split(':').filter(|part| !part.is_empty()).collect();
+        let mut seen = HashSet::new();
+        candidates
+            .into_iter()
+            .filter(|(dir, _, _)| !dir.is_empty() && !listed.contains(dir.as_str()) && seen.insert(dir.clone()))
+            .filter_map(|(dir, description, weight)| {
+                let score = fast_fuzzy_score(entry, &dir)?;
+                let completion = format!("{}={}{}{}", name, quote, before, dir);
+                Some(
+                    Suggestion::new(dir, completion, SuggestionKind::EnvVar)
+                        .with_description(description.to_string())
+                        .with_score(score * weight),
+                )
+            })
+            .collect()
+    }
+
+    fn installed(&self) -> &HashSet<&'static str> {
+        self.installed.get_or_init(|| {
+            let dirs: Vec<_> = env::var_os("PATH").map(|path| env::split_paths(&path).collect()).unwrap_or_default();
+            PROGRAMS
+                .iter()
+                .flat_map(|(_, programs)| programs.iter().copied())
+                .filter(|program| {
+                    let binary = program.split(' ').next().unwrap_or(*program);
+                    dirs.iter().any(|dir| fs::metadata(dir.join(binary)).map_or(false, |m| m.is_file()))
+                })
+                .collect()
+        })
+    }
+}
+
+/// `NAME` and the value typed so far, for a word that assigns a variable
+fn split_assignment(word: &str) -> Option<(&str, &str)> {
+    let (name, value) = word.split_once('=')?;
+    let valid = !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    valid.then_some((name, value))
+}
+
+/// Variables holding `:`-separated lists of directories
+fn is_path_list(name: &str) -> bool {
+    name.ends_with("PATH") || name.ends_with("_DIRS")
+}
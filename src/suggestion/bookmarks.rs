@@ -0,0 +1,167 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use super::fuzzy::fast_fuzzy_score;
+use super::{Suggestion, SuggestionKind};
+
+/// Name of the bookmarks file inside the user data directory
+pub const BOOKMARKS_FILE: &str = "bookmarks.json";
+
+/// Bookmarks outrank other sources at equal match quality
+const BOOKMARK_SCORE_BOOST: f32 = 50.0;
+
+/// A saved command line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub command: String,
+
+    #[serde(default)]
+    pub description: Option<String>,
+
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Unix timestamp when the bookmark was added
+    #[serde(default)]
+    pub created_at: u64,
+}
+
+impl Bookmark {
+    pub fn new(command: &str, description: Option<String>, tags: Vec<String>) -> Self {
+        Self {
+            command: command.trim().to_string(),
+            description,
+            tags,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+        }
+    }
+
+    /// How well the bookmark matches a query; `#tag` matches tags only
+    pub fn matches(&self, query: &str) -> Option<f32> {
+        if let Some(tag) = query.strip_prefix('#') {
+            return self.tags.iter()
+                .filter(|t| t.starts_with(tag))
+                .map(|_| 1.0)
+                .next();
+        }
+
+        fast_fuzzy_score(query, &self.command)
+            .into_iter()
+            .chain(self.tags.iter().filter_map(|tag| fast_fuzzy_score(query, tag)))
+            .chain(self.description.iter().filter_map(|d| fast_fuzzy_score(query, d)).map(|score| score * 0.9))
+            .fold(None, |best: Option<f32>, score| Some(best.map_or(score, |best| best.max(score))))
+    }
+}
+
+/// Bookmarks persisted as JSON in the user data directory
+pub struct BookmarkStore {
+    path: PathBuf,
+}
+
+impl BookmarkStore {
+    pub fn new(user_data_dir: &Path) -> Self {
+        Self {
+            path: user_data_dir.join(BOOKMARKS_FILE),
+        }
+    }
+
+    pub fn load(&self) -> Vec<Bookmark> {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, bookmarks: &[Bookmark]) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        // Write to a temporary file first so a crash never leaves a torn file behind
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_string_pretty(bookmarks)?)?;
+        fs::rename(tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Add a bookmark, replacing an existing one for the same command
+    pub fn add(&self, bookmark: Bookmark) -> Result<(), Box<dyn Error>> {
+        if bookmark.command.is_empty() {
+            return Err("Cannot bookmark an empty command".into());
+        }
+
+        let mut bookmarks = self.load();
+        bookmarks.retain(|existing| existing.command != bookmark.command);
+        bookmarks.push(bookmark);
+        self.save(&bookmarks)
+    }
+
+    /// Remove a bookmark by its 1-based number in `list` order or by exact command
+    pub fn remove(&self, target: &str) -> Result<Bookmark, Box<dyn Error>> {
+        let mut bookmarks = self.load();
+
+        let index = match target.parse::<usize>() {
+            Ok(number) if number >= 1 && number <= bookmarks.len() => number - 1,
+            _ => bookmarks.iter()
+                .position(|bookmark| bookmark.command == target.trim())
+                .ok_or_else(|| format!("No bookmark matches '{}'", target))?,
+        };
+
+        let removed = bookmarks.remove(index);
+        self.save(&bookmarks)?;
+        Ok(removed)
+    }
+}
+
+/// Surfaces bookmarks in the dropdown ahead of other sources
+pub struct BookmarkSuggester {
+    enabled: bool,
+    store: BookmarkStore,
+}
+
+impl BookmarkSuggester {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            enabled: config.suggestions.enable_bookmarks.unwrap_or(true),
+            store: BookmarkStore::new(&config.general.user_data_dir),
+        }
+    }
+
+    /// Bookmarks matching the input by command, tag, or description
+    pub fn suggest(&self, input: &str, limit: usize) -> Vec<Suggestion> {
+        let input = input.trim();
+        if !self.enabled || input.is_empty() {
+            return Vec::new();
+        }
+
+        let mut suggestions: Vec<Suggestion> = self.store.load()
+            .into_iter()
+            .filter_map(|bookmark| {
+                let score = bookmark.matches(input)?;
+
+                let mut description = bookmark.description.clone().unwrap_or_else(|| "bookmark".to_string());
+                if !bookmark.tags.is_empty() {
+                    let tags: Vec<String> = bookmark.tags.iter().map(|tag| format!("#{}", tag)).collect();
+                    description = format!("{} {}", description, tags.join(" "));
+                }
+
+                Some(
+                    Suggestion::new(bookmark.command.clone(), bookmark.command, SuggestionKind::Bookmark)
+                        .with_description(description)
+                        .with_score(score * 100.0 + BOOKMARK_SCORE_BOOST)
+                )
+            })
+            .collect();
+
+        suggestions.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        suggestions.truncate(limit);
+        suggestions
+    }
+}
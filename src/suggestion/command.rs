@@ -0,0 +1,217 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use parking_lot::RwLock;
+
+use super::fuzzy::{edit_distance, max_typo_edits};
+use super::{Suggestion, SuggestionKind};
+use crate::config::Config;
+use crate::shell::aliases::AliasTable;
+use crate::shell::symbols::ShellSymbols;
+
+/// Suggests command names: aliases, shell functions, builtins, and binaries on PATH
+pub struct CommandSuggester {
+    shell_name: String,
+    aliases: RwLock<AliasTable>,
+    symbols: RwLock<ShellSymbols>,
+    path_commands: RwLock<Option<(Instant, Arc<Vec<String>>)>>,
+    cache_lifetime: Duration,
+}
+
+impl CommandSuggester {
+    pub fn new(config: &Config) -> Self {
+        let aliases = AliasTable::load_for_current_shell(config);
+        let shell_name = env::var("SHELL")
+            .ok()
+            .and_then(|s| s.rsplit('/').next().map(|s| s.to_string()))
+            .unwrap_or_else(|| "bash".to_string());
+
+        Self {
+            symbols: RwLock::new(ShellSymbols::load(&shell_name)),
+            shell_name,
+            aliases: RwLock::new(aliases),
+            path_commands: RwLock::new(None),
+            cache_lifetime: Duration::from_secs(config.suggestions.cache_lifetime_secs),
+        }
+    }
+
+    /// Suggest command names starting with the given prefix
+    pub fn suggest(&self, prefix: &str, limit: usize) -> Vec<Suggestion> {
+        let mut seen = HashSet::new();
+        let mut suggestions = Vec::new();
+
+        // Aliases and functions shadow binaries of the same name, so list them first
+        for suggestion in self.aliases.read().suggestions(prefix) {
+            seen.insert(suggestion.completion.clone());
+            suggestions.push(suggestion.with_score(90.0));
+        }
+
+        {
+            let symbols = self.symbols.read();
+
+            for name in symbols.functions.iter().filter(|f| f.starts_with(prefix)) {
+                if seen.insert(name.clone()) {
+                    suggestions.push(
+                        Suggestion::new(name.clone(), name.clone(), SuggestionKind::Command)
+                            .with_description("shell function".to_string())
+                            .with_score(85.0)
+                    );
+                }
+            }
+
+            for name in symbols.builtins.iter().filter(|b| b.starts_with(prefix)) {
+                if seen.insert(name.clone()) {
+                    suggestions.push(
+                        Suggestion::new(name.clone(), name.clone(), SuggestionKind::Command)
+                            .with_description("shell builtin".to_string())
+                            .with_score(80.0)
+                    );
+                }
+            }
+        }
+
+        for name in self.path_commands().iter().filter(|c| c.starts_with(prefix)) {
+            if suggestions.len() >= limit {
+                break;
+            }
+
+            if seen.insert(name.clone()) {
+                suggestions.push(
+                    Suggestion::new(name.clone(), name.clone(), SuggestionKind::Command)
+                        .with_score(70.0)
+                );
+            }
+        }
+
+        suggestions.truncate(limit);
+        suggestions
+    }
+
+    /// Commands `word` is likely a typo of (`gti` for `git`), closest first; none when
+    /// it names a command or starts one. `history` holds the command names the user has
+    /// run, newest first, which win ties.
+    pub fn corrections(&self, word: &str, history: &[&str], limit: usize) -> Vec<Suggestion> {
+        let max = match max_typo_edits(word) {
+            Some(max) if !word.contains(['/', '=', '$']) => max,
+            _ => return Vec::new(),
+        };
+        if self.is_known_command(word) || !self.suggest(word, 1).is_empty() {
+            return Vec::new();
+        }
+
+        // How often each name was run, which matters only among equally close names
+        let mut runs: HashMap<&str, usize> = HashMap::new();
+        for name in history {
+            *runs.entry(*name).or_insert(0) += 1;
+        }
+
+        let mut candidates: Vec<(String, usize, usize)> = Vec::new();
+        {
+            let aliases = self.aliases.read();
+            let symbols = self.symbols.read();
+            let path_commands = self.path_commands();
+            let runnable = |name: &str| {
+                aliases.get(name).is_some()
+                    || symbols.contains(name)
+                    || path_commands.binary_search_by(|c| c.as_str().cmp(name)).is_ok()
+            };
+
+            let mut seen = HashSet::new();
+            let names = runs
+                .keys()
+                .copied()
+                .chain(aliases.names())
+                .chain(symbols.functions.iter().map(String::as_str))
+                .chain(path_commands.iter().map(String::as_str));
+            for name in names {
+                if !seen.insert(name) {
+                    continue;
+                }
+                if let Some(distance) = edit_distance(word, name, max) {
+                    // History may name commands that are gone since
+                    if distance > 0 && runnable(name) {
+                        candidates.push((name.to_string(), distance, runs.get(name).copied().unwrap_or(0)));
+                    }
+                }
+            }
+        }
+
+        candidates.sort_by(|a, b| a.1.cmp(&b.1).then(b.2.cmp(&a.2)).then_with(|| a.0.cmp(&b.0)));
+        candidates
+            .into_iter()
+            .take(limit)
+            .map(|(name, distance, runs)| {
+                Suggestion::new(name.clone(), name, SuggestionKind::Command)
+                    .with_description(format!("did you mean? ({} not found)", word))
+                    .with_score(75.0 - distance as f32 * 10.0 + runs.min(10) as f32)
+            })
+            .collect()
+    }
+
+    /// Whether a name resolves to anything the shell can run
+    pub fn is_known_command(&self, name: &str) -> bool {
+        self.aliases.read().get(name).is_some()
+            || self.symbols.read().contains(name)
+            || self.path_commands().binary_search_by(|c| c.as_str().cmp(name)).is_ok()
+    }
+
+    /// Re-read aliases, functions, and builtins dumped by the integration script
+    pub fn refresh_shell_symbols(&self) {
+        self.aliases.write().reload();
+        *self.symbols.write() = ShellSymbols::load(&self.shell_name);
+    }
+
+    /// Sorted list of executables on PATH, rescanned after the cache lifetime
+    fn path_commands(&self) -> Arc<Vec<String>> {
+        if let Some((scanned_at, commands)) = self.path_commands.read().as_ref() {
+            if scanned_at.elapsed() < self.cache_lifetime {
+                return Arc::clone(commands);
+            }
+        }
+
+        let commands = Arc::new(scan_path());
+        *self.path_commands.write() = Some((Instant::now(), Arc::clone(&commands)));
+        commands
+    }
+}
+
+/// Collect executable names from every directory on PATH
+fn scan_path() -> Vec<String> {
+    let mut commands = Vec::new();
+
+    if let Some(path_var) = env::var_os("PATH") {
+        for dir in env::split_paths(&path_var) {
+            let entries = match fs::read_dir(&dir) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+
+            for entry in entries.flatten() {
+                if is_executable(&entry) {
+                    commands.push(entry.file_name().to_string_lossy().to_string());
+                }
+            }
+        }
+    }
+
+    commands.sort();
+    commands.dedup();
+    commands
+}
+
+#[cfg(unix)]
+fn is_executable(entry: &fs::DirEntry) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    // Follow symlinks; most package managers install binaries as links
+    fs::metadata(entry.path())
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(entry: &fs::DirEntry) -> bool {
+    entry.path().is_file()
+}
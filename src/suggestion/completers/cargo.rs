@@ -0,0 +1,379 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use once_cell::sync::Lazy;
+use toml::Value;
+
+use crate::plugin::api::CompletionProvider;
+use crate::suggestion::project::{find_upwards, ManifestCache};
+use crate::suggestion::spec::{generated_suggestions, ArgSpec, CommandSpec, Generator, GeneratorRegistry, OptionSpec};
+use crate::suggestion::Suggestion;
+
+/// Parsed workspaces keyed by their root manifest
+static CARGO_PROJECTS: Lazy<ManifestCache<CargoProject>> = Lazy::new(ManifestCache::new);
+
+/// Targets, features, and dependencies of one package
+#[derive(Debug, Clone, Default)]
+pub struct CargoPackage {
+    pub name: String,
+    pub dir: PathBuf,
+    pub bins: Vec<String>,
+    pub examples: Vec<String>,
+    pub tests: Vec<String>,
+    pub benches: Vec<String>,
+    pub features: Vec<String>,
+    /// Dependency names with their table (`dependencies`, `dev-dependencies`, ...)
+    pub dependencies: Vec<(String, &'static str)>,
+}
+
+/// A Cargo package or workspace, parsed from `Cargo.toml`
+///
+/// Cached by the root manifest's mtime; member manifests are read along with it.
+#[derive(Debug, Clone, Default)]
+pub struct CargoProject {
+    pub root: PathBuf,
+    pub packages: Vec<CargoPackage>,
+}
+
+impl CargoProject {
+    /// Load the project containing `dir`, resolving up to the workspace root
+    pub fn load(dir: &Path) -> Option<Arc<CargoProject>> {
+        let manifest = find_upwards(dir, "Cargo.toml")?;
+        let root_manifest = workspace_root(&manifest).unwrap_or(manifest);
+
+        CARGO_PROJECTS.get(&root_manifest, |path, content| {
+            let document: Value = toml::from_str(content).ok()?;
+            let root = path.parent()?.to_path_buf();
+            let mut packages = Vec::new();
+
+            if document.get("package").is_some() {
+                packages.push(parse_package(&root, &document));
+            }
+
+            for member_dir in workspace_members(&root, &document) {
+                let member: Value = match fs::read_to_string(member_dir.join("Cargo.toml"))
+                    .ok()
+                    .and_then(|content| toml::from_str(&content).ok())
+                {
+                    Some(member) => member,
+                    None => continue,
+                };
+                packages.push(parse_package(&member_dir, &member));
+            }
+
+            Some(CargoProject { root, packages })
+        })
+    }
+
+    /// Packages relevant from `dir`: the enclosing member, or every package at the workspace root
+    pub fn packages_for(&self, dir: &Path) -> Vec<&CargoPackage> {
+        let enclosing = self.packages.iter()
+            .filter(|package| dir.starts_with(&package.dir))
+            .max_by_key(|package| package.dir.components().count());
+
+        match enclosing {
+            Some(package) if package.dir != self.root || self.packages.len() == 1 => vec![package],
+            _ => self.packages.iter().collect(),
+        }
+    }
+}
+
+/// The nearest ancestor manifest declaring `[workspace]`
+fn workspace_root(manifest: &Path) -> Option<PathBuf> {
+    manifest.parent()?.ancestors()
+        .map(|dir| dir.join("Cargo.toml"))
+        .filter(|candidate| candidate.is_file())
+        .find(|candidate| {
+            fs::read_to_string(candidate)
+                .ok()
+                .and_then(|content| toml::from_str::<Value>(&content).ok())
+                .map_or(false, |document| document.get("workspace").is_some())
+        })
+}
+
+/// Expand `workspace.members` (supporting trailing `/*` globs), minus `workspace.exclude`
+fn workspace_members(root: &Path, document: &Value) -> Vec<PathBuf> {
+    let strings = |key: &str| -> Vec<String> {
+        document.get("workspace")
+            .and_then(|w| w.get(key))
+            .and_then(|v| v.as_array())
+            .map(|items| items.iter().filter_map(|i| i.as_str().map(|s| s.to_string())).collect())
+            .unwrap_or_default()
+    };
+
+    let excluded: Vec<PathBuf> = strings("exclude").iter().map(|e| root.join(e)).collect();
+    let mut members = Vec::new();
+
+    for pattern in strings("members") {
+        match pattern.strip_suffix("/*") {
+            Some(parent) => {
+                if let Ok(entries) = fs::read_dir(root.join(parent)) {
+                    members.extend(entries.flatten().map(|e| e.path()).filter(|p| p.join("Cargo.toml").is_file()));
+                }
+            }
+            None => members.push(root.join(pattern)),
+        }
+    }
+
+    members.retain(|member| member != root && !excluded.contains(member));
+    members.sort();
+    members
+}
+
+fn parse_package(dir: &Path, document: &Value) -> CargoPackage {
+    let name = document.get("package")
+        .and_then(|p| p.get("name"))
+        .and_then(|n| n.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    // Explicit `[[bin]]`-style targets plus the ones Cargo discovers from the layout
+    let targets = |table: &str, auto_dir: &str| -> Vec<String> {
+        let mut names: Vec<String> = document.get(table)
+            .and_then(|t| t.as_array())
+            .map(|items| items.iter().filter_map(|i| i.get("name")?.as_str().map(|s| s.to_string())).collect())
+            .unwrap_or_default();
+        names.extend(discover_targets(&dir.join(auto_dir)));
+        names.sort();
+        names.dedup();
+        names
+    };
+
+    let mut bins = targets("bin", "src/bin");
+    if dir.join("src/main.rs").is_file() && !name.is_empty() && !bins.contains(&name) {
+        bins.insert(0, name.clone());
+    }
+
+    let mut dependencies = Vec::new();
+    for table in ["dependencies", "dev-dependencies", "build-dependencies"] {
+        if let Some(deps) = document.get(table).and_then(|d| d.as_table()) {
+            dependencies.extend(deps.keys().map(|dep| (dep.clone(), table)));
+        }
+    }
+
+    CargoPackage {
+        dir: dir.to_path_buf(),
+        bins,
+        examples: targets("example", "examples"),
+        tests: targets("test", "tests"),
+        benches: targets("bench", "benches"),
+        features: parse_features(document),
+        dependencies,
+        name,
+    }
+}
+
+/// Declared features plus the implicit features of optional dependencies
+fn parse_features(document: &Value) -> Vec<String> {
+    let declared = document.get("features").and_then(|f| f.as_table());
+    let mut features: Vec<String> = declared.map(|f| f.keys().cloned().collect()).unwrap_or_default();
+
+    // An optional dependency is a feature unless some feature refers to it as `dep:name`
+    let explicit_deps: Vec<String> = declared
+        .into_iter()
+        .flat_map(|f| f.values())
+        .filter_map(|v| v.as_array())
+        .flatten()
+        .filter_map(|v| v.as_str()?.strip_prefix("dep:").map(|s| s.to_string()))
+        .collect();
+
+    if let Some(deps) = document.get("dependencies").and_then(|d| d.as_table()) {
+        for (dep, spec) in deps {
+            let optional = spec.get("optional").and_then(|o| o.as_bool()).unwrap_or(false);
+            if optional && !explicit_deps.contains(dep) && !features.contains(dep) {
+                features.push(dep.clone());
+            }
+        }
+    }
+
+    features.sort();
+    features
+}
+
+/// Target names from `dir/*.rs` and `dir/*/main.rs`
+fn discover_targets(dir: &Path) -> Vec<String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension().map_or(false, |e| e == "rs") {
+                path.file_stem().map(|s| s.to_string_lossy().to_string())
+            } else if path.join("main.rs").is_file() {
+                path.file_name().map(|s| s.to_string_lossy().to_string())
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Which part of the project a generator lists
+#[derive(Clone, Copy)]
+enum CargoItem {
+    Bins,
+    Examples,
+    Tests,
+    Benches,
+    Features,
+    Packages,
+    Dependencies,
+}
+
+/// Completes targets, features, packages, and dependencies of the project around the cwd
+struct CargoGenerator {
+    item: CargoItem,
+}
+
+impl Generator for CargoGenerator {
+    fn generate(&self, _args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        let cwd = match env::current_dir() {
+            Ok(cwd) => cwd,
+            Err(_) => return Vec::new(),
+        };
+        let project = match CargoProject::load(&cwd) {
+            Some(project) => project,
+            None => return Vec::new(),
+        };
+
+        let packages = match self.item {
+            CargoItem::Packages => project.packages.iter().collect(),
+            _ => project.packages_for(&cwd),
+        };
+
+        let mut candidates: Vec<(String, String)> = Vec::new();
+        for package in packages {
+            let (names, label): (Vec<String>, &str) = match self.item {
+                CargoItem::Bins => (package.bins.clone(), "binary"),
+                CargoItem::Examples => (package.examples.clone(), "example"),
+                CargoItem::Tests => (package.tests.clone(), "test"),
+                CargoItem::Benches => (package.benches.clone(), "bench"),
+                CargoItem::Features => (package.features.clone(), "feature"),
+                CargoItem::Packages => (vec![package.name.clone()], "package"),
+                CargoItem::Dependencies => {
+                    for (dep, table) in &package.dependencies {
+                        let kind = match *table {
+                            "dev-dependencies" => "dev-dependency",
+                            "build-dependencies" => "build-dependency",
+                            _ => "dependency",
+                        };
+                        candidates.push((dep.clone(), format!("{} of {}", kind, package.name)));
+                    }
+                    continue;
+                }
+            };
+
+            candidates.extend(names.into_iter().map(|name| (name, format!("{} in {}", label, package.name))));
+        }
+
+        candidates.sort();
+        candidates.dedup_by(|a, b| a.0 == b.0);
+
+        // `--features a,b,<current>` completes the last item of the list
+        if let CargoItem::Features = self.item {
+            if let Some((done, last)) = current_arg.rsplit_once(',') {
+                return generated_suggestions(candidates.into_iter(), last)
+                    .into_iter()
+                    .map(|mut suggestion| {
+                        suggestion.completion = format!("{},{}", done, suggestion.completion);
+                        suggestion
+                    })
+                    .collect();
+            }
+        }
+
+        generated_suggestions(candidates.into_iter(), current_arg)
+    }
+}
+
+/// Cargo completion driven by the project's manifests
+pub struct CargoCompleter {
+    spec: CommandSpec,
+    generators: GeneratorRegistry,
+}
+
+impl CargoCompleter {
+    pub fn new() -> Self {
+        let mut generators = GeneratorRegistry::new();
+        let items = [
+            ("cargo:bins", CargoItem::Bins),
+            ("cargo:examples", CargoItem::Examples),
+            ("cargo:tests", CargoItem::Tests),
+            ("cargo:benches", CargoItem::Benches),
+            ("cargo:features", CargoItem::Features),
+            ("cargo:packages", CargoItem::Packages),
+            ("cargo:dependencies", CargoItem::Dependencies),
+        ];
+        for (name, item) in items {
+            generators.register(name, Arc::new(CargoGenerator { item }));
+        }
+
+        Self {
+            spec: cargo_spec(),
+            generators,
+        }
+    }
+}
+
+impl CompletionProvider for CargoCompleter {
+    fn name(&self) -> &str {
+        "cargo"
+    }
+
+    fn can_provide_completions(&self, command: &str) -> bool {
+        command == "cargo"
+    }
+
+    fn provide_completions(&self, _command: &str, args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        self.spec.suggestions_with(args, current_arg, &self.generators)
+    }
+}
+
+/// Built-in spec for cargo's subcommands and the options whose values we can complete
+pub fn cargo_spec() -> CommandSpec {
+    let package = || OptionSpec::generated(&["-p", "--package"], "SPEC", "Package to operate on", "cargo:packages");
+    let features = || OptionSpec::generated(&["-F", "--features"], "FEATURES", "Space or comma separated list of features to activate", "cargo:features");
+    let bin = || OptionSpec::generated(&["--bin"], "NAME", "Name of the bin target", "cargo:bins");
+    let example = || OptionSpec::generated(&["--example"], "NAME", "Name of the example target", "cargo:examples");
+    let test = || OptionSpec::generated(&["--test"], "NAME", "Name of the test target", "cargo:tests");
+    let bench = || OptionSpec::generated(&["--bench"], "NAME", "Name of the bench target", "cargo:benches");
+
+    let targeted = |name: &str, description: &str| {
+        let mut spec = CommandSpec::with_args(name, description, Vec::new());
+        spec.options = vec![package(), features(), bin(), example(), test(), bench()];
+        spec
+    };
+
+    let mut run = CommandSpec::with_args("run", "Run a binary or example of the local package", Vec::new());
+    run.options = vec![package(), features(), bin(), example()];
+
+    let mut remove = CommandSpec::with_args(
+        "remove",
+        "Remove dependencies from a Cargo.toml manifest file",
+        vec![ArgSpec::generated("dependency", "cargo:dependencies", true)],
+    );
+    remove.options = vec![package()];
+
+    let mut update = CommandSpec::with_args("update", "Update dependencies as recorded in the local lock file", Vec::new());
+    update.options = vec![OptionSpec::generated(&["-p", "--package"], "SPEC", "Package to update", "cargo:dependencies")];
+
+    let mut spec = CommandSpec::new("cargo");
+    spec.subcommands = vec![
+        run,
+        targeted("build", "Compile the current package"),
+        targeted("check", "Analyze the current package and report errors"),
+        targeted("clippy", "Checks a package to catch common mistakes"),
+        targeted("test", "Execute all unit and integration tests"),
+        targeted("bench", "Execute all benchmarks of a local package"),
+        targeted("doc", "Build a package's documentation"),
+        remove,
+        update,
+    ];
+
+    spec
+}
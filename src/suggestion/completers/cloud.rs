@@ -0,0 +1,349 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use serde_json::Value;
+
+use crate::plugin::api::CompletionProvider;
+use crate::suggestion::spec::{generated_suggestions, ArgSpec, CommandSpec, Generator, GeneratorRegistry, OptionSpec};
+use crate::suggestion::Suggestion;
+
+/// Regions offered even when no profile pins one
+const AWS_REGIONS: &[&str] = &[
+    "us-east-1", "us-east-2", "us-west-1", "us-west-2",
+    "ca-central-1", "sa-east-1",
+    "eu-west-1", "eu-west-2", "eu-west-3", "eu-central-1", "eu-north-1", "eu-south-1",
+    "ap-south-1", "ap-northeast-1", "ap-northeast-2", "ap-northeast-3",
+    "ap-southeast-1", "ap-southeast-2", "ap-east-1",
+    "me-south-1", "af-south-1",
+];
+
+/// One `[section]` of an INI-style file with its key/value pairs
+struct IniSection {
+    name: String,
+    values: Vec<(String, String)>,
+}
+
+impl IniSection {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.values.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+}
+
+/// Parse the simple INI dialect used by the AWS and gcloud CLIs
+fn parse_ini(content: &str) -> Vec<IniSection> {
+    let mut sections: Vec<IniSection> = Vec::new();
+
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            sections.push(IniSection {
+                name: name.trim().to_string(),
+                values: Vec::new(),
+            });
+        } else if let (Some(section), Some((key, value))) = (sections.last_mut(), line.split_once('=')) {
+            section.values.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    sections
+}
+
+fn read_ini(path: Option<PathBuf>) -> Vec<IniSection> {
+    path.and_then(|path| fs::read_to_string(path).ok())
+        .map(|content| parse_ini(&content))
+        .unwrap_or_default()
+}
+
+/// `$VAR` if set, otherwise a path under the home directory
+fn config_path(var: &str, home_relative: &str) -> Option<PathBuf> {
+    env::var_os(var)
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(home_relative)))
+}
+
+/// Completes AWS profiles from `~/.aws/config` and `~/.aws/credentials`
+struct AwsProfilesGenerator;
+
+impl Generator for AwsProfilesGenerator {
+    fn generate(&self, _args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        let mut profiles: Vec<(String, String)> = Vec::new();
+
+        // `~/.aws/config` names sections `[profile foo]` (except `[default]`)
+        for section in read_ini(config_path("AWS_CONFIG_FILE", ".aws/config")) {
+            let name = section.name.strip_prefix("profile ").unwrap_or(&section.name).trim().to_string();
+            if section.name.starts_with("sso-session ") || section.name.starts_with("services ") {
+                continue;
+            }
+
+            let description = match section.get("region") {
+                Some(region) => format!("profile ({})", region),
+                None => "profile".to_string(),
+            };
+            profiles.push((name, description));
+        }
+
+        for section in read_ini(config_path("AWS_SHARED_CREDENTIALS_FILE", ".aws/credentials")) {
+            if !profiles.iter().any(|(name, _)| *name == section.name) {
+                profiles.push((section.name, "profile (credentials)".to_string()));
+            }
+        }
+
+        generated_suggestions(profiles.into_iter(), current_arg)
+    }
+}
+
+/// Completes AWS regions, listing regions pinned by local profiles first
+struct AwsRegionsGenerator;
+
+impl Generator for AwsRegionsGenerator {
+    fn generate(&self, _args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        let mut regions: Vec<(String, String)> = Vec::new();
+
+        for section in read_ini(config_path("AWS_CONFIG_FILE", ".aws/config")) {
+            if let Some(region) = section.get("region") {
+                if !regions.iter().any(|(r, _)| r == region) {
+                    regions.push((region.to_string(), "region (configured)".to_string()));
+                }
+            }
+        }
+
+        for region in AWS_REGIONS {
+            if !regions.iter().any(|(r, _)| r == region) {
+                regions.push((region.to_string(), "region".to_string()));
+            }
+        }
+
+        generated_suggestions(regions.into_iter(), current_arg)
+    }
+}
+
+/// Directory holding gcloud's `configurations/config_<name>` files
+fn gcloud_config_dir() -> Option<PathBuf> {
+    config_path("CLOUDSDK_CONFIG", ".config/gcloud")
+}
+
+/// gcloud named configurations as (name, core/project)
+fn gcloud_configurations() -> Vec<(String, Option<String>)> {
+    let dir = match gcloud_config_dir() {
+        Some(dir) => dir.join("configurations"),
+        None => return Vec::new(),
+    };
+
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut configurations: Vec<(String, Option<String>)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let name = file_name.strip_prefix("config_")?.to_string();
+
+            let project = read_ini(Some(entry.path()))
+                .iter()
+                .find(|section| section.name == "core")
+                .and_then(|core| core.get("project").map(|p| p.to_string()));
+
+            Some((name, project))
+        })
+        .collect();
+
+    configurations.sort();
+    configurations
+}
+
+/// Completes gcloud configuration names
+struct GcloudConfigurationsGenerator;
+
+impl Generator for GcloudConfigurationsGenerator {
+    fn generate(&self, _args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        let active = gcloud_config_dir()
+            .and_then(|dir| fs::read_to_string(dir.join("active_config")).ok())
+            .map(|name| name.trim().to_string());
+
+        let candidates = gcloud_configurations().into_iter().map(|(name, project)| {
+            let mut description = match project {
+                Some(project) => format!("configuration ({})", project),
+                None => "configuration".to_string(),
+            };
+            if active.as_deref() == Some(name.as_str()) {
+                description.push_str(" · active");
+            }
+            (name, description)
+        });
+
+        generated_suggestions(candidates, current_arg)
+    }
+}
+
+/// Completes gcloud projects referenced by local configurations
+struct GcloudProjectsGenerator;
+
+impl Generator for GcloudProjectsGenerator {
+    fn generate(&self, _args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        let mut projects: Vec<(String, String)> = gcloud_configurations()
+            .into_iter()
+            .filter_map(|(name, project)| project.map(|p| (p, format!("project of {}", name))))
+            .collect();
+        projects.dedup_by(|a, b| a.0 == b.0);
+
+        generated_suggestions(projects.into_iter(), current_arg)
+    }
+}
+
+/// Completes Azure subscriptions from `~/.azure/azureProfile.json`
+struct AzureSubscriptionsGenerator;
+
+impl Generator for AzureSubscriptionsGenerator {
+    fn generate(&self, _args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        let profile = config_path("AZURE_CONFIG_DIR", ".azure")
+            .and_then(|dir| fs::read_to_string(dir.join("azureProfile.json")).ok());
+
+        // The Azure CLI writes this file with a UTF-8 byte order mark
+        let profile: Value = match profile.and_then(|p| serde_json::from_str(p.trim_start_matches('\u{feff}')).ok()) {
+            Some(profile) => profile,
+            None => return Vec::new(),
+        };
+
+        let mut candidates = Vec::new();
+        for subscription in profile["subscriptions"].as_array().into_iter().flatten() {
+            let (name, id) = match (subscription["name"].as_str(), subscription["id"].as_str()) {
+                (Some(name), Some(id)) => (name, id),
+                _ => continue,
+            };
+            let default = if subscription["isDefault"].as_bool() == Some(true) { " · default" } else { "" };
+
+            candidates.push((name.to_string(), format!("subscription {}{}", id, default)));
+            candidates.push((id.to_string(), format!("subscription {}{}", name, default)));
+        }
+
+        generated_suggestions(candidates.into_iter(), current_arg)
+    }
+}
+
+/// Completes the most common aws/gcloud/az arguments from local config files, without network calls
+pub struct CloudCompleter {
+    aws: CommandSpec,
+    gcloud: CommandSpec,
+    az: CommandSpec,
+    generators: GeneratorRegistry,
+}
+
+impl CloudCompleter {
+    pub fn new() -> Self {
+        let mut generators = GeneratorRegistry::new();
+        generators.register("aws:profiles", Arc::new(AwsProfilesGenerator));
+        generators.register("aws:regions", Arc::new(AwsRegionsGenerator));
+        generators.register("gcloud:configurations", Arc::new(GcloudConfigurationsGenerator));
+        generators.register("gcloud:projects", Arc::new(GcloudProjectsGenerator));
+        generators.register("az:subscriptions", Arc::new(AzureSubscriptionsGenerator));
+
+        Self {
+            aws: aws_spec(),
+            gcloud: gcloud_spec(),
+            az: az_spec(),
+            generators,
+        }
+    }
+}
+
+impl CompletionProvider for CloudCompleter {
+    fn name(&self) -> &str {
+        "cloud"
+    }
+
+    fn can_provide_completions(&self, command: &str) -> bool {
+        matches!(command, "aws" | "gcloud" | "az")
+    }
+
+    fn provide_completions(&self, command: &str, args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        let spec = match command {
+            "aws" => &self.aws,
+            "gcloud" => &self.gcloud,
+            "az" => &self.az,
+            _ => return Vec::new(),
+        };
+
+        spec.suggestions_with(args, current_arg, &self.generators)
+    }
+}
+
+fn aws_spec() -> CommandSpec {
+    let mut spec = CommandSpec::new("aws");
+    spec.options = vec![
+        OptionSpec::generated(&["--profile"], "PROFILE", "Use a specific profile from your credential file", "aws:profiles"),
+        OptionSpec::generated(&["--region"], "REGION", "The region to use", "aws:regions"),
+    ];
+
+    let mut configure = CommandSpec::with_args("configure", "Configure AWS CLI options", Vec::new());
+    configure.subcommands = vec![
+        CommandSpec::with_args("list", "List configuration values", Vec::new()),
+        CommandSpec::with_args("sso", "Configure an SSO profile", Vec::new()),
+    ];
+    let mut sso = CommandSpec::with_args("sso", "AWS IAM Identity Center", Vec::new());
+    sso.subcommands = vec![
+        CommandSpec::with_args("login", "Sign in with an SSO profile", Vec::new()),
+        CommandSpec::with_args("logout", "Sign out of SSO sessions", Vec::new()),
+    ];
+    spec.subcommands = vec![configure, sso];
+
+    // AWS accepts global options after the service and operation
+    let global_options = spec.options.clone();
+    spec.propagate_options(&global_options);
+    spec
+}
+
+fn gcloud_spec() -> CommandSpec {
+    let mut spec = CommandSpec::new("gcloud");
+    spec.options = vec![
+        OptionSpec::generated(&["--configuration"], "CONFIGURATION", "Named configuration to use", "gcloud:configurations"),
+        OptionSpec::generated(&["--project"], "PROJECT_ID", "Google Cloud project to use", "gcloud:projects"),
+    ];
+
+    let configuration = || vec![ArgSpec::generated("configuration", "gcloud:configurations", false)];
+    let mut configurations = CommandSpec::with_args("configurations", "Manage named configurations", Vec::new());
+    configurations.subcommands = vec![
+        CommandSpec::with_args("activate", "Activate an existing named configuration", configuration()),
+        CommandSpec::with_args("describe", "Describe a named configuration", configuration()),
+        CommandSpec::with_args("delete", "Delete a named configuration", configuration()),
+        CommandSpec::with_args("list", "List existing named configurations", Vec::new()),
+        CommandSpec::with_args("create", "Create a new named configuration", Vec::new()),
+    ];
+
+    let mut config = CommandSpec::with_args("config", "View and edit gcloud properties", Vec::new());
+    config.subcommands = vec![
+        configurations,
+        CommandSpec::with_args("list", "List gcloud properties", Vec::new()),
+        CommandSpec::with_args("set", "Set a gcloud property", Vec::new()),
+    ];
+    spec.subcommands = vec![config];
+
+    let global_options = spec.options.clone();
+    spec.propagate_options(&global_options);
+    spec
+}
+
+fn az_spec() -> CommandSpec {
+    let subscription = OptionSpec::generated(&["--subscription"], "SUBSCRIPTION", "Name or ID of subscription", "az:subscriptions");
+
+    let mut set = CommandSpec::with_args("set", "Set a subscription to be the current active subscription", Vec::new());
+    set.options = vec![OptionSpec::generated(&["-s", "--subscription"], "SUBSCRIPTION", "Name or ID of subscription", "az:subscriptions")];
+
+    let mut account = CommandSpec::with_args("account", "Manage Azure subscription information", Vec::new());
+    account.subcommands = vec![
+        set,
+        CommandSpec::with_args("show", "Get the details of a subscription", Vec::new()),
+        CommandSpec::with_args("list", "Get a list of subscriptions for the logged in account", Vec::new()),
+    ];
+
+    let mut spec = CommandSpec::new("az");
+    spec.options = vec![subscription.clone()];
+    spec.subcommands = vec![account];
+    spec.propagate_options(&[subscription]);
+    spec
+}
@@ -0,0 +1,388 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use parking_lot::RwLock;
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::plugin::api::CompletionProvider;
+use crate::suggestion::{Suggestion, SuggestionKind};
+
+/// Commands whose pattern completes from words in their files
+const PATTERN_COMMANDS: &[&str] = &["grep", "egrep", "fgrep", "rg", "ag", "sed"];
+
+/// Options that take the following word as their value, by command
+const VALUE_FLAGS: &[(&str, &[&str])] = &[
+    ("grep", &["-A", "-B", "-C", "-m", "-d", "-D", "--label", "--include", "--exclude", "--exclude-dir"]),
+    ("egrep", &["-A", "-B", "-C", "-m", "-d", "-D"]),
+    ("fgrep", &["-A", "-B", "-C", "-m", "-d", "-D"]),
+    ("rg", &["-A", "-B", "-C", "-m", "-g", "--glob", "-t", "--type", "-T", "--type-not", "-M", "-j", "--max-depth"]),
+    ("ag", &["-A", "-B", "-C", "-m", "-G", "--ignore"]),
+    ("sed", &["-l"]),
+    ("jq", &["--indent"]),
+];
+
+/// `jq` options that take a name and a value
+const JQ_PAIR_FLAGS: &[&str] = &["--arg", "--argjson", "--slurpfile", "--rawfile"];
+
+/// Options whose value is the pattern (or script) itself; `jq -e` is something else
+const PATTERN_FLAGS: &[&str] = &["-e", "--regexp", "--expression"];
+
+/// Options whose value is a file of patterns, so every positional is a file to read
+const PATTERN_FILE_FLAGS: &[&str] = &["-f", "--file"];
+
+/// Files read for one completion
+const MAX_FILES: usize = 3;
+
+/// Most of a file read for words; patterns near the top are as good as any
+const MAX_READ_BYTES: u64 = 256 * 1024;
+
+/// Largest JSON file parsed for keys; a partial document can't be parsed
+const MAX_JSON_BYTES: u64 = 1024 * 1024;
+
+/// Time spent reading files for one completion; files already sampled are still used
+const READ_BUDGET: Duration = Duration::from_millis(15);
+
+/// Words and key paths kept per file
+const MAX_ENTRIES: usize = 2000;
+
+/// Files remembered before the cache starts over
+const MAX_CACHED_FILES: usize = 64;
+
+/// Shortest word worth completing
+const MIN_WORD_LEN: usize = 3;
+
+/// Deepest key path offered for `jq`
+const MAX_KEY_DEPTH: usize = 6;
+
+/// What was sampled from one file, kept while it's unchanged
+struct Sample<T> {
+    modified: Option<SystemTime>,
+    len: u64,
+    entries: Arc<Vec<T>>,
+}
+
+/// Completes `grep`/`rg`/`ag`/`sed` patterns from words in the files being searched, and
+/// `jq` filters from the key paths of the JSON being read (`suggestions.enable_file_content`)
+///
+/// The files are the ones named on the line; as the pattern usually comes before them, the
+/// most recently modified files in the current directory stand in until one is typed.
+pub struct FileContentCompleter {
+    enabled: bool,
+    /// Words by frequency, with how often each occurs
+    words: RwLock<HashMap<PathBuf, Sample<(String, usize)>>>,
+    /// `jq` key paths, with the type of value each leads to
+    keys: RwLock<HashMap<PathBuf, Sample<(String, &'static str)>>>,
+}
+
+impl FileContentCompleter {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            enabled: config.suggestions.enable_file_content.unwrap_or(false),
+            words: RwLock::new(HashMap::new()),
+            keys: RwLock::new(HashMap::new()),
+        }
+    }
+
+    fn complete_pattern(&self, command: &str, files: &[PathBuf], current_arg: &str) -> Vec<Suggestion> {
+        let (quote, typed) = split_quote(current_arg);
+
+        // A sed script completes the text being substituted: `s/pat`
+        let (script_prefix, prefix) = if command == "sed" {
+            match sed_pattern(typed) {
+                Some(split) => split,
+                None => return Vec::new(),
+            }
+        } else {
+            ("", typed)
+        };
+        if prefix.is_empty() {
+            return Vec::new();
+        }
+
+        let deadline = Instant::now() + READ_BUDGET;
+        let samples: Vec<(&Path, Arc<Vec<(String, usize)>>)> = files
+            .iter()
+            .filter_map(|path| Some((path.as_path(), sampled(&self.words, path, deadline, read_words)?)))
+            .collect();
+        let mut counts: HashMap<&str, (usize, &Path)> = HashMap::new();
+        for (path, words) in &samples {
+            for (word, count) in words.iter().filter(|(word, _)| word.starts_with(prefix) && word.len() > prefix.len()) {
+                counts.entry(word.as_str()).or_insert((0, *path)).0 += count;
+            }
+        }
+
+        counts
+            .into_iter()
+            .map(|(word, (count, path))| {
+                let file = path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+                let times = if count == 1 { "once".to_string() } else { format!("{}×", count) };
+                Suggestion::new(word.to_string(), format!("{}{}{}", quote, script_prefix, word), SuggestionKind::Argument)
+                    .with_description(format!("{} in {}", times, file))
+                    .with_score(60.0 + (count as f32).ln_1p() * 5.0)
+            })
+            .collect()
+    }
+
+    fn complete_filter(&self, files: &[PathBuf], current_arg: &str) -> Vec<Suggestion> {
+        let (quote, typed) = split_quote(current_arg);
+        if !typed.is_empty() && !typed.starts_with('.') {
+            return Vec::new();
+        }
+
+        let deadline = Instant::now() + READ_BUDGET;
+        let mut seen: HashMap<String, &'static str> = HashMap::new();
+        for path in files {
+            let keys = match sampled(&self.keys, path, deadline, read_keys) {
+                Some(keys) => keys,
+                None => continue,
+            };
+            for (key_path, kind) in keys.iter().filter(|(key_path, _)| key_path.starts_with(typed) && key_path.len() > typed.len()) {
+                seen.entry(key_path.clone()).or_insert(*kind);
+            }
+        }
+
+        seen.into_iter()
+            .map(|(key_path, kind)| {
+                // Nearer keys first
+                let depth = key_path.matches(['.', '[']).count();
+                Suggestion::new(key_path.clone(), format!("{}{}", quote, key_path), SuggestionKind::Argument)
+                    .with_description(kind.to_string())
+                    .with_score(80.0 - depth as f32 * 5.0)
+            })
+            .collect()
+    }
+}
+
+impl CompletionProvider for FileContentCompleter {
+    fn name(&self) -> &str {
+        "file_content"
+    }
+
+    fn can_provide_completions(&self, command: &str) -> bool {
+        self.enabled && (command == "jq" || PATTERN_COMMANDS.contains(&command))
+    }
+
+    fn provide_completions(&self, command: &str, args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        if current_arg.starts_with('-') {
+            return Vec::new();
+        }
+        let value_flags = VALUE_FLAGS.iter().find(|(name, _)| *name == command).map_or(&[][..], |(_, flags)| *flags);
+        let line = Positionals::of(command, args, value_flags);
+
+        // Only the pattern completes: the word after `-e`, or the first positional
+        let is_pattern = line.after_pattern_flag || (!line.pattern_given && line.positionals.is_empty());
+        if !is_pattern {
+            return Vec::new();
+        }
+
+        let cwd = match env::current_dir() {
+            Ok(cwd) => cwd,
+            Err(_) => return Vec::new(),
+        };
+        // Files named so far, after the pattern unless `-e` or `-f` gave it
+        let skip = if line.pattern_given { 0 } else { 1 };
+        let mut files: Vec<PathBuf> = line
+            .positionals
+            .iter()
+            .skip(skip)
+            .map(|arg| cwd.join(arg.trim_matches(|c| c == '\'' || c == '"')))
+            .filter(|path| path.is_file())
+            .take(MAX_FILES)
+            .collect();
+        if files.is_empty() {
+            files = recent_files(&cwd, command == "jq");
+        }
+
+        let mut suggestions = if command == "jq" {
+            self.complete_filter(&files, current_arg)
+        } else {
+            self.complete_pattern(command, &files, current_arg)
+        };
+        suggestions.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        suggestions
+    }
+}
+
+/// The completed words of a line, sorted into options and positionals
+struct Positionals<'a> {
+    positionals: Vec<&'a str>,
+    /// `-e` or `-f` supplied the pattern, so no positional is one
+    pattern_given: bool,
+    /// The last word was `-e`, so the one being typed is a pattern
+    after_pattern_flag: bool,
+}
+
+impl<'a> Positionals<'a> {
+    fn of(command: &str, args: &[&'a str], value_flags: &[&str]) -> Self {
+        let jq = command == "jq";
+        let mut line = Self { positionals: Vec::new(), pattern_given: false, after_pattern_flag: false };
+        let mut skip_values = 0;
+        for (index, arg) in args.iter().enumerate() {
+            let last = index + 1 == args.len();
+            if skip_values > 0 {
+                skip_values -= 1;
+                continue;
+            }
+            if (!jq && PATTERN_FLAGS.contains(arg)) || PATTERN_FILE_FLAGS.contains(arg) {
+                line.pattern_given = true;
+                line.after_pattern_flag = last && PATTERN_FLAGS.contains(arg);
+                skip_values = 1;
+            } else if jq && JQ_PAIR_FLAGS.contains(arg) {
+                skip_values = 2;
+            } else if value_flags.contains(arg) {
+                skip_values = 1;
+            } else if !arg.starts_with('-') || *arg == "-" {
+                line.positionals.push(arg);
+            }
+        }
+        line
+    }
+}
+
+/// An opening quote, and the text after it
+fn split_quote(word: &str) -> (&str, &str) {
+    match word.chars().next() {
+        Some(quote @ ('\'' | '"')) => word.split_at(quote.len_utf8()),
+        _ => ("", word),
+    }
+}
+
+/// For a sed script typed as far as `s/pat`, the `s/` and the `pat`; None once the
+/// pattern is finished or for other commands
+fn sed_pattern(script: &str) -> Option<(&str, &str)> {
+    let mut chars = script.char_indices();
+    let (_, command) = chars.next()?;
+    let (start, delimiter) = chars.next()?;
+    if command != 's' || delimiter.is_alphanumeric() || delimiter.is_whitespace() {
+        return None;
+    }
+    let split = start + delimiter.len_utf8();
+    let pattern = &script[split..];
+    (!pattern.contains(delimiter)).then(|| (&script[..split], pattern))
+}
+
+/// The most recently modified files in `dir`, JSON only for `jq`
+fn recent_files(dir: &Path, json: bool) -> Vec<PathBuf> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut files: Vec<(SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+        .filter(|entry| !json || entry.path().extension().map_or(false, |ext| ext == "json"))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            let limit = if json { MAX_JSON_BYTES } else { u64::MAX };
+            (metadata.is_file() && metadata.len() > 0 && metadata.len() <= limit)
+                .then(|| (metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH), entry.path()))
+        })
+        .collect();
+    files.sort_by(|a, b| b.0.cmp(&a.0));
+    files.into_iter().take(MAX_FILES).map(|(_, path)| path).collect()
+}
+
+/// What was sampled from `path`, read with `read` if it's new or changed and there's time left
+fn sampled<T>(
+    cache: &RwLock<HashMap<PathBuf, Sample<T>>>,
+    path: &Path,
+    deadline: Instant,
+    read: fn(&Path, u64) -> Option<Vec<T>>,
+) -> Option<Arc<Vec<T>>> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok();
+    if let Some(sample) = cache.read().get(path) {
+        if sample.modified == modified && sample.len == metadata.len() {
+            return Some(Arc::clone(&sample.entries));
+        }
+    }
+    if Instant::now() >= deadline {
+        return None;
+    }
+
+    let entries = Arc::new(read(path, metadata.len())?);
+    let mut cache = cache.write();
+    if cache.len() >= MAX_CACHED_FILES {
+        cache.clear();
+    }
+    cache.insert(path.to_path_buf(), Sample { modified, len: metadata.len(), entries: Arc::clone(&entries) });
+    Some(entries)
+}
+
+/// Words from the start of a text file, most frequent first
+fn read_words(path: &Path, _len: u64) -> Option<Vec<(String, usize)>> {
+    let mut bytes = Vec::new();
+    File::open(path).ok()?.take(MAX_READ_BYTES).read_to_end(&mut bytes).ok()?;
+    // Binary files have no words worth searching for
+    if bytes.contains(&0) {
+        return None;
+    }
+
+    let text = String::from_utf8_lossy(&bytes);
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for word in text.split(|c: char| !(c.is_alphanumeric() || c == '_')) {
+        if word.chars().count() >= MIN_WORD_LEN && !word.chars().all(|c| c.is_ascii_digit()) {
+            *counts.entry(word).or_insert(0) += 1;
+        }
+    }
+
+    let mut words: Vec<(String, usize)> = counts.into_iter().map(|(word, count)| (word.to_string(), count)).collect();
+    words.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    words.truncate(MAX_ENTRIES);
+    Some(words)
+}
+
+/// The key paths of a JSON file, as `jq` writes them: `.name`, `.items[].id`
+fn read_keys(path: &Path, len: u64) -> Option<Vec<(String, &'static str)>> {
+    if len > MAX_JSON_BYTES {
+        return None;
+    }
+    let value: Value = serde_json::from_slice(&fs::read(path).ok()?).ok()?;
+    let mut keys = Vec::new();
+    collect_keys(&value, String::new(), 0, &mut keys, &mut HashSet::new());
+    Some(keys)
+}
+
+/// Add the paths below `value`, at `path`, that aren't in `seen` yet
+fn collect_keys(value: &Value, path: String, depth: usize, keys: &mut Vec<(String, &'static str)>, seen: &mut HashSet<String>) {
+    if depth >= MAX_KEY_DEPTH || keys.len() >= MAX_ENTRIES {
+        return;
+    }
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map {
+                let is_identifier = key.chars().next().map_or(false, |c| c.is_alphabetic() || c == '_')
+                    && key.chars().all(|c| c.is_alphanumeric() || c == '_');
+                let child_path = if is_identifier { format!("{}.{}", path, key) } else { format!("{}.\"{}\"", path, key) };
+                if seen.insert(child_path.clone()) {
+                    keys.push((child_path.clone(), type_name(child)));
+                }
+                collect_keys(child, child_path, depth + 1, keys, seen);
+            }
+        }
+        Value::Array(items) => {
+            // The first few elements stand for the rest
+            let child_path = format!("{}[]", if path.is_empty() { "." } else { &path });
+            for item in items.iter().take(20) {
+                collect_keys(item, child_path.clone(), depth + 1, keys, seen);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
@@ -0,0 +1,450 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::{Component, Path, PathBuf};
+
+use crate::plugin::api::CompletionProvider;
+use crate::suggestion::fuzzy::fast_fuzzy_score;
+use crate::suggestion::{Suggestion, SuggestionKind};
+
+/// Working tree state of a single file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+    Modified,
+    Untracked,
+    Deleted,
+    Conflicted,
+    /// Changed in the index relative to HEAD
+    Staged,
+}
+
+impl FileStatus {
+    /// Short `git status --short` style badge
+    pub fn badge(&self) -> &'static str {
+        match self {
+            FileStatus::Modified => "M",
+            FileStatus::Untracked => "??",
+            FileStatus::Deleted => "D",
+            FileStatus::Conflicted => "U",
+            FileStatus::Staged => "A",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FileStatus::Modified => "modified",
+            FileStatus::Untracked => "untracked",
+            FileStatus::Deleted => "deleted",
+            FileStatus::Conflicted => "conflicted",
+            FileStatus::Staged => "staged",
+        }
+    }
+}
+
+/// A file that differs between HEAD, the index, and the working tree
+///
+/// A file with both staged and unstaged changes appears once for each.
+#[derive(Debug, Clone)]
+pub struct StatusEntry {
+    /// Path relative to the repository root
+    pub path: String,
+    pub status: FileStatus,
+}
+
+/// Stop counting ahead/behind commits past this many; the exact number doesn't matter
+const MAX_COUNTED_COMMITS: usize = 1000;
+
+/// Which family of references to list
+enum RefKind {
+    Local,
+    Remote,
+    Tag,
+}
+
+/// In-process view of a git repository, so completion never shells out to `git`
+pub struct GitRepository {
+    repo: gix::Repository,
+}
+
+impl GitRepository {
+    /// Find the repository containing `dir`, if any
+    pub fn discover(dir: &Path) -> Option<Self> {
+        gix::discover(dir).ok().map(|repo| Self { repo })
+    }
+
+    /// Root of the working tree (`None` for bare repositories)
+    pub fn work_dir(&self) -> Option<&Path> {
+        self.repo.work_dir()
+    }
+
+    /// Short name of the checked-out branch (`None` when HEAD is detached)
+    pub fn current_branch(&self) -> Option<String> {
+        self.repo.head_name().ok().flatten().map(|name| name.shorten().to_string())
+    }
+
+    pub fn local_branches(&self) -> Vec<String> {
+        self.reference_names(RefKind::Local)
+    }
+
+    /// Remote-tracking branches as `remote/branch`, without the symbolic `remote/HEAD`
+    pub fn remote_branches(&self) -> Vec<String> {
+        let mut branches = self.reference_names(RefKind::Remote);
+        branches.retain(|branch| !branch.ends_with("/HEAD"));
+        branches
+    }
+
+    pub fn tags(&self) -> Vec<String> {
+        self.reference_names(RefKind::Tag)
+    }
+
+    pub fn remotes(&self) -> Vec<String> {
+        self.repo.remote_names().into_iter().map(|name| name.to_string()).collect()
+    }
+
+    /// Configured upstream of a local branch as `remote/branch`, if any
+    pub fn upstream(&self, branch: &str) -> Option<String> {
+        let config = self.repo.config_snapshot();
+        let remote = config.string(format!("branch.{}.remote", branch).as_str())?.to_string();
+        let merge = config.string(format!("branch.{}.merge", branch).as_str())?.to_string();
+        let merge = merge.strip_prefix("refs/heads/").unwrap_or(&merge);
+
+        Some(format!("{}/{}", remote, merge))
+    }
+
+    /// Commits the local branch is (ahead, behind) its upstream, capped at `MAX_COUNTED_COMMITS`
+    pub fn ahead_behind(&self, branch: &str, upstream: &str) -> Option<(usize, usize)> {
+        let local = self.repo.rev_parse_single(format!("refs/heads/{}", branch).as_str()).ok()?.detach();
+        let upstream = self.repo.rev_parse_single(format!("refs/remotes/{}", upstream).as_str()).ok()?.detach();
+
+        Some((
+            self.count_exclusive(local, upstream)?,
+            self.count_exclusive(upstream, local)?,
+        ))
+    }
+
+    /// Stash entries as (`stash@{n}`, message), newest first
+    pub fn stashes(&self) -> Vec<(String, String)> {
+        let reference = match self.repo.find_reference("refs/stash") {
+            Ok(reference) => reference,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut log = reference.log_iter();
+        let lines = match log.rev() {
+            Ok(Some(lines)) => lines,
+            _ => return Vec::new(),
+        };
+
+        lines
+            .flatten()
+            .enumerate()
+            .map(|(index, line)| (format!("stash@{{{}}}", index), line.message.to_string()))
+            .collect()
+    }
+
+    /// Changed files: staged changes, working tree changes, and untracked files
+    pub fn status(&self) -> Vec<StatusEntry> {
+        use gix::diff::index::Change;
+        use gix::status::index_worktree::iter::Summary;
+        use gix::status::Item;
+
+        let platform = match self.repo.status(gix::progress::Discard) {
+            Ok(platform) => platform,
+            Err(_) => return Vec::new(),
+        };
+
+        let items = match platform
+            .untracked_files(gix::status::UntrackedFiles::Files)
+            .into_iter(Vec::<gix::bstr::BString>::new())
+        {
+            Ok(items) => items,
+            Err(_) => return Vec::new(),
+        };
+
+        items
+            .flatten()
+            .filter_map(|item| match item {
+                Item::IndexWorktree(item) => {
+                    let status = match item.summary()? {
+                        Summary::Added => FileStatus::Untracked,
+                        Summary::Removed => FileStatus::Deleted,
+                        Summary::Conflict => FileStatus::Conflicted,
+                        _ => FileStatus::Modified,
+                    };
+
+                    Some(StatusEntry {
+                        path: item.rela_path().to_string(),
+                        status,
+                    })
+                }
+                Item::TreeIndex(change) => {
+                    let path = match &change {
+                        Change::Addition { location, .. }
+                        | Change::Deletion { location, .. }
+                        | Change::Modification { location, .. }
+                        | Change::Rewrite { location, .. } => location.to_string(),
+                    };
+
+                    Some(StatusEntry {
+                        path,
+                        status: FileStatus::Staged,
+                    })
+                }
+            })
+            .collect()
+    }
+
+    /// When each entry of `dir` (relative to the work tree) was last committed, as Unix
+    /// seconds, going back along first parents at most `max_commits`; entries unchanged
+    /// for longer than that are missing
+    pub fn last_commits(&self, dir: &Path, max_commits: usize) -> HashMap<String, u64> {
+        let mut committed = HashMap::new();
+        let Ok(mut commit) = self.repo.head_commit() else { return committed };
+        // The entries of the commit after this one, and its time
+        let mut newer: Option<(HashMap<String, gix::ObjectId>, u64)> = None;
+
+        for _ in 0..max_commits {
+            let entries = Self::tree_entries(&commit, dir);
+            let time = commit.time().map(|time| time.seconds.max(0) as u64).unwrap_or(0);
+            if let Some((newer_entries, newer_time)) = &newer {
+                for (name, id) in newer_entries {
+                    if entries.get(name) != Some(id) {
+                        committed.entry(name.clone()).or_insert(*newer_time);
+                    }
+                }
+            }
+
+            let parent = commit.parent_ids().next().and_then(|id| id.object().ok()?.try_into_commit().ok());
+            match parent {
+                Some(parent) => {
+                    newer = Some((entries, time));
+                    commit = parent;
+                }
+                None => {
+                    // What the root commit has, it added
+                    for name in entries.into_keys() {
+                        committed.entry(name).or_insert(time);
+                    }
+                    break;
+                }
+            }
+        }
+
+        committed
+    }
+
+    /// Names and object ids of the entries of `dir` in a commit's tree
+    fn tree_entries(commit: &gix::Commit<'_>, dir: &Path) -> HashMap<String, gix::ObjectId> {
+        let Ok(mut tree) = commit.tree() else { return HashMap::new() };
+        if !dir.as_os_str().is_empty() {
+            let subtree = tree.lookup_entry_by_path(dir).ok().flatten().and_then(|entry| entry.object().ok()?.try_into_tree().ok());
+            match subtree {
+                Some(subtree) => tree = subtree,
+                None => return HashMap::new(),
+            }
+        }
+
+        tree.iter()
+            .flatten()
+            .map(|entry| (entry.filename().to_string(), entry.oid().to_owned()))
+            .collect()
+    }
+
+    /// Commits reachable from `tip` but not from `hidden`
+    fn count_exclusive(&self, tip: gix::ObjectId, hidden: gix::ObjectId) -> Option<usize> {
+        let walk = self.repo.rev_walk([tip]).with_hidden([hidden]).all().ok()?;
+        Some(walk.take(MAX_COUNTED_COMMITS).count())
+    }
+
+    fn reference_names(&self, kind: RefKind) -> Vec<String> {
+        let platform = match self.repo.references() {
+            Ok(platform) => platform,
+            Err(_) => return Vec::new(),
+        };
+
+        let references = match kind {
+            RefKind::Local => platform.local_branches(),
+            RefKind::Remote => platform.remote_branches(),
+            RefKind::Tag => platform.tags(),
+        };
+
+        let mut names: Vec<String> = match references {
+            Ok(references) => references
+                .flatten()
+                .map(|reference| reference.name().shorten().to_string())
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+/// Completes branches, tags, remotes, and stash refs for git subcommands
+pub struct GitCompleter;
+
+impl GitCompleter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Candidate (name, description) pairs for the argument being typed
+    fn candidates(repo: &GitRepository, subcommand: &str, positionals: &[&str]) -> Vec<(String, &'static str)> {
+        let current = repo.current_branch();
+        let branches = || {
+            repo.local_branches()
+                .into_iter()
+                .filter(|branch| Some(branch) != current.as_ref())
+                .map(|branch| (branch, "branch"))
+                .collect::<Vec<_>>()
+        };
+        let remote_branches = || {
+            repo.remote_branches().into_iter().map(|branch| (branch, "remote branch")).collect::<Vec<_>>()
+        };
+        let tags = || repo.tags().into_iter().map(|tag| (tag, "tag")).collect::<Vec<_>>();
+
+        match subcommand {
+            "checkout" | "merge" | "rebase" => {
+                let mut candidates = branches();
+                candidates.extend(remote_branches());
+                candidates.extend(tags());
+                candidates
+            }
+            "switch" => {
+                // `git switch foo` creates a tracking branch for `origin/foo`, so offer the short name
+                let mut candidates = branches();
+                let local = repo.local_branches();
+                for remote_branch in repo.remote_branches() {
+                    if let Some((_, name)) = remote_branch.split_once('/') {
+                        if !local.iter().any(|branch| branch == name) {
+                            candidates.push((name.to_string(), "remote branch"));
+                        }
+                    }
+                }
+                candidates
+            }
+            // `git push <remote> <branch>`
+            "push" => match positionals.len() {
+                0 => repo.remotes().into_iter().map(|remote| (remote, "remote")).collect(),
+                _ => repo.local_branches().into_iter().map(|branch| (branch, "branch")).collect(),
+            },
+            "stash" => match positionals.first() {
+                Some(&"apply") | Some(&"pop") | Some(&"drop") | Some(&"show") | Some(&"branch") => repo
+                    .stashes()
+                    .into_iter()
+                    .map(|(name, _)| (name, "stash"))
+                    .collect(),
+                _ => Vec::new(),
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    /// Changed files matching the wanted statuses, as paths relative to `cwd` with status badges
+    fn status_suggestions(repo: &GitRepository, cwd: &Path, wanted: &[FileStatus], current_arg: &str) -> Vec<Suggestion> {
+        let work_dir = match repo.work_dir() {
+            Some(work_dir) => work_dir.to_path_buf(),
+            None => return Vec::new(),
+        };
+
+        repo.status()
+            .into_iter()
+            .filter(|entry| wanted.contains(&entry.status))
+            .filter_map(|entry| {
+                let path = relative_to(&work_dir.join(&entry.path), cwd);
+                let path = path.to_string_lossy().to_string();
+                let score = fast_fuzzy_score(current_arg, &path)?;
+
+                Some(
+                    Suggestion::new(path.clone(), path, SuggestionKind::File)
+                        .with_description(format!("{} {}", entry.status.badge(), entry.status.label()))
+                        .with_score(score * 100.0)
+                )
+            })
+            .collect()
+    }
+}
+
+impl CompletionProvider for GitCompleter {
+    fn name(&self) -> &str {
+        "git"
+    }
+
+    fn can_provide_completions(&self, command: &str) -> bool {
+        command == "git"
+    }
+
+    fn provide_completions(&self, _command: &str, args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        if current_arg.starts_with('-') {
+            return Vec::new();
+        }
+
+        // Skip global options like `-C dir` to find the subcommand
+        let mut words = args.iter().filter(|arg| !arg.starts_with('-'));
+        let subcommand = match words.next() {
+            Some(subcommand) => *subcommand,
+            None => return Vec::new(),
+        };
+        let positionals: Vec<&str> = words.copied().collect();
+
+        let cwd = match env::current_dir() {
+            Ok(cwd) => cwd,
+            Err(_) => return Vec::new(),
+        };
+        let repo = match GitRepository::discover(&cwd) {
+            Some(repo) => repo,
+            None => return Vec::new(),
+        };
+
+        let mut suggestions = match file_filter(subcommand, args) {
+            Some(wanted) => Self::status_suggestions(&repo, &cwd, wanted, current_arg),
+            None => Self::candidates(&repo, subcommand, &positionals)
+                .into_iter()
+                .filter_map(|(name, description)| {
+                    let score = fast_fuzzy_score(current_arg, &name)?;
+                    Some(
+                        Suggestion::new(name.clone(), name, SuggestionKind::GitRef)
+                            .with_description(description.to_string())
+                            .with_score(score * 100.0)
+                    )
+                })
+                .collect(),
+        };
+
+        suggestions.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        suggestions
+    }
+}
+
+/// Statuses worth completing when a subcommand takes changed files, or `None` if it takes refs
+fn file_filter(subcommand: &str, args: &[&str]) -> Option<&'static [FileStatus]> {
+    let staged = args.iter().any(|arg| matches!(*arg, "--staged" | "--cached" | "-S"));
+    let unstaged: &'static [FileStatus] = &[FileStatus::Modified, FileStatus::Deleted, FileStatus::Conflicted];
+
+    match subcommand {
+        "add" => Some(&[FileStatus::Modified, FileStatus::Untracked, FileStatus::Deleted, FileStatus::Conflicted]),
+        "restore" | "diff" if staged => Some(&[FileStatus::Staged]),
+        "restore" | "diff" => Some(unstaged),
+        // `git checkout -- <file>` discards working tree changes
+        "checkout" if args.contains(&"--") => Some(unstaged),
+        _ => None,
+    }
+}
+
+/// Express `path` relative to `base`, walking up with `..` where needed
+fn relative_to(path: &Path, base: &Path) -> PathBuf {
+    let path: Vec<Component> = path.components().collect();
+    let base: Vec<Component> = base.components().collect();
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..base.len() {
+        relative.push("..");
+    }
+    for component in &path[common..] {
+        relative.push(component.as_os_str());
+    }
+
+    relative
+}
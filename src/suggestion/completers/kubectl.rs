@@ -0,0 +1,383 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use parking_lot::RwLock;
+use serde_yaml::Value;
+
+use crate::config::Config;
+use crate::plugin::api::CompletionProvider;
+use crate::suggestion::spec::{flag_value, generated_suggestions, ArgSpec, CommandSpec, Generator, GeneratorRegistry, OptionSpec};
+use crate::suggestion::Suggestion;
+use crate::utils::offline;
+use crate::utils::process::run_with_timeout;
+
+/// How long live resource names are reused
+const QUERY_CACHE_LIFETIME: Duration = Duration::from_secs(15);
+
+/// Options whose following word is a value, not a positional
+const VALUE_FLAGS: &[&str] = &["-n", "--namespace", "--context", "-l", "--selector", "-o", "--output", "-c", "--container"];
+
+/// Resource types offered for `kubectl get <type>`: plural, singular and short name
+const RESOURCE_TYPES: &[(&str, &str, &str)] = &[
+    ("pods", "pod", "po"),
+    ("deployments", "deployment", "deploy"),
+    ("services", "service", "svc"),
+    ("statefulsets", "statefulset", "sts"),
+    ("daemonsets", "daemonset", "ds"),
+    ("replicasets", "replicaset", "rs"),
+    ("jobs", "job", ""),
+    ("cronjobs", "cronjob", "cj"),
+    ("configmaps", "configmap", "cm"),
+    ("secrets", "secret", ""),
+    ("ingresses", "ingress", "ing"),
+    ("namespaces", "namespace", "ns"),
+    ("nodes", "node", "no"),
+    ("persistentvolumeclaims", "persistentvolumeclaim", "pvc"),
+    ("persistentvolumes", "persistentvolume", "pv"),
+    ("serviceaccounts", "serviceaccount", "sa"),
+    ("events", "event", "ev"),
+];
+
+/// A context entry from kubeconfig
+#[derive(Debug, Clone)]
+pub struct KubeContext {
+    pub name: String,
+    pub cluster: Option<String>,
+    pub namespace: Option<String>,
+}
+
+/// Contexts read from the kubeconfig files kubectl would use
+#[derive(Debug, Clone, Default)]
+pub struct KubeConfig {
+    pub current_context: Option<String>,
+    pub contexts: Vec<KubeContext>,
+}
+
+impl KubeConfig {
+    /// Load and merge every file in `$KUBECONFIG`, or `~/.kube/config`
+    pub fn load() -> Self {
+        let mut config = KubeConfig::default();
+
+        for path in Self::paths() {
+            let content = match fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(_) => continue,
+            };
+            let document: Value = match serde_yaml::from_str(&content) {
+                Ok(document) => document,
+                Err(_) => continue,
+            };
+
+            // Like kubectl, the first file that sets current-context wins
+            if config.current_context.is_none() {
+                config.current_context = document["current-context"].as_str().map(|s| s.to_string());
+            }
+
+            for entry in document["contexts"].as_sequence().into_iter().flatten() {
+                let name = match entry["name"].as_str() {
+                    Some(name) => name.to_string(),
+                    None => continue,
+                };
+
+                if config.contexts.iter().any(|c| c.name == name) {
+                    continue;
+                }
+
+                config.contexts.push(KubeContext {
+                    name,
+                    cluster: entry["context"]["cluster"].as_str().map(|s| s.to_string()),
+                    namespace: entry["context"]["namespace"].as_str().map(|s| s.to_string()),
+                });
+            }
+        }
+
+        config
+    }
+
+    fn paths() -> Vec<PathBuf> {
+        match env::var_os("KUBECONFIG") {
+            Some(paths) if !paths.is_empty() => env::split_paths(&paths).collect(),
+            _ => dirs::home_dir()
+                .map(|home| vec![home.join(".kube").join("config")])
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Opt-in, cached `kubectl get <kind> -o name` lookups against the API server
+struct ClusterQuery {
+    enabled: bool,
+    timeout: Duration,
+    cache: RwLock<HashMap<(String, Option<String>, Option<String>), (Instant, Arc<Vec<String>>)>>,
+}
+
+impl ClusterQuery {
+    /// Names of a resource kind, honoring `--context` and `--namespace` from the command line
+    fn names(&self, kind: &str, context: Option<&str>, namespace: Option<&str>) -> Arc<Vec<String>> {
+        // The cluster may be remote; offline, only kubeconfig is used
+        if !self.enabled || offline::is_offline() {
+            return Arc::new(Vec::new());
+        }
+
+        let key = (kind.to_string(), context.map(|s| s.to_string()), namespace.map(|s| s.to_string()));
+
+        if let Some((queried_at, names)) = self.cache.read().get(&key) {
+            if queried_at.elapsed() < QUERY_CACHE_LIFETIME {
+                return Arc::clone(names);
+            }
+        }
+
+        let names = Arc::new(self.query(kind, context, namespace).unwrap_or_default());
+        self.cache.write().insert(key, (Instant::now(), Arc::clone(&names)));
+        names
+    }
+
+    fn query(&self, kind: &str, context: Option<&str>, namespace: Option<&str>) -> Option<Vec<String>> {
+        let mut command = Command::new("kubectl");
+        command
+            .arg("get")
+            .arg(kind)
+            .arg("-o")
+            .arg("name")
+            .arg(format!("--request-timeout={}ms", self.timeout.as_millis()));
+
+        if let Some(context) = context {
+            command.arg("--context").arg(context);
+        }
+        if let Some(namespace) = namespace {
+            command.arg("--namespace").arg(namespace);
+        }
+
+        let output = run_with_timeout(&mut command, self.timeout + Duration::from_millis(200), 1024 * 1024)?;
+        if !output.status.success() {
+            return None;
+        }
+
+        // `-o name` prints `pod/web-7d9f`; keep the part after the slash
+        let names = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(|line| line.rsplit('/').next().unwrap_or(line).to_string())
+            .filter(|name| !name.is_empty())
+            .collect();
+
+        Some(names)
+    }
+}
+
+/// Completes kubeconfig context names
+struct ContextsGenerator;
+
+impl Generator for ContextsGenerator {
+    fn generate(&self, _args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        let config = KubeConfig::load();
+
+        let candidates = config.contexts.iter().map(|context| {
+            let mut description = context.cluster.clone().unwrap_or_else(|| "context".to_string());
+            if config.current_context.as_deref() == Some(context.name.as_str()) {
+                description.push_str(" (current)");
+            }
+            (context.name.clone(), description)
+        });
+
+        generated_suggestions(candidates, current_arg)
+    }
+}
+
+/// Completes namespaces from kubeconfig plus, when enabled, the cluster itself
+struct NamespacesGenerator {
+    cluster: Arc<ClusterQuery>,
+}
+
+impl Generator for NamespacesGenerator {
+    fn generate(&self, args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        let mut namespaces: Vec<(String, String)> = KubeConfig::load()
+            .contexts
+            .into_iter()
+            .filter_map(|context| context.namespace.map(|ns| (ns, format!("namespace of {}", context.name))))
+            .collect();
+        namespaces.sort();
+        namespaces.dedup_by(|a, b| a.0 == b.0);
+
+        let context = flag_value(args, &["--context"]);
+        for name in self.cluster.names("namespaces", context, None).iter() {
+            if !namespaces.iter().any(|(ns, _)| ns == name) {
+                namespaces.push((name.clone(), "namespace".to_string()));
+            }
+        }
+
+        generated_suggestions(namespaces.into_iter(), current_arg)
+    }
+}
+
+/// Completes resource types for `get`, `describe`, `delete`, ...
+struct ResourceTypesGenerator;
+
+impl Generator for ResourceTypesGenerator {
+    fn generate(&self, _args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        let candidates = RESOURCE_TYPES.iter().map(|(name, _, short)| {
+            let description = if short.is_empty() {
+                "resource type".to_string()
+            } else {
+                format!("resource type ({})", short)
+            };
+            (name.to_string(), description)
+        });
+
+        generated_suggestions(candidates, current_arg)
+    }
+}
+
+/// Completes live names of a resource kind; the kind is either fixed or read from the command line
+struct ResourcesGenerator {
+    cluster: Arc<ClusterQuery>,
+    kind: Option<&'static str>,
+}
+
+impl Generator for ResourcesGenerator {
+    fn generate(&self, args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        // `kubectl get <type> <name>`: the type is the positional before the names
+        let words = positional_words(args);
+        let kind = match self.kind.or_else(|| resource_kind(&words)) {
+            Some(kind) => kind,
+            None => return Vec::new(),
+        };
+
+        let context = flag_value(args, &["--context"]);
+        let namespace = flag_value(args, &["-n", "--namespace"]);
+        let names = self.cluster.names(kind, context, namespace);
+
+        let description = RESOURCE_TYPES
+            .iter()
+            .find(|(name, _, _)| *name == kind)
+            .map_or(kind, |(_, singular, _)| *singular)
+            .to_string();
+        generated_suggestions(names.iter().map(|name| (name.clone(), description.clone())), current_arg)
+    }
+}
+
+/// Spec-driven kubectl completion with kubeconfig and (opt-in) live cluster generators
+pub struct KubectlCompleter {
+    spec: CommandSpec,
+    generators: GeneratorRegistry,
+}
+
+impl KubectlCompleter {
+    pub fn new(config: &Config) -> Self {
+        let cluster = Arc::new(ClusterQuery {
+            enabled: config.suggestions.enable_cluster_queries.unwrap_or(false),
+            timeout: Duration::from_millis(config.suggestions.cluster_query_timeout_ms.unwrap_or(800)),
+            cache: RwLock::new(HashMap::new()),
+        });
+
+        let mut generators = GeneratorRegistry::new();
+        generators.register("kubectl:contexts", Arc::new(ContextsGenerator));
+        generators.register("kubectl:namespaces", Arc::new(NamespacesGenerator { cluster: Arc::clone(&cluster) }));
+        generators.register("kubectl:resource-types", Arc::new(ResourceTypesGenerator));
+        generators.register("kubectl:resources", Arc::new(ResourcesGenerator { cluster: Arc::clone(&cluster), kind: None }));
+        generators.register("kubectl:pods", Arc::new(ResourcesGenerator { cluster, kind: Some("pods") }));
+
+        Self {
+            spec: kubectl_spec(),
+            generators,
+        }
+    }
+}
+
+impl CompletionProvider for KubectlCompleter {
+    fn name(&self) -> &str {
+        "kubectl"
+    }
+
+    fn can_provide_completions(&self, command: &str) -> bool {
+        command == "kubectl"
+    }
+
+    fn provide_completions(&self, _command: &str, args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        self.spec.suggestions_with(args, current_arg, &self.generators)
+    }
+}
+
+/// Built-in spec for the kubectl subcommands whose arguments we can complete
+pub fn kubectl_spec() -> CommandSpec {
+    let global_options = vec![
+        OptionSpec::generated(&["--context"], "CONTEXT", "Kubeconfig context to use", "kubectl:contexts"),
+        OptionSpec::generated(&["-n", "--namespace"], "NAMESPACE", "Namespace scope for this request", "kubectl:namespaces"),
+    ];
+
+    let typed_resource = || vec![
+        ArgSpec::generated("type", "kubectl:resource-types", false),
+        ArgSpec::generated("name", "kubectl:resources", true),
+    ];
+    let pod = || vec![ArgSpec::generated("pod", "kubectl:pods", false)];
+    let context = || vec![ArgSpec::generated("context", "kubectl:contexts", false)];
+
+    let mut config = CommandSpec::with_args("config", "Modify kubeconfig files", Vec::new());
+    config.subcommands = vec![
+        CommandSpec::with_args("use-context", "Set the current context", context()),
+        CommandSpec::with_args("set-context", "Set a context entry", context()),
+        CommandSpec::with_args("delete-context", "Delete a context", context()),
+        CommandSpec::with_args("rename-context", "Rename a context", context()),
+        CommandSpec::with_args("get-contexts", "Describe one or many contexts", context()),
+        CommandSpec::with_args("current-context", "Display the current context", Vec::new()),
+    ];
+
+    let mut rollout = CommandSpec::with_args("rollout", "Manage the rollout of a resource", Vec::new());
+    rollout.subcommands = ["status", "restart", "undo", "history", "pause", "resume"]
+        .iter()
+        .map(|name| CommandSpec::with_args(name, "", typed_resource()))
+        .collect();
+
+    let mut spec = CommandSpec::new("kubectl");
+    spec.subcommands = vec![
+        CommandSpec::with_args("get", "Display one or many resources", typed_resource()),
+        CommandSpec::with_args("describe", "Show details of a resource", typed_resource()),
+        CommandSpec::with_args("delete", "Delete resources", typed_resource()),
+        CommandSpec::with_args("edit", "Edit a resource on the server", typed_resource()),
+        CommandSpec::with_args("scale", "Set a new size for a workload", typed_resource()),
+        CommandSpec::with_args("logs", "Print the logs for a container in a pod", pod()),
+        CommandSpec::with_args("exec", "Execute a command in a container", pod()),
+        CommandSpec::with_args("attach", "Attach to a running container", pod()),
+        CommandSpec::with_args("port-forward", "Forward local ports to a pod", pod()),
+        rollout,
+        config,
+    ];
+
+    // kubectl accepts global options anywhere on the line
+    spec.options = global_options.clone();
+    spec.propagate_options(&global_options);
+
+    spec
+}
+
+/// Non-flag words, skipping the values of flags that take one
+fn positional_words<'a>(args: &[&'a str]) -> Vec<&'a str> {
+    let mut words = Vec::new();
+    let mut skip_next = false;
+
+    for arg in args {
+        if skip_next {
+            skip_next = false;
+        } else if arg.starts_with('-') {
+            skip_next = VALUE_FLAGS.contains(arg);
+        } else {
+            words.push(*arg);
+        }
+    }
+
+    words
+}
+
+/// The resource type named by the first word that is one (`pods`, `pod`, `po`), as its plural
+fn resource_kind(words: &[&str]) -> Option<&'static str> {
+    words.iter().skip(1).find_map(|&word| {
+        RESOURCE_TYPES
+            .iter()
+            .find(|(name, singular, short)| word == *name || word == *singular || (!short.is_empty() && word == *short))
+            .map(|(name, _, _)| *name)
+    })
+}
+
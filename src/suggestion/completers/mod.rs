@@ -0,0 +1,26 @@
+// Built-in completion providers for specific tools
+#[cfg(feature = "git-integration")]
+pub mod git;
+
+#[cfg(feature = "git-integration")]
+pub use self::git::{GitCompleter, GitRepository};
+
+pub mod kubectl;
+pub mod cloud;
+pub mod cargo;
+pub mod node;
+pub mod task_runners;
+pub mod systemd;
+pub mod packages;
+pub mod processes;
+pub mod file_content;
+
+pub use self::kubectl::KubectlCompleter;
+pub use self::cloud::CloudCompleter;
+pub use self::cargo::{CargoCompleter, CargoProject};
+pub use self::node::{NodeScriptsCompleter, PackageJson};
+pub use self::task_runners::{TaskRunner, TaskRunnerCompleter};
+pub use self::systemd::SystemdCompleter;
+pub use self::packages::PackageCompleter;
+pub use self::processes::ProcessCompleter;
+pub use self::file_content::FileContentCompleter;
@@ -0,0 +1,121 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use once_cell::sync::Lazy;
+use serde_json::Value;
+
+use crate::plugin::api::CompletionProvider;
+use crate::suggestion::project::{find_upwards, ManifestCache};
+use crate::suggestion::spec::generated_suggestions;
+use crate::suggestion::Suggestion;
+
+/// Parsed package.json files keyed by path
+static PACKAGE_JSONS: Lazy<ManifestCache<PackageJson>> = Lazy::new(ManifestCache::new);
+
+/// yarn and pnpm run scripts without `run`, but these built-ins take precedence
+const YARN_BUILTINS: &[&str] = &["add", "remove", "install", "upgrade", "why", "info", "init", "dlx", "exec", "workspace", "workspaces"];
+
+/// The parts of package.json relevant to completion
+#[derive(Debug, Clone, Default)]
+pub struct PackageJson {
+    pub dir: PathBuf,
+    pub name: Option<String>,
+    /// Script names with their command bodies, in file order
+    pub scripts: Vec<(String, String)>,
+}
+
+impl PackageJson {
+    /// Load the nearest package.json at or above `dir`
+    pub fn load(dir: &Path) -> Option<Arc<PackageJson>> {
+        let path = find_upwards(dir, "package.json")?;
+
+        PACKAGE_JSONS.get(&path, |path, content| {
+            let document: Value = serde_json::from_str(content).ok()?;
+
+            let scripts = document["scripts"]
+                .as_object()
+                .map(|scripts| {
+                    scripts.iter()
+                        .filter_map(|(name, body)| Some((name.clone(), body.as_str()?.to_string())))
+                        .collect()
+                })
+                .unwrap_or_default();
+
+            Some(PackageJson {
+                dir: path.parent()?.to_path_buf(),
+                name: document["name"].as_str().map(|s| s.to_string()),
+                scripts,
+            })
+        })
+    }
+
+    pub fn has_script(&self, name: &str) -> bool {
+        self.scripts.iter().any(|(script, _)| script == name)
+    }
+
+    /// Package manager the project uses, judged by its lockfile
+    pub fn package_manager(&self) -> &'static str {
+        if self.dir.join("pnpm-lock.yaml").exists() {
+            "pnpm"
+        } else if self.dir.join("yarn.lock").exists() {
+            "yarn"
+        } else if self.dir.join("bun.lockb").exists() {
+            "bun"
+        } else {
+            "npm"
+        }
+    }
+}
+
+/// Completes package.json scripts for npm, yarn, pnpm, and bun
+pub struct NodeScriptsCompleter;
+
+impl NodeScriptsCompleter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Whether the argument being typed is a script name
+    fn expects_script(command: &str, args: &[&str]) -> bool {
+        let words: Vec<&str> = args.iter().copied().filter(|arg| !arg.starts_with('-')).collect();
+
+        match (command, words.as_slice()) {
+            (_, ["run"]) | ("npm", ["run-script"]) => true,
+            // `yarn build` / `pnpm build` run scripts directly
+            ("yarn" | "pnpm" | "bun", []) => true,
+            _ => false,
+        }
+    }
+}
+
+impl CompletionProvider for NodeScriptsCompleter {
+    fn name(&self) -> &str {
+        "node-scripts"
+    }
+
+    fn can_provide_completions(&self, command: &str) -> bool {
+        matches!(command, "npm" | "yarn" | "pnpm" | "bun")
+    }
+
+    fn provide_completions(&self, command: &str, args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        if current_arg.starts_with('-') || !Self::expects_script(command, args) {
+            return Vec::new();
+        }
+
+        let cwd = match env::current_dir() {
+            Ok(cwd) => cwd,
+            Err(_) => return Vec::new(),
+        };
+        let package = match PackageJson::load(&cwd) {
+            Some(package) => package,
+            None => return Vec::new(),
+        };
+
+        let bare = args.iter().all(|arg| arg.starts_with('-'));
+        let scripts = package.scripts.iter()
+            .filter(|(name, _)| !(bare && YARN_BUILTINS.contains(&name.as_str())))
+            .map(|(name, body)| (name.clone(), body.clone()));
+
+        generated_suggestions(scripts, current_arg)
+    }
+}
@@ -0,0 +1,333 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use once_cell::sync::Lazy;
+use parking_lot::{Mutex, RwLock};
+
+use crate::config::Config;
+use crate::plugin::api::CompletionProvider;
+use crate::suggestion::project::ManifestCache;
+use crate::suggestion::spec::generated_suggestions;
+use crate::suggestion::Suggestion;
+use crate::utils::process::run_with_timeout;
+
+/// Name of the index dump directory inside the user data directory
+pub const PACKAGE_INDEX_DIR: &str = "package-index";
+
+/// Index dumps older than this are refreshed in the background
+const INDEX_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Deadline for a background index dump
+const DUMP_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How long installed-package listings that aren't a single file are reused
+const INSTALLED_CACHE_LIFETIME: Duration = Duration::from_secs(60);
+
+/// Available packages are only offered once this much of the name is typed
+const MIN_AVAILABLE_PREFIX: usize = 1;
+
+/// Parsed dpkg status files and index dumps, reloaded when they change on disk
+static PACKAGE_LISTS: Lazy<ManifestCache<Vec<String>>> = Lazy::new(ManifestCache::new);
+
+/// A system package manager
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PackageManager {
+    Apt,
+    Brew,
+    Dnf,
+    Pacman,
+}
+
+/// Which package set the argument being typed draws from
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PackageSet {
+    Installed,
+    Available,
+}
+
+impl PackageManager {
+    pub fn from_command(command: &str) -> Option<Self> {
+        match command {
+            "apt" | "apt-get" | "apt-cache" => Some(PackageManager::Apt),
+            "brew" => Some(PackageManager::Brew),
+            "dnf" | "yum" => Some(PackageManager::Dnf),
+            "pacman" | "yay" | "paru" => Some(PackageManager::Pacman),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            PackageManager::Apt => "apt",
+            PackageManager::Brew => "brew",
+            PackageManager::Dnf => "dnf",
+            PackageManager::Pacman => "pacman",
+        }
+    }
+
+    /// Package set expected by the command line so far, if a package name is expected at all
+    fn package_set(&self, args: &[&str]) -> Option<PackageSet> {
+        if *self == PackageManager::Pacman {
+            // pacman selects the operation with a flag: `-S`, `-Syu`, `-R`, `-Qi`
+            let operation = args.iter().find(|arg| arg.starts_with('-') && !arg.starts_with("--"))?;
+            return match operation.chars().nth(1)? {
+                'S' if !operation.contains('c') => Some(PackageSet::Available),
+                'R' | 'Q' => Some(PackageSet::Installed),
+                _ => None,
+            };
+        }
+
+        let verb = args.iter().find(|arg| !arg.starts_with('-'))?;
+        match (*self, *verb) {
+            (_, "install") | (_, "info") | (PackageManager::Apt, "show" | "policy" | "depends")
+                | (PackageManager::Brew, "home" | "deps" | "fetch") => Some(PackageSet::Available),
+            (_, "remove" | "reinstall" | "upgrade")
+                | (PackageManager::Apt, "purge" | "autoremove")
+                | (PackageManager::Brew, "uninstall" | "rm" | "pin" | "unpin" | "link" | "unlink" | "list" | "outdated")
+                | (PackageManager::Dnf, "erase" | "downgrade") => Some(PackageSet::Installed),
+            _ => None,
+        }
+    }
+
+    /// Command that prints every available package name, one per line
+    fn dump_commands(&self) -> Vec<Command> {
+        let command = |program: &str, args: &[&str]| {
+            let mut command = Command::new(program);
+            command.args(args);
+            command
+        };
+
+        match self {
+            PackageManager::Apt => vec![command("apt-cache", &["pkgnames"])],
+            PackageManager::Brew => vec![command("brew", &["formulae"]), command("brew", &["casks"])],
+            PackageManager::Dnf => vec![command("dnf", &["-q", "repoquery", "--available", "--qf", "%{name}\n"])],
+            PackageManager::Pacman => vec![command("pacman", &["-Slq"])],
+        }
+    }
+}
+
+/// Installed packages read straight from the package manager's local database
+fn installed_packages(manager: PackageManager) -> Option<Vec<String>> {
+    match manager {
+        PackageManager::Apt => {
+            let status = PACKAGE_LISTS.get(Path::new("/var/lib/dpkg/status"), |_, content| Some(parse_dpkg_status(content)))?;
+            Some(status.as_ref().clone())
+        }
+        PackageManager::Brew => {
+            let prefix = brew_prefix()?;
+            let names = ["Cellar", "Caskroom"]
+                .iter()
+                .filter_map(|dir| fs::read_dir(prefix.join(dir)).ok())
+                .flatten()
+                .flatten()
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .filter(|name| !name.starts_with('.'))
+                .collect();
+            Some(names)
+        }
+        PackageManager::Pacman => {
+            // Each installed package has a `name-version-release` directory
+            let names = fs::read_dir("/var/lib/pacman/local").ok()?
+                .flatten()
+                .filter(|entry| entry.path().is_dir())
+                .filter_map(|entry| {
+                    let dir_name = entry.file_name().to_string_lossy().into_owned();
+                    let name = dir_name.rsplitn(3, '-').nth(2)?;
+                    Some(name.to_string())
+                })
+                .collect();
+            Some(names)
+        }
+        PackageManager::Dnf => {
+            let mut command = Command::new("rpm");
+            command.args(["-qa", "--qf", "%{NAME}\n"]);
+
+            let output = run_with_timeout(&mut command, Duration::from_millis(1500), 4 * 1024 * 1024)?;
+            if !output.status.success() {
+                return None;
+            }
+
+            Some(String::from_utf8_lossy(&output.stdout).lines().map(|line| line.to_string()).collect())
+        }
+    }
+}
+
+/// Package names marked installed in dpkg's status file
+fn parse_dpkg_status(content: &str) -> Vec<String> {
+    content.split("\n\n")
+        .filter(|stanza| stanza.lines().any(|line| line.starts_with("Status:") && line.ends_with(" installed")))
+        .filter_map(|stanza| stanza.lines().find_map(|line| line.strip_prefix("Package: ")))
+        .map(|name| name.trim().to_string())
+        .collect()
+}
+
+fn brew_prefix() -> Option<PathBuf> {
+    if let Ok(prefix) = env::var("HOMEBREW_PREFIX") {
+        return Some(PathBuf::from(prefix));
+    }
+
+    ["/opt/homebrew", "/usr/local", "/home/linuxbrew/.linuxbrew"]
+        .iter()
+        .map(PathBuf::from)
+        .find(|prefix| prefix.join("Cellar").is_dir())
+}
+
+/// Dumps of available package names, refreshed in the background when stale
+struct PackageIndex {
+    dir: PathBuf,
+    /// Managers with a refresh currently running
+    refreshing: Arc<Mutex<HashSet<PackageManager>>>,
+}
+
+impl PackageIndex {
+    fn path(&self, manager: PackageManager) -> PathBuf {
+        self.dir.join(format!("{}.txt", manager.name()))
+    }
+
+    /// Available package names from the last dump, scheduling a refresh if it's missing or stale
+    fn available(&self, manager: PackageManager) -> Option<Arc<Vec<String>>> {
+        let path = self.path(manager);
+
+        let age = fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+
+        if age.map_or(true, |age| age > INDEX_MAX_AGE) {
+            self.schedule_refresh(manager);
+        }
+
+        PACKAGE_LISTS.get(&path, |_, content| Some(content.lines().map(|line| line.to_string()).collect()))
+    }
+
+    fn schedule_refresh(&self, manager: PackageManager) {
+        if !self.refreshing.lock().insert(manager) {
+            return;
+        }
+
+        let path = self.path(manager);
+        let refreshing = Arc::clone(&self.refreshing);
+
+        thread::spawn(move || {
+            let mut names: Vec<String> = Vec::new();
+            for mut command in manager.dump_commands() {
+                if let Some(output) = run_with_timeout(&mut command, DUMP_TIMEOUT, 32 * 1024 * 1024) {
+                    if output.status.success() {
+                        names.extend(String::from_utf8_lossy(&output.stdout).lines().map(|line| line.trim().to_string()));
+                    }
+                }
+            }
+            names.retain(|name| !name.is_empty());
+            names.sort();
+            names.dedup();
+
+            // Keep the old dump if this one came back empty (offline, manager missing)
+            if !names.is_empty() {
+                if let Some(dir) = path.parent() {
+                    let _ = fs::create_dir_all(dir);
+                }
+
+                // Write to a temporary file first so readers never see a partial dump
+                let tmp_path = path.with_extension("txt.tmp");
+                if fs::write(&tmp_path, names.join("\n")).is_ok() {
+                    let _ = fs::rename(&tmp_path, &path);
+                }
+            }
+
+            refreshing.lock().remove(&manager);
+        });
+    }
+}
+
+/// Completes installed and available package names for apt, brew, dnf, and pacman
+pub struct PackageCompleter {
+    index: PackageIndex,
+    installed: RwLock<HashMap<PackageManager, (Instant, Arc<Vec<String>>)>>,
+}
+
+impl PackageCompleter {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            index: PackageIndex {
+                dir: config.general.user_data_dir.join(PACKAGE_INDEX_DIR),
+                refreshing: Arc::new(Mutex::new(HashSet::new())),
+            },
+            installed: RwLock::new(HashMap::new()),
+        }
+    }
+
+    fn installed(&self, manager: PackageManager) -> Arc<Vec<String>> {
+        if let Some((listed_at, names)) = self.installed.read().get(&manager) {
+            if listed_at.elapsed() < INSTALLED_CACHE_LIFETIME {
+                return Arc::clone(names);
+            }
+        }
+
+        // Sorted so `install` can mark already-installed packages with a binary search
+        let mut names = installed_packages(manager).unwrap_or_default();
+        names.sort();
+        names.dedup();
+
+        let names = Arc::new(names);
+        self.installed.write().insert(manager, (Instant::now(), Arc::clone(&names)));
+        names
+    }
+}
+
+impl CompletionProvider for PackageCompleter {
+    fn name(&self) -> &str {
+        "packages"
+    }
+
+    fn can_provide_completions(&self, command: &str) -> bool {
+        PackageManager::from_command(command).is_some()
+    }
+
+    fn provide_completions(&self, command: &str, args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        if current_arg.starts_with('-') {
+            return Vec::new();
+        }
+
+        let manager = match PackageManager::from_command(command) {
+            Some(manager) => manager,
+            None => return Vec::new(),
+        };
+
+        let installed = self.installed(manager);
+
+        match manager.package_set(args) {
+            Some(PackageSet::Installed) => {
+                generated_suggestions(installed.iter().map(|name| (name.clone(), "installed".to_string())), current_arg)
+            }
+            Some(PackageSet::Available) => {
+                // Indexes hold tens of thousands of names, so narrow by prefix before fuzzy scoring
+                if current_arg.len() < MIN_AVAILABLE_PREFIX {
+                    return Vec::new();
+                }
+                let available = match self.index.available(manager) {
+                    Some(available) => available,
+                    None => return Vec::new(),
+                };
+
+                let candidates = available.iter()
+                    .filter(|name| name.starts_with(current_arg))
+                    .map(|name| {
+                        let description = if installed.binary_search(name).is_ok() {
+                            "installed"
+                        } else {
+                            "available"
+                        };
+                        (name.clone(), description.to_string())
+                    });
+
+                generated_suggestions(candidates, current_arg)
+            }
+            None => Vec::new(),
+        }
+    }
+}
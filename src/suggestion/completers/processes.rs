@@ -0,0 +1,164 @@
+use std::process::Command;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use parking_lot::RwLock;
+
+use crate::plugin::api::CompletionProvider;
+use crate::suggestion::fuzzy::fast_fuzzy_score;
+use crate::suggestion::{Suggestion, SuggestionKind};
+use crate::utils::process::run_with_timeout;
+
+/// Commands taking process IDs
+const PID_COMMANDS: &[&str] = &["kill"];
+
+/// Commands taking process names
+const NAME_COMMANDS: &[&str] = &["pkill", "killall", "pgrep", "pidof"];
+
+/// Processes come and go; a listing is reused only while typing one argument
+const CACHE_LIFETIME: Duration = Duration::from_secs(2);
+
+/// Deadline for `ps` where there's no /proc
+const PS_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// A process the user owns
+#[derive(Debug, Clone)]
+struct ProcessInfo {
+    pid: u32,
+    name: String,
+}
+
+/// Completes the user's own processes: PIDs (found by name too) for `kill`, names for
+/// `pkill` and `killall`
+pub struct ProcessCompleter {
+    cache: RwLock<Option<(Instant, Arc<Vec<ProcessInfo>>)>>,
+}
+
+impl ProcessCompleter {
+    pub fn new() -> Self {
+        Self { cache: RwLock::new(None) }
+    }
+
+    fn processes(&self) -> Arc<Vec<ProcessInfo>> {
+        if let Some((listed_at, processes)) = self.cache.read().as_ref() {
+            if listed_at.elapsed() < CACHE_LIFETIME {
+                return Arc::clone(processes);
+            }
+        }
+
+        let processes = Arc::new(list_processes());
+        *self.cache.write() = Some((Instant::now(), Arc::clone(&processes)));
+        processes
+    }
+}
+
+impl CompletionProvider for ProcessCompleter {
+    fn name(&self) -> &str {
+        "processes"
+    }
+
+    fn can_provide_completions(&self, command: &str) -> bool {
+        PID_COMMANDS.contains(&command) || NAME_COMMANDS.contains(&command)
+    }
+
+    fn provide_completions(&self, command: &str, _args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        // Signals and options
+        if current_arg.starts_with('-') {
+            return Vec::new();
+        }
+        let processes = self.processes();
+
+        let mut suggestions: Vec<Suggestion> = if PID_COMMANDS.contains(&command) {
+            processes
+                .iter()
+                .filter_map(|process| {
+                    let pid = process.pid.to_string();
+                    let by_pid = pid.starts_with(current_arg).then_some(1.0);
+                    let score = by_pid.or_else(|| fast_fuzzy_score(current_arg, &process.name))?;
+                    Some(
+                        Suggestion::new(pid.clone(), pid, SuggestionKind::Process)
+                            .with_description(process.name.clone())
+                            .with_score(score * 100.0),
+                    )
+                })
+                .collect()
+        } else {
+            let mut names: Vec<(&str, usize)> = Vec::new();
+            for process in processes.iter() {
+                match names.iter_mut().find(|(name, _)| *name == process.name) {
+                    Some((_, count)) => *count += 1,
+                    None => names.push((&process.name, 1)),
+                }
+            }
+            names
+                .into_iter()
+                .filter_map(|(name, count)| {
+                    let score = fast_fuzzy_score(current_arg, name)?;
+                    let description = if count == 1 { "1 process".to_string() } else { format!("{} processes", count) };
+                    Some(
+                        Suggestion::new(name.to_string(), name.to_string(), SuggestionKind::Process)
+                            .with_description(description)
+                            .with_score(score * 100.0),
+                    )
+                })
+                .collect()
+        };
+
+        suggestions.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        suggestions
+    }
+}
+
+/// The user's processes, from /proc where there is one, otherwise from `ps`
+fn list_processes() -> Vec<ProcessInfo> {
+    list_from_proc().unwrap_or_else(|| list_from_ps().unwrap_or_default())
+}
+
+#[cfg(target_os = "linux")]
+fn list_from_proc() -> Option<Vec<ProcessInfo>> {
+    use std::fs;
+    use std::os::unix::fs::MetadataExt;
+
+    let own_uid = fs::metadata("/proc/self").ok()?.uid();
+    let own_pid = std::process::id();
+
+    let processes = fs::read_dir("/proc")
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+            if pid == own_pid || entry.metadata().ok()?.uid() != own_uid {
+                return None;
+            }
+            let name = fs::read_to_string(entry.path().join("comm")).ok()?.trim().to_string();
+            Some(ProcessInfo { pid, name })
+        })
+        .collect();
+    Some(processes)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn list_from_proc() -> Option<Vec<ProcessInfo>> {
+    None
+}
+
+fn list_from_ps() -> Option<Vec<ProcessInfo>> {
+    let mut command = Command::new("ps");
+    command.args(["-xo", "pid=,comm="]);
+    let output = run_with_timeout(&mut command, PS_TIMEOUT, 1024 * 1024)?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let own_pid = std::process::id();
+    let processes = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (pid, name) = line.trim().split_once(char::is_whitespace)?;
+            let pid: u32 = pid.parse().ok()?;
+            // `comm` is a path on macOS
+            let name = name.trim().rsplit('/').next()?.to_string();
+            (pid != own_pid && !name.is_empty()).then_some(ProcessInfo { pid, name })
+        })
+        .collect();
+    Some(processes)
+}
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::process::Command;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use parking_lot::RwLock;
+
+use crate::plugin::api::CompletionProvider;
+use crate::suggestion::spec::{generated_suggestions, ArgSpec, CommandSpec, Generator, GeneratorRegistry, OptionSpec};
+use crate::suggestion::Suggestion;
+use crate::utils::process::run_with_timeout;
+
+/// How long a unit listing is reused
+const UNIT_CACHE_LIFETIME: Duration = Duration::from_secs(30);
+
+/// Deadline for `systemctl list-unit-files`
+const LIST_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Which systemd instance a command talks to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnitScope {
+    System,
+    User,
+}
+
+/// An installed unit file and its enablement state (`enabled`, `disabled`, `static`, ...)
+#[derive(Debug, Clone)]
+pub struct UnitFile {
+    pub name: String,
+    pub state: String,
+}
+
+/// Cached `systemctl list-unit-files` output per scope
+struct UnitCache {
+    entries: RwLock<HashMap<UnitScope, (Instant, Arc<Vec<UnitFile>>)>>,
+}
+
+impl UnitCache {
+    fn units(&self, scope: UnitScope) -> Arc<Vec<UnitFile>> {
+        if let Some((listed_at, units)) = self.entries.read().get(&scope) {
+            if listed_at.elapsed() < UNIT_CACHE_LIFETIME {
+                return Arc::clone(units);
+            }
+        }
+
+        // Failed listings are cached too, so a missing systemd isn't retried on every keystroke
+        let units = Arc::new(list_unit_files(scope).unwrap_or_default());
+        self.entries.write().insert(scope, (Instant::now(), Arc::clone(&units)));
+        units
+    }
+}
+
+fn list_unit_files(scope: UnitScope) -> Option<Vec<UnitFile>> {
+    let mut command = Command::new("systemctl");
+    if scope == UnitScope::User {
+        command.arg("--user");
+    }
+    command.args(["list-unit-files", "--no-legend", "--no-pager", "--plain"]);
+
+    let output = run_with_timeout(&mut command, LIST_TIMEOUT, 1024 * 1024)?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let units = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut columns = line.split_whitespace();
+            let name = columns.next()?;
+
+            // Templates like `getty@.service` can't be started without an instance name
+            if name.contains("@.") {
+                return None;
+            }
+
+            Some(UnitFile {
+                name: name.to_string(),
+                state: columns.next().unwrap_or("unknown").to_string(),
+            })
+        })
+        .collect();
+
+    Some(units)
+}
+
+/// Completes unit names in the scope selected by `--user` on the command line
+struct UnitsGenerator {
+    cache: Arc<UnitCache>,
+    /// Always list user units (`journalctl --user-unit`)
+    user_only: bool,
+    /// Hide units already in this state (`enable` skips enabled units)
+    exclude_state: Option<&'static str>,
+}
+
+impl Generator for UnitsGenerator {
+    fn generate(&self, args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        let scope = if self.user_only || args.contains(&"--user") {
+            UnitScope::User
+        } else {
+            UnitScope::System
+        };
+
+        let units = self.cache.units(scope);
+        let candidates = units.iter()
+            .filter(|unit| Some(unit.state.as_str()) != self.exclude_state)
+            .map(|unit| (unit.name.clone(), unit.state.clone()));
+
+        generated_suggestions(candidates, current_arg)
+    }
+}
+
+/// Completes systemd units for `systemctl` and `journalctl`
+pub struct SystemdCompleter {
+    systemctl: CommandSpec,
+    journalctl: CommandSpec,
+    generators: GeneratorRegistry,
+}
+
+impl SystemdCompleter {
+    pub fn new() -> Self {
+        let cache = Arc::new(UnitCache {
+            entries: RwLock::new(HashMap::new()),
+        });
+
+        let units = |user_only, exclude_state| Arc::new(UnitsGenerator {
+            cache: Arc::clone(&cache),
+            user_only,
+            exclude_state,
+        });
+
+        let mut generators = GeneratorRegistry::new();
+        generators.register("systemd:units", units(false, None));
+        generators.register("systemd:user-units", units(true, None));
+        generators.register("systemd:disabled-units", units(false, Some("enabled")));
+        generators.register("systemd:enabled-units", units(false, Some("disabled")));
+
+        Self {
+            systemctl: systemctl_spec(),
+            journalctl: journalctl_spec(),
+            generators,
+        }
+    }
+}
+
+impl CompletionProvider for SystemdCompleter {
+    fn name(&self) -> &str {
+        "systemd"
+    }
+
+    fn can_provide_completions(&self, command: &str) -> bool {
+        matches!(command, "systemctl" | "journalctl")
+    }
+
+    fn provide_completions(&self, command: &str, args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        let spec = if command == "journalctl" { &self.journalctl } else { &self.systemctl };
+        spec.suggestions_with(args, current_arg, &self.generators)
+    }
+}
+
+/// An option that takes no value
+fn flag(names: &[&str], description: &str) -> OptionSpec {
+    OptionSpec {
+        names: names.iter().map(|n| n.to_string()).collect(),
+        description: Some(description.to_string()),
+        ..Default::default()
+    }
+}
+
+/// Built-in spec for the systemctl verbs that take unit names
+fn systemctl_spec() -> CommandSpec {
+    let scope_options = vec![
+        flag(&["--user"], "Talk to the service manager of the calling user"),
+        flag(&["--system"], "Talk to the system service manager"),
+        flag(&["--now"], "Also start or stop the unit"),
+    ];
+
+    let units = |generator| vec![ArgSpec::generated("unit", generator, true)];
+
+    let mut spec = CommandSpec::new("systemctl");
+    spec.subcommands = vec![
+        CommandSpec::with_args("start", "Start units", units("systemd:units")),
+        CommandSpec::with_args("stop", "Stop units", units("systemd:units")),
+        CommandSpec::with_args("restart", "Restart units", units("systemd:units")),
+        CommandSpec::with_args("reload", "Reload units", units("systemd:units")),
+        CommandSpec::with_args("try-restart", "Restart units if running", units("systemd:units")),
+        CommandSpec::with_args("reload-or-restart", "Reload units if supported, restart otherwise", units("systemd:units")),
+        CommandSpec::with_args("status", "Show runtime status of units", units("systemd:units")),
+        CommandSpec::with_args("show", "Show properties of units", units("systemd:units")),
+        CommandSpec::with_args("cat", "Show unit files", units("systemd:units")),
+        CommandSpec::with_args("edit", "Edit unit files", units("systemd:units")),
+        CommandSpec::with_args("is-active", "Check whether units are active", units("systemd:units")),
+        CommandSpec::with_args("is-enabled", "Check whether units are enabled", units("systemd:units")),
+        CommandSpec::with_args("enable", "Enable unit files", units("systemd:disabled-units")),
+        CommandSpec::with_args("disable", "Disable unit files", units("systemd:enabled-units")),
+        CommandSpec::with_args("mask", "Mask unit files", units("systemd:units")),
+        CommandSpec::with_args("unmask", "Unmask unit files", units("systemd:units")),
+        CommandSpec::with_args("daemon-reload", "Reload systemd manager configuration", Vec::new()),
+        CommandSpec::with_args("list-units", "List loaded units", Vec::new()),
+        CommandSpec::with_args("list-unit-files", "List installed unit files", Vec::new()),
+    ];
+
+    // `--user` may come before or after the verb
+    spec.options = scope_options.clone();
+    spec.propagate_options(&scope_options);
+
+    spec
+}
+
+/// Built-in spec for journalctl's unit filters
+fn journalctl_spec() -> CommandSpec {
+    let mut spec = CommandSpec::new("journalctl");
+    spec.options = vec![
+        OptionSpec::generated(&["-u", "--unit"], "UNIT", "Show logs from the specified unit", "systemd:units"),
+        OptionSpec::generated(&["--user-unit"], "UNIT", "Show logs from the specified user unit", "systemd:user-units"),
+        flag(&["--user"], "Show the user journal"),
+        flag(&["-f", "--follow"], "Follow the journal"),
+        flag(&["-e", "--pager-end"], "Jump to the end of the journal"),
+        flag(&["-b", "--boot"], "Show logs from the current boot"),
+        flag(&["-x", "--catalog"], "Add explanatory help texts"),
+    ];
+
+    spec
+}
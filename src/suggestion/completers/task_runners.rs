@@ -0,0 +1,236 @@
+use std::env;
+use std::path::Path;
+use std::sync::Arc;
+use once_cell::sync::Lazy;
+
+use crate::plugin::api::CompletionProvider;
+use crate::suggestion::project::{find_upwards, ManifestCache};
+use crate::suggestion::spec::generated_suggestions;
+use crate::suggestion::Suggestion;
+
+/// Parsed recipe files keyed by path
+static RECIPE_FILES: Lazy<ManifestCache<Vec<Recipe>>> = Lazy::new(ManifestCache::new);
+
+/// A project-local task runner
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TaskRunner {
+    Just,
+    Task,
+    Invoke,
+}
+
+/// A named recipe/task with its description (or body when undocumented)
+#[derive(Debug, Clone)]
+pub struct Recipe {
+    pub name: String,
+    pub description: String,
+}
+
+impl TaskRunner {
+    pub const ALL: [TaskRunner; 3] = [TaskRunner::Just, TaskRunner::Task, TaskRunner::Invoke];
+
+    pub fn from_command(command: &str) -> Option<Self> {
+        match command {
+            "just" => Some(TaskRunner::Just),
+            "task" => Some(TaskRunner::Task),
+            "invoke" | "inv" => Some(TaskRunner::Invoke),
+            _ => None,
+        }
+    }
+
+    pub fn command(&self) -> &'static str {
+        match self {
+            TaskRunner::Just => "just",
+            TaskRunner::Task => "task",
+            TaskRunner::Invoke => "invoke",
+        }
+    }
+
+    fn file_names(&self) -> &'static [&'static str] {
+        match self {
+            TaskRunner::Just => &["justfile", "Justfile", ".justfile"],
+            TaskRunner::Task => &["Taskfile.yml", "Taskfile.yaml", "taskfile.yml", "taskfile.yaml"],
+            TaskRunner::Invoke => &["tasks.py"],
+        }
+    }
+
+    /// Recipes from the runner's config file nearest to `dir`
+    pub fn recipes(&self, dir: &Path) -> Option<Arc<Vec<Recipe>>> {
+        let path = self.file_names().iter().find_map(|name| find_upwards(dir, name))?;
+        let runner = *self;
+
+        RECIPE_FILES.get(&path, move |_, content| {
+            Some(match runner {
+                TaskRunner::Just => parse_justfile(content),
+                TaskRunner::Task => parse_taskfile(content),
+                TaskRunner::Invoke => parse_invoke(content),
+            })
+        })
+    }
+}
+
+/// Recipes are unindented `name args...:` lines; a `#` comment right above documents them
+fn parse_justfile(content: &str) -> Vec<Recipe> {
+    let mut recipes = Vec::new();
+    let mut comment: Option<String> = None;
+
+    for line in content.lines() {
+        if line.starts_with(char::is_whitespace) || line.trim().is_empty() {
+            comment = None;
+            continue;
+        }
+
+        if let Some(text) = line.strip_prefix('#') {
+            comment = Some(text.trim().to_string());
+            continue;
+        }
+
+        // `alias b := build` makes `b` runnable too
+        if let Some(alias) = line.strip_prefix("alias ") {
+            if let Some((name, target)) = alias.split_once(":=") {
+                recipes.push(Recipe {
+                    name: name.trim().to_string(),
+                    description: format!("alias for {}", target.trim()),
+                });
+            }
+            comment = None;
+            continue;
+        }
+
+        // Skip settings, exports, variable assignments, and attributes
+        if line.starts_with("set ") || line.starts_with("export ") || line.starts_with('[')
+            || line.starts_with("import ") || line.starts_with("mod ") || line.contains(":=")
+        {
+            comment = None;
+            continue;
+        }
+
+        if let Some((header, body)) = line.split_once(':') {
+            let name = header.split_whitespace().next().unwrap_or("").trim_start_matches('@');
+
+            // Recipes starting with `_` are private
+            if !name.is_empty() && !name.starts_with('_') {
+                let description = comment.take().unwrap_or_else(|| {
+                    let dependencies = body.trim();
+                    if dependencies.is_empty() { "recipe".to_string() } else { format!("recipe → {}", dependencies) }
+                });
+
+                recipes.push(Recipe {
+                    name: name.to_string(),
+                    description,
+                });
+            }
+        }
+
+        comment = None;
+    }
+
+    recipes
+}
+
+/// Tasks are the keys of the top-level `tasks:` mapping, described by `desc`/`summary`
+fn parse_taskfile(content: &str) -> Vec<Recipe> {
+    let document: serde_yaml::Value = match serde_yaml::from_str(content) {
+        Ok(document) => document,
+        Err(_) => return Vec::new(),
+    };
+
+    let tasks = match document["tasks"].as_mapping() {
+        Some(tasks) => tasks,
+        None => return Vec::new(),
+    };
+
+    tasks.iter()
+        .filter_map(|(name, task)| {
+            let name = name.as_str()?;
+
+            // `internal: true` tasks can't be called from the command line
+            if task["internal"].as_bool() == Some(true) {
+                return None;
+            }
+
+            let description = task["desc"].as_str()
+                .or_else(|| task["summary"].as_str())
+                .unwrap_or("task")
+                .lines()
+                .next()
+                .unwrap_or("task")
+                .to_string();
+
+            Some(Recipe {
+                name: name.to_string(),
+                description,
+            })
+        })
+        .collect()
+}
+
+/// Tasks are functions decorated with `@task`; invoke exposes `snake_case` as `snake-case`
+fn parse_invoke(content: &str) -> Vec<Recipe> {
+    let mut recipes = Vec::new();
+    let mut decorated = false;
+
+    for line in content.lines().map(str::trim) {
+        if line.starts_with("@task") || line.starts_with("@invoke.task") {
+            decorated = true;
+            continue;
+        }
+
+        if decorated {
+            if let Some(signature) = line.strip_prefix("def ") {
+                if let Some(name) = signature.split('(').next() {
+                    if !name.starts_with('_') {
+                        recipes.push(Recipe {
+                            name: name.trim().replace('_', "-"),
+                            description: "invoke task".to_string(),
+                        });
+                    }
+                }
+                decorated = false;
+            } else if !line.starts_with('@') {
+                decorated = false;
+            }
+        }
+    }
+
+    recipes
+}
+
+/// Completes recipes for just, task, and invoke from the project's config files
+pub struct TaskRunnerCompleter;
+
+impl TaskRunnerCompleter {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl CompletionProvider for TaskRunnerCompleter {
+    fn name(&self) -> &str {
+        "task-runners"
+    }
+
+    fn can_provide_completions(&self, command: &str) -> bool {
+        TaskRunner::from_command(command).is_some()
+    }
+
+    fn provide_completions(&self, command: &str, _args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        if current_arg.starts_with('-') {
+            return Vec::new();
+        }
+
+        let runner = match TaskRunner::from_command(command) {
+            Some(runner) => runner,
+            None => return Vec::new(),
+        };
+        let recipes = match env::current_dir().ok().and_then(|cwd| runner.recipes(&cwd)) {
+            Some(recipes) => recipes,
+            None => return Vec::new(),
+        };
+
+        generated_suggestions(
+            recipes.iter().map(|recipe| (recipe.name.clone(), recipe.description.clone())),
+            current_arg,
+        )
+    }
+}
@@ -0,0 +1,32 @@
+//! Where a command is being typed: the directory and what kind of project it's in.
+
+use std::path::PathBuf;
+
+/// The surroundings of the line being completed, as gathered by the `ContextAnalyzer`
+#[derive(Debug, Clone)]
+pub struct Context {
+    pub current_dir: PathBuf,
+    pub in_git_repo: bool,
+    pub in_docker_context: bool,
+    /// The command word of the line
+    pub current_command: String,
+    pub project_type: ProjectType,
+}
+
+/// The kind of project a directory holds, going by its build files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProjectType {
+    Rust,
+    Node,
+    Python,
+    Go,
+    Java,
+    Ruby,
+    Php,
+    Elixir,
+    Haskell,
+    C,
+    DotNet,
+    Terraform,
+    Unknown,
+}
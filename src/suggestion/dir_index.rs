@@ -0,0 +1,231 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use ignore::WalkBuilder;
+use parking_lot::{Mutex, RwLock};
+
+use crate::utils::memory::{self, MemoryConsumer};
+
+#[cfg(feature = "path-monitoring")]
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+/// Maximum number of directories kept in the index
+const MAX_INDEXED_DIRS: usize = 64;
+
+/// A single entry of an indexed directory
+#[derive(Debug, Clone)]
+pub struct IndexedEntry {
+    pub name: String,
+    pub is_dir: bool,
+    pub is_hidden: bool,
+    pub modified: Option<SystemTime>,
+}
+
+/// Complete, ignore-filtered listing of one directory
+#[derive(Debug)]
+pub struct IndexedDir {
+    pub entries: Vec<IndexedEntry>,
+    pub scanned_at: Instant,
+    /// Approximate size of the listing, for memory accounting
+    pub bytes: usize,
+}
+
+impl IndexedDir {
+    /// A listing scanned just now
+    pub fn new(entries: Vec<IndexedEntry>) -> Self {
+        let bytes = entries.iter()
+            .map(|entry| std::mem::size_of::<IndexedEntry>() + entry.name.len())
+            .sum();
+        Self { entries, scanned_at: Instant::now(), bytes }
+    }
+}
+
+type IndexMap = Arc<RwLock<HashMap<PathBuf, Arc<IndexedDir>>>>;
+
+/// Background directory index so huge directories are never listed on the hot path
+///
+/// Listings are built on a background thread and invalidated by filesystem
+/// events when the `path-monitoring` feature is enabled, or by age otherwise.
+#[derive(Clone)]
+pub struct DirectoryIndex {
+    entries: IndexMap,
+    pending: Arc<Mutex<HashSet<PathBuf>>>,
+    ignored_dirs: Arc<Vec<String>>,
+    max_age: Duration,
+    #[cfg(feature = "path-monitoring")]
+    watcher: Arc<Mutex<Option<RecommendedWatcher>>>,
+}
+
+impl DirectoryIndex {
+    pub fn new(ignored_dirs: Vec<String>, max_age: Duration) -> Self {
+        let entries: IndexMap = Arc::new(RwLock::new(HashMap::new()));
+
+        Self {
+            #[cfg(feature = "path-monitoring")]
+            watcher: Arc::new(Mutex::new(Self::create_watcher(Arc::clone(&entries)))),
+            entries,
+            pending: Arc::new(Mutex::new(HashSet::new())),
+            ignored_dirs: Arc::new(ignored_dirs),
+            max_age,
+        }
+    }
+
+    /// Get the indexed listing for a directory without blocking
+    ///
+    /// Returns `None` (and schedules a background scan) when the directory
+    /// hasn't been indexed yet or its listing is stale.
+    pub fn get(&self, dir: &Path) -> Option<Arc<IndexedDir>> {
+        let cached = self.entries.read().get(dir).cloned();
+
+        match cached {
+            Some(indexed) if !self.is_stale(&indexed) => Some(indexed),
+            _ => {
+                self.schedule_scan(dir);
+                None
+            }
+        }
+    }
+
+    /// Drop the listing for a directory so the next lookup rescans it
+    pub fn invalidate(&self, dir: &Path) {
+        self.entries.write().remove(dir);
+
+        #[cfg(feature = "path-monitoring")]
+        if let Some(watcher) = self.watcher.lock().as_mut() {
+            let _ = watcher.unwatch(dir);
+        }
+    }
+
+    /// Scan a directory on a background thread unless a scan is already running
+    pub fn schedule_scan(&self, dir: &Path) {
+        if !self.pending.lock().insert(dir.to_path_buf()) {
+            return;
+        }
+
+        let index = self.clone();
+        let dir = dir.to_path_buf();
+
+        thread::spawn(move || {
+            let entries = scan_directory(&dir, &index.ignored_dirs, None);
+            index.insert(dir.clone(), entries);
+            index.pending.lock().remove(&dir);
+        });
+    }
+
+    /// Store a fresh listing, evicting the oldest directory if the index is full
+    fn insert(&self, dir: PathBuf, entries: Vec<IndexedEntry>) {
+        #[cfg(feature = "path-monitoring")]
+        if let Some(watcher) = self.watcher.lock().as_mut() {
+            let _ = watcher.watch(&dir, RecursiveMode::NonRecursive);
+        }
+
+        self.store(dir, entries);
+
+        // With the lock released, since staying in budget may shrink the index
+        memory::enforce();
+    }
+
+    fn store(&self, dir: PathBuf, entries: Vec<IndexedEntry>) {
+        let mut map = self.entries.write();
+
+        if map.len() >= MAX_INDEXED_DIRS && !map.contains_key(&dir) {
+            let oldest = map.iter()
+                .min_by_key(|(_, indexed)| indexed.scanned_at)
+                .map(|(path, _)| path.clone());
+
+            if let Some(oldest) = oldest {
+                map.remove(&oldest);
+            }
+        }
+
+        map.insert(dir, Arc::new(IndexedDir::new(entries)));
+    }
+
+    /// With a watcher, listings stay valid until an event arrives; otherwise they age out
+    fn is_stale(&self, indexed: &IndexedDir) -> bool {
+        #[cfg(feature = "path-monitoring")]
+        if self.watcher.lock().is_some() {
+            return false;
+        }
+
+        indexed.scanned_at.elapsed() > self.max_age
+    }
+
+    /// Create a watcher that evicts listings whose directory contents changed
+    #[cfg(feature = "path-monitoring")]
+    fn create_watcher(entries: IndexMap) -> Option<RecommendedWatcher> {
+        notify::recommended_watcher(move |result: notify::Result<notify::Event>| {
+            if let Ok(event) = result {
+                let mut map = entries.write();
+                for path in &event.paths {
+                    if let Some(parent) = path.parent() {
+                        map.remove(parent);
+                    }
+                    map.remove(path);
+                }
+            }
+        })
+        .ok()
+    }
+}
+
+impl MemoryConsumer for DirectoryIndex {
+    fn memory_bytes(&self) -> usize {
+        self.entries.read().values().map(|indexed| indexed.bytes).sum()
+    }
+
+    /// Drop whole directory listings, least recently scanned first; they're rescanned on demand
+    fn shrink_to(&self, target: usize) {
+        let mut map = self.entries.write();
+        let mut total: usize = map.values().map(|indexed| indexed.bytes).sum();
+
+        let mut oldest: Vec<(PathBuf, Instant, usize)> = map.iter()
+            .map(|(path, indexed)| (path.clone(), indexed.scanned_at, indexed.bytes))
+            .collect();
+        oldest.sort_by_key(|(_, scanned_at, _)| *scanned_at);
+
+        for (path, _, bytes) in oldest {
+            if total <= target {
+                break;
+            }
+            map.remove(&path);
+            total -= bytes;
+        }
+    }
+}
+
+/// List one directory level, honoring `.gitignore` and the configured ignored directories
+pub fn scan_directory(dir: &Path, ignored_dirs: &[String], limit: Option<usize>) -> Vec<IndexedEntry> {
+    let ignored_dirs = ignored_dirs.to_vec();
+    let walker = WalkBuilder::new(dir)
+        .max_depth(Some(1))
+        .hidden(false)
+        .git_ignore(true)
+        .git_exclude(true)
+        .parents(true)
+        .filter_entry(move |entry| {
+            let name = entry.file_name().to_string_lossy();
+            !(entry.file_type().map_or(false, |t| t.is_dir())
+                && ignored_dirs.iter().any(|ignored| *ignored == name))
+        })
+        .build();
+
+    walker
+        .flatten()
+        .skip(1) // The root directory itself
+        .take(limit.unwrap_or(usize::MAX))
+        .map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let metadata = entry.metadata().ok();
+
+            IndexedEntry {
+                is_hidden: name.starts_with('.'),
+                is_dir: metadata.as_ref().map_or(false, |m| m.is_dir()),
+                modified: metadata.as_ref().and_then(|m| m.modified().ok()),
+                name,
+            }
+        })
+        .collect()
+}
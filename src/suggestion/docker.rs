@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+use std::env;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use parking_lot::RwLock;
+use serde_json::Value;
+
+use super::fuzzy::fast_fuzzy_score;
+use super::{Suggestion, SuggestionKind};
+use crate::plugin::api::CompletionProvider;
+
+/// How long API responses are reused; containers come and go, so keep it short
+const CACHE_LIFETIME: Duration = Duration::from_secs(3);
+
+/// The socket must answer quickly or we give up for this keystroke
+const SOCKET_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// Cap on a single API response
+const MAX_RESPONSE_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Which Docker objects an argument position expects
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Target {
+    RunningContainers,
+    StoppedContainers,
+    Containers,
+    Images,
+    Networks,
+    Volumes,
+    ComposeServices,
+}
+
+/// Completes containers, images, networks, and volumes by querying the local Docker API
+pub struct DockerSuggester {
+    socket: PathBuf,
+    cache: RwLock<HashMap<&'static str, (Instant, Arc<Value>)>>,
+}
+
+impl DockerSuggester {
+    pub fn new() -> Self {
+        Self {
+            socket: docker_socket(),
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Suggest Docker objects for the argument being typed
+    pub fn suggest(&self, command: &str, args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        let target = match target_for(command, args) {
+            Some(target) => target,
+            None => return Vec::new(),
+        };
+
+        let mut suggestions: Vec<Suggestion> = self.candidates(target)
+            .into_iter()
+            .filter_map(|(name, description)| {
+                let score = fast_fuzzy_score(current_arg, &name)?;
+                Some(
+                    Suggestion::new(name.clone(), name, SuggestionKind::Argument)
+                        .with_description(description)
+                        .with_score(score * 100.0)
+                )
+            })
+            .collect();
+
+        suggestions.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        suggestions
+    }
+
+    /// (name, description) pairs for a target, from the cached API responses
+    fn candidates(&self, target: Target) -> Vec<(String, String)> {
+        match target {
+            Target::RunningContainers | Target::StoppedContainers | Target::Containers => {
+                let containers = match self.get("/containers/json?all=1") {
+                    Some(containers) => containers,
+                    None => return Vec::new(),
+                };
+
+                containers.as_array().into_iter().flatten()
+                    .filter(|container| {
+                        let running = container["State"].as_str() == Some("running");
+                        match target {
+                            Target::RunningContainers => running,
+                            Target::StoppedContainers => !running,
+                            _ => true,
+                        }
+                    })
+                    .filter_map(|container| {
+                        // Names are reported with a leading slash
+                        let name = container["Names"][0].as_str()?.trim_start_matches('/');
+                        let description = format!(
+                            "{} · {}",
+                            container["Image"].as_str().unwrap_or("?"),
+                            container["Status"].as_str().unwrap_or("?"),
+                        );
+                        Some((name.to_string(), description))
+                    })
+                    .collect()
+            }
+            Target::Images => {
+                let images = match self.get("/images/json") {
+                    Some(images) => images,
+                    None => return Vec::new(),
+                };
+
+                images.as_array().into_iter().flatten()
+                    .flat_map(|image| {
+                        let id: String = image["Id"].as_str().unwrap_or("")
+                            .trim_start_matches("sha256:")
+                            .chars()
+                            .take(12)
+                            .collect();
+
+                        image["RepoTags"].as_array().into_iter().flatten()
+                            .filter_map(|tag| tag.as_str())
+                            .filter(|tag| *tag != "<none>:<none>")
+                            .map(move |tag| (tag.to_string(), format!("image {}", id)))
+                            .collect::<Vec<_>>()
+                    })
+                    .collect()
+            }
+            Target::Networks => {
+                let networks = match self.get("/networks") {
+                    Some(networks) => networks,
+                    None => return Vec::new(),
+                };
+
+                networks.as_array().into_iter().flatten()
+                    .filter_map(|network| {
+                        let name = network["Name"].as_str()?;
+                        let driver = network["Driver"].as_str().unwrap_or("?");
+                        Some((name.to_string(), format!("{} network", driver)))
+                    })
+                    .collect()
+            }
+            Target::Volumes => {
+                let volumes = match self.get("/volumes") {
+                    Some(volumes) => volumes,
+                    None => return Vec::new(),
+                };
+
+                volumes["Volumes"].as_array().into_iter().flatten()
+                    .filter_map(|volume| {
+                        let name = volume["Name"].as_str()?;
+                        let driver = volume["Driver"].as_str().unwrap_or("?");
+                        Some((name.to_string(), format!("{} volume", driver)))
+                    })
+                    .collect()
+            }
+            Target::ComposeServices => {
+                let containers = match self.get("/containers/json?all=1") {
+                    Some(containers) => containers,
+                    None => return Vec::new(),
+                };
+
+                let mut services: Vec<(String, String)> = containers.as_array().into_iter().flatten()
+                    .filter_map(|container| {
+                        let labels = &container["Labels"];
+                        let service = labels["com.docker.compose.service"].as_str()?;
+                        let project = labels["com.docker.compose.project"].as_str().unwrap_or("?");
+                        Some((service.to_string(), format!("compose service ({})", project)))
+                    })
+                    .collect();
+
+                services.sort();
+                services.dedup_by(|a, b| a.0 == b.0);
+                services
+            }
+        }
+    }
+
+    /// GET an API path, reusing a recent response
+    fn get(&self, path: &'static str) -> Option<Arc<Value>> {
+        if let Some((fetched_at, value)) = self.cache.read().get(path) {
+            if fetched_at.elapsed() < CACHE_LIFETIME {
+                return Some(Arc::clone(value));
+            }
+        }
+
+        let value = Arc::new(self.request(path)?);
+        self.cache.write().insert(path, (Instant::now(), Arc::clone(&value)));
+        Some(value)
+    }
+
+    /// Minimal HTTP/1.0 request over the Docker unix socket (1.0 keeps the body unchunked)
+    fn request(&self, path: &str) -> Option<Value> {
+        let mut stream = UnixStream::connect(&self.socket).ok()?;
+        stream.set_read_timeout(Some(SOCKET_TIMEOUT)).ok()?;
+        stream.set_write_timeout(Some(SOCKET_TIMEOUT)).ok()?;
+
+        write!(stream, "GET {} HTTP/1.0\r\nHost: docker\r\n\r\n", path).ok()?;
+
+        let mut response = Vec::new();
+        stream.take(MAX_RESPONSE_BYTES).read_to_end(&mut response).ok()?;
+
+        let header_end = response.windows(4).position(|window| window == b"\r\n\r\n")?;
+        let status_line = String::from_utf8_lossy(&response[..header_end]);
+        if !status_line.split_whitespace().nth(1).map_or(false, |code| code.starts_with('2')) {
+            return None;
+        }
+
+        serde_json::from_slice(&response[header_end + 4..]).ok()
+    }
+}
+
+impl CompletionProvider for DockerSuggester {
+    fn name(&self) -> &str {
+        "docker"
+    }
+
+    fn can_provide_completions(&self, command: &str) -> bool {
+        matches!(command, "docker" | "docker-compose") && self.socket.exists()
+    }
+
+    fn provide_completions(&self, command: &str, args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        if current_arg.starts_with('-') {
+            return Vec::new();
+        }
+
+        self.suggest(command, args, current_arg)
+    }
+}
+
+/// Work out what the argument being typed refers to from the words before it
+fn target_for(command: &str, args: &[&str]) -> Option<Target> {
+    // Flag values: `--network <net>`, `--volumes-from <container>`
+    match args.last() {
+        Some(&"--network") | Some(&"--net") => return Some(Target::Networks),
+        Some(&"--volumes-from") => return Some(Target::Containers),
+        _ => {}
+    }
+
+    let words: Vec<&str> = args.iter().copied().filter(|arg| !arg.starts_with('-')).collect();
+
+    if command == "docker-compose" || words.first() == Some(&"compose") {
+        let words = if command == "docker-compose" { &words[..] } else { &words[1..] };
+        // Services follow the compose subcommand (`docker compose logs <service>`)
+        return words.first().map(|_| Target::ComposeServices);
+    }
+
+    let (group, subcommand, positionals) = match words.as_slice() {
+        [group @ ("container" | "image" | "network" | "volume"), subcommand, rest @ ..] => (Some(*group), *subcommand, rest.len()),
+        [subcommand, rest @ ..] => (None, *subcommand, rest.len()),
+        [] => return None,
+    };
+
+    match (group, subcommand) {
+        (Some("network"), "connect" | "disconnect") if positionals == 0 => Some(Target::Networks),
+        (Some("network"), "connect" | "disconnect") => Some(Target::Containers),
+        (Some("network"), "rm" | "inspect") => Some(Target::Networks),
+        (Some("volume"), "rm" | "inspect") => Some(Target::Volumes),
+        (Some("image"), "rm" | "inspect" | "push" | "tag" | "history" | "save") => Some(Target::Images),
+        (None, "rmi" | "push" | "tag" | "history" | "save") => Some(Target::Images),
+        // Only the first positional of `run`/`create` is the image; the rest is the container's command
+        (None, "run" | "create") if positionals == 0 => Some(Target::Images),
+        // These accept several containers
+        (None | Some("container"), "stop" | "kill" | "pause" | "stats") => Some(Target::RunningContainers),
+        (None | Some("container"), "start" | "unpause") => Some(Target::StoppedContainers),
+        (None | Some("container"), "rm" | "restart" | "inspect" | "wait") => Some(Target::Containers),
+        // These take a single container followed by other arguments
+        (None | Some("container"), "exec" | "attach" | "top" | "port") if positionals == 0 => Some(Target::RunningContainers),
+        (None | Some("container"), "logs" | "rename" | "commit" | "diff" | "export" | "cp") if positionals == 0 => {
+            Some(Target::Containers)
+        }
+        _ => None,
+    }
+}
+
+/// Docker socket from `DOCKER_HOST=unix://...`, the rootless socket, or the system default
+fn docker_socket() -> PathBuf {
+    if let Ok(host) = env::var("DOCKER_HOST") {
+        if let Some(path) = host.strip_prefix("unix://") {
+            return PathBuf::from(path);
+        }
+    }
+
+    if let Ok(runtime_dir) = env::var("XDG_RUNTIME_DIR") {
+        let rootless = PathBuf::from(runtime_dir).join("docker.sock");
+        if rootless.exists() {
+            return rootless;
+        }
+    }
+
+    PathBuf::from("/var/run/docker.sock")
+}
@@ -0,0 +1,522 @@
+use std::env;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use parking_lot::RwLock;
+use tokio::time::timeout_at;
+
+use crate::config::Config;
+use crate::plugin::api::CompletionRegistry;
+use crate::shell::history::{HistoryDb, HistoryEntry};
+use crate::shell::parser::{CommandParser, QuoteKind, WordPosition};
+use crate::shell::LineEdit;
+use crate::utils::merge::{better_description, merge_by_key, AGREEMENT_SHARE};
+use crate::utils::perf_metrics::PerformanceMetrics;
+use crate::utils::usage_stats;
+use super::assignments::AssignmentSuggester;
+use super::bookmarks::BookmarkSuggester;
+use super::command::CommandSuggester;
+use super::completers;
+use super::frecency::FrecencyCompleter;
+use super::help_scraper::HelpScraper;
+use super::path::PathSuggester;
+use super::remote::RemotePathSuggester;
+use super::scoring::Scorer;
+use super::snippets::{SnippetSuggester, TabStop};
+use super::spec_files::SpecFileCompleter;
+use super::subcommands::SubcommandCorrector;
+use super::variables::VariableSuggester;
+use super::workflows::WorkflowCompleter;
+
+/// How long one round of suggestions waits for its sources; slower ones are left out
+const SOURCE_DEADLINE: Duration = Duration::from_millis(50);
+
+/// What a suggestion completes, which decides how it's shown and applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SuggestionKind {
+    Command,
+    Flag,
+    Argument,
+    File,
+    Directory,
+    /// A whole command line from history
+    History,
+    Snippet,
+    Bookmark,
+    /// Guessed by an AI provider rather than known to exist
+    Ai,
+    /// A branch, tag, remote or stash
+    GitRef,
+    /// An environment variable reference
+    EnvVar,
+    /// A running process, by PID or name
+    Process,
+    /// A file or directory on another host
+    Remote,
+}
+
+impl SuggestionKind {
+    /// Whether suggestions of this kind are whole command lines, which replace the line
+    /// rather than the word under the cursor
+    pub fn is_full_line(self) -> bool {
+        matches!(self, SuggestionKind::History | SuggestionKind::Snippet | SuggestionKind::Bookmark)
+    }
+}
+
+/// One entry of the completion dropdown
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    /// Text shown in the dropdown
+    pub display: String,
+    /// Text that replaces the word under the cursor when the suggestion is accepted, or
+    /// the whole line for full-line kinds
+    pub completion: String,
+    pub kind: SuggestionKind,
+    pub description: Option<String>,
+    /// Ranking score: the source's 0-100 match, plus points from `suggestions.scoring`;
+    /// higher is shown first
+    pub score: f32,
+    /// Placeholders in `completion` to walk after accepting it; only snippets have any
+    pub tab_stops: Vec<TabStop>,
+}
+
+impl Suggestion {
+    pub fn new(display: String, completion: String, kind: SuggestionKind) -> Self {
+        Self {
+            display,
+            completion,
+            kind,
+            description: None,
+            score: 0.0,
+            tab_stops: Vec::new(),
+        }
+    }
+
+    pub fn with_description(mut self, description: String) -> Self {
+        self.description = Some(description);
+        self
+    }
+
+    pub fn with_score(mut self, score: f32) -> Self {
+        self.score = score;
+        self
+    }
+
+    pub fn with_tab_stops(mut self, tab_stops: Vec<TabStop>) -> Self {
+        self.tab_stops = tab_stops;
+        self
+    }
+
+    /// The edit that accepts this suggestion, for a word under the cursor starting at
+    /// byte `token_start`
+    pub fn edit(&self, token_start: usize) -> LineEdit<'_> {
+        if self.kind.is_full_line() {
+            LineEdit::ReplaceLine(&self.completion)
+        } else {
+            LineEdit::ReplaceToken { start: token_start, text: &self.completion }
+        }
+    }
+
+    /// Fold in the same completion from another source: the better-scored one's text and
+    /// kind, the more informative description, and a share of the other's score
+    pub fn absorb(&mut self, mut other: Suggestion) {
+        if other.score > self.score {
+            std::mem::swap(self, &mut other);
+        }
+        self.score += AGREEMENT_SHARE * other.score;
+        better_description(&mut self.description, other.description);
+    }
+}
+
+/// A suggestion source the engine can run on its own thread
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Source {
+    Commands,
+    Completions,
+    Paths,
+    History,
+    Snippets,
+    Bookmarks,
+    Variables,
+    Assignments,
+}
+
+impl Source {
+    /// Operation name its timings are recorded under
+    fn name(self) -> &'static str {
+        match self {
+            Source::Commands => "commands",
+            Source::Completions => "completions",
+            Source::Paths => "paths",
+            Source::History => "history",
+            Source::Snippets => "snippets",
+            Source::Bookmarks => "bookmarks",
+            Source::Variables => "variables",
+            Source::Assignments => "assignments",
+        }
+    }
+}
+
+/// The line being completed, owned so sources can run on other threads
+struct Request {
+    line: String,
+    command: String,
+    args: Vec<String>,
+    current_arg: String,
+    position: WordPosition,
+    /// Quote left open at the cursor, with no closing one after it
+    open_quote: Option<QuoteKind>,
+    cwd: PathBuf,
+    limit: usize,
+}
+
+/// Every suggester, shared with the threads running them
+struct Sources {
+    commands: CommandSuggester,
+    paths: PathSuggester,
+    providers: CompletionRegistry,
+    snippets: SnippetSuggester,
+    bookmarks: BookmarkSuggester,
+    variables: VariableSuggester,
+    assignments: AssignmentSuggester,
+    subcommands: SubcommandCorrector,
+    history: HistorySource,
+    scorer: Scorer,
+    enable_commands: bool,
+    enable_paths: bool,
+    enable_flags: bool,
+    /// Per-source latencies, which `rustfig stats` reports
+    timings: PerformanceMetrics,
+}
+
+impl Sources {
+    /// Sources worth asking about this request
+    fn relevant(&self, request: &Request) -> Vec<Source> {
+        let mut sources = Vec::new();
+
+        // Inside an unclosed quote the word isn't finished; only a variable reference in
+        // double quotes or an assignment's value completes
+        if let Some(quote) = request.open_quote {
+            if quote == QuoteKind::Double && self.variables.applies(&request.current_arg) {
+                sources.push(Source::Variables);
+            } else if self.assignments.applies(&request.command, &request.current_arg) {
+                sources.push(Source::Assignments);
+            }
+            return sources;
+        }
+
+        if self.assignments.applies(&request.command, &request.current_arg) {
+            sources.push(Source::Assignments);
+        }
+
+        match request.position {
+            WordPosition::Command => {
+                if self.enable_commands {
+                    sources.push(Source::Commands);
+                }
+            }
+            WordPosition::Argument => {
+                if self.enable_flags {
+                    sources.push(Source::Completions);
+                }
+                if self.enable_paths && !request.current_arg.starts_with('-') {
+                    sources.push(Source::Paths);
+                }
+            }
+            WordPosition::RedirectTarget => {
+                if self.enable_paths {
+                    sources.push(Source::Paths);
+                }
+            }
+        }
+
+        if self.variables.applies(&request.current_arg) {
+            sources.push(Source::Variables);
+        }
+
+        // Whole-line sources match against everything typed so far
+        if !request.line.trim().is_empty() {
+            sources.extend([Source::History, Source::Snippets, Source::Bookmarks]);
+        }
+
+        sources
+    }
+
+    fn run(&self, source: Source, request: &Request) -> Vec<Suggestion> {
+        let _timing = self.timings.measure_operation(source.name());
+        match source {
+            Source::Commands => {
+                let suggestions = self.commands.suggest(&request.current_arg, request.limit);
+                if !suggestions.is_empty() {
+                    return suggestions;
+                }
+                // Nothing starts with the word, so it may be a typo
+                let history = self.history.entries();
+                self.commands.corrections(&request.current_arg, &command_names(&history), request.limit)
+            }
+            Source::Completions => {
+                let args: Vec<&str> = request.args.iter().map(String::as_str).collect();
+                let mut suggestions = self.subcommands.suggest(&request.command, &args, &request.current_arg);
+                suggestions.extend(self.providers.get_completions(&request.command, &args, &request.current_arg));
+                suggestions
+            }
+            Source::Paths => self.paths.suggest(&request.current_arg, &request.cwd, request.limit),
+            Source::History => self.history.suggest(&request.line, request.limit),
+            Source::Snippets => self.snippets.suggest(&request.line, request.limit),
+            Source::Bookmarks => self.bookmarks.suggest(&request.line, request.limit),
+            Source::Variables => self.variables.suggest(&request.current_arg, request.limit),
+            Source::Assignments => self.assignments.suggest(&request.current_arg, request.limit),
+        }
+    }
+}
+
+/// Earlier command lines that extend what's typed, most recent first
+struct HistorySource {
+    db: HistoryDb,
+    shell: String,
+    max_items: usize,
+    cache_lifetime: Duration,
+    entries: RwLock<Option<(Instant, Arc<Vec<HistoryEntry>>)>>,
+}
+
+impl HistorySource {
+    fn new(config: &Config) -> Self {
+        let shell = env::var("SHELL")
+            .ok()
+            .and_then(|s| s.rsplit('/').next().map(|s| s.to_string()))
+            .unwrap_or_else(|| "bash".to_string());
+
+        Self {
+            db: HistoryDb::from_config(config),
+            shell,
+            max_items: config.suggestions.max_history_items.unwrap_or(1000),
+            cache_lifetime: Duration::from_secs(config.suggestions.cache_lifetime_secs),
+            entries: RwLock::new(None),
+        }
+    }
+
+    fn suggest(&self, line: &str, limit: usize) -> Vec<Suggestion> {
+        let entries = self.entries();
+        let count = entries.len().max(1) as f32;
+
+        entries
+            .iter()
+            .map(|entry| &entry.command)
+            .enumerate()
+            .filter(|(_, command)| command.len() > line.len() && command.starts_with(line))
+            .take(limit)
+            // Newer commands rank higher, within the range of the other sources
+            .map(|(age, command)| {
+                Suggestion::new(command.clone(), command.clone(), SuggestionKind::History)
+                    .with_description("history".to_string())
+                    .with_score(60.0 + 20.0 * (1.0 - age as f32 / count))
+            })
+            .collect()
+    }
+
+    /// Newest-first history, reloaded after the cache lifetime
+    fn entries(&self) -> Arc<Vec<HistoryEntry>> {
+        if let Some((loaded_at, entries)) = self.entries.read().as_ref() {
+            if loaded_at.elapsed() < self.cache_lifetime {
+                return Arc::clone(entries);
+            }
+        }
+
+        let entries: Arc<Vec<HistoryEntry>> =
+            Arc::new(self.db.load_unified(&self.shell).into_iter().take(self.max_items).collect());
+        *self.entries.write() = Some((Instant::now(), Arc::clone(&entries)));
+        entries
+    }
+}
+
+/// The command name of each history entry, newest first
+fn command_names(entries: &[HistoryEntry]) -> Vec<&str> {
+    entries.iter().filter_map(|entry| entry.command.split_whitespace().next()).collect()
+}
+
+/// Collects suggestions for the word under the cursor from every source
+///
+/// With `performance.parallel_suggestions` the sources run concurrently and the
+/// round waits at most `SOURCE_DEADLINE` for them; otherwise they run one after
+/// another, which is lighter on low-power machines and SSH sessions.
+pub struct SuggestionEngine {
+    sources: Arc<Sources>,
+    parser: CommandParser,
+    parallel: bool,
+    performance_metrics: PerformanceMetrics,
+}
+
+impl SuggestionEngine {
+    pub fn new(config: &Config) -> Self {
+        let mut providers = CompletionRegistry::new();
+        providers.register(Box::new(SpecFileCompleter::new(config)));
+        providers.register(Box::new(FrecencyCompleter::new(&config.general.user_data_dir)));
+        providers.register(Box::new(WorkflowCompleter::new()));
+        providers.register(Box::new(RemotePathSuggester::new(config)));
+        #[cfg(feature = "git-integration")]
+        providers.register(Box::new(completers::GitCompleter::new()));
+        providers.register(Box::new(completers::KubectlCompleter::new(config)));
+        providers.register(Box::new(completers::CloudCompleter::new()));
+        providers.register(Box::new(completers::CargoCompleter::new()));
+        providers.register(Box::new(completers::NodeScriptsCompleter::new()));
+        providers.register(Box::new(completers::TaskRunnerCompleter::new()));
+        providers.register(Box::new(completers::SystemdCompleter::new()));
+        providers.register(Box::new(completers::PackageCompleter::new(config)));
+        providers.register(Box::new(completers::ProcessCompleter::new()));
+        providers.register(Box::new(completers::FileContentCompleter::new(config)));
+        #[cfg(all(unix, feature = "docker-integration"))]
+        providers.register(Box::new(super::docker::DockerSuggester::new()));
+        providers.register(Box::new(HelpScraper::new(config)));
+
+        let sources = Sources {
+            commands: CommandSuggester::new(config),
+            paths: PathSuggester::new(config),
+            providers,
+            snippets: SnippetSuggester::new(config),
+            bookmarks: BookmarkSuggester::new(config),
+            variables: VariableSuggester::new(config),
+            assignments: AssignmentSuggester::new(config),
+            subcommands: SubcommandCorrector::new(),
+            history: HistorySource::new(config),
+            scorer: Scorer::new(config),
+            enable_commands: config.suggestions.enable_commands,
+            enable_paths: config.suggestions.enable_paths,
+            enable_flags: config.suggestions.enable_flags,
+            timings: PerformanceMetrics::new(usage_stats::SOURCE_COMPONENT),
+        };
+
+        Self {
+            sources: Arc::new(sources),
+            parser: CommandParser::new(),
+            parallel: config.performance.as_ref().map_or(true, |performance| performance.parallel_suggestions),
+            performance_metrics: PerformanceMetrics::new("suggestion_engine"),
+        }
+    }
+
+    /// Show or hide type, size and age columns on path suggestions; returns whether
+    /// they're shown now
+    pub fn toggle_path_details(&self) -> bool {
+        self.sources.paths.toggle_details()
+    }
+
+    /// Suggestions for the word under `cursor_pos` (a byte offset into `line`), best first
+    pub async fn get_suggestions(&self, line: &str, cursor_pos: usize, limit: usize) -> Vec<Suggestion> {
+        let _timing = self.performance_metrics.measure_operation("get_suggestions");
+
+        // An unclosed quote still leaves `"$VAR` and `NAME="value` to complete
+        let parsed = match self.parser.parse(line, cursor_pos) {
+            Ok(parsed) if !parsed.in_heredoc => parsed,
+            _ => return Vec::new(),
+        };
+        let open_quote = parsed.open_quote.filter(|_| parsed.is_incomplete());
+
+        let request = Arc::new(Request {
+            line: parsed.command_line().to_string(),
+            command: parsed.command.to_string(),
+            args: parsed.args.iter().map(|arg| arg.to_string()).collect(),
+            current_arg: parsed.current_arg.to_string(),
+            position: parsed.position,
+            open_quote,
+            cwd: env::current_dir().unwrap_or_default(),
+            limit,
+        });
+
+        let sources = self.sources.relevant(&request);
+        let results = if self.parallel {
+            self.run_parallel(&sources, &request).await
+        } else {
+            self.run_serial(&sources, &request)
+        };
+
+        // Recency, frequency and context points, once per distinct completion
+        let mut suggestions = merge_by_key(results, |s| s.completion.clone(), Suggestion::absorb);
+        let history = self.sources.history.entries();
+        self.sources.scorer.apply(&mut suggestions, &request.line, &request.current_arg, &request.cwd, &history);
+        rank(suggestions, limit)
+    }
+
+    /// The line with its command name corrected, when that name is a typo of a known
+    /// command (`gti status` becomes `git status`), or else with its first misspelled
+    /// subcommand corrected (`git pshu` becomes `git push`)
+    pub fn correction(&self, line: &str) -> Option<String> {
+        let parsed = self.parser.parse(line, line.len()).ok()?;
+        if parsed.is_incomplete() {
+            return None;
+        }
+        let (word, start) = if parsed.position == WordPosition::Command {
+            (parsed.current_arg, parsed.current_arg_start)
+        } else if !parsed.command.is_empty() {
+            let offset = line[parsed.command_start..].find(parsed.command)?;
+            (parsed.command, parsed.command_start + offset)
+        } else {
+            return None;
+        };
+
+        let history = self.sources.history.entries();
+        if let Some(best) = self.sources.commands.corrections(word, &command_names(&history), 1).into_iter().next() {
+            return Some(format!("{}{}{}", &line[..start], best.completion, &line[start + word.len()..]));
+        }
+        if parsed.position == WordPosition::Command {
+            return None;
+        }
+
+        let mut words = parsed.args.clone();
+        if !parsed.current_arg.is_empty() {
+            words.push(parsed.current_arg);
+        }
+        let fix = self.sources.subcommands.correct(parsed.command, &words, !parsed.current_arg.is_empty())?;
+        // The words are slices of `line`, so the typo's place is where its slice starts
+        let typo = words[fix.index];
+        let start = typo.as_ptr() as usize - line.as_ptr() as usize;
+        Some(format!("{}{}{}", &line[..start], fix.corrected, &line[start + typo.len()..]))
+    }
+
+    /// Run every source on the blocking pool and keep what's back by the deadline
+    async fn run_parallel(&self, sources: &[Source], request: &Arc<Request>) -> Vec<Suggestion> {
+        let deadline = tokio::time::Instant::now() + SOURCE_DEADLINE;
+
+        let handles: Vec<_> = sources
+            .iter()
+            .map(|&source| {
+                let sources = Arc::clone(&self.sources);
+                let request = Arc::clone(request);
+                tokio::task::spawn_blocking(move || sources.run(source, &request))
+            })
+            .collect();
+
+        let mut suggestions = Vec::new();
+        for handle in handles {
+            // A late source keeps running in the background; its results are dropped
+            if let Ok(Ok(results)) = timeout_at(deadline, handle).await {
+                suggestions.extend(results);
+            }
+        }
+        suggestions
+    }
+
+    /// Run the sources in order on this thread, skipping the rest once the deadline passes
+    fn run_serial(&self, sources: &[Source], request: &Request) -> Vec<Suggestion> {
+        let deadline = Instant::now() + SOURCE_DEADLINE;
+
+        let mut suggestions = Vec::new();
+        for &source in sources {
+            if Instant::now() >= deadline {
+                break;
+            }
+            suggestions.extend(self.sources.run(source, request));
+        }
+        suggestions
+    }
+}
+
+/// The best `limit` suggestions, best first
+fn rank(mut merged: Vec<Suggestion>, limit: usize) -> Vec<Suggestion> {
+    merged.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.display.cmp(&b.display))
+    });
+    merged.truncate(limit);
+    merged
+}
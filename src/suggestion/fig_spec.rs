@@ -0,0 +1,865 @@
+//! Converting Fig completion specs
+//!
+//! The Fig (now Amazon Q) autocomplete corpus describes hundreds of CLIs as TypeScript
+//! objects, published compiled to JavaScript. `rustfig spec convert` reads either, or
+//! JSON, without running anything: the spec is parsed as a JavaScript object literal,
+//! with the constants it refers to resolved and spreads merged. What only JavaScript
+//! can produce (generator scripts, `loadSpec`, `generateSpec`) is left out and counted.
+
+use std::collections::HashMap;
+use std::error::Error;
+use serde_json::{Map, Value};
+
+use super::spec::{ArgSpec, CommandSpec, OptionSpec};
+
+/// How deep constants referring to constants are followed
+const MAX_RESOLVE_DEPTH: usize = 32;
+
+/// Keywords after which a `/` starts a regular expression
+const REGEX_KEYWORDS: &[&str] = &[
+    "return", "typeof", "instanceof", "in", "of", "new", "delete", "void", "throw", "case", "do", "else", "yield", "await",
+];
+
+/// A converted spec and what it couldn't carry over
+pub struct Conversion {
+    pub spec: CommandSpec,
+    pub dropped: Dropped,
+}
+
+/// Parts of a Fig spec with no equivalent in a native spec
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Dropped {
+    /// Generators that run scripts or JavaScript
+    pub generators: usize,
+    /// Subcommands whose spec is loaded from elsewhere (`loadSpec`, `generateSpec`)
+    pub loaded_specs: usize,
+    /// Names the spec refers to that aren't defined in the file, like imports
+    pub unresolved: usize,
+}
+
+impl Dropped {
+    pub fn is_empty(&self) -> bool {
+        self.generators == 0 && self.loaded_specs == 0 && self.unresolved == 0
+    }
+}
+
+/// Convert a Fig spec from JSON, JavaScript or TypeScript source
+pub fn convert(source: &str) -> Result<Conversion, Box<dyn Error>> {
+    let mut dropped = Dropped::default();
+    let value = match serde_json::from_str::<Value>(source) {
+        Ok(value) => value,
+        Err(_) => {
+            let module = Parser::new(source).module();
+            let spec = module.spec().ok_or("No completion spec found; expected `export default` or `completionSpec`")?;
+            resolve(spec, &module.declarations, 0, &mut dropped.unresolved)
+        }
+    };
+
+    let spec = command(&value, &mut dropped)
+        .into_iter()
+        .next()
+        .ok_or("The spec has no `name`")?;
+    Ok(Conversion { spec, dropped })
+}
+
+/// A JavaScript value, as far as a spec needs one; anything computed is `Opaque`
+#[derive(Debug, Clone)]
+enum Js {
+    Null,
+    Bool(bool),
+    Number(f64),
+    Str(String),
+    Array(Vec<Js>),
+    /// Entries in order; a spread is the key `...`
+    Object(Vec<(String, Js)>),
+    Ident(String),
+    Spread(Box<Js>),
+    Opaque,
+}
+
+/// The top-level declarations of a file and what it exports as default
+struct Module {
+    declarations: HashMap<String, Js>,
+    /// Declared names, in order
+    order: Vec<String>,
+    default_export: Option<Js>,
+}
+
+impl Module {
+    /// The default export, then `completionSpec`, then the last top-level object with a name
+    fn spec(&self) -> Option<&Js> {
+        self.default_export
+            .as_ref()
+            .or_else(|| self.declarations.get("completionSpec"))
+            .or_else(|| {
+                self.order.iter().rev().filter_map(|name| self.declarations.get(name)).find(|value| {
+                    matches!(value, Js::Object(entries) if entries.iter().any(|(key, _)| key == "name"))
+                })
+            })
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(source: &str) -> Self {
+        Self { chars: source.chars().collect(), pos: 0 }
+    }
+
+    /// Declarations and exports at the top level; every other statement is skipped
+    fn module(&mut self) -> Module {
+        let mut module = Module { declarations: HashMap::new(), order: Vec::new(), default_export: None };
+
+        loop {
+            let before = self.pos;
+            self.skip_trivia();
+            let Some(c) = self.peek() else { break };
+            if !is_ident_start(c) {
+                self.skip_token();
+                self.ensure_progress(before);
+                continue;
+            }
+
+            match self.ident().as_deref() {
+                Some("const" | "let" | "var") => self.declarators(&mut module),
+                Some("export") => {
+                    self.skip_trivia();
+                    if self.keyword("default") {
+                        module.default_export = Some(self.expression());
+                    } else if self.peek() == Some('{') {
+                        // `export { spec as default }`, as bundlers write it
+                        if let Some(name) = self.default_in_export_list() {
+                            module.default_export = Some(Js::Ident(name));
+                        }
+                    }
+                }
+                // `module.exports = ...`
+                Some("module") if self.eat_str(".exports") => {
+                    self.skip_trivia();
+                    if self.eat('=') {
+                        module.default_export = Some(self.expression());
+                    }
+                }
+                _ => {}
+            }
+            self.ensure_progress(before);
+        }
+
+        module
+    }
+
+    /// `a = 1, b: Type = 2;` after `const`
+    fn declarators(&mut self, module: &mut Module) {
+        loop {
+            self.skip_trivia();
+            let Some(name) = self.ident() else {
+                // Destructuring; nothing a spec needs
+                self.skip_expression();
+                return;
+            };
+            self.skip_trivia();
+            if self.eat(':') {
+                self.skip_type_annotation();
+            }
+            self.skip_trivia();
+            if self.peek() == Some('=') && self.peek_at(1) != Some('=') {
+                self.pos += 1;
+                let value = self.expression();
+                module.order.push(name.clone());
+                module.declarations.insert(name, value);
+            }
+            self.skip_trivia();
+            if !self.eat(',') {
+                return;
+            }
+        }
+    }
+
+    /// The local name exported as `default` from `{ a as b, c as default }`
+    fn default_in_export_list(&mut self) -> Option<String> {
+        self.pos += 1;
+        let mut found = None;
+        loop {
+            self.skip_trivia();
+            match self.peek() {
+                None => return found,
+                Some('}') => {
+                    self.pos += 1;
+                    return found;
+                }
+                Some(',') => self.pos += 1,
+                Some(c) if is_ident_start(c) => {
+                    let local = self.ident()?;
+                    self.skip_trivia();
+                    if self.keyword("as") {
+                        self.skip_trivia();
+                        if self.ident().as_deref() == Some("default") {
+                            found = Some(local);
+                        }
+                    }
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    /// A whole expression up to the next `,`, `;` or closing bracket; anything beyond a
+    /// single literal or name is `Opaque`, except TypeScript's `as` and `satisfies`
+    fn expression(&mut self) -> Js {
+        let value = self.value();
+        self.skip_trivia();
+        match self.peek() {
+            None | Some(',' | ';' | '}' | ']' | ')') => value,
+            Some(_) => {
+                let typed = self.keyword("as") || self.keyword("satisfies");
+                self.skip_expression();
+                if typed { value } else { Js::Opaque }
+            }
+        }
+    }
+
+    fn value(&mut self) -> Js {
+        self.skip_trivia();
+        match self.peek() {
+            Some('{') => self.object(),
+            Some('[') => self.array(),
+            Some(quote @ ('"' | '\'')) => {
+                self.pos += 1;
+                Js::Str(self.string(quote))
+            }
+            Some('`') => self.template(),
+            Some(c) if c.is_ascii_digit() || (c == '-' && self.peek_at(1).map_or(false, |d| d.is_ascii_digit())) => {
+                self.number()
+            }
+            Some(c) if is_ident_start(c) => {
+                let start = self.pos;
+                let name = self.ident().unwrap_or_default();
+                match name.as_str() {
+                    "true" => Js::Bool(true),
+                    "false" => Js::Bool(false),
+                    "null" | "undefined" => Js::Null,
+                    "function" | "async" | "new" | "class" => {
+                        self.pos = start;
+                        self.skip_expression();
+                        Js::Opaque
+                    }
+                    _ => {
+                        // A call or member access is computed
+                        self.skip_trivia();
+                        match self.peek() {
+                            Some('.' | '(' | '[') => {
+                                self.skip_expression();
+                                Js::Opaque
+                            }
+                            Some('=') if self.peek_at(1) == Some('>') => {
+                                self.skip_expression();
+                                Js::Opaque
+                            }
+                            _ => Js::Ident(name),
+                        }
+                    }
+                }
+            }
+            _ => {
+                self.skip_expression();
+                Js::Opaque
+            }
+        }
+    }
+
+    fn object(&mut self) -> Js {
+        self.pos += 1;
+        let mut entries = Vec::new();
+        loop {
+            let before = self.pos;
+            self.skip_trivia();
+            match self.peek() {
+                None => break,
+                Some('}') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(',') => self.pos += 1,
+                _ if self.eat_str("...") => entries.push(("...".to_string(), Js::Spread(Box::new(self.expression())))),
+                _ => entries.extend(self.property()),
+            }
+            self.ensure_progress(before);
+        }
+        Js::Object(entries)
+    }
+
+    /// One `key: value` of an object, or `None` for a computed key or something unparsable
+    fn property(&mut self) -> Option<(String, Js)> {
+        let key = match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                self.pos += 1;
+                Some(self.string(quote))
+            }
+            Some('[') => {
+                // A computed key; its value is skipped below
+                self.skip_balanced();
+                None
+            }
+            Some(c) if is_ident_start(c) || c.is_ascii_digit() => {
+                let mut key = self.word();
+                // `async name() {}`, `get name() {}`
+                self.skip_trivia();
+                if matches!(key.as_str(), "async" | "get" | "set") && self.peek().map_or(false, is_ident_start) {
+                    key = self.word();
+                }
+                Some(key)
+            }
+            _ => {
+                self.skip_expression();
+                return None;
+            }
+        };
+
+        self.skip_trivia();
+        let value = match self.peek() {
+            Some(':') => {
+                self.pos += 1;
+                self.expression()
+            }
+            Some('(') => {
+                // A method
+                self.skip_expression();
+                Js::Opaque
+            }
+            // `{ name }` is `{ name: name }`
+            _ => key.clone().map_or(Js::Opaque, Js::Ident),
+        };
+        key.map(|key| (key, value))
+    }
+
+    fn array(&mut self) -> Js {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            let before = self.pos;
+            self.skip_trivia();
+            match self.peek() {
+                None => break,
+                Some(']') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(',') => self.pos += 1,
+                _ if self.eat_str("...") => items.push(Js::Spread(Box::new(self.expression()))),
+                _ => {
+                    let start = self.pos;
+                    let item = self.expression();
+                    // A stray closing bracket is no item
+                    if self.pos > start {
+                        items.push(item);
+                    }
+                }
+            }
+            self.ensure_progress(before);
+        }
+        Js::Array(items)
+    }
+
+    /// The rest of a quoted string, escapes resolved
+    fn string(&mut self, quote: char) -> String {
+        let mut value = String::new();
+        while let Some(c) = self.next() {
+            match c {
+                c if c == quote => break,
+                '\\' => match self.next() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some('r') => value.push('\r'),
+                    Some('u') => value.extend(self.unicode_escape()),
+                    Some('x') => value.extend(self.hex_escape(2)),
+                    // A line continuation
+                    Some('\n') => {}
+                    Some(other) => value.push(other),
+                    None => break,
+                },
+                _ => value.push(c),
+            }
+        }
+        value
+    }
+
+    /// A template literal; one with substitutions is computed
+    fn template(&mut self) -> Js {
+        self.pos += 1;
+        let mut value = String::new();
+        let mut computed = false;
+        while let Some(c) = self.next() {
+            match c {
+                '`' => break,
+                '\\' => {
+                    if let Some(escaped) = self.next() {
+                        value.push(if escaped == 'n' { '\n' } else { escaped });
+                    }
+                }
+                '$' if self.peek() == Some('{') => {
+                    computed = true;
+                    self.skip_balanced();
+                }
+                _ => value.push(c),
+            }
+        }
+        if computed { Js::Opaque } else { Js::Str(value) }
+    }
+
+    fn unicode_escape(&mut self) -> Option<char> {
+        if self.eat('{') {
+            let start = self.pos;
+            while self.peek().map_or(false, |c| c != '}') {
+                self.pos += 1;
+            }
+            let hex: String = self.chars[start..self.pos].iter().collect();
+            self.pos += 1;
+            return u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32);
+        }
+        self.hex_escape(4)
+    }
+
+    fn hex_escape(&mut self, digits: usize) -> Option<char> {
+        let end = (self.pos + digits).min(self.chars.len());
+        let hex: String = self.chars[self.pos..end].iter().collect();
+        self.pos = end;
+        u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)
+    }
+
+    fn number(&mut self) -> Js {
+        let start = self.pos;
+        self.pos += 1;
+        while self.peek().map_or(false, |c| c.is_ascii_alphanumeric() || c == '.' || c == '_') {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().filter(|&&c| c != '_').collect();
+        text.parse().map_or(Js::Opaque, Js::Number)
+    }
+
+    /// Skip to the end of the expression at the cursor: the next `,` or `;` outside any
+    /// brackets, or the bracket closing the one it's in. At a stray closing bracket that
+    /// is nothing at all, so loops calling this must see to their own progress
+    fn skip_expression(&mut self) {
+        let mut depth = 0usize;
+        loop {
+            self.skip_trivia();
+            match self.peek() {
+                None => return,
+                Some('(' | '[' | '{') => {
+                    depth += 1;
+                    self.pos += 1;
+                }
+                Some(')' | ']' | '}') => {
+                    if depth == 0 {
+                        return;
+                    }
+                    depth -= 1;
+                    self.pos += 1;
+                }
+                Some(',' | ';') if depth == 0 => return,
+                Some(_) => self.skip_token(),
+            }
+        }
+    }
+
+    /// Skip the bracketed group at the cursor
+    fn skip_balanced(&mut self) {
+        let mut depth = 0usize;
+        loop {
+            self.skip_trivia();
+            match self.peek() {
+                None => return,
+                Some('(' | '[' | '{') => {
+                    depth += 1;
+                    self.pos += 1;
+                }
+                Some(')' | ']' | '}') => {
+                    self.pos += 1;
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                Some(_) => self.skip_token(),
+            }
+        }
+    }
+
+    /// Skip `Fig.Spec` in `const spec: Fig.Spec = ...`, up to the `=`
+    fn skip_type_annotation(&mut self) {
+        let mut depth = 0usize;
+        loop {
+            self.skip_trivia();
+            match self.peek() {
+                None => return,
+                Some('=') if depth == 0 && self.peek_at(1) != Some('>') => return,
+                Some(',' | ';') if depth == 0 => return,
+                Some('(' | '[' | '{' | '<') => {
+                    depth += 1;
+                    self.pos += 1;
+                }
+                Some(')' | ']' | '}' | '>') => {
+                    depth = depth.saturating_sub(1);
+                    self.pos += 1;
+                }
+                Some(_) => self.skip_token(),
+            }
+        }
+    }
+
+    /// Skip one string, template, regular expression, word or other character
+    fn skip_token(&mut self) {
+        match self.peek() {
+            Some('/') if self.regex_allowed() => self.skip_regex(),
+            Some(quote @ ('"' | '\'')) => {
+                self.pos += 1;
+                self.string(quote);
+            }
+            Some('`') => {
+                self.template();
+            }
+            Some(c) if is_ident_start(c) => {
+                self.word();
+            }
+            Some(_) => self.pos += 1,
+            None => {}
+        }
+    }
+
+    /// Whether a `/` at the cursor starts a regular expression rather than dividing: it
+    /// does where a value is expected, after an operator, bracket or keyword like `return`
+    fn regex_allowed(&self) -> bool {
+        let before = self.chars[..self.pos].iter().rposition(|c| !c.is_whitespace());
+        let Some(end) = before else { return true };
+        let c = self.chars[end];
+        if c == ')' || c == ']' || c == '}' || c == '.' {
+            return false;
+        }
+        if !(c.is_alphanumeric() || c == '_' || c == '$') {
+            return true;
+        }
+        let start = self.chars[..end].iter().rposition(|&c| !(c.is_alphanumeric() || c == '_' || c == '$')).map_or(0, |i| i + 1);
+        let word: String = self.chars[start..=end].iter().collect();
+        REGEX_KEYWORDS.contains(&word.as_str())
+    }
+
+    /// `/pattern/flags`; a `/` inside a character class or escaped doesn't end it
+    fn skip_regex(&mut self) {
+        self.pos += 1;
+        let mut in_class = false;
+        while let Some(c) = self.next() {
+            match c {
+                '\\' => {
+                    self.next();
+                }
+                '[' => in_class = true,
+                ']' => in_class = false,
+                '/' if !in_class => break,
+                // Regular expressions can't span lines; this was something else
+                '\n' => break,
+                _ => {}
+            }
+        }
+        self.word();
+    }
+
+    fn skip_trivia(&mut self) {
+        loop {
+            match (self.peek(), self.peek_at(1)) {
+                (Some(c), _) if c.is_whitespace() => self.pos += 1,
+                (Some('/'), Some('/')) => {
+                    while self.peek().map_or(false, |c| c != '\n') {
+                        self.pos += 1;
+                    }
+                }
+                (Some('/'), Some('*')) => {
+                    self.pos += 2;
+                    while self.pos < self.chars.len() && !(self.peek() == Some('*') && self.peek_at(1) == Some('/')) {
+                        self.pos += 1;
+                    }
+                    self.pos = (self.pos + 2).min(self.chars.len());
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn ident(&mut self) -> Option<String> {
+        self.peek().filter(|&c| is_ident_start(c))?;
+        Some(self.word())
+    }
+
+    /// Letters, digits, `_` and `$`
+    fn word(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().map_or(false, |c| c.is_alphanumeric() || c == '_' || c == '$') {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// Consume `word` if it's the whole next word
+    fn keyword(&mut self, word: &str) -> bool {
+        let start = self.pos;
+        if self.ident().as_deref() == Some(word) {
+            return true;
+        }
+        self.pos = start;
+        false
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn eat_str(&mut self, text: &str) -> bool {
+        let len = text.chars().count();
+        if self.pos + len <= self.chars.len() && self.chars[self.pos..self.pos + len].iter().copied().eq(text.chars()) {
+            self.pos += len;
+            return true;
+        }
+        false
+    }
+
+    /// Step over the character at the cursor if nothing was consumed since `before`, so
+    /// malformed input can't stall a loop
+    fn ensure_progress(&mut self, before: usize) {
+        if self.pos == before {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$'
+}
+
+/// `js` as JSON, with names looked up in `declarations` and spreads merged; what can't be
+/// known without running it is `null`
+fn resolve(js: &Js, declarations: &HashMap<String, Js>, depth: usize, unresolved: &mut usize) -> Value {
+    if depth > MAX_RESOLVE_DEPTH {
+        return Value::Null;
+    }
+    match js {
+        Js::Null | Js::Opaque => Value::Null,
+        Js::Bool(value) => Value::Bool(*value),
+        Js::Number(value) => serde_json::Number::from_f64(*value).map_or(Value::Null, Value::Number),
+        Js::Str(value) => Value::String(value.clone()),
+        Js::Ident(name) => match declarations.get(name) {
+            Some(value) => resolve(value, declarations, depth + 1, unresolved),
+            None => {
+                *unresolved += 1;
+                Value::Null
+            }
+        },
+        Js::Spread(inner) => resolve(inner, declarations, depth + 1, unresolved),
+        Js::Array(items) => {
+            let mut values = Vec::new();
+            for item in items {
+                match (item, resolve(item, declarations, depth + 1, unresolved)) {
+                    (Js::Spread(_), Value::Array(spread)) => values.extend(spread),
+                    (Js::Spread(_), _) => {}
+                    (_, value) => values.push(value),
+                }
+            }
+            Value::Array(values)
+        }
+        Js::Object(entries) => {
+            let mut map = Map::new();
+            for (key, value) in entries {
+                match (value, resolve(value, declarations, depth + 1, unresolved)) {
+                    (Js::Spread(_), Value::Object(spread)) => map.extend(spread),
+                    (Js::Spread(_), _) => {}
+                    (_, value) => {
+                        map.insert(key.clone(), value);
+                    }
+                }
+            }
+            Value::Object(map)
+        }
+    }
+}
+
+/// A Fig subcommand as native specs, one per name it goes by
+fn command(value: &Value, dropped: &mut Dropped) -> Vec<CommandSpec> {
+    let names = names(value.get("name"));
+    if names.is_empty() || is_hidden(value) {
+        return Vec::new();
+    }
+    if value.get("loadSpec").map_or(false, |load| !load.is_null()) || value.get("generateSpec").is_some() {
+        dropped.loaded_specs += 1;
+    }
+
+    let mut spec = CommandSpec::new(&names[0]);
+    spec.description = text(value.get("description"));
+    spec.subcommands = items(value.get("subcommands")).flat_map(|sub| command(sub, dropped)).collect();
+
+    let mut persistent = Vec::new();
+    for option_value in items(value.get("options")) {
+        if let Some(option) = option(option_value, dropped) {
+            if option_value.get("isPersistent").and_then(Value::as_bool).unwrap_or(false) {
+                persistent.push(option.clone());
+            }
+            spec.options.push(option);
+        }
+    }
+    spec.args = items(value.get("args")).map(|arg| argument(arg, dropped)).collect();
+    // Persistent options are accepted by every subcommand below
+    propagate(&mut spec.subcommands, &persistent);
+
+    let aliases = names[1..].iter().map(|alias| CommandSpec { name: alias.clone(), ..spec.clone() });
+    let mut specs: Vec<CommandSpec> = aliases.collect();
+    specs.insert(0, spec);
+    specs
+}
+
+fn option(value: &Value, dropped: &mut Dropped) -> Option<OptionSpec> {
+    let names = names(value.get("name"));
+    if names.is_empty() || is_hidden(value) {
+        return None;
+    }
+    let arg_value = items(value.get("args")).next();
+    let arg = arg_value.map(|arg| argument(arg, dropped));
+    Some(OptionSpec {
+        names,
+        description: text(value.get("description")),
+        value_name: arg_value.map(|arg| text(arg.get("name")).unwrap_or_else(|| "value".to_string())),
+        generator: None,
+        suggestions: arg.map(|arg| arg.suggestions).unwrap_or_default(),
+    })
+}
+
+fn argument(value: &Value, dropped: &mut Dropped) -> ArgSpec {
+    // Templates name what's completed; paths come from the path source anyway
+    let template = items(value.get("template")).chain(items(value.get("generators")).filter_map(|g| g.get("template"))).find_map(Value::as_str);
+    let scripted = items(value.get("generators")).filter(|generator| generator.get("template").is_none()).count();
+    dropped.generators += scripted;
+
+    let name = text(value.get("name")).unwrap_or_else(|| {
+        match template {
+            Some("folders") => "directory",
+            Some("filepaths") => "path",
+            _ => "arg",
+        }
+        .to_string()
+    });
+    ArgSpec {
+        name,
+        description: text(value.get("description")),
+        generator: None,
+        variadic: value.get("isVariadic").and_then(Value::as_bool).unwrap_or(false),
+        suggestions: items(value.get("suggestions"))
+            .filter(|suggestion| !is_hidden(suggestion))
+            .flat_map(|suggestion| match suggestion {
+                Value::String(name) => vec![name.clone()],
+                other => names(other.get("name")),
+            })
+            .collect(),
+    }
+}
+
+/// Add options to every subcommand below, unless it has its own of the same name
+fn propagate(subcommands: &mut [CommandSpec], options: &[OptionSpec]) {
+    if options.is_empty() {
+        return;
+    }
+    for sub in subcommands.iter_mut() {
+        for option in options {
+            if !sub.options.iter().any(|own| own.names.iter().any(|name| option.names.contains(name))) {
+                sub.options.push(option.clone());
+            }
+        }
+        propagate(&mut sub.subcommands, options);
+    }
+}
+
+/// Fig's `name`, a string or a list of names
+fn names(value: Option<&Value>) -> Vec<String> {
+    items(value).filter_map(Value::as_str).filter(|name| !name.is_empty()).map(str::to_string).collect()
+}
+
+/// A value that's either one item or a list of them
+fn items(value: Option<&Value>) -> Box<dyn Iterator<Item = &Value> + '_> {
+    match value {
+        Some(Value::Array(items)) => Box::new(items.iter()),
+        Some(Value::Null) | None => Box::new(std::iter::empty()),
+        Some(item) => Box::new(std::iter::once(item)),
+    }
+}
+
+fn text(value: Option<&Value>) -> Option<String> {
+    value.and_then(Value::as_str).filter(|text| !text.is_empty()).map(str::to_string)
+}
+
+fn is_hidden(value: &Value) -> bool {
+    value.get("hidden").and_then(Value::as_bool).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_typescript_spec() {
+        let source = r#"
+            const verbose: Fig.Option = { name: ["-v", "--verbose"], description: "Say more" };
+            const completionSpec: Fig.Spec = {
+                name: "tool",
+                subcommands: [{ name: "run", args: { name: "target", suggestions: ["a", "b"] } }],
+                options: [verbose],
+            };
+            export default completionSpec;
+        "#;
+        let spec = convert(source).unwrap().spec;
+        assert_eq!(spec.name, "tool");
+        assert_eq!(spec.options[0].names, ["-v", "--verbose"]);
+        assert_eq!(spec.subcommands[0].args[0].suggestions, ["a", "b"]);
+    }
+
+    #[test]
+    fn stray_brackets_dont_stall() {
+        let spec = convert(r#"export default { name: "x", ) }"#).unwrap().spec;
+        assert_eq!(spec.name, "x");
+
+        assert!(convert("export default [ ) ]").is_err());
+        assert!(convert("export default { name: ] ) }").is_err());
+        assert!(convert("export default { name: \"x\", options: [ } ] ) }").is_ok());
+    }
+
+    #[test]
+    fn skips_regular_expressions() {
+        let source = r#"export default {
+            name: "x",
+            args: {
+                name: "item",
+                generators: { script: ["ls"], postProcess: (o) => o.split(/[)]/).map((line) => ({ name: line })) },
+            },
+            options: [{ name: "--ratio", description: "a / b", args: { name: "n", default: 4 / 2 } }],
+        };"#;
+        let conversion = convert(source).unwrap();
+        assert_eq!(conversion.spec.args[0].name, "item");
+        assert_eq!(conversion.spec.options[0].names, ["--ratio"]);
+        assert_eq!(conversion.dropped.generators, 1);
+    }
+}
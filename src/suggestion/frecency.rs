@@ -0,0 +1,217 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+use crate::plugin::api::CompletionProvider;
+use super::{Suggestion, SuggestionKind};
+
+/// Name of the visited-directories database inside the user data directory
+pub const DIRS_FILE: &str = "dirs.json";
+
+/// Once ranks sum past this, every rank decays so old favorites fade out
+const MAX_TOTAL_RANK: f64 = 10_000.0;
+
+/// A directory the user has visited
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirEntry {
+    pub path: PathBuf,
+    /// Visit count, decayed over time
+    pub rank: f64,
+    /// Unix timestamp of the last visit
+    pub last_access: u64,
+}
+
+impl DirEntry {
+    /// Rank weighted by how recently the directory was visited
+    pub fn frecency(&self, now: u64) -> f64 {
+        let age = now.saturating_sub(self.last_access);
+        let recency = match age {
+            a if a < 3600 => 4.0,
+            a if a < 86400 => 2.0,
+            a if a < 7 * 86400 => 0.5,
+            _ => 0.25,
+        };
+        self.rank * recency
+    }
+}
+
+/// zoxide-style frecency database of visited directories
+pub struct FrecencyDb {
+    path: PathBuf,
+}
+
+impl FrecencyDb {
+    pub fn new(user_data_dir: &Path) -> Self {
+        Self {
+            path: user_data_dir.join(DIRS_FILE),
+        }
+    }
+
+    pub fn load(&self) -> Vec<DirEntry> {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, entries: &[DirEntry]) -> Result<(), Box<dyn Error>> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        // Write to a temporary file first so concurrent shells never read a torn file
+        let tmp_path = self.path.with_extension(format!("json.{}.tmp", std::process::id()));
+        fs::write(&tmp_path, serde_json::to_string(entries)?)?;
+        fs::rename(tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Record a visit to `dir`
+    pub fn visit(&self, dir: &Path) -> Result<(), Box<dyn Error>> {
+        // The home directory is one keystroke away already
+        if dirs::home_dir().as_deref() == Some(dir) || !dir.is_absolute() {
+            return Ok(());
+        }
+
+        let now = now();
+        let mut entries = self.load();
+
+        match entries.iter_mut().find(|entry| entry.path == dir) {
+            Some(entry) => {
+                entry.rank += 1.0;
+                entry.last_access = now;
+            }
+            None => entries.push(DirEntry {
+                path: dir.to_path_buf(),
+                rank: 1.0,
+                last_access: now,
+            }),
+        }
+
+        // Age everything once the database grows too heavy, dropping what decays below one visit
+        let total: f64 = entries.iter().map(|entry| entry.rank).sum();
+        if total > MAX_TOTAL_RANK {
+            for entry in &mut entries {
+                entry.rank *= 0.9;
+            }
+            entries.retain(|entry| entry.rank >= 1.0);
+        }
+
+        self.save(&entries)
+    }
+
+    /// Existing directories matching the query, best first
+    pub fn query(&self, terms: &[&str]) -> Vec<(DirEntry, f64)> {
+        let now = now();
+        let cwd = std::env::current_dir().ok();
+
+        let mut matches: Vec<(DirEntry, f64)> = self.load()
+            .into_iter()
+            .filter(|entry| cwd.as_deref() != Some(entry.path.as_path()))
+            .filter(|entry| matches_terms(&entry.path, terms))
+            .filter(|entry| entry.path.is_dir())
+            .map(|entry| {
+                let score = entry.frecency(now);
+                (entry, score)
+            })
+            .collect();
+
+        matches.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        matches
+    }
+
+    /// Remove a directory from the database
+    pub fn remove(&self, dir: &Path) -> Result<bool, Box<dyn Error>> {
+        let mut entries = self.load();
+        let before = entries.len();
+        entries.retain(|entry| entry.path != dir);
+
+        if entries.len() == before {
+            return Ok(false);
+        }
+        self.save(&entries)?;
+        Ok(true)
+    }
+}
+
+/// zoxide's matching rules: terms appear in order (case-insensitively), and the last
+/// term matches within the last path component
+pub fn matches_terms(path: &Path, terms: &[&str]) -> bool {
+    let path_str = path.to_string_lossy().to_lowercase();
+    let mut position = 0;
+
+    for term in terms {
+        let term = term.to_lowercase();
+        match path_str[position..].find(&term) {
+            Some(found) => position += found + term.len(),
+            None => return false,
+        }
+    }
+
+    match terms.last() {
+        Some(last) => {
+            let last_component = path.file_name().map(|name| name.to_string_lossy().to_lowercase()).unwrap_or_default();
+            last_component.contains(&last.to_lowercase())
+        }
+        None => true,
+    }
+}
+
+/// Completes `cd <query>` with frecent directories from anywhere on disk
+pub struct FrecencyCompleter {
+    db: FrecencyDb,
+}
+
+impl FrecencyCompleter {
+    pub fn new(user_data_dir: &Path) -> Self {
+        Self {
+            db: FrecencyDb::new(user_data_dir),
+        }
+    }
+}
+
+impl CompletionProvider for FrecencyCompleter {
+    fn name(&self) -> &str {
+        "frecency"
+    }
+
+    fn can_provide_completions(&self, command: &str) -> bool {
+        matches!(command, "cd" | "pushd")
+    }
+
+    fn provide_completions(&self, _command: &str, _args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        // Paths typed explicitly are left to the path suggester
+        if current_arg.is_empty() || current_arg.contains('/') || current_arg.starts_with(['.', '~', '-']) {
+            return Vec::new();
+        }
+
+        let home = dirs::home_dir();
+
+        self.db.query(&[current_arg])
+            .into_iter()
+            .take(5)
+            .map(|(entry, score)| {
+                // Show `~/…` but complete the absolute path, escaping what the shell would split
+                let display = match home.as_deref().and_then(|home| entry.path.strip_prefix(home).ok()) {
+                    Some(relative) => format!("~/{}", relative.display()),
+                    None => entry.path.display().to_string(),
+                };
+                let completion = entry.path.to_string_lossy().replace(' ', "\\ ");
+
+                Suggestion::new(display, completion, SuggestionKind::Directory)
+                    .with_description("frecent".to_string())
+                    // Keep frecent jumps in the same range as fuzzy-scored sources
+                    .with_score(50.0 + score.min(50.0) as f32)
+            })
+            .collect()
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
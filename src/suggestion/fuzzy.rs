@@ -0,0 +1,181 @@
+use crate::utils::simd;
+
+/// Score a candidate against a typed pattern, or `None` if it doesn't match
+///
+/// Exact-case prefixes score highest, then case-insensitive prefixes, then
+/// subsequence matches (`crgo` ~ `cargo`), which are rewarded for contiguous
+/// runs and matches at word boundaries. Scores are in the range 0.0-1.0.
+pub fn fast_fuzzy_score(pattern: &str, candidate: &str) -> Option<f32> {
+    if pattern.is_empty() {
+        return Some(0.5);
+    }
+
+    // Commands, flags and paths are nearly always ASCII, which the SIMD kernels (NEON,
+    // AVX2 or SSE2) compare without building lowercase copies
+    if pattern.is_ascii() && candidate.is_ascii() {
+        return ascii_score(pattern, candidate);
+    }
+
+    scalar_fuzzy_score(pattern, candidate)
+}
+
+/// `fast_fuzzy_score` without the SIMD fast path; used for non-ASCII input, and by
+/// `rustfig bench` as the baseline
+pub fn scalar_fuzzy_score(pattern: &str, candidate: &str) -> Option<f32> {
+    if pattern.is_empty() {
+        return Some(0.5);
+    }
+
+    if candidate.starts_with(pattern) {
+        return Some(1.0 - length_penalty(pattern, candidate));
+    }
+
+    let pattern_lower = pattern.to_lowercase();
+    let candidate_lower = candidate.to_lowercase();
+
+    if candidate_lower.starts_with(&pattern_lower) {
+        return Some(0.9 - length_penalty(pattern, candidate));
+    }
+
+    subsequence_score(&pattern_lower, &candidate_lower)
+        .map(|score| score * 0.7 - length_penalty(pattern, candidate))
+}
+
+/// `scalar_fuzzy_score` for ASCII strings, with identical results
+fn ascii_score(pattern: &str, candidate: &str) -> Option<f32> {
+    let (pattern_bytes, candidate_bytes) = (pattern.as_bytes(), candidate.as_bytes());
+    if simd::starts_with(candidate_bytes, pattern_bytes) {
+        return Some(1.0 - length_penalty(pattern, candidate));
+    }
+
+    if simd::starts_with_ignore_case(candidate_bytes, pattern_bytes) {
+        return Some(0.9 - length_penalty(pattern, candidate));
+    }
+
+    let mut subsequence = Subsequence::default();
+    let mut position = 0;
+    for p in pattern_bytes {
+        let index = position + simd::find_ignore_case(&candidate_bytes[position..], p.to_ascii_lowercase())?;
+        subsequence.matched_at(index, index == 0 || is_separator(candidate_bytes[index - 1] as char));
+        position = index + 1;
+    }
+
+    Some(subsequence.score(candidate_bytes.len()) * 0.7 - length_penalty(pattern, candidate))
+}
+
+/// Whether a candidate matches the pattern at all (cheaper than scoring)
+pub fn fuzzy_matches(pattern: &str, candidate: &str) -> bool {
+    let mut candidate_chars = candidate.chars().flat_map(char::to_lowercase);
+
+    pattern
+        .chars()
+        .flat_map(char::to_lowercase)
+        .all(|p| candidate_chars.any(|c| c == p))
+}
+
+/// Score an in-order subsequence match between lowercase strings
+fn subsequence_score(pattern: &str, candidate: &str) -> Option<f32> {
+    let candidate: Vec<char> = candidate.chars().collect();
+    let mut subsequence = Subsequence::default();
+    let mut position = 0;
+
+    for p in pattern.chars() {
+        let index = position + candidate[position..].iter().position(|&c| c == p)?;
+        subsequence.matched_at(index, index == 0 || is_separator(candidate[index - 1]));
+        position = index + 1;
+    }
+
+    Some(subsequence.score(candidate.len()))
+}
+
+/// Running score of an in-order subsequence match
+#[derive(Default)]
+struct Subsequence {
+    matched: usize,
+    consecutive: usize,
+    bonus: f32,
+    last_match: Option<usize>,
+}
+
+impl Subsequence {
+    /// Record the next pattern character, found at `index` of the candidate
+    fn matched_at(&mut self, index: usize, after_separator: bool) {
+        if self.last_match == Some(index.wrapping_sub(1)) {
+            self.consecutive += 1;
+            self.bonus += 0.1 * self.consecutive as f32;
+        } else {
+            self.consecutive = 0;
+        }
+
+        // Matching right after a separator reads like an abbreviation
+        if after_separator {
+            self.bonus += 0.2;
+        }
+
+        self.matched += 1;
+        self.last_match = Some(index);
+    }
+
+    fn score(&self, candidate_len: usize) -> f32 {
+        let coverage = self.matched as f32 / candidate_len.max(1) as f32;
+        (0.4 + coverage * 0.3 + self.bonus.min(0.3)).min(1.0)
+    }
+}
+
+fn is_separator(c: char) -> bool {
+    matches!(c, '-' | '_' | '.' | '/' | ' ')
+}
+
+/// Slightly prefer shorter candidates among otherwise equal matches
+fn length_penalty(pattern: &str, candidate: &str) -> f32 {
+    let extra = candidate.len().saturating_sub(pattern.len()) as f32;
+    (extra / 200.0).min(0.05)
+}
+
+/// Shortest word checked for typos; `sl` still counts
+const MIN_TYPO_LEN: usize = 2;
+
+/// Words this long may be two edits off (`pshu` for `push`), shorter ones only one
+const LONG_TYPO_LEN: usize = 4;
+
+/// How many edits away a correction for `word` may be, or None if it's too short to tell
+pub fn max_typo_edits(word: &str) -> Option<usize> {
+    match word.chars().count() {
+        length if length < MIN_TYPO_LEN => None,
+        length if length < LONG_TYPO_LEN => Some(1),
+        _ => Some(2),
+    }
+}
+
+/// Damerau-Levenshtein distance (optimal string alignment) between two words, counting a
+/// swap of neighbouring characters as one edit; `None` once it's certain to exceed `max`
+pub fn edit_distance(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    // Three rows: two back for transpositions, the previous one, and the one being filled
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current: Vec<usize> = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1).min(current[j - 1] + 1).min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        // Every later row builds on these two, so past `max` here is past it for good
+        if current.iter().chain(previous.iter()).all(|&distance| distance > max) {
+            return None;
+        }
+        std::mem::swap(&mut before, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    Some(previous[b.len()]).filter(|&distance| distance <= max)
+}
@@ -0,0 +1,126 @@
+//! Git status on path suggestions
+//!
+//! In a repository, path suggestions show what git thinks of each entry (modified,
+//! untracked, staged, conflicted) and rank changed ones first, so `git add <Tab>` and
+//! `vim <Tab>` lead with the files being worked on. A directory shows the most pressing
+//! status of the changes inside it. Ignored files need no badge: the path suggester
+//! doesn't list them. With `suggestions.git_commit_age`, unchanged entries show how
+//! long ago they were last committed instead.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use parking_lot::Mutex;
+
+use super::completers::git::{FileStatus, GitRepository};
+use crate::ai::conversation::format_age;
+
+/// How long a directory's status is reused; long enough to cover a burst of keystrokes
+const STATUS_TTL: Duration = Duration::from_secs(2);
+
+/// Commits looked through for commit ages
+const MAX_AGE_COMMITS: usize = 200;
+
+/// Directories whose status is kept
+const MAX_CACHED_DIRS: usize = 64;
+
+/// What git says about the entries of one directory
+#[derive(Debug, Default)]
+pub struct DirBadges {
+    /// Entries that are, or contain, changes
+    pub statuses: HashMap<String, FileStatus>,
+    /// When entries were last committed, as Unix seconds
+    pub committed: HashMap<String, u64>,
+}
+
+impl DirBadges {
+    /// The badge text for an entry: its status, or how long ago it was committed
+    pub fn describe(&self, name: &str) -> Option<String> {
+        if let Some(status) = self.statuses.get(name) {
+            return Some(format!("{} {}", status.badge(), status.label()));
+        }
+        self.committed.get(name).map(|&time| format!("committed {}", format_age(time)))
+    }
+
+    /// Ranking bonus for an entry, on the path suggester's 0-1 scale
+    pub fn bonus(&self, name: &str) -> f32 {
+        match self.statuses.get(name) {
+            Some(FileStatus::Conflicted) => 0.2,
+            Some(FileStatus::Modified | FileStatus::Untracked) => 0.15,
+            Some(FileStatus::Staged) => 0.1,
+            Some(FileStatus::Deleted) | None => 0.0,
+        }
+    }
+}
+
+/// Badges per listed directory, refreshed every `STATUS_TTL`
+pub struct GitBadges {
+    commit_age: bool,
+    /// `None` records a directory outside any repository
+    cache: Mutex<HashMap<PathBuf, (Instant, Option<Arc<DirBadges>>)>>,
+}
+
+impl GitBadges {
+    pub fn new(commit_age: bool) -> Self {
+        Self {
+            commit_age,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Badges for the entries of `dir`, or `None` outside a repository
+    pub fn for_dir(&self, dir: &Path) -> Option<Arc<DirBadges>> {
+        if let Some((read_at, badges)) = self.cache.lock().get(dir) {
+            if read_at.elapsed() < STATUS_TTL {
+                return badges.clone();
+            }
+        }
+
+        let badges = self.read(dir).map(Arc::new);
+
+        let mut cache = self.cache.lock();
+        if cache.len() >= MAX_CACHED_DIRS {
+            cache.retain(|_, (read_at, _)| read_at.elapsed() < STATUS_TTL);
+        }
+        cache.insert(dir.to_path_buf(), (Instant::now(), badges.clone()));
+        badges
+    }
+
+    fn read(&self, dir: &Path) -> Option<DirBadges> {
+        let repo = GitRepository::discover(dir)?;
+        let work_dir = repo.work_dir()?.canonicalize().ok()?;
+        let dir = dir.canonicalize().ok()?;
+        let relative = dir.strip_prefix(&work_dir).ok()?;
+
+        let mut badges = DirBadges::default();
+        for entry in repo.status() {
+            // Changes further down count for the entry they're under
+            let Ok(rest) = Path::new(&entry.path).strip_prefix(relative) else { continue };
+            let Some(name) = rest.components().next() else { continue };
+            let name = name.as_os_str().to_string_lossy().to_string();
+            badges
+                .statuses
+                .entry(name)
+                .and_modify(|status| *status = most_pressing(*status, entry.status))
+                .or_insert(entry.status);
+        }
+
+        if self.commit_age {
+            badges.committed = repo.last_commits(relative, MAX_AGE_COMMITS);
+        }
+        Some(badges)
+    }
+}
+
+/// The status to show for a directory with changes of both kinds
+fn most_pressing(a: FileStatus, b: FileStatus) -> FileStatus {
+    let rank = |status| match status {
+        FileStatus::Conflicted => 4,
+        FileStatus::Modified => 3,
+        FileStatus::Staged => 2,
+        FileStatus::Untracked => 1,
+        FileStatus::Deleted => 0,
+    };
+    if rank(b) > rank(a) { b } else { a }
+}
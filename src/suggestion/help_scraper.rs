@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+use super::spec::{CommandSpec, OptionSpec};
+use super::spec_cache::SpecCache;
+use super::Suggestion;
+use crate::config::Config;
+use crate::plugin::api::CompletionProvider;
+use crate::utils::process::run_with_timeout;
+use crate::utils::cache_file;
+
+/// Maximum amount of help output we are willing to read and parse
+const MAX_HELP_OUTPUT: u64 = 256 * 1024;
+
+/// Commands that must never be executed speculatively, even with `--help`
+const DENYLIST: &[&str] = &[
+    "halt", "poweroff", "reboot", "shutdown", "init", "telinit",
+    "kill", "killall", "pkill", "sudo", "su", "doas", "mkfs",
+];
+
+/// Identifies a specific build of a binary so cached specs can be invalidated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BinaryFingerprint {
+    /// Modification time in seconds since the epoch
+    pub mtime: u64,
+    /// File size in bytes
+    pub size: u64,
+}
+
+impl BinaryFingerprint {
+    /// Fingerprint the binary at the given path
+    pub fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let mtime = metadata.modified().ok()?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        Some(Self {
+            mtime,
+            size: metadata.len(),
+        })
+    }
+}
+
+/// Scraping result for one binary; `None` records a failed attempt so we don't retry it
+struct ScrapedSpec {
+    fingerprint: BinaryFingerprint,
+    spec: Option<Arc<CommandSpec>>,
+}
+
+/// Opt-in generator that synthesizes specs from `<cmd> --help` output
+pub struct HelpScraper {
+    enabled: bool,
+    timeout: Duration,
+    cache: RwLock<HashMap<PathBuf, ScrapedSpec>>,
+    disk_cache: SpecCache,
+}
+
+impl HelpScraper {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            enabled: config.suggestions.enable_help_scraping.unwrap_or(false),
+            timeout: Duration::from_millis(config.suggestions.help_scrape_timeout_ms.unwrap_or(500)),
+            cache: RwLock::new(HashMap::new()),
+            disk_cache: SpecCache::new(&config.general.user_data_dir, cache_file::compression_enabled(config)),
+        }
+    }
+
+    /// Get the generated spec for a command, scraping it if the binary changed
+    pub fn spec_for(&self, command: &str) -> Option<Arc<CommandSpec>> {
+        if !self.enabled {
+            return None;
+        }
+
+        let binary = resolve_binary(command).filter(|binary| !is_denied(binary))?;
+        let fingerprint = BinaryFingerprint::of(&binary)?;
+
+        // Fast path: cached result for this exact build of the binary
+        {
+            let cache = self.cache.read();
+            if let Some(entry) = cache.get(&binary) {
+                if entry.fingerprint == fingerprint {
+                    return entry.spec.clone();
+                }
+            }
+        }
+
+        // Next best: a spec generated by an earlier session for the same build
+        let spec = match self.disk_cache.load(&binary, fingerprint) {
+            Some(cached) => cached.map(Arc::new),
+            None => {
+                let spec = Self::generate(command, &binary, self.timeout);
+                if let Err(e) = self.disk_cache.store(&binary, fingerprint, spec.as_ref()) {
+                    eprintln!("Failed to persist generated spec for {}: {}", command, e);
+                }
+                spec.map(Arc::new)
+            }
+        };
+
+        self.cache.write().insert(binary, ScrapedSpec {
+            fingerprint,
+            spec: spec.clone(),
+        });
+
+        spec
+    }
+
+    /// Run the binary with `--help` and parse the output into a spec
+    ///
+    /// There's no `-h` fallback: plenty of tools give `-h` another meaning (`shutdown -h`
+    /// halts), and a tool that ignores unknown flags would just run.
+    pub fn generate(command: &str, binary: &Path, timeout: Duration) -> Option<CommandSpec> {
+        let output = run_help(binary, &["--help"], timeout)?;
+        let spec = Self::parse_help(command, &output);
+        (!spec.options.is_empty() || !spec.subcommands.is_empty()).then_some(spec)
+    }
+
+    /// Parse a conventional help text (flag table and command list) into a spec
+    pub fn parse_help(command: &str, text: &str) -> CommandSpec {
+        let mut spec = CommandSpec::new(command);
+        let mut in_commands_section = false;
+
+        for line in text.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            // Section headers are unindented lines like "Options:" or "Available Commands:"
+            if !line.starts_with(char::is_whitespace) {
+                let header = line.trim().to_lowercase();
+                in_commands_section = header.ends_with(':') && header.contains("command");
+
+                if spec.description.is_none() && !header.is_empty() && !header.ends_with(':')
+                    && !header.starts_with("usage")
+                {
+                    spec.description = Some(line.trim().to_string());
+                }
+                continue;
+            }
+
+            let trimmed = line.trim_start();
+
+            if trimmed.starts_with('-') {
+                if let Some(option) = parse_option_line(trimmed) {
+                    if !spec.options.iter().any(|o| o.names == option.names) {
+                        spec.options.push(option);
+                    }
+                }
+            } else if in_commands_section {
+                if let Some(sub) = parse_command_line(trimmed) {
+                    if spec.subcommand(&sub.name).is_none() {
+                        spec.subcommands.push(sub);
+                    }
+                }
+            }
+        }
+
+        spec
+    }
+}
+
+impl CompletionProvider for HelpScraper {
+    fn name(&self) -> &str {
+        "help-scraper"
+    }
+
+    fn can_provide_completions(&self, command: &str) -> bool {
+        self.enabled && resolve_binary(command).map_or(false, |binary| !is_denied(&binary))
+    }
+
+    fn provide_completions(&self, command: &str, args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        match self.spec_for(command) {
+            Some(spec) => spec.suggestions_for(args, current_arg),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Locate an executable on PATH
+pub fn resolve_binary(command: &str) -> Option<PathBuf> {
+    if command.is_empty() {
+        return None;
+    }
+
+    if command.contains('/') {
+        let path = PathBuf::from(command);
+        return if path.is_file() { Some(path) } else { None };
+    }
+
+    let path_var = env::var_os("PATH")?;
+    env::split_paths(&path_var)
+        .map(|dir| dir.join(command))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Whether `binary` is on the denylist, by its own name or, through a symlink, its
+/// target's; `/sbin/reboot` and `./shutdown` are as dangerous as `reboot`
+pub fn is_denied(binary: &Path) -> bool {
+    let denied = |path: &Path| {
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .map_or(false, |name| DENYLIST.contains(&name))
+    };
+    denied(binary) || binary.canonicalize().map_or(false, |target| denied(&target))
+}
+
+/// Execute `<binary> <args>` (a help flag, maybe after a subcommand) with a scrubbed
+/// environment, no stdin, and a hard timeout
+pub fn run_help(binary: &Path, args: &[&str], timeout: Duration) -> Option<String> {
+    let mut command = Command::new(binary);
+    command
+        .args(args)
+        .env_clear()
+        .env("PATH", env::var_os("PATH").unwrap_or_default())
+        .env("LC_ALL", "C")
+        .env("TERM", "dumb")
+        .env("NO_COLOR", "1")
+        .env("PAGER", "cat")
+        .current_dir(env::temp_dir());
+
+    let captured = run_with_timeout(&mut command, timeout, MAX_HELP_OUTPUT)?;
+
+    // Many tools print help to stderr, so prefer whichever stream has content
+    let output = if captured.stdout.is_empty() {
+        captured.stderr
+    } else {
+        captured.stdout
+    };
+
+    if output.is_empty() {
+        None
+    } else {
+        Some(String::from_utf8_lossy(&output).into_owned())
+    }
+}
+
+/// Parse a flag table row such as `-a, --all  do not ignore entries starting with .`
+pub fn parse_option_line(line: &str) -> Option<OptionSpec> {
+    // The flag column ends at the first run of two spaces or a tab
+    let (flags, description) = match line.find("  ").or_else(|| line.find('\t')) {
+        Some(idx) => (&line[..idx], line[idx..].trim()),
+        None => (line, ""),
+    };
+
+    let mut option = OptionSpec::default();
+
+    for token in flags.split(|c: char| c == ',' || c.is_whitespace()) {
+        let token = token.trim();
+        if token.is_empty() {
+            continue;
+        }
+
+        if token.starts_with('-') {
+            // Split `--block-size=SIZE` and `--color[=WHEN]` into name and value
+            let (name, value) = match token.find(|c: char| c == '=' || c == '[') {
+                Some(idx) => (&token[..idx], Some(&token[idx..])),
+                None => (token, None),
+            };
+
+            let valid = name.len() > 1
+                && name.trim_start_matches('-').chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '?');
+
+            if !valid {
+                return None;
+            }
+
+            option.names.push(name.to_string());
+
+            if let Some(value) = value {
+                option.value_name = Some(clean_value_name(value));
+            }
+        } else if option.value_name.is_none() {
+            option.value_name = Some(clean_value_name(token));
+        }
+    }
+
+    if option.names.is_empty() {
+        return None;
+    }
+
+    if !description.is_empty() {
+        option.description = Some(description.to_string());
+    }
+
+    Some(option)
+}
+
+/// Parse a command list row such as `  build    Compile the current package`
+fn parse_command_line(line: &str) -> Option<CommandSpec> {
+    let (name, description) = match line.find("  ") {
+        Some(idx) => (&line[..idx], line[idx..].trim()),
+        None => (line.trim(), ""),
+    };
+
+    let name = name.trim().trim_end_matches(',');
+    let valid = name.chars().next().map_or(false, |c| c.is_ascii_lowercase())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if !valid {
+        return None;
+    }
+
+    let mut spec = CommandSpec::new(name);
+    if !description.is_empty() {
+        spec.description = Some(description.to_string());
+    }
+
+    Some(spec)
+}
+
+/// Strip decoration from value placeholders (`=<FILE>`, `[=WHEN]`)
+fn clean_value_name(value: &str) -> String {
+    value
+        .trim_matches(|c: char| c == '=' || c == '[' || c == ']' || c == '<' || c == '>')
+        .trim_start_matches('=')
+        .to_string()
+}
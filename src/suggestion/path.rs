@@ -0,0 +1,274 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use super::dir_index::{scan_directory, DirectoryIndex, IndexedDir};
+use super::fuzzy::fast_fuzzy_score;
+#[cfg(feature = "git-integration")]
+use super::git_badges::{DirBadges, GitBadges};
+use super::{Suggestion, SuggestionKind};
+use crate::config::Config;
+use crate::utils::memory::{self, MemoryTier};
+use crate::utils::system::format_age;
+
+/// Upper bound on directory entries listed inline while the index is still building
+const MAX_ENTRIES_SCANNED: usize = 5000;
+
+/// Suggests files and directories for path arguments
+pub struct PathSuggester {
+    ignored_dirs: Vec<String>,
+    fuzzy_matching: bool,
+    index: Arc<DirectoryIndex>,
+    /// Show type, size and age columns; toggled at the prompt
+    details: AtomicBool,
+    /// Git status of entries in a repository, with `suggestions.git_badges`
+    #[cfg(feature = "git-integration")]
+    git_badges: Option<GitBadges>,
+}
+
+/// A directory entry that matched the typed fragment
+struct PathCandidate {
+    name: String,
+    is_dir: bool,
+    score: f32,
+    /// What git says about it, like "M modified"
+    badge: Option<String>,
+}
+
+impl PathSuggester {
+    pub fn new(config: &Config) -> Self {
+        let index = Arc::new(DirectoryIndex::new(
+            config.suggestions.ignored_dirs.clone(),
+            Duration::from_secs(config.suggestions.cache_lifetime_secs),
+        ));
+        memory::register(MemoryTier::DirectoryIndex, &index);
+
+        Self {
+            ignored_dirs: config.suggestions.ignored_dirs.clone(),
+            fuzzy_matching: config.suggestions.fuzzy_matching.unwrap_or(true),
+            index,
+            details: AtomicBool::new(config.suggestions.path_details.unwrap_or(false)),
+            #[cfg(feature = "git-integration")]
+            git_badges: config.suggestions.git_badges.unwrap_or(true).then(|| {
+                GitBadges::new(config.suggestions.git_commit_age.unwrap_or(false))
+            }),
+        }
+    }
+
+    /// Suggest completions for a partially typed path relative to `cwd`
+    pub fn suggest(&self, partial: &str, cwd: &Path, limit: usize) -> Vec<Suggestion> {
+        // Split `src/sug` into the directory to list (`src/`) and the fragment to match (`sug`)
+        let (dir_part, fragment) = match partial.rfind('/') {
+            Some(idx) => (&partial[..=idx], &partial[idx + 1..]),
+            None => ("", partial),
+        };
+
+        let dir = resolve_dir(dir_part, cwd);
+        if !dir.is_dir() {
+            return Vec::new();
+        }
+
+        let mut candidates = self.collect_candidates(&dir, fragment);
+
+        candidates.sort_by(|a, b| {
+            b.score.partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        candidates.truncate(limit);
+        let details = self.details.load(Ordering::Relaxed);
+        let now = SystemTime::now();
+
+        candidates.into_iter()
+            .map(|candidate| {
+                let (display, kind) = if candidate.is_dir {
+                    (format!("{}/", candidate.name), SuggestionKind::Directory)
+                } else {
+                    (candidate.name.clone(), SuggestionKind::File)
+                };
+
+                let suggestion = Suggestion::new(display.clone(), format!("{}{}", dir_part, display), kind)
+                    .with_score(candidate.score * 100.0);
+                let details = details.then(|| entry_details(&dir.join(&candidate.name), now));
+                match (details, candidate.badge) {
+                    (Some(details), Some(badge)) => suggestion.with_description(format!("{}  {}", details, badge)),
+                    (Some(text), None) | (None, Some(text)) => suggestion.with_description(text),
+                    (None, None) => suggestion,
+                }
+            })
+            .collect()
+    }
+
+    /// Score each entry of a directory listing against the fragment
+    fn collect_candidates(&self, dir: &Path, fragment: &str) -> Vec<PathCandidate> {
+        // Only show dotfiles once the user has started typing one
+        let show_hidden = fragment.starts_with('.');
+        let listing = self.listing(dir);
+        let badges = self.dir_badges(dir);
+
+        let now = SystemTime::now();
+        let mut candidates = Vec::new();
+
+        for entry in listing.entries.iter().filter(|e| show_hidden || !e.is_hidden) {
+            let match_score = if self.fuzzy_matching {
+                fast_fuzzy_score(fragment, &entry.name)
+            } else if entry.name.starts_with(fragment) {
+                Some(1.0)
+            } else {
+                None
+            };
+
+            let match_score = match match_score {
+                Some(score) => score,
+                None => continue,
+            };
+
+            // Files being worked on rank above ones that weren't touched
+            let (git_bonus, badge) = git_badge(&badges, &entry.name);
+
+            let score = match_score * 0.8
+                + recency_bonus(entry.modified, now)
+                + git_bonus
+                + if entry.is_dir { 0.05 } else { 0.0 };
+
+            candidates.push(PathCandidate {
+                name: entry.name.clone(),
+                is_dir: entry.is_dir,
+                score: score.min(1.0),
+                badge,
+            });
+        }
+
+        candidates
+    }
+
+    /// Show or hide the type, size and age columns; returns whether they're shown now
+    pub fn toggle_details(&self) -> bool {
+        !self.details.fetch_xor(true, Ordering::Relaxed)
+    }
+
+    /// Git status of the entries of `dir`, when it's in a repository
+    #[cfg(feature = "git-integration")]
+    fn dir_badges(&self, dir: &Path) -> Option<Arc<DirBadges>> {
+        self.git_badges.as_ref().and_then(|git| git.for_dir(dir))
+    }
+
+    #[cfg(not(feature = "git-integration"))]
+    fn dir_badges(&self, _dir: &Path) -> Option<()> {
+        None
+    }
+
+    /// Indexed listing if ready, otherwise a bounded inline scan while the index builds
+    fn listing(&self, dir: &Path) -> Arc<IndexedDir> {
+        if let Some(indexed) = self.index.get(dir) {
+            return indexed;
+        }
+
+        Arc::new(IndexedDir::new(scan_directory(dir, &self.ignored_dirs, Some(MAX_ENTRIES_SCANNED))))
+    }
+}
+
+/// Resolve the directory portion of a typed path (`~/`, absolute, or relative)
+fn resolve_dir(dir_part: &str, cwd: &Path) -> PathBuf {
+    if dir_part.is_empty() {
+        return cwd.to_path_buf();
+    }
+
+    if let Some(rest) = dir_part.strip_prefix("~/") {
+        if let Some(home) = dirs::home_dir() {
+            return home.join(rest);
+        }
+    }
+
+    let path = Path::new(dir_part);
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        cwd.join(path)
+    }
+}
+
+/// Ranking bonus and badge text for an entry, from its directory's git status
+#[cfg(feature = "git-integration")]
+fn git_badge(badges: &Option<Arc<DirBadges>>, name: &str) -> (f32, Option<String>) {
+    match badges {
+        Some(badges) => (badges.bonus(name), badges.describe(name)),
+        None => (0.0, None),
+    }
+}
+
+#[cfg(not(feature = "git-integration"))]
+fn git_badge(_badges: &Option<()>, _name: &str) -> (f32, Option<String>) {
+    (0.0, None)
+}
+
+/// Type, size and age as fixed-width columns, like `file   4.2K   3h`
+fn entry_details(path: &Path, now: SystemTime) -> String {
+    let Ok(metadata) = path.symlink_metadata() else { return String::new() };
+    let kind = if metadata.file_type().is_symlink() {
+        "link"
+    } else if metadata.is_dir() {
+        "dir"
+    } else if is_executable(&metadata) {
+        "exec"
+    } else {
+        "file"
+    };
+    let size = if metadata.is_dir() { "-".to_string() } else { short_size(metadata.len()) };
+    let age = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| now.duration_since(modified).ok())
+        .map_or_else(String::new, |age| format_age(age.as_secs()));
+
+    format!("{:<4} {:>6} {:>4}", kind, size, age)
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+/// Size the way `ls -h` puts it: `512B`, `4.2K`, `13M`
+fn short_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "K", "M", "G", "T"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{}B", bytes)
+    } else if size < 10.0 {
+        format!("{:.1}{}", size, UNITS[unit])
+    } else {
+        format!("{:.0}{}", size, UNITS[unit])
+    }
+}
+
+/// Recently modified entries are the ones the user is most likely working on
+fn recency_bonus(modified: Option<SystemTime>, now: SystemTime) -> f32 {
+    let age = match modified.and_then(|m| now.duration_since(m).ok()) {
+        Some(age) => age,
+        None => return 0.0,
+    };
+
+    if age < Duration::from_secs(3600) {
+        0.15
+    } else if age < Duration::from_secs(86_400) {
+        0.1
+    } else if age < Duration::from_secs(7 * 86_400) {
+        0.05
+    } else {
+        0.0
+    }
+}
@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use parking_lot::RwLock;
+
+/// Parsed project manifests (Cargo.toml, package.json, ...) keyed by path
+///
+/// Entries are reparsed only when the manifest's mtime changes, so completers
+/// can consult them on every keystroke.
+pub struct ManifestCache<T> {
+    entries: RwLock<HashMap<PathBuf, (Option<SystemTime>, Arc<T>)>>,
+}
+
+impl<T> ManifestCache<T> {
+    pub fn new() -> Self {
+        Self {
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Get the parsed manifest at `path`, parsing it again if it changed on disk
+    pub fn get(&self, path: &Path, parse: impl FnOnce(&Path, &str) -> Option<T>) -> Option<Arc<T>> {
+        let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+
+        if let Some((cached_mtime, parsed)) = self.entries.read().get(path) {
+            if *cached_mtime == modified && modified.is_some() {
+                return Some(Arc::clone(parsed));
+            }
+        }
+
+        let content = fs::read_to_string(path).ok()?;
+        let parsed = Arc::new(parse(path, &content)?);
+        self.entries.write().insert(path.to_path_buf(), (modified, Arc::clone(&parsed)));
+        Some(parsed)
+    }
+}
+
+/// Find `file_name` in `dir` or the nearest ancestor that has it
+pub fn find_upwards(dir: &Path, file_name: &str) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|ancestor| ancestor.join(file_name))
+        .find(|candidate| candidate.is_file())
+}
@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::fs;
+use std::process::Command;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use parking_lot::RwLock;
+
+use super::fuzzy::fast_fuzzy_score;
+use super::{Suggestion, SuggestionKind};
+use crate::config::Config;
+use crate::plugin::api::CompletionProvider;
+use crate::shell::runtime_dir;
+use crate::utils::offline;
+use crate::utils::process::run_with_timeout;
+
+/// Commands whose arguments may be `[user@]host:path` targets
+const REMOTE_COMMANDS: &[&str] = &["scp", "rsync"];
+
+/// Cap on remote listing size; huge remote directories are truncated
+const MAX_LISTING_OUTPUT: u64 = 512 * 1024;
+
+/// A `[user@]host:path` argument split into its parts
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteTarget {
+    /// `host` or `user@host`, passed to ssh as-is
+    pub host: String,
+    /// Path on the remote side (may be empty, meaning the login directory)
+    pub path: String,
+}
+
+impl RemoteTarget {
+    /// Parse an scp/rsync argument, rejecting local paths, rsync daemon syntax, URLs, and
+    /// hosts ssh would take for an option (`-oProxyCommand=...:x`)
+    pub fn parse(arg: &str) -> Option<Self> {
+        let colon = arg.find(':')?;
+        let (host, rest) = (&arg[..colon], &arg[colon + 1..]);
+
+        // `host::module` and `rsync://` talk to an rsync daemon, not ssh
+        if host.is_empty() || rest.starts_with(':') || rest.starts_with("//") {
+            return None;
+        }
+
+        // A slash before the colon means a local path like `./a:b`
+        if host.contains('/') || host.starts_with('-') {
+            return None;
+        }
+
+        Some(Self {
+            host: host.to_string(),
+            path: rest.to_string(),
+        })
+    }
+}
+
+/// A single entry of a remote directory listing
+#[derive(Debug, Clone)]
+struct RemoteEntry {
+    name: String,
+    is_dir: bool,
+}
+
+/// Completes remote paths for scp/rsync by listing them over a shared ssh connection
+///
+/// Opt-in: every listing runs `ssh host ls`, multiplexed over a ControlMaster
+/// socket so only the first request pays for the handshake.
+pub struct RemotePathSuggester {
+    enabled: bool,
+    timeout: Duration,
+    cache_lifetime: Duration,
+    cache: RwLock<HashMap<(String, String), (Instant, Arc<Vec<RemoteEntry>>)>>,
+}
+
+impl RemotePathSuggester {
+    pub fn new(config: &Config) -> Self {
+        let ssh = config.ssh.clone().unwrap_or_default();
+
+        Self {
+            enabled: ssh.enable_remote_completion.unwrap_or(false),
+            timeout: Duration::from_millis(ssh.remote_completion_timeout_ms.unwrap_or(1500)),
+            cache_lifetime: Duration::from_secs(ssh.remote_cache_secs.unwrap_or(30)),
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Suggest remote entries for a partially typed `host:path` argument
+    pub fn suggest(&self, arg: &str, limit: usize) -> Vec<Suggestion> {
+        let target = match RemoteTarget::parse(arg) {
+            Some(target) => target,
+            None => return Vec::new(),
+        };
+
+        // Split `host:src/sug` into the remote directory (`src/`) and the fragment (`sug`)
+        let (dir_part, fragment) = match target.path.rfind('/') {
+            Some(idx) => (&target.path[..=idx], &target.path[idx + 1..]),
+            None => ("", target.path.as_str()),
+        };
+
+        let listing = self.list(&target.host, dir_part);
+
+        let show_hidden = fragment.starts_with('.');
+        let mut matches: Vec<(f32, &RemoteEntry)> = listing.iter()
+            .filter(|entry| show_hidden || !entry.name.starts_with('.'))
+            .filter_map(|entry| fast_fuzzy_score(fragment, &entry.name).map(|score| (score, entry)))
+            .collect();
+
+        matches.sort_by(|a, b| {
+            b.0.partial_cmp(&a.0)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then_with(|| a.1.name.cmp(&b.1.name))
+        });
+        matches.truncate(limit);
+
+        matches.into_iter()
+            .map(|(score, entry)| {
+                let display = if entry.is_dir {
+                    format!("{}/", entry.name)
+                } else {
+                    entry.name.clone()
+                };
+
+                Suggestion::new(
+                    display.clone(),
+                    format!("{}:{}{}", target.host, dir_part, display),
+                    SuggestionKind::Remote,
+                )
+                .with_description(target.host.clone())
+                .with_score(score * 100.0)
+            })
+            .collect()
+    }
+
+    /// Cached listing of a remote directory; failures are cached too so a dead host isn't retried per keystroke
+    fn list(&self, host: &str, dir: &str) -> Arc<Vec<RemoteEntry>> {
+        let key = (host.to_string(), dir.to_string());
+
+        if let Some((listed_at, entries)) = self.cache.read().get(&key) {
+            if listed_at.elapsed() < self.cache_lifetime {
+                return Arc::clone(entries);
+            }
+        }
+
+        let entries = Arc::new(self.list_remote(host, dir).unwrap_or_default());
+        self.cache.write().insert(key, (Instant::now(), Arc::clone(&entries)));
+        entries
+    }
+
+    /// Run `ls` on the remote host over a multiplexed, non-interactive ssh connection
+    fn list_remote(&self, host: &str, dir: &str) -> Option<Vec<RemoteEntry>> {
+        let control_dir = runtime_dir();
+        fs::create_dir_all(&control_dir).ok()?;
+        let control_path = control_dir.join("ssh-%C");
+
+        let connect_timeout = self.timeout.as_secs().max(1);
+
+        let mut command = Command::new("ssh");
+        command
+            .arg("-o").arg("BatchMode=yes")
+            .arg("-o").arg(format!("ConnectTimeout={}", connect_timeout))
+            .arg("-o").arg("ControlMaster=auto")
+            .arg("-o").arg(format!("ControlPath={}", control_path.display()))
+            .arg("-o").arg("ControlPersist=60")
+            .arg("-T")
+            // Whatever the host looks like, it's never read as an option
+            .arg("--")
+            .arg(host)
+            .arg(format!("LC_ALL=C ls -1Ap -- {}", remote_quote(dir)));
+
+        let output = run_with_timeout(&mut command, self.timeout, MAX_LISTING_OUTPUT)?;
+        if !output.status.success() {
+            return None;
+        }
+
+        let entries = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| match line.strip_suffix('/') {
+                Some(name) => RemoteEntry { name: name.to_string(), is_dir: true },
+                None => RemoteEntry { name: line.to_string(), is_dir: false },
+            })
+            .collect();
+
+        Some(entries)
+    }
+}
+
+impl CompletionProvider for RemotePathSuggester {
+    fn name(&self) -> &str {
+        "remote-paths"
+    }
+
+    fn can_provide_completions(&self, command: &str) -> bool {
+        self.enabled && REMOTE_COMMANDS.contains(&command) && !offline::is_offline()
+    }
+
+    fn provide_completions(&self, _command: &str, _args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        self.suggest(current_arg, 50)
+    }
+}
+
+/// Quote a path for the remote shell, leaving a leading `~/` unquoted so it still expands
+fn remote_quote(dir: &str) -> String {
+    if dir.is_empty() {
+        return ".".to_string();
+    }
+
+    let (tilde, rest) = match dir.strip_prefix("~/") {
+        Some(rest) => ("~/", rest),
+        None if dir == "~" => return "~".to_string(),
+        None => ("", dir),
+    };
+
+    if rest.is_empty() {
+        return tilde.to_string();
+    }
+
+    format!("{}'{}'", tilde, rest.replace('\'', "'\\''"))
+}
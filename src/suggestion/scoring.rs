@@ -0,0 +1,142 @@
+//! Ranking suggestions by how the user works
+//!
+//! A source scores a suggestion by how well it matches what's typed. On top of that,
+//! the `suggestions.scoring` weights add points for the line the suggestion would make:
+//! how recently it was run, how often it was accepted (the learning data), and whether
+//! it was run in the current directory. Each weight at 1.0 is worth up to
+//! `POINTS_PER_WEIGHT`; with `scoring.debug` the breakdown is shown in the dropdown.
+
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use once_cell::sync::OnceCell;
+
+use crate::config::schema::ScoringConfig;
+use crate::config::Config;
+use crate::prediction::learning::UserLearningSystem;
+use crate::shell::history::HistoryEntry;
+use crate::utils::cache_file;
+use super::engine::Suggestion;
+
+/// Points a signal adds at full strength with a weight of 1.0
+const POINTS_PER_WEIGHT: f32 = 10.0;
+
+/// Age at which a command's recency counts half
+const RECENCY_HALF_LIFE_SECS: f64 = 3.0 * 24.0 * 60.0 * 60.0;
+
+/// Acceptances at which frequency is at full strength
+const FULL_FREQUENCY: usize = 10;
+
+/// What a suggestion's score is made of
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoreBreakdown {
+    /// The source's own score
+    pub base: f32,
+    pub recency: f32,
+    pub frequency: f32,
+    pub context: f32,
+}
+
+impl ScoreBreakdown {
+    pub fn total(&self) -> f32 {
+        self.base + self.recency + self.frequency + self.context
+    }
+
+    /// `72 = 60 base + 5.2 recency + 4.0 frequency + 2.8 context`
+    pub fn describe(&self) -> String {
+        format!(
+            "{:.0} = {:.0} base + {:.1} recency + {:.1} frequency + {:.1} context",
+            self.total(),
+            self.base,
+            self.recency,
+            self.frequency,
+            self.context,
+        )
+    }
+}
+
+/// Adds the recency, frequency and context points to suggestions
+pub struct Scorer {
+    weights: ScoringConfig,
+    debug: bool,
+    data_dir: PathBuf,
+    compress: bool,
+    /// Loaded on first use; only accepted counts are read
+    learning: OnceCell<UserLearningSystem>,
+}
+
+impl Scorer {
+    pub fn new(config: &Config) -> Self {
+        let weights = config.suggestions.scoring.clone().unwrap_or_default();
+        Self {
+            debug: weights.debug.unwrap_or(false),
+            weights,
+            data_dir: config.general.user_data_dir.clone(),
+            compress: cache_file::compression_enabled(config),
+            learning: OnceCell::new(),
+        }
+    }
+
+    fn learning(&self) -> &UserLearningSystem {
+        self.learning.get_or_init(|| UserLearningSystem::new(&self.data_dir, self.compress))
+    }
+
+    /// Score `suggestions` for the word `current_arg` at the end of `line`, typed in `cwd`,
+    /// given the history newest first
+    pub fn apply(&self, suggestions: &mut [Suggestion], line: &str, current_arg: &str, cwd: &Path, history: &[HistoryEntry]) {
+        let weights = &self.weights;
+        if weights.recency_weight <= 0.0 && weights.frequency_weight <= 0.0 && weights.context_weight <= 0.0 && !self.debug {
+            return;
+        }
+
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let typed_before = line.strip_suffix(current_arg).unwrap_or(line);
+
+        for suggestion in suggestions.iter_mut() {
+            let candidate = if suggestion.kind.is_full_line() {
+                suggestion.completion.clone()
+            } else {
+                format!("{}{}", typed_before, suggestion.completion)
+            };
+            let candidate = candidate.trim_end();
+
+            let mut recency: f32 = 0.0;
+            let mut context: f32 = 0.0;
+            for entry in history.iter().filter(|entry| extends(&entry.command, candidate)) {
+                if recency == 0.0 && entry.timestamp > 0 {
+                    let age = now.saturating_sub(entry.timestamp) as f64;
+                    recency = 0.5f64.powf(age / RECENCY_HALF_LIFE_SECS) as f32;
+                }
+                if entry.cwd.as_deref() == Some(cwd) {
+                    context = 1.0;
+                }
+                if recency > 0.0 && context > 0.0 {
+                    break;
+                }
+            }
+            let accepted = self.learning().accepted_count(candidate);
+            let frequency = accepted.min(FULL_FREQUENCY) as f32 / FULL_FREQUENCY as f32;
+
+            let breakdown = ScoreBreakdown {
+                base: suggestion.score,
+                recency: recency * weights.recency_weight * POINTS_PER_WEIGHT,
+                frequency: frequency * weights.frequency_weight * POINTS_PER_WEIGHT,
+                context: context * weights.context_weight * POINTS_PER_WEIGHT,
+            };
+            suggestion.score = breakdown.total();
+            if self.debug {
+                suggestion.description = Some(match suggestion.description.take() {
+                    Some(description) => format!("{} · {}", description, breakdown.describe()),
+                    None => breakdown.describe(),
+                });
+            }
+        }
+    }
+}
+
+/// Whether `command` is `candidate` or continues it with more words
+fn extends(command: &str, candidate: &str) -> bool {
+    match command.strip_prefix(candidate) {
+        Some(rest) => rest.is_empty() || rest.starts_with(' ') || candidate.ends_with('/'),
+        None => false,
+    }
+}
@@ -0,0 +1,384 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use once_cell::sync::Lazy;
+use serde_yaml::{Mapping, Value};
+
+use crate::config::Config;
+use super::fuzzy::fast_fuzzy_score;
+use super::project::ManifestCache;
+use super::{Suggestion, SuggestionKind};
+
+/// Name of the snippets directory inside the config directory
+pub const SNIPPETS_DIR: &str = "snippets";
+
+/// File new snippets are added to
+pub const DEFAULT_SNIPPET_FILE: &str = "snippets.yaml";
+
+/// Parsed snippet files keyed by path
+static SNIPPET_FILES: Lazy<ManifestCache<Vec<Snippet>>> = Lazy::new(ManifestCache::new);
+
+/// A saved command with optional `${1:placeholder}` tab stops
+#[derive(Debug, Clone)]
+pub struct Snippet {
+    pub name: String,
+    /// Command text, possibly containing placeholders
+    pub body: String,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    /// File the snippet was loaded from
+    pub file: PathBuf,
+}
+
+impl Snippet {
+    /// Expand placeholders to their defaults and record where the tab stops landed
+    pub fn expand(&self) -> SnippetExpansion {
+        SnippetExpansion::parse(&self.body)
+    }
+}
+
+/// A placeholder's position in expanded snippet text, as a byte range
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TabStop {
+    /// Visit order; `$0` is the final cursor position
+    pub index: u32,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Snippet text with placeholders replaced by their defaults
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnippetExpansion {
+    pub text: String,
+    /// Tab stops in visit order (`$1`, `$2`, ..., then `$0`)
+    pub tab_stops: Vec<TabStop>,
+}
+
+impl SnippetExpansion {
+    /// Parse `$1`, `${1}`, and `${1:default}` tab stops; `\$` is a literal dollar sign
+    ///
+    /// Anything else after `$` (`$HOME`, `${VAR}`, `$(date)`) is left alone, so
+    /// ordinary shell syntax survives expansion.
+    pub fn parse(body: &str) -> Self {
+        let mut text = String::with_capacity(body.len());
+        let mut tab_stops = Vec::new();
+        let mut rest = body;
+
+        while let Some(pos) = rest.find(['$', '\\']) {
+            text.push_str(&rest[..pos]);
+            rest = &rest[pos..];
+
+            if let Some(after) = rest.strip_prefix("\\$") {
+                text.push('$');
+                rest = after;
+                continue;
+            }
+            if rest.starts_with('\\') {
+                text.push('\\');
+                rest = &rest[1..];
+                continue;
+            }
+
+            match parse_tab_stop(&rest[1..]) {
+                Some((index, default, consumed)) => {
+                    let start = text.len();
+                    text.push_str(default);
+                    tab_stops.push(TabStop {
+                        index,
+                        start,
+                        end: text.len(),
+                    });
+                    rest = &rest[1 + consumed..];
+                }
+                None => {
+                    text.push('$');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        text.push_str(rest);
+
+        // `$0` goes last; stops sharing an index keep their order in the text
+        tab_stops.sort_by_key(|stop| (stop.index == 0, stop.index, stop.start));
+
+        Self { text, tab_stops }
+    }
+}
+
+/// Parse the part after `$`: returns (index, default text, bytes consumed)
+fn parse_tab_stop(input: &str) -> Option<(u32, &str, usize)> {
+    if let Some(inner) = input.strip_prefix('{') {
+        let digits = inner.bytes().take_while(|b| b.is_ascii_digit()).count();
+        if digits == 0 {
+            return None;
+        }
+        let index = inner[..digits].parse().ok()?;
+        let after_digits = &inner[digits..];
+
+        if after_digits.starts_with('}') {
+            return Some((index, "", 1 + digits + 1));
+        }
+
+        let default_and_rest = after_digits.strip_prefix(':')?;
+        let close = default_and_rest.find('}')?;
+        return Some((index, &default_and_rest[..close], 1 + digits + 1 + close + 1));
+    }
+
+    let digits = input.bytes().take_while(|b| b.is_ascii_digit()).count();
+    if digits == 0 {
+        return None;
+    }
+    Some((input[..digits].parse().ok()?, "", digits))
+}
+
+/// Tab-stop navigation for a snippet that was just inserted into the command line
+#[derive(Debug, Clone)]
+pub struct SnippetSession {
+    stops: Vec<TabStop>,
+    /// Index into `stops` of the stop currently being edited
+    current: Option<usize>,
+}
+
+impl SnippetSession {
+    /// Start a session for a snippet expansion inserted at byte `offset`; `None` if it has
+    /// no tab stops
+    pub fn start(tab_stops: &[TabStop], offset: usize) -> Option<Self> {
+        if tab_stops.is_empty() {
+            return None;
+        }
+
+        let stops = tab_stops.iter()
+            .map(|stop| TabStop {
+                index: stop.index,
+                start: stop.start + offset,
+                end: stop.end + offset,
+            })
+            .collect();
+
+        Some(Self { stops, current: None })
+    }
+
+    /// Advance to the next tab stop, returning the range to select
+    pub fn next_stop(&mut self) -> Option<TabStop> {
+        let next = self.current.map_or(0, |current| current + 1);
+        let stop = *self.stops.get(next)?;
+        self.current = Some(next);
+        Some(stop)
+    }
+
+    /// Whether the last tab stop has been visited
+    pub fn is_finished(&self) -> bool {
+        self.current.map_or(false, |current| current + 1 >= self.stops.len())
+    }
+
+    /// Account for the user typing into the current stop, changing the line length by `delta`
+    ///
+    /// The current stop grows or shrinks and every stop after it in the text shifts.
+    pub fn record_edit(&mut self, delta: isize) {
+        let current = match self.current.and_then(|current| self.stops.get(current).copied()) {
+            Some(current) => current,
+            None => return,
+        };
+
+        let shift = |position: usize| position.saturating_add_signed(delta);
+
+        for stop in &mut self.stops {
+            if *stop == current {
+                stop.end = shift(stop.end).max(stop.start);
+            } else if stop.start >= current.end {
+                stop.start = shift(stop.start).max(current.start);
+                stop.end = shift(stop.end).max(stop.start);
+            }
+        }
+    }
+}
+
+/// Load every snippet from the YAML files in `dir`
+///
+/// Files map snippet names either to a command string or to a mapping with
+/// `command`, `description`, and `tags`.
+pub fn load_snippets(dir: &Path) -> Vec<Snippet> {
+    let mut files: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| matches!(path.extension().and_then(|e| e.to_str()), Some("yaml" | "yml")))
+            .collect(),
+        Err(_) => return Vec::new(),
+    };
+    files.sort();
+
+    files.iter()
+        .filter_map(|file| SNIPPET_FILES.get(file, |path, content| Some(parse_snippet_file(path, content))))
+        .flat_map(|snippets| snippets.as_ref().clone())
+        .collect()
+}
+
+fn parse_snippet_file(path: &Path, content: &str) -> Vec<Snippet> {
+    let document: Value = match serde_yaml::from_str(content) {
+        Ok(document) => document,
+        Err(_) => return Vec::new(),
+    };
+
+    let entries = match document.as_mapping() {
+        Some(entries) => entries,
+        None => return Vec::new(),
+    };
+
+    entries.iter()
+        .filter_map(|(name, value)| {
+            let name = name.as_str()?.to_string();
+
+            let (body, description, tags) = match value {
+                Value::String(body) => (body.clone(), None, Vec::new()),
+                Value::Mapping(_) => {
+                    let body = value["command"].as_str().or_else(|| value["body"].as_str())?.to_string();
+                    let description = value["description"].as_str().map(|s| s.to_string());
+                    let tags = value["tags"]
+                        .as_sequence()
+                        .map(|tags| tags.iter().filter_map(|t| t.as_str().map(|s| s.to_string())).collect())
+                        .unwrap_or_default();
+                    (body, description, tags)
+                }
+                _ => return None,
+            };
+
+            Some(Snippet {
+                name,
+                body,
+                description,
+                tags,
+                file: path.to_path_buf(),
+            })
+        })
+        .collect()
+}
+
+/// Write `snippet` to its file, replacing the entry named `previous_name` if it was renamed
+pub fn save_snippet(snippet: &Snippet, previous_name: Option<&str>) -> Result<(), Box<dyn Error>> {
+    update_snippet_file(&snippet.file, |entries| {
+        if let Some(previous_name) = previous_name {
+            entries.remove(previous_name);
+        }
+
+        // Plain snippets stay in the compact `name: command` form
+        let value = if snippet.description.is_none() && snippet.tags.is_empty() {
+            Value::String(snippet.body.clone())
+        } else {
+            let mut fields = Mapping::new();
+            fields.insert("command".into(), snippet.body.clone().into());
+            if let Some(description) = &snippet.description {
+                fields.insert("description".into(), description.clone().into());
+            }
+            if !snippet.tags.is_empty() {
+                fields.insert("tags".into(), Value::Sequence(snippet.tags.iter().map(|t| t.clone().into()).collect()));
+            }
+            Value::Mapping(fields)
+        };
+
+        entries.insert(snippet.name.clone().into(), value);
+    })
+}
+
+/// Remove `snippet` from its file
+pub fn delete_snippet(snippet: &Snippet) -> Result<(), Box<dyn Error>> {
+    update_snippet_file(&snippet.file, |entries| {
+        entries.remove(snippet.name.as_str());
+    })
+}
+
+fn update_snippet_file(path: &Path, update: impl FnOnce(&mut Mapping)) -> Result<(), Box<dyn Error>> {
+    let mut entries = match fs::read_to_string(path) {
+        Ok(content) => match serde_yaml::from_str::<Value>(&content)? {
+            Value::Mapping(entries) => entries,
+            Value::Null => Mapping::new(),
+            _ => return Err(format!("{} is not a mapping of snippets", path.display()).into()),
+        },
+        Err(_) => Mapping::new(),
+    };
+
+    update(&mut entries);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    // Write to a temporary file first so a crash never leaves a torn library behind
+    let tmp_path = path.with_extension("yaml.tmp");
+    fs::write(&tmp_path, serde_yaml::to_string(&Value::Mapping(entries))?)?;
+    fs::rename(tmp_path, path)?;
+
+    Ok(())
+}
+
+/// Surfaces saved snippets in the dropdown
+pub struct SnippetSuggester {
+    enabled: bool,
+    dir: Option<PathBuf>,
+}
+
+impl SnippetSuggester {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            enabled: config.suggestions.enable_snippets.unwrap_or(true),
+            dir: config.suggestions.snippets_dir.clone().or_else(snippets_dir),
+        }
+    }
+
+    /// All snippets in the library
+    pub fn snippets(&self) -> Vec<Snippet> {
+        match &self.dir {
+            Some(dir) => load_snippets(dir),
+            None => Vec::new(),
+        }
+    }
+
+    /// Snippets whose name, tags, or command match the input
+    ///
+    /// The completion is the expanded text and carries its tab stops; the expanded text
+    /// can't be parsed again, since defaults and escaped `\$` are already resolved in it.
+    /// Accepting it should start a `SnippetSession` from those stops.
+    pub fn suggest(&self, input: &str, limit: usize) -> Vec<Suggestion> {
+        let input = input.trim();
+        if !self.enabled || input.is_empty() {
+            return Vec::new();
+        }
+
+        let mut suggestions: Vec<Suggestion> = self.snippets()
+            .into_iter()
+            .filter_map(|snippet| {
+                // Names and tags are what users search by; the command itself matches more weakly
+                let score = fast_fuzzy_score(input, &snippet.name)
+                    .into_iter()
+                    .chain(snippet.tags.iter().filter_map(|tag| fast_fuzzy_score(input, tag)))
+                    .chain(fast_fuzzy_score(input, &snippet.body).map(|score| score * 0.8))
+                    .fold(None, |best: Option<f32>, score| Some(best.map_or(score, |best| best.max(score))))?;
+
+                let SnippetExpansion { text, tab_stops } = snippet.expand();
+                let description = snippet.description.clone().unwrap_or_else(|| snippet.body.clone());
+
+                Some(
+                    Suggestion::new(snippet.name.clone(), text, SuggestionKind::Snippet)
+                        .with_description(description)
+                        .with_score(score * 100.0)
+                        .with_tab_stops(tab_stops)
+                )
+            })
+            .collect();
+
+        suggestions.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        suggestions.truncate(limit);
+        suggestions
+    }
+
+    /// Look up a snippet by its exact name
+    pub fn find(&self, name: &str) -> Option<Snippet> {
+        self.snippets().into_iter().find(|snippet| snippet.name == name)
+    }
+}
+
+/// Directory holding the user's snippet files
+pub fn snippets_dir() -> Option<PathBuf> {
+    crate::config::loader::get_config_dir().ok().map(|dir| dir.join(SNIPPETS_DIR))
+}
+
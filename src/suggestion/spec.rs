@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+
+use super::fuzzy::{edit_distance, fast_fuzzy_score, max_typo_edits};
+use super::{Suggestion, SuggestionKind};
+
+/// Declarative description of a command's subcommands and options
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommandSpec {
+    /// Command or subcommand name
+    pub name: String,
+
+    /// Short description shown in the dropdown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Nested subcommands
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subcommands: Vec<CommandSpec>,
+
+    /// Options/flags accepted at this level
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<OptionSpec>,
+
+    /// Positional arguments accepted at this level, in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<ArgSpec>,
+}
+
+/// A single option, possibly with several spellings (`-v`, `--verbose`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OptionSpec {
+    /// All accepted spellings of the option
+    pub names: Vec<String>,
+
+    /// Short description shown in the dropdown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Name of the value the option takes, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_name: Option<String>,
+
+    /// Generator that completes the option's value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generator: Option<String>,
+
+    /// Fixed values the option takes, offered when there's no generator
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
+}
+
+/// A positional argument
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ArgSpec {
+    /// Placeholder name (`file`, `pod`)
+    pub name: String,
+
+    /// Short description shown in the dropdown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// Generator that completes the argument's value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generator: Option<String>,
+
+    /// Whether this argument repeats (`rm <file>...`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub variadic: bool,
+
+    /// Fixed values the argument takes, offered alongside the generator's
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
+}
+
+impl ArgSpec {
+    /// A positional argument completed by a named generator
+    pub fn generated(name: &str, generator: &str, variadic: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            description: None,
+            generator: Some(generator.to_string()),
+            variadic,
+            suggestions: Vec::new(),
+        }
+    }
+}
+
+/// Produces dynamic values (branches, pods, profiles) for a spec argument
+pub trait Generator: Send + Sync {
+    /// Suggestions for the value being typed, given the words typed before it
+    fn generate(&self, args: &[&str], current_arg: &str) -> Vec<Suggestion>;
+}
+
+/// Named generators that specs refer to by string
+#[derive(Default, Clone)]
+pub struct GeneratorRegistry {
+    generators: HashMap<String, Arc<dyn Generator>>,
+}
+
+impl GeneratorRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, name: &str, generator: Arc<dyn Generator>) {
+        self.generators.insert(name.to_string(), generator);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Arc<dyn Generator>> {
+        self.generators.get(name)
+    }
+}
+
+impl CommandSpec {
+    /// Create an empty spec for a command
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Create a subcommand spec with positional arguments
+    pub fn with_args(name: &str, description: &str, args: Vec<ArgSpec>) -> Self {
+        let mut spec = Self::new(name);
+        if !description.is_empty() {
+            spec.description = Some(description.to_string());
+        }
+        spec.args = args;
+        spec
+    }
+
+    /// Copy options into every nested subcommand (for tools that accept global flags anywhere)
+    pub fn propagate_options(&mut self, options: &[OptionSpec]) {
+        for sub in self.subcommands.iter_mut() {
+            sub.options.extend(options.iter().cloned());
+            sub.propagate_options(options);
+        }
+    }
+
+    /// Find a direct subcommand by name
+    pub fn subcommand(&self, name: &str) -> Option<&CommandSpec> {
+        self.subcommands.iter().find(|s| s.name == name)
+    }
+
+    /// The subcommand `word` is most likely a typo of, with how many edits off it is
+    pub fn closest_subcommand(&self, word: &str) -> Option<(&CommandSpec, usize)> {
+        let max = max_typo_edits(word)?;
+        self.subcommands
+            .iter()
+            .filter_map(|sub| Some((sub, edit_distance(word, &sub.name, max)?)))
+            .filter(|(_, distance)| *distance > 0)
+            .min_by_key(|(_, distance)| *distance)
+    }
+
+    /// Walk the subcommand tree along the already-typed arguments
+    pub fn resolve<'a>(&'a self, args: &[&str]) -> &'a CommandSpec {
+        let mut current = self;
+
+        for arg in args {
+            if arg.starts_with('-') {
+                continue;
+            }
+
+            match current.subcommand(arg) {
+                Some(sub) => current = sub,
+                None => break,
+            }
+        }
+
+        current
+    }
+
+    /// Find an option at this level by any of its spellings
+    pub fn option(&self, name: &str) -> Option<&OptionSpec> {
+        self.options.iter().find(|o| o.names.iter().any(|n| n == name))
+    }
+
+    /// Resolve subcommands and count positional arguments typed after them
+    ///
+    /// Values consumed by options that take one are not counted as positionals.
+    pub fn resolve_positionals<'a>(&'a self, args: &[&str]) -> (&'a CommandSpec, usize) {
+        let mut current = self;
+        let mut positionals = 0;
+        let mut expecting_value = false;
+
+        for arg in args {
+            if expecting_value {
+                expecting_value = false;
+                continue;
+            }
+
+            if arg.starts_with('-') {
+                expecting_value = !arg.contains('=')
+                    && current.option(arg).or_else(|| self.option(arg)).map_or(false, |o| o.value_name.is_some());
+                continue;
+            }
+
+            match current.subcommand(arg) {
+                Some(sub) if positionals == 0 => current = sub,
+                _ => positionals += 1,
+            }
+        }
+
+        (current, positionals)
+    }
+
+    /// Produce suggestions for the argument currently being typed
+    pub fn suggestions_for(&self, args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        self.suggestions_with(args, current_arg, &GeneratorRegistry::default())
+    }
+
+    /// Produce suggestions, running generators for option values and positional arguments
+    pub fn suggestions_with(&self, args: &[&str], current_arg: &str, generators: &GeneratorRegistry) -> Vec<Suggestion> {
+        let (spec, positionals) = self.resolve_positionals(args);
+        let mut suggestions = Vec::new();
+
+        // The previous word was an option waiting for its value
+        if let Some(option) = args.last().and_then(|last| spec.option(last).or_else(|| self.option(last))) {
+            if option.value_name.is_some() {
+                if let Some(generator) = option.generator.as_deref().and_then(|g| generators.get(g)) {
+                    return generator.generate(args, current_arg);
+                }
+                return fixed_values(&option.suggestions, current_arg);
+            }
+        }
+
+        if current_arg.starts_with('-') {
+            for option in &spec.options {
+                for name in &option.names {
+                    if name.starts_with(current_arg) {
+                        suggestions.push(option.to_suggestion(name));
+                    }
+                }
+            }
+        } else {
+            // Subcommands can only appear before any positional argument
+            let subcommands = if positionals == 0 { &spec.subcommands[..] } else { &[] };
+
+            for sub in subcommands {
+                if sub.name.starts_with(current_arg) {
+                    let mut suggestion = Suggestion::new(
+                        sub.name.clone(),
+                        sub.name.clone(),
+                        SuggestionKind::Command,
+                    );
+
+                    if let Some(description) = &sub.description {
+                        suggestion = suggestion.with_description(description.clone());
+                    }
+
+                    suggestions.push(suggestion);
+                }
+            }
+
+            let arg = spec.args.get(positionals)
+                .or_else(|| spec.args.last().filter(|a| a.variadic));
+
+            if let Some(generator) = arg.and_then(|a| a.generator.as_deref()).and_then(|g| generators.get(g)) {
+                suggestions.extend(generator.generate(args, current_arg));
+            }
+            if let Some(arg) = arg {
+                suggestions.extend(fixed_values(&arg.suggestions, current_arg));
+            }
+        }
+
+        suggestions
+    }
+}
+
+impl OptionSpec {
+    /// An option whose value is completed by a named generator
+    pub fn generated(names: &[&str], value_name: &str, description: &str, generator: &str) -> Self {
+        Self {
+            names: names.iter().map(|n| n.to_string()).collect(),
+            description: Some(description.to_string()),
+            value_name: Some(value_name.to_string()),
+            generator: Some(generator.to_string()),
+            suggestions: Vec::new(),
+        }
+    }
+
+    /// Build a dropdown suggestion for one spelling of this option
+    fn to_suggestion(&self, name: &str) -> Suggestion {
+        let display = match &self.value_name {
+            Some(value) => format!("{} <{}>", name, value),
+            None => name.to_string(),
+        };
+
+        let mut suggestion = Suggestion::new(display, name.to_string(), SuggestionKind::Flag);
+
+        if let Some(description) = &self.description {
+            suggestion = suggestion.with_description(description.clone());
+        }
+
+        suggestion
+    }
+}
+
+/// A spec's fixed values, ranked like a generator's
+fn fixed_values(values: &[String], current_arg: &str) -> Vec<Suggestion> {
+    generated_suggestions(values.iter().map(|value| (value.clone(), String::new())), current_arg)
+}
+
+/// Fuzzy-filter and rank (value, description) pairs produced by a generator
+pub fn generated_suggestions(candidates: impl Iterator<Item = (String, String)>, current_arg: &str) -> Vec<Suggestion> {
+    let mut suggestions: Vec<Suggestion> = candidates
+        .filter_map(|(value, description)| {
+            let score = fast_fuzzy_score(current_arg, &value)?;
+            Some(
+                Suggestion::new(value.clone(), value, SuggestionKind::Argument)
+                    .with_description(description)
+                    .with_score(score * 100.0)
+            )
+        })
+        .collect();
+
+    suggestions.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    suggestions
+}
+
+/// Value of a flag typed as `--flag value` or `--flag=value`
+pub fn flag_value<'a>(args: &[&'a str], names: &[&str]) -> Option<&'a str> {
+    for (index, arg) in args.iter().enumerate() {
+        if let Some((name, value)) = arg.split_once('=') {
+            if names.contains(&name) {
+                return Some(value);
+            }
+        } else if names.contains(arg) {
+            return args.get(index + 1).copied();
+        }
+    }
+
+    None
+}
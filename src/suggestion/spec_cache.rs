@@ -0,0 +1,96 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::cache_file;
+use super::help_scraper::BinaryFingerprint;
+use super::spec::CommandSpec;
+
+/// Bump when the on-disk format or the generators change in incompatible ways
+const CACHE_FORMAT_VERSION: u32 = 3;
+
+/// Name of the cache directory inside the user data directory
+pub const SPECS_CACHE_DIR: &str = "specs-cache";
+
+/// On-disk record of a generated spec
+#[derive(Serialize, Deserialize)]
+struct CachedSpecFile {
+    /// Binary the spec was generated from
+    binary: PathBuf,
+    /// Fingerprint of the binary at generation time
+    fingerprint: BinaryFingerprint,
+    /// Generated spec as JSON, or `None` if generation produced nothing. Specs skip
+    /// empty fields when serialized, which only a self-describing format reads back
+    spec: Option<String>,
+}
+
+/// Persistent cache of generated specs, invalidated when the binary changes
+pub struct SpecCache {
+    dir: PathBuf,
+    compress: bool,
+}
+
+impl SpecCache {
+    /// Create a cache rooted at `user_data_dir/specs-cache`, compressing entries with `compress`
+    pub fn new(user_data_dir: &Path, compress: bool) -> Self {
+        Self {
+            dir: user_data_dir.join(SPECS_CACHE_DIR),
+            compress,
+        }
+    }
+
+    /// Look up a cached spec for this exact binary build
+    ///
+    /// Returns `Some(None)` when a previous generation attempt for this build
+    /// produced nothing, so callers don't retry it.
+    pub fn load(&self, binary: &Path, fingerprint: BinaryFingerprint) -> Option<Option<CommandSpec>> {
+        // A corrupt or outdated entry is a miss, and gets overwritten by the regenerated spec
+        let cached: CachedSpecFile = cache_file::read(&self.entry_path(binary), CACHE_FORMAT_VERSION).ok()?;
+
+        if cached.binary != binary || cached.fingerprint != fingerprint {
+            return None;
+        }
+
+        match cached.spec {
+            Some(json) => serde_json::from_str(&json).ok().map(Some),
+            None => Some(None),
+        }
+    }
+
+    /// Persist a generation result for this binary build
+    pub fn store(
+        &self,
+        binary: &Path,
+        fingerprint: BinaryFingerprint,
+        spec: Option<&CommandSpec>,
+    ) -> Result<(), Box<dyn Error>> {
+        let cached = CachedSpecFile {
+            binary: binary.to_path_buf(),
+            fingerprint,
+            spec: spec.map(serde_json::to_string).transpose()?,
+        };
+
+        cache_file::write(&self.entry_path(binary), CACHE_FORMAT_VERSION, &cached, self.compress)
+    }
+
+    /// Remove every cached spec
+    pub fn clear(&self) -> Result<(), Box<dyn Error>> {
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir)?;
+        }
+
+        Ok(())
+    }
+
+    /// Cache file for a binary; the full path is encoded so same-named tools don't collide
+    fn entry_path(&self, binary: &Path) -> PathBuf {
+        let name: String = binary
+            .to_string_lossy()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+            .collect();
+
+        self.dir.join(format!("{}.bin", name.trim_start_matches('_')))
+    }
+}
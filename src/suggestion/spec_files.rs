@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::plugin::api::CompletionProvider;
+use super::spec::CommandSpec;
+use super::Suggestion;
+
+/// Name of the user's spec directory inside the config directory
+pub const SPECS_DIR: &str = "specs";
+
+/// Load every spec from the YAML and JSON files in `dir`, one command per file
+///
+/// Files that don't parse are skipped so one broken spec doesn't hide the rest.
+pub fn load_spec_dir(dir: &Path) -> Vec<CommandSpec> {
+    let mut files: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| matches!(path.extension().and_then(|e| e.to_str()), Some("yaml" | "yml" | "json")))
+            .collect(),
+        Err(_) => return Vec::new(),
+    };
+    files.sort();
+
+    files.iter()
+        .filter_map(|file| {
+            let content = fs::read_to_string(file).ok()?;
+            match file.extension().and_then(|e| e.to_str()) {
+                Some("json") => serde_json::from_str(&content).ok(),
+                _ => serde_yaml::from_str(&content).ok(),
+            }
+        })
+        .collect()
+}
+
+/// Completes commands described by spec files on disk
+pub struct SpecFileCompleter {
+    specs: HashMap<String, CommandSpec>,
+}
+
+impl SpecFileCompleter {
+    /// Load the user's `specs` directory, then `suggestions.spec_dirs`; later directories
+    /// win for the same command, so a project can override a personal spec
+    pub fn new(config: &Config) -> Self {
+        let mut dirs: Vec<PathBuf> = crate::config::loader::get_config_dir()
+            .map(|dir| dir.join(SPECS_DIR))
+            .into_iter()
+            .collect();
+        dirs.extend(config.suggestions.spec_dirs.iter().flatten().cloned());
+
+        let specs = dirs.iter()
+            .flat_map(|dir| load_spec_dir(dir))
+            .map(|spec| (spec.name.clone(), spec))
+            .collect();
+
+        Self { specs }
+    }
+}
+
+impl CompletionProvider for SpecFileCompleter {
+    fn name(&self) -> &str {
+        "spec-files"
+    }
+
+    fn can_provide_completions(&self, command: &str) -> bool {
+        self.specs.contains_key(command)
+    }
+
+    fn provide_completions(&self, command: &str, args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        match self.specs.get(command) {
+            Some(spec) => spec.suggestions_for(args, current_arg),
+            None => Vec::new(),
+        }
+    }
+}
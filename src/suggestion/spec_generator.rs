@@ -0,0 +1,187 @@
+//! Skeleton specs for `rustfig generate-spec`
+//!
+//! Combines what a command says about itself in `--help` with its man page: help output
+//! usually lists the subcommands, the man page the options and what the command is for.
+//! The result is a starting point to tune by hand, written like any other spec file.
+
+use std::env;
+use std::error::Error;
+use std::process::Command;
+use std::time::Duration;
+
+use super::help_scraper::{is_denied, parse_option_line, resolve_binary, run_help, HelpScraper};
+use super::spec::{CommandSpec, OptionSpec};
+use crate::utils::process::run_with_timeout;
+
+/// Deadline for each `--help` run and for rendering the man page
+const GENERATE_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Most man page output read; some pages run to megabytes
+const MAX_MAN_OUTPUT: u64 = 2 * 1024 * 1024;
+
+/// Most subcommands asked for their own `--help`
+const MAX_SUBCOMMAND_SCRAPES: usize = 64;
+
+/// A generated spec and where it came from, like `ls --help` and `man ls`
+pub struct GeneratedSpec {
+    pub spec: CommandSpec,
+    pub sources: Vec<String>,
+}
+
+/// Build a spec for `command` from its help output and man page; with `deep`, each
+/// subcommand's `--help` is read for its options too
+pub fn generate(command: &str, deep: bool) -> Result<GeneratedSpec, Box<dyn Error>> {
+    let binary = resolve_binary(command).ok_or_else(|| format!("'{}' isn't on PATH", command))?;
+    if is_denied(&binary) {
+        return Err(format!("'{}' isn't run to read its help", command).into());
+    }
+
+    let mut sources = Vec::new();
+    let help = HelpScraper::generate(command, &binary, GENERATE_TIMEOUT);
+    if help.is_some() {
+        sources.push(format!("{} --help", command));
+    }
+    let man = read_man(command).map(|text| parse_man(command, &text)).filter(|spec| {
+        spec.description.is_some() || !spec.options.is_empty()
+    });
+    if man.is_some() {
+        sources.push(format!("man {}", command));
+    }
+
+    let mut spec = match (help, man) {
+        (Some(help), Some(man)) => merge(help, man),
+        (Some(spec), None) | (None, Some(spec)) => spec,
+        (None, None) => return Err(format!("'{}' has neither usable --help output nor a man page", command).into()),
+    };
+
+    if deep {
+        let mut scraped = 0;
+        for sub in spec.subcommands.iter_mut().take(MAX_SUBCOMMAND_SCRAPES) {
+            let Some(text) = run_help(&binary, &[sub.name.as_str(), "--help"], GENERATE_TIMEOUT) else { continue };
+            let parsed = HelpScraper::parse_help(&sub.name, &text);
+            if !parsed.options.is_empty() || !parsed.subcommands.is_empty() {
+                sub.options = parsed.options;
+                sub.subcommands = parsed.subcommands;
+                scraped += 1;
+            }
+        }
+        if scraped > 0 {
+            sources.push(format!("{} <subcommand> --help", command));
+        }
+    }
+
+    Ok(GeneratedSpec { spec, sources })
+}
+
+/// The help spec, with the man page's description and the options help didn't list
+fn merge(mut help: CommandSpec, man: CommandSpec) -> CommandSpec {
+    if man.description.is_some() {
+        help.description = man.description;
+    }
+    for option in man.options {
+        match help.options.iter_mut().find(|own| own.names.iter().any(|name| option.names.contains(name))) {
+            Some(own) => {
+                if own.description.is_none() {
+                    own.description = option.description;
+                }
+                if own.value_name.is_none() {
+                    own.value_name = option.value_name;
+                }
+            }
+            None => help.options.push(option),
+        }
+    }
+    help
+}
+
+/// The man page as plain text, if there is one
+fn read_man(command: &str) -> Option<String> {
+    let mut man = Command::new("man");
+    man.args(["-P", "cat", command])
+        .env_clear()
+        .env("PATH", env::var_os("PATH").unwrap_or_default())
+        .env("LC_ALL", "C")
+        .env("TERM", "dumb")
+        // Wide enough that option descriptions rarely wrap
+        .env("MANWIDTH", "200")
+        .env("GROFF_NO_SGR", "1");
+
+    let output = run_with_timeout(&mut man, GENERATE_TIMEOUT, MAX_MAN_OUTPUT)?;
+    if !output.status.success() || output.stdout.is_empty() {
+        return None;
+    }
+    Some(strip_overstrike(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Remove the `X\bX` bold and `_\bX` underline that man renders for terminals
+fn strip_overstrike(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == '\u{8}' {
+            plain.pop();
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+/// The description from NAME and the options from any section: each an indented row
+/// starting with `-`, described on the same line or the more indented lines after it
+pub fn parse_man(command: &str, text: &str) -> CommandSpec {
+    let mut spec = CommandSpec::new(command);
+    let mut section = String::new();
+    // An option row still waiting for the description under it, and its indent
+    let mut pending: Option<(OptionSpec, usize)> = None;
+
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let indent = line.len() - line.trim_start().len();
+        let trimmed = line.trim();
+
+        if indent == 0 {
+            section = trimmed.to_uppercase();
+            add_option(&mut spec, pending.take().map(|(option, _)| option));
+            continue;
+        }
+
+        if section == "NAME" {
+            if let Some((_, description)) = trimmed.split_once(" - ") {
+                spec.description.get_or_insert_with(|| description.trim().to_string());
+            }
+            continue;
+        }
+
+        if let Some((mut option, option_indent)) = pending.take() {
+            if indent > option_indent {
+                option.description = Some(trimmed.to_string());
+                add_option(&mut spec, Some(option));
+                continue;
+            }
+            add_option(&mut spec, Some(option));
+        }
+
+        if trimmed.starts_with('-') {
+            if let Some(option) = parse_option_line(trimmed) {
+                if option.description.is_some() {
+                    add_option(&mut spec, Some(option));
+                } else {
+                    pending = Some((option, indent));
+                }
+            }
+        }
+    }
+    add_option(&mut spec, pending.map(|(option, _)| option));
+
+    spec
+}
+
+/// Add an option unless one of its names is already taken
+fn add_option(spec: &mut CommandSpec, option: Option<OptionSpec>) {
+    let Some(option) = option else { return };
+    if !spec.options.iter().any(|own| own.names.iter().any(|name| option.names.contains(name))) {
+        spec.options.push(option);
+    }
+}
@@ -0,0 +1,182 @@
+//! Checking spec files
+//!
+//! `rustfig spec lint` reads a spec the way the loader does, then points out what would
+//! make it complete wrongly or not at all: misspelled keys the loader silently ignores,
+//! duplicate names, options that can never be offered a value. `rustfig spec test` uses
+//! `load` to complete an input against one file without installing it.
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use serde_json::Value;
+
+use super::spec::{ArgSpec, CommandSpec, OptionSpec};
+
+const COMMAND_KEYS: &[&str] = &["name", "description", "subcommands", "options", "args"];
+const OPTION_KEYS: &[&str] = &["names", "description", "value_name", "generator", "suggestions"];
+const ARG_KEYS: &[&str] = &["name", "description", "generator", "variadic", "suggestions"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The spec completes wrongly
+    Error,
+    /// Part of the spec has no effect
+    Warning,
+}
+
+/// One thing wrong with a spec
+#[derive(Debug, Clone)]
+pub struct Problem {
+    pub severity: Severity,
+    /// Where it is, like `git remote add` or `git checkout -b`
+    pub location: String,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}: {}: {}", severity, self.location, self.message)
+    }
+}
+
+/// Read a spec file as the loader does, but with the parser's error instead of skipping it
+pub fn load(path: &Path) -> Result<CommandSpec, Box<dyn Error>> {
+    let content = fs::read_to_string(path)?;
+    let spec = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => serde_json::from_str(&content)?,
+        _ => serde_yaml::from_str(&content)?,
+    };
+    Ok(spec)
+}
+
+/// Everything wrong with the spec in `path`; a file that doesn't parse is an `Err`
+pub fn lint_file(path: &Path) -> Result<Vec<Problem>, Box<dyn Error>> {
+    let spec = load(path)?;
+    let content = fs::read_to_string(path)?;
+    let raw: Value = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => serde_json::from_str(&content)?,
+        _ => serde_yaml::from_str(&content)?,
+    };
+
+    let mut problems = Vec::new();
+    unknown_keys(&raw, &spec.name, &mut problems);
+    command(&spec, &spec.name, &mut problems);
+    Ok(problems)
+}
+
+fn command(spec: &CommandSpec, location: &str, problems: &mut Vec<Problem>) {
+    let mut report = |severity, message: String| {
+        problems.push(Problem { severity, location: location.to_string(), message });
+    };
+
+    if spec.name.is_empty() {
+        report(Severity::Error, "has no name".to_string());
+    } else if spec.name.contains(char::is_whitespace) {
+        report(Severity::Error, format!("name '{}' has whitespace, so it can never be typed as one word", spec.name));
+    }
+
+    let mut seen = HashSet::new();
+    for sub in &spec.subcommands {
+        if !seen.insert(sub.name.as_str()) {
+            report(Severity::Warning, format!("subcommand '{}' is defined twice; only the first is used", sub.name));
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for option in &spec.options {
+        if option.names.is_empty() {
+            report(Severity::Error, "an option has no names".to_string());
+        }
+        for name in &option.names {
+            if !name.starts_with('-') {
+                report(Severity::Error, format!("option '{}' doesn't start with '-', so it's never offered", name));
+            }
+            if !seen.insert(name.as_str()) {
+                report(Severity::Warning, format!("option '{}' is defined twice; only the first is used", name));
+            }
+        }
+    }
+
+    if let Some(index) = spec.args.iter().position(|arg| arg.variadic) {
+        if index + 1 < spec.args.len() {
+            report(Severity::Error, format!("argument '{}' is variadic, so the arguments after it are never reached", spec.args[index].name));
+        }
+    }
+
+    for option in &spec.options {
+        option_spec(option, location, problems);
+    }
+    for arg in &spec.args {
+        arg_spec(arg, location, problems);
+    }
+    for sub in &spec.subcommands {
+        command(sub, &format!("{} {}", location, sub.name), problems);
+    }
+}
+
+fn option_spec(option: &OptionSpec, location: &str, problems: &mut Vec<Problem>) {
+    let location = format!("{} {}", location, option.names.first().map(String::as_str).unwrap_or("<option>"));
+    if option.value_name.is_none() && (option.generator.is_some() || !option.suggestions.is_empty()) {
+        problems.push(Problem {
+            severity: Severity::Warning,
+            location: location.clone(),
+            message: "has values but no value_name, so it's completed as a flag and they're never offered".to_string(),
+        });
+    }
+    generator(option.generator.as_deref(), &location, problems);
+}
+
+fn arg_spec(arg: &ArgSpec, location: &str, problems: &mut Vec<Problem>) {
+    let location = format!("{} <{}>", location, arg.name);
+    if arg.name.is_empty() {
+        problems.push(Problem { severity: Severity::Warning, location: location.clone(), message: "has no name".to_string() });
+    }
+    generator(arg.generator.as_deref(), &location, problems);
+}
+
+/// Generators are registered by the built-in completers; spec files have none to call
+fn generator(name: Option<&str>, location: &str, problems: &mut Vec<Problem>) {
+    if let Some(name) = name {
+        problems.push(Problem {
+            severity: Severity::Warning,
+            location: location.to_string(),
+            message: format!("generator '{}' is ignored: spec files can't run generators; list values under suggestions", name),
+        });
+    }
+}
+
+/// Keys the loader ignores, usually typos like `subcommand` or `value-name`
+fn unknown_keys(raw: &Value, location: &str, problems: &mut Vec<Problem>) {
+    check_keys(raw, COMMAND_KEYS, location, problems);
+
+    for option in raw.get("options").and_then(Value::as_array).into_iter().flatten() {
+        let name = option.get("names").and_then(|names| names.get(0)).and_then(Value::as_str).unwrap_or("<option>");
+        check_keys(option, OPTION_KEYS, &format!("{} {}", location, name), problems);
+    }
+    for arg in raw.get("args").and_then(Value::as_array).into_iter().flatten() {
+        let name = arg.get("name").and_then(Value::as_str).unwrap_or_default();
+        check_keys(arg, ARG_KEYS, &format!("{} <{}>", location, name), problems);
+    }
+    for sub in raw.get("subcommands").and_then(Value::as_array).into_iter().flatten() {
+        let name = sub.get("name").and_then(Value::as_str).unwrap_or_default();
+        unknown_keys(sub, &format!("{} {}", location, name), problems);
+    }
+}
+
+fn check_keys(raw: &Value, known: &[&str], location: &str, problems: &mut Vec<Problem>) {
+    for key in raw.as_object().into_iter().flat_map(|object| object.keys()) {
+        if !known.contains(&key.as_str()) {
+            problems.push(Problem {
+                severity: Severity::Warning,
+                location: location.to_string(),
+                message: format!("unknown key '{}' is ignored; expected one of {}", key, known.join(", ")),
+            });
+        }
+    }
+}
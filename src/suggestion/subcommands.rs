@@ -0,0 +1,246 @@
+//! "Did you mean" for subcommands
+//!
+//! `git pshu` names a real command but no subcommand of it. For tools whose subcommands
+//! are known (git, cargo, kubectl, docker), a word at a subcommand's position that
+//! neither names one nor starts one is matched against that level's subcommands, and
+//! the closest within a typo's reach is offered: as the top of the dropdown while the
+//! word is typed, and as the corrected line once it's finished.
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::process::Command;
+use std::time::Duration;
+use once_cell::sync::OnceCell;
+
+use super::completers::cargo::cargo_spec;
+use super::completers::kubectl::kubectl_spec;
+use super::spec::{CommandSpec, OptionSpec};
+use super::{Suggestion, SuggestionKind};
+use crate::utils::process::run_with_timeout;
+
+/// Above every other suggestion's score, so the correction comes first
+const CORRECTION_SCORE: f32 = 120.0;
+
+/// Deadline for reading the user's git aliases
+const GIT_CONFIG_TIMEOUT: Duration = Duration::from_millis(200);
+
+const GIT_SUBCOMMANDS: &[&str] = &[
+    "add", "am", "apply", "archive", "bisect", "blame", "branch", "bundle", "checkout", "cherry-pick", "clean",
+    "clone", "commit", "config", "describe", "diff", "difftool", "fetch", "format-patch", "fsck", "gc", "grep",
+    "help", "init", "log", "ls-files", "ls-remote", "merge", "mergetool", "mv", "notes", "prune", "pull", "push",
+    "range-diff", "rebase", "reflog", "remote", "repack", "replace", "reset", "restore", "revert", "rev-parse", "rm",
+    "send-email", "shortlog", "show", "sparse-checkout", "stash", "status", "submodule", "switch", "tag", "worktree",
+];
+
+const GIT_NESTED: &[(&str, &[&str])] = &[
+    ("stash", &["list", "show", "drop", "pop", "apply", "push", "clear", "branch", "save"]),
+    ("remote", &["add", "remove", "rename", "set-url", "get-url", "show", "prune", "update"]),
+    ("worktree", &["add", "list", "remove", "prune", "move", "lock", "unlock", "repair"]),
+    ("submodule", &["add", "update", "init", "deinit", "status", "sync", "foreach", "summary"]),
+    ("bisect", &["start", "good", "bad", "new", "old", "skip", "reset", "run", "log", "replay"]),
+];
+
+/// Global options of git that take a value, so their value isn't read as the subcommand
+const GIT_VALUE_OPTIONS: &[&str] = &["-C", "-c", "--git-dir", "--work-tree", "--namespace"];
+
+const DOCKER_SUBCOMMANDS: &[&str] = &[
+    "attach", "build", "builder", "buildx", "commit", "compose", "config", "container", "context", "cp", "create",
+    "diff", "events", "exec", "export", "history", "image", "images", "import", "info", "init", "inspect", "kill",
+    "load", "login", "logout", "logs", "manifest", "network", "node", "pause", "plugin", "port", "ps", "pull",
+    "push", "rename", "restart", "rm", "rmi", "run", "save", "search", "secret", "service", "stack", "start",
+    "stats", "stop", "swarm", "system", "tag", "top", "trust", "unpause", "update", "version", "volume", "wait",
+];
+
+const DOCKER_NESTED: &[(&str, &[&str])] = &[
+    ("compose", &["build", "config", "cp", "create", "down", "events", "exec", "images", "kill", "logs", "ls", "pause", "port", "ps", "pull", "push", "restart", "rm", "run", "start", "stop", "top", "unpause", "up", "version", "watch"]),
+    ("container", &["attach", "commit", "cp", "create", "diff", "exec", "export", "inspect", "kill", "logs", "ls", "pause", "port", "prune", "rename", "restart", "rm", "run", "start", "stats", "stop", "top", "unpause", "update", "wait"]),
+    ("image", &["build", "history", "import", "inspect", "load", "ls", "prune", "pull", "push", "rm", "save", "tag"]),
+    ("network", &["connect", "create", "disconnect", "inspect", "ls", "prune", "rm"]),
+    ("volume", &["create", "inspect", "ls", "prune", "rm"]),
+    ("system", &["df", "events", "info", "prune"]),
+];
+
+/// Global options of docker that take a value
+const DOCKER_VALUE_OPTIONS: &[&str] = &["--context", "-c", "-H", "--host", "--config", "-l", "--log-level"];
+
+/// Subcommands the built-in specs leave out, since there's nothing to complete after them
+const CARGO_EXTRA: &[&str] = &[
+    "add", "clean", "fetch", "fix", "fmt", "generate-lockfile", "help", "init", "install", "locate-project", "login",
+    "logout", "metadata", "new", "owner", "package", "pkgid", "publish", "report", "rustc", "rustdoc", "search",
+    "tree", "uninstall", "vendor", "verify-project", "version", "yank",
+];
+
+const KUBECTL_EXTRA: &[&str] = &[
+    "annotate", "api-resources", "api-versions", "apply", "auth", "autoscale", "certificate", "cluster-info",
+    "completion", "cordon", "cp", "create", "debug", "diff", "drain", "events", "explain", "expose", "help",
+    "kustomize", "label", "patch", "plugin", "proxy", "replace", "run", "set", "taint", "top", "uncordon",
+    "version", "wait",
+];
+
+/// A misspelled subcommand and its fix
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubcommandCorrection {
+    /// Index of the misspelled word among the words after the command
+    pub index: usize,
+    pub typed: String,
+    pub corrected: String,
+}
+
+/// Finds misspelled subcommands of tools with known subcommands
+pub struct SubcommandCorrector {
+    /// Built on first use; plugins and git aliases are read then
+    specs: OnceCell<HashMap<&'static str, CommandSpec>>,
+}
+
+impl SubcommandCorrector {
+    pub fn new() -> Self {
+        Self { specs: OnceCell::new() }
+    }
+
+    fn spec(&self, command: &str) -> Option<&CommandSpec> {
+        self.specs.get_or_init(build_specs).get(command)
+    }
+
+    /// The first misspelled subcommand in `words` (the arguments after `command`); the
+    /// last word may be partial, so one that starts a subcommand isn't a typo
+    pub fn correct(&self, command: &str, words: &[&str], last_partial: bool) -> Option<SubcommandCorrection> {
+        let root = self.spec(command)?;
+        let mut level = root;
+        let mut skip_value = false;
+
+        for (index, word) in words.iter().enumerate() {
+            if skip_value {
+                skip_value = false;
+                continue;
+            }
+            if word.starts_with('-') {
+                skip_value = !word.contains('=')
+                    && level.option(word).or_else(|| root.option(word)).map_or(false, |o| o.value_name.is_some());
+                continue;
+            }
+            // Past the subcommands, or at a level whose words are arguments
+            if level.subcommands.is_empty() || !level.args.is_empty() {
+                return None;
+            }
+            if let Some(sub) = level.subcommand(word) {
+                level = sub;
+                continue;
+            }
+
+            let partial = last_partial && index + 1 == words.len();
+            if partial && level.subcommands.iter().any(|sub| sub.name.starts_with(word)) {
+                return None;
+            }
+            let (sub, _) = level.closest_subcommand(word)?;
+            return Some(SubcommandCorrection { index, typed: word.to_string(), corrected: sub.name.clone() });
+        }
+
+        None
+    }
+
+    /// The correction for the word being typed, as a dropdown entry showing the whole
+    /// corrected command
+    pub fn suggest(&self, command: &str, args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        if current_arg.is_empty() || current_arg.starts_with('-') {
+            return Vec::new();
+        }
+        let mut words = args.to_vec();
+        words.push(current_arg);
+
+        match self.correct(command, &words, true) {
+            // Only the word being typed; an earlier typo is fixed from the ghost text
+            Some(correction) if correction.index == args.len() => {
+                let corrected: Vec<&str> = args.iter().copied().chain([correction.corrected.as_str()]).collect();
+                let line = format!("{} {}", command, corrected.join(" "));
+                vec![Suggestion::new(line, correction.corrected, SuggestionKind::Command)
+                    .with_description(format!("did you mean? ({} is not a {} command)", correction.typed, command))
+                    .with_score(CORRECTION_SCORE)]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn build_specs() -> HashMap<&'static str, CommandSpec> {
+    let mut git = nested_spec("git", GIT_SUBCOMMANDS, GIT_NESTED, GIT_VALUE_OPTIONS);
+    add_subcommands(&mut git, git_aliases());
+    add_subcommands(&mut git, plugins("git-"));
+
+    let mut docker = nested_spec("docker", DOCKER_SUBCOMMANDS, DOCKER_NESTED, DOCKER_VALUE_OPTIONS);
+    add_subcommands(&mut docker, docker_plugins());
+
+    let mut cargo = cargo_spec();
+    add_subcommands(&mut cargo, CARGO_EXTRA.iter().map(|name| name.to_string()));
+    add_subcommands(&mut cargo, plugins("cargo-"));
+
+    let mut kubectl = kubectl_spec();
+    add_subcommands(&mut kubectl, KUBECTL_EXTRA.iter().map(|name| name.to_string()));
+    add_subcommands(&mut kubectl, plugins("kubectl-"));
+
+    HashMap::from([("git", git), ("docker", docker), ("cargo", cargo), ("kubectl", kubectl)])
+}
+
+/// A spec that's only subcommand names, with one level of nesting
+fn nested_spec(name: &str, subcommands: &[&str], nested: &[(&str, &[&str])], value_options: &[&str]) -> CommandSpec {
+    let mut spec = CommandSpec::new(name);
+    spec.subcommands = subcommands
+        .iter()
+        .map(|sub| {
+            let mut sub_spec = CommandSpec::new(sub);
+            if let Some((_, names)) = nested.iter().find(|(parent, _)| parent == sub) {
+                sub_spec.subcommands = names.iter().map(|name| CommandSpec::new(name)).collect();
+            }
+            sub_spec
+        })
+        .collect();
+    spec.options = value_options
+        .iter()
+        .map(|option| OptionSpec { names: vec![option.to_string()], value_name: Some("VALUE".to_string()), ..Default::default() })
+        .collect();
+    spec
+}
+
+/// Add subcommands the spec doesn't have yet; they take anything after them
+fn add_subcommands(spec: &mut CommandSpec, names: impl IntoIterator<Item = String>) {
+    for name in names {
+        if spec.subcommand(&name).is_none() {
+            spec.subcommands.push(CommandSpec::new(&name));
+        }
+    }
+}
+
+/// External subcommands on PATH, like `cargo-watch` for `cargo watch`
+fn plugins(prefix: &str) -> Vec<String> {
+    let Some(path) = env::var_os("PATH") else { return Vec::new() };
+    env::split_paths(&path)
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten())
+        .filter_map(|entry| entry.file_name().to_str()?.strip_prefix(prefix).map(str::to_string))
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Docker CLI plugins, like `compose` and `buildx`
+fn docker_plugins() -> Vec<String> {
+    let Some(home) = dirs::home_dir() else { return Vec::new() };
+    ["/usr/libexec/docker/cli-plugins", "/usr/lib/docker/cli-plugins", "/usr/local/lib/docker/cli-plugins"]
+        .iter()
+        .map(std::path::PathBuf::from)
+        .chain([home.join(".docker/cli-plugins")])
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten())
+        .filter_map(|entry| entry.file_name().to_str()?.strip_prefix("docker-").map(str::to_string))
+        .collect()
+}
+
+/// The user's git aliases, which are subcommands as far as typos go
+fn git_aliases() -> Vec<String> {
+    let mut command = Command::new("git");
+    command.args(["config", "--get-regexp", r"^alias\."]);
+    let Some(output) = run_with_timeout(&mut command, GIT_CONFIG_TIMEOUT, 64 * 1024) else { return Vec::new() };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.strip_prefix("alias.")?.split_whitespace().next().map(str::to_string))
+        .collect()
+}
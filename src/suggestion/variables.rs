@@ -0,0 +1,79 @@
+use std::env;
+
+use super::fuzzy::fast_fuzzy_score;
+use super::{Suggestion, SuggestionKind};
+use crate::config::Config;
+
+/// Longest value shown as a variable's description
+const MAX_VALUE_CHARS: usize = 40;
+
+/// Names whose values aren't shown
+const SECRET_WORDS: &[&str] = &["TOKEN", "SECRET", "PASSWORD", "PASSWD", "KEY", "CREDENTIAL", "AUTH"];
+
+/// Completes `$NAME` and `${NAME}` from the environment
+pub struct VariableSuggester {
+    enabled: bool,
+}
+
+impl VariableSuggester {
+    pub fn new(config: &Config) -> Self {
+        Self { enabled: config.suggestions.enable_variables.unwrap_or(true) }
+    }
+
+    /// Whether `current_arg` ends in a variable reference being typed
+    pub fn applies(&self, current_arg: &str) -> bool {
+        self.enabled && current_arg.contains('$')
+    }
+
+    /// Variables for the reference after the last `$` in `current_arg`
+    pub fn suggest(&self, current_arg: &str, limit: usize) -> Vec<Suggestion> {
+        let Some(dollar) = current_arg.rfind('$') else { return Vec::new() };
+        let (before, reference) = current_arg.split_at(dollar + 1);
+        let (braced, fragment) = match reference.strip_prefix('{') {
+            Some(fragment) => (true, fragment),
+            None => (false, reference),
+        };
+        // Already past the name, e.g. `$HOME/`
+        if fragment.contains(|c: char| !(c.is_ascii_alphanumeric() || c == '_')) {
+            return Vec::new();
+        }
+
+        let mut suggestions: Vec<Suggestion> = env::vars()
+            .filter_map(|(name, value)| {
+                let score = fast_fuzzy_score(fragment, &name)?;
+                let completion = if braced {
+                    format!("{}{{{}}}", before, name)
+                } else {
+                    format!("{}{}", before, name)
+                };
+                Some(
+                    Suggestion::new(format!("${}", name), completion, SuggestionKind::EnvVar)
+                        .with_description(describe(&name, &value))
+                        .with_score(score * 100.0),
+                )
+            })
+            .collect();
+
+        suggestions.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        suggestions.truncate(limit);
+        suggestions
+    }
+}
+
+/// Whether the name suggests the value is a secret
+pub fn is_secret(name: &str) -> bool {
+    let upper = name.to_ascii_uppercase();
+    SECRET_WORDS.iter().any(|word| upper.contains(word))
+}
+
+/// The value, cut short, unless the name suggests a secret
+fn describe(name: &str, value: &str) -> String {
+    if is_secret(name) {
+        return "(hidden)".to_string();
+    }
+    if value.chars().count() > MAX_VALUE_CHARS {
+        let shown: String = value.chars().take(MAX_VALUE_CHARS - 1).collect();
+        return format!("{}…", shown);
+    }
+    value.to_string()
+}
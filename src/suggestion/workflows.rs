@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::plugin::api::CompletionProvider;
+use super::spec::generated_suggestions;
+use super::Suggestion;
+
+/// Name of the workflows directory inside the config directory
+pub const WORKFLOWS_DIR: &str = "workflows";
+
+/// Project-local workflows, shared with the team through the repository
+pub const PROJECT_WORKFLOWS_DIR: &str = ".rustfig/workflows";
+
+/// A named multi-step procedure with typed parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workflow {
+    pub name: String,
+
+    #[serde(default)]
+    pub description: Option<String>,
+
+    #[serde(default)]
+    pub parameters: Vec<WorkflowParameter>,
+
+    pub steps: Vec<WorkflowStep>,
+
+    /// File the workflow was loaded from
+    #[serde(skip)]
+    pub file: PathBuf,
+}
+
+/// A parameter prompted for (or passed as `--name value`) before the steps run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowParameter {
+    pub name: String,
+
+    #[serde(default, rename = "type")]
+    pub kind: ParameterType,
+
+    #[serde(default)]
+    pub description: Option<String>,
+
+    #[serde(default)]
+    pub default: Option<String>,
+
+    /// Allowed values for `choice` parameters
+    #[serde(default)]
+    pub choices: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParameterType {
+    #[default]
+    String,
+    Int,
+    Bool,
+    Choice,
+    Path,
+}
+
+/// A single shell command; `{{param}}` is replaced with the parameter's quoted value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkflowStep {
+    pub name: String,
+
+    pub run: String,
+
+    /// Ask before running this step
+    #[serde(default)]
+    pub confirm: bool,
+
+    /// Keep going if this step fails
+    #[serde(default)]
+    pub continue_on_error: bool,
+}
+
+impl WorkflowParameter {
+    /// Check a value against the parameter's type, normalizing booleans
+    pub fn validate(&self, value: &str) -> Result<String, String> {
+        match self.kind {
+            ParameterType::String => Ok(value.to_string()),
+            ParameterType::Int => value.trim().parse::<i64>()
+                .map(|n| n.to_string())
+                .map_err(|_| format!("{} must be an integer", self.name)),
+            ParameterType::Bool => match value.trim().to_lowercase().as_str() {
+                "true" | "yes" | "y" | "1" => Ok("true".to_string()),
+                "false" | "no" | "n" | "0" => Ok("false".to_string()),
+                _ => Err(format!("{} must be true or false", self.name)),
+            },
+            ParameterType::Choice => {
+                if self.choices.iter().any(|choice| choice == value) {
+                    Ok(value.to_string())
+                } else {
+                    Err(format!("{} must be one of: {}", self.name, self.choices.join(", ")))
+                }
+            }
+            ParameterType::Path => {
+                if value.is_empty() {
+                    Err(format!("{} must be a path", self.name))
+                } else {
+                    Ok(value.to_string())
+                }
+            }
+        }
+    }
+
+    /// Values worth offering for completion
+    pub fn candidates(&self) -> Vec<String> {
+        match self.kind {
+            ParameterType::Choice => self.choices.clone(),
+            ParameterType::Bool => vec!["true".to_string(), "false".to_string()],
+            _ => self.default.iter().cloned().collect(),
+        }
+    }
+}
+
+impl Workflow {
+    /// Substitute validated parameter values into a step's command
+    pub fn render_step(&self, step: &WorkflowStep, values: &HashMap<String, String>) -> String {
+        let mut command = step.run.clone();
+        for parameter in &self.parameters {
+            if let Some(value) = values.get(&parameter.name) {
+                command = command.replace(&format!("{{{{{}}}}}", parameter.name), &shell_quote(value));
+            }
+        }
+        command
+    }
+}
+
+/// Quote a value for `sh` unless it's made only of safe characters
+pub fn shell_quote(value: &str) -> String {
+    let safe = !value.is_empty()
+        && value.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:=@,+%".contains(c));
+
+    if safe {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+/// Directories searched for workflows: the project's `.rustfig/workflows`, then the user's
+pub fn workflow_dirs(cwd: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = cwd.ancestors()
+        .map(|ancestor| ancestor.join(PROJECT_WORKFLOWS_DIR))
+        .find(|dir| dir.is_dir())
+        .into_iter()
+        .collect();
+
+    if let Ok(config_dir) = crate::config::loader::get_config_dir() {
+        dirs.push(config_dir.join(WORKFLOWS_DIR));
+    }
+
+    dirs
+}
+
+/// Load every workflow visible from `cwd`; project workflows shadow user ones of the same name
+pub fn load_workflows(cwd: &Path) -> Vec<Workflow> {
+    let mut workflows: Vec<Workflow> = Vec::new();
+
+    for dir in workflow_dirs(cwd) {
+        let mut files: Vec<PathBuf> = match fs::read_dir(&dir) {
+            Ok(entries) => entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| matches!(path.extension().and_then(|e| e.to_str()), Some("yaml" | "yml")))
+                .collect(),
+            Err(_) => continue,
+        };
+        files.sort();
+
+        for file in files {
+            if let Ok(workflow) = load_workflow_file(&file) {
+                if !workflows.iter().any(|existing| existing.name == workflow.name) {
+                    workflows.push(workflow);
+                }
+            }
+        }
+    }
+
+    workflows
+}
+
+/// Parse a single workflow file
+pub fn load_workflow_file(path: &Path) -> Result<Workflow, Box<dyn Error>> {
+    let content = fs::read_to_string(path)?;
+    let mut workflow: Workflow = serde_yaml::from_str(&content)?;
+    workflow.file = path.to_path_buf();
+
+    for parameter in &workflow.parameters {
+        if parameter.kind == ParameterType::Choice && parameter.choices.is_empty() {
+            return Err(format!("parameter {} in {} is a choice without choices", parameter.name, path.display()).into());
+        }
+    }
+
+    Ok(workflow)
+}
+
+/// Completes `rustfig run <workflow> --param value`
+pub struct WorkflowCompleter;
+
+impl WorkflowCompleter {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl CompletionProvider for WorkflowCompleter {
+    fn name(&self) -> &str {
+        "workflows"
+    }
+
+    fn can_provide_completions(&self, command: &str) -> bool {
+        command == "rustfig"
+    }
+
+    fn provide_completions(&self, _command: &str, args: &[&str], current_arg: &str) -> Vec<Suggestion> {
+        if args.first() != Some(&"run") {
+            return Vec::new();
+        }
+
+        let cwd = match env::current_dir() {
+            Ok(cwd) => cwd,
+            Err(_) => return Vec::new(),
+        };
+        let workflows = load_workflows(&cwd);
+
+        // `rustfig run <workflow>`
+        let name = match args.get(1) {
+            Some(name) => *name,
+            None => {
+                let candidates = workflows.iter().map(|workflow| {
+                    (workflow.name.clone(), workflow.description.clone().unwrap_or_else(|| "workflow".to_string()))
+                });
+                return generated_suggestions(candidates, current_arg);
+            }
+        };
+
+        let workflow = match workflows.iter().find(|workflow| workflow.name == name) {
+            Some(workflow) => workflow,
+            None => return Vec::new(),
+        };
+
+        // `--param <value>`
+        if let Some(parameter) = args.last()
+            .and_then(|last| last.strip_prefix("--"))
+            .and_then(|flag| workflow.parameters.iter().find(|p| p.name == flag))
+        {
+            let description = parameter.description.clone().unwrap_or_else(|| parameter.name.clone());
+            let candidates = parameter.candidates().into_iter().map(|value| (value, description.clone()));
+            return generated_suggestions(candidates, current_arg);
+        }
+
+        // `--param` names not given yet
+        let candidates = workflow.parameters.iter()
+            .filter(|parameter| !args.contains(&format!("--{}", parameter.name).as_str()))
+            .map(|parameter| {
+                let description = parameter.description.clone().unwrap_or_else(|| format!("{:?}", parameter.kind).to_lowercase());
+                (format!("--{}", parameter.name), description)
+            });
+
+        generated_suggestions(candidates, current_arg)
+    }
+}
@@ -42,7 +42,7 @@ pub struct Feedback {
 }
 
 /// Feedback categories
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FeedbackCategory {
     /// General feedback
     General,
@@ -60,6 +60,30 @@ pub enum FeedbackCategory {
     FeatureRequest,
 }
 
+impl FeedbackCategory {
+    pub const ALL: [FeedbackCategory; 7] = [
+        FeedbackCategory::General,
+        FeedbackCategory::Suggestions,
+        FeedbackCategory::AI,
+        FeedbackCategory::UI,
+        FeedbackCategory::Performance,
+        FeedbackCategory::BugReport,
+        FeedbackCategory::FeatureRequest,
+    ];
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            FeedbackCategory::General => "General feedback",
+            FeedbackCategory::Suggestions => "Suggestions and completions",
+            FeedbackCategory::AI => "AI features",
+            FeedbackCategory::UI => "Interface",
+            FeedbackCategory::Performance => "Performance",
+            FeedbackCategory::BugReport => "Bug report",
+            FeedbackCategory::FeatureRequest => "Feature request",
+        }
+    }
+}
+
 /// System information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemInfo {
@@ -81,13 +105,7 @@ impl FeedbackCollector {
     /// Create a new feedback collector
     pub fn new(config: TelemetryConfig) -> Self {
         // Determine storage path
-        let storage_path = config.data_dir.clone()
-            .unwrap_or_else(|| {
-                dirs::data_dir()
-                    .unwrap_or_else(|| PathBuf::from("."))
-                    .join("rustfig")
-                    .join("feedback")
-            });
+        let storage_path = super::feedback_dir(&config);
         
         // Create directory if it doesn't exist
         if let Err(e) = fs::create_dir_all(&storage_path) {
@@ -107,14 +125,20 @@ impl FeedbackCollector {
         // Save locally always
         self.save_feedback_locally(&feedback)?;
         
-        // Upload if telemetry is enabled
-        if self.enabled {
+        // Upload if telemetry is enabled and the network can be used
+        if self.enabled && !crate::utils::offline::is_offline() {
             self.upload_feedback(&feedback).await?;
         }
         
         Ok(())
     }
     
+    /// Save and upload whatever the telemetry setting, for something the user chose to send
+    pub async fn submit_explicitly(&self, feedback: Feedback) -> Result<(), Box<dyn Error>> {
+        self.save_feedback_locally(&feedback)?;
+        self.upload_feedback(&feedback).await
+    }
+    
     /// Save feedback locally
     fn save_feedback_locally(&self, feedback: &Feedback) -> Result<(), Box<dyn Error>> {
         let file_path = self.storage_path.join(format!("feedback_{}.json", feedback.id));
@@ -125,6 +149,10 @@ impl FeedbackCollector {
     
     /// Upload feedback to server
     async fn upload_feedback(&self, feedback: &Feedback) -> Result<(), Box<dyn Error>> {
+        if crate::utils::offline::is_offline() {
+            return Err("RustFig is offline; the feedback was saved locally but not sent".into());
+        }
+        
         let response = self.client.post(&self.upload_url)
             .json(feedback)
             .send()
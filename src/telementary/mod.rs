@@ -1,12 +1,18 @@
 mod usage;
 mod feedback;
+mod redact;
 
 pub use usage::UsageTracker;
-pub use feedback::FeedbackCollector;
+pub use feedback::{Feedback, FeedbackCategory, FeedbackCollector};
+pub use redact::Redactor;
 
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use once_cell::sync::Lazy;
+use crate::config::TelemetryConfig;
 
 // Global telemetry enabled flag
 static TELEMETRY_ENABLED: Lazy<Arc<AtomicBool>> = Lazy::new(|| {
@@ -43,3 +49,46 @@ pub fn create_feedback_collector(config: &crate::config::Config) -> FeedbackColl
     let telemetry_config = config.telemetry.clone().unwrap_or_default();
     FeedbackCollector::new(telemetry_config)
 }
+
+/// Where usage events and the anonymous user id are kept
+pub fn usage_dir(config: &TelemetryConfig) -> PathBuf {
+    config.data_dir.clone().unwrap_or_else(|| default_data_dir().join("telemetry"))
+}
+
+/// Where submitted feedback is kept
+pub fn feedback_dir(config: &TelemetryConfig) -> PathBuf {
+    config.data_dir.clone().unwrap_or_else(|| default_data_dir().join("feedback"))
+}
+
+fn default_data_dir() -> PathBuf {
+    dirs::data_dir().unwrap_or_else(|| PathBuf::from(".")).join("rustfig")
+}
+
+/// Files telemetry and feedback have stored on this machine
+pub fn stored_files(config: &TelemetryConfig) -> Vec<PathBuf> {
+    let usage_dir = usage_dir(config);
+    let mut files: Vec<PathBuf> = [usage::USAGE_FILE_NAME, usage::USER_ID_FILE_NAME]
+        .iter()
+        .map(|name| usage_dir.join(name))
+        .filter(|path| path.is_file())
+        .collect();
+
+    // Only rustfig's own files: `data_dir` may be shared with other things
+    if let Ok(entries) = fs::read_dir(feedback_dir(config)) {
+        files.extend(entries.flatten().map(|entry| entry.path()).filter(|path| {
+            path.is_file()
+                && path.extension().map_or(false, |ext| ext == "json")
+                && path.file_name().and_then(|name| name.to_str()).map_or(false, |name| name.starts_with("feedback_"))
+        }));
+    }
+    files
+}
+
+/// Delete everything telemetry and feedback have stored; returns how many files went
+pub fn purge(config: &TelemetryConfig) -> Result<usize, Box<dyn Error>> {
+    let files = stored_files(config);
+    for file in &files {
+        fs::remove_file(file)?;
+    }
+    Ok(files.len())
+}
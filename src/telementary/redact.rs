@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use regex::Regex;
+use crate::config::schema::CommandDetail;
+use crate::config::TelemetryConfig;
+
+/// Event properties that hold command lines
+const COMMAND_PROPERTIES: &[&str] = &["command", "suggestion", "ghost_text"];
+
+/// What replaces a match of a `redact_patterns` entry
+const REDACTED: &str = "<redacted>";
+
+/// Short flag clusters longer than this are taken to carry a value (`-psecret`)
+const MAX_FLAG_CLUSTER: usize = 4;
+
+/// Strips event properties down to what `telemetry.command_detail` allows, then masks
+/// matches of `telemetry.redact_patterns` and the home directory in what's left
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    detail: CommandDetail,
+    patterns: Vec<Regex>,
+    home: Option<String>,
+}
+
+impl Redactor {
+    pub fn new(config: &TelemetryConfig) -> Self {
+        // A pattern that doesn't compile is skipped rather than disabling telemetry
+        let patterns = config
+            .redact_patterns
+            .iter()
+            .flatten()
+            .filter_map(|pattern| Regex::new(pattern).ok())
+            .collect();
+
+        Self {
+            detail: config.command_detail.unwrap_or(CommandDetail::Flags),
+            patterns,
+            home: dirs::home_dir()
+                .and_then(|home| home.to_str().map(str::to_string))
+                .filter(|home| home.len() > 1),
+        }
+    }
+
+    /// Redact an event's properties in place
+    pub fn redact(&self, properties: &mut HashMap<String, String>) {
+        for key in COMMAND_PROPERTIES {
+            if let Some(command) = properties.remove(*key) {
+                if let Some(kept) = self.command(&command) {
+                    properties.insert(key.to_string(), kept);
+                }
+            }
+        }
+        for value in properties.values_mut() {
+            *value = self.mask(value);
+        }
+    }
+
+    /// The part of a command line that may be kept, or None for nothing
+    fn command(&self, line: &str) -> Option<String> {
+        let kept = match self.detail {
+            CommandDetail::None => return None,
+            CommandDetail::Program => reduce(line, false),
+            CommandDetail::Flags => reduce(line, true),
+            CommandDetail::Full => line.to_string(),
+        };
+        Some(kept)
+    }
+
+    fn mask(&self, value: &str) -> String {
+        let mut value = value.to_string();
+        for pattern in &self.patterns {
+            value = pattern.replace_all(&value, REDACTED).into_owned();
+        }
+        if let Some(home) = &self.home {
+            value = value.replace(home.as_str(), "~");
+        }
+        value
+    }
+}
+
+/// Program names (and flag names, with `flags`) of each command on the line, joined by
+/// the operators between them
+fn reduce(line: &str, flags: bool) -> String {
+    let mut kept = Vec::new();
+    let mut at_command = true;
+    for word in words(line) {
+        if is_operator(&word) {
+            kept.push(word);
+            at_command = true;
+        } else if at_command {
+            // Assignments in `FOO=bar cmd` aren't the program
+            if word.contains('=') && !word.starts_with('=') {
+                continue;
+            }
+            kept.push(program_name(&word).to_string());
+            at_command = false;
+        } else if flags {
+            if let Some(flag) = flag_name(&word) {
+                kept.push(flag);
+            }
+        }
+    }
+    kept.join(" ")
+}
+
+/// The last path component, so `/home/me/bin/tool` doesn't give the path away
+fn program_name(word: &str) -> &str {
+    word.rsplit('/').next().unwrap_or(word)
+}
+
+/// `--name` from `--name=value`, a short cluster like `-la`, or None for anything else
+fn flag_name(word: &str) -> Option<String> {
+    if let Some(long) = word.strip_prefix("--") {
+        let name = long.split('=').next().unwrap_or("");
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return None;
+        }
+        return Some(format!("--{}", name));
+    }
+
+    let short = word.strip_prefix('-')?;
+    let first = short.chars().next().filter(|c| c.is_ascii_alphabetic())?;
+    if short.len() <= MAX_FLAG_CLUSTER && short.chars().all(|c| c.is_ascii_alphabetic()) {
+        Some(word.to_string())
+    } else {
+        // A value glued to the flag
+        Some(format!("-{}", first))
+    }
+}
+
+fn is_operator(word: &str) -> bool {
+    matches!(word, "|" | "||" | "&&" | ";" | "&")
+}
+
+/// Words of the line, quotes kept inside their word, with control operators as words
+/// of their own
+fn words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote = None;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => {
+                quote = None;
+                word.push(c);
+            }
+            (quote, '\\') if quote != Some('\'') => {
+                word.push(c);
+                if let Some(escaped) = chars.next() {
+                    word.push(escaped);
+                }
+            }
+            (Some(_), c) => word.push(c),
+            // `2>&1` and `<&3` are redirections, not background operators
+            (None, '&') if word.ends_with('>') || word.ends_with('<') => word.push(c),
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                word.push(c);
+            }
+            (None, '|' | '&' | ';') => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+                let mut operator = c.to_string();
+                if c != ';' && chars.peek() == Some(&c) {
+                    operator.push(c);
+                    chars.next();
+                }
+                words.push(operator);
+            }
+            (None, c) if c.is_whitespace() => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            (None, c) => word.push(c),
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
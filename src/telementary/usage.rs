@@ -11,8 +11,10 @@ use uuid::Uuid;
 use crate::config::TelemetryConfig;
 
 use super::is_telemetry_enabled;
+use super::redact::Redactor;
 
-const USAGE_FILE_NAME: &str = "usage_data.json";
+pub(super) const USAGE_FILE_NAME: &str = "usage_data.json";
+pub(super) const USER_ID_FILE_NAME: &str = "user_id";
 const UPLOAD_INTERVAL: Duration = Duration::from_secs(3600); // 1 hour
 
 /// Tracks usage statistics for RustFig
@@ -23,6 +25,8 @@ pub struct UsageTracker {
     config: TelemetryConfig,
     /// Path to usage data file
     data_path: PathBuf,
+    /// Applied to every event before it's queued or uploaded
+    redactor: Redactor,
     /// Event queue
     event_queue: Mutex<Vec<UsageEvent>>,
     /// Shutdown signal
@@ -65,13 +69,7 @@ impl UsageTracker {
     /// Create a new usage tracker
     pub fn new(config: TelemetryConfig) -> Self {
         // Determine data path
-        let data_dir = config.data_dir.clone()
-            .unwrap_or_else(|| {
-                dirs::data_dir()
-                    .unwrap_or_else(|| PathBuf::from("."))
-                    .join("rustfig")
-                    .join("telemetry")
-            });
+        let data_dir = super::usage_dir(&config);
         
         // Get or create user ID
         let user_id = Self::get_or_create_user_id(&data_dir).unwrap_or_else(|_| {
@@ -80,6 +78,7 @@ impl UsageTracker {
         
         Self {
             user_id,
+            redactor: Redactor::new(&config),
             config,
             data_path: data_dir.join(USAGE_FILE_NAME),
             event_queue: Mutex::new(Vec::new()),
@@ -101,6 +100,7 @@ impl UsageTracker {
             
             let data_path = self.data_path.clone();
             let upload_url = self.config.upload_url.clone();
+            let redactor = self.redactor.clone();
             
             tokio::spawn(async move {
                 let mut interval = time::interval(UPLOAD_INTERVAL);
@@ -108,7 +108,11 @@ impl UsageTracker {
                 loop {
                     tokio::select! {
                         _ = interval.tick() => {
-                            if let Err(e) = Self::upload_usage_data(&data_path, &upload_url).await {
+                            // Events stay queued until the network is back
+                            if crate::utils::offline::is_offline() {
+                                continue;
+                            }
+                            if let Err(e) = Self::upload_usage_data(&data_path, &upload_url, &redactor).await {
                                 eprintln!("Failed to upload usage data: {}", e);
                             }
                         }
@@ -131,11 +135,13 @@ impl UsageTracker {
     }
     
     /// Record a usage event
-    pub fn record_event(&self, event_type: &str, properties: HashMap<String, String>) {
+    pub fn record_event(&self, event_type: &str, mut properties: HashMap<String, String>) {
         if !is_telemetry_enabled() {
             return;
         }
         
+        self.redactor.redact(&mut properties);
+        
         // Create event
         let event = UsageEvent {
             event_type: event_type.to_string(),
@@ -246,7 +252,7 @@ impl UsageTracker {
     
     /// Get or create user ID
     fn get_or_create_user_id(data_dir: &Path) -> Result<String, Box<dyn Error>> {
-        let id_file = data_dir.join("user_id");
+        let id_file = data_dir.join(USER_ID_FILE_NAME);
         
         if id_file.exists() {
             let id = fs::read_to_string(id_file)?;
@@ -260,7 +266,7 @@ impl UsageTracker {
     }
     
     /// Upload usage data
-    async fn upload_usage_data(data_path: &Path, upload_url: &str) -> Result<(), Box<dyn Error>> {
+    async fn upload_usage_data(data_path: &Path, upload_url: &str, redactor: &Redactor) -> Result<(), Box<dyn Error>> {
         if !data_path.exists() {
             return Ok(());
         }
@@ -274,6 +280,11 @@ impl UsageTracker {
             return Ok(());
         }
         
+        // Again at upload, for events stored before the settings were tightened
+        for event in &mut data.events {
+            redactor.redact(&mut event.properties);
+        }
+        
         // Upload data
         let client = reqwest::Client::new();
         let response = client.post(upload_url)
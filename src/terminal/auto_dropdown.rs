@@ -0,0 +1,70 @@
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+
+/// Opens the dropdown without Tab, once typing pauses on a long enough word
+///
+/// `ui.auto_show_dropdown` turns it on; the word under the cursor needs
+/// `suggestions.min_prefix_length` characters, and the dropdown appears
+/// `ui.dropdown_delay_ms` after the last edit so fast typing doesn't flicker it open.
+/// With `suggestions.complete_while_typing` off, the dropdown never follows typing:
+/// nothing opens on its own and an open one closes on the next edit.
+pub struct AutoDropdown {
+    enabled: bool,
+    follows_typing: bool,
+    min_prefix: usize,
+    delay: Duration,
+    /// When to open it, set by the last edit that left a long enough word
+    due_at: Option<Instant>,
+}
+
+impl AutoDropdown {
+    pub fn new(config: &Config) -> Self {
+        let follows_typing = config.suggestions.complete_while_typing.unwrap_or(true);
+        Self {
+            enabled: follows_typing && config.ui.auto_show_dropdown.unwrap_or(false),
+            follows_typing,
+            min_prefix: config.suggestions.min_prefix_length.unwrap_or(1),
+            delay: Duration::from_millis(config.ui.dropdown_delay_ms.unwrap_or(100)),
+            due_at: None,
+        }
+    }
+
+    /// Whether an open dropdown is updated as the line changes
+    pub fn follows_typing(&self) -> bool {
+        self.follows_typing
+    }
+
+    /// Whether the word before `cursor` is long enough for the dropdown to open on its own;
+    /// always true when it doesn't open on its own
+    pub fn prefix_ready(&self, line: &str, cursor: usize) -> bool {
+        !self.enabled || word_before(line, cursor).chars().count() >= self.min_prefix
+    }
+
+    /// Record an edit that left `line` with the cursor at `cursor`
+    pub fn edited(&mut self, line: &str, cursor: usize, now: Instant) {
+        self.due_at = (self.enabled && !line.trim().is_empty() && self.prefix_ready(line, cursor)).then(|| now + self.delay);
+    }
+
+    /// Forget a pending open, when the dropdown was opened or dismissed another way
+    pub fn cancel(&mut self) {
+        self.due_at = None;
+    }
+
+    /// Whether the dropdown should open now; true once per edit
+    pub fn due(&mut self, now: Instant) -> bool {
+        match self.due_at {
+            Some(at) if now >= at => {
+                self.due_at = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// The part of the word under the cursor that's already typed
+fn word_before(line: &str, cursor: usize) -> &str {
+    let before = line.get(..cursor).unwrap_or(line);
+    before.rsplit(char::is_whitespace).next().unwrap_or("")
+}
@@ -0,0 +1,259 @@
+//! What the terminal can do, and the rendering profile that follows from it
+//!
+//! The terminal is recognized from its environment variables, then asked directly: a
+//! DECRQM query for synchronized output (mode 2026) and `CSI 16 t` for the cell size,
+//! followed by a primary device attributes request that every terminal answers, so the
+//! wait ends as soon as that reply arrives. `terminal.overrides` in the config corrects
+//! whatever detection gets wrong for a given terminal.
+
+use std::env;
+use std::time::Duration;
+
+use crate::config::schema::{RenderProfile, TerminalOverride};
+use crate::config::Config;
+
+/// Terminals with known quirks, recognized from the environment
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminalKind {
+    AppleTerminal,
+    WindowsTerminal,
+    Alacritty,
+    WezTerm,
+    Kitty,
+    Iterm2,
+    VsCode,
+    /// The Linux virtual console or a dumb terminal
+    Console,
+    Unknown,
+}
+
+impl TerminalKind {
+    pub fn detect() -> Self {
+        let var = |name: &str| env::var(name).unwrap_or_default();
+        let term = var("TERM");
+
+        match var("TERM_PROGRAM").as_str() {
+            "Apple_Terminal" => return Self::AppleTerminal,
+            "WezTerm" => return Self::WezTerm,
+            "iTerm.app" => return Self::Iterm2,
+            "vscode" => return Self::VsCode,
+            _ => {}
+        }
+        if !var("WT_SESSION").is_empty() {
+            Self::WindowsTerminal
+        } else if !var("KITTY_WINDOW_ID").is_empty() || term == "xterm-kitty" {
+            Self::Kitty
+        } else if !var("ALACRITTY_WINDOW_ID").is_empty() || term == "alacritty" {
+            Self::Alacritty
+        } else if !var("WEZTERM_PANE").is_empty() {
+            Self::WezTerm
+        } else if term == "linux" || term == "dumb" {
+            Self::Console
+        } else {
+            Self::Unknown
+        }
+    }
+
+    /// Key under `terminal.overrides`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::AppleTerminal => "apple_terminal",
+            Self::WindowsTerminal => "windows_terminal",
+            Self::Alacritty => "alacritty",
+            Self::WezTerm => "wezterm",
+            Self::Kitty => "kitty",
+            Self::Iterm2 => "iterm2",
+            Self::VsCode => "vscode",
+            Self::Console => "console",
+            Self::Unknown => "unknown",
+        }
+    }
+
+    /// Whether 24-bit color is known to work, when `COLORTERM` doesn't say
+    fn truecolor(&self) -> bool {
+        // Terminal.app maps 24-bit colors onto its 256-color palette, badly
+        !matches!(self, Self::AppleTerminal | Self::Console | Self::Unknown)
+    }
+
+    /// Whether OSC sequences (hyperlinks, prompt marks) are understood rather than printed
+    fn osc(&self) -> bool {
+        !matches!(self, Self::Console)
+    }
+}
+
+/// What the terminal supports
+#[derive(Debug, Clone)]
+pub struct Capabilities {
+    pub terminal: TerminalKind,
+    pub truecolor: bool,
+    /// Frames can be wrapped in mode 2026 so they appear all at once, without tearing
+    pub synchronized_output: bool,
+    pub osc: bool,
+    /// Width and height of a cell in pixels, if the terminal reports them
+    pub cell_size: Option<(u16, u16)>,
+    pub profile: RenderProfile,
+}
+
+impl Capabilities {
+    /// Detect from the environment only; no terminal queries
+    pub fn from_env(config: &Config) -> Self {
+        let terminal = TerminalKind::detect();
+        let truecolor = match env::var("COLORTERM").as_deref() {
+            Ok("truecolor") | Ok("24bit") => true,
+            _ => terminal.truecolor(),
+        };
+
+        let mut capabilities = Self {
+            terminal,
+            truecolor,
+            synchronized_output: false,
+            osc: terminal.osc(),
+            cell_size: None,
+            profile: RenderProfile::Basic,
+        };
+        capabilities.finish(config);
+        capabilities
+    }
+
+    /// Detect from the environment, then query the terminal if `terminal.probe` allows
+    ///
+    /// Needs raw mode, and must run before anything else reads input: the replies arrive
+    /// on stdin.
+    pub fn detect(config: &Config) -> Self {
+        let mut capabilities = Self::from_env(config);
+        let settings = config.terminal.as_ref();
+        if settings.and_then(|terminal| terminal.probe).unwrap_or(true) && capabilities.terminal != TerminalKind::Console {
+            let timeout = Duration::from_millis(settings.and_then(|terminal| terminal.probe_timeout_ms).unwrap_or(100));
+            if let Some(reply) = probe::query(timeout) {
+                capabilities.synchronized_output = reply.synchronized_output;
+                capabilities.cell_size = reply.cell_size;
+            }
+        }
+        capabilities.finish(config);
+        capabilities
+    }
+
+    /// Apply the configured override for this terminal and pick the profile
+    fn finish(&mut self, config: &Config) {
+        let overrides: Option<&TerminalOverride> = config
+            .terminal
+            .as_ref()
+            .and_then(|terminal| terminal.overrides.get(self.terminal.name()));
+
+        if let Some(overrides) = overrides {
+            self.truecolor = overrides.truecolor.unwrap_or(self.truecolor);
+            self.synchronized_output = overrides.synchronized_output.unwrap_or(self.synchronized_output);
+            self.osc = overrides.osc.unwrap_or(self.osc);
+        }
+
+        self.profile = match overrides.and_then(|overrides| overrides.profile) {
+            Some(profile) => profile,
+            None if self.terminal == TerminalKind::Console => RenderProfile::Minimal,
+            None if self.truecolor => RenderProfile::Full,
+            None => RenderProfile::Basic,
+        };
+    }
+}
+
+#[cfg(unix)]
+mod probe {
+    use std::io::{self, Write};
+    use std::time::{Duration, Instant};
+
+    pub struct Reply {
+        pub synchronized_output: bool,
+        pub cell_size: Option<(u16, u16)>,
+    }
+
+    /// DECRQM for mode 2026, cell size in pixels, then primary device attributes
+    const QUERY: &[u8] = b"\x1b[?2026$p\x1b[16t\x1b[c";
+
+    /// Send the queries and read replies until the device attributes arrive
+    pub fn query(timeout: Duration) -> Option<Reply> {
+        let mut stdout = io::stdout();
+        stdout.write_all(QUERY).ok()?;
+        stdout.flush().ok()?;
+
+        let deadline = Instant::now() + timeout;
+        let mut replies = Vec::new();
+        while !has_device_attributes(&replies) {
+            let left = deadline.checked_duration_since(Instant::now())?;
+            let mut fds = libc::pollfd { fd: libc::STDIN_FILENO, events: libc::POLLIN, revents: 0 };
+            // SAFETY: one valid pollfd, for the duration of the call
+            if unsafe { libc::poll(&mut fds, 1, left.as_millis() as libc::c_int) } <= 0 {
+                return None;
+            }
+            // Read the descriptor directly: `Stdin` would buffer keys typed meanwhile,
+            // hiding them from the event reader
+            let mut buf = [0u8; 256];
+            // SAFETY: reads at most `buf.len()` bytes into `buf`
+            let read = unsafe { libc::read(libc::STDIN_FILENO, buf.as_mut_ptr().cast(), buf.len()) };
+            if read <= 0 {
+                return None;
+            }
+            replies.extend_from_slice(&buf[..read as usize]);
+        }
+
+        Some(Reply {
+            synchronized_output: mode_2026(&replies),
+            cell_size: window_pixels().or_else(|| cell_size_reply(&replies)),
+        })
+    }
+
+    /// `CSI ? ... c`
+    fn has_device_attributes(replies: &[u8]) -> bool {
+        sequences(replies).any(|(params, fin)| fin == b'c' && params.starts_with(b"?"))
+    }
+
+    /// `CSI ? 2026 ; Ps $ y`, where 1 (set) and 2 (reset) mean the mode is recognized
+    fn mode_2026(replies: &[u8]) -> bool {
+        sequences(replies).any(|(params, fin)| fin == b'y' && matches!(params, b"?2026;1$" | b"?2026;2$"))
+    }
+
+    /// `CSI 6 ; height ; width t`
+    fn cell_size_reply(replies: &[u8]) -> Option<(u16, u16)> {
+        sequences(replies).find_map(|(params, fin)| {
+            let params = std::str::from_utf8(params).ok()?;
+            let mut fields = params.split(';');
+            if fin != b't' || fields.next()? != "6" {
+                return None;
+            }
+            let height = fields.next()?.parse().ok()?;
+            let width = fields.next()?.parse().ok()?;
+            Some((width, height))
+        })
+    }
+
+    /// Cell size from the pixel dimensions the terminal reports with the window size
+    fn window_pixels() -> Option<(u16, u16)> {
+        let size = crossterm::terminal::window_size().ok()?;
+        if size.width == 0 || size.height == 0 || size.columns == 0 || size.rows == 0 {
+            return None;
+        }
+        Some((size.width / size.columns, size.height / size.rows))
+    }
+
+    /// Parameters and final byte of each CSI sequence in `bytes`
+    fn sequences(bytes: &[u8]) -> impl Iterator<Item = (&[u8], u8)> + '_ {
+        bytes.windows(2).enumerate().filter(|(_, pair)| pair == &b"\x1b[").filter_map(move |(start, _)| {
+            let body = &bytes[start + 2..];
+            let end = body.iter().position(|b| (0x40..=0x7e).contains(b))?;
+            Some((&body[..end], body[end]))
+        })
+    }
+}
+
+#[cfg(not(unix))]
+mod probe {
+    use std::time::Duration;
+
+    pub struct Reply {
+        pub synchronized_output: bool,
+        pub cell_size: Option<(u16, u16)>,
+    }
+
+    /// Console input on Windows doesn't carry the replies; rely on the environment
+    pub fn query(_timeout: Duration) -> Option<Reply> {
+        None
+    }
+}
@@ -0,0 +1,216 @@
+use std::error::Error;
+use std::io::{self, Write};
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
+    terminal::{self, disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+    QueueableCommand,
+};
+
+use crate::ai::conversation::{format_age, ConversationManager, ConversationSummary};
+
+/// What the keyboard is currently driving
+enum Mode {
+    Browse,
+    Rename { title: String },
+    ConfirmDelete,
+}
+
+/// Interactive list of saved conversations (`rustfig chat pick`)
+pub struct ConversationPicker<'a> {
+    manager: &'a mut ConversationManager,
+    conversations: Vec<ConversationSummary>,
+    selected: usize,
+    mode: Mode,
+    status: Option<String>,
+}
+
+impl<'a> ConversationPicker<'a> {
+    pub fn new(manager: &'a mut ConversationManager) -> Self {
+        let conversations = manager.list_conversations();
+        Self { manager, conversations, selected: 0, mode: Mode::Browse, status: None }
+    }
+
+    /// Run the TUI until the user opens a conversation or quits; returns the ID opened
+    pub fn run(&mut self) -> Result<Option<String>, Box<dyn Error>> {
+        enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        stdout.queue(EnterAlternateScreen)?.queue(Hide)?;
+        stdout.flush()?;
+
+        let result = self.event_loop();
+
+        // Always restore the terminal, even if the loop failed
+        let _ = stdout.queue(Show).and_then(|out| out.queue(LeaveAlternateScreen)).and_then(|out| out.flush());
+        let _ = disable_raw_mode();
+
+        result
+    }
+
+    fn event_loop(&mut self) -> Result<Option<String>, Box<dyn Error>> {
+        loop {
+            self.render()?;
+
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                match self.handle_key(key)? {
+                    Some(Exit::Open(id)) => return Ok(Some(id)),
+                    Some(Exit::Quit) => return Ok(None),
+                    None => {}
+                }
+            }
+        }
+    }
+
+    fn reload(&mut self) {
+        self.conversations = self.manager.list_conversations();
+        self.selected = self.selected.min(self.conversations.len().saturating_sub(1));
+    }
+
+    fn selected_conversation(&self) -> Option<&ConversationSummary> {
+        self.conversations.get(self.selected)
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Result<Option<Exit>, Box<dyn Error>> {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return Ok(Some(Exit::Quit));
+        }
+
+        match &mut self.mode {
+            Mode::Browse => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(Some(Exit::Quit)),
+                KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+                KeyCode::Down | KeyCode::Char('j') => {
+                    if self.selected + 1 < self.conversations.len() {
+                        self.selected += 1;
+                    }
+                }
+                KeyCode::Enter => {
+                    if let Some(conversation) = self.selected_conversation() {
+                        return Ok(Some(Exit::Open(conversation.id.clone())));
+                    }
+                }
+                KeyCode::Char('r') => {
+                    if let Some(conversation) = self.selected_conversation() {
+                        self.mode = Mode::Rename { title: conversation.title.clone() };
+                    }
+                }
+                KeyCode::Char('d') | KeyCode::Delete => {
+                    if self.selected_conversation().is_some() {
+                        self.mode = Mode::ConfirmDelete;
+                    }
+                }
+                _ => {}
+            },
+            Mode::Rename { title } => match key.code {
+                KeyCode::Esc => self.mode = Mode::Browse,
+                KeyCode::Backspace => {
+                    title.pop();
+                }
+                KeyCode::Char(c) => title.push(c),
+                KeyCode::Enter => {
+                    let title = title.clone();
+                    if let Some(id) = self.selected_conversation().map(|c| c.id.clone()) {
+                        match self.manager.rename_conversation(&id, &title) {
+                            Ok(()) => self.status = Some(format!("Renamed to {}", title.trim())),
+                            Err(e) => self.status = Some(e.to_string()),
+                        }
+                    }
+                    self.mode = Mode::Browse;
+                    self.reload();
+                }
+                _ => {}
+            },
+            Mode::ConfirmDelete => {
+                if key.code == KeyCode::Char('y') {
+                    if let Some(conversation) = self.selected_conversation().cloned() {
+                        self.manager.delete_conversation(&conversation.id)?;
+                        self.status = Some(format!("Deleted {}", conversation.title));
+                        self.reload();
+                    }
+                }
+                self.mode = Mode::Browse;
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn render(&self) -> io::Result<()> {
+        let (width, height) = terminal::size()?;
+        let width = width as usize;
+        let mut stdout = io::stdout();
+
+        stdout.queue(Clear(ClearType::All))?.queue(MoveTo(0, 0))?;
+        stdout
+            .queue(SetAttribute(Attribute::Bold))?
+            .queue(Print(format!("RustFig conversations ({})", self.conversations.len())))?
+            .queue(SetAttribute(Attribute::Reset))?;
+
+        if self.conversations.is_empty() {
+            stdout.queue(MoveTo(0, 2))?.queue(Print("No saved conversations; start one with 'rustfig chat'"))?;
+        }
+
+        // Leave room for the header, the rename field, and the help line
+        let list_height = (height as usize).saturating_sub(5);
+        let scroll = self.selected.saturating_sub(list_height.saturating_sub(1));
+
+        for (row, conversation) in self.conversations.iter().skip(scroll).take(list_height).enumerate() {
+            let is_selected = scroll + row == self.selected;
+            let line = format!(
+                "{} {:<50} {:>16}  {} messages",
+                if is_selected { ">" } else { " " },
+                conversation.title,
+                format_age(conversation.last_used),
+                conversation.message_count,
+            );
+
+            stdout.queue(MoveTo(0, 2 + row as u16))?;
+            if is_selected {
+                stdout.queue(SetAttribute(Attribute::Reverse))?;
+            }
+            stdout.queue(Print(truncate(&line, width)))?.queue(SetAttribute(Attribute::Reset))?;
+        }
+
+        if let Mode::Rename { title } = &self.mode {
+            stdout
+                .queue(MoveTo(0, height.saturating_sub(3)))?
+                .queue(SetForegroundColor(Color::Cyan))?
+                .queue(Print(truncate(&format!("Title: {}", title), width)))?
+                .queue(ResetColor)?;
+        } else if let Some(conversation) = self.selected_conversation() {
+            stdout
+                .queue(MoveTo(0, height.saturating_sub(3)))?
+                .queue(SetForegroundColor(Color::DarkGrey))?
+                .queue(Print(truncate(&conversation.id, width)))?
+                .queue(ResetColor)?;
+        }
+
+        let help = match self.mode {
+            Mode::Browse => "↑/↓ move  enter open  r rename  d delete  q quit",
+            Mode::Rename { .. } => "type a title  enter save  esc cancel",
+            Mode::ConfirmDelete => "delete this conversation? y/n",
+        };
+        let footer = match &self.status {
+            Some(status) => format!("{}  |  {}", help, status),
+            None => help.to_string(),
+        };
+        stdout.queue(MoveTo(0, height.saturating_sub(1)))?.queue(Print(truncate(&footer, width)))?;
+
+        stdout.flush()
+    }
+}
+
+/// How the picker was left
+enum Exit {
+    Open(String),
+    Quit,
+}
+
+/// Cut a line to the terminal width
+fn truncate(line: &str, width: usize) -> String {
+    line.chars().take(width).collect()
+}
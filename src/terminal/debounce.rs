@@ -0,0 +1,61 @@
+use std::time::{Duration, Instant};
+
+/// Holds predictions back while keys arrive faster than the threshold, firing once typing pauses
+///
+/// The pause that counts adapts to the user: one and a half times their recent interval
+/// between keys, capped at the threshold, so fast typists see ghost text sooner after
+/// they stop. A zero threshold predicts on every key.
+pub struct Debouncer {
+    threshold: Duration,
+    last_key: Option<Instant>,
+    /// Moving average of the intervals that counted as rapid typing
+    average_interval: Option<Duration>,
+    pending: bool,
+}
+
+impl Debouncer {
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            last_key: None,
+            average_interval: None,
+            pending: false,
+        }
+    }
+
+    /// Record a key that changed the line; true when predictions should run right away
+    pub fn keystroke(&mut self, now: Instant) -> bool {
+        let interval = self.last_key.map(|last| now.saturating_duration_since(last));
+        self.last_key = Some(now);
+
+        match interval {
+            Some(interval) if interval < self.threshold => {
+                self.average_interval = Some(match self.average_interval {
+                    Some(average) => (average * 3 + interval) / 4,
+                    None => interval,
+                });
+                self.pending = true;
+                false
+            }
+            _ => {
+                self.pending = false;
+                true
+            }
+        }
+    }
+
+    /// Whether a held-back prediction should run now that no key has come for a while
+    pub fn due(&mut self, now: Instant) -> bool {
+        let paused = self.last_key.map_or(true, |last| now.saturating_duration_since(last) >= self.pause());
+        if self.pending && paused {
+            self.pending = false;
+            return true;
+        }
+        false
+    }
+
+    fn pause(&self) -> Duration {
+        self.average_interval
+            .map_or(self.threshold, |average| (average * 3 / 2).min(self.threshold))
+    }
+}
@@ -0,0 +1,179 @@
+use crossterm::style::Color;
+
+use crate::suggestion::engine::{Suggestion, SuggestionKind};
+use super::render::{display_width, truncate_to_width, CellBuffer, Style};
+
+/// Narrowest dropdown that still has room for kind labels
+const MIN_WIDTH_FOR_LABELS: u16 = 30;
+
+/// Fewest columns worth giving a description; with less it's left out
+const MIN_DESCRIPTION_WIDTH: u16 = 8;
+
+/// Share of the dropdown the text column may take before descriptions stop lining up
+const MAX_TEXT_SHARE: f32 = 0.6;
+
+/// Lays suggestions out as rows of cells for the renderer
+pub struct Dropdown {
+    pub width: u16,
+    pub max_height: u16,
+    pub show_icons: bool,
+    pub show_descriptions: bool,
+}
+
+impl Dropdown {
+    pub fn new() -> Self {
+        Self {
+            width: 50,
+            max_height: 10,
+            show_icons: true,
+            show_descriptions: true,
+        }
+    }
+
+    /// One row per suggestion, the selected one highlighted; `max_width` is the room
+    /// the terminal has
+    pub fn layout(&self, suggestions: &[Suggestion], selected: usize, max_width: u16) -> CellBuffer {
+        let width = self.width.min(max_width);
+        let height = (suggestions.len() as u16).min(self.max_height);
+        let mut buffer = CellBuffer::new(width, height);
+
+        // Keep the selection in view
+        let first = (selected + 1).saturating_sub(height as usize);
+        let visible = &suggestions[first.min(suggestions.len())..];
+        let visible = &visible[..visible.len().min(height as usize)];
+
+        // Descriptions line up in a second column after the widest text on screen
+        let description_col = self.description_column(visible, width);
+
+        for (row, suggestion) in visible.iter().enumerate() {
+            let row = row as u16;
+            let is_selected = first + row as usize == selected;
+            let base = if is_selected {
+                Style { fg: Some(Color::White), bg: Some(Color::DarkBlue), dim: false }
+            } else {
+                Style { fg: None, bg: Some(Color::AnsiValue(236)), dim: false }
+            };
+
+            // The kind's color marks its icon and label, except on the selected row
+            let kind_style = if is_selected { base } else { Style { fg: Some(color(suggestion.kind)), ..base } };
+
+            buffer.fill_row(row, base);
+            let mut col = buffer.put_str(0, row, " ", base);
+            if self.show_icons {
+                col = buffer.put_str(col, row, icon(suggestion.kind), kind_style);
+                col = buffer.put_str(col, row, " ", base);
+            }
+            col = buffer.put_str(col, row, &suggestion.display, base);
+
+            // The kind's label is right-aligned, when the text leaves room for it
+            let mut end = width;
+            if width >= MIN_WIDTH_FOR_LABELS {
+                let label = label(suggestion.kind);
+                let label_col = width.saturating_sub(display_width(label) as u16 + 1);
+                if label_col > col + 1 {
+                    buffer.put_str(label_col, row, label, Style { dim: true, ..kind_style });
+                    end = label_col.saturating_sub(1);
+                }
+            }
+
+            // The description goes between, dimmed, in its column or just after a text too
+            // long for it
+            if let Some(description) = suggestion.description.as_deref().filter(|_| self.show_descriptions) {
+                let start = description_col.max(col + 2);
+                if start + MIN_DESCRIPTION_WIDTH <= end {
+                    let room = (end - start) as usize;
+                    let shown = if display_width(description) <= room {
+                        description.to_string()
+                    } else {
+                        format!("{}…", truncate_to_width(description, room - 1))
+                    };
+                    buffer.put_str(start, row, &shown, Style { dim: !is_selected, ..base });
+                }
+            }
+        }
+
+        buffer
+    }
+
+    /// Where descriptions start: two columns after the widest text among `visible`,
+    /// but no further than `MAX_TEXT_SHARE` of `width`
+    fn description_column(&self, visible: &[Suggestion], width: u16) -> u16 {
+        let prefix = if self.show_icons { 3 } else { 1 };
+        let widest = visible
+            .iter()
+            .filter(|suggestion| suggestion.description.is_some())
+            .map(|suggestion| display_width(&suggestion.display) as u16)
+            .max()
+            .unwrap_or(0);
+        (prefix + widest + 2).min((width as f32 * MAX_TEXT_SHARE) as u16)
+    }
+
+    /// Numbered lines of plain text, for accessible mode
+    pub fn plain_lines(&self, suggestions: &[Suggestion]) -> Vec<String> {
+        suggestions
+            .iter()
+            .take(self.max_height as usize)
+            .enumerate()
+            .map(|(index, suggestion)| match suggestion.description.as_deref().filter(|_| self.show_descriptions) {
+                Some(description) => format!("{}. {} - {} ({})", index + 1, suggestion.display, description, label(suggestion.kind)),
+                None => format!("{}. {} ({})", index + 1, suggestion.display, label(suggestion.kind)),
+            })
+            .collect()
+    }
+}
+
+fn icon(kind: SuggestionKind) -> &'static str {
+    match kind {
+        SuggestionKind::Command => "›",
+        SuggestionKind::Flag => "⚑",
+        SuggestionKind::Argument => "∙",
+        SuggestionKind::File => "□",
+        SuggestionKind::Directory => "▸",
+        SuggestionKind::History => "↺",
+        SuggestionKind::Snippet => "✂",
+        SuggestionKind::Bookmark => "★",
+        SuggestionKind::Ai => "✦",
+        SuggestionKind::GitRef => "⎇",
+        SuggestionKind::EnvVar => "$",
+        SuggestionKind::Process => "⚙",
+        SuggestionKind::Remote => "⇄",
+    }
+}
+
+fn color(kind: SuggestionKind) -> Color {
+    match kind {
+        SuggestionKind::Command => Color::Green,
+        SuggestionKind::Flag => Color::Yellow,
+        SuggestionKind::Argument => Color::Cyan,
+        SuggestionKind::File => Color::Grey,
+        SuggestionKind::Directory => Color::Blue,
+        SuggestionKind::History => Color::DarkCyan,
+        SuggestionKind::Snippet => Color::DarkYellow,
+        SuggestionKind::Bookmark => Color::Yellow,
+        // Stands apart from everything the user has run or that's known to exist
+        SuggestionKind::Ai => Color::Magenta,
+        SuggestionKind::GitRef => Color::AnsiValue(208),
+        SuggestionKind::EnvVar => Color::DarkGreen,
+        SuggestionKind::Process => Color::Red,
+        SuggestionKind::Remote => Color::DarkMagenta,
+    }
+}
+
+/// Short name of a kind, shown with each suggestion
+fn label(kind: SuggestionKind) -> &'static str {
+    match kind {
+        SuggestionKind::Command => "command",
+        SuggestionKind::Flag => "flag",
+        SuggestionKind::Argument => "arg",
+        SuggestionKind::File => "file",
+        SuggestionKind::Directory => "dir",
+        SuggestionKind::History => "history",
+        SuggestionKind::Snippet => "snippet",
+        SuggestionKind::Bookmark => "bookmark",
+        SuggestionKind::Ai => "AI",
+        SuggestionKind::GitRef => "git",
+        SuggestionKind::EnvVar => "env",
+        SuggestionKind::Process => "process",
+        SuggestionKind::Remote => "remote",
+    }
+}
@@ -6,13 +6,20 @@ use crossterm::{
     terminal::size,
 };
 use crate::prediction::models::Prediction;
+use super::render::{display_width, truncate_to_width};
 
 /// Renders ghost text in the terminal
 pub struct GhostTextRenderer {
     ghost_color: Color,
     enabled: bool,
     current_ghost: Option<String>,
+    /// A corrected line offered in place of the typed one, shown as `→ line`
+    current_correction: Option<String>,
+    /// Columns actually drawn, which is what needs clearing
+    rendered_width: usize,
     cursor_pos: (u16, u16),
+    /// Leave flushing to the caller, which writes a whole frame at once
+    batched: bool,
 }
 
 impl GhostTextRenderer {
@@ -21,7 +28,10 @@ impl GhostTextRenderer {
             ghost_color: Color::DarkGrey,
             enabled: true,
             current_ghost: None,
+            current_correction: None,
+            rendered_width: 0,
             cursor_pos: (0, 0),
+            batched: false,
         }
     }
     
@@ -30,12 +40,33 @@ impl GhostTextRenderer {
         self.ghost_color = color;
     }
     
+    /// Set the color from `#rrggbb`, as `ui.ghost_text_color` gives it; false if it doesn't parse
+    pub fn set_hex_color(&mut self, hex: &str) -> bool {
+        let digits = hex.trim_start_matches('#');
+        let channel = |range: std::ops::Range<usize>| digits.get(range).and_then(|digits| u8::from_str_radix(digits, 16).ok());
+        match (digits.len(), channel(0..2), channel(2..4), channel(4..6)) {
+            (6, Some(r), Some(g), Some(b)) => {
+                self.ghost_color = Color::Rgb { r, g, b };
+                true
+            }
+            _ => false,
+        }
+    }
+    
     /// Enable or disable ghost text
     pub fn set_enabled(&mut self, enabled: bool) {
         self.enabled = enabled;
     }
     
+    /// Queue output without flushing it, for low-bandwidth sessions that write once per frame
+    pub fn set_batched(&mut self, batched: bool) {
+        self.batched = batched;
+    }
+    
     /// Update current cursor position
+    ///
+    /// On a continuation prompt this is the position on the last line, which is where
+    /// the ghost text goes.
     pub fn update_cursor_pos(&mut self, x: u16, y: u16) {
         self.cursor_pos = (x, y);
     }
@@ -48,6 +79,7 @@ impl GhostTextRenderer {
         
         // Clear any existing ghost text
         self.clear_ghost_text()?;
+        self.current_correction = None;
         
         let ghost_text = match prediction {
             Some(pred) => pred.get_ghost_text(current_input),
@@ -58,21 +90,33 @@ impl GhostTextRenderer {
             self.current_ghost = None;
             return Ok(());
         }
-        
+        self.draw_ghost(ghost_text)
+    }
+    
+    /// Offer a whole command as ghost text on an empty line; accepting it types it in
+    pub fn render_line(&mut self, command: &str) -> io::Result<()> {
+        if !self.enabled || command.is_empty() {
+            return Ok(());
+        }
+        self.clear_ghost_text()?;
+        self.current_correction = None;
+        self.draw_ghost(command.to_string())
+    }
+    
+    fn draw_ghost(&mut self, ghost_text: String) -> io::Result<()> {
         // Store current ghost text
         self.current_ghost = Some(ghost_text.clone());
         
         // Get terminal size to avoid drawing off-screen
         let (term_width, _) = size()?;
         
-        // Calculate visible ghost text
-        let visible_ghost = if self.cursor_pos.0 + ghost_text.len() as u16 > term_width {
-            let visible_len = term_width.saturating_sub(self.cursor_pos.0) as usize;
-            &ghost_text[..visible_len.min(ghost_text.len())]
-        } else {
-            &ghost_text
-        };
+        // Multi-line predictions only show the rest of the current line; accepting inserts it all
+        let first_line = ghost_text.split('\n').next().unwrap_or("");
+        
+        // Cut at the right edge by columns, so wide characters aren't split or overflowed
+        let visible_ghost = truncate_to_width(first_line, term_width.saturating_sub(self.cursor_pos.0) as usize);
         
+        self.rendered_width = display_width(visible_ghost);
         if visible_ghost.is_empty() {
             return Ok(());
         }
@@ -85,33 +129,78 @@ impl GhostTextRenderer {
               .queue(ResetColor)?
               .queue(RestorePosition)?;
         
-        stdout.flush()?;
+        self.flush(&mut stdout)
+    }
+    
+    /// Offer `corrected` as a replacement for the whole line, after the cursor
+    pub fn render_correction(&mut self, corrected: &str) -> io::Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        self.clear_ghost_text()?;
+        self.current_ghost = None;
+        
+        let (term_width, _) = size()?;
+        let hint = format!("  → {}", corrected.split('\n').next().unwrap_or(""));
+        let visible = truncate_to_width(&hint, term_width.saturating_sub(self.cursor_pos.0) as usize);
+        self.rendered_width = display_width(visible);
+        self.current_correction = Some(corrected.to_string());
+        
+        let mut stdout = io::stdout();
+        stdout.queue(SavePosition)?
+              .queue(SetForegroundColor(self.ghost_color))?
+              .queue(Print(visible))?
+              .queue(ResetColor)?
+              .queue(RestorePosition)?;
         
-        Ok(())
+        self.flush(&mut stdout)
+    }
+    
+    fn flush(&self, stdout: &mut io::Stdout) -> io::Result<()> {
+        if self.batched {
+            return Ok(());
+        }
+        stdout.flush()
     }
     
     /// Clear existing ghost text
     pub fn clear_ghost_text(&self) -> io::Result<()> {
-        if !self.enabled || self.current_ghost.is_none() {
+        if !self.enabled || (self.current_ghost.is_none() && self.current_correction.is_none()) {
             return Ok(());
         }
         
-        if let Some(ghost) = &self.current_ghost {
-            let mut stdout = io::stdout();
-            
-            // Save current position
-            stdout.queue(SavePosition)?;
-            
-            // Clear ghost text by overwriting with spaces
-            let spaces = " ".repeat(ghost.len());
-            stdout.queue(Print(&spaces))?;
-            
-            // Restore position
-            stdout.queue(RestorePosition)?;
-            stdout.flush()?;
-        }
+        let mut stdout = io::stdout();
+        
+        // Save current position
+        stdout.queue(SavePosition)?;
+        
+        // Clear ghost text by overwriting with spaces
+        let spaces = " ".repeat(self.rendered_width);
+        stdout.queue(Print(&spaces))?;
         
-        Ok(())
+        // Restore position
+        stdout.queue(RestorePosition)?;
+        self.flush(&mut stdout)
+    }
+    
+    /// Whether ghost text is on screen
+    pub fn is_showing(&self) -> bool {
+        self.enabled && (self.current_ghost.is_some() || self.current_correction.is_some())
+    }
+    
+    /// Forget the correction once the line it was for changes; the hint is erased with
+    /// `clear_ghost_text`
+    pub fn dismiss_correction(&mut self) {
+        self.current_correction = None;
+    }
+    
+    /// Take the corrected line on offer, erasing the hint; the caller replaces the line
+    pub fn accept_correction(&mut self) -> io::Result<Option<String>> {
+        if self.current_correction.is_none() {
+            return Ok(None);
+        }
+        self.clear_ghost_text()?;
+        Ok(self.current_correction.take())
     }
     
     /// Accept the current ghost text
@@ -122,7 +211,7 @@ impl GhostTextRenderer {
             let mut stdout = io::stdout();
             let _ = stdout
                 .queue(Print(ghost))
-                .and_then(|_| stdout.flush());
+                .and_then(|stdout| self.flush(stdout));
         }
         ghost
     }
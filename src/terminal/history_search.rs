@@ -0,0 +1,272 @@
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
+    terminal::{self, disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+    QueueableCommand,
+};
+
+use crate::shell::history::HistoryEntry;
+use crate::suggestion::fuzzy::fast_fuzzy_score;
+use crate::utils::system::format_age;
+
+/// Which exit statuses to show
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExitFilter {
+    All,
+    Succeeded,
+    Failed,
+}
+
+impl ExitFilter {
+    fn next(self) -> Self {
+        match self {
+            ExitFilter::All => ExitFilter::Succeeded,
+            ExitFilter::Succeeded => ExitFilter::Failed,
+            ExitFilter::Failed => ExitFilter::All,
+        }
+    }
+
+    fn accepts(self, entry: &HistoryEntry) -> bool {
+        match self {
+            ExitFilter::All => true,
+            ExitFilter::Succeeded => entry.exit_code == Some(0),
+            ExitFilter::Failed => entry.exit_code.map_or(false, |code| code != 0),
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ExitFilter::All => "any exit",
+            ExitFilter::Succeeded => "succeeded",
+            ExitFilter::Failed => "failed",
+        }
+    }
+}
+
+/// Full-screen fuzzy history search (the Ctrl+R replacement)
+///
+/// Draws on stderr so the shell widget can capture the chosen command(s) from stdout.
+pub struct HistorySearch {
+    entries: Vec<HistoryEntry>,
+    cwd: Option<PathBuf>,
+    query: String,
+    cwd_only: bool,
+    exit_filter: ExitFilter,
+    /// Indices into `entries` matching the query and filters, best first
+    visible: Vec<usize>,
+    selected: usize,
+    /// Indices into `entries` marked for multi-select, in the order they were marked
+    marked: Vec<usize>,
+}
+
+impl HistorySearch {
+    /// `entries` should be newest first, as returned by `HistoryDb::load_unified`
+    pub fn new(entries: Vec<HistoryEntry>, query: &str, cwd: Option<PathBuf>) -> Self {
+        let mut search = Self {
+            entries,
+            cwd,
+            query: query.to_string(),
+            cwd_only: false,
+            exit_filter: ExitFilter::All,
+            visible: Vec::new(),
+            selected: 0,
+            marked: Vec::new(),
+        };
+        search.filter();
+        search
+    }
+
+    /// Run until the user picks or cancels; returns the chosen commands joined by newlines
+    pub fn run(&mut self) -> Result<Option<String>, Box<dyn Error>> {
+        enable_raw_mode()?;
+        let mut stderr = io::stderr();
+        stderr.queue(EnterAlternateScreen)?.queue(Hide)?;
+        stderr.flush()?;
+
+        let result = self.event_loop();
+
+        // Always restore the terminal, even if the loop failed
+        let _ = stderr.queue(Show).and_then(|out| out.queue(LeaveAlternateScreen)).and_then(|out| out.flush());
+        let _ = disable_raw_mode();
+
+        result
+    }
+
+    fn event_loop(&mut self) -> Result<Option<String>, Box<dyn Error>> {
+        loop {
+            self.render()?;
+
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                if let Some(outcome) = self.handle_key(key) {
+                    return Ok(outcome);
+                }
+            }
+        }
+    }
+
+    /// Recompute the visible list from the query and filters
+    fn filter(&mut self) {
+        let mut scored: Vec<(usize, f32)> = self.entries.iter()
+            .enumerate()
+            .filter(|(_, entry)| self.exit_filter.accepts(entry))
+            .filter(|(_, entry)| !self.cwd_only || (entry.cwd.is_some() && entry.cwd == self.cwd))
+            .filter_map(|(index, entry)| {
+                if self.query.is_empty() {
+                    return Some((index, 0.0));
+                }
+                fast_fuzzy_score(&self.query, &entry.command).map(|score| (index, score))
+            })
+            .collect();
+
+        // Entries are newest first, so a stable sort keeps recency as the tiebreaker
+        if !self.query.is_empty() {
+            scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        }
+
+        self.visible = scored.into_iter().map(|(index, _)| index).collect();
+        self.selected = self.selected.min(self.visible.len().saturating_sub(1));
+    }
+
+    /// Handle a key press; returns `Some` when the search is over
+    fn handle_key(&mut self, key: KeyEvent) -> Option<Option<String>> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+
+        match key.code {
+            KeyCode::Esc => return Some(None),
+            KeyCode::Char('c') | KeyCode::Char('g') if ctrl => return Some(None),
+            KeyCode::Enter => return Some(self.chosen()),
+            KeyCode::Up => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Char('p') if ctrl => self.selected = self.selected.saturating_sub(1),
+            KeyCode::Down => self.move_down(),
+            KeyCode::Char('n') if ctrl => self.move_down(),
+            KeyCode::Char('d') if ctrl => {
+                self.cwd_only = !self.cwd_only;
+                self.filter();
+            }
+            KeyCode::Char('e') if ctrl => {
+                self.exit_filter = self.exit_filter.next();
+                self.filter();
+            }
+            KeyCode::Tab => {
+                // Toggle the mark and move on, like fzf's multi-select
+                if let Some(&index) = self.visible.get(self.selected) {
+                    match self.marked.iter().position(|&marked| marked == index) {
+                        Some(position) => {
+                            self.marked.remove(position);
+                        }
+                        None => self.marked.push(index),
+                    }
+                    self.move_down();
+                }
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.filter();
+            }
+            KeyCode::Char(c) if !ctrl => {
+                self.query.push(c);
+                self.selected = 0;
+                self.filter();
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    fn move_down(&mut self) {
+        if self.selected + 1 < self.visible.len() {
+            self.selected += 1;
+        }
+    }
+
+    /// Marked commands in marking order, or the highlighted one if nothing is marked
+    fn chosen(&self) -> Option<String> {
+        if !self.marked.is_empty() {
+            let commands: Vec<&str> = self.marked.iter().map(|&index| self.entries[index].command.as_str()).collect();
+            return Some(commands.join("\n"));
+        }
+
+        self.visible.get(self.selected).map(|&index| self.entries[index].command.clone())
+    }
+
+    fn render(&self) -> io::Result<()> {
+        let (width, height) = terminal::size()?;
+        let width = width as usize;
+        let mut stderr = io::stderr();
+
+        stderr.queue(Clear(ClearType::All))?.queue(MoveTo(0, 0))?;
+        stderr
+            .queue(SetAttribute(Attribute::Bold))?
+            .queue(Print("history> "))?
+            .queue(SetAttribute(Attribute::Reset))?
+            .queue(Print(truncate(&self.query, width.saturating_sub(9))))?;
+
+        let mut status = format!("{}/{}", self.visible.len(), self.entries.len());
+        if self.cwd_only {
+            status.push_str("  this dir");
+        }
+        if self.exit_filter != ExitFilter::All {
+            status.push_str(&format!("  {}", self.exit_filter.label()));
+        }
+        if !self.marked.is_empty() {
+            status.push_str(&format!("  {} marked", self.marked.len()));
+        }
+        stderr
+            .queue(MoveTo(0, 1))?
+            .queue(SetForegroundColor(Color::DarkGrey))?
+            .queue(Print(truncate(&status, width)))?
+            .queue(ResetColor)?;
+
+        // Leave room for the prompt, the status line, and the help line
+        let list_height = (height as usize).saturating_sub(3);
+        let scroll = self.selected.saturating_sub(list_height.saturating_sub(1));
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+
+        for (row, &index) in self.visible.iter().skip(scroll).take(list_height).enumerate() {
+            let entry = &self.entries[index];
+            let is_selected = scroll + row == self.selected;
+            let marker = if self.marked.contains(&index) { "●" } else { " " };
+            let age = if entry.timestamp > 0 { format_age(now.saturating_sub(entry.timestamp)) } else { String::new() };
+
+            stderr.queue(MoveTo(0, 2 + row as u16))?;
+            if is_selected {
+                stderr.queue(SetAttribute(Attribute::Reverse))?;
+            }
+
+            // Failed commands get a red status column
+            match entry.exit_code {
+                Some(0) | None => stderr.queue(Print(format!("{} ", marker)))?,
+                Some(_) => stderr
+                    .queue(SetForegroundColor(Color::Red))?
+                    .queue(Print(format!("{}✗", marker)))?
+                    .queue(SetForegroundColor(Color::Reset))?,
+            };
+
+            let command = entry.command.lines().next().unwrap_or("");
+            let line = format!(" {:>4}  {}", age, command);
+            stderr.queue(Print(truncate(&line, width.saturating_sub(2))))?.queue(SetAttribute(Attribute::Reset))?;
+        }
+
+        stderr
+            .queue(MoveTo(0, height.saturating_sub(1)))?
+            .queue(SetForegroundColor(Color::DarkGrey))?
+            .queue(Print(truncate("enter accept  tab mark  ^D this dir  ^E exit status  esc cancel", width)))?
+            .queue(ResetColor)?;
+
+        stderr.flush()
+    }
+}
+
+/// Cut a line to the terminal width
+fn truncate(line: &str, width: usize) -> String {
+    line.chars().take(width).collect()
+}
@@ -0,0 +1,21 @@
+use std::error::Error;
+use std::time::Duration;
+use crossterm::event::{self, Event};
+
+/// Reads terminal events without blocking the main loop for long
+pub struct InputHandler;
+
+impl InputHandler {
+    pub fn new() -> Self {
+        Self
+    }
+    
+    /// The next event, or `None` if none arrives within `timeout_ms`
+    pub fn next_event(&mut self, timeout_ms: u64) -> Result<Option<Event>, Box<dyn Error>> {
+        if event::poll(Duration::from_millis(timeout_ms))? {
+            Ok(Some(event::read()?))
+        } else {
+            Ok(None)
+        }
+    }
+}
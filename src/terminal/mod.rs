@@ -2,33 +2,84 @@ pub mod input;
 pub mod render;
 pub mod dropdown;
 pub mod ghosting;
+pub mod snippet_manager;
+pub mod conversation_picker;
+pub mod history_search;
+pub mod debounce;
+pub mod auto_dropdown;
+pub mod render_mode;
+pub mod tmux;
+pub mod semantic_prompt;
+pub mod capabilities;
 
 use std::error::Error;
-use std::io;
-use std::time::Instant;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use crossterm::{
     event::{self, Event, KeyCode, KeyEvent, KeyModifiers, KeyEventKind},
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{disable_raw_mode, enable_raw_mode, size, EnterAlternateScreen, LeaveAlternateScreen},
     cursor::{position, MoveTo},
     execute,
 };
 use crate::{
+    ai::assist::{command_from_description, comment_description, explain_error},
+    ai::{AiProvider, AiProviderFactory},
     config::Config,
-    shell::ShellIntegration,
-    suggestion::engine::SuggestionEngine,
-    prediction::PredictionEngine,
+    shell::{impact, CommandParser, LineEdit, PackageFinder, ShellIntegration},
+    suggestion::engine::{Suggestion, SuggestionEngine, SuggestionKind},
+    suggestion::snippets::SnippetSession,
+    suggestion::bookmarks::{Bookmark, BookmarkStore},
+    prediction::{Prediction, PredictionEngine},
+    utils::offline,
     utils::perf_metrics::PerformanceMetrics,
+    utils::usage_stats::StatsRecorder,
 };
 
 pub use self::input::InputHandler;
 pub use self::render::Renderer;
 pub use self::dropdown::Dropdown;
 pub use self::ghosting::GhostTextRenderer;
+pub use self::snippet_manager::SnippetManager;
+pub use self::conversation_picker::ConversationPicker;
+pub use self::history_search::HistorySearch;
+pub use self::debounce::Debouncer;
+pub use self::auto_dropdown::AutoDropdown;
+pub use self::render_mode::{FrameLimiter, RenderMode};
+pub use self::tmux::TmuxMenu;
+pub use self::semantic_prompt::{MarkParser, PromptMark, SemanticPrompt};
+pub use self::capabilities::{Capabilities, TerminalKind};
+
+/// Exit statuses that mean the user stopped the command (Ctrl+C), not that it failed
+pub(crate) const INTERRUPTED: &[i32] = &[130];
+
+/// Exit status shells give a command they couldn't find
+const COMMAND_NOT_FOUND: i32 = 127;
+
+/// Most lines of an error explanation drawn below the prompt
+const MAX_EXPLANATION_LINES: usize = 12;
+
+/// Most lines of a destructive command's preview
+const MAX_IMPACT_LINES: usize = 14;
+
+/// The last command, which failed, and what it printed
+struct FailedCommand {
+    command: String,
+    status: i32,
+    output: Vec<u8>,
+}
 
 pub struct Terminal {
     input_handler: InputHandler,
     renderer: Renderer,
     ghost_renderer: GhostTextRenderer,
+    /// The shell shows predictions as ghost text itself (zsh's POSTDISPLAY); corrections
+    /// and install hints are still drawn here
+    shell_draws_ghost: bool,
+    /// Over SSH, the service on the machine the user connected from
+    #[cfg(unix)]
+    remote: Option<crate::service::remote::RemotePredictor>,
     performance_metrics: PerformanceMetrics,
 }
 
@@ -41,62 +92,338 @@ impl Terminal {
             input_handler: InputHandler::new(),
             renderer: Renderer::new()?,
             ghost_renderer: GhostTextRenderer::new(),
+            shell_draws_ghost: false,
+            #[cfg(unix)]
+            remote: None,
             performance_metrics: PerformanceMetrics::new("terminal"),
         })
     }
     
     pub async fn run(
         &mut self,
-        mut suggestion_engine: SuggestionEngine,
+        suggestion_engine: SuggestionEngine,
         shell_integration: Box<dyn ShellIntegration>,
         config: &Config,
     ) -> Result<(), Box<dyn Error>> {
         let mut current_input = String::new();
+        let mut current_cursor = 0;
         let mut dropdown_visible = false;
+        let mut current_suggestions: Vec<Suggestion> = Vec::new();
+        let mut snippet_session: Option<SnippetSession> = None;
+        
+        // Create a prediction engine; its learning data and aliases load in the background
+        // so the prompt is usable right away
+        let prediction_engine = Arc::new(PredictionEngine::new(config));
+        let warm_engine = Arc::clone(&prediction_engine);
+        tokio::task::spawn_blocking(move || warm_engine.warm_up());
         
-        // Create a prediction engine
-        let prediction_engine = PredictionEngine::new(config);
+        // On a remote host, predict with the forwarded service of the user's own machine
+        #[cfg(unix)]
+        {
+            self.remote = crate::service::remote::RemotePredictor::detect(config);
+        }
+        
+        // Ask the terminal what it supports before anything else reads its replies
+        let capabilities = Capabilities::detect(config);
         
-        // Initialize ghost mode
-        let ghost_enabled = config.general.enable_ghost_text.unwrap_or(true);
+        // Over SSH: a shorter dropdown without icons, and fewer, batched redraws
+        let render_mode = RenderMode::detect(config, &capabilities);
+        
+        // Initialize ghost mode; the configured color needs 24-bit color, and accessible
+        // mode draws nothing over the line
+        let ghost_enabled = config.general.enable_ghost_text.unwrap_or(true) && !render_mode.accessible;
         self.ghost_renderer.set_enabled(ghost_enabled);
+        self.shell_draws_ghost = shell_integration.draws_ghost_text();
+        if capabilities.truecolor {
+            if let Some(color) = &config.ui.ghost_text_color {
+                self.ghost_renderer.set_hex_color(color);
+            }
+        }
+        
+        self.renderer.set_mode(&render_mode);
+        self.renderer.set_capabilities(&capabilities);
+        self.ghost_renderer.set_batched(render_mode.batch_output);
+        let dropdown_limit = render_mode.dropdown_max_height as usize;
+        let mut frames = FrameLimiter::new(render_mode.min_frame_interval);
+        
+        // Inside tmux the list can be a tmux menu, which tmux draws and takes input for
+        let mut tmux_menu = TmuxMenu::detect(config);
+        
+        // OSC 133 marks from the shell say where the command line starts, when it relays them
+        let mut marks = MarkParser::new();
+        let mut prompt = SemanticPrompt::new();
+        
+        // Hold predictions back during bursts of typing; they run once the user pauses
+        let mut debouncer = Debouncer::new(Duration::from_millis(
+            config.prediction.debounce_threshold_ms.unwrap_or(100),
+        ));
+        
+        // With `ui.auto_show_dropdown`, the dropdown opens by itself once typing pauses
+        let mut auto_dropdown = AutoDropdown::new(config);
+        
+        // Local counters for `rustfig stats`
+        let mut stats = StatsRecorder::new(&config.general.user_data_dir);
+        
+        // Connected the first time a `# description` line is turned into a command
+        let mut ai_provider: Option<Box<dyn AiProvider>> = None;
+        
+        // A failed command offers an explanation of its error at the next prompt
+        let explain_errors = config.ai.explain_errors.unwrap_or(true);
+        let mut submitted = String::new();
+        let mut failed: Option<FailedCommand> = None;
+        let mut offer_explanation = false;
+        let mut note_visible = false;
+        
+        // A command that wasn't found offers to install the package providing it, once
+        // the shell's not-found hook has looked that up
+        let packages = PackageFinder::from_config(config);
+        let mut install_hint: Option<String> = None;
+        let mut offer_install = false;
+        
+        // With `ui.preview_destructive`, a suggestion that makes the line delete files is
+        // previewed and held here until a second Tab
+        let preview_destructive = config.ui.preview_destructive.unwrap_or(false);
+        let mut awaiting_confirmation: Option<Suggestion> = None;
+        
+        // Redraws owed for edits to the line, made once the frame limiter allows
+        let mut dropdown_stale = false;
+        let mut predictions_stale = false;
         
         loop {
             // Process input
-            if let Some(event) = self.input_handler.next_event(config.general.input_timeout_ms)? {
+            let event = self.input_handler.next_event(config.general.input_timeout_ms)?;
+            if event.is_none() && debouncer.due(Instant::now()) {
+                predictions_stale = true;
+            }
+            
+            let output = shell_integration.take_terminal_output()?;
+            for mark in marks.feed(&output) {
+                match mark {
+                    PromptMark::CommandStart => {
+                        let typed = current_input.get(..current_cursor).unwrap_or(&current_input);
+                        prompt.command_started(position()?, typed, size()?.0);
+                        offer_explanation = failed.is_some();
+                        offer_install = install_hint.is_some();
+                    }
+                    PromptMark::CommandExecuted => {
+                        // Never draw over the output of the command that was submitted
+                        prompt.apply(mark);
+                        submitted = current_input.clone();
+                        prediction_engine.note_command(&submitted);
+                        failed = None;
+                        install_hint = None;
+                        note_visible = false;
+                        auto_dropdown.cancel();
+                        self.ghost_renderer.clear_ghost_text()?;
+                        if dropdown_visible {
+                            dropdown_visible = false;
+                            self.renderer.clear_dropdown()?;
+                        }
+                    }
+                    PromptMark::CommandFinished(status) => {
+                        prompt.apply(mark);
+                        let output = marks.take_output().unwrap_or_default();
+                        prediction_engine.note_result(&submitted, status, &output);
+                        if status == Some(COMMAND_NOT_FOUND) {
+                            install_hint = missing_command(&submitted)
+                                .and_then(|command| packages.cached(&command))
+                                .and_then(|matches| matches.first().map(|m| m.install_command()));
+                        }
+                        failed = status
+                            .filter(|&status| status != 0 && !INTERRUPTED.contains(&status))
+                            .filter(|_| explain_errors && !submitted.trim().is_empty())
+                            .map(|status| FailedCommand { command: std::mem::take(&mut submitted), status, output });
+                    }
+                    mark => prompt.apply(mark),
+                }
+                self.renderer.set_anchor_row(prompt.below_command(&current_input, size()?.0));
+            }
+            
+            // Once the prompt is back, say how to have the error explained
+            if offer_explanation {
+                offer_explanation = false;
+                if let Some(failed) = &failed {
+                    let hint = format!("Exited with {} · Alt+E explains the error", failed.status);
+                    self.renderer.render_note(&hint, 1)?;
+                    note_visible = true;
+                }
+            }
+            
+            // ... and the install command for a missing one, while the line is still empty
+            if offer_install {
+                offer_install = false;
+                let line_empty = shell_integration.get_current_command_line()?.is_empty();
+                if let Some(install) = install_hint.take().filter(|_| line_empty) {
+                    let (x, y) = position()?;
+                    self.ghost_renderer.update_cursor_pos(x, y);
+                    self.ghost_renderer.render_line(&install)?;
+                }
+            }
+            
+            if let Some(event) = event {
+                let confirmed = match &event {
+                    Event::Key(KeyEvent { code: KeyCode::Tab, kind: KeyEventKind::Press, .. }) => awaiting_confirmation.take(),
+                    Event::Key(KeyEvent { kind: KeyEventKind::Press, .. }) if awaiting_confirmation.take().is_some() => {
+                        // Any other key drops the previewed suggestion
+                        self.renderer.clear_dropdown()?;
+                        note_visible = false;
+                        None
+                    }
+                    _ => None,
+                };
+                
                 match event {
                     Event::Key(KeyEvent { code: KeyCode::Char('c'), modifiers: KeyModifiers::CONTROL, kind: KeyEventKind::Press, .. }) => {
                         break;
                     },
                     Event::Key(KeyEvent { code: KeyCode::Tab, kind: KeyEventKind::Press, .. }) => {
-                        if dropdown_visible {
-                            // Select current dropdown item
-                            // Implementation depends on your dropdown selection system
+                        if let Some(suggestion) = confirmed {
+                            // The preview was seen; accept what it was for
+                            self.renderer.clear_dropdown()?;
+                            note_visible = false;
+                            current_input = accept_suggestion(shell_integration.as_ref(), &suggestion, &mut snippet_session, &mut stats)?;
+                        } else if let Some(session) = snippet_session.as_mut() {
+                            // Jump to the next placeholder of the snippet being filled in
+                            if let Some(stop) = session.next_stop() {
+                                shell_integration.select_range(stop.start, stop.end)?;
+                            }
+                            if session.is_finished() {
+                                snippet_session = None;
+                            }
+                        } else if dropdown_visible {
+                            // Accept the selected dropdown item
+                            auto_dropdown.cancel();
+                            dropdown_visible = false;
+                            self.renderer.clear_dropdown()?;
+                            if let Some(suggestion) = current_suggestions.first() {
+                                if preview_destructive && self.preview_impact(shell_integration.as_ref(), suggestion)? {
+                                    awaiting_confirmation = Some(suggestion.clone());
+                                    note_visible = true;
+                                } else {
+                                    current_input = accept_suggestion(shell_integration.as_ref(), suggestion, &mut snippet_session, &mut stats)?;
+                                }
+                            }
+                        } else if let Some(corrected) = self.ghost_renderer.accept_correction()? {
+                            // Take the corrected command name
+                            shell_integration.replace_command_line(&corrected)?;
+                            current_input = shell_integration.get_current_command_line()?;
+                            current_cursor = shell_integration.get_cursor_position()?;
                         } else if let Some(ghost) = self.ghost_renderer.accept_ghost() {
                             // Accept ghost text
-                            shell_integration.apply_completion(&ghost)?;
+                            stats.ghost_accepted(ghost.chars().count());
+                            shell_integration.apply_edit(LineEdit::Insert(&ghost))?;
                             current_input = shell_integration.get_current_command_line()?;
                         } else {
                             // No ghost text, show dropdown
+                            auto_dropdown.cancel();
                             let cmd_line = shell_integration.get_current_command_line()?;
+                            let cursor_pos = shell_integration.get_cursor_position()?;
                             
-                            // Generate suggestions
-                            let suggestions = suggestion_engine.get_suggestions(&cmd_line, 10).await;
+                            // Generate suggestions for the word under the cursor
+                            let suggestions = suggestion_engine.get_suggestions(&cmd_line, cursor_pos, dropdown_limit).await;
                             
                             if !suggestions.is_empty() {
-                                dropdown_visible = true;
-                                self.renderer.render_dropdown(&suggestions, 0)?;
+                                match tmux_menu.as_mut() {
+                                    Some(menu) => menu.show(&suggestions)?,
+                                    None => {
+                                        dropdown_visible = true;
+                                        self.renderer.render_dropdown(&suggestions, 0)?;
+                                    }
+                                }
+                            }
+                            current_suggestions = suggestions;
+                        }
+                    },
+                    Event::Key(KeyEvent { code: KeyCode::Char(digit @ '1'..='9'), modifiers: KeyModifiers::NONE, kind: KeyEventKind::Press, .. })
+                        if dropdown_visible && render_mode.accessible =>
+                    {
+                        // Pick from the numbered list by its number
+                        let index = digit as usize - '1' as usize;
+                        if let Some(suggestion) = current_suggestions.get(index).cloned() {
+                            dropdown_visible = false;
+                            if preview_destructive && self.preview_impact(shell_integration.as_ref(), &suggestion)? {
+                                awaiting_confirmation = Some(suggestion);
+                            } else {
+                                current_input = accept_suggestion(shell_integration.as_ref(), &suggestion, &mut snippet_session, &mut stats)?;
+                                current_cursor = shell_integration.get_cursor_position()?;
+                            }
+                        }
+                    },
+                    Event::Key(KeyEvent { code: KeyCode::Enter, modifiers: KeyModifiers::ALT, kind: KeyEventKind::Press, .. })
+                        if comment_description(&current_input).is_some() =>
+                    {
+                        // `# what to do` is replaced by a command from the AI provider; the
+                        // line stays as it was if none comes back
+                        self.ghost_renderer.clear_ghost_text()?;
+                        if dropdown_visible {
+                            dropdown_visible = false;
+                            self.renderer.clear_dropdown()?;
+                        }
+                        // Offline, only a provider on this machine may be used
+                        if ai_provider.is_none() || offline::is_offline() {
+                            ai_provider = AiProviderFactory::create_provider(config).await;
+                        }
+                        if let (Some(provider), Some(description)) = (ai_provider.as_deref(), comment_description(&current_input)) {
+                            let directory = shell_integration.get_current_directory()?;
+                            let shell = shell_integration.get_shell_name();
+                            if let Ok(command) = command_from_description(provider, config, description, shell, &directory).await {
+                                shell_integration.replace_command_line(&command)?;
+                                current_input = shell_integration.get_current_command_line()?;
+                                current_cursor = shell_integration.get_cursor_position()?;
                             }
                         }
                     },
+                    Event::Key(KeyEvent { code: KeyCode::Char('e'), modifiers: KeyModifiers::ALT, kind: KeyEventKind::Press, .. })
+                        if failed.is_some() =>
+                    {
+                        // Explain the last command's error below the prompt
+                        if let Some(failed) = failed.take() {
+                            self.ghost_renderer.clear_ghost_text()?;
+                            dropdown_visible = false;
+                            self.renderer.render_note("Explaining…", 1)?;
+                            note_visible = true;
+                            
+                            if ai_provider.is_none() || offline::is_offline() {
+                                ai_provider = AiProviderFactory::create_provider(config).await;
+                            }
+                            let explanation = match ai_provider.as_deref() {
+                                Some(provider) => {
+                                    let shell = shell_integration.get_shell_name();
+                                    explain_error(provider, config, &failed.command, &failed.output, failed.status, shell)
+                                        .await
+                                        .unwrap_or_else(|e| format!("Couldn't explain the error: {}", e))
+                                }
+                                None if offline::is_offline() => "RustFig is offline; no local AI provider is available.".to_string(),
+                                None => "No AI provider configured. Check your configuration.".to_string(),
+                            };
+                            self.renderer.render_note(&explanation, MAX_EXPLANATION_LINES)?;
+                        }
+                    },
+                    Event::Key(KeyEvent { code: KeyCode::Char('b'), modifiers: KeyModifiers::ALT, kind: KeyEventKind::Press, .. }) => {
+                        // Bookmark the current command line
+                        let cmd_line = shell_integration.get_current_command_line()?;
+                        if !cmd_line.trim().is_empty() {
+                            BookmarkStore::new(&config.general.user_data_dir).add(Bookmark::new(&cmd_line, None, Vec::new()))?;
+                        }
+                    },
+                    Event::Key(KeyEvent { code: KeyCode::Char('i'), modifiers: KeyModifiers::ALT, kind: KeyEventKind::Press, .. }) => {
+                        // Show or hide type, size and age columns on path suggestions
+                        suggestion_engine.toggle_path_details();
+                        dropdown_stale |= dropdown_visible;
+                    },
                     Event::Key(KeyEvent { code: KeyCode::Right, kind: KeyEventKind::Press, .. }) => {
                         // Accept ghost text on right arrow if at end of input
                         let cmd_line = shell_integration.get_current_command_line()?;
-                        let (cur_x, _) = position()?;
+                        let cursor_pos = shell_integration.get_cursor_position()?;
                         
-                        if cur_x as usize >= cmd_line.len() {
-                            if let Some(ghost) = self.ghost_renderer.accept_ghost() {
-                                shell_integration.apply_completion(&ghost)?;
+                        if cursor_pos >= cmd_line.len() {
+                            if let Some(corrected) = self.ghost_renderer.accept_correction()? {
+                                shell_integration.replace_command_line(&corrected)?;
+                                current_input = shell_integration.get_current_command_line()?;
+                                current_cursor = shell_integration.get_cursor_position()?;
+                            } else if let Some(ghost) = self.ghost_renderer.accept_ghost() {
+                                stats.ghost_accepted(ghost.chars().count());
+                                shell_integration.apply_edit(LineEdit::Insert(&ghost))?;
                                 current_input = shell_integration.get_current_command_line()?;
                             }
                         }
@@ -105,47 +432,228 @@ impl Terminal {
                     _ => {
                         // Clear ghost text on any other key
                         self.ghost_renderer.clear_ghost_text()?;
+                        self.ghost_renderer.dismiss_correction();
                         
                         // Update current input
                         let new_input = shell_integration.get_current_command_line()?;
+                        let new_cursor = shell_integration.get_cursor_position()?;
                         
-                        // Only update predictions if input changed
-                        if new_input != current_input {
+                        // Only update predictions if input or cursor changed
+                        if new_input != current_input || new_cursor != current_cursor {
+                            // Typing into a placeholder moves the ones after it
+                            if let Some(session) = snippet_session.as_mut() {
+                                session.record_edit(new_input.len() as isize - current_input.len() as isize);
+                            }
                             current_input = new_input;
+                            current_cursor = new_cursor;
                             
-                            // Get cursor position for ghost text
-                            let (cur_x, cur_y) = position()?;
+                            // Get cursor position for ghost text, from where the command line
+                            // starts if the shell marked it
+                            let term_width = size()?.0;
+                            let before_cursor = current_input.get(..current_cursor).unwrap_or(&current_input);
+                            let (cur_x, cur_y) = match prompt.cursor_position(before_cursor, term_width) {
+                                Some(cursor) => cursor,
+                                None => position()?,
+                            };
                             self.ghost_renderer.update_cursor_pos(cur_x, cur_y);
+                            self.renderer.set_anchor_row(prompt.below_command(&current_input, term_width));
                             
-                            // Update dropdown if visible
-                            if dropdown_visible {
-                                let suggestions = suggestion_engine.get_suggestions(&current_input, 10).await;
-                                if suggestions.is_empty() {
-                                    dropdown_visible = false;
+                            // A printed list can't be updated in place, and without
+                            // `complete_while_typing` the list doesn't follow the line; Tab
+                            // shows a new one. Nor does it stay open once the word is shorter
+                            // than it would open for.
+                            if dropdown_visible
+                                && (render_mode.accessible
+                                    || !auto_dropdown.follows_typing()
+                                    || !auto_dropdown.prefix_ready(&current_input, current_cursor))
+                            {
+                                dropdown_visible = false;
+                                if !render_mode.accessible {
                                     self.renderer.clear_dropdown()?;
-                                } else {
-                                    self.renderer.render_dropdown(&suggestions, 0)?;
                                 }
                             }
+                            // A screen reader would hear a new list at every pause
+                            if !dropdown_visible && !render_mode.accessible && tmux_menu.is_none() && snippet_session.is_none() {
+                                auto_dropdown.edited(&current_input, current_cursor, Instant::now());
+                            }
                             
-                            // Generate predictions for ghost text with performance timing
-                            let timing_start = Instant::now();
-                            let predictions = prediction_engine.predict(&current_input, 5).await;
-                            let timing_elapsed = timing_start.elapsed();
-                            
-                            // Only show ghost text if predictions were fast enough (<5ms)
-                            if timing_elapsed.as_millis() < 5 && !predictions.is_empty() {
-                                let prediction = predictions.first();
-                                self.ghost_renderer.render_ghost_text(&current_input, prediction)?;
+                            // Notes go away once the user types
+                            if note_visible && !dropdown_visible {
+                                self.renderer.clear_dropdown()?;
                             }
+                            note_visible = false;
+                            dropdown_stale |= dropdown_visible;
+                            predictions_stale |= debouncer.keystroke(Instant::now());
                         }
                     }
                 }
             }
+            
+            // An item chosen from the tmux menu is accepted like one from the dropdown
+            if let Some(suggestion) = tmux_menu.as_mut().and_then(|menu| menu.take_pick()) {
+                if preview_destructive && self.preview_impact(shell_integration.as_ref(), &suggestion)? {
+                    awaiting_confirmation = Some(suggestion);
+                    note_visible = true;
+                } else {
+                    current_input = accept_suggestion(shell_integration.as_ref(), &suggestion, &mut snippet_session, &mut stats)?;
+                    current_cursor = shell_integration.get_cursor_position()?;
+                }
+            }
+            
+            // Open the dropdown once typing has paused for `ui.dropdown_delay_ms`
+            if !dropdown_visible && !prompt.is_running() && auto_dropdown.due(Instant::now()) {
+                let suggestions = suggestion_engine.get_suggestions(&current_input, current_cursor, dropdown_limit).await;
+                if !suggestions.is_empty() {
+                    dropdown_visible = true;
+                    self.renderer.render_dropdown(&suggestions, 0)?;
+                }
+                current_suggestions = suggestions;
+            }
+            
+            if (dropdown_stale || predictions_stale) && frames.try_frame(Instant::now()) {
+                // Update dropdown if visible
+                if dropdown_stale && dropdown_visible {
+                    let suggestions = suggestion_engine.get_suggestions(&current_input, current_cursor, dropdown_limit).await;
+                    if suggestions.is_empty() {
+                        dropdown_visible = false;
+                        self.renderer.clear_dropdown()?;
+                    } else {
+                        self.renderer.render_dropdown(&suggestions, 0)?;
+                    }
+                    current_suggestions = suggestions;
+                }
+                
+                if predictions_stale && !prompt.is_running() {
+                    self.show_predictions(&prediction_engine, &suggestion_engine, &current_input, current_cursor, &mut stats).await?;
+                }
+                
+                dropdown_stale = false;
+                predictions_stale = false;
+            }
+            
+            // Batched output goes out as one write per frame
+            if render_mode.batch_output {
+                io::stdout().flush()?;
+            }
         }
         
         Ok(())
     }
+    
+    /// Predict for the line and draw the best prediction as ghost text, or a correction
+    /// when the command name is a typo
+    async fn show_predictions(
+        &mut self,
+        prediction_engine: &PredictionEngine,
+        suggestion_engine: &SuggestionEngine,
+        input: &str,
+        cursor: usize,
+        stats: &mut StatsRecorder,
+    ) -> Result<(), Box<dyn Error>> {
+        // Generate predictions for ghost text with performance timing
+        let timing_start = Instant::now();
+        let (predictions, remote) = match self.remote_predictions(input, cursor, 5) {
+            Some(predictions) => (predictions, true),
+            None => (prediction_engine.predict(input, cursor, 5).await, false),
+        };
+        let timing_elapsed = timing_start.elapsed();
+        
+        // Only show ghost text if predictions were fast enough (<5ms), which a round trip
+        // to the remote service never is; mid-line there's no room to draw it, so
+        // predictions only feed the dropdown
+        let at_end = cursor >= input.len();
+        let mut shown = false;
+        if at_end && self.shell_draws_ghost {
+            // Drawn by the shell after the line, where a correction would overlap it
+            shown = predictions.first().map_or(false, |prediction| !prediction.get_ghost_text(input).is_empty());
+        } else if at_end && (remote || timing_elapsed.as_millis() < 5) && !predictions.is_empty() {
+            let prediction = predictions.first();
+            let was_showing = self.ghost_renderer.is_showing();
+            self.ghost_renderer.render_ghost_text(input, prediction)?;
+            
+            // Typing through ghost text keeps it up; it's counted once, when it appears
+            if !was_showing && self.ghost_renderer.is_showing() {
+                stats.ghost_shown();
+            }
+            shown = self.ghost_renderer.is_showing();
+        }
+        
+        // Nothing extends the line; if its command name is a typo, offer the fix instead
+        if at_end && !shown {
+            if let Some(corrected) = suggestion_engine.correction(input) {
+                self.ghost_renderer.render_correction(&corrected)?;
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Show what the line `suggestion` makes would delete or change, if its command is a
+    /// destructive one; true when it's shown, and accepting waits for a second Tab
+    fn preview_impact(&mut self, shell_integration: &dyn ShellIntegration, suggestion: &Suggestion) -> Result<bool, Box<dyn Error>> {
+        let line = shell_integration.get_current_command_line()?;
+        let cursor = shell_integration.get_cursor_position()?.min(line.len());
+        let edited = suggestion.edit(token_start(&line, cursor)).apply_to(&line, cursor);
+        let dir = PathBuf::from(shell_integration.get_current_directory()?);
+        
+        let Some(impact) = impact::preview(&edited, &dir) else { return Ok(false) };
+        let text = format!("{}\nTab accepts · any other key cancels", impact.describe());
+        self.renderer.render_note(&text, MAX_IMPACT_LINES)?;
+        Ok(true)
+    }
+    
+    /// Predictions from the forwarded service, or None to predict locally
+    #[cfg(unix)]
+    fn remote_predictions(&self, input: &str, cursor: usize, limit: usize) -> Option<Vec<Prediction>> {
+        self.remote.as_ref()?.predict(input, cursor, limit)
+    }
+    
+    #[cfg(not(unix))]
+    fn remote_predictions(&self, _input: &str, _cursor: usize, _limit: usize) -> Option<Vec<Prediction>> {
+        None
+    }
+}
+
+/// Apply a suggestion to the command line and return the new line
+///
+/// Snippets start a session at their first placeholder.
+fn accept_suggestion(
+    shell_integration: &dyn ShellIntegration,
+    suggestion: &Suggestion,
+    snippet_session: &mut Option<SnippetSession>,
+    stats: &mut StatsRecorder,
+) -> Result<String, Box<dyn Error>> {
+    let line = shell_integration.get_current_command_line()?;
+    let cursor = shell_integration.get_cursor_position()?.min(line.len());
+    let before = line.chars().count();
+    
+    // Full lines replace the line; everything else replaces the word under the cursor
+    shell_integration.apply_edit(suggestion.edit(token_start(&line, cursor)))?;
+    
+    if matches!(suggestion.kind, SuggestionKind::Snippet) {
+        // Snippets replace the whole line, so their stops are already line offsets
+        *snippet_session = SnippetSession::start(&suggestion.tab_stops, 0);
+        if let Some(stop) = snippet_session.as_mut().and_then(|session| session.next_stop()) {
+            shell_integration.select_range(stop.start, stop.end)?;
+        }
+    }
+    
+    let line = shell_integration.get_current_command_line()?;
+    stats.suggestion_accepted(line.chars().count().saturating_sub(before));
+    Ok(line)
+}
+
+/// Where the word under the cursor starts, which a suggestion replaces
+fn token_start(line: &str, cursor: usize) -> usize {
+    CommandParser::new().parse(line, cursor).map_or(cursor, |parsed| parsed.current_arg_start)
+}
+
+/// The command name of a submitted line, which is what wasn't found when it exits
+/// with 127
+fn missing_command(submitted: &str) -> Option<String> {
+    let parsed = CommandParser::new().parse(submitted, submitted.len()).ok()?;
+    let command = if parsed.command.is_empty() { parsed.current_arg } else { parsed.command };
+    (!command.is_empty()).then(|| command.to_string())
 }
 
 impl Drop for Terminal {
@@ -0,0 +1,386 @@
+//! Drawing the dropdown without flicker
+//!
+//! The dropdown is laid out into a `CellBuffer`, which is compared with the buffer
+//! already on screen; only cells that differ are written, in runs, so an update while
+//! typing usually touches a few cells instead of repainting the whole widget. On
+//! terminals that support it each update is wrapped in synchronized output (mode 2026)
+//! so it appears at once.
+
+use std::error::Error;
+use std::io::{self, Write};
+use crossterm::{
+    cursor::{position, MoveTo, RestorePosition, SavePosition},
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor},
+    terminal::{size, BeginSynchronizedUpdate, EndSynchronizedUpdate},
+    QueueableCommand,
+};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::suggestion::engine::Suggestion;
+use super::capabilities::Capabilities;
+use super::dropdown::Dropdown;
+use super::render_mode::RenderMode;
+
+/// Colors and attributes of a cell; `None` is the terminal's default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    pub fg: Option<Color>,
+    pub bg: Option<Color>,
+    pub dim: bool,
+}
+
+/// One column of the screen
+///
+/// `symbol` is a whole grapheme cluster; a wide one is followed by a cell with an empty
+/// symbol for the second column it covers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cell {
+    pub symbol: String,
+    pub style: Style,
+}
+
+impl Cell {
+    fn blank() -> Self {
+        Self { symbol: " ".to_string(), style: Style::default() }
+    }
+}
+
+/// Columns `text` takes on screen
+pub fn display_width(text: &str) -> usize {
+    text.width()
+}
+
+/// The longest prefix of `text` that fits in `columns`, never splitting a grapheme
+pub fn truncate_to_width(text: &str, columns: usize) -> &str {
+    let mut width = 0;
+    for (offset, grapheme) in text.grapheme_indices(true) {
+        width += grapheme.width();
+        if width > columns {
+            return &text[..offset];
+        }
+    }
+    text
+}
+
+/// `text` broken into lines of at most `columns`, at spaces where possible
+pub fn wrap_to_width(text: &str, columns: usize) -> Vec<String> {
+    let columns = columns.max(1);
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let mut word = word;
+            if !line.is_empty() && display_width(&line) + 1 + display_width(word) > columns {
+                lines.push(std::mem::take(&mut line));
+            }
+            // A word wider than the line is split wherever it has to be
+            while display_width(word) > columns {
+                let head = truncate_to_width(word, columns);
+                let head = if head.is_empty() { word.graphemes(true).next().unwrap_or(word) } else { head };
+                lines.push(head.to_string());
+                word = &word[head.len()..];
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// A rectangle of cells, row by row
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellBuffer {
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+}
+
+impl CellBuffer {
+    pub fn new(width: u16, height: u16) -> Self {
+        Self { width, height, cells: vec![Cell::blank(); width as usize * height as usize] }
+    }
+
+    pub fn size(&self) -> (u16, u16) {
+        (self.width, self.height)
+    }
+
+    pub fn get(&self, x: u16, y: u16) -> Option<&Cell> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.cells.get(y as usize * self.width as usize + x as usize)
+    }
+
+    /// Blank the row in `style`, for backgrounds that span the width
+    pub fn fill_row(&mut self, y: u16, style: Style) {
+        for x in 0..self.width {
+            self.set(x, y, Cell { symbol: " ".to_string(), style });
+        }
+    }
+
+    /// Write `text` from column `x`, cut off at the right edge with an ellipsis; returns
+    /// the column after it
+    pub fn put_str(&mut self, x: u16, y: u16, text: &str, style: Style) -> u16 {
+        let room = self.width.saturating_sub(x) as usize;
+        let fits = display_width(text) <= room;
+        let shown = if fits { text } else { truncate_to_width(text, room.saturating_sub(1)) };
+
+        let mut col = x;
+        for grapheme in shown.graphemes(true) {
+            // Combining marks and the like come attached to a grapheme; controls take no room
+            let width = grapheme.width() as u16;
+            if width == 0 {
+                continue;
+            }
+            self.set(col, y, Cell { symbol: grapheme.to_string(), style });
+            for covered in 1..width {
+                self.set(col + covered, y, Cell { symbol: String::new(), style });
+            }
+            col += width;
+        }
+        if !fits && room > 0 {
+            self.set(col, y, Cell { symbol: "…".to_string(), style });
+            col += 1;
+        }
+        col
+    }
+
+    fn set(&mut self, x: u16, y: u16, cell: Cell) {
+        if x < self.width && y < self.height {
+            self.cells[y as usize * self.width as usize + x as usize] = cell;
+        }
+    }
+}
+
+/// What's on screen: a buffer and where its top left corner is
+struct Drawn {
+    origin: (u16, u16),
+    buffer: CellBuffer,
+}
+
+impl Drawn {
+    fn contains(&self, x: u16, y: u16) -> bool {
+        let (width, height) = self.buffer.size();
+        x >= self.origin.0 && x < self.origin.0 + width && y >= self.origin.1 && y < self.origin.1 + height
+    }
+}
+
+/// Writes cells, moving the cursor and switching styles only when needed
+struct Painter<'a> {
+    out: &'a mut io::Stdout,
+    cursor: Option<(u16, u16)>,
+    style: Style,
+}
+
+impl Painter<'_> {
+    fn put(&mut self, x: u16, y: u16, cell: &Cell) -> io::Result<()> {
+        // The second column of a wide character was written along with the first
+        if cell.symbol.is_empty() {
+            return Ok(());
+        }
+        if self.cursor != Some((x, y)) {
+            self.out.queue(MoveTo(x, y))?;
+        }
+        if cell.style != self.style {
+            self.out.queue(SetAttribute(Attribute::Reset))?;
+            if let Some(fg) = cell.style.fg {
+                self.out.queue(SetForegroundColor(fg))?;
+            }
+            if let Some(bg) = cell.style.bg {
+                self.out.queue(SetBackgroundColor(bg))?;
+            }
+            if cell.style.dim {
+                self.out.queue(SetAttribute(Attribute::Dim))?;
+            }
+            self.style = cell.style;
+        }
+        self.out.queue(Print(&cell.symbol))?;
+        self.cursor = Some((x + display_width(&cell.symbol) as u16, y));
+        Ok(())
+    }
+}
+
+/// Widest a note gets, however wide the terminal
+const NOTE_MAX_WIDTH: u16 = 100;
+
+/// Draws the dropdown, or a note, below the command line
+pub struct Renderer {
+    dropdown: Dropdown,
+    /// Row to draw at, when the prompt marks tell where the command line ends
+    anchor_row: Option<u16>,
+    /// Print numbered lines instead of drawing over the screen
+    accessible: bool,
+    synchronized: bool,
+    batched: bool,
+    front: Option<Drawn>,
+}
+
+impl Renderer {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        Ok(Self {
+            dropdown: Dropdown::new(),
+            anchor_row: None,
+            accessible: false,
+            synchronized: false,
+            batched: false,
+            front: None,
+        })
+    }
+
+    pub fn set_mode(&mut self, mode: &RenderMode) {
+        self.dropdown.width = mode.dropdown_width;
+        self.dropdown.max_height = mode.dropdown_max_height;
+        self.dropdown.show_icons = mode.show_icons;
+        self.dropdown.show_descriptions = mode.show_descriptions;
+        self.batched = mode.batch_output;
+        self.accessible = mode.accessible;
+    }
+
+    pub fn set_capabilities(&mut self, capabilities: &Capabilities) {
+        self.synchronized = capabilities.synchronized_output;
+    }
+
+    /// Draw from this row instead of the one below the cursor
+    pub fn set_anchor_row(&mut self, row: Option<u16>) {
+        self.anchor_row = row;
+    }
+
+    /// Draw the dropdown, or update the one on screen to match
+    pub fn render_dropdown(&mut self, suggestions: &[Suggestion], selected: usize) -> Result<(), Box<dyn Error>> {
+        if self.accessible {
+            self.print_plain(suggestions)?;
+            return Ok(());
+        }
+
+        let (columns, rows) = size()?;
+        let (cursor_x, cursor_y) = position()?;
+
+        let buffer = self.dropdown.layout(suggestions, selected, columns);
+        let (width, height) = buffer.size();
+
+        // Below the command line, or above the cursor when there's no room below
+        let below = self.anchor_row.unwrap_or(cursor_y + 1);
+        let y = if below + height <= rows { below } else { cursor_y.saturating_sub(height) };
+        let x = cursor_x.min(columns.saturating_sub(width));
+
+        self.present(Some(Drawn { origin: (x, y), buffer }))?;
+        Ok(())
+    }
+
+    /// Draw lines of dimmed text below the command line in place of the dropdown, wrapped
+    /// to the terminal and cut off at `max_lines`
+    pub fn render_note(&mut self, text: &str, max_lines: usize) -> Result<(), Box<dyn Error>> {
+        let (columns, rows) = size()?;
+        let width = columns.min(NOTE_MAX_WIDTH);
+        let mut lines = wrap_to_width(text, width.saturating_sub(2) as usize);
+        if lines.len() > max_lines {
+            lines.truncate(max_lines);
+            if let Some(last) = lines.last_mut() {
+                last.push('…');
+            }
+        }
+
+        if self.accessible {
+            let mut out = io::stdout();
+            for line in &lines {
+                out.queue(Print("\r\n"))?.queue(Print(line))?;
+            }
+            out.queue(Print("\r\n"))?;
+            out.flush()?;
+            return Ok(());
+        }
+
+        let (_, cursor_y) = position()?;
+        let height = lines.len() as u16;
+        let mut buffer = CellBuffer::new(width, height);
+        let style = Style { dim: true, ..Style::default() };
+        for (row, line) in lines.iter().enumerate() {
+            buffer.put_str(1, row as u16, line, style);
+        }
+
+        let below = self.anchor_row.unwrap_or(cursor_y + 1);
+        let y = if below + height <= rows { below } else { cursor_y.saturating_sub(height) };
+        self.present(Some(Drawn { origin: (0, y), buffer }))?;
+        Ok(())
+    }
+
+    /// Remove the dropdown, or the note, from the screen
+    pub fn clear_dropdown(&mut self) -> Result<(), Box<dyn Error>> {
+        self.present(None)?;
+        Ok(())
+    }
+
+    /// Print the list as lines after the command line, the way shells list completions:
+    /// no colors and no cursor movement, so screen readers read it in order
+    fn print_plain(&mut self, suggestions: &[Suggestion]) -> io::Result<()> {
+        let mut out = io::stdout();
+        for line in self.dropdown.plain_lines(suggestions) {
+            out.queue(Print("\r\n"))?.queue(Print(line))?;
+        }
+        out.queue(Print("\r\n"))?;
+        out.flush()
+    }
+
+    /// Make the screen show `next`, writing only the cells that change
+    fn present(&mut self, next: Option<Drawn>) -> io::Result<()> {
+        let previous = self.front.take();
+        if previous.is_none() && next.is_none() {
+            return Ok(());
+        }
+
+        let mut out = io::stdout();
+        if self.synchronized {
+            out.queue(BeginSynchronizedUpdate)?;
+        }
+        out.queue(SavePosition)?;
+
+        let mut painter = Painter { out: &mut out, cursor: None, style: Style::default() };
+
+        // Blank what the new dropdown doesn't cover
+        if let Some(previous) = &previous {
+            let (width, height) = previous.buffer.size();
+            for y in previous.origin.1..previous.origin.1 + height {
+                for x in previous.origin.0..previous.origin.0 + width {
+                    if !next.as_ref().map_or(false, |next| next.contains(x, y)) {
+                        painter.put(x, y, &Cell::blank())?;
+                    }
+                }
+            }
+        }
+
+        // Write cells that differ from what's there now
+        if let Some(next) = &next {
+            let (width, height) = next.buffer.size();
+            for row in 0..height {
+                for col in 0..width {
+                    let (x, y) = (next.origin.0 + col, next.origin.1 + row);
+                    let Some(cell) = next.buffer.get(col, row) else { continue };
+                    let current = previous
+                        .as_ref()
+                        .filter(|previous| previous.contains(x, y))
+                        .and_then(|previous| previous.buffer.get(x - previous.origin.0, y - previous.origin.1));
+                    if current != Some(cell) {
+                        painter.put(x, y, cell)?;
+                    }
+                }
+            }
+        }
+
+        out.queue(SetAttribute(Attribute::Reset))?
+            .queue(ResetColor)?
+            .queue(RestorePosition)?;
+        if self.synchronized {
+            out.queue(EndSynchronizedUpdate)?;
+        }
+        if !self.batched {
+            out.flush()?;
+        }
+
+        self.front = next;
+        Ok(())
+    }
+}
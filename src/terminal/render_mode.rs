@@ -0,0 +1,109 @@
+use std::env;
+use std::time::{Duration, Instant};
+
+use crate::config::schema::{RenderProfile, SshConfig};
+use crate::config::Config;
+use crate::utils::ssh::is_ssh_session;
+use super::capabilities::Capabilities;
+
+/// Rows the dropdown is cut to over SSH
+const SSH_DROPDOWN_HEIGHT: u16 = 5;
+
+/// Redraws over SSH are at least this far apart, whatever the bandwidth allows
+const SSH_MIN_FRAME_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Cursor movement, colors and line clearing around each drawn row
+const ROW_OVERHEAD_BYTES: usize = 24;
+
+/// How the terminal draws; over SSH (with `ssh.enable_optimizations`) it trades polish for bytes
+#[derive(Debug, Clone)]
+pub struct RenderMode {
+    /// Plain numbered lines instead of drawing over the screen, for screen readers
+    pub accessible: bool,
+    pub low_bandwidth: bool,
+    pub animations: bool,
+    pub dropdown_width: u16,
+    pub dropdown_max_height: u16,
+    pub show_icons: bool,
+    /// Descriptions next to suggestions, from `ui.show_explanations`
+    pub show_descriptions: bool,
+    /// Queue output and write it once per frame instead of after every operation
+    pub batch_output: bool,
+    /// Shortest time between redraws while typing
+    pub min_frame_interval: Duration,
+}
+
+impl RenderMode {
+    pub fn detect(config: &Config, capabilities: &Capabilities) -> Self {
+        // The minimal profile is for terminals that can't be trusted with glyphs or motion
+        let minimal = capabilities.profile == RenderProfile::Minimal;
+        let accessible = config.ui.accessible.unwrap_or_else(accessible_by_default);
+        let local = Self {
+            accessible,
+            low_bandwidth: false,
+            animations: !minimal && !accessible && config.ui.animation_speed.map_or(true, |speed| speed > 0),
+            dropdown_width: config.ui.dropdown_width,
+            dropdown_max_height: config.ui.dropdown_max_height,
+            show_icons: !minimal && !accessible && config.ui.show_icons,
+            show_descriptions: config.ui.show_explanations.unwrap_or(true),
+            batch_output: false,
+            min_frame_interval: Duration::ZERO,
+        };
+
+        match config.ssh.as_ref() {
+            Some(ssh) if ssh.enable_optimizations && is_ssh_session() => local.over_ssh(ssh),
+            _ => local,
+        }
+    }
+
+    fn over_ssh(self, ssh: &SshConfig) -> Self {
+        let dropdown_max_height = self.dropdown_max_height.min(SSH_DROPDOWN_HEIGHT);
+
+        // Space redraws so that a full frame, dropdown plus ghost text, fits in `max_bandwidth_kb`
+        let frame_bytes = (dropdown_max_height as usize + 1) * (self.dropdown_width as usize + ROW_OVERHEAD_BYTES);
+        let bandwidth_interval = ssh
+            .max_bandwidth_kb
+            .filter(|kb| *kb > 0)
+            .map_or(Duration::ZERO, |kb| Duration::from_secs_f64(frame_bytes as f64 / (kb as f64 * 1024.0)));
+
+        Self {
+            accessible: self.accessible,
+            low_bandwidth: true,
+            animations: self.animations && !ssh.reduce_animations.unwrap_or(true),
+            dropdown_width: self.dropdown_width,
+            dropdown_max_height,
+            // Glyphs take several bytes each, and the local terminal's font may lack them
+            show_icons: false,
+            show_descriptions: self.show_descriptions,
+            batch_output: true,
+            min_frame_interval: bandwidth_interval.max(SSH_MIN_FRAME_INTERVAL),
+        }
+    }
+}
+
+/// `NO_COLOR` (any non-empty value) or a dumb terminal asks for plain output
+fn accessible_by_default() -> bool {
+    env::var_os("NO_COLOR").map_or(false, |value| !value.is_empty())
+        || env::var("TERM").map_or(false, |term| term == "dumb")
+}
+
+/// Keeps redraws at least `RenderMode::min_frame_interval` apart
+pub struct FrameLimiter {
+    interval: Duration,
+    last_frame: Option<Instant>,
+}
+
+impl FrameLimiter {
+    pub fn new(interval: Duration) -> Self {
+        Self { interval, last_frame: None }
+    }
+
+    /// Whether a redraw may happen now; if so it counts as the latest frame
+    pub fn try_frame(&mut self, now: Instant) -> bool {
+        if self.last_frame.map_or(false, |last| now.saturating_duration_since(last) < self.interval) {
+            return false;
+        }
+        self.last_frame = Some(now);
+        true
+    }
+}
@@ -0,0 +1,217 @@
+//! OSC 133 semantic prompt marks
+//!
+//! The shell integration scripts wrap the prompt in `ESC ] 133 ; A` (prompt start) and
+//! `ESC ] 133 ; B` (command start), and emit `C` when a command starts running and
+//! `D ; <status>` when it finishes. Knowing where the command begins means ghost text and
+//! the dropdown are placed from the line itself rather than wherever the cursor happened
+//! to be while a prompt like starship or powerlevel10k was redrawing. The output between
+//! `C` and `D`, with the status `D` carries, is what an error explanation is built from.
+
+use super::render::display_width;
+
+/// A mark the shell emitted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptMark {
+    /// `A`: the prompt is about to be drawn
+    PromptStart,
+    /// `B`: the prompt is drawn and the command line begins at the cursor
+    CommandStart,
+    /// `C`: the command was submitted; what follows is its output
+    CommandExecuted,
+    /// `D`: the command finished, with its exit status if the shell gave one
+    CommandFinished(Option<i32>),
+}
+
+/// Longest OSC 133 sequence kept while waiting for its terminator
+const MAX_SEQUENCE: usize = 64;
+
+/// Most of a command's output kept, from its end, where errors usually are
+const MAX_OUTPUT: usize = 16 * 1024;
+
+/// Finds OSC 133 marks in terminal output, including sequences split across reads, and
+/// keeps what commands print between `C` and `D`
+#[derive(Default)]
+pub struct MarkParser {
+    /// An unterminated sequence from the end of the last read, starting at its ESC
+    pending: Vec<u8>,
+    /// Output of the running command so far
+    output: Option<Vec<u8>>,
+    /// Output of the command that finished last, until taken
+    finished_output: Option<Vec<u8>>,
+}
+
+impl MarkParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The marks in the next chunk of output, in order
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<PromptMark> {
+        let mut data = std::mem::take(&mut self.pending);
+        data.extend_from_slice(bytes);
+
+        let mut marks = Vec::new();
+        let mut pos = 0;
+        // Output before `copied` has been captured; from `held` on it waits in `pending`
+        let mut copied = 0;
+        let mut held = data.len();
+        while let Some(offset) = memchr::memchr(0x1b, &data[pos..]) {
+            let start = pos + offset;
+            let body = &data[start..];
+
+            // Not an OSC 133 sequence (or not provably one yet)
+            const INTRO: &[u8] = b"\x1b]133;";
+            if !INTRO.starts_with(&body[..body.len().min(INTRO.len())]) {
+                pos = start + 1;
+                continue;
+            }
+            if body.len() < INTRO.len() {
+                self.pending = body.to_vec();
+                held = start;
+                break;
+            }
+
+            // Terminated by BEL or ST (`ESC \`)
+            let params = &body[INTRO.len()..];
+            let end = params
+                .iter()
+                .enumerate()
+                .find(|&(i, &b)| b == 0x07 || (b == 0x1b && params.get(i + 1) == Some(&b'\\')));
+            match end {
+                Some((len, &terminator)) => {
+                    let mark = parse_mark(&params[..len]);
+                    self.capture(&data[copied..start]);
+                    if let Some(mark) = mark {
+                        self.track_output(mark);
+                        marks.push(mark);
+                    }
+                    pos = start + INTRO.len() + len + if terminator == 0x07 { 1 } else { 2 };
+                    copied = pos;
+                }
+                None => {
+                    // A lone trailing ESC may be the start of ST
+                    if body.len() <= MAX_SEQUENCE {
+                        self.pending = body.to_vec();
+                    }
+                    held = start;
+                    break;
+                }
+            }
+        }
+        self.capture(&data[copied..held]);
+
+        marks
+    }
+
+    /// What the command that finished last printed, escape sequences and all, once
+    pub fn take_output(&mut self) -> Option<Vec<u8>> {
+        self.finished_output.take()
+    }
+
+    fn track_output(&mut self, mark: PromptMark) {
+        match mark {
+            PromptMark::CommandExecuted => self.output = Some(Vec::new()),
+            PromptMark::CommandFinished(_) => self.finished_output = self.output.take(),
+            _ => {}
+        }
+    }
+
+    fn capture(&mut self, bytes: &[u8]) {
+        if let Some(output) = self.output.as_mut() {
+            output.extend_from_slice(bytes);
+            if output.len() > MAX_OUTPUT {
+                output.drain(..output.len() - MAX_OUTPUT);
+            }
+        }
+    }
+}
+
+/// `A`, `B`, `C` or `D` with its exit status, ignoring the key=value options that follow
+fn parse_mark(params: &[u8]) -> Option<PromptMark> {
+    let mut fields = params.split(|&b| b == b';');
+    match fields.next()? {
+        b"A" => Some(PromptMark::PromptStart),
+        b"B" => Some(PromptMark::CommandStart),
+        b"C" => Some(PromptMark::CommandExecuted),
+        b"D" => {
+            let status = fields.next().and_then(|field| std::str::from_utf8(field).ok()?.parse().ok());
+            Some(PromptMark::CommandFinished(status))
+        }
+        _ => None,
+    }
+}
+
+/// Where the command line is, as told by the marks
+#[derive(Debug, Default)]
+pub struct SemanticPrompt {
+    /// Screen position the command line begins at, while at a prompt
+    command_start: Option<(u16, u16)>,
+    /// A submitted command is running; nothing should be drawn over its output
+    running: bool,
+}
+
+impl SemanticPrompt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a mark; the position for `CommandStart` comes from `command_started`
+    pub fn apply(&mut self, mark: PromptMark) {
+        match mark {
+            PromptMark::PromptStart | PromptMark::CommandStart => {
+                self.running = false;
+                self.command_start = None;
+            }
+            PromptMark::CommandExecuted => {
+                self.running = true;
+                self.command_start = None;
+            }
+            PromptMark::CommandFinished(_) => self.running = false,
+        }
+    }
+
+    /// The command line began at the prompt; `cursor` is where the cursor is now, after
+    /// `typed`, whatever was typed before it by the time the mark was read
+    pub fn command_started(&mut self, cursor: (u16, u16), typed: &str, term_width: u16) {
+        self.apply(PromptMark::CommandStart);
+        self.command_start = Some(retreat(cursor, display_width(typed), term_width));
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Screen position after `before_cursor`, the part of the line left of the cursor
+    pub fn cursor_position(&self, before_cursor: &str, term_width: u16) -> Option<(u16, u16)> {
+        Some(end_of(self.command_start?, before_cursor, term_width))
+    }
+
+    /// First screen row below the whole command line, where the dropdown goes
+    pub fn below_command(&self, line: &str, term_width: u16) -> Option<u16> {
+        Some(end_of(self.command_start?, line, term_width).1.saturating_add(1))
+    }
+}
+
+/// Where `text` ends when drawn from `start`; continuation lines restart at column 0
+fn end_of(start: (u16, u16), text: &str, term_width: u16) -> (u16, u16) {
+    let mut end = start;
+    for (index, segment) in text.split('\n').enumerate() {
+        let from = if index == 0 { start } else { (0, end.1.saturating_add(1)) };
+        end = advance(from, display_width(segment), term_width);
+    }
+    end
+}
+
+/// Position `width` columns after `from`, wrapping at the terminal edge
+fn advance(from: (u16, u16), width: usize, term_width: u16) -> (u16, u16) {
+    let term_width = term_width.max(1) as usize;
+    let offset = from.0 as usize + width;
+    ((offset % term_width) as u16, from.1.saturating_add((offset / term_width) as u16))
+}
+
+/// Position `width` columns before `from`, wrapping back over earlier rows
+fn retreat(from: (u16, u16), width: usize, term_width: u16) -> (u16, u16) {
+    let term_width = term_width.max(1) as usize;
+    let offset = (from.1 as usize * term_width + from.0 as usize).saturating_sub(width);
+    ((offset % term_width) as u16, (offset / term_width) as u16)
+}
@@ -0,0 +1,325 @@
+use std::error::Error;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
+    terminal::{self, disable_raw_mode, enable_raw_mode, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+    QueueableCommand,
+};
+
+use crate::suggestion::fuzzy::fast_fuzzy_score;
+use crate::suggestion::snippets::{self, Snippet, DEFAULT_SNIPPET_FILE};
+
+/// Labels of the editable fields, in form order
+const FIELDS: [&str; 4] = ["Name", "Command", "Description", "Tags"];
+
+/// What the keyboard is currently driving
+enum Mode {
+    Browse,
+    Search,
+    /// Editing a snippet; `original` is `None` when adding a new one
+    Edit { original: Option<Snippet>, fields: [String; 4], focus: usize },
+    ConfirmDelete,
+}
+
+/// Interactive browser for the snippet library (`rustfig snippets`)
+pub struct SnippetManager {
+    dir: PathBuf,
+    snippets: Vec<Snippet>,
+    query: String,
+    /// Indices into `snippets` matching the query, best first
+    visible: Vec<usize>,
+    selected: usize,
+    mode: Mode,
+    status: Option<String>,
+}
+
+impl SnippetManager {
+    pub fn new(dir: PathBuf) -> Self {
+        let mut manager = Self {
+            dir,
+            snippets: Vec::new(),
+            query: String::new(),
+            visible: Vec::new(),
+            selected: 0,
+            mode: Mode::Browse,
+            status: None,
+        };
+        manager.reload();
+        manager
+    }
+
+    /// Run the TUI until the user quits
+    pub fn run(&mut self) -> Result<(), Box<dyn Error>> {
+        enable_raw_mode()?;
+        let mut stdout = io::stdout();
+        stdout.queue(EnterAlternateScreen)?.queue(Hide)?;
+        stdout.flush()?;
+
+        let result = self.event_loop();
+
+        // Always restore the terminal, even if the loop failed
+        let _ = stdout.queue(Show).and_then(|out| out.queue(LeaveAlternateScreen)).and_then(|out| out.flush());
+        let _ = disable_raw_mode();
+
+        result
+    }
+
+    fn event_loop(&mut self) -> Result<(), Box<dyn Error>> {
+        loop {
+            self.render()?;
+
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                if !self.handle_key(key)? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn reload(&mut self) {
+        self.snippets = snippets::load_snippets(&self.dir);
+        self.filter();
+    }
+
+    /// Recompute the visible list from the search query
+    fn filter(&mut self) {
+        let mut scored: Vec<(usize, f32)> = self.snippets.iter()
+            .enumerate()
+            .filter_map(|(index, snippet)| {
+                if self.query.is_empty() {
+                    return Some((index, 0.0));
+                }
+                let score = fast_fuzzy_score(&self.query, &snippet.name)
+                    .into_iter()
+                    .chain(snippet.tags.iter().filter_map(|tag| fast_fuzzy_score(&self.query, tag)))
+                    .chain(fast_fuzzy_score(&self.query, &snippet.body))
+                    .fold(None, |best: Option<f32>, score| Some(best.map_or(score, |best| best.max(score))))?;
+                Some((index, score))
+            })
+            .collect();
+
+        if !self.query.is_empty() {
+            scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+        }
+
+        self.visible = scored.into_iter().map(|(index, _)| index).collect();
+        self.selected = self.selected.min(self.visible.len().saturating_sub(1));
+    }
+
+    fn selected_snippet(&self) -> Option<&Snippet> {
+        self.visible.get(self.selected).map(|&index| &self.snippets[index])
+    }
+
+    /// Handle a key press; returns `false` when the manager should exit
+    fn handle_key(&mut self, key: KeyEvent) -> Result<bool, Box<dyn Error>> {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return Ok(false);
+        }
+
+        match &mut self.mode {
+            Mode::Browse => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+                KeyCode::Up | KeyCode::Char('k') => self.selected = self.selected.saturating_sub(1),
+                KeyCode::Down | KeyCode::Char('j') => {
+                    if self.selected + 1 < self.visible.len() {
+                        self.selected += 1;
+                    }
+                }
+                KeyCode::Char('/') => self.mode = Mode::Search,
+                KeyCode::Char('a') => {
+                    self.mode = Mode::Edit { original: None, fields: Default::default(), focus: 0 };
+                }
+                KeyCode::Char('e') | KeyCode::Enter => {
+                    if let Some(snippet) = self.selected_snippet().cloned() {
+                        let fields = [
+                            snippet.name.clone(),
+                            snippet.body.clone(),
+                            snippet.description.clone().unwrap_or_default(),
+                            snippet.tags.join(", "),
+                        ];
+                        self.mode = Mode::Edit { original: Some(snippet), fields, focus: 1 };
+                    }
+                }
+                KeyCode::Char('d') | KeyCode::Delete => {
+                    if self.selected_snippet().is_some() {
+                        self.mode = Mode::ConfirmDelete;
+                    }
+                }
+                _ => {}
+            },
+            Mode::Search => match key.code {
+                KeyCode::Enter | KeyCode::Esc => self.mode = Mode::Browse,
+                KeyCode::Backspace => {
+                    self.query.pop();
+                    self.filter();
+                }
+                KeyCode::Char(c) => {
+                    self.query.push(c);
+                    self.selected = 0;
+                    self.filter();
+                }
+                _ => {}
+            },
+            Mode::ConfirmDelete => {
+                if key.code == KeyCode::Char('y') {
+                    if let Some(snippet) = self.selected_snippet().cloned() {
+                        snippets::delete_snippet(&snippet)?;
+                        self.status = Some(format!("Deleted {}", snippet.name));
+                        self.reload();
+                    }
+                }
+                self.mode = Mode::Browse;
+            }
+            Mode::Edit { fields, focus, .. } => match key.code {
+                KeyCode::Esc => self.mode = Mode::Browse,
+                KeyCode::Tab | KeyCode::Down => *focus = (*focus + 1) % FIELDS.len(),
+                KeyCode::BackTab | KeyCode::Up => *focus = (*focus + FIELDS.len() - 1) % FIELDS.len(),
+                KeyCode::Backspace => {
+                    fields[*focus].pop();
+                }
+                KeyCode::Char(c) => fields[*focus].push(c),
+                KeyCode::Enter => self.save_form()?,
+                _ => {}
+            },
+        }
+
+        Ok(true)
+    }
+
+    /// Validate and write the snippet being edited
+    fn save_form(&mut self) -> Result<(), Box<dyn Error>> {
+        let (original, fields) = match &self.mode {
+            Mode::Edit { original, fields, .. } => (original.clone(), fields.clone()),
+            _ => return Ok(()),
+        };
+
+        let name = fields[0].trim().to_string();
+        let body = fields[1].trim().to_string();
+        if name.is_empty() || body.is_empty() {
+            self.status = Some("Name and command are required".to_string());
+            return Ok(());
+        }
+
+        let renamed = original.as_ref().map_or(true, |original| original.name != name);
+        if renamed && self.snippets.iter().any(|snippet| snippet.name == name) {
+            self.status = Some(format!("A snippet named {} already exists", name));
+            return Ok(());
+        }
+
+        let description = Some(fields[2].trim().to_string()).filter(|d| !d.is_empty());
+        let tags = fields[3]
+            .split(',')
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect();
+
+        let snippet = Snippet {
+            name,
+            body,
+            description,
+            tags,
+            file: original.as_ref()
+                .map(|original| original.file.clone())
+                .unwrap_or_else(|| self.dir.join(DEFAULT_SNIPPET_FILE)),
+        };
+
+        snippets::save_snippet(&snippet, original.as_ref().map(|original| original.name.as_str()))?;
+
+        self.status = Some(format!("Saved {}", snippet.name));
+        self.mode = Mode::Browse;
+        self.reload();
+        Ok(())
+    }
+
+    fn render(&self) -> io::Result<()> {
+        let (width, height) = terminal::size()?;
+        let width = width as usize;
+        let mut stdout = io::stdout();
+
+        stdout.queue(Clear(ClearType::All))?.queue(MoveTo(0, 0))?;
+        stdout
+            .queue(SetAttribute(Attribute::Bold))?
+            .queue(Print(format!("RustFig snippets ({})", self.snippets.len())))?
+            .queue(SetAttribute(Attribute::Reset))?;
+
+        if !self.query.is_empty() || matches!(self.mode, Mode::Search) {
+            stdout.queue(Print(format!("   /{}", self.query)))?;
+        }
+
+        match &self.mode {
+            Mode::Edit { original, fields, focus } => {
+                let title = if original.is_some() { "Edit snippet" } else { "New snippet" };
+                stdout.queue(MoveTo(0, 2))?.queue(Print(title))?;
+
+                for (index, (label, value)) in FIELDS.iter().zip(fields.iter()).enumerate() {
+                    stdout.queue(MoveTo(0, 4 + index as u16 * 2))?;
+                    if index == *focus {
+                        stdout.queue(SetForegroundColor(Color::Cyan))?;
+                    }
+                    stdout
+                        .queue(Print(truncate(&format!("{:>12}: {}", label, value), width)))?
+                        .queue(ResetColor)?;
+                }
+
+                stdout.queue(MoveTo(0, 13))?.queue(Print(truncate(
+                    "Use ${1:default} for tab stops and \\$ for a literal dollar sign",
+                    width,
+                )))?;
+            }
+            _ => {
+                // Leave room for the header, the preview, and the help line
+                let list_height = (height as usize).saturating_sub(6);
+                let scroll = self.selected.saturating_sub(list_height.saturating_sub(1));
+
+                for (row, &index) in self.visible.iter().skip(scroll).take(list_height).enumerate() {
+                    let snippet = &self.snippets[index];
+                    let line = format!(
+                        "{} {:<24} {}",
+                        if scroll + row == self.selected { ">" } else { " " },
+                        snippet.name,
+                        snippet.description.as_deref().unwrap_or(&snippet.body),
+                    );
+
+                    stdout.queue(MoveTo(0, 2 + row as u16))?;
+                    if scroll + row == self.selected {
+                        stdout.queue(SetAttribute(Attribute::Reverse))?;
+                    }
+                    stdout.queue(Print(truncate(&line, width)))?.queue(SetAttribute(Attribute::Reset))?;
+                }
+
+                if let Some(snippet) = self.selected_snippet() {
+                    stdout
+                        .queue(MoveTo(0, height.saturating_sub(3)))?
+                        .queue(SetForegroundColor(Color::DarkGrey))?
+                        .queue(Print(truncate(&format!("$ {}", snippet.body), width)))?
+                        .queue(ResetColor)?;
+                }
+            }
+        }
+
+        let help = match self.mode {
+            Mode::Browse => "↑/↓ move  / search  a add  e edit  d delete  q quit",
+            Mode::Search => "type to filter  enter/esc done",
+            Mode::Edit { .. } => "tab next field  enter save  esc cancel",
+            Mode::ConfirmDelete => "delete this snippet? y/n",
+        };
+        let footer = match &self.status {
+            Some(status) => format!("{}  |  {}", help, status),
+            None => help.to_string(),
+        };
+        stdout.queue(MoveTo(0, height.saturating_sub(1)))?.queue(Print(truncate(&footer, width)))?;
+
+        stdout.flush()
+    }
+}
+
+/// Cut a line to the terminal width
+fn truncate(line: &str, width: usize) -> String {
+    line.chars().take(width).collect()
+}
@@ -0,0 +1,126 @@
+//! The suggestion list as a tmux menu (`display-menu`)
+//!
+//! Inside tmux, drawing the dropdown over the pane fights tmux's own redraws and spills
+//! into neighbouring panes. tmux draws the menu itself as a popup over the pane instead.
+//! Choosing an item stores its index in the pane option `@rustfig-pick` and signals a
+//! `wait-for` channel; a thread blocked on the channel hands the index back.
+
+use std::error::Error;
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use crate::config::Config;
+use crate::suggestion::engine::Suggestion;
+
+/// Pane option the chosen item's index is written to
+const PICK_OPTION: &str = "@rustfig-pick";
+
+/// Items beyond this get no shortcut key
+const KEYED_ITEMS: usize = 9;
+
+/// Shows suggestion lists as tmux menus and reports which item was chosen
+pub struct TmuxMenu {
+    pane: String,
+    /// `wait-for` channel signalled when an item is chosen
+    channel: String,
+    picks: Receiver<usize>,
+    /// The list in the menu last shown, which picks index into
+    shown: Vec<Suggestion>,
+}
+
+impl TmuxMenu {
+    /// A menu for this pane, when running in tmux with `ui.tmux_popup` on
+    pub fn detect(config: &Config) -> Option<Self> {
+        if !config.ui.tmux_popup.unwrap_or(false) || std::env::var_os("TMUX").map_or(true, |tmux| tmux.is_empty()) {
+            return None;
+        }
+        let pane = std::env::var("TMUX_PANE").ok().filter(|pane| !pane.is_empty())?;
+        let channel = format!("rustfig-pick-{}", std::process::id());
+
+        let (sender, picks) = mpsc::channel();
+        let waiter_pane = pane.clone();
+        let waiter_channel = channel.clone();
+        thread::spawn(move || {
+            // Menu items set the pick before signalling; a signal without one means the
+            // menu was dropped
+            while tmux(&["wait-for", waiter_channel.as_str()]).is_ok() {
+                let pick = tmux(&["show-options", "-pqv", "-t", waiter_pane.as_str(), PICK_OPTION])
+                    .ok()
+                    .and_then(|value| value.trim().parse().ok());
+                match pick {
+                    Some(index) if sender.send(index).is_ok() => {}
+                    _ => break,
+                }
+            }
+        });
+
+        Some(Self { pane, channel, picks, shown: Vec::new() })
+    }
+
+    /// Open a menu listing `suggestions` at the bottom left of the pane
+    ///
+    /// tmux only draws it; this returns at once, and the choice arrives through `take_pick`.
+    pub fn show(&mut self, suggestions: &[Suggestion]) -> Result<(), Box<dyn Error>> {
+        // Forget the previous menu's choice, in case it's read before this one's
+        tmux(&["set-option", "-pu", "-t", self.pane.as_str(), PICK_OPTION])?;
+
+        let mut args: Vec<String> = ["display-menu", "-t", self.pane.as_str(), "-x", "P", "-y", "P", "-T", "rustfig"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        for (index, suggestion) in suggestions.iter().enumerate() {
+            let name = match &suggestion.description {
+                Some(description) => format!("{}  {}", suggestion.display, description),
+                None => suggestion.display.clone(),
+            };
+            let key = if index < KEYED_ITEMS { (index + 1).to_string() } else { String::new() };
+            args.push(menu_text(&name));
+            args.push(key);
+            args.push(format!(
+                "set-option -p -t {} {} {} ; wait-for -S {}",
+                self.pane, PICK_OPTION, index, self.channel
+            ));
+        }
+
+        tmux(&args)?;
+        self.shown = suggestions.to_vec();
+        Ok(())
+    }
+
+    /// The suggestion chosen from the last menu, if one was chosen since the last call
+    pub fn take_pick(&mut self) -> Option<Suggestion> {
+        let index = self.picks.try_iter().last()?;
+        self.shown.get(index).cloned()
+    }
+}
+
+impl Drop for TmuxMenu {
+    fn drop(&mut self) {
+        // Wake the waiting thread with no pick set, so it exits
+        let _ = tmux(&["set-option", "-pu", "-t", self.pane.as_str(), PICK_OPTION]);
+        let _ = tmux(&["wait-for", "-S", self.channel.as_str()]);
+    }
+}
+
+/// Menu item names are formats: a leading `-` disables the item and `#` starts a format
+fn menu_text(text: &str) -> String {
+    let text = text.replace('#', "##");
+    if text.starts_with('-') {
+        format!(" {}", text)
+    } else {
+        text
+    }
+}
+
+/// Run a tmux command and return its output
+fn tmux<S: AsRef<str>>(args: &[S]) -> Result<String, Box<dyn Error>> {
+    let output = Command::new("tmux")
+        .args(args.iter().map(|arg| arg.as_ref()))
+        .stdin(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        return Err(format!("tmux {}: {}", args[0].as_ref(), String::from_utf8_lossy(&output.stderr).trim()).into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
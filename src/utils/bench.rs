@@ -0,0 +1,74 @@
+use std::time::Duration;
+use serde::Serialize;
+
+/// Representative inputs by source, replayed by `rustfig bench`
+///
+/// Fixed rather than taken from the user's history so results compare across machines
+/// and releases.
+pub const CORPUS: &[(&str, &[&str])] = &[
+    ("history", &[
+        "", "l", "ls -", "cd ", "g", "grep -r ", "docker ", "docker ps -", "kubectl get ",
+        "cargo ", "cargo b", "npm run ", "make ", "ssh ", "python -m ", "echo $", "ls | grep ",
+    ]),
+    ("paths", &[
+        "ls ", "cd ~/", "cd ../", "cat ./", "vim src/", "cp -r ./", "rm ", "tail -f /var/log/",
+        "ls /usr/", "cd /tmp/", "less ~/.", "find . -name ",
+    ]),
+    ("git", &[
+        "git ", "git s", "git ch", "git checkout ", "git switch ", "git commit -", "git push ",
+        "git pull --", "git log --", "git rebase -i ", "git diff ", "git stash ", "git branch -",
+    ]),
+];
+
+/// Patterns scored against every entry of `FUZZY_CANDIDATES` by the fuzzy matcher rows
+pub const FUZZY_PATTERNS: &[&str] = &[
+    "c", "crgo", "gco", "dcu", "kgp", "Mak", "src/m", "--no", "tst", "READ", "dkr", "pyt",
+];
+
+/// Names, paths and whole history lines, long enough for the SIMD kernels to matter
+pub const FUZZY_CANDIDATES: &[&str] = &[
+    "cargo", "Cargo.toml", "Makefile", "README.md", "src/main.rs", "src/suggestion/fuzzy.rs",
+    "--no-verify", "--no-default-features", "docker-compose", "kubectl", "python3",
+    "git checkout -b feature/simd-fuzzy-matching",
+    "docker compose -f docker-compose.dev.yml up --build --remove-orphans",
+    "kubectl get pods --all-namespaces --field-selector=status.phase!=Running",
+    "cargo test --workspace --all-features -- --nocapture --test-threads=1",
+    "find . -type f -name '*.rs' -not -path './target/*' | xargs wc -l | sort -n",
+    "python -m pytest tests/integration/test_shell_hooks.py -k 'not slow' -x -vv",
+    "ssh -L 8080:localhost:80 -i ~/.ssh/id_ed25519 deploy@staging.example.com",
+];
+
+/// Latency percentiles over a set of samples
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyStats {
+    pub samples: usize,
+    pub p50_us: u64,
+    pub p95_us: u64,
+    pub p99_us: u64,
+    pub max_us: u64,
+}
+
+impl LatencyStats {
+    /// Summarize `samples`; `None` when there are none
+    pub fn from_samples(mut samples: Vec<Duration>) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        samples.sort();
+
+        let micros = |d: Duration| d.as_micros() as u64;
+        Some(Self {
+            samples: samples.len(),
+            p50_us: micros(percentile(&samples, 50.0)),
+            p95_us: micros(percentile(&samples, 95.0)),
+            p99_us: micros(percentile(&samples, 99.0)),
+            max_us: micros(samples[samples.len() - 1]),
+        })
+    }
+}
+
+/// Nearest-rank percentile of sorted, non-empty `samples`
+fn percentile(samples: &[Duration], p: f64) -> Duration {
+    let rank = (p / 100.0 * samples.len() as f64).ceil() as usize;
+    samples[rank.clamp(1, samples.len()) - 1]
+}
@@ -0,0 +1,119 @@
+//! Binary files for persisted caches and learning data
+//!
+//! A file is a fixed header followed by the bincode payload, zstd-compressed when
+//! `performance.optimizations.compress_cache` is on:
+//!
+//! | bytes | field                                         |
+//! |-------|-----------------------------------------------|
+//! | 4     | magic, `RFCF`                                 |
+//! | 1     | header format                                 |
+//! | 1     | flags; bit 0 means the payload is compressed  |
+//! | 4     | version of the data, chosen by the caller     |
+//! | 8     | payload length                                |
+//! | 4     | CRC-32 of the payload                         |
+//!
+//! Integers are little-endian. Compression is recorded per file, so changing the
+//! setting never makes existing files unreadable.
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::config::Config;
+
+const MAGIC: &[u8; 4] = b"RFCF";
+
+/// Bump when the header layout changes
+const HEADER_FORMAT: u8 = 1;
+
+const HEADER_LEN: usize = 22;
+
+const FLAG_COMPRESSED: u8 = 1;
+
+/// Fast enough to write on every save; higher levels barely shrink these files further
+const ZSTD_LEVEL: i32 = 3;
+
+/// `performance.optimizations.compress_cache`, on unless configured otherwise
+pub fn compression_enabled(config: &Config) -> bool {
+    config
+        .performance
+        .as_ref()
+        .and_then(|performance| performance.optimizations.as_ref())
+        .map_or(true, |optimizations| optimizations.compress_cache)
+}
+
+/// Serialize `value` to `path`, replacing it atomically
+pub fn write<T: Serialize>(path: &Path, version: u32, value: &T, compress: bool) -> Result<(), Box<dyn Error>> {
+    let mut payload = bincode::serialize(value)?;
+    let mut flags = 0;
+    if compress {
+        payload = zstd::stream::encode_all(payload.as_slice(), ZSTD_LEVEL)?;
+        flags |= FLAG_COMPRESSED;
+    }
+
+    let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.push(HEADER_FORMAT);
+    bytes.push(flags);
+    bytes.extend_from_slice(&version.to_le_bytes());
+    bytes.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+    bytes.extend_from_slice(&payload);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    // Write to a temporary file first so a crash never leaves a torn file behind
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(format!(".{}.tmp", std::process::id()));
+    fs::write(&tmp_name, bytes)?;
+    fs::rename(tmp_name, path)?;
+    Ok(())
+}
+
+/// Read a value written by `write` with the same `version`
+///
+/// Fails on a missing, truncated, corrupt or outdated file; for a cache that means
+/// regenerating the entry.
+pub fn read<T: DeserializeOwned>(path: &Path, version: u32) -> Result<T, Box<dyn Error>> {
+    decode(&fs::read(path)?, version)
+}
+
+/// Whether `bytes` start like a file written by `write`, as opposed to older raw data
+pub fn has_header(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+fn decode<T: DeserializeOwned>(bytes: &[u8], version: u32) -> Result<T, Box<dyn Error>> {
+    if bytes.len() < HEADER_LEN || &bytes[..4] != MAGIC {
+        return Err("not a RustFig cache file".into());
+    }
+    if bytes[4] != HEADER_FORMAT {
+        return Err(format!("unsupported header format {}", bytes[4]).into());
+    }
+
+    let flags = bytes[5];
+    let found = u32::from_le_bytes(bytes[6..10].try_into()?);
+    if found != version {
+        return Err(format!("data version {}, expected {}", found, version).into());
+    }
+
+    let len = u64::from_le_bytes(bytes[10..18].try_into()?);
+    let checksum = u32::from_le_bytes(bytes[18..22].try_into()?);
+    let payload = &bytes[HEADER_LEN..];
+    if payload.len() as u64 != len {
+        return Err(format!("truncated: {} of {} payload bytes", payload.len(), len).into());
+    }
+    if crc32fast::hash(payload) != checksum {
+        return Err("checksum mismatch".into());
+    }
+
+    if flags & FLAG_COMPRESSED != 0 {
+        Ok(bincode::deserialize(&zstd::stream::decode_all(payload)?)?)
+    } else {
+        Ok(bincode::deserialize(payload)?)
+    }
+}
@@ -33,7 +33,7 @@ impl CancellationToken {
 /// Execute a function with a timeout
 pub fn with_timeout<F, R>(f: F, timeout: Duration) -> Option<R>
 where
-    F: FnOnce() -> R,
+    F: FnOnce() -> R + Send + 'static,
     R: Send + 'static,
 {
     let (tx, rx) = std::sync::mpsc::channel();
@@ -84,3 +84,18 @@ impl PeriodicTask {
         }
     }
 }
+
+/// Build the multi-threaded async runtime sized by `performance.worker_threads`
+///
+/// 0 (or no performance section) lets tokio use one worker per CPU core.
+pub fn build_runtime(config: &crate::config::Config) -> std::io::Result<tokio::runtime::Runtime> {
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    builder.enable_all().thread_name("rustfig-worker");
+
+    let workers = config.performance.as_ref().map_or(0, |performance| performance.worker_threads);
+    if workers > 0 {
+        builder.worker_threads(workers);
+    }
+
+    builder.build()
+}
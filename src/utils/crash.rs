@@ -0,0 +1,146 @@
+//! Panic hook: put the terminal back and keep a crash report
+//!
+//! A panic in the prompt used to leave the terminal in raw mode on the alternate screen.
+//! The hook restores it before the panic message is printed, then writes the message,
+//! location, backtrace, version and a fingerprint of the config to `logs/crash-*.txt`
+//! in the config directory, which `rustfig crash-report submit` can send.
+
+use std::backtrace::Backtrace;
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::panic;
+use std::path::{Path, PathBuf};
+use crossterm::{
+    cursor::Show,
+    execute,
+    style::ResetColor,
+    terminal::{disable_raw_mode, LeaveAlternateScreen},
+};
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::service::log;
+
+/// Reports not yet sent have this extension; sent ones are renamed to `SENT_EXTENSION`
+const REPORT_EXTENSION: &str = "txt";
+const SENT_EXTENSION: &str = "sent";
+
+/// The process that crashed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrashSource {
+    /// The interactive prompt, which owns the terminal
+    Terminal,
+    Service,
+}
+
+impl CrashSource {
+    fn name(self) -> &'static str {
+        match self {
+            CrashSource::Terminal => "terminal",
+            CrashSource::Service => "service",
+        }
+    }
+}
+
+/// Install the hook for this process; the default hook still prints the message
+pub fn install(config: &Config, source: CrashSource) {
+    let fingerprint = config_fingerprint(config);
+    let dir = report_dir().ok();
+    let previous = panic::take_hook();
+
+    panic::set_hook(Box::new(move |info| {
+        // Release builds abort on any panic; otherwise only the main thread's ends the
+        // prompt, and a panicking background task is contained while it keeps running
+        let fatal = cfg!(panic = "abort") || std::thread::current().name() == Some("main");
+        if source == CrashSource::Terminal && fatal {
+            restore_terminal();
+        }
+        previous(info);
+
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        let location = info.location().map_or_else(|| "unknown".to_string(), |location| location.to_string());
+        let report = format!(
+            "RustFig crash report\n\
+             version: {}\n\
+             component: {}\n\
+             time: {}\n\
+             os: {} ({})\n\
+             config: {}\n\
+             thread: {}\n\
+             message: {}\n\
+             location: {}\n\
+             \n\
+             backtrace:\n{}\n",
+            env!("CARGO_PKG_VERSION"),
+            source.name(),
+            log::format_timestamp(log::unix_now()),
+            std::env::consts::OS,
+            std::env::consts::ARCH,
+            fingerprint,
+            std::thread::current().name().unwrap_or("unnamed"),
+            message,
+            location,
+            Backtrace::force_capture(),
+        );
+
+        if let Some(path) = dir.as_deref().and_then(|dir| write_report(dir, &report).ok()) {
+            log::error(&format!("panicked at {}: {}; crash report in {}", location, message, path.display()));
+            eprintln!("RustFig crashed; a report was saved to {}", path.display());
+            eprintln!("Send it with 'rustfig crash-report submit'");
+        }
+    }));
+}
+
+/// Leave raw mode and the alternate screen, and show the cursor again
+fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, ResetColor, Show);
+}
+
+/// Identifies the configuration without revealing it
+fn config_fingerprint(config: &Config) -> String {
+    let yaml = serde_yaml::to_string(config).unwrap_or_default();
+    let digest = Sha256::digest(yaml.as_bytes());
+    digest.iter().take(8).map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Where crash reports go: next to the service log
+pub fn report_dir() -> Result<PathBuf, Box<dyn Error>> {
+    Ok(crate::config::loader::get_config_dir()?.join(log::LOG_DIR))
+}
+
+fn write_report(dir: &Path, report: &str) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("crash-{}-{}.{}", log::unix_now(), std::process::id(), REPORT_EXTENSION));
+    fs::write(&path, report)?;
+    Ok(path)
+}
+
+/// Reports not sent yet, newest first
+pub fn unsent_reports() -> Vec<PathBuf> {
+    let Ok(dir) = report_dir() else { return Vec::new() };
+    let Ok(entries) = fs::read_dir(dir) else { return Vec::new() };
+
+    let mut reports: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension().map_or(false, |ext| ext == REPORT_EXTENSION)
+                && path.file_name().and_then(|name| name.to_str()).map_or(false, |name| name.starts_with("crash-"))
+        })
+        .filter_map(|path| Some((fs::metadata(&path).ok()?.modified().ok()?, path)))
+        .collect();
+    reports.sort_by(|a, b| b.0.cmp(&a.0));
+    reports.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Keep a sent report, but stop offering it
+pub fn mark_sent(report: &Path) -> io::Result<()> {
+    fs::rename(report, report.with_extension(SENT_EXTENSION))
+}
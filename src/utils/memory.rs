@@ -0,0 +1,155 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Weak};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+static ACCOUNTANT: Lazy<MemoryAccountant> = Lazy::new(MemoryAccountant::new);
+
+/// Something holding memory the accountant can measure and, unless it's user data, reclaim
+pub trait MemoryConsumer: Send + Sync {
+    /// Approximate bytes held; called on every cache insert, so keep it cheap
+    fn memory_bytes(&self) -> usize;
+
+    /// Drop the least valuable data until at most `target` bytes remain
+    fn shrink_to(&self, target: usize);
+}
+
+/// What a consumer holds, in the order memory is reclaimed when over budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryTier {
+    PredictionCache,
+    AiCache,
+    DirectoryIndex,
+    /// Learned patterns are the user's data and are never evicted
+    Learning,
+}
+
+impl MemoryTier {
+    pub const ALL: [MemoryTier; 4] = [
+        MemoryTier::PredictionCache,
+        MemoryTier::AiCache,
+        MemoryTier::DirectoryIndex,
+        MemoryTier::Learning,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MemoryTier::PredictionCache => "prediction cache",
+            MemoryTier::AiCache => "AI response cache",
+            MemoryTier::DirectoryIndex => "directory index",
+            MemoryTier::Learning => "learning data",
+        }
+    }
+
+    fn evictable(self) -> bool {
+        self != MemoryTier::Learning
+    }
+}
+
+/// Accounted memory by tier, for `rustfig perf memory`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryReport {
+    /// 0 means unlimited
+    pub budget_bytes: usize,
+    pub tiers: Vec<(MemoryTier, usize)>,
+    /// Resident size of the whole process, when the platform reports it
+    pub rss_bytes: Option<u64>,
+    /// Bytes reclaimed since startup to stay within the budget
+    pub evicted_bytes: usize,
+}
+
+impl MemoryReport {
+    pub fn accounted_bytes(&self) -> usize {
+        self.tiers.iter().map(|(_, bytes)| bytes).sum()
+    }
+}
+
+/// Keeps the caches within `performance.max_memory_mb`
+struct MemoryAccountant {
+    budget_bytes: AtomicUsize,
+    evicted_bytes: AtomicUsize,
+    consumers: Mutex<Vec<(MemoryTier, Weak<dyn MemoryConsumer>)>>,
+}
+
+impl MemoryAccountant {
+    fn new() -> Self {
+        Self {
+            budget_bytes: AtomicUsize::new(0),
+            evicted_bytes: AtomicUsize::new(0),
+            consumers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Live consumers, forgetting the ones that were dropped
+    fn consumers(&self) -> Vec<(MemoryTier, Arc<dyn MemoryConsumer>)> {
+        let mut consumers = self.consumers.lock();
+        consumers.retain(|(_, consumer)| consumer.strong_count() > 0);
+        consumers
+            .iter()
+            .filter_map(|(tier, consumer)| consumer.upgrade().map(|consumer| (*tier, consumer)))
+            .collect()
+    }
+}
+
+/// Set the budget from `performance.max_memory_mb` (0 = unlimited) and apply it right away
+pub fn set_budget_mb(mb: usize) {
+    ACCOUNTANT.budget_bytes.store(mb.saturating_mul(1024 * 1024), Ordering::Relaxed);
+    enforce();
+}
+
+/// Account for `consumer` until it's dropped
+pub fn register<T: MemoryConsumer + 'static>(tier: MemoryTier, consumer: &Arc<T>) {
+    let consumer: Arc<dyn MemoryConsumer> = consumer.clone();
+    ACCOUNTANT.consumers.lock().push((tier, Arc::downgrade(&consumer)));
+}
+
+/// Reclaim memory, cheapest to rebuild first, until usage is back within the budget
+///
+/// Call after adding to a cache, without holding any of its locks.
+pub fn enforce() {
+    let budget = ACCOUNTANT.budget_bytes.load(Ordering::Relaxed);
+    if budget == 0 {
+        return;
+    }
+
+    let consumers = ACCOUNTANT.consumers();
+    let mut total: usize = consumers.iter().map(|(_, consumer)| consumer.memory_bytes()).sum();
+
+    for tier in MemoryTier::ALL.iter().filter(|tier| tier.evictable()) {
+        for (_, consumer) in consumers.iter().filter(|(t, _)| t == tier) {
+            if total <= budget {
+                return;
+            }
+            let before = consumer.memory_bytes();
+            consumer.shrink_to(before.saturating_sub(total - budget));
+            let freed = before.saturating_sub(consumer.memory_bytes());
+            ACCOUNTANT.evicted_bytes.fetch_add(freed, Ordering::Relaxed);
+            total -= freed;
+        }
+    }
+}
+
+/// Current accounted usage by tier
+pub fn report() -> MemoryReport {
+    let consumers = ACCOUNTANT.consumers();
+    let tiers = MemoryTier::ALL
+        .iter()
+        .map(|tier| {
+            let bytes = consumers
+                .iter()
+                .filter(|(t, _)| t == tier)
+                .map(|(_, consumer)| consumer.memory_bytes())
+                .sum();
+            (*tier, bytes)
+        })
+        .collect();
+
+    MemoryReport {
+        budget_bytes: ACCOUNTANT.budget_bytes.load(Ordering::Relaxed),
+        tiers,
+        rss_bytes: super::system::process_rss_bytes(),
+        evicted_bytes: ACCOUNTANT.evicted_bytes.load(Ordering::Relaxed),
+    }
+}
@@ -0,0 +1,39 @@
+//! Folding together results that several sources produced
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Share of a duplicate's score added to the one it's merged into, so text several
+/// sources agree on ranks above text only one of them offers
+pub const AGREEMENT_SHARE: f32 = 0.1;
+
+/// `items` with each group sharing a key folded into one by `merge(kept, duplicate)`,
+/// in the order the groups were first seen
+pub fn merge_by_key<T, K, F, M>(items: Vec<T>, key: F, mut merge: M) -> Vec<T>
+where
+    K: Hash + Eq,
+    F: Fn(&T) -> K,
+    M: FnMut(&mut T, T),
+{
+    let mut positions: HashMap<K, usize> = HashMap::with_capacity(items.len());
+    let mut merged: Vec<T> = Vec::with_capacity(items.len());
+    for item in items {
+        match positions.get(&key(&item)) {
+            Some(&position) => merge(&mut merged[position], item),
+            None => {
+                positions.insert(key(&item), merged.len());
+                merged.push(item);
+            }
+        }
+    }
+    merged
+}
+
+/// The more informative of two optional descriptions: any over none, then the longer
+pub fn better_description<T: AsRef<str>>(kept: &mut Option<T>, other: Option<T>) {
+    let other_len = other.as_ref().map_or(0, |d| d.as_ref().trim().len());
+    let kept_len = kept.as_ref().map_or(0, |d| d.as_ref().trim().len());
+    if other_len > kept_len {
+        *kept = other;
+    }
+}
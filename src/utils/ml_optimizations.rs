@@ -1,8 +1,7 @@
 //! Apple Silicon-specific optimizations for RustFig
-//! This module is only compiled on Apple M-series chips
-
-#[cfg(all(target_arch = "aarch64", target_os = "macos"))]
-use std::arch::aarch64::*;
+//!
+//! The string helpers run on the NEON kernels in `utils::simd` there, and on the
+//! AVX2/SSE2 ones on x86_64.
 
 /// Check if running on Apple Silicon
 #[inline]
@@ -14,48 +13,16 @@ pub fn is_apple_silicon() -> bool {
     return false;
 }
 
-/// Optimized string matching using SIMD where available
+/// Whether `needle` occurs in `haystack`
 #[inline]
 pub fn fast_string_match(haystack: &str, needle: &str) -> bool {
-    #[cfg(all(target_arch = "aarch64", target_os = "macos"))]
-    {
-        if haystack.len() < needle.len() {
-            return false;
-        }
-        
-        // Use NEON SIMD instructions for faster string matching
-        // Note: This is simplified and would need actual NEON implementation
-        unsafe {
-            // This would be replaced with actual NEON instructions
-            // This is a placeholder for the concept
-            haystack.contains(needle)
-        }
-    }
-    
-    #[cfg(not(all(target_arch = "aarch64", target_os = "macos")))]
-    {
-        haystack.contains(needle)
-    }
+    super::simd::contains(haystack.as_bytes(), needle.as_bytes())
 }
 
 /// Accelerated string prefix check
 #[inline]
 pub fn fast_starts_with(haystack: &str, prefix: &str) -> bool {
-    #[cfg(all(target_arch = "aarch64", target_os = "macos"))]
-    {
-        if haystack.len() < prefix.len() {
-            return false;
-        }
-        
-        // Optimized prefix check using NEON instructions
-        // Would be implemented with actual SIMD in real code
-        haystack.starts_with(prefix)
-    }
-    
-    #[cfg(not(all(target_arch = "aarch64", target_os = "macos")))]
-    {
-        haystack.starts_with(prefix)
-    }
+    super::simd::starts_with(haystack.as_bytes(), prefix.as_bytes())
 }
 
 /// Memory pool optimized for M1's unified memory architecture
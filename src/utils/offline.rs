@@ -0,0 +1,148 @@
+//! Offline mode: no network traffic, local suggestions only
+//!
+//! `rustfig offline on` leaves an `offline` file in the config directory, which every
+//! RustFig process checks before touching the network, so the switch takes effect
+//! without a restart. Without it, a machine with no default route is treated as
+//! offline too; that's noted with a `no-network` file in the runtime directory so the
+//! shell's prompt indicator can show it. AI providers on this machine (Ollama, a local
+//! API server) keep working either way.
+
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::shell::runtime_dir;
+
+/// Marks offline mode as switched on, in the config directory
+const FORCED_FILE: &str = "offline";
+
+/// Marks the network as missing, in the runtime directory
+const NO_NETWORK_FILE: &str = "no-network";
+
+/// How long a status is reused before the files and routes are checked again
+const RECHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+static CACHED: Lazy<Mutex<Option<(Instant, Status)>>> = Lazy::new(|| Mutex::new(None));
+
+/// Whether RustFig may use the network
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Online,
+    /// Switched on with `rustfig offline on`
+    Forced,
+    /// There's no route to anywhere
+    NoNetwork,
+}
+
+impl Status {
+    pub fn description(self) -> &'static str {
+        match self {
+            Status::Online => "online",
+            Status::Forced => "offline (switched on with 'rustfig offline on')",
+            Status::NoNetwork => "offline (no network detected)",
+        }
+    }
+}
+
+/// The current status, checked at most every `RECHECK_INTERVAL`
+pub fn status() -> Status {
+    let mut cached = CACHED.lock();
+    if let Some((checked, status)) = *cached {
+        if checked.elapsed() < RECHECK_INTERVAL {
+            return status;
+        }
+    }
+
+    let status = if forced_path().map_or(false, |path| path.exists()) {
+        Status::Forced
+    } else if !network_available() {
+        Status::NoNetwork
+    } else {
+        Status::Online
+    };
+    note_no_network(status == Status::NoNetwork);
+
+    *cached = Some((Instant::now(), status));
+    status
+}
+
+pub fn is_offline() -> bool {
+    status() != Status::Online
+}
+
+/// Whether a request to `url` may go out: always for this machine, otherwise only
+/// when online
+pub fn allows_url(url: &str) -> bool {
+    is_loopback(url) || !is_offline()
+}
+
+/// Switch offline mode on or off for every RustFig process
+pub fn set_forced(offline: bool) -> Result<(), Box<dyn Error>> {
+    let path = forced_path().ok_or("Could not determine configuration directory")?;
+    if offline {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, "")?;
+    } else if path.exists() {
+        fs::remove_file(&path)?;
+    }
+    *CACHED.lock() = None;
+    Ok(())
+}
+
+fn forced_path() -> Option<PathBuf> {
+    crate::config::loader::get_config_dir().ok().map(|dir| dir.join(FORCED_FILE))
+}
+
+/// Keep the runtime directory's marker in step with what was detected
+fn note_no_network(missing: bool) {
+    let path = runtime_dir().join(NO_NETWORK_FILE);
+    if missing && !path.exists() {
+        let _ = fs::create_dir_all(runtime_dir()).and_then(|_| fs::write(&path, ""));
+    } else if !missing && path.exists() {
+        let _ = fs::remove_file(&path);
+    }
+}
+
+/// Whether there's a default route through anything but loopback; reading the routing
+/// table sends nothing, unlike probing a server
+#[cfg(target_os = "linux")]
+fn network_available() -> bool {
+    let ipv4 = fs::read_to_string("/proc/net/route").unwrap_or_default();
+    let has_ipv4_route = ipv4.lines().skip(1).any(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        fields.len() > 1 && fields[0] != "lo" && fields[1] == "00000000"
+    });
+
+    // Destination and prefix length first, interface name last
+    let ipv6 = fs::read_to_string("/proc/net/ipv6_route").unwrap_or_default();
+    let has_ipv6_route = ipv6.lines().any(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        fields.len() == 10 && fields[0].bytes().all(|b| b == b'0') && fields[1] == "00" && fields[9] != "lo"
+    });
+
+    // No routing table to read (a restricted container) isn't evidence of no network
+    (ipv4.is_empty() && ipv6.is_empty()) || has_ipv4_route || has_ipv6_route
+}
+
+#[cfg(not(target_os = "linux"))]
+fn network_available() -> bool {
+    true
+}
+
+/// Whether `url` points at this machine
+fn is_loopback(url: &str) -> bool {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or("");
+    let host_port = authority.rsplit_once('@').map_or(authority, |(_, host)| host);
+    let host = match host_port.strip_prefix('[') {
+        Some(bracketed) => bracketed.split(']').next().unwrap_or(""),
+        None => host_port.split(':').next().unwrap_or(""),
+    };
+
+    host.eq_ignore_ascii_case("localhost") || host.parse::<std::net::IpAddr>().map_or(false, |ip| ip.is_loopback())
+}
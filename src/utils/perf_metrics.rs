@@ -3,6 +3,7 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 
 static GLOBAL_METRICS: Lazy<Arc<Mutex<HashMap<String, ComponentMetrics>>>> = 
     Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
@@ -53,6 +54,65 @@ impl OperationMetrics {
     }
 }
 
+/// Recorded timings for one component, in a form that can cross the service socket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentSnapshot {
+    pub component: String,
+    pub operation_count: usize,
+    pub operations: Vec<OperationSnapshot>,
+}
+
+/// Recorded timings for one operation, in microseconds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationSnapshot {
+    pub operation: String,
+    pub calls: usize,
+    pub total_us: u64,
+    pub avg_us: u64,
+    pub min_us: u64,
+    pub max_us: u64,
+}
+
+impl From<&OperationMetrics> for OperationSnapshot {
+    fn from(metrics: &OperationMetrics) -> Self {
+        Self {
+            operation: metrics.name.clone(),
+            calls: metrics.call_count,
+            total_us: metrics.total_duration.as_micros() as u64,
+            avg_us: metrics.avg_duration().as_micros() as u64,
+            // min starts out at the maximum duration until the first call
+            min_us: if metrics.call_count == 0 { 0 } else { metrics.min_duration.as_micros() as u64 },
+            max_us: metrics.max_duration.as_micros() as u64,
+        }
+    }
+}
+
+/// Everything recorded in this process, sorted by component and operation
+pub fn snapshot() -> Vec<ComponentSnapshot> {
+    let global = GLOBAL_METRICS.lock().unwrap();
+
+    let mut components: Vec<ComponentSnapshot> = global
+        .values()
+        .map(|metrics| {
+            let mut operations: Vec<OperationSnapshot> = metrics.operation_metrics
+                .lock()
+                .unwrap()
+                .values()
+                .map(OperationSnapshot::from)
+                .collect();
+            operations.sort_by(|a, b| a.operation.cmp(&b.operation));
+
+            ComponentSnapshot {
+                component: metrics.name.clone(),
+                operation_count: metrics.operation_count.load(Ordering::Relaxed),
+                operations,
+            }
+        })
+        .collect();
+    components.sort_by(|a, b| a.component.cmp(&b.component));
+    components
+}
+
 /// Performance measurement utility
 pub struct PerformanceMetrics {
     component_name: String,
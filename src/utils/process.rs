@@ -0,0 +1,57 @@
+use std::io::Read;
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Captured result of a subprocess that finished within its deadline
+#[derive(Debug)]
+pub struct CapturedOutput {
+    pub status: ExitStatus,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+/// Run a command with no stdin, killing it if it outlives `timeout`
+///
+/// Each output stream is capped at `max_output` bytes. Returns `None` if the
+/// process couldn't be spawned or was killed at the deadline.
+pub fn run_with_timeout(command: &mut Command, timeout: Duration, max_output: u64) -> Option<CapturedOutput> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .ok()?;
+
+    // Drain both pipes on background threads so a chatty child can't block on a full pipe
+    let stdout = spawn_reader(child.stdout.take()?, max_output);
+    let stderr = spawn_reader(child.stderr.take()?, max_output);
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() < deadline => thread::sleep(Duration::from_millis(5)),
+            _ => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return None;
+            }
+        }
+    };
+
+    Some(CapturedOutput {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// Read a pipe to completion (bounded) on a separate thread
+fn spawn_reader<R: Read + Send + 'static>(reader: R, limit: u64) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = reader.take(limit).read_to_end(&mut buffer);
+        buffer
+    })
+}
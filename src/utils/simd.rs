@@ -0,0 +1,498 @@
+//! SIMD string kernels behind the fuzzy matcher
+//!
+//! aarch64 uses NEON, which every aarch64 CPU has. x86_64 uses AVX2 when the CPU
+//! reports it at runtime and SSE2 (part of the x86_64 baseline) otherwise. Other
+//! targets get plain loops. Each vector loop finishes its tail with scalar code.
+
+/// Which kernels this machine runs, for `rustfig bench`
+pub fn backend() -> &'static str {
+    #[cfg(target_arch = "aarch64")]
+    return "neon";
+
+    #[cfg(target_arch = "x86_64")]
+    return if x86::has_avx2() { "avx2" } else { "sse2" };
+
+    #[cfg(not(any(target_arch = "aarch64", target_arch = "x86_64")))]
+    return "portable";
+}
+
+/// Whether `haystack` starts with `prefix`
+#[inline]
+pub fn starts_with(haystack: &[u8], prefix: &[u8]) -> bool {
+    haystack.len() >= prefix.len() && prefix_eq(haystack, prefix, false)
+}
+
+/// Whether `haystack` starts with `prefix`, ignoring ASCII case
+#[inline]
+pub fn starts_with_ignore_case(haystack: &[u8], prefix: &[u8]) -> bool {
+    haystack.len() >= prefix.len() && prefix_eq(haystack, prefix, true)
+}
+
+/// Position of the first byte equal to `needle` (which must be lowercase), ignoring ASCII case
+#[inline]
+pub fn find_ignore_case(haystack: &[u8], needle: u8) -> Option<usize> {
+    #[cfg(target_arch = "aarch64")]
+    // SAFETY: NEON is part of the aarch64 baseline
+    return unsafe { neon::find_ignore_case(haystack, needle) };
+
+    #[cfg(target_arch = "x86_64")]
+    // SAFETY: AVX2 is only used when the CPU reports it; SSE2 is part of the x86_64 baseline
+    return unsafe {
+        if x86::has_avx2() {
+            x86::find_ignore_case_avx2(haystack, needle)
+        } else {
+            x86::find_ignore_case_sse2(haystack, needle)
+        }
+    };
+
+    #[cfg(not(any(target_arch = "aarch64", target_arch = "x86_64")))]
+    return scalar::find_ignore_case(haystack, 0, needle);
+}
+
+/// Whether `needle` occurs in `haystack`
+#[inline]
+pub fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    if needle.is_empty() {
+        return true;
+    }
+    if haystack.len() < needle.len() {
+        return false;
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    // SAFETY: NEON is part of the aarch64 baseline; lengths were checked above
+    return unsafe { neon::contains(haystack, needle) };
+
+    #[cfg(target_arch = "x86_64")]
+    // SAFETY: as in `find_ignore_case`; lengths were checked above
+    return unsafe {
+        if x86::has_avx2() {
+            x86::contains_avx2(haystack, needle)
+        } else {
+            x86::contains_sse2(haystack, needle)
+        }
+    };
+
+    #[cfg(not(any(target_arch = "aarch64", target_arch = "x86_64")))]
+    return scalar::contains(haystack, 0, needle);
+}
+
+/// `haystack` is at least as long as `prefix`
+#[inline]
+fn prefix_eq(haystack: &[u8], prefix: &[u8], ignore_case: bool) -> bool {
+    #[cfg(target_arch = "aarch64")]
+    // SAFETY: NEON is part of the aarch64 baseline
+    return unsafe { neon::prefix_eq(haystack, prefix, ignore_case) };
+
+    #[cfg(target_arch = "x86_64")]
+    // SAFETY: as in `find_ignore_case`
+    return unsafe {
+        if x86::has_avx2() {
+            x86::prefix_eq_avx2(haystack, prefix, ignore_case)
+        } else {
+            x86::prefix_eq_sse2(haystack, prefix, ignore_case)
+        }
+    };
+
+    #[cfg(not(any(target_arch = "aarch64", target_arch = "x86_64")))]
+    return scalar::prefix_eq(haystack, prefix, 0, ignore_case);
+}
+
+/// The loops the vector kernels finish with, starting at `offset`
+mod scalar {
+    pub fn prefix_eq(haystack: &[u8], prefix: &[u8], offset: usize, ignore_case: bool) -> bool {
+        let (head, rest) = (&haystack[offset..prefix.len()], &prefix[offset..]);
+        if ignore_case { head.eq_ignore_ascii_case(rest) } else { head == rest }
+    }
+
+    pub fn find_ignore_case(haystack: &[u8], offset: usize, needle: u8) -> Option<usize> {
+        haystack[offset..]
+            .iter()
+            .position(|c| c.to_ascii_lowercase() == needle)
+            .map(|position| offset + position)
+    }
+
+    pub fn contains(haystack: &[u8], offset: usize, needle: &[u8]) -> bool {
+        haystack[offset..].windows(needle.len()).any(|window| window == needle)
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod neon {
+    use std::arch::aarch64::*;
+    use super::scalar;
+
+    const LANES: usize = 16;
+
+    /// Set the case bit on ASCII uppercase letters
+    #[inline(always)]
+    unsafe fn to_lower(bytes: uint8x16_t) -> uint8x16_t {
+        // `byte - 'A' < 26` (unsigned) picks out exactly 'A'..='Z'
+        let upper = vcltq_u8(vsubq_u8(bytes, vdupq_n_u8(b'A')), vdupq_n_u8(26));
+        vorrq_u8(bytes, vandq_u8(upper, vdupq_n_u8(0x20)))
+    }
+
+    /// A comparison result packed into a u64, four bits per lane, lane 0 lowest
+    #[inline(always)]
+    unsafe fn lane_mask(matches: uint8x16_t) -> u64 {
+        vget_lane_u64(vreinterpret_u64_u8(vshrn_n_u16(vreinterpretq_u16_u8(matches), 4)), 0)
+    }
+
+    pub unsafe fn prefix_eq(haystack: &[u8], prefix: &[u8], ignore_case: bool) -> bool {
+        let mut offset = 0;
+        while offset + LANES <= prefix.len() {
+            let mut a = vld1q_u8(haystack.as_ptr().add(offset));
+            let mut b = vld1q_u8(prefix.as_ptr().add(offset));
+            if ignore_case {
+                a = to_lower(a);
+                b = to_lower(b);
+            }
+            if vminvq_u8(vceqq_u8(a, b)) != 0xFF {
+                return false;
+            }
+            offset += LANES;
+        }
+        scalar::prefix_eq(haystack, prefix, offset, ignore_case)
+    }
+
+    pub unsafe fn find_ignore_case(haystack: &[u8], needle: u8) -> Option<usize> {
+        let target = vdupq_n_u8(needle);
+        let mut offset = 0;
+        while offset + LANES <= haystack.len() {
+            let mask = lane_mask(vceqq_u8(to_lower(vld1q_u8(haystack.as_ptr().add(offset))), target));
+            if mask != 0 {
+                return Some(offset + mask.trailing_zeros() as usize / 4);
+            }
+            offset += LANES;
+        }
+        scalar::find_ignore_case(haystack, offset, needle)
+    }
+
+    /// Compares the needle's first and last bytes at 16 positions at once, and the
+    /// whole needle only where both match
+    pub unsafe fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        let first = vdupq_n_u8(needle[0]);
+        let last = vdupq_n_u8(needle[needle.len() - 1]);
+        let last_offset = needle.len() - 1;
+
+        let mut offset = 0;
+        while offset + last_offset + LANES <= haystack.len() {
+            let starts = vceqq_u8(vld1q_u8(haystack.as_ptr().add(offset)), first);
+            let ends = vceqq_u8(vld1q_u8(haystack.as_ptr().add(offset + last_offset)), last);
+            let mut mask = lane_mask(vandq_u8(starts, ends));
+
+            while mask != 0 {
+                let start = offset + mask.trailing_zeros() as usize / 4;
+                if &haystack[start..start + needle.len()] == needle {
+                    return true;
+                }
+                mask &= !(0xF << (mask.trailing_zeros() / 4 * 4));
+            }
+            offset += LANES;
+        }
+        scalar::contains(haystack, offset, needle)
+    }
+}
+
+/// The same kernels at two widths; `movemask` gives one bit per lane
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use std::arch::x86_64::*;
+    use super::scalar;
+
+    /// `byte + 63` lands 'A'..='Z', and only those, below this as a signed byte
+    const UPPER_LIMIT: i8 = -102;
+    const UPPER_SHIFT: i8 = 63;
+
+    pub fn has_avx2() -> bool {
+        // The standard library caches the CPUID result
+        is_x86_feature_detected!("avx2")
+    }
+
+    #[inline(always)]
+    unsafe fn to_lower_sse2(bytes: __m128i) -> __m128i {
+        let shifted = _mm_add_epi8(bytes, _mm_set1_epi8(UPPER_SHIFT));
+        let upper = _mm_cmpgt_epi8(_mm_set1_epi8(UPPER_LIMIT), shifted);
+        _mm_or_si128(bytes, _mm_and_si128(upper, _mm_set1_epi8(0x20)))
+    }
+
+    #[inline(always)]
+    unsafe fn to_lower_avx2(bytes: __m256i) -> __m256i {
+        let shifted = _mm256_add_epi8(bytes, _mm256_set1_epi8(UPPER_SHIFT));
+        let upper = _mm256_cmpgt_epi8(_mm256_set1_epi8(UPPER_LIMIT), shifted);
+        _mm256_or_si256(bytes, _mm256_and_si256(upper, _mm256_set1_epi8(0x20)))
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn prefix_eq_sse2(haystack: &[u8], prefix: &[u8], ignore_case: bool) -> bool {
+        const LANES: usize = 16;
+        let mut offset = 0;
+        while offset + LANES <= prefix.len() {
+            let mut a = _mm_loadu_si128(haystack.as_ptr().add(offset) as *const __m128i);
+            let mut b = _mm_loadu_si128(prefix.as_ptr().add(offset) as *const __m128i);
+            if ignore_case {
+                a = to_lower_sse2(a);
+                b = to_lower_sse2(b);
+            }
+            if _mm_movemask_epi8(_mm_cmpeq_epi8(a, b)) != 0xFFFF {
+                return false;
+            }
+            offset += LANES;
+        }
+        scalar::prefix_eq(haystack, prefix, offset, ignore_case)
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn prefix_eq_avx2(haystack: &[u8], prefix: &[u8], ignore_case: bool) -> bool {
+        const LANES: usize = 32;
+        let mut offset = 0;
+        while offset + LANES <= prefix.len() {
+            let mut a = _mm256_loadu_si256(haystack.as_ptr().add(offset) as *const __m256i);
+            let mut b = _mm256_loadu_si256(prefix.as_ptr().add(offset) as *const __m256i);
+            if ignore_case {
+                a = to_lower_avx2(a);
+                b = to_lower_avx2(b);
+            }
+            if _mm256_movemask_epi8(_mm256_cmpeq_epi8(a, b)) != -1 {
+                return false;
+            }
+            offset += LANES;
+        }
+        // Shorter prefixes are the common case; give them the 16-byte loop
+        prefix_eq_sse2(&haystack[offset..], &prefix[offset..], ignore_case)
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn find_ignore_case_sse2(haystack: &[u8], needle: u8) -> Option<usize> {
+        const LANES: usize = 16;
+        let target = _mm_set1_epi8(needle as i8);
+        let mut offset = 0;
+        while offset + LANES <= haystack.len() {
+            let chunk = to_lower_sse2(_mm_loadu_si128(haystack.as_ptr().add(offset) as *const __m128i));
+            let mask = _mm_movemask_epi8(_mm_cmpeq_epi8(chunk, target));
+            if mask != 0 {
+                return Some(offset + mask.trailing_zeros() as usize);
+            }
+            offset += LANES;
+        }
+        scalar::find_ignore_case(haystack, offset, needle)
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn find_ignore_case_avx2(haystack: &[u8], needle: u8) -> Option<usize> {
+        const LANES: usize = 32;
+        let target = _mm256_set1_epi8(needle as i8);
+        let mut offset = 0;
+        while offset + LANES <= haystack.len() {
+            let chunk = to_lower_avx2(_mm256_loadu_si256(haystack.as_ptr().add(offset) as *const __m256i));
+            let mask = _mm256_movemask_epi8(_mm256_cmpeq_epi8(chunk, target)) as u32;
+            if mask != 0 {
+                return Some(offset + mask.trailing_zeros() as usize);
+            }
+            offset += LANES;
+        }
+        find_ignore_case_sse2(&haystack[offset..], needle).map(|position| offset + position)
+    }
+
+    /// First/last-byte filter as in the NEON kernel
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn contains_sse2(haystack: &[u8], needle: &[u8]) -> bool {
+        const LANES: usize = 16;
+        let first = _mm_set1_epi8(needle[0] as i8);
+        let last = _mm_set1_epi8(needle[needle.len() - 1] as i8);
+        let last_offset = needle.len() - 1;
+
+        let mut offset = 0;
+        while offset + last_offset + LANES <= haystack.len() {
+            let starts = _mm_cmpeq_epi8(_mm_loadu_si128(haystack.as_ptr().add(offset) as *const __m128i), first);
+            let ends = _mm_cmpeq_epi8(
+                _mm_loadu_si128(haystack.as_ptr().add(offset + last_offset) as *const __m128i),
+                last,
+            );
+            let mut mask = _mm_movemask_epi8(_mm_and_si128(starts, ends)) as u32;
+
+            while mask != 0 {
+                let start = offset + mask.trailing_zeros() as usize;
+                if &haystack[start..start + needle.len()] == needle {
+                    return true;
+                }
+                mask &= mask - 1;
+            }
+            offset += LANES;
+        }
+        scalar::contains(haystack, offset, needle)
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn contains_avx2(haystack: &[u8], needle: &[u8]) -> bool {
+        const LANES: usize = 32;
+        let first = _mm256_set1_epi8(needle[0] as i8);
+        let last = _mm256_set1_epi8(needle[needle.len() - 1] as i8);
+        let last_offset = needle.len() - 1;
+
+        let mut offset = 0;
+        while offset + last_offset + LANES <= haystack.len() {
+            let starts = _mm256_cmpeq_epi8(_mm256_loadu_si256(haystack.as_ptr().add(offset) as *const __m256i), first);
+            let ends = _mm256_cmpeq_epi8(
+                _mm256_loadu_si256(haystack.as_ptr().add(offset + last_offset) as *const __m256i),
+                last,
+            );
+            let mut mask = _mm256_movemask_epi8(_mm256_and_si256(starts, ends)) as u32;
+
+            while mask != 0 {
+                let start = offset + mask.trailing_zeros() as usize;
+                if &haystack[start..start + needle.len()] == needle {
+                    return true;
+                }
+                mask &= mask - 1;
+            }
+            offset += LANES;
+        }
+        // The needle may still start in the last few positions
+        contains_sse2(&haystack[offset..], needle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::suggestion::fuzzy::{fast_fuzzy_score, scalar_fuzzy_score};
+
+    /// Lengths on either side of the 16 and 32 byte blocks, plus a few longer ones
+    const LENGTHS: &[usize] = &[0, 1, 2, 3, 15, 16, 17, 31, 32, 33, 47, 48, 49, 63, 64, 65, 100];
+
+    const ROUNDS: usize = 200;
+
+    /// Letters, separators, and the bytes either side of `A-Z` and `a-z`
+    const ASCII: &[char] = &['a', 'b', 'c', 'z', 'A', 'B', 'C', 'Z', '-', '_', '.', '/', ' ', '@', '[', '`', '{'];
+
+    /// Characters whose lowercase differs in length or lies outside ASCII (`K` is the Kelvin sign)
+    const WIDE: &[char] = &['é', 'É', 'ß', 'İ', 'ſ', 'K', 'ﬀ', '日'];
+
+    /// xorshift64, so a failure reproduces without a rand dependency
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: usize) -> usize {
+            (self.next() % n.max(1) as u64) as usize
+        }
+
+        /// `len` characters, a quarter of them non-ASCII with `utf8`
+        fn text(&mut self, len: usize, utf8: bool) -> String {
+            (0..len)
+                .map(|_| {
+                    if utf8 && self.below(4) == 0 {
+                        WIDE[self.below(WIDE.len())]
+                    } else {
+                        ASCII[self.below(ASCII.len())]
+                    }
+                })
+                .collect()
+        }
+
+        /// Needles to look for in `haystack`: a slice of it, the same with its case
+        /// flipped or one byte changed, and an unrelated one
+        fn needles(&mut self, haystack: &[u8]) -> Vec<Vec<u8>> {
+            let start = self.below(haystack.len() + 1);
+            let end = start + self.below(haystack.len() - start + 1);
+            let slice = haystack[start..end].to_vec();
+
+            let flipped = slice.iter().map(|&b| if b.is_ascii_alphabetic() { b ^ 0x20 } else { b }).collect();
+            let mut changed = slice.clone();
+            if !changed.is_empty() {
+                let at = self.below(changed.len());
+                changed[at] = changed[at].wrapping_add(1);
+            }
+            let len = self.below(8);
+            let unrelated = self.text(len, false).into_bytes();
+
+            vec![slice, flipped, changed, unrelated, haystack.to_vec()]
+        }
+    }
+
+    fn check_kernels(haystack: &[u8], needle: &[u8]) {
+        let context = format!("haystack {:?}, needle {:?}", String::from_utf8_lossy(haystack), String::from_utf8_lossy(needle));
+
+        let found = needle.is_empty() || haystack.windows(needle.len()).any(|window| window == needle);
+        assert_eq!(contains(haystack, needle), found, "contains: {}", context);
+        assert_eq!(starts_with(haystack, needle), haystack.starts_with(needle), "starts_with: {}", context);
+        let prefixed = haystack.len() >= needle.len() && haystack[..needle.len()].eq_ignore_ascii_case(needle);
+        assert_eq!(starts_with_ignore_case(haystack, needle), prefixed, "starts_with_ignore_case: {}", context);
+
+        for &byte in needle.iter().take(4) {
+            let lower = byte.to_ascii_lowercase();
+            let position = haystack.iter().position(|c| c.to_ascii_lowercase() == lower);
+            assert_eq!(find_ignore_case(haystack, lower), position, "find_ignore_case({}): {}", lower, context);
+        }
+
+        // The dispatch above only runs the widest kernel the CPU has; check SSE2 too
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: SSE2 is part of the x86_64 baseline; lengths are checked as the public functions do
+        unsafe {
+            if haystack.len() >= needle.len() {
+                assert_eq!(x86::prefix_eq_sse2(haystack, needle, false), haystack.starts_with(needle), "sse2 prefix: {}", context);
+                assert_eq!(x86::prefix_eq_sse2(haystack, needle, true), prefixed, "sse2 prefix ignoring case: {}", context);
+                if !needle.is_empty() {
+                    assert_eq!(x86::contains_sse2(haystack, needle), found, "sse2 contains: {}", context);
+                }
+            }
+            for &byte in needle.iter().take(4) {
+                let lower = byte.to_ascii_lowercase();
+                let position = haystack.iter().position(|c| c.to_ascii_lowercase() == lower);
+                assert_eq!(x86::find_ignore_case_sse2(haystack, lower), position, "sse2 find: {}", context);
+            }
+        }
+    }
+
+    #[test]
+    fn kernels_match_scalar_loops() {
+        let mut rng = Rng(0x5eed_1382);
+        for &len in LENGTHS {
+            for round in 0..ROUNDS {
+                let haystack = rng.text(len, round % 2 == 1).into_bytes();
+                for needle in rng.needles(&haystack) {
+                    check_kernels(&haystack, &needle);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fast_fuzzy_score_matches_scalar() {
+        let mut rng = Rng(0xf022_1382);
+        for &len in LENGTHS {
+            for round in 0..ROUNDS {
+                let candidate = rng.text(len, round % 2 == 1);
+                let chars: Vec<char> = candidate.chars().collect();
+
+                // An in-order pick of the candidate's characters, some with their case
+                // flipped, and an unrelated pattern
+                let mut picked = String::new();
+                for &c in &chars {
+                    if rng.below(3) == 0 {
+                        picked.push(if rng.below(2) == 0 { c.to_ascii_uppercase() } else { c });
+                    }
+                }
+                let len = rng.below(6);
+                let unrelated = rng.text(len, false);
+
+                for pattern in [picked.as_str(), unrelated.as_str(), candidate.as_str()] {
+                    assert_eq!(
+                        fast_fuzzy_score(pattern, &candidate),
+                        scalar_fuzzy_score(pattern, &candidate),
+                        "pattern {:?}, candidate {:?}",
+                        pattern,
+                        candidate
+                    );
+                }
+            }
+        }
+    }
+}
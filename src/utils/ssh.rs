@@ -0,0 +1,11 @@
+use std::env;
+
+/// Whether this process runs in a shell on the far end of an SSH connection
+///
+/// sshd sets these for the login shell, and they're inherited by everything under it,
+/// including tmux and screen sessions started from it.
+pub fn is_ssh_session() -> bool {
+    ["SSH_CONNECTION", "SSH_CLIENT", "SSH_TTY"]
+        .iter()
+        .any(|var| env::var_os(var).map_or(false, |value| !value.is_empty()))
+}
@@ -0,0 +1,51 @@
+use std::time::{Duration, Instant};
+
+/// Phase timings for `rustfig --profile-startup`
+pub struct StartupProfile {
+    started: Instant,
+    last: Instant,
+    phases: Vec<(&'static str, Duration, bool)>,
+}
+
+impl Default for StartupProfile {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StartupProfile {
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self { started: now, last: now, phases: Vec::new() }
+    }
+
+    /// Record the time since the previous phase as `name`, which blocks the prompt
+    pub fn phase(&mut self, name: &'static str) {
+        self.record(name, false);
+    }
+
+    /// Record the time since the previous phase as `name`, which runs after the prompt is up
+    pub fn deferred_phase(&mut self, name: &'static str) {
+        self.record(name, true);
+    }
+
+    fn record(&mut self, name: &'static str, deferred: bool) {
+        let now = Instant::now();
+        self.phases.push((name, now - self.last, deferred));
+        self.last = now;
+    }
+
+    /// Time until the prompt is usable, counting only blocking phases
+    pub fn blocking_total(&self) -> Duration {
+        self.phases.iter().filter(|(_, _, deferred)| !deferred).map(|(_, time, _)| *time).sum()
+    }
+
+    pub fn print(&self) {
+        for (name, time, deferred) in &self.phases {
+            let marker = if *deferred { " (deferred)" } else { "" };
+            println!("{:>10.2}ms  {}{}", time.as_secs_f64() * 1000.0, name, marker);
+        }
+        println!("{:>10.2}ms  until the prompt is usable", self.blocking_total().as_secs_f64() * 1000.0);
+        println!("{:>10.2}ms  total", self.started.elapsed().as_secs_f64() * 1000.0);
+    }
+}
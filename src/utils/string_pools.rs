@@ -1,10 +1,31 @@
 use std::collections::HashSet;
 use std::sync::Arc;
+use once_cell::sync::Lazy;
 use parking_lot::RwLock;
 
+/// Pool shared by the prediction pipeline
+static GLOBAL_POOL: Lazy<StringPool> = Lazy::new(StringPool::new);
+
+/// Pool size at which strings nobody else holds any more are dropped
+const PRUNE_THRESHOLD: usize = 4096;
+
+/// Intern `s` in the shared pool
+///
+/// Repeated prediction texts ("git status", "cargo build") then share one allocation,
+/// and cloning a prediction only bumps reference counts.
+pub fn intern(s: &str) -> Arc<str> {
+    GLOBAL_POOL.intern(s)
+}
+
 /// String interning pool for zero-copy operations
 pub struct StringPool {
-    pool: Arc<RwLock<HashSet<Arc<String>>>>,
+    pool: Arc<RwLock<HashSet<Arc<str>>>>,
+}
+
+impl Default for StringPool {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl StringPool {
@@ -15,30 +36,27 @@ impl StringPool {
     }
     
     /// Get an existing string from the pool or add it if it doesn't exist
-    pub fn intern(&self, s: &str) -> Arc<String> {
-        // Fast path: check if string already exists
-        {
-            let pool = self.pool.read();
-            for existing in pool.iter() {
-                if existing.as_str() == s {
-                    return Arc::clone(existing);
-                }
-            }
+    pub fn intern(&self, s: &str) -> Arc<str> {
+        // Fast path: the string is already pooled
+        if let Some(existing) = self.pool.read().get(s) {
+            return Arc::clone(existing);
         }
         
-        // Slow path: add to pool
-        let s_arc = Arc::new(s.to_string());
         let mut pool = self.pool.write();
         
         // Double-check in case another thread added it while we were waiting
-        for existing in pool.iter() {
-            if existing.as_str() == s {
-                return Arc::clone(existing);
-            }
+        if let Some(existing) = pool.get(s) {
+            return Arc::clone(existing);
+        }
+        
+        // Strings only the pool still holds are just taking up memory
+        if pool.len() >= PRUNE_THRESHOLD {
+            pool.retain(|existing| Arc::strong_count(existing) > 1);
         }
         
-        pool.insert(Arc::clone(&s_arc));
-        s_arc
+        let interned: Arc<str> = Arc::from(s);
+        pool.insert(Arc::clone(&interned));
+        interned
     }
     
     /// Clear the pool
@@ -0,0 +1,66 @@
+use std::fs;
+use std::process::Command;
+use std::time::Duration;
+
+use super::process::run_with_timeout;
+
+/// Physical memory of this machine in bytes, if it can be determined
+pub fn total_memory_bytes() -> Option<u64> {
+    if cfg!(target_os = "linux") {
+        // "MemTotal:       16318480 kB"
+        let meminfo = fs::read_to_string("/proc/meminfo").ok()?;
+        let line = meminfo.lines().find(|line| line.starts_with("MemTotal:"))?;
+        let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+        Some(kib * 1024)
+    } else if cfg!(target_os = "macos") {
+        let output = run_with_timeout(Command::new("sysctl").args(["-n", "hw.memsize"]), Duration::from_secs(1), 64)?;
+        String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Resident memory of this process in bytes, if it can be determined
+pub fn process_rss_bytes() -> Option<u64> {
+    if cfg!(target_os = "linux") {
+        // "VmRSS:	   10468 kB"
+        let status = fs::read_to_string("/proc/self/status").ok()?;
+        let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+        let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+        Some(kib * 1024)
+    } else if cfg!(target_os = "macos") {
+        // ps reports kilobytes
+        let pid = std::process::id().to_string();
+        let output = run_with_timeout(Command::new("ps").args(["-o", "rss=", "-p", &pid]), Duration::from_secs(1), 64)?;
+        let kib: u64 = String::from_utf8_lossy(&output.stdout).trim().parse().ok()?;
+        Some(kib * 1024)
+    } else {
+        None
+    }
+}
+
+/// Bytes as a short human-readable size, e.g. `4.7 GB`
+pub fn format_bytes(bytes: u64) -> String {
+    const GB: f64 = 1024.0 * 1024.0 * 1024.0;
+    const MB: f64 = 1024.0 * 1024.0;
+    const KB: f64 = 1024.0;
+
+    let bytes = bytes as f64;
+    if bytes >= GB {
+        format!("{:.1} GB", bytes / GB)
+    } else if bytes >= MB {
+        format!("{:.0} MB", bytes / MB)
+    } else {
+        format!("{:.0} KB", bytes / KB)
+    }
+}
+
+/// Compact age like `5m`, `3h`, `2d`
+pub fn format_age(seconds: u64) -> String {
+    match seconds {
+        s if s < 60 => format!("{}s", s),
+        s if s < 3600 => format!("{}m", s / 60),
+        s if s < 86400 => format!("{}h", s / 3600),
+        s => format!("{}d", s / 86400),
+    }
+}
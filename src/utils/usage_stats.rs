@@ -0,0 +1,210 @@
+//! Local usage counters behind `rustfig stats`
+//!
+//! How often ghost text was shown and accepted, how many suggestions were accepted, how
+//! many characters accepting put on the line, and how long each suggestion source took.
+//! They're kept in `stats.json` in the user data directory and never uploaded.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+use super::perf_metrics;
+
+const STATS_FILE: &str = "stats.json";
+
+/// Counted events between saves
+const SAVE_INTERVAL: u64 = 20;
+
+/// Perf metrics component the suggestion engine times its sources under
+pub const SOURCE_COMPONENT: &str = "suggestion_sources";
+
+/// Counters accumulated across sessions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageStats {
+    /// Unix time counting started
+    #[serde(default)]
+    pub since: u64,
+    #[serde(default)]
+    pub ghost_shown: u64,
+    #[serde(default)]
+    pub ghost_accepted: u64,
+    #[serde(default)]
+    pub suggestions_accepted: u64,
+    /// Characters put on the line by accepting ghost text and suggestions
+    #[serde(default)]
+    pub chars_inserted: u64,
+    /// Latencies by suggestion source
+    #[serde(default)]
+    pub sources: HashMap<String, SourceTiming>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct SourceTiming {
+    pub calls: u64,
+    pub total_us: u64,
+    pub max_us: u64,
+}
+
+impl SourceTiming {
+    pub fn avg_us(&self) -> u64 {
+        if self.calls == 0 {
+            0
+        } else {
+            self.total_us / self.calls
+        }
+    }
+}
+
+impl UsageStats {
+    /// What's been counted so far; empty if nothing has been or the file is unreadable
+    pub fn load(user_data_dir: &Path) -> Self {
+        fs::read_to_string(stats_path(user_data_dir))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Forget everything counted
+    pub fn reset(user_data_dir: &Path) -> Result<(), Box<dyn Error>> {
+        match fs::remove_file(stats_path(user_data_dir)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Share of shown ghost text that was accepted, once any was shown
+    pub fn ghost_acceptance_rate(&self) -> Option<f64> {
+        (self.ghost_shown > 0).then(|| self.ghost_accepted as f64 / self.ghost_shown as f64)
+    }
+
+    /// Characters not typed: each acceptance is one key press standing in for what it inserted
+    pub fn keystrokes_saved(&self) -> u64 {
+        self.chars_inserted.saturating_sub(self.ghost_accepted + self.suggestions_accepted)
+    }
+
+    /// Sources by average latency, slowest first
+    pub fn slowest_sources(&self) -> Vec<(&str, SourceTiming)> {
+        let mut sources: Vec<(&str, SourceTiming)> = self
+            .sources
+            .iter()
+            .filter(|(_, timing)| timing.calls > 0)
+            .map(|(name, timing)| (name.as_str(), *timing))
+            .collect();
+        sources.sort_by(|a, b| b.1.avg_us().cmp(&a.1.avg_us()));
+        sources
+    }
+
+    fn add(&mut self, other: &UsageStats) {
+        if self.since == 0 {
+            self.since = other.since;
+        }
+        self.ghost_shown += other.ghost_shown;
+        self.ghost_accepted += other.ghost_accepted;
+        self.suggestions_accepted += other.suggestions_accepted;
+        self.chars_inserted += other.chars_inserted;
+        for (name, timing) in &other.sources {
+            let total = self.sources.entry(name.clone()).or_default();
+            total.calls += timing.calls;
+            total.total_us += timing.total_us;
+            total.max_us = total.max_us.max(timing.max_us);
+        }
+    }
+}
+
+/// Counts events during a session and adds them to `stats.json` now and then
+pub struct StatsRecorder {
+    path: PathBuf,
+    pending: UsageStats,
+    events: u64,
+    /// Source calls and time already saved, since the perf metrics only ever grow
+    saved_sources: HashMap<String, (u64, u64)>,
+}
+
+impl StatsRecorder {
+    pub fn new(user_data_dir: &Path) -> Self {
+        Self {
+            path: stats_path(user_data_dir),
+            pending: UsageStats::default(),
+            events: 0,
+            saved_sources: HashMap::new(),
+        }
+    }
+
+    pub fn ghost_shown(&mut self) {
+        self.pending.ghost_shown += 1;
+        self.counted();
+    }
+
+    /// Ghost text was accepted, putting `inserted` characters on the line
+    pub fn ghost_accepted(&mut self, inserted: usize) {
+        self.pending.ghost_accepted += 1;
+        self.pending.chars_inserted += inserted as u64;
+        self.counted();
+    }
+
+    /// A suggestion was accepted, putting `inserted` characters on the line
+    pub fn suggestion_accepted(&mut self, inserted: usize) {
+        self.pending.suggestions_accepted += 1;
+        self.pending.chars_inserted += inserted as u64;
+        self.counted();
+    }
+
+    fn counted(&mut self) {
+        self.events += 1;
+        if self.events % SAVE_INTERVAL == 0 {
+            let _ = self.save();
+        }
+    }
+
+    /// Add what's been counted, and source timings since the last save, to the file
+    pub fn save(&mut self) -> Result<(), Box<dyn Error>> {
+        let snapshot = perf_metrics::snapshot();
+        let operations = snapshot
+            .iter()
+            .filter(|component| component.component == SOURCE_COMPONENT)
+            .flat_map(|component| &component.operations);
+        for operation in operations {
+            let saved = self.saved_sources.entry(operation.operation.clone()).or_default();
+            let calls = (operation.calls as u64).saturating_sub(saved.0);
+            if calls == 0 {
+                continue;
+            }
+            self.pending.sources.insert(
+                operation.operation.clone(),
+                SourceTiming { calls, total_us: operation.total_us.saturating_sub(saved.1), max_us: operation.max_us },
+            );
+            *saved = (operation.calls as u64, operation.total_us);
+        }
+
+        let pending = std::mem::take(&mut self.pending);
+        if pending.ghost_shown + pending.ghost_accepted + pending.suggestions_accepted == 0 && pending.sources.is_empty() {
+            return Ok(());
+        }
+
+        let user_data_dir = self.path.parent().unwrap_or(Path::new("."));
+        let mut stats = UsageStats::load(user_data_dir);
+        if stats.since == 0 {
+            stats.since = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        }
+        stats.add(&pending);
+
+        fs::create_dir_all(user_data_dir)?;
+        let tmp_path = self.path.with_extension(format!("json.{}.tmp", std::process::id()));
+        fs::write(&tmp_path, serde_json::to_string(&stats)?)?;
+        fs::rename(tmp_path, &self.path)?;
+        Ok(())
+    }
+}
+
+impl Drop for StatsRecorder {
+    fn drop(&mut self) {
+        let _ = self.save();
+    }
+}
+
+fn stats_path(user_data_dir: &Path) -> PathBuf {
+    user_data_dir.join(STATS_FILE)
+}